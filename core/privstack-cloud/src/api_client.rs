@@ -5,6 +5,7 @@
 
 use crate::config::CloudConfig;
 use crate::error::{CloudError, CloudResult};
+use crate::key_transparency::{decode_key, SignedKeyLog};
 use crate::types::*;
 use privstack_crypto::SealedEnvelope;
 use reqwest::Client;
@@ -592,7 +593,7 @@ impl CloudApiClient {
         Ok(())
     }
 
-    pub async fn get_share_key(&self, entity_id: &str) -> CloudResult<SealedEnvelope> {
+    pub async fn get_share_key(&self, entity_id: &str) -> CloudResult<ShareKey> {
         let resp = self
             .auth_get(&format!("/api/share/keys/{entity_id}"))
             .await?
@@ -607,6 +608,7 @@ impl CloudApiClient {
         entity_id: &str,
         recipient_id: i64,
         envelope: &SealedEnvelope,
+        dek_generation: u32,
     ) -> CloudResult<()> {
         self.auth_post(
             "/api/share/keys/store",
@@ -614,6 +616,7 @@ impl CloudApiClient {
                 "entity_id": entity_id,
                 "recipient_user_id": recipient_id,
                 "encrypted_dek": serde_json::to_string(envelope)?,
+                "dek_generation": dek_generation,
             }),
         )
         .await?
@@ -664,22 +667,17 @@ impl CloudApiClient {
             public_key: String,
         }
         let data: Resp = resp.json().await?;
+        decode_key(&data.public_key)
+    }
 
-        use base64::{engine::general_purpose::STANDARD, Engine};
-        let bytes = STANDARD
-            .decode(&data.public_key)
-            .map_err(|e| CloudError::Api(format!("invalid public key encoding: {e}")))?;
-
-        if bytes.len() != 32 {
-            return Err(CloudError::Api(format!(
-                "invalid public key length: expected 32, got {}",
-                bytes.len()
-            )));
-        }
-
-        let mut key = [0u8; 32];
-        key.copy_from_slice(&bytes);
-        Ok(key)
+    /// Fetches a user's signed public-key log (see `crate::key_transparency`).
+    pub async fn get_key_log(&self, user_id: i64) -> CloudResult<SignedKeyLog> {
+        let resp = self
+            .auth_get(&format!("/api/cloud/keys/log/{user_id}"))
+            .await?
+            .error_for_status()
+            .map_err(|e| CloudError::Api(e.to_string()))?;
+        Ok(resp.json().await?)
     }
 
     pub async fn upload_public_key(&self, key: &[u8; 32]) -> CloudResult<()> {
@@ -0,0 +1,31 @@
+//! Crash-safe replacement of small local state files.
+
+use crate::error::{CloudError, CloudResult};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Replaces `path` with `contents`: writes a sibling temp file, syncs it and
+/// renames it over `path`, so a crash never leaves a truncated file.
+/// `what` names the file in the error (e.g. "key pins").
+pub(crate) fn write_atomic(path: &Path, contents: &[u8], what: &str) -> CloudResult<()> {
+    let tmp = temp_path(path);
+    std::fs::File::create(&tmp)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|()| std::fs::rename(&tmp, path))
+        .map_err(|e| {
+            let _ = std::fs::remove_file(&tmp);
+            CloudError::Config(format!("failed to write {what} {}: {e}", path.display()))
+        })
+}
+
+/// `<file name>.tmp` next to `path`, so files differing only in extension
+/// never share a temp file.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
@@ -1,24 +1,160 @@
 //! Blob upload/download for file attachments.
 //!
-//! Encrypts blobs with the entity DEK before uploading to S3, and registers
-//! metadata with the API for quota tracking and share partner access.
+//! Encrypts blobs chunk by chunk with the entity DEK (see [`crate::blob_transfer`]),
+//! uploads them as S3 multipart uploads and downloads them with ranged GETs,
+//! and registers metadata with the API for quota tracking and share partner
+//! access. File transfers record their progress so an interrupted transfer
+//! resumes where it stopped.
+//!
+//! Every upload goes to a new object version and only then replaces the
+//! manifest, so a failed or interrupted re-upload leaves the previous
+//! version readable. The superseded version is deleted once the new
+//! manifest is in place.
 
 use crate::api_client::CloudApiClient;
+use crate::blob_transfer::{
+    chunk_count, chunk_hash, decrypt_chunk, encrypt_chunk, is_object_of, manifest_s3_key,
+    object_s3_key, BlobManifest, BlobProgress, CompletedChunk, TransferDirection, TransferState,
+    TransferStateStore, BLOB_CHUNK_SIZE,
+};
 use crate::compaction::blob_s3_key;
 use crate::credential_manager::CredentialManager;
 use crate::error::{CloudError, CloudResult};
 use crate::s3_transport::S3Transport;
 use crate::types::*;
-use privstack_crypto::{decrypt, encrypt, DerivedKey, EncryptedData};
+use privstack_crypto::{decrypt, DerivedKey, EncryptedData};
 use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tracing::debug;
+use tracing::{debug, warn};
+
+/// S3 allows at most this many parts per multipart upload.
+const MAX_PARTS: u64 = 10_000;
+
+/// A fresh object version key for the blob at `s3_key`.
+fn new_object_key(s3_key: &str) -> String {
+    object_s3_key(s3_key, &uuid::Uuid::new_v4().simple().to_string())
+}
+
+/// Identifies a blob and the workspace/entity it belongs to.
+#[derive(Debug, Clone, Copy)]
+pub struct BlobRef<'a> {
+    pub user_id: i64,
+    pub workspace_id: &'a str,
+    pub blob_id: &'a str,
+    pub entity_id: Option<&'a str>,
+}
+
+/// Callback invoked after every chunk of a transfer.
+pub type ProgressFn<'a> = &'a (dyn Fn(&BlobProgress) + Sync);
+
+/// Plaintext being uploaded.
+enum ChunkSource<'a> {
+    Memory(&'a [u8]),
+    File { file: File, path: PathBuf, len: u64 },
+}
+
+impl ChunkSource<'_> {
+    fn len(&self) -> u64 {
+        match self {
+            ChunkSource::Memory(data) => data.len() as u64,
+            ChunkSource::File { len, .. } => *len,
+        }
+    }
+
+    /// Local file, if the upload can be resumed.
+    fn path(&self) -> Option<&Path> {
+        match self {
+            ChunkSource::Memory(_) => None,
+            ChunkSource::File { path, .. } => Some(path.as_path()),
+        }
+    }
+
+    fn read_chunk(&mut self, offset: u64, len: u64) -> CloudResult<Vec<u8>> {
+        match self {
+            ChunkSource::Memory(data) => {
+                Ok(data[offset as usize..(offset + len) as usize].to_vec())
+            }
+            ChunkSource::File { file, .. } => read_at(file, offset, len),
+        }
+    }
+}
+
+/// Destination of downloaded plaintext.
+enum ChunkSink<'a> {
+    Memory(&'a mut Vec<u8>),
+    File { file: File, path: PathBuf },
+}
+
+impl ChunkSink<'_> {
+    /// Local file, if the download can be resumed.
+    fn path(&self) -> Option<&Path> {
+        match self {
+            ChunkSink::Memory(_) => None,
+            ChunkSink::File { path, .. } => Some(path.as_path()),
+        }
+    }
+
+    /// Reads back a chunk written by an earlier attempt.
+    fn read_back(&mut self, offset: u64, len: u64) -> Option<Vec<u8>> {
+        match self {
+            ChunkSink::Memory(_) => None,
+            ChunkSink::File { file, .. } => read_at(file, offset, len).ok(),
+        }
+    }
+
+    fn write_chunk(&mut self, offset: u64, data: &[u8]) -> CloudResult<()> {
+        match self {
+            ChunkSink::Memory(out) => out.extend_from_slice(data),
+            ChunkSink::File { file, .. } => {
+                file.seek(SeekFrom::Start(offset))?;
+                file.write_all(data)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, total_size: u64) -> CloudResult<()> {
+        if let ChunkSink::File { file, .. } = self {
+            file.set_len(total_size)?;
+            file.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+fn read_at(file: &mut File, offset: u64, len: u64) -> CloudResult<Vec<u8>> {
+    let mut buf = vec![0u8; len as usize];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn report(
+    progress: Option<ProgressFn<'_>>,
+    blob_id: &str,
+    direction: TransferDirection,
+    bytes_done: u64,
+    bytes_total: u64,
+) {
+    if let Some(progress) = progress {
+        progress(&BlobProgress {
+            blob_id: blob_id.to_string(),
+            direction,
+            bytes_done,
+            bytes_total,
+        });
+    }
+}
 
 /// Manages blob upload/download with encryption and quota tracking.
 pub struct BlobSyncManager {
     api: Arc<CloudApiClient>,
     transport: Arc<S3Transport>,
     cred_manager: Arc<CredentialManager>,
+    transfers: Arc<TransferStateStore>,
 }
 
 impl BlobSyncManager {
@@ -31,9 +167,20 @@ impl BlobSyncManager {
             api,
             transport,
             cred_manager,
+            transfers: Arc::new(TransferStateStore::in_memory()),
         }
     }
 
+    /// Sets the store that keeps resume state of file transfers.
+    pub fn set_transfer_store(&mut self, transfers: Arc<TransferStateStore>) {
+        self.transfers = transfers;
+    }
+
+    /// Unfinished file transfers that can be resumed.
+    pub fn pending_transfers(&self) -> Vec<TransferState> {
+        self.transfers.list()
+    }
+
     /// Uploads a blob encrypted with the entity DEK.
     pub async fn upload_blob(
         &self,
@@ -44,53 +191,431 @@ impl BlobSyncManager {
         data: &[u8],
         entity_dek: &DerivedKey,
     ) -> CloudResult<()> {
-        // Encrypt blob
-        let encrypted = encrypt(entity_dek, data)
-            .map_err(|e| CloudError::Envelope(format!("blob encryption failed: {e}")))?;
+        let blob = BlobRef {
+            user_id,
+            workspace_id,
+            blob_id,
+            entity_id,
+        };
+        self.upload_chunked(&blob, ChunkSource::Memory(data), entity_dek, None)
+            .await
+    }
+
+    /// Uploads a file as a blob, one chunk at a time. Calling it again after
+    /// a failure resumes from the last uploaded chunk.
+    pub async fn upload_blob_file(
+        &self,
+        blob: &BlobRef<'_>,
+        path: &Path,
+        entity_dek: &DerivedKey,
+        progress: Option<ProgressFn<'_>>,
+    ) -> CloudResult<()> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let source = ChunkSource::File {
+            file,
+            path: path.to_path_buf(),
+            len,
+        };
+        self.upload_chunked(blob, source, entity_dek, progress).await
+    }
+
+    /// Downloads and decrypts a blob.
+    pub async fn download_blob(
+        &self,
+        s3_key: &str,
+        entity_dek: &DerivedKey,
+    ) -> CloudResult<Vec<u8>> {
+        let mut data = Vec::new();
+        self.download_chunked(s3_key, s3_key, ChunkSink::Memory(&mut data), entity_dek, None)
+            .await?;
+        Ok(data)
+    }
+
+    /// Downloads and decrypts a blob into `dest`, one chunk at a time.
+    /// Calling it again after a failure resumes from the last verified chunk.
+    pub async fn download_blob_file(
+        &self,
+        blob_id: &str,
+        s3_key: &str,
+        dest: &Path,
+        entity_dek: &DerivedKey,
+        progress: Option<ProgressFn<'_>>,
+    ) -> CloudResult<()> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dest)?;
+        let sink = ChunkSink::File {
+            file,
+            path: dest.to_path_buf(),
+        };
+        self.download_chunked(blob_id, s3_key, sink, entity_dek, progress)
+            .await
+    }
+
+    /// Abandons an unfinished transfer, aborting its multipart upload.
+    /// Returns false if there was nothing to cancel.
+    pub async fn cancel_transfer(
+        &self,
+        blob_id: &str,
+        direction: TransferDirection,
+    ) -> CloudResult<bool> {
+        let Some(state) = self.transfers.remove(blob_id, direction)? else {
+            return Ok(false);
+        };
+        if let Some(upload_id) = &state.upload_id {
+            let creds = self.cred_manager.get_credentials().await?;
+            self.transport
+                .abort_multipart_upload(&creds, &state.s3_key, upload_id)
+                .await?;
+        }
+        Ok(true)
+    }
+
+    /// Gets blob metadata for an entity.
+    pub async fn get_entity_blobs(&self, entity_id: &str) -> CloudResult<Vec<BlobMeta>> {
+        self.api.get_entity_blobs(entity_id).await
+    }
+
+    // ── Upload ──────────────────────────────────────────────────────
+
+    async fn upload_chunked(
+        &self,
+        blob: &BlobRef<'_>,
+        mut source: ChunkSource<'_>,
+        dek: &DerivedKey,
+        progress: Option<ProgressFn<'_>>,
+    ) -> CloudResult<()> {
+        let total_size = source.len();
+        let chunks = chunk_count(total_size, BLOB_CHUNK_SIZE);
+        if chunks > MAX_PARTS {
+            return Err(CloudError::S3(format!(
+                "blob {} is too large ({total_size} bytes)",
+                blob.blob_id
+            )));
+        }
+        let s3_key = blob_s3_key(blob.user_id, blob.workspace_id, blob.blob_id);
 
-        let encrypted_bytes = serde_json::to_vec(&encrypted)?;
-        let s3_key = blob_s3_key(user_id, workspace_id, blob_id);
-        let content_hash = hex::encode(Sha256::digest(data));
+        let manifest = if chunks == 1 {
+            let plaintext = source.read_chunk(0, total_size)?;
+            let encrypted = encrypt_chunk(dek, &plaintext)?;
+            let manifest = BlobManifest::new(
+                s3_key.clone(),
+                new_object_key(&s3_key),
+                BLOB_CHUNK_SIZE,
+                total_size,
+                hex::encode(Sha256::digest(&plaintext)),
+                vec![chunk_hash(&encrypted)],
+            );
+            let creds = self.cred_manager.get_credentials().await?;
+            self.transport.upload(&creds, &manifest.object_key, encrypted).await?;
+            manifest
+        } else {
+            let mut state = self.upload_state(blob.blob_id, &s3_key, &source).await?;
+            let result = self
+                .upload_parts(&s3_key, &mut state, &mut source, dek, progress)
+                .await;
+            if result.is_err() && source.path().is_none() {
+                // Nothing to resume from memory: release the parts.
+                self.abort_quietly(&state).await;
+            }
+            result?
+        };
 
-        // Upload to S3
+        let previous = self.current_object_key(&s3_key, dek).await;
+        // Switch readers to the new version.
         let creds = self.cred_manager.get_credentials().await?;
         self.transport
-            .upload(&creds, &s3_key, encrypted_bytes.clone())
+            .upload(&creds, &manifest_s3_key(&s3_key), manifest.seal(dek)?)
             .await?;
 
-        // Register with API for quota tracking
         self.api
             .register_blob(&RegisterBlobRequest {
-                workspace_id: workspace_id.to_string(),
-                blob_id: blob_id.to_string(),
-                entity_id: entity_id.map(|s| s.to_string()),
+                workspace_id: blob.workspace_id.to_string(),
+                blob_id: blob.blob_id.to_string(),
+                entity_id: blob.entity_id.map(|s| s.to_string()),
                 s3_key: s3_key.clone(),
-                size_bytes: encrypted_bytes.len() as u64,
-                content_hash: Some(content_hash),
+                size_bytes: manifest.encrypted_size(),
+                content_hash: Some(manifest.content_hash.clone()),
             })
             .await?;
 
-        debug!("uploaded blob {blob_id} ({} bytes encrypted)", encrypted_bytes.len());
+        if let Some(previous) = previous
+            && previous != manifest.object_key
+            && let Err(e) = self.transport.delete(&creds, &previous).await
+        {
+            warn!("failed to delete superseded object {previous}: {e}");
+        }
+
+        report(progress, blob.blob_id, TransferDirection::Upload, total_size, total_size);
+        debug!("uploaded blob {} ({} bytes encrypted)", blob.blob_id, manifest.encrypted_size());
         Ok(())
     }
 
-    /// Downloads and decrypts a blob.
-    pub async fn download_blob(
+    /// Object version the current manifest of `s3_key` points at, if there
+    /// is a readable one.
+    async fn current_object_key(&self, s3_key: &str, dek: &DerivedKey) -> Option<String> {
+        let creds = self.cred_manager.get_credentials().await.ok()?;
+        let manifest_key = manifest_s3_key(s3_key);
+        if !self.transport.exists(&creds, &manifest_key).await.ok()? {
+            return None;
+        }
+        let bytes = self.transport.download(&creds, &manifest_key).await.ok()?;
+        BlobManifest::open(dek, &bytes, s3_key)
+            .ok()
+            .map(|manifest| manifest.object_key)
+    }
+
+    /// Returns the saved state of a matching interrupted upload, or starts
+    /// a new multipart upload to a new version of `s3_key`.
+    async fn upload_state(
         &self,
+        blob_id: &str,
         s3_key: &str,
-        entity_dek: &DerivedKey,
-    ) -> CloudResult<Vec<u8>> {
+        source: &ChunkSource<'_>,
+    ) -> CloudResult<TransferState> {
+        if let Some(saved) = self.transfers.get(blob_id, TransferDirection::Upload) {
+            let resumable = saved.upload_id.is_some()
+                && is_object_of(s3_key, &saved.s3_key)
+                && Some(saved.local_path.as_path()) == source.path()
+                && saved.total_size == source.len()
+                && saved.chunk_size == BLOB_CHUNK_SIZE;
+            if resumable {
+                debug!("resuming upload of blob {blob_id} ({} chunks done)", saved.completed.len());
+                return Ok(saved);
+            }
+            self.transfers.remove(blob_id, TransferDirection::Upload)?;
+            self.abort_quietly(&saved).await;
+        }
+
+        let object_key = new_object_key(s3_key);
         let creds = self.cred_manager.get_credentials().await?;
-        let encrypted_bytes = self.transport.download(&creds, s3_key).await?;
+        let upload_id = self.transport.create_multipart_upload(&creds, &object_key).await?;
+        Ok(TransferState {
+            blob_id: blob_id.to_string(),
+            direction: TransferDirection::Upload,
+            s3_key: object_key,
+            local_path: source.path().map(Path::to_path_buf).unwrap_or_default(),
+            total_size: source.len(),
+            chunk_size: BLOB_CHUNK_SIZE,
+            upload_id: Some(upload_id),
+            completed: Vec::new(),
+        })
+    }
 
-        let encrypted: EncryptedData = serde_json::from_slice(&encrypted_bytes)?;
+    /// Uploads every chunk not already in `state` as a multipart part and
+    /// completes the upload.
+    async fn upload_parts(
+        &self,
+        s3_key: &str,
+        state: &mut TransferState,
+        source: &mut ChunkSource<'_>,
+        dek: &DerivedKey,
+        progress: Option<ProgressFn<'_>>,
+    ) -> CloudResult<BlobManifest> {
+        let upload_id = state.upload_id.clone().unwrap_or_default();
+        let resumable = source.path().is_some();
+        let chunks = chunk_count(state.total_size, state.chunk_size);
+        let mut content = Sha256::new();
+        let mut chunk_hashes = Vec::with_capacity(chunks as usize);
+
+        for index in 0..chunks {
+            let offset = index * state.chunk_size;
+            let len = state.total_size.saturating_sub(offset).min(state.chunk_size);
+            let plaintext = source.read_chunk(offset, len)?;
+            content.update(&plaintext);
+            let plain_hash = hex::encode(Sha256::digest(&plaintext));
 
-        decrypt(entity_dek, &encrypted)
+            if let Some(done) = state.completed_chunk(index)
+                && done.plain_hash == plain_hash
+            {
+                chunk_hashes.push(done.hash.clone());
+            } else {
+                state.completed.retain(|c| c.index != index);
+                let encrypted = encrypt_chunk(dek, &plaintext)?;
+                let hash = chunk_hash(&encrypted);
+                let creds = self.cred_manager.get_credentials().await?;
+                let etag = self
+                    .transport
+                    .upload_part(&creds, &state.s3_key, &upload_id, index as i32 + 1, encrypted)
+                    .await?;
+                state.completed.push(CompletedChunk {
+                    index,
+                    hash: hash.clone(),
+                    plain_hash,
+                    etag: Some(etag),
+                });
+                chunk_hashes.push(hash);
+                if resumable {
+                    self.transfers.put(state)?;
+                }
+            }
+            report(progress, &state.blob_id, state.direction, offset + len, state.total_size);
+        }
+
+        let mut parts: Vec<(i32, String)> = state
+            .completed
+            .iter()
+            .map(|c| (c.index as i32 + 1, c.etag.clone().unwrap_or_default()))
+            .collect();
+        parts.sort();
+        let creds = self.cred_manager.get_credentials().await?;
+        self.transport
+            .complete_multipart_upload(&creds, &state.s3_key, &upload_id, &parts)
+            .await?;
+        // The multipart upload is gone now; a retry starts over.
+        self.transfers.remove(&state.blob_id, TransferDirection::Upload)?;
+
+        Ok(BlobManifest::new(
+            s3_key.to_string(),
+            state.s3_key.clone(),
+            state.chunk_size,
+            state.total_size,
+            hex::encode(content.finalize()),
+            chunk_hashes,
+        ))
+    }
+
+    async fn abort_quietly(&self, state: &TransferState) {
+        let Some(upload_id) = &state.upload_id else {
+            return;
+        };
+        let result = match self.cred_manager.get_credentials().await {
+            Ok(creds) => {
+                self.transport
+                    .abort_multipart_upload(&creds, &state.s3_key, upload_id)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            warn!("failed to abort multipart upload of blob {}: {e}", state.blob_id);
+        }
+    }
+
+    // ── Download ────────────────────────────────────────────────────
+
+    async fn download_chunked(
+        &self,
+        blob_id: &str,
+        s3_key: &str,
+        mut sink: ChunkSink<'_>,
+        dek: &DerivedKey,
+        progress: Option<ProgressFn<'_>>,
+    ) -> CloudResult<()> {
+        let creds = self.cred_manager.get_credentials().await?;
+        let manifest_key = manifest_s3_key(s3_key);
+        if !self.transport.exists(&creds, &manifest_key).await? {
+            let data = self.download_legacy(s3_key, dek).await?;
+            let len = data.len() as u64;
+            sink.write_chunk(0, &data)?;
+            sink.finish(len)?;
+            report(progress, blob_id, TransferDirection::Download, len, len);
+            return Ok(());
+        }
+
+        let manifest_bytes = self.transport.download(&creds, &manifest_key).await?;
+        let manifest = BlobManifest::open(dek, &manifest_bytes, s3_key)?;
+        let object_key = manifest.object_key.as_str();
+        let mut state = self.download_state(blob_id, object_key, &sink, &manifest);
+        let resumable = sink.path().is_some();
+        let chunks = manifest.chunk_hashes.len() as u64;
+        let mut content = Sha256::new();
+
+        // Keep the chunks an earlier attempt wrote, as long as they still
+        // read back intact.
+        let mut next = 0;
+        while let Some(done) = state.completed_chunk(next) {
+            let offset = next * manifest.chunk_size;
+            match sink.read_back(offset, manifest.plain_len(next)) {
+                Some(data) if hex::encode(Sha256::digest(&data)) == done.plain_hash => {
+                    content.update(&data);
+                    next += 1;
+                }
+                _ => break,
+            }
+        }
+        state.completed.retain(|c| c.index < next);
+
+        for index in next..chunks {
+            let (start, end) = manifest.encrypted_range(index);
+            let creds = self.cred_manager.get_credentials().await?;
+            let encrypted = self.transport.download_range(&creds, object_key, start, end).await?;
+            let hash = chunk_hash(&encrypted);
+            if hash != manifest.chunk_hashes[index as usize] {
+                return Err(CloudError::Envelope(format!(
+                    "blob chunk {index} of {s3_key} failed its integrity check"
+                )));
+            }
+            let plaintext = decrypt_chunk(dek, &encrypted)?;
+            let offset = index * manifest.chunk_size;
+            sink.write_chunk(offset, &plaintext)?;
+            content.update(&plaintext);
+            state.completed.push(CompletedChunk {
+                index,
+                hash,
+                plain_hash: hex::encode(Sha256::digest(&plaintext)),
+                etag: None,
+            });
+            if resumable {
+                self.transfers.put(&state)?;
+            }
+            let done = offset + plaintext.len() as u64;
+            report(progress, blob_id, TransferDirection::Download, done, manifest.total_size);
+        }
+
+        if hex::encode(content.finalize()) != manifest.content_hash {
+            self.transfers.remove(blob_id, TransferDirection::Download)?;
+            return Err(CloudError::Envelope(format!(
+                "blob {s3_key} does not match its content hash"
+            )));
+        }
+        sink.finish(manifest.total_size)?;
+        self.transfers.remove(blob_id, TransferDirection::Download)?;
+        Ok(())
+    }
+
+    /// Downloads a blob uploaded before chunking: a single JSON-encoded
+    /// ciphertext.
+    async fn download_legacy(&self, s3_key: &str, dek: &DerivedKey) -> CloudResult<Vec<u8>> {
+        let creds = self.cred_manager.get_credentials().await?;
+        let encrypted_bytes = self.transport.download(&creds, s3_key).await?;
+        let encrypted: EncryptedData = serde_json::from_slice(&encrypted_bytes)?;
+        decrypt(dek, &encrypted)
             .map_err(|e| CloudError::Envelope(format!("blob decryption failed: {e}")))
     }
 
-    /// Gets blob metadata for an entity.
-    pub async fn get_entity_blobs(&self, entity_id: &str) -> CloudResult<Vec<BlobMeta>> {
-        self.api.get_entity_blobs(entity_id).await
+    /// Returns the saved state of a matching interrupted download of the
+    /// object version `object_key`, or a fresh one.
+    fn download_state(
+        &self,
+        blob_id: &str,
+        object_key: &str,
+        sink: &ChunkSink<'_>,
+        manifest: &BlobManifest,
+    ) -> TransferState {
+        let local_path = sink.path().map(Path::to_path_buf).unwrap_or_default();
+        if sink.path().is_some()
+            && let Some(saved) = self.transfers.get(blob_id, TransferDirection::Download)
+            && saved.s3_key == object_key
+            && saved.local_path == local_path
+            && saved.total_size == manifest.total_size
+            && saved.chunk_size == manifest.chunk_size
+        {
+            return saved;
+        }
+        TransferState {
+            blob_id: blob_id.to_string(),
+            direction: TransferDirection::Download,
+            s3_key: object_key.to_string(),
+            local_path,
+            total_size: manifest.total_size,
+            chunk_size: manifest.chunk_size,
+            upload_id: None,
+            completed: Vec::new(),
+        }
     }
 }
@@ -0,0 +1,346 @@
+//! Chunked blob format and resumable transfer state.
+//!
+//! Blobs are split into fixed-size plaintext chunks, each encrypted on its
+//! own as `nonce || ciphertext`, so every encrypted chunk has a known size
+//! and offset in the S3 object. Uploads send one chunk per multipart part and
+//! downloads fetch one chunk per ranged GET; neither holds more than a chunk
+//! in memory.
+//!
+//! A [`BlobManifest`] stored next to the object (encrypted with the same
+//! DEK) lists the SHA-256 of every encrypted chunk in order plus the hash of
+//! the whole plaintext, so a swapped, reordered or truncated chunk is
+//! rejected before decryption. The manifest names the blob it belongs to,
+//! so a manifest copied from another blob fails to open.
+//!
+//! Each upload writes the encrypted object under a fresh versioned key
+//! ([`object_s3_key`]) and then replaces the manifest, which names that
+//! version. The manifest PUT is the switch: until it lands readers keep
+//! reading the previous version whole, never a new object with an old
+//! manifest.
+//!
+//! Progress of an interrupted transfer lives in a [`TransferStateStore`], so
+//! calling the same upload or download again skips the chunks already done.
+
+use crate::atomic_file::write_atomic;
+use crate::error::{CloudError, CloudResult};
+use privstack_crypto::{decrypt, encrypt, DerivedKey, EncryptedData, NONCE_SIZE, TAG_SIZE};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Plaintext bytes per chunk. Above the S3 multipart minimum part size (5 MiB).
+pub const BLOB_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Version of the manifest format.
+const MANIFEST_VERSION: u32 = 1;
+
+/// S3 key of the manifest for the blob object at `blob_key`.
+pub fn manifest_s3_key(blob_key: &str) -> String {
+    format!("{blob_key}.manifest")
+}
+
+/// S3 key of one uploaded version of the blob at `blob_key`.
+pub fn object_s3_key(blob_key: &str, version: &str) -> String {
+    format!("{blob_key}.v{version}")
+}
+
+/// Whether `object_key` is a version of the blob at `blob_key`.
+pub fn is_object_of(blob_key: &str, object_key: &str) -> bool {
+    object_key
+        .strip_prefix(blob_key)
+        .and_then(|rest| rest.strip_prefix(".v"))
+        .is_some_and(|version| !version.is_empty() && !version.contains('/'))
+}
+
+/// Encrypts one chunk as `nonce || ciphertext`.
+pub fn encrypt_chunk(dek: &DerivedKey, plaintext: &[u8]) -> CloudResult<Vec<u8>> {
+    let encrypted = encrypt(dek, plaintext)
+        .map_err(|e| CloudError::Envelope(format!("blob chunk encryption failed: {e}")))?;
+    let mut bytes = Vec::with_capacity(encrypted.len());
+    bytes.extend_from_slice(&encrypted.nonce);
+    bytes.extend_from_slice(&encrypted.ciphertext);
+    Ok(bytes)
+}
+
+/// Decrypts a chunk produced by [`encrypt_chunk`].
+pub fn decrypt_chunk(dek: &DerivedKey, bytes: &[u8]) -> CloudResult<Vec<u8>> {
+    if bytes.len() < NONCE_SIZE + TAG_SIZE {
+        return Err(CloudError::Envelope("blob chunk too short".to_string()));
+    }
+    let mut nonce = [0u8; NONCE_SIZE];
+    nonce.copy_from_slice(&bytes[..NONCE_SIZE]);
+    let encrypted = EncryptedData {
+        nonce,
+        ciphertext: bytes[NONCE_SIZE..].to_vec(),
+    };
+    decrypt(dek, &encrypted)
+        .map_err(|e| CloudError::Envelope(format!("blob chunk decryption failed: {e}")))
+}
+
+/// Hex SHA-256 of an encrypted chunk.
+pub fn chunk_hash(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// Number of chunks for a blob of `total_size` bytes. An empty blob is one
+/// empty chunk.
+pub fn chunk_count(total_size: u64, chunk_size: u64) -> u64 {
+    total_size.div_ceil(chunk_size).max(1)
+}
+
+/// Chunk layout and integrity hashes of an uploaded blob.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobManifest {
+    pub version: u32,
+    /// S3 key of the blob this manifest describes.
+    pub blob_key: String,
+    /// S3 key of the encrypted object version the chunks live in.
+    pub object_key: String,
+    /// Plaintext bytes per chunk (the last chunk may be shorter).
+    pub chunk_size: u64,
+    /// Plaintext size of the blob.
+    pub total_size: u64,
+    /// Hex SHA-256 of the plaintext.
+    pub content_hash: String,
+    /// Hex SHA-256 of each encrypted chunk, in order.
+    pub chunk_hashes: Vec<String>,
+}
+
+impl BlobManifest {
+    pub fn new(
+        blob_key: String,
+        object_key: String,
+        chunk_size: u64,
+        total_size: u64,
+        content_hash: String,
+        chunk_hashes: Vec<String>,
+    ) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            blob_key,
+            object_key,
+            chunk_size,
+            total_size,
+            content_hash,
+            chunk_hashes,
+        }
+    }
+
+    /// Checks that the chunk list matches the declared sizes.
+    pub fn validate(&self) -> CloudResult<()> {
+        if self.version > MANIFEST_VERSION {
+            return Err(CloudError::Envelope(format!(
+                "blob manifest version {} is newer than supported ({MANIFEST_VERSION})",
+                self.version
+            )));
+        }
+        if self.chunk_size == 0
+            || self.chunk_hashes.len() as u64 != chunk_count(self.total_size, self.chunk_size)
+        {
+            return Err(CloudError::Envelope(
+                "blob manifest chunk layout is inconsistent".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Plaintext length of chunk `index`.
+    pub fn plain_len(&self, index: u64) -> u64 {
+        let start = index * self.chunk_size;
+        self.total_size.saturating_sub(start).min(self.chunk_size)
+    }
+
+    /// Inclusive byte range of encrypted chunk `index` within the object.
+    pub fn encrypted_range(&self, index: u64) -> (u64, u64) {
+        let overhead = (NONCE_SIZE + TAG_SIZE) as u64;
+        let start = index * (self.chunk_size + overhead);
+        (start, start + self.plain_len(index) + overhead - 1)
+    }
+
+    /// Total size of the encrypted object.
+    pub fn encrypted_size(&self) -> u64 {
+        let overhead = (NONCE_SIZE + TAG_SIZE) as u64;
+        self.total_size + overhead * self.chunk_hashes.len() as u64
+    }
+
+    /// Encrypts the manifest for storage next to the blob.
+    pub fn seal(&self, dek: &DerivedKey) -> CloudResult<Vec<u8>> {
+        let encrypted = encrypt(dek, &serde_json::to_vec(self)?)
+            .map_err(|e| CloudError::Envelope(format!("manifest encryption failed: {e}")))?;
+        Ok(serde_json::to_vec(&encrypted)?)
+    }
+
+    /// Decrypts and validates a stored manifest, rejecting one that
+    /// describes a blob other than `blob_key`.
+    pub fn open(dek: &DerivedKey, bytes: &[u8], blob_key: &str) -> CloudResult<Self> {
+        let encrypted: EncryptedData = serde_json::from_slice(bytes)?;
+        let plaintext = decrypt(dek, &encrypted)
+            .map_err(|e| CloudError::Envelope(format!("manifest decryption failed: {e}")))?;
+        let manifest: Self = serde_json::from_slice(&plaintext)?;
+        manifest.validate()?;
+        if manifest.blob_key != blob_key || !is_object_of(blob_key, &manifest.object_key) {
+            return Err(CloudError::Envelope(format!(
+                "manifest at {} does not belong to blob {blob_key}",
+                manifest_s3_key(blob_key)
+            )));
+        }
+        Ok(manifest)
+    }
+}
+
+/// Direction of a blob transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+/// A chunk that finished transferring.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompletedChunk {
+    pub index: u64,
+    /// Hex SHA-256 of the encrypted chunk.
+    pub hash: String,
+    /// Hex SHA-256 of the plaintext chunk, to notice a local file that
+    /// changed between attempts.
+    pub plain_hash: String,
+    /// Multipart ETag (uploads only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+}
+
+/// Resume point of an interrupted transfer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferState {
+    pub blob_id: String,
+    pub direction: TransferDirection,
+    pub s3_key: String,
+    /// File being uploaded from or downloaded to.
+    pub local_path: PathBuf,
+    /// Plaintext size of the blob.
+    pub total_size: u64,
+    pub chunk_size: u64,
+    /// Multipart upload ID (uploads only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_id: Option<String>,
+    pub completed: Vec<CompletedChunk>,
+}
+
+impl TransferState {
+    /// Plaintext bytes covered by the completed chunks.
+    pub fn bytes_done(&self) -> u64 {
+        self.completed
+            .iter()
+            .map(|c| {
+                let start = c.index * self.chunk_size;
+                self.total_size.saturating_sub(start).min(self.chunk_size)
+            })
+            .sum()
+    }
+
+    /// Returns the completed record for chunk `index`.
+    pub fn completed_chunk(&self, index: u64) -> Option<&CompletedChunk> {
+        self.completed.iter().find(|c| c.index == index)
+    }
+}
+
+/// Progress of a blob transfer, reported after every chunk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobProgress {
+    pub blob_id: String,
+    pub direction: TransferDirection,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+type TransferKey = (String, TransferDirection);
+
+/// Resume state of interrupted transfers, optionally persisted to a JSON file.
+pub struct TransferStateStore {
+    path: Option<PathBuf>,
+    states: Mutex<HashMap<TransferKey, TransferState>>,
+}
+
+impl TransferStateStore {
+    /// Opens the store at `path`, loading saved transfers if the file exists.
+    pub fn open(path: impl Into<PathBuf>) -> CloudResult<Self> {
+        let path = path.into();
+        let states = match std::fs::read(&path) {
+            Ok(bytes) => {
+                let list: Vec<TransferState> = serde_json::from_slice(&bytes)?;
+                list.into_iter()
+                    .map(|s| ((s.blob_id.clone(), s.direction), s))
+                    .collect()
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                return Err(CloudError::Config(format!(
+                    "failed to read transfer state {}: {e}",
+                    path.display()
+                )))
+            }
+        };
+        Ok(Self {
+            path: Some(path),
+            states: Mutex::new(states),
+        })
+    }
+
+    /// Creates a store that keeps transfer state in memory only.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            states: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the saved state of a transfer.
+    pub fn get(&self, blob_id: &str, direction: TransferDirection) -> Option<TransferState> {
+        self.states
+            .lock()
+            .unwrap()
+            .get(&(blob_id.to_string(), direction))
+            .cloned()
+    }
+
+    /// Returns every unfinished transfer.
+    pub fn list(&self) -> Vec<TransferState> {
+        let mut list: Vec<TransferState> = self.states.lock().unwrap().values().cloned().collect();
+        list.sort_by(|a, b| (&a.blob_id, a.direction).cmp(&(&b.blob_id, b.direction)));
+        list
+    }
+
+    /// Saves (or replaces) a transfer's state.
+    pub fn put(&self, state: &TransferState) -> CloudResult<()> {
+        let mut states = self.states.lock().unwrap();
+        states.insert((state.blob_id.clone(), state.direction), state.clone());
+        self.persist(&states)
+    }
+
+    /// Forgets a transfer. Returns its last state, if any.
+    pub fn remove(
+        &self,
+        blob_id: &str,
+        direction: TransferDirection,
+    ) -> CloudResult<Option<TransferState>> {
+        let mut states = self.states.lock().unwrap();
+        let removed = states.remove(&(blob_id.to_string(), direction));
+        if removed.is_some() {
+            self.persist(&states)?;
+        }
+        Ok(removed)
+    }
+
+    fn persist(&self, states: &HashMap<TransferKey, TransferState>) -> CloudResult<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut list: Vec<&TransferState> = states.values().collect();
+        list.sort_by(|a, b| (&a.blob_id, a.direction).cmp(&(&b.blob_id, b.direction)));
+        let json = serde_json::to_vec_pretty(&list)?;
+        write_atomic(path, &json, "transfer state")
+    }
+}
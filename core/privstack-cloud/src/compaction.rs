@@ -6,9 +6,11 @@
 
 use crate::api_client::CloudApiClient;
 use crate::credential_manager::CredentialManager;
+use crate::dek_registry::DekRegistry;
 use crate::error::{CloudError, CloudResult};
 use crate::s3_transport::S3Transport;
-use privstack_crypto::{encrypt, DerivedKey};
+use crate::types::EncryptedPayload;
+use privstack_crypto::encrypt;
 use tracing::{debug, info};
 
 /// Threshold: trigger compaction after this many batches per entity.
@@ -65,15 +67,19 @@ pub async fn create_snapshot(
     user_id: i64,
     workspace_id: &str,
     entity_id: &str,
-    entity_dek: &DerivedKey,
+    dek_registry: &DekRegistry,
     serialized_state: &[u8],
     cursor_position: i64,
 ) -> CloudResult<()> {
-    // Encrypt snapshot with entity DEK
-    let encrypted = encrypt(entity_dek, serialized_state)
+    // Encrypt snapshot with the entity's current DEK generation
+    let (dek_generation, entity_dek) = dek_registry.current(entity_id).await?;
+    let encrypted = encrypt(&entity_dek, serialized_state)
         .map_err(|e| CloudError::Envelope(format!("snapshot encryption failed: {e}")))?;
 
-    let snapshot_bytes = serde_json::to_vec(&encrypted)?;
+    let snapshot_bytes = serde_json::to_vec(&EncryptedPayload {
+        dek_generation,
+        data: encrypted,
+    })?;
     let s3_key = snapshot_s3_key(user_id, workspace_id, entity_id, cursor_position);
 
     // Upload to S3
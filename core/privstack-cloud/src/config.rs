@@ -22,6 +22,11 @@ pub struct CloudConfig {
 
     /// Poll interval for checking new data from other devices (seconds).
     pub poll_interval_secs: u64,
+
+    /// Base64 Ed25519 key that signs the server's public-key log. When set,
+    /// recipient keys are only used if the log verifies against it.
+    #[serde(default)]
+    pub key_log_public_key: Option<String>,
 }
 
 impl Default for CloudConfig {
@@ -33,6 +38,7 @@ impl Default for CloudConfig {
             s3_endpoint_override: None,
             credential_refresh_margin_secs: 300, // 5 minutes before expiry
             poll_interval_secs: 30,
+            key_log_public_key: None,
         }
     }
 }
@@ -48,6 +54,7 @@ impl CloudConfig {
             s3_endpoint_override: Some("http://localhost:9000".to_string()),
             credential_refresh_margin_secs: 60,
             poll_interval_secs: 5,
+            key_log_public_key: None,
         }
     }
 }
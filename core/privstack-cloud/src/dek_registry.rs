@@ -3,12 +3,87 @@
 //! The sync engine reads DEKs from this registry to encrypt outgoing
 //! batches and decrypt incoming batches. The application layer populates
 //! it when entities are loaded, created, or received via sharing.
+//!
+//! Each entity key has a generation. Revoking a share rotates the entity to
+//! a new generation; older generations are kept so batches encrypted before
+//! the rotation stay readable. Generation 0 is the entity's original key,
+//! which falls back to the workspace default DEK. Later generations are
+//! random IDs rather than a counter, so two devices rotating the same entity
+//! never register different keys under one generation; the registry tracks
+//! which one is current.
+//!
+//! Once [`DekRegistry::open_persisted`] is called, per-entity keys are kept
+//! in a file encrypted under the local master key and rewritten on every
+//! change, so shared entities decrypt after a restart without contacting
+//! the server. The workspace default DEK is never written out.
 
+use crate::atomic_file::write_atomic;
+use crate::envelope::EnvelopeManager;
 use crate::error::{CloudError, CloudResult};
-use privstack_crypto::DerivedKey;
-use std::collections::HashMap;
-use std::sync::Arc;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use privstack_crypto::{
+    decrypt, encrypt, generate_random_key, DerivedKey, EncryptedData, KEY_SIZE,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+/// Version of the persisted registry format.
+const DEK_FILE_VERSION: u32 = 1;
+
+/// All known key generations of one entity.
+#[derive(Clone)]
+struct EntityDeks {
+    current: u32,
+    keys: HashMap<u32, DerivedKey>,
+    /// Received through a share rather than owned locally.
+    shared: bool,
+    /// A rotation was started but could not be sealed to every recipient.
+    rotation_pending: bool,
+}
+
+impl EntityDeks {
+    fn new(current: u32) -> Self {
+        Self {
+            current,
+            keys: HashMap::new(),
+            shared: false,
+            rotation_pending: false,
+        }
+    }
+
+    fn current_key(&self) -> Option<&DerivedKey> {
+        self.keys.get(&self.current)
+    }
+}
+
+/// On-disk form of one entity's keys (inside the encrypted file).
+#[derive(Serialize, Deserialize)]
+struct PersistedEntity {
+    current: u32,
+    /// Base64 key per generation.
+    keys: BTreeMap<u32, String>,
+    #[serde(default)]
+    shared: bool,
+    #[serde(default)]
+    rotation_pending: bool,
+}
+
+/// Plaintext of the registry file.
+#[derive(Serialize, Deserialize)]
+struct PersistedRegistry {
+    version: u32,
+    entities: BTreeMap<String, PersistedEntity>,
+}
+
+/// Where and under which key the registry is persisted.
+struct DekFile {
+    path: PathBuf,
+    master_key: DerivedKey,
+}
 
 /// Thread-safe entity DEK registry for cloud sync encryption.
 ///
@@ -17,8 +92,9 @@ use tokio::sync::RwLock;
 /// key has been registered.
 #[derive(Clone)]
 pub struct DekRegistry {
-    deks: Arc<RwLock<HashMap<String, DerivedKey>>>,
+    deks: Arc<RwLock<HashMap<String, EntityDeks>>>,
     default_dek: Arc<RwLock<Option<DerivedKey>>>,
+    file: Arc<Mutex<Option<DekFile>>>,
 }
 
 impl DekRegistry {
@@ -26,18 +102,130 @@ impl DekRegistry {
         Self {
             deks: Arc::new(RwLock::new(HashMap::new())),
             default_dek: Arc::new(RwLock::new(None)),
+            file: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// Loads the registry file at `path` (if it exists), merges it with the
+    /// keys already registered and persists every later change there,
+    /// encrypted under `master_key`. Returns the number of entities loaded.
+    pub async fn open_persisted(
+        &self,
+        path: impl Into<PathBuf>,
+        master_key: DerivedKey,
+    ) -> CloudResult<usize> {
+        let path = path.into();
+        let loaded = read_dek_file(&path, &master_key)?;
+        let count = loaded.len();
+
+        let mut deks = self.deks.write().await;
+        for (entity_id, persisted) in loaded {
+            let entry = deks
+                .entry(entity_id)
+                .or_insert_with(|| EntityDeks::new(persisted.current));
+            for (generation, dek) in persisted.keys {
+                entry.keys.entry(generation).or_insert(dek);
+            }
+            // A key registered this session is newer than the file, unless
+            // it is only the original one.
+            if entry.current == 0 {
+                entry.current = persisted.current;
+            }
+            entry.shared |= persisted.shared;
+            entry.rotation_pending |= persisted.rotation_pending;
+        }
+        *self.file.lock().unwrap() = Some(DekFile { path, master_key });
+        self.persist(&deks)?;
+        info!("loaded {count} persisted entity DEKs");
+        Ok(count)
+    }
+
+    /// Re-encrypts the registry file under a new master key (e.g. after a
+    /// password change). Does nothing if the registry is not persisted; see
+    /// [`Self::rekey_file`] for a file that has not been opened.
+    pub async fn rekey(&self, master_key: DerivedKey) -> CloudResult<()> {
+        let deks = self.deks.read().await;
+        match self.file.lock().unwrap().as_mut() {
+            Some(file) => file.master_key = master_key,
+            None => return Ok(()),
+        }
+        self.persist(&deks)
+    }
+
+    /// Returns true once [`Self::open_persisted`] has attached a file.
+    pub fn is_persisted(&self) -> bool {
+        self.file.lock().unwrap().is_some()
+    }
+
+    /// Re-encrypts a registry file that no registry has open from
+    /// `old_master` to `new_master`. Returns false if there is no file.
+    pub fn rekey_file(
+        path: &Path,
+        old_master: &DerivedKey,
+        new_master: &DerivedKey,
+    ) -> CloudResult<bool> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => {
+                return Err(CloudError::Config(format!(
+                    "failed to read DEK registry {}: {e}",
+                    path.display()
+                )))
+            }
+        };
+        let encrypted: EncryptedData = serde_json::from_slice(&bytes)?;
+        let plaintext = decrypt(old_master, &encrypted)
+            .map_err(|e| CloudError::Envelope(format!("DEK registry decryption failed: {e}")))?;
+        let encrypted = encrypt(new_master, &plaintext)
+            .map_err(|e| CloudError::Envelope(format!("DEK registry encryption failed: {e}")))?;
+        write_atomic(path, &serde_json::to_vec(&encrypted)?, "DEK registry")?;
+        Ok(true)
+    }
+
     /// Sets the workspace-level default DEK used for all entities that
     /// don't have a per-entity key registered.
     pub async fn set_default(&self, dek: DerivedKey) {
         *self.default_dek.write().await = Some(dek);
     }
 
-    /// Registers a DEK for an entity.
+    /// Registers a DEK for an entity's current generation.
     pub async fn insert(&self, entity_id: String, dek: DerivedKey) {
-        self.deks.write().await.insert(entity_id, dek);
+        let mut deks = self.deks.write().await;
+        let entry = deks.entry(entity_id).or_insert_with(|| EntityDeks::new(0));
+        entry.keys.insert(entry.current, dek);
+        self.persist_or_warn(&deks);
+    }
+
+    /// Registers the DEK of a specific generation (e.g. the one in the
+    /// entity's current share envelope) and makes it current.
+    pub async fn insert_generation(&self, entity_id: String, generation: u32, dek: DerivedKey) {
+        self.insert_generation_inner(entity_id, generation, dek, false)
+            .await;
+    }
+
+    /// Like [`Self::insert_generation`], but marks the entity as shared with
+    /// us so [`Self::prune_shared`] drops it once the share is revoked.
+    pub async fn insert_shared(&self, entity_id: String, generation: u32, dek: DerivedKey) {
+        self.insert_generation_inner(entity_id, generation, dek, true)
+            .await;
+    }
+
+    async fn insert_generation_inner(
+        &self,
+        entity_id: String,
+        generation: u32,
+        dek: DerivedKey,
+        shared: bool,
+    ) {
+        let mut deks = self.deks.write().await;
+        let entry = deks
+            .entry(entity_id)
+            .or_insert_with(|| EntityDeks::new(generation));
+        entry.keys.insert(generation, dek);
+        entry.current = generation;
+        entry.shared |= shared;
+        self.persist_or_warn(&deks);
     }
 
     /// Retrieves a cloned DEK for an entity.
@@ -45,24 +233,168 @@ impl DekRegistry {
     /// Looks up a per-entity key first, then falls back to the workspace
     /// default DEK. Returns an error only if neither is available.
     pub async fn get(&self, entity_id: &str) -> CloudResult<DerivedKey> {
-        if let Some(dek) = self.deks.read().await.get(entity_id).cloned() {
+        self.current(entity_id).await.map(|(_, dek)| dek)
+    }
+
+    /// Retrieves the current DEK of an entity along with its generation.
+    pub async fn current(&self, entity_id: &str) -> CloudResult<(u32, DerivedKey)> {
+        let (generation, dek) = match self.deks.read().await.get(entity_id) {
+            Some(entry) => (entry.current, entry.current_key().cloned()),
+            None => (0, None),
+        };
+        match dek {
+            Some(dek) => Ok((generation, dek)),
+            None => {
+                let dek = self.get_generation(entity_id, generation).await?;
+                Ok((generation, dek))
+            }
+        }
+    }
+
+    /// Returns the current generation for an entity, if any per-entity key
+    /// is registered.
+    pub async fn current_generation(&self, entity_id: &str) -> Option<u32> {
+        self.deks.read().await.get(entity_id).map(|e| e.current)
+    }
+
+    /// Retrieves the DEK an entity used at `generation`. Generation 0 falls
+    /// back to the workspace default DEK.
+    pub async fn get_generation(
+        &self,
+        entity_id: &str,
+        generation: u32,
+    ) -> CloudResult<DerivedKey> {
+        if let Some(dek) = self
+            .deks
+            .read()
+            .await
+            .get(entity_id)
+            .and_then(|entry| entry.keys.get(&generation).cloned())
+        {
+            return Ok(dek);
+        }
+        if generation == 0
+            && let Some(dek) = self.default_dek.read().await.clone()
+        {
             return Ok(dek);
         }
-        self.default_dek
+        Err(CloudError::Envelope(if generation == 0 {
+            format!("no DEK registered for entity {entity_id}")
+        } else {
+            format!("no DEK registered for entity {entity_id} generation {generation}")
+        }))
+    }
+
+    /// Like [`Self::get_generation`], but when the generation is unknown,
+    /// opens the entity's current key envelope sealed to this account and
+    /// registers it first. This is how an owner's device picks up a
+    /// rotation done by another of its devices, and how a recipient picks up
+    /// one that happened since the last [`ShareManager::refresh_shared_keys`].
+    ///
+    /// [`ShareManager::refresh_shared_keys`]: crate::sharing::ShareManager::refresh_shared_keys
+    pub async fn fetch_generation(
+        &self,
+        envelope_mgr: &EnvelopeManager,
+        entity_id: &str,
+        generation: u32,
+    ) -> CloudResult<DerivedKey> {
+        if let Ok(dek) = self.get_generation(entity_id, generation).await {
+            return Ok(dek);
+        }
+        let (current, dek) = envelope_mgr.open_entity_key(entity_id).await?;
+        if self.get_generation(entity_id, current).await.is_err() {
+            info!("fetched DEK generation {current} of entity {entity_id}");
+            self.insert_generation(entity_id.to_string(), current, dek)
+                .await;
+        }
+        self.get_generation(entity_id, generation).await
+    }
+
+    /// Generates a fresh DEK under a new random generation for an entity,
+    /// without registering it. Seal it to the entity's recipients, then make
+    /// it current with [`Self::commit_rotation`].
+    pub async fn new_generation(&self, entity_id: &str) -> (u32, DerivedKey) {
+        let deks = self.deks.read().await;
+        let used = deks.get(entity_id).map(|entry| &entry.keys);
+        let generation = loop {
+            let candidate = uuid::Uuid::new_v4().as_u128() as u32;
+            if candidate != 0 && used.is_none_or(|keys| !keys.contains_key(&candidate)) {
+                break candidate;
+            }
+        };
+        (generation, generate_random_key())
+    }
+
+    /// Makes a generation from [`Self::new_generation`] current and clears
+    /// any pending rotation. Older generations stay registered for
+    /// decrypting existing batches.
+    pub async fn commit_rotation(&self, entity_id: &str, generation: u32, dek: DerivedKey) {
+        let mut deks = self.deks.write().await;
+        let entry = deks
+            .entry(entity_id.to_string())
+            .or_insert_with(|| EntityDeks::new(0));
+        entry.keys.insert(generation, dek);
+        entry.current = generation;
+        entry.rotation_pending = false;
+        self.persist_or_warn(&deks);
+    }
+
+    /// Records that an entity still needs a rotation (e.g. a share was
+    /// revoked but the new key could not be sealed to everyone yet).
+    pub async fn mark_rotation_pending(&self, entity_id: &str) {
+        let mut deks = self.deks.write().await;
+        deks.entry(entity_id.to_string())
+            .or_insert_with(|| EntityDeks::new(0))
+            .rotation_pending = true;
+        self.persist_or_warn(&deks);
+    }
+
+    /// Returns the entities with a pending rotation, sorted.
+    pub async fn pending_rotations(&self) -> Vec<String> {
+        let mut pending: Vec<String> = self
+            .deks
             .read()
             .await
-            .clone()
-            .ok_or_else(|| {
-                CloudError::Envelope(format!("no DEK registered for entity {entity_id}"))
-            })
+            .iter()
+            .filter(|(_, entry)| entry.rotation_pending)
+            .map(|(id, _)| id.clone())
+            .collect();
+        pending.sort();
+        pending
     }
 
-    /// Removes a DEK (e.g. after entity deletion or DEK rotation).
+    /// Removes an entity's DEKs (e.g. after entity deletion), returning the
+    /// current one.
     pub async fn remove(&self, entity_id: &str) -> Option<DerivedKey> {
-        self.deks.write().await.remove(entity_id)
+        let mut deks = self.deks.write().await;
+        let removed = deks
+            .remove(entity_id)
+            .and_then(|entry| entry.current_key().cloned());
+        self.persist_or_warn(&deks);
+        removed
+    }
+
+    /// Drops the keys of shared-with-us entities that are not in
+    /// `still_shared` (their share was revoked). Returns the dropped IDs.
+    pub async fn prune_shared(&self, still_shared: &HashSet<String>) -> Vec<String> {
+        let mut deks = self.deks.write().await;
+        let mut dropped: Vec<String> = deks
+            .iter()
+            .filter(|(id, entry)| entry.shared && !still_shared.contains(*id))
+            .map(|(id, _)| id.clone())
+            .collect();
+        if dropped.is_empty() {
+            return dropped;
+        }
+        for id in &dropped {
+            deks.remove(id);
+        }
+        self.persist_or_warn(&deks);
+        dropped.sort();
+        dropped
     }
 
-    /// Returns the number of registered DEKs.
+    /// Returns the number of entities with a registered DEK.
     pub async fn len(&self) -> usize {
         self.deks.read().await.len()
     }
@@ -71,6 +403,91 @@ impl DekRegistry {
     pub async fn is_empty(&self) -> bool {
         self.deks.read().await.is_empty()
     }
+
+    fn persist_or_warn(&self, deks: &HashMap<String, EntityDeks>) {
+        if let Err(e) = self.persist(deks) {
+            warn!("failed to persist DEK registry: {e}");
+        }
+    }
+
+    fn persist(&self, deks: &HashMap<String, EntityDeks>) -> CloudResult<()> {
+        let file = self.file.lock().unwrap();
+        let Some(file) = file.as_ref() else {
+            return Ok(());
+        };
+        let registry = PersistedRegistry {
+            version: DEK_FILE_VERSION,
+            entities: deks
+                .iter()
+                .map(|(id, entry)| {
+                    let keys = entry
+                        .keys
+                        .iter()
+                        .map(|(g, dek)| (*g, STANDARD.encode(dek.as_bytes())))
+                        .collect();
+                    let persisted = PersistedEntity {
+                        current: entry.current,
+                        keys,
+                        shared: entry.shared,
+                        rotation_pending: entry.rotation_pending,
+                    };
+                    (id.clone(), persisted)
+                })
+                .collect(),
+        };
+        let plaintext = serde_json::to_vec(&registry)?;
+        let encrypted = encrypt(&file.master_key, &plaintext)
+            .map_err(|e| CloudError::Envelope(format!("DEK registry encryption failed: {e}")))?;
+        write_atomic(&file.path, &serde_json::to_vec(&encrypted)?, "DEK registry")
+    }
+}
+
+/// Reads and decrypts a registry file. A missing file is an empty registry.
+fn read_dek_file(
+    path: &Path,
+    master_key: &DerivedKey,
+) -> CloudResult<HashMap<String, EntityDeks>> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => {
+            return Err(CloudError::Config(format!(
+                "failed to read DEK registry {}: {e}",
+                path.display()
+            )))
+        }
+    };
+    let encrypted: EncryptedData = serde_json::from_slice(&bytes)?;
+    let plaintext = decrypt(master_key, &encrypted)
+        .map_err(|e| CloudError::Envelope(format!("DEK registry decryption failed: {e}")))?;
+    let registry: PersistedRegistry = serde_json::from_slice(&plaintext)?;
+    if registry.version > DEK_FILE_VERSION {
+        return Err(CloudError::Config(format!(
+            "DEK registry version {} is newer than supported ({DEK_FILE_VERSION})",
+            registry.version
+        )));
+    }
+
+    let mut deks = HashMap::new();
+    for (entity_id, persisted) in registry.entities {
+        let mut entry = EntityDeks::new(persisted.current);
+        entry.shared = persisted.shared;
+        entry.rotation_pending = persisted.rotation_pending;
+        for (generation, encoded) in persisted.keys {
+            let bytes: [u8; KEY_SIZE] = STANDARD
+                .decode(&encoded)
+                .ok()
+                .and_then(|b| b.try_into().ok())
+                .ok_or_else(|| {
+                    CloudError::Envelope(format!(
+                        "invalid DEK for entity {entity_id} generation {generation}"
+                    ))
+                })?;
+            entry.keys.insert(generation, DerivedKey::from_bytes(bytes));
+        }
+        deks.insert(entity_id, entry);
+    }
+    Ok(deks)
 }
 
 impl Default for DekRegistry {
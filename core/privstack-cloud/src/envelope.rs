@@ -3,24 +3,51 @@
 //! Wraps privstack-crypto envelope primitives to provide high-level
 //! share key management: creating envelopes for recipients, opening
 //! received envelopes, and coordinating DEK rotation on revocation.
+//!
+//! Recipient keys are checked against local pins and, when configured, the
+//! server's signed key log before anything is sealed to them (see
+//! [`crate::key_transparency`]).
 
 use crate::api_client::CloudApiClient;
 use crate::error::{CloudError, CloudResult};
+use crate::key_transparency::{safety_number, KeyPinStore, PinCheck};
 use crypto_box::PublicKey;
 use privstack_crypto::envelope::{self as crypto_env, CloudKeyPair};
-use privstack_crypto::SealedEnvelope;
+use privstack_crypto::{DerivedKey, IdentityPublicKey, SealedEnvelope, KEY_SIZE};
 use std::sync::Arc;
-use tracing::debug;
+use tracing::{debug, info, warn};
 
 /// Manages envelope encryption for entity sharing.
 pub struct EnvelopeManager {
     api: Arc<CloudApiClient>,
     keypair: Option<CloudKeyPair>,
+    pins: Option<Arc<KeyPinStore>>,
+    key_log_key: Option<IdentityPublicKey>,
 }
 
 impl EnvelopeManager {
     pub fn new(api: Arc<CloudApiClient>) -> Self {
-        Self { api, keypair: None }
+        Self {
+            api,
+            keypair: None,
+            pins: None,
+            key_log_key: None,
+        }
+    }
+
+    /// Enables trust-on-first-use pinning of recipient keys.
+    pub fn set_key_pins(&mut self, pins: Arc<KeyPinStore>) {
+        self.pins = Some(pins);
+    }
+
+    /// Returns the pin store, if pinning is enabled.
+    pub fn key_pins(&self) -> Option<&Arc<KeyPinStore>> {
+        self.pins.as_ref()
+    }
+
+    /// Requires recipient keys to come from a key log signed by `key`.
+    pub fn set_key_log_key(&mut self, key: IdentityPublicKey) {
+        self.key_log_key = Some(key);
     }
 
     /// Sets the local keypair (after passphrase unlock or mnemonic recovery).
@@ -38,16 +65,126 @@ impl EnvelopeManager {
         self.keypair.as_ref().map(|kp| kp.public_bytes())
     }
 
+    /// Fetches a user's key from the server, through the signed key log when
+    /// one is configured. Returns the key and the verified log head.
+    async fn fetch_recipient_key(
+        &self,
+        user_id: i64,
+    ) -> CloudResult<([u8; 32], Option<(u64, String)>)> {
+        let Some(log_key) = &self.key_log_key else {
+            return Ok((self.api.get_public_key(user_id).await?, None));
+        };
+        let log = self.api.get_key_log(user_id).await?;
+        if log.user_id != user_id {
+            return Err(CloudError::KeyLog(format!(
+                "requested the log of user {user_id}, got user {}",
+                log.user_id
+            )));
+        }
+        let key = log.verify(log_key)?;
+        if let Some(pin) = self.pins.as_ref().and_then(|p| p.get(user_id))
+            && let (Some(size), Some(head)) = (pin.log_size, &pin.log_head)
+        {
+            log.verify_extends(size, head)?;
+        }
+        Ok((key, Some((log.entries.len() as u64, log.head_hash))))
+    }
+
+    /// Returns the key to seal to for a user.
+    ///
+    /// The first key seen is pinned. A key that differs from the pin is
+    /// refused with `CloudError::RecipientKeyChanged` until the change is
+    /// accepted with [`Self::accept_recipient_key`].
+    pub async fn recipient_key(&self, user_id: i64) -> CloudResult<[u8; 32]> {
+        let (key, log_head) = self.fetch_recipient_key(user_id).await?;
+        let Some(pins) = &self.pins else {
+            return Ok(key);
+        };
+        match pins.check(user_id, &key)? {
+            PinCheck::Unpinned => {
+                pins.pin(user_id, &key)?;
+                info!("pinned public key of user {user_id} on first use");
+            }
+            PinCheck::Match => {}
+            PinCheck::Changed { .. } => {
+                warn!("public key of user {user_id} changed since it was pinned; refusing to seal");
+                return Err(CloudError::RecipientKeyChanged { user_id });
+            }
+        }
+        if let Some((size, head)) = log_head {
+            pins.record_log_head(user_id, size, &head)?;
+        }
+        Ok(key)
+    }
+
+    /// Returns the key the server currently serves for a user, without
+    /// pinning it, so a changed key can be shown to the user (e.g. as a
+    /// safety number) before it is accepted.
+    pub async fn pending_recipient_key(&self, user_id: i64) -> CloudResult<[u8; 32]> {
+        self.fetch_recipient_key(user_id).await.map(|(key, _)| key)
+    }
+
+    /// Re-pins a user's key to `expected`, the changed key the user approved.
+    /// Fails with `CloudError::RecipientKeyChanged`, pinning nothing, if the
+    /// server no longer serves that key. The new pin is unverified until
+    /// safety numbers are compared again.
+    pub async fn accept_recipient_key(&self, user_id: i64, expected: &[u8; 32]) -> CloudResult<()> {
+        let pins = self
+            .pins
+            .as_ref()
+            .ok_or_else(|| CloudError::Config("key pinning is not enabled".into()))?;
+        let (key, log_head) = self.fetch_recipient_key(user_id).await?;
+        if key != *expected {
+            warn!("public key of user {user_id} is not the one approved; keeping the pin");
+            return Err(CloudError::RecipientKeyChanged { user_id });
+        }
+        pins.pin(user_id, &key)?;
+        if let Some((size, head)) = log_head {
+            pins.record_log_head(user_id, size, &head)?;
+        }
+        info!("accepted new public key for user {user_id}");
+        Ok(())
+    }
+
+    /// Returns the safety number for the local user and a user whose key is pinned.
+    /// See [`key_safety_number`](Self::key_safety_number) for an unpinned key.
+    pub fn safety_number(&self, local_user_id: i64, remote_user_id: i64) -> CloudResult<String> {
+        let local = self
+            .public_key_bytes()
+            .ok_or(CloudError::Envelope("no keypair loaded".to_string()))?;
+        let pin = self
+            .pins
+            .as_ref()
+            .and_then(|p| p.get(remote_user_id))
+            .ok_or_else(|| {
+                CloudError::NotFound(format!("no pinned key for user {remote_user_id}"))
+            })?;
+        Ok(safety_number(local_user_id, &local, remote_user_id, &pin.key_bytes()?))
+    }
+
+    /// Returns the safety number for the local user and `remote_key`.
+    pub fn key_safety_number(
+        &self,
+        local_user_id: i64,
+        remote_user_id: i64,
+        remote_key: &[u8; 32],
+    ) -> CloudResult<String> {
+        let local = self
+            .public_key_bytes()
+            .ok_or(CloudError::Envelope("no keypair loaded".to_string()))?;
+        Ok(safety_number(local_user_id, &local, remote_user_id, remote_key))
+    }
+
     /// Seals a DEK for a recipient by their user ID.
     ///
-    /// Fetches the recipient's public key from the API, then encrypts
-    /// the DEK with an ephemeral X25519 keypair.
+    /// Resolves the recipient's public key (see [`Self::recipient_key`]),
+    /// then encrypts the DEK with an ephemeral X25519 keypair.
     pub async fn seal_dek_for_user(
         &self,
         dek: &[u8],
         recipient_user_id: i64,
     ) -> CloudResult<SealedEnvelope> {
-        let pk_bytes = self.api.get_public_key(recipient_user_id).await?;
+        let pk_bytes = self.recipient_key(recipient_user_id).await?;
         let recipient_pk = PublicKey::from(pk_bytes);
 
         crypto_env::seal_dek(dek, &recipient_pk)
@@ -66,25 +203,51 @@ impl EnvelopeManager {
     }
 
     /// Creates and stores an envelope for a specific entity and recipient.
+    ///
+    /// `dek_generation` is the registry generation of `dek`, stored with the
+    /// envelope so the recipient can tell which batches it decrypts.
     pub async fn create_and_store_envelope(
         &self,
         entity_id: &str,
         dek: &[u8],
+        dek_generation: u32,
         recipient_user_id: i64,
     ) -> CloudResult<()> {
         let envelope = self.seal_dek_for_user(dek, recipient_user_id).await?;
         self.api
-            .store_share_key(entity_id, recipient_user_id, &envelope)
+            .store_share_key(entity_id, recipient_user_id, &envelope, dek_generation)
             .await?;
         debug!(
-            "stored share key envelope for entity {entity_id} -> user {recipient_user_id}"
+            "stored share key envelope for entity {entity_id} -> user {recipient_user_id} \
+             (generation {dek_generation})"
         );
         Ok(())
     }
 
     /// Retrieves and opens a DEK envelope for an entity shared with us.
-    pub async fn retrieve_and_open_dek(&self, entity_id: &str) -> CloudResult<Vec<u8>> {
-        let envelope = self.api.get_share_key(entity_id).await?;
-        self.open_dek(&envelope)
+    ///
+    /// Returns the DEK generation along with the key; register it with
+    /// `DekRegistry::insert_generation`.
+    pub async fn retrieve_and_open_dek(&self, entity_id: &str) -> CloudResult<(u32, Vec<u8>)> {
+        let share_key = self.api.get_share_key(entity_id).await?;
+        let dek = self.open_dek(&share_key.envelope)?;
+        Ok((share_key.dek_generation, dek))
+    }
+
+    /// Like [`Self::retrieve_and_open_dek`], but checks the key length and
+    /// returns it ready for the `DekRegistry`.
+    ///
+    /// The server keeps an envelope sealed to the owner's own account as well
+    /// as to each recipient, so this also yields the current generation of
+    /// an entity we own after another of our devices rotated it.
+    pub async fn open_entity_key(&self, entity_id: &str) -> CloudResult<(u32, DerivedKey)> {
+        let (generation, dek) = self.retrieve_and_open_dek(entity_id).await?;
+        let bytes = <[u8; KEY_SIZE]>::try_from(dek.as_slice()).map_err(|_| {
+            CloudError::Envelope(format!(
+                "key for entity {entity_id} has invalid length {}",
+                dek.len()
+            ))
+        })?;
+        Ok((generation, DerivedKey::from_bytes(bytes)))
     }
 }
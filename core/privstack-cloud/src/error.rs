@@ -29,12 +29,21 @@ pub enum CloudError {
     #[error("envelope encryption error: {0}")]
     Envelope(String),
 
+    #[error("public key of user {user_id} changed since it was pinned")]
+    RecipientKeyChanged { user_id: i64 },
+
+    #[error("key log verification failed: {0}")]
+    KeyLog(String),
+
     #[error("authentication required")]
     AuthRequired,
 
     #[error("authentication failed: {0}")]
     AuthFailed(String),
 
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
     #[error("serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
@@ -0,0 +1,355 @@
+//! Recipient key trust for cloud sharing.
+//!
+//! Entity DEKs are sealed to public keys served by the control plane, so a
+//! compromised server could hand out its own key and read shared data. Three
+//! defenses guard against that:
+//!
+//! - **Pinning (TOFU).** The first key seen for a user is pinned locally.
+//!   A different key later is refused (`CloudError::RecipientKeyChanged`)
+//!   until the user accepts it.
+//! - **Safety numbers.** Both parties can compare a number derived from both
+//!   keys out of band and mark the pin verified.
+//! - **Key log.** When a log signing key is configured, the server must
+//!   return each user's key history as a hash-chained log with a signed
+//!   head. The newest entry is the user's key, and a log that does not extend
+//!   the previously seen head is rejected, so silently swapping a key leaves
+//!   a permanent record.
+
+use crate::atomic_file::write_atomic;
+use crate::error::{CloudError, CloudResult};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use privstack_crypto::IdentityPublicKey;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// `prev_hash` of the first entry of a key log.
+pub const KEY_LOG_GENESIS_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+// ── Pinning ──────────────────────────────────────────────────────
+
+/// A locally pinned recipient key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyPin {
+    pub user_id: i64,
+    /// Base64 X25519 public key.
+    pub public_key: String,
+    pub pinned_at: DateTime<Utc>,
+    /// Set once the user compared safety numbers with the recipient.
+    #[serde(default)]
+    pub verified: bool,
+    /// Size of the last verified key log for this user.
+    #[serde(default)]
+    pub log_size: Option<u64>,
+    /// Head hash of the last verified key log for this user.
+    #[serde(default)]
+    pub log_head: Option<String>,
+}
+
+impl KeyPin {
+    /// Returns the pinned key bytes.
+    pub fn key_bytes(&self) -> CloudResult<[u8; 32]> {
+        decode_key(&self.public_key)
+    }
+}
+
+/// Outcome of comparing a server-provided key with the local pin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinCheck {
+    /// No key is pinned for the user yet.
+    Unpinned,
+    /// The key matches the pin.
+    Match,
+    /// The key differs from the pinned one.
+    Changed { pinned: [u8; 32] },
+}
+
+/// Pinned recipient keys, optionally persisted to a JSON file.
+pub struct KeyPinStore {
+    path: Option<PathBuf>,
+    pins: Mutex<HashMap<i64, KeyPin>>,
+}
+
+impl KeyPinStore {
+    /// Opens the pin store at `path`, loading existing pins if the file exists.
+    pub fn open(path: impl Into<PathBuf>) -> CloudResult<Self> {
+        let path = path.into();
+        let pins = match std::fs::read(&path) {
+            Ok(bytes) => {
+                let list: Vec<KeyPin> = serde_json::from_slice(&bytes)?;
+                list.into_iter().map(|pin| (pin.user_id, pin)).collect()
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                return Err(CloudError::Config(format!(
+                    "failed to read key pins {}: {e}",
+                    path.display()
+                )))
+            }
+        };
+        Ok(Self {
+            path: Some(path),
+            pins: Mutex::new(pins),
+        })
+    }
+
+    /// Creates a store that keeps pins in memory only.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            pins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the pin for a user.
+    pub fn get(&self, user_id: i64) -> Option<KeyPin> {
+        self.pins.lock().unwrap().get(&user_id).cloned()
+    }
+
+    /// Compares `key` with the user's pin.
+    pub fn check(&self, user_id: i64, key: &[u8; 32]) -> CloudResult<PinCheck> {
+        let Some(pin) = self.get(user_id) else {
+            return Ok(PinCheck::Unpinned);
+        };
+        let pinned = pin.key_bytes()?;
+        Ok(if &pinned == key {
+            PinCheck::Match
+        } else {
+            PinCheck::Changed { pinned }
+        })
+    }
+
+    /// Pins `key` for a user, replacing any previous pin. The new pin starts
+    /// unverified; a previously recorded log head is kept.
+    pub fn pin(&self, user_id: i64, key: &[u8; 32]) -> CloudResult<KeyPin> {
+        let mut pins = self.pins.lock().unwrap();
+        let (log_size, log_head) = pins
+            .get(&user_id)
+            .map(|p| (p.log_size, p.log_head.clone()))
+            .unwrap_or_default();
+        let pin = KeyPin {
+            user_id,
+            public_key: STANDARD.encode(key),
+            pinned_at: Utc::now(),
+            verified: false,
+            log_size,
+            log_head,
+        };
+        pins.insert(user_id, pin.clone());
+        self.persist(&pins)?;
+        Ok(pin)
+    }
+
+    /// Marks a pin as verified (or not) after comparing safety numbers.
+    pub fn set_verified(&self, user_id: i64, verified: bool) -> CloudResult<()> {
+        let mut pins = self.pins.lock().unwrap();
+        let pin = pins
+            .get_mut(&user_id)
+            .ok_or_else(|| CloudError::NotFound(format!("no pinned key for user {user_id}")))?;
+        pin.verified = verified;
+        self.persist(&pins)
+    }
+
+    /// Records the last verified key log head for a user.
+    pub fn record_log_head(&self, user_id: i64, size: u64, head: &str) -> CloudResult<()> {
+        let mut pins = self.pins.lock().unwrap();
+        if let Some(pin) = pins.get_mut(&user_id) {
+            pin.log_size = Some(size);
+            pin.log_head = Some(head.to_string());
+            self.persist(&pins)?;
+        }
+        Ok(())
+    }
+
+    /// Removes a user's pin. Returns true if one existed.
+    pub fn remove(&self, user_id: i64) -> CloudResult<bool> {
+        let mut pins = self.pins.lock().unwrap();
+        let existed = pins.remove(&user_id).is_some();
+        self.persist(&pins)?;
+        Ok(existed)
+    }
+
+    fn persist(&self, pins: &HashMap<i64, KeyPin>) -> CloudResult<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut list: Vec<&KeyPin> = pins.values().collect();
+        list.sort_by_key(|p| p.user_id);
+        let json = serde_json::to_vec_pretty(&list)?;
+        write_atomic(path, &json, "key pins")
+    }
+}
+
+// ── Safety numbers ───────────────────────────────────────────────
+
+/// Digits contributed by each party.
+const SAFETY_DIGITS_PER_PARTY: usize = 30;
+
+/// Returns the safety number for a pair of users: 60 digits in groups of
+/// five. Both parties compute the same number regardless of who is local.
+pub fn safety_number(
+    local_user_id: i64,
+    local_key: &[u8; 32],
+    remote_user_id: i64,
+    remote_key: &[u8; 32],
+) -> String {
+    let mut halves = [
+        (local_user_id, fingerprint_digits(local_user_id, local_key)),
+        (remote_user_id, fingerprint_digits(remote_user_id, remote_key)),
+    ];
+    halves.sort();
+    let digits = format!("{}{}", halves[0].1, halves[1].1);
+    digits
+        .as_bytes()
+        .chunks(5)
+        .map(|c| std::str::from_utf8(c).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 30 decimal digits derived from a user's ID and key.
+fn fingerprint_digits(user_id: i64, key: &[u8; 32]) -> String {
+    let mut hasher = Sha512::new();
+    hasher.update(b"privstack-safety-number-v1");
+    hasher.update(user_id.to_be_bytes());
+    hasher.update(key);
+    let digest = hasher.finalize();
+    digest
+        .chunks(5)
+        .take(SAFETY_DIGITS_PER_PARTY / 5)
+        .map(|chunk| {
+            let value = chunk.iter().fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+            format!("{:05}", value % 100_000)
+        })
+        .collect()
+}
+
+// ── Key log ──────────────────────────────────────────────────────
+
+/// One entry in a user's public-key history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyLogEntry {
+    /// Position in the log, starting at 0.
+    pub index: u64,
+    /// Base64 X25519 public key.
+    pub public_key: String,
+    /// When the server accepted the key (as returned by the server).
+    pub created_at: String,
+    /// Hash of the previous entry, or `KEY_LOG_GENESIS_HASH`.
+    pub prev_hash: String,
+}
+
+/// A user's key history with a head signed by the server's log key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedKeyLog {
+    pub user_id: i64,
+    pub entries: Vec<KeyLogEntry>,
+    /// Hash of the last entry.
+    pub head_hash: String,
+    /// Base64 Ed25519 signature over `signed_bytes()`.
+    pub signature: String,
+}
+
+impl SignedKeyLog {
+    /// Hash of a log entry.
+    pub fn entry_hash(user_id: i64, entry: &KeyLogEntry) -> String {
+        // A JSON array keeps field boundaries unambiguous.
+        let fields = serde_json::json!([
+            entry.prev_hash,
+            entry.index,
+            user_id,
+            entry.public_key,
+            entry.created_at,
+        ]);
+        hex::encode(Sha256::digest(fields.to_string().as_bytes()))
+    }
+
+    /// Bytes covered by the head signature.
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        serde_json::json!([
+            "privstack-key-log-v1",
+            self.user_id,
+            self.entries.len(),
+            self.head_hash,
+        ])
+        .to_string()
+        .into_bytes()
+    }
+
+    /// Checks the hash chain and head signature, returning the newest key.
+    pub fn verify(&self, log_key: &IdentityPublicKey) -> CloudResult<[u8; 32]> {
+        let mut prev = KEY_LOG_GENESIS_HASH.to_string();
+        for (position, entry) in self.entries.iter().enumerate() {
+            if entry.index != position as u64 {
+                return Err(CloudError::KeyLog(format!(
+                    "entry {position} has index {}",
+                    entry.index
+                )));
+            }
+            if entry.prev_hash != prev {
+                return Err(CloudError::KeyLog(format!(
+                    "entry {position} does not link to the previous entry"
+                )));
+            }
+            prev = Self::entry_hash(self.user_id, entry);
+        }
+        let Some(newest) = self.entries.last() else {
+            return Err(CloudError::KeyLog(format!(
+                "key log for user {} is empty",
+                self.user_id
+            )));
+        };
+        if prev != self.head_hash {
+            return Err(CloudError::KeyLog("head hash does not match entries".into()));
+        }
+
+        let signature = STANDARD
+            .decode(&self.signature)
+            .map_err(|e| CloudError::KeyLog(format!("invalid signature encoding: {e}")))?;
+        log_key
+            .verify(&self.signed_bytes(), &signature)
+            .map_err(|e| CloudError::KeyLog(format!("head signature: {e}")))?;
+
+        decode_key(&newest.public_key)
+    }
+
+    /// Checks that this log extends a previously verified log of `size`
+    /// entries ending in `head` (the server may only append).
+    pub fn verify_extends(&self, size: u64, head: &str) -> CloudResult<()> {
+        if size == 0 {
+            return Ok(());
+        }
+        let Some(entry) = usize::try_from(size - 1)
+            .ok()
+            .and_then(|i| self.entries.get(i))
+        else {
+            return Err(CloudError::KeyLog(format!(
+                "log shrank from {size} to {} entries",
+                self.entries.len()
+            )));
+        };
+        if Self::entry_hash(self.user_id, entry) != head {
+            return Err(CloudError::KeyLog(format!(
+                "log rewrote history before entry {size}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Decodes a base64 32-byte public key.
+pub(crate) fn decode_key(encoded: &str) -> CloudResult<[u8; 32]> {
+    let bytes = STANDARD
+        .decode(encoded)
+        .map_err(|e| CloudError::Api(format!("invalid public key encoding: {e}")))?;
+    bytes.as_slice().try_into().map_err(|_| {
+        CloudError::Api(format!(
+            "invalid public key length: expected 32, got {}",
+            bytes.len()
+        ))
+    })
+}
@@ -5,17 +5,21 @@
 //! - STS credential management with auto-refresh
 //! - API client for the Express control plane
 //! - Per-entity sharing via envelope encryption
-//! - Blob sync for file attachments
+//! - Recipient key pinning, safety numbers and key-log verification
+//! - Blob sync for file attachments (chunked, resumable transfers)
 //! - Compaction for storage efficiency
 
 pub mod api_client;
+mod atomic_file;
 pub mod blob_sync;
+pub mod blob_transfer;
 pub mod compaction;
 pub mod config;
 pub mod credential_manager;
 pub mod dek_registry;
 pub mod envelope;
 pub mod error;
+pub mod key_transparency;
 pub mod outbox;
 pub mod s3_transport;
 pub mod sharing;
@@ -7,6 +7,7 @@ use crate::error::{CloudError, CloudResult};
 use crate::types::StsCredentials;
 use aws_sdk_s3::Client as S3Client;
 use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
 use aws_smithy_http_client::tls;
 use tracing::debug;
 
@@ -132,6 +133,186 @@ impl S3Transport {
         Ok(bytes)
     }
 
+    /// Downloads the inclusive byte range `start..=end` of an object.
+    pub async fn download_range(
+        &self,
+        creds: &StsCredentials,
+        key: &str,
+        start: u64,
+        end: u64,
+    ) -> CloudResult<Vec<u8>> {
+        if creds.is_expired() {
+            return Err(CloudError::CredentialExpired);
+        }
+
+        let client = self.build_client(creds).await?;
+
+        let resp = client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .range(format!("bytes={start}-{end}"))
+            .send()
+            .await
+            .map_err(|e| {
+                CloudError::S3(format!("ranged download failed for {key} [{start}-{end}]: {e:?}"))
+            })?;
+
+        let body = resp
+            .body
+            .collect()
+            .await
+            .map_err(|e| CloudError::S3(format!("failed to read body for {key}: {e}")))?;
+
+        Ok(body.into_bytes().to_vec())
+    }
+
+    /// Starts a multipart upload and returns its upload ID.
+    pub async fn create_multipart_upload(
+        &self,
+        creds: &StsCredentials,
+        key: &str,
+    ) -> CloudResult<String> {
+        if creds.is_expired() {
+            return Err(CloudError::CredentialExpired);
+        }
+
+        let client = self.build_client(creds).await?;
+
+        let resp = client
+            .create_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| CloudError::S3(format!("create multipart upload failed for {key}: {e}")))?;
+
+        resp.upload_id()
+            .map(|id| id.to_string())
+            .ok_or_else(|| CloudError::S3(format!("no upload id returned for {key}")))
+    }
+
+    /// Uploads one part of a multipart upload and returns its ETag.
+    /// Part numbers start at 1.
+    pub async fn upload_part(
+        &self,
+        creds: &StsCredentials,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        data: Vec<u8>,
+    ) -> CloudResult<String> {
+        if creds.is_expired() {
+            return Err(CloudError::CredentialExpired);
+        }
+
+        let client = self.build_client(creds).await?;
+        let size = data.len();
+
+        let resp = client
+            .upload_part()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(data))
+            .send()
+            .await
+            .map_err(|e| {
+                CloudError::S3(format!("upload of part {part_number} failed for {key}: {e}"))
+            })?;
+
+        debug!("uploaded part {part_number} ({size} bytes) of s3://{}/{key}", self.bucket);
+        resp.e_tag()
+            .map(|etag| etag.to_string())
+            .ok_or_else(|| CloudError::S3(format!("no ETag for part {part_number} of {key}")))
+    }
+
+    /// Completes a multipart upload from `(part_number, etag)` pairs.
+    pub async fn complete_multipart_upload(
+        &self,
+        creds: &StsCredentials,
+        key: &str,
+        upload_id: &str,
+        parts: &[(i32, String)],
+    ) -> CloudResult<()> {
+        if creds.is_expired() {
+            return Err(CloudError::CredentialExpired);
+        }
+
+        let client = self.build_client(creds).await?;
+        let completed = CompletedMultipartUpload::builder()
+            .set_parts(Some(
+                parts
+                    .iter()
+                    .map(|(number, etag)| {
+                        CompletedPart::builder()
+                            .part_number(*number)
+                            .e_tag(etag)
+                            .build()
+                    })
+                    .collect(),
+            ))
+            .build();
+
+        client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .multipart_upload(completed)
+            .send()
+            .await
+            .map_err(|e| {
+                CloudError::S3(format!("complete multipart upload failed for {key}: {e}"))
+            })?;
+
+        debug!("completed {}-part upload to s3://{}/{key}", parts.len(), self.bucket);
+        Ok(())
+    }
+
+    /// Aborts a multipart upload, discarding its uploaded parts.
+    pub async fn abort_multipart_upload(
+        &self,
+        creds: &StsCredentials,
+        key: &str,
+        upload_id: &str,
+    ) -> CloudResult<()> {
+        if creds.is_expired() {
+            return Err(CloudError::CredentialExpired);
+        }
+
+        let client = self.build_client(creds).await?;
+
+        client
+            .abort_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .send()
+            .await
+            .map_err(|e| CloudError::S3(format!("abort multipart upload failed for {key}: {e}")))?;
+        Ok(())
+    }
+
+    /// Deletes an object. Deleting a missing key succeeds.
+    pub async fn delete(&self, creds: &StsCredentials, key: &str) -> CloudResult<()> {
+        if creds.is_expired() {
+            return Err(CloudError::CredentialExpired);
+        }
+
+        let client = self.build_client(creds).await?;
+
+        client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| CloudError::S3(format!("delete failed for {key}: {e}")))?;
+        Ok(())
+    }
+
     /// Checks if an object exists in S3 (HEAD request).
     pub async fn exists(&self, creds: &StsCredentials, key: &str) -> CloudResult<bool> {
         if creds.is_expired() {
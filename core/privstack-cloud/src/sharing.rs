@@ -4,11 +4,14 @@
 //! (share CRUD, limits) and the envelope manager (DEK encryption).
 
 use crate::api_client::CloudApiClient;
+use crate::dek_registry::DekRegistry;
 use crate::envelope::EnvelopeManager;
 use crate::error::CloudResult;
 use crate::types::*;
+use privstack_crypto::DerivedKey;
+use std::collections::HashSet;
 use std::sync::Arc;
-use tracing::info;
+use tracing::{info, warn};
 
 /// Orchestrates entity sharing workflows.
 pub struct ShareManager {
@@ -38,10 +41,11 @@ impl ShareManager {
         envelope_mgr: &EnvelopeManager,
         entity_id: &str,
         entity_dek: &[u8],
+        dek_generation: u32,
         recipient_user_id: i64,
     ) -> CloudResult<()> {
         envelope_mgr
-            .create_and_store_envelope(entity_id, entity_dek, recipient_user_id)
+            .create_and_store_envelope(entity_id, entity_dek, dek_generation, recipient_user_id)
             .await
     }
 
@@ -52,15 +56,168 @@ impl ShareManager {
         Ok(())
     }
 
-    /// Revokes a share. The caller must then rotate the entity DEK.
+    /// Revokes a share and rotates the entity DEK.
+    ///
+    /// The former recipient may still hold the DEK it opened, so the entity
+    /// moves to a new DEK generation (see [`Self::rotate_entity_dek`]).
+    /// Returns the new generation, or `None` if the share was revoked but
+    /// the rotation failed; it stays pending until
+    /// [`Self::retry_pending_rotations`] completes it.
     pub async fn revoke_share(
         &self,
+        envelope_mgr: &EnvelopeManager,
+        dek_registry: &DekRegistry,
         entity_id: &str,
         recipient_email: &str,
-    ) -> CloudResult<()> {
+    ) -> CloudResult<Option<u32>> {
         self.api.revoke_share(entity_id, recipient_email).await?;
         info!("revoked share for entity {entity_id} from {recipient_email}");
-        Ok(())
+        match self
+            .rotate_entity_dek(envelope_mgr, dek_registry, entity_id)
+            .await
+        {
+            Ok(generation) => Ok(Some(generation)),
+            Err(e) => {
+                warn!("DEK rotation for entity {entity_id} failed, will retry: {e}");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Generates a new DEK generation for an entity and seals it to every
+    /// accepted recipient and to the owner's own account (for the owner's
+    /// other devices). Only once every envelope is stored does the new key
+    /// become current: new batches and snapshots use it, and older
+    /// generations stay registered so existing batches remain readable.
+    ///
+    /// Sealing continues past individual failures and the first error is
+    /// returned afterwards. The entity then keeps its current key and is
+    /// marked for [`Self::retry_pending_rotations`], which seals a fresh
+    /// generation to everyone.
+    pub async fn rotate_entity_dek(
+        &self,
+        envelope_mgr: &EnvelopeManager,
+        dek_registry: &DekRegistry,
+        entity_id: &str,
+    ) -> CloudResult<u32> {
+        let result = self
+            .seal_new_generation(envelope_mgr, dek_registry, entity_id)
+            .await;
+        match result {
+            Ok((generation, dek)) => {
+                dek_registry.commit_rotation(entity_id, generation, dek).await;
+                info!("rotated DEK for entity {entity_id} to generation {generation}");
+                Ok(generation)
+            }
+            Err(e) => {
+                dek_registry.mark_rotation_pending(entity_id).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Retries the rotations that failed earlier (see
+    /// [`Self::rotate_entity_dek`]). Returns how many completed; failures
+    /// are logged and stay pending.
+    pub async fn retry_pending_rotations(
+        &self,
+        envelope_mgr: &EnvelopeManager,
+        dek_registry: &DekRegistry,
+    ) -> usize {
+        let mut completed = 0;
+        for entity_id in dek_registry.pending_rotations().await {
+            match self
+                .rotate_entity_dek(envelope_mgr, dek_registry, &entity_id)
+                .await
+            {
+                Ok(_) => completed += 1,
+                Err(e) => warn!("DEK rotation for entity {entity_id} still failing: {e}"),
+            }
+        }
+        completed
+    }
+
+    /// Seals a new, not yet registered DEK generation to every recipient of
+    /// the entity. Fails if any envelope could not be stored.
+    async fn seal_new_generation(
+        &self,
+        envelope_mgr: &EnvelopeManager,
+        dek_registry: &DekRegistry,
+        entity_id: &str,
+    ) -> CloudResult<(u32, DerivedKey)> {
+        let (generation, dek) = dek_registry.new_generation(entity_id).await;
+        let shares = self.api.get_entity_shares(entity_id).await?;
+
+        let mut recipients: Vec<i64> = shares
+            .iter()
+            .filter(|s| s.status == ShareStatus::Accepted)
+            .filter_map(|s| {
+                if s.recipient_user_id.is_none() {
+                    warn!(
+                        "accepted share {} for entity {entity_id} has no recipient user id",
+                        s.share_id
+                    );
+                }
+                s.recipient_user_id
+            })
+            .collect();
+        if let Some(owner) = self.api.user_id().await {
+            recipients.push(owner);
+        }
+        recipients.sort_unstable();
+        recipients.dedup();
+
+        let mut first_err = None;
+        for user_id in recipients {
+            if let Err(e) = envelope_mgr
+                .create_and_store_envelope(entity_id, dek.as_bytes(), generation, user_id)
+                .await
+            {
+                warn!("failed to re-seal DEK for entity {entity_id} -> user {user_id}: {e}");
+                first_err.get_or_insert(e);
+            }
+        }
+        match first_err {
+            Some(e) => Err(e),
+            None => Ok((generation, dek)),
+        }
+    }
+
+    /// Brings the registry in line with the entities shared with us: opens
+    /// the current share key of each one (picking up rotations) and drops
+    /// the keys of shares that were revoked. Returns the number of entities
+    /// whose key generation changed.
+    ///
+    /// Call after accepting a share and when sync starts; a failure for one
+    /// entity is logged and does not stop the others.
+    pub async fn refresh_shared_keys(
+        &self,
+        envelope_mgr: &EnvelopeManager,
+        dek_registry: &DekRegistry,
+    ) -> CloudResult<usize> {
+        let shared = self.api.get_shared_with_me().await?;
+        let mut updated = 0;
+        for entity in &shared {
+            let entity_id = &entity.entity_id;
+            let (generation, dek) = match envelope_mgr.open_entity_key(entity_id).await {
+                Ok(opened) => opened,
+                Err(e) => {
+                    warn!("failed to open share key for entity {entity_id}: {e}");
+                    continue;
+                }
+            };
+            let known = dek_registry.current_generation(entity_id).await;
+            dek_registry.insert_shared(entity_id.clone(), generation, dek).await;
+            if known != Some(generation) {
+                updated += 1;
+            }
+        }
+
+        let still_shared: HashSet<String> = shared.into_iter().map(|s| s.entity_id).collect();
+        for entity_id in dek_registry.prune_shared(&still_shared).await {
+            info!("dropped DEKs of entity {entity_id}: no longer shared with us");
+        }
+        Ok(updated)
     }
 
     /// Gets all shares for an entity owned by the current user.
@@ -12,19 +12,20 @@ use crate::api_client::CloudApiClient;
 use crate::compaction::batch_s3_key;
 use crate::credential_manager::CredentialManager;
 use crate::dek_registry::DekRegistry;
+use crate::envelope::EnvelopeManager;
 use crate::error::{CloudError, CloudResult};
 use crate::outbox::Outbox;
 use crate::s3_transport::S3Transport;
 use crate::types::*;
 
 use chrono::{DateTime, Utc};
-use privstack_crypto::{decrypt, encrypt, EncryptedData};
-use privstack_storage::EntityStore;
+use privstack_crypto::{decrypt, encrypt, verify_event, DerivedKey, SignatureMode};
+use privstack_storage::{EntityStore, EventStore};
 use privstack_types::Event;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, Mutex, RwLock};
 use tracing::{debug, error, info, warn};
 
 /// Cloud sync engine — main orchestration loop.
@@ -50,6 +51,10 @@ pub struct CloudSyncEngine {
     entity_store: Arc<EntityStore>,
     /// Server-provided throttling configuration (queried on startup).
     rate_limits: RateLimitConfig,
+    /// Registered author keys and signature mode for inbound events.
+    author_keys: Option<Arc<EventStore>>,
+    /// Opens entity key envelopes for DEK generations not yet registered.
+    envelopes: Option<Arc<Mutex<EnvelopeManager>>>,
 }
 
 /// Handle for sending commands to the sync engine.
@@ -169,12 +174,28 @@ pub fn create_cloud_sync_engine(
         last_sync_at,
         entity_store,
         rate_limits: RateLimitConfig::default(),
+        author_keys: None,
+        envelopes: None,
     };
 
     (handle, inbound_tx, engine)
 }
 
 impl CloudSyncEngine {
+    /// Checks inbound events against the author keys registered in `store` and
+    /// its signature mode. Without a store, signatures are still verified but
+    /// unsigned legacy events are accepted.
+    pub fn set_author_key_store(&mut self, store: Arc<EventStore>) {
+        self.author_keys = Some(store);
+    }
+
+    /// Lets the engine fetch the key envelope sealed to this account when a
+    /// batch was written with a DEK generation it has not seen, e.g. after
+    /// another of the owner's devices rotated the key to revoke a share.
+    pub fn set_envelope_manager(&mut self, envelopes: Arc<Mutex<EnvelopeManager>>) {
+        self.envelopes = Some(envelopes);
+    }
+
     /// Runs the sync engine event loop.
     pub async fn run(&mut self) {
         info!(
@@ -348,8 +369,8 @@ impl CloudSyncEngine {
             } else {
                 tokio::time::sleep(inter_delay).await;
             }
-            let dek = match self.dek_registry.get(&entity_id).await {
-                Ok(dek) => dek,
+            let (dek_generation, dek) = match self.dek_registry.current(&entity_id).await {
+                Ok(current) => current,
                 Err(e) => {
                     warn!("skipping flush for entity {entity_id} (no DEK): {e}");
                     for ev in entity_events {
@@ -367,7 +388,10 @@ impl CloudSyncEngine {
             let serialized = serde_json::to_vec(&entity_events)?;
             let encrypted = encrypt(&dek, &serialized)
                 .map_err(|e| CloudError::Envelope(format!("batch encryption failed: {e}")))?;
-            let encrypted_bytes = serde_json::to_vec(&encrypted)?;
+            let encrypted_bytes = serde_json::to_vec(&EncryptedPayload {
+                dek_generation,
+                data: encrypted,
+            })?;
 
             let s3_key = batch_s3_key(
                 self.user_id,
@@ -456,13 +480,10 @@ impl CloudSyncEngine {
         }
 
         for entity in &pending.pending {
-            let dek = match self.dek_registry.get(&entity.entity_id).await {
-                Ok(dek) => dek,
-                Err(e) => {
-                    warn!("skipping entity {} (no DEK available): {e}", entity.entity_id);
-                    continue;
-                }
-            };
+            if let Err(e) = self.dek_registry.get(&entity.entity_id).await {
+                warn!("skipping entity {} (no DEK available): {e}", entity.entity_id);
+                continue;
+            }
 
             // Fetch batch metadata for this entity from the server
             let batches = self
@@ -478,29 +499,49 @@ impl CloudSyncEngine {
                 entity.latest_cursor
             );
 
+            // Only advance past batches that were applied (or can never be):
+            // a batch we cannot decrypt yet is retried on the next poll.
+            let mut cursor = entity.device_cursor;
+            let mut blocked = false;
             for batch in &batches {
                 let creds = self.cred_manager.get_credentials().await?;
                 let data = self.transport.download(&creds, &batch.s3_key).await?;
 
-                // Deserialize encrypted envelope and decrypt with entity DEK
-                let encrypted: EncryptedData = match serde_json::from_slice(&data) {
+                // Deserialize encrypted envelope and decrypt with the entity
+                // DEK generation it was written with
+                let encrypted: EncryptedPayload = match serde_json::from_slice(&data) {
                     Ok(enc) => enc,
                     Err(e) => {
                         warn!("failed to deserialize encrypted batch {}: {e}", batch.s3_key);
+                        cursor = batch.cursor_end;
                         continue;
                     }
                 };
 
-                let plaintext = match decrypt(&dek, &encrypted) {
+                let dek = match self
+                    .batch_key(&entity.entity_id, encrypted.dek_generation)
+                    .await
+                {
+                    Ok(dek) => dek,
+                    Err(e) => {
+                        warn!("cannot decrypt batch {} yet: {e}", batch.s3_key);
+                        blocked = true;
+                        break;
+                    }
+                };
+
+                let plaintext = match decrypt(&dek, &encrypted.data) {
                     Ok(pt) => pt,
                     Err(e) => {
                         warn!("failed to decrypt batch {}: {e}", batch.s3_key);
-                        continue;
+                        blocked = true;
+                        break;
                     }
                 };
 
                 match serde_json::from_slice::<Vec<Event>>(&plaintext) {
                     Ok(events) => {
+                        let events = verify_inbound_events(events, self.author_keys.as_deref());
                         for event in events {
                             // Defense-in-depth: skip events originating from this device
                             if event.peer_id.to_string() == self.device_id {
@@ -519,13 +560,20 @@ impl CloudSyncEngine {
                         );
                     }
                 }
+                cursor = batch.cursor_end;
+            }
+            if !blocked {
+                cursor = cursor.max(entity.latest_cursor);
+            }
+            if cursor <= entity.device_cursor {
+                continue;
             }
 
             // Advance download cursor locally and acknowledge to server
-            self.cursors.insert(entity.entity_id.clone(), entity.latest_cursor);
+            self.cursors.insert(entity.entity_id.clone(), cursor);
             if let Err(e) = self
                 .entity_store
-                .save_cloud_cursor(&entity.entity_id, entity.latest_cursor)
+                .save_cloud_cursor(&entity.entity_id, cursor)
             {
                 warn!(
                     "failed to persist download cursor for {}: {e}",
@@ -539,7 +587,7 @@ impl CloudSyncEngine {
                     &self.workspace_id,
                     &self.device_id,
                     &entity.entity_id,
-                    entity.latest_cursor,
+                    cursor,
                 )
                 .await
             {
@@ -552,4 +600,51 @@ impl CloudSyncEngine {
 
         Ok(())
     }
+
+    /// The DEK a batch was written with, fetching the envelope sealed to this
+    /// account when the generation is not registered yet (see
+    /// [`DekRegistry::fetch_generation`]).
+    async fn batch_key(&self, entity_id: &str, generation: u32) -> CloudResult<DerivedKey> {
+        let known = self.dek_registry.get_generation(entity_id, generation).await;
+        match (&self.envelopes, known) {
+            (Some(envelopes), Err(_)) => {
+                let envelopes = envelopes.lock().await;
+                self.dek_registry
+                    .fetch_generation(&envelopes, entity_id, generation)
+                    .await
+            }
+            (_, known) => known,
+        }
+    }
+}
+
+/// Returns the events whose author signature verifies, dropping the rest.
+///
+/// With a store, signing keys are checked against the key registered for the
+/// author's `peer_id` and the store's `SignatureMode` applies; without one,
+/// only the signature itself is checked and unsigned events pass.
+pub fn verify_inbound_events(
+    events: Vec<Event>,
+    author_keys: Option<&EventStore>,
+) -> Vec<Event> {
+    events
+        .into_iter()
+        .filter(|event| {
+            let result = match author_keys {
+                Some(store) => store.verify_author(event).map_err(|e| e.to_string()),
+                None => verify_event(event, SignatureMode::LegacyMigration)
+                    .map_err(|e| e.to_string()),
+            };
+            match result {
+                Ok(_) => true,
+                Err(e) => {
+                    warn!(
+                        "dropping event {} for entity {} (author {}): {e}",
+                        event.id, event.entity_id, event.peer_id
+                    );
+                    false
+                }
+            }
+        })
+        .collect()
 }
@@ -1,6 +1,7 @@
 //! Shared types for cloud sync operations.
 
 use chrono::{DateTime, Utc};
+use privstack_crypto::{EncryptedData, SealedEnvelope};
 use serde::{Deserialize, Serialize};
 
 /// STS temporary credentials for S3 access.
@@ -78,6 +79,23 @@ pub struct BatchMeta {
     pub is_snapshot: bool,
 }
 
+/// Encrypted batch or snapshot body as stored on S3.
+///
+/// Serializes as the plain `EncryptedData` JSON plus a `dek_generation`
+/// field once the entity DEK has been rotated, so objects written before
+/// rotation existed still parse (as generation 0).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EncryptedPayload {
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub dek_generation: u32,
+    #[serde(flatten)]
+    pub data: EncryptedData,
+}
+
+fn is_zero(v: &u32) -> bool {
+    *v == 0
+}
+
 /// Accepts a JSON boolean or a `0`/`1` integer.
 fn deserialize_bool_from_int_or_bool<'de, D>(deserializer: D) -> Result<bool, D::Error>
 where
@@ -106,12 +124,25 @@ pub struct ShareInfo {
     pub entity_type: String,
     pub entity_name: Option<String>,
     pub recipient_email: String,
+    /// Set once the recipient has accepted and has an account.
+    #[serde(default)]
+    pub recipient_user_id: Option<i64>,
     pub permission: SharePermission,
     pub status: ShareStatus,
     pub created_at: DateTime<Utc>,
     pub accepted_at: Option<DateTime<Utc>>,
 }
 
+/// A sealed entity DEK stored for a share recipient.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShareKey {
+    #[serde(flatten)]
+    pub envelope: SealedEnvelope,
+    /// Generation of the sealed DEK (see `DekRegistry`).
+    #[serde(default)]
+    pub dek_generation: u32,
+}
+
 /// Storage quota info.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct QuotaInfo {
@@ -13,6 +13,7 @@ async fn setup(server: &MockServer) -> CloudApiClient {
         s3_endpoint_override: None,
         credential_refresh_margin_secs: 60,
         poll_interval_secs: 5,
+        key_log_public_key: None,
     };
     CloudApiClient::new(config)
 }
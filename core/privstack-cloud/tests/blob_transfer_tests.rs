@@ -0,0 +1,180 @@
+use privstack_cloud::blob_transfer::{
+    chunk_count, chunk_hash, decrypt_chunk, encrypt_chunk, is_object_of, manifest_s3_key,
+    object_s3_key, BlobManifest, CompletedChunk, TransferDirection, TransferState,
+    TransferStateStore, BLOB_CHUNK_SIZE,
+};
+use privstack_crypto::{generate_random_key, NONCE_SIZE, TAG_SIZE};
+use std::path::PathBuf;
+
+const OVERHEAD: u64 = (NONCE_SIZE + TAG_SIZE) as u64;
+const BLOB_KEY: &str = "users/1/workspaces/ws/blobs/b-1.enc";
+
+fn manifest(chunk_size: u64, total_size: u64, chunk_hashes: Vec<String>) -> BlobManifest {
+    BlobManifest::new(
+        BLOB_KEY.into(),
+        object_s3_key(BLOB_KEY, "1"),
+        chunk_size,
+        total_size,
+        "c".into(),
+        chunk_hashes,
+    )
+}
+
+fn state(blob_id: &str, direction: TransferDirection) -> TransferState {
+    TransferState {
+        blob_id: blob_id.into(),
+        direction,
+        s3_key: format!("users/1/workspaces/ws/blobs/{blob_id}.enc"),
+        local_path: PathBuf::from("/tmp/file.bin"),
+        total_size: 25,
+        chunk_size: 10,
+        upload_id: None,
+        completed: Vec::new(),
+    }
+}
+
+fn completed(index: u64) -> CompletedChunk {
+    CompletedChunk {
+        index,
+        hash: format!("h{index}"),
+        plain_hash: format!("p{index}"),
+        etag: Some(format!("etag-{index}")),
+    }
+}
+
+// ── Chunks ──────────────────────────────────────────────────────
+
+#[test]
+fn chunk_roundtrip_has_fixed_overhead() {
+    let dek = generate_random_key();
+    let encrypted = encrypt_chunk(&dek, b"chunk data").unwrap();
+    assert_eq!(encrypted.len() as u64, 10 + OVERHEAD);
+    assert_eq!(decrypt_chunk(&dek, &encrypted).unwrap(), b"chunk data");
+}
+
+#[test]
+fn chunk_rejects_wrong_key_and_truncation() {
+    let encrypted = encrypt_chunk(&generate_random_key(), b"chunk data").unwrap();
+    assert!(decrypt_chunk(&generate_random_key(), &encrypted).is_err());
+    assert!(decrypt_chunk(&generate_random_key(), &encrypted[..NONCE_SIZE]).is_err());
+}
+
+#[test]
+fn chunk_count_rounds_up_and_keeps_empty_blob() {
+    assert_eq!(chunk_count(0, 10), 1);
+    assert_eq!(chunk_count(10, 10), 1);
+    assert_eq!(chunk_count(11, 10), 2);
+    assert_eq!(chunk_count(3 * BLOB_CHUNK_SIZE + 1, BLOB_CHUNK_SIZE), 4);
+}
+
+#[test]
+fn manifest_key_sits_next_to_blob() {
+    assert_eq!(manifest_s3_key("a/b.enc"), "a/b.enc.manifest");
+}
+
+#[test]
+fn object_versions_belong_to_their_blob() {
+    let key = object_s3_key("a/b.enc", "7f3a");
+    assert_eq!(key, "a/b.enc.v7f3a");
+    assert!(is_object_of("a/b.enc", &key));
+    assert!(!is_object_of("a/b.enc", "a/b.enc"));
+    assert!(!is_object_of("a/b.enc", "a/b.enc.v"));
+    assert!(!is_object_of("a/b.enc", "a/b.enc.manifest"));
+    assert!(!is_object_of("a/b.enc", &object_s3_key("a/c.enc", "7f3a")));
+    assert!(!is_object_of("a/b", "a/b.enc.v1"));
+}
+
+// ── Manifest ────────────────────────────────────────────────────
+
+#[test]
+fn manifest_ranges_cover_encrypted_object() {
+    let manifest = manifest(10, 25, vec!["a".into(); 3]);
+    manifest.validate().unwrap();
+
+    assert_eq!(manifest.plain_len(0), 10);
+    assert_eq!(manifest.plain_len(2), 5);
+    assert_eq!(manifest.encrypted_range(0), (0, 10 + OVERHEAD - 1));
+    assert_eq!(manifest.encrypted_range(1), (10 + OVERHEAD, 2 * (10 + OVERHEAD) - 1));
+    let (_, last) = manifest.encrypted_range(2);
+    assert_eq!(last + 1, manifest.encrypted_size());
+    assert_eq!(manifest.encrypted_size(), 25 + 3 * OVERHEAD);
+}
+
+#[test]
+fn manifest_validation_rejects_bad_layout() {
+    assert!(manifest(10, 25, vec!["a".into(); 2]).validate().is_err());
+    assert!(manifest(0, 25, vec![]).validate().is_err());
+
+    let mut future = manifest(10, 5, vec!["a".into()]);
+    future.version += 1;
+    assert!(future.validate().is_err());
+}
+
+#[test]
+fn manifest_seal_and_open() {
+    let dek = generate_random_key();
+    let encrypted = encrypt_chunk(&dek, b"hello").unwrap();
+    let manifest = manifest(10, 5, vec![chunk_hash(&encrypted)]);
+
+    let sealed = manifest.seal(&dek).unwrap();
+    assert_eq!(BlobManifest::open(&dek, &sealed, BLOB_KEY).unwrap(), manifest);
+    assert!(BlobManifest::open(&generate_random_key(), &sealed, BLOB_KEY).is_err());
+}
+
+#[test]
+fn manifest_open_rejects_another_blobs_manifest() {
+    let dek = generate_random_key();
+    let sealed = manifest(10, 5, vec!["a".into()]).seal(&dek).unwrap();
+    assert!(BlobManifest::open(&dek, &sealed, "users/1/workspaces/ws/blobs/b-2.enc").is_err());
+
+    // A manifest naming this blob but pointing at another blob's object.
+    let mut foreign = manifest(10, 5, vec!["a".into()]);
+    foreign.object_key = object_s3_key("users/1/workspaces/ws/blobs/b-2.enc", "1");
+    let sealed = foreign.seal(&dek).unwrap();
+    assert!(BlobManifest::open(&dek, &sealed, BLOB_KEY).is_err());
+}
+
+// ── Transfer state ──────────────────────────────────────────────
+
+#[test]
+fn transfer_state_counts_completed_bytes() {
+    let mut s = state("b-1", TransferDirection::Upload);
+    s.completed = vec![completed(0), completed(2)];
+    assert_eq!(s.bytes_done(), 15);
+    assert!(s.completed_chunk(2).is_some());
+    assert!(s.completed_chunk(1).is_none());
+}
+
+#[test]
+fn transfer_store_keys_by_blob_and_direction() {
+    let store = TransferStateStore::in_memory();
+    store.put(&state("b-1", TransferDirection::Upload)).unwrap();
+    store.put(&state("b-1", TransferDirection::Download)).unwrap();
+    assert_eq!(store.list().len(), 2);
+
+    let removed = store.remove("b-1", TransferDirection::Upload).unwrap();
+    assert_eq!(removed.unwrap().direction, TransferDirection::Upload);
+    assert!(store.get("b-1", TransferDirection::Upload).is_none());
+    assert!(store.get("b-1", TransferDirection::Download).is_some());
+    assert!(store.remove("b-1", TransferDirection::Upload).unwrap().is_none());
+}
+
+#[test]
+fn transfer_store_persists_to_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("data.blob_transfers");
+    let mut saved = state("b-1", TransferDirection::Upload);
+    saved.upload_id = Some("upload-1".into());
+    saved.completed = vec![completed(0)];
+    {
+        let store = TransferStateStore::open(&file).unwrap();
+        store.put(&saved).unwrap();
+    }
+
+    let reopened = TransferStateStore::open(&file).unwrap();
+    assert_eq!(reopened.get("b-1", TransferDirection::Upload).unwrap(), saved);
+
+    reopened.remove("b-1", TransferDirection::Upload).unwrap();
+    let again = TransferStateStore::open(&file).unwrap();
+    assert!(again.list().is_empty());
+}
@@ -346,3 +346,48 @@ fn full_batch_pipeline_wrong_dek_fails_cleanly() {
 
     assert!(result.is_err(), "wrong DEK must fail, not silently produce garbage");
 }
+
+// ── Event Author Signatures ──
+
+#[test]
+fn inbound_events_with_bad_signatures_are_dropped() {
+    use privstack_cloud::sync_engine::verify_inbound_events;
+    use privstack_crypto::IdentityKeyPair;
+    use privstack_types::{EntityId, Event, PeerId};
+
+    let key = IdentityKeyPair::generate();
+    let mut good = Event::entity_created(EntityId::new(), PeerId::new(), "note", "{}");
+    key.sign_event(&mut good);
+    let mut tampered = good.clone();
+    tampered.id = privstack_types::EventId::new();
+    let legacy = Event::entity_created(EntityId::new(), PeerId::new(), "note", "{}");
+
+    let kept = verify_inbound_events(vec![good.clone(), tampered, legacy.clone()], None);
+    let ids: Vec<_> = kept.iter().map(|e| e.id).collect();
+    assert_eq!(ids, vec![good.id, legacy.id]);
+}
+
+#[test]
+fn inbound_events_checked_against_registered_author_keys() {
+    use privstack_cloud::sync_engine::verify_inbound_events;
+    use privstack_crypto::{IdentityKeyPair, SignatureMode};
+    use privstack_storage::EventStore;
+    use privstack_types::{EntityId, Event, PeerId};
+
+    let store = EventStore::open_in_memory()
+        .unwrap()
+        .with_signature_mode(SignatureMode::Required);
+    let author = PeerId::new();
+    let key = IdentityKeyPair::generate();
+    store.register_author_key(&author, &key.public_key()).unwrap();
+
+    let mut genuine = Event::entity_created(EntityId::new(), author, "note", "{}");
+    key.sign_event(&mut genuine);
+    let mut forged = Event::entity_created(EntityId::new(), author, "note", "{}");
+    IdentityKeyPair::generate().sign_event(&mut forged);
+    let unsigned = Event::entity_created(EntityId::new(), author, "note", "{}");
+
+    let kept = verify_inbound_events(vec![genuine.clone(), forged, unsigned], Some(&store));
+    assert_eq!(kept.len(), 1);
+    assert_eq!(kept[0].id, genuine.id);
+}
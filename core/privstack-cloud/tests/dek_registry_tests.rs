@@ -3,9 +3,10 @@
 //! Validates thread-safety, missing entity behavior, key overwrite semantics,
 //! and concurrent read/write correctness under contention.
 
+use base64::{engine::general_purpose::STANDARD, Engine};
 use privstack_cloud::dek_registry::DekRegistry;
 use privstack_cloud::error::CloudError;
-use privstack_crypto::generate_random_key;
+use privstack_crypto::{generate_random_key, DerivedKey};
 use std::sync::Arc;
 
 #[tokio::test]
@@ -214,3 +215,209 @@ async fn default_equals_new() {
     assert!(a.is_empty().await);
     assert!(b.is_empty().await);
 }
+
+// ── Generations ──
+
+/// Creates and commits a new generation in one step.
+async fn rotate(registry: &DekRegistry, entity_id: &str) -> (u32, DerivedKey) {
+    let (generation, dek) = registry.new_generation(entity_id).await;
+    registry.commit_rotation(entity_id, generation, dek.clone()).await;
+    (generation, dek)
+}
+
+#[tokio::test]
+async fn rotate_keeps_older_generations() {
+    let registry = DekRegistry::new();
+    let original = generate_random_key();
+    let original_bytes = *original.as_bytes();
+    registry.insert("entity-1".to_string(), original).await;
+
+    let (generation, rotated) = rotate(&registry, "entity-1").await;
+    assert_ne!(generation, 0);
+    assert_ne!(*rotated.as_bytes(), original_bytes);
+
+    let (current_gen, current) = registry.current("entity-1").await.unwrap();
+    assert_eq!(current_gen, generation);
+    assert_eq!(current.as_bytes(), rotated.as_bytes());
+    assert_eq!(registry.get("entity-1").await.unwrap().as_bytes(), rotated.as_bytes());
+    assert_eq!(
+        *registry.get_generation("entity-1", 0).await.unwrap().as_bytes(),
+        original_bytes
+    );
+}
+
+#[tokio::test]
+async fn rotate_from_default_dek_keeps_default_as_generation_zero() {
+    let registry = DekRegistry::new();
+    let default = generate_random_key();
+    let default_bytes = *default.as_bytes();
+    registry.set_default(default).await;
+
+    assert_eq!(registry.current("entity-1").await.unwrap().0, 0);
+    let (generation, _) = rotate(&registry, "entity-1").await;
+    assert_eq!(registry.current_generation("entity-1").await, Some(generation));
+    assert_eq!(
+        *registry.get_generation("entity-1", 0).await.unwrap().as_bytes(),
+        default_bytes
+    );
+    // Other entities still use the default.
+    assert_eq!(*registry.get("entity-2").await.unwrap().as_bytes(), default_bytes);
+}
+
+#[tokio::test]
+async fn new_generation_is_unregistered_until_committed() {
+    let registry = DekRegistry::new();
+    registry.insert("entity-1".to_string(), generate_random_key()).await;
+
+    let (generation, _) = registry.new_generation("entity-1").await;
+    assert_eq!(registry.current_generation("entity-1").await, Some(0));
+    assert!(registry.get_generation("entity-1", generation).await.is_err());
+
+    // Generations are random IDs, not a counter two devices could both advance
+    let (a, _) = rotate(&registry, "entity-1").await;
+    let (b, _) = rotate(&registry, "entity-1").await;
+    assert_ne!(a, b);
+    assert_eq!(registry.current_generation("entity-1").await, Some(b));
+    assert!(registry.get_generation("entity-1", a).await.is_ok());
+}
+
+#[tokio::test]
+async fn failed_rotations_stay_pending_until_committed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.cloud_deks");
+    let master = generate_random_key();
+
+    let registry = DekRegistry::new();
+    registry.open_persisted(&path, master.clone()).await.unwrap();
+    registry.insert("entity-1".to_string(), generate_random_key()).await;
+    registry.mark_rotation_pending("entity-1").await;
+
+    let reloaded = DekRegistry::new();
+    reloaded.open_persisted(&path, master).await.unwrap();
+    assert_eq!(reloaded.pending_rotations().await, vec!["entity-1"]);
+    rotate(&reloaded, "entity-1").await;
+    assert!(reloaded.pending_rotations().await.is_empty());
+}
+
+#[tokio::test]
+async fn insert_generation_makes_it_current() {
+    let registry = DekRegistry::new();
+    let gen_2 = generate_random_key();
+    let gen_2_bytes = *gen_2.as_bytes();
+
+    registry
+        .insert_generation("entity-1".to_string(), 1, generate_random_key())
+        .await;
+    registry.insert_generation("entity-1".to_string(), 2, gen_2).await;
+
+    let (generation, current) = registry.current("entity-1").await.unwrap();
+    assert_eq!(generation, 2);
+    assert_eq!(*current.as_bytes(), gen_2_bytes);
+    assert!(registry.get_generation("entity-1", 1).await.is_ok());
+
+    let err = registry.get_generation("entity-1", 3).await.unwrap_err();
+    assert!(matches!(err, CloudError::Envelope(msg) if msg.contains("generation 3")));
+}
+
+// ── Persistence ──
+
+#[tokio::test]
+async fn persisted_registry_survives_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.cloud_deks");
+    let master = generate_random_key();
+
+    let registry = DekRegistry::new();
+    registry.open_persisted(&path, master.clone()).await.unwrap();
+    let owned = generate_random_key();
+    let owned_bytes = *owned.as_bytes();
+    registry.insert("owned".to_string(), owned).await;
+    let (rotated_gen, rotated) = rotate(&registry, "owned").await;
+    registry
+        .insert_shared("shared".to_string(), 2, generate_random_key())
+        .await;
+
+    let reloaded = DekRegistry::new();
+    assert_eq!(reloaded.open_persisted(&path, master).await.unwrap(), 2);
+    let (generation, current) = reloaded.current("owned").await.unwrap();
+    assert_eq!(generation, rotated_gen);
+    assert_eq!(current.as_bytes(), rotated.as_bytes());
+    assert_eq!(
+        *reloaded.get_generation("owned", 0).await.unwrap().as_bytes(),
+        owned_bytes
+    );
+    assert_eq!(reloaded.current_generation("shared").await, Some(2));
+}
+
+#[tokio::test]
+async fn persisted_registry_is_encrypted_under_master_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.cloud_deks");
+    let master = generate_random_key();
+
+    let registry = DekRegistry::new();
+    registry.open_persisted(&path, master.clone()).await.unwrap();
+    let dek = generate_random_key();
+    let encoded = STANDARD.encode(dek.as_bytes());
+    registry.insert("entity-1".to_string(), dek).await;
+
+    let raw = std::fs::read_to_string(&path).unwrap();
+    assert!(!raw.contains("entity-1"));
+    assert!(!raw.contains(&encoded));
+
+    let err = DekRegistry::new()
+        .open_persisted(&path, generate_random_key())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, CloudError::Envelope(_)));
+}
+
+#[tokio::test]
+async fn rekey_reencrypts_registry_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.cloud_deks");
+    let (old_master, new_master) = (generate_random_key(), generate_random_key());
+
+    let registry = DekRegistry::new();
+    registry.open_persisted(&path, old_master.clone()).await.unwrap();
+    registry.insert("entity-1".to_string(), generate_random_key()).await;
+    registry.rekey(new_master.clone()).await.unwrap();
+
+    assert!(DekRegistry::new().open_persisted(&path, old_master).await.is_err());
+    assert_eq!(DekRegistry::new().open_persisted(&path, new_master).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn rekey_file_reencrypts_a_registry_that_is_not_open() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.cloud_deks");
+    let (old_master, new_master) = (generate_random_key(), generate_random_key());
+    assert!(!DekRegistry::rekey_file(&path, &old_master, &new_master).unwrap());
+
+    let registry = DekRegistry::new();
+    registry.open_persisted(&path, old_master.clone()).await.unwrap();
+    registry.insert("entity-1".to_string(), generate_random_key()).await;
+    drop(registry);
+
+    assert!(DekRegistry::rekey_file(&path, &old_master, &new_master).unwrap());
+    assert!(DekRegistry::new().open_persisted(&path, old_master.clone()).await.is_err());
+    assert_eq!(DekRegistry::new().open_persisted(&path, new_master.clone()).await.unwrap(), 1);
+
+    // The wrong old key leaves the file untouched.
+    assert!(DekRegistry::rekey_file(&path, &old_master, &generate_random_key()).is_err());
+    assert_eq!(DekRegistry::new().open_persisted(&path, new_master).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn prune_shared_drops_only_revoked_shares() {
+    let registry = DekRegistry::new();
+    registry.insert("owned".to_string(), generate_random_key()).await;
+    registry.insert_shared("kept".to_string(), 0, generate_random_key()).await;
+    registry.insert_shared("revoked".to_string(), 1, generate_random_key()).await;
+
+    let still_shared = std::collections::HashSet::from(["kept".to_string()]);
+    assert_eq!(registry.prune_shared(&still_shared).await, vec!["revoked"]);
+    assert!(registry.get("owned").await.is_ok());
+    assert!(registry.get("kept").await.is_ok());
+    assert!(registry.get("revoked").await.is_err());
+}
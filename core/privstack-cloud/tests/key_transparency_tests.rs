@@ -0,0 +1,235 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use privstack_cloud::api_client::CloudApiClient;
+use privstack_cloud::config::CloudConfig;
+use privstack_cloud::envelope::EnvelopeManager;
+use privstack_cloud::error::CloudError;
+use privstack_cloud::key_transparency::{
+    safety_number, KeyLogEntry, KeyPinStore, PinCheck, SignedKeyLog, KEY_LOG_GENESIS_HASH,
+};
+use privstack_crypto::envelope::generate_cloud_keypair;
+use privstack_crypto::IdentityKeyPair;
+use std::sync::Arc;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn setup(server: &MockServer) -> Arc<CloudApiClient> {
+    let config = CloudConfig {
+        api_base_url: server.uri(),
+        s3_bucket: "test-bucket".into(),
+        s3_region: "us-east-2".into(),
+        s3_endpoint_override: None,
+        credential_refresh_margin_secs: 60,
+        poll_interval_secs: 5,
+        key_log_public_key: None,
+    };
+    let client = CloudApiClient::new(config);
+    client.set_tokens("at".into(), "rt".into(), 1).await;
+    Arc::new(client)
+}
+
+async fn serve_public_key(server: &MockServer, user_id: i64, key: [u8; 32]) {
+    server.reset().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/api/cloud/keys/public/{user_id}")))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "public_key": STANDARD.encode(key)
+        })))
+        .mount(server)
+        .await;
+}
+
+/// Builds a log of `keys` for `user_id` signed by `log_key`.
+fn signed_log(log_key: &IdentityKeyPair, user_id: i64, keys: &[[u8; 32]]) -> SignedKeyLog {
+    let mut entries = Vec::new();
+    let mut prev = KEY_LOG_GENESIS_HASH.to_string();
+    for (i, key) in keys.iter().enumerate() {
+        let entry = KeyLogEntry {
+            index: i as u64,
+            public_key: STANDARD.encode(key),
+            created_at: format!("2026-01-0{}T00:00:00Z", i + 1),
+            prev_hash: prev,
+        };
+        prev = SignedKeyLog::entry_hash(user_id, &entry);
+        entries.push(entry);
+    }
+    let mut log = SignedKeyLog {
+        user_id,
+        entries,
+        head_hash: prev,
+        signature: String::new(),
+    };
+    log.signature = STANDARD.encode(log_key.sign(&log.signed_bytes()));
+    log
+}
+
+// ── Pin store ───────────────────────────────────────────────────
+
+#[test]
+fn pin_store_tracks_first_use_and_changes() {
+    let pins = KeyPinStore::in_memory();
+    let (first, second) = ([1u8; 32], [2u8; 32]);
+
+    assert_eq!(pins.check(7, &first).unwrap(), PinCheck::Unpinned);
+    pins.pin(7, &first).unwrap();
+    assert_eq!(pins.check(7, &first).unwrap(), PinCheck::Match);
+    assert_eq!(pins.check(7, &second).unwrap(), PinCheck::Changed { pinned: first });
+
+    pins.set_verified(7, true).unwrap();
+    assert!(pins.get(7).unwrap().verified);
+    pins.pin(7, &second).unwrap();
+    assert!(!pins.get(7).unwrap().verified, "a new key starts unverified");
+    assert!(pins.set_verified(8, true).is_err());
+}
+
+#[test]
+fn pin_store_persists_to_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("data.key_pins");
+    {
+        let pins = KeyPinStore::open(&file).unwrap();
+        pins.pin(3, &[9u8; 32]).unwrap();
+        pins.record_log_head(3, 2, "abc").unwrap();
+    }
+    let reopened = KeyPinStore::open(&file).unwrap();
+    let pin = reopened.get(3).unwrap();
+    assert_eq!(pin.key_bytes().unwrap(), [9u8; 32]);
+    assert_eq!(pin.log_size, Some(2));
+    assert_eq!(pin.log_head.as_deref(), Some("abc"));
+    // The temp file used for the atomic replace is renamed away
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+// ── Safety numbers ──────────────────────────────────────────────
+
+#[test]
+fn safety_number_is_symmetric_and_key_bound() {
+    let (alice, bob) = ([1u8; 32], [2u8; 32]);
+    let from_alice = safety_number(1, &alice, 2, &bob);
+    let from_bob = safety_number(2, &bob, 1, &alice);
+    assert_eq!(from_alice, from_bob);
+
+    let groups: Vec<&str> = from_alice.split(' ').collect();
+    assert_eq!(groups.len(), 12);
+    assert!(groups.iter().all(|g| g.len() == 5 && g.chars().all(|c| c.is_ascii_digit())));
+
+    assert_ne!(from_alice, safety_number(1, &alice, 2, &[3u8; 32]));
+}
+
+// ── Key log ─────────────────────────────────────────────────────
+
+#[test]
+fn key_log_verifies_and_returns_newest_key() {
+    let log_key = IdentityKeyPair::generate();
+    let log = signed_log(&log_key, 5, &[[1u8; 32], [2u8; 32]]);
+    assert_eq!(log.verify(&log_key.public_key()).unwrap(), [2u8; 32]);
+
+    let other = IdentityKeyPair::generate();
+    assert!(matches!(log.verify(&other.public_key()), Err(CloudError::KeyLog(_))));
+}
+
+#[test]
+fn key_log_detects_tampering() {
+    let log_key = IdentityKeyPair::generate();
+    let mut log = signed_log(&log_key, 5, &[[1u8; 32], [2u8; 32]]);
+    log.entries[0].public_key = STANDARD.encode([6u8; 32]);
+    assert!(matches!(log.verify(&log_key.public_key()), Err(CloudError::KeyLog(_))));
+
+    let empty = signed_log(&log_key, 5, &[]);
+    assert!(empty.verify(&log_key.public_key()).is_err());
+}
+
+#[test]
+fn key_log_must_extend_previous_head() {
+    let log_key = IdentityKeyPair::generate();
+    let old = signed_log(&log_key, 5, &[[1u8; 32]]);
+    let extended = signed_log(&log_key, 5, &[[1u8; 32], [2u8; 32]]);
+    let rewritten = signed_log(&log_key, 5, &[[3u8; 32], [2u8; 32]]);
+
+    assert!(extended.verify_extends(1, &old.head_hash).is_ok());
+    assert!(rewritten.verify_extends(1, &old.head_hash).is_err());
+    assert!(old.verify_extends(2, &extended.head_hash).is_err());
+}
+
+// ── Envelope manager ────────────────────────────────────────────
+
+#[tokio::test]
+async fn sealing_pins_on_first_use_and_refuses_changed_key() {
+    let server = MockServer::start().await;
+    let api = setup(&server).await;
+    let mut envelopes = EnvelopeManager::new(api);
+    envelopes.set_key_pins(Arc::new(KeyPinStore::in_memory()));
+
+    let recipient = generate_cloud_keypair();
+    serve_public_key(&server, 5, recipient.public_bytes()).await;
+    envelopes.seal_dek_for_user(&[0u8; 32], 5).await.unwrap();
+    assert!(envelopes.key_pins().unwrap().get(5).is_some());
+
+    let attacker = generate_cloud_keypair();
+    serve_public_key(&server, 5, attacker.public_bytes()).await;
+    let err = envelopes.seal_dek_for_user(&[0u8; 32], 5).await.unwrap_err();
+    assert!(matches!(err, CloudError::RecipientKeyChanged { user_id: 5 }));
+
+    // Accepting pins only the key the user approved.
+    let pending = envelopes.pending_recipient_key(5).await.unwrap();
+    assert_eq!(pending, attacker.public_bytes());
+    let err = envelopes.accept_recipient_key(5, &recipient.public_bytes()).await.unwrap_err();
+    assert!(matches!(err, CloudError::RecipientKeyChanged { user_id: 5 }));
+    assert!(envelopes.seal_dek_for_user(&[0u8; 32], 5).await.is_err());
+    envelopes.accept_recipient_key(5, &pending).await.unwrap();
+    envelopes.seal_dek_for_user(&[0u8; 32], 5).await.unwrap();
+}
+
+#[tokio::test]
+async fn safety_number_uses_pinned_key() {
+    let server = MockServer::start().await;
+    let api = setup(&server).await;
+    let mut envelopes = EnvelopeManager::new(api);
+    envelopes.set_key_pins(Arc::new(KeyPinStore::in_memory()));
+    let local = generate_cloud_keypair();
+    let local_public = local.public_bytes();
+    envelopes.set_keypair(local);
+
+    assert!(envelopes.safety_number(1, 5).is_err(), "nothing pinned yet");
+
+    let recipient = generate_cloud_keypair();
+    serve_public_key(&server, 5, recipient.public_bytes()).await;
+    envelopes.recipient_key(5).await.unwrap();
+    assert_eq!(
+        envelopes.safety_number(1, 5).unwrap(),
+        safety_number(1, &local_public, 5, &recipient.public_bytes())
+    );
+}
+
+#[tokio::test]
+async fn key_log_is_required_when_configured() {
+    let server = MockServer::start().await;
+    let api = setup(&server).await;
+    let log_key = IdentityKeyPair::generate();
+    let mut envelopes = EnvelopeManager::new(api);
+    envelopes.set_key_pins(Arc::new(KeyPinStore::in_memory()));
+    envelopes.set_key_log_key(log_key.public_key());
+
+    let first = generate_cloud_keypair().public_bytes();
+    let log = signed_log(&log_key, 5, &[first]);
+    Mock::given(method("GET"))
+        .and(path("/api/cloud/keys/log/5"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&log))
+        .mount(&server)
+        .await;
+    assert_eq!(envelopes.recipient_key(5).await.unwrap(), first);
+    let pin = envelopes.key_pins().unwrap().get(5).unwrap();
+    assert_eq!(pin.log_head.as_deref(), Some(log.head_hash.as_str()));
+
+    // A log whose head is signed by someone else is rejected.
+    server.reset().await;
+    let forged = signed_log(&IdentityKeyPair::generate(), 5, &[first]);
+    Mock::given(method("GET"))
+        .and(path("/api/cloud/keys/log/5"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&forged))
+        .mount(&server)
+        .await;
+    assert!(matches!(
+        envelopes.recipient_key(5).await,
+        Err(CloudError::KeyLog(_))
+    ));
+}
@@ -0,0 +1,341 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use privstack_cloud::api_client::CloudApiClient;
+use privstack_cloud::config::CloudConfig;
+use privstack_cloud::dek_registry::DekRegistry;
+use privstack_cloud::envelope::EnvelopeManager;
+use privstack_cloud::sharing::ShareManager;
+use privstack_crypto::envelope::{generate_cloud_keypair, seal_dek};
+use privstack_crypto::{decrypt, encrypt, generate_random_key, DerivedKey};
+use std::sync::Arc;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn setup(server: &MockServer) -> Arc<CloudApiClient> {
+    let config = CloudConfig {
+        api_base_url: server.uri(),
+        s3_bucket: "test-bucket".into(),
+        s3_region: "us-east-2".into(),
+        s3_endpoint_override: None,
+        credential_refresh_margin_secs: 60,
+        poll_interval_secs: 5,
+        key_log_public_key: None,
+    };
+    let client = CloudApiClient::new(config);
+    client.set_tokens("at".into(), "rt".into(), 1).await;
+    Arc::new(client)
+}
+
+fn share_json(share_id: i64, email: &str, status: &str, user_id: Option<i64>) -> serde_json::Value {
+    serde_json::json!({
+        "share_id": share_id,
+        "entity_id": "e-1",
+        "entity_type": "note",
+        "entity_name": null,
+        "recipient_email": email,
+        "recipient_user_id": user_id,
+        "permission": "read",
+        "status": status,
+        "created_at": "2025-01-01T00:00:00Z",
+        "accepted_at": null
+    })
+}
+
+async fn mount_public_key(server: &MockServer, user_id: i64) {
+    Mock::given(method("GET"))
+        .and(path(format!("/api/cloud/keys/public/{user_id}")))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "public_key": STANDARD.encode(generate_cloud_keypair().public_bytes())
+        })))
+        .mount(server)
+        .await;
+}
+
+// ── Revocation ──────────────────────────────────────────────────
+
+#[tokio::test]
+async fn revoke_rotates_dek_and_reseals_to_remaining_recipients() {
+    let server = MockServer::start().await;
+    let api = setup(&server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/share/revoke"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"revoked": true})))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/share/entity/e-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "shares": [
+                share_json(1, "bob@example.com", "revoked", Some(6)),
+                share_json(2, "carol@example.com", "accepted", Some(7)),
+                share_json(3, "dave@example.com", "pending", None),
+            ]
+        })))
+        .mount(&server)
+        .await;
+    mount_public_key(&server, 1).await;
+    mount_public_key(&server, 7).await;
+    // Carol and the owner's own account get the new generation; Bob does not.
+    Mock::given(method("POST"))
+        .and(path("/api/share/keys/store"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"success": true})))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let registry = DekRegistry::new();
+    let original = generate_random_key();
+    let original_bytes = *original.as_bytes();
+    registry.insert("e-1".to_string(), original).await;
+
+    let envelopes = EnvelopeManager::new(api.clone());
+    let shares = ShareManager::new(api);
+    let generation = shares
+        .revoke_share(&envelopes, &registry, "e-1", "bob@example.com")
+        .await
+        .unwrap()
+        .expect("rotation should complete");
+
+    let (current_gen, current) = registry.current("e-1").await.unwrap();
+    assert_eq!(current_gen, generation);
+    assert_ne!(*current.as_bytes(), original_bytes);
+    assert_eq!(
+        *registry.get_generation("e-1", 0).await.unwrap().as_bytes(),
+        original_bytes
+    );
+}
+
+#[tokio::test]
+async fn rotation_reports_reseal_failure_after_trying_everyone() {
+    let server = MockServer::start().await;
+    let api = setup(&server).await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/share/entity/e-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "shares": [share_json(2, "carol@example.com", "accepted", Some(7))]
+        })))
+        .mount(&server)
+        .await;
+    // Carol's key lookup fails; the owner's succeeds.
+    Mock::given(method("GET"))
+        .and(path("/api/cloud/keys/public/7"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+    mount_public_key(&server, 1).await;
+    Mock::given(method("POST"))
+        .and(path("/api/share/keys/store"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"success": true})))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let registry = DekRegistry::new();
+    registry.insert("e-1".to_string(), generate_random_key()).await;
+    let envelopes = EnvelopeManager::new(api.clone());
+    let shares = ShareManager::new(api);
+
+    assert!(shares.rotate_entity_dek(&envelopes, &registry, "e-1").await.is_err());
+    // Carol could not open a new key, so the old one stays current
+    assert_eq!(registry.current("e-1").await.unwrap().0, 0);
+    assert_eq!(registry.pending_rotations().await, vec!["e-1"]);
+}
+
+#[tokio::test]
+async fn revoke_succeeds_when_rotation_fails_and_rotation_is_retried() {
+    let server = MockServer::start().await;
+    let api = setup(&server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/share/revoke"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"revoked": true})))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/share/entity/e-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "shares": [share_json(2, "carol@example.com", "accepted", Some(7))]
+        })))
+        .mount(&server)
+        .await;
+    mount_public_key(&server, 1).await;
+    mount_public_key(&server, 7).await;
+    // The first store fails; later ones succeed.
+    Mock::given(method("POST"))
+        .and(path("/api/share/keys/store"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/share/keys/store"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"success": true})))
+        .mount(&server)
+        .await;
+
+    let registry = DekRegistry::new();
+    registry.insert("e-1".to_string(), generate_random_key()).await;
+    let envelopes = EnvelopeManager::new(api.clone());
+    let shares = ShareManager::new(api);
+
+    let revoked = shares
+        .revoke_share(&envelopes, &registry, "e-1", "bob@example.com")
+        .await
+        .unwrap();
+    assert_eq!(revoked, None);
+    assert_eq!(registry.current_generation("e-1").await, Some(0));
+
+    assert_eq!(shares.retry_pending_rotations(&envelopes, &registry).await, 1);
+    assert_ne!(registry.current_generation("e-1").await, Some(0));
+    assert!(registry.pending_rotations().await.is_empty());
+}
+
+#[tokio::test]
+async fn owners_other_device_reads_batches_after_rotation() {
+    let server = MockServer::start().await;
+    let api = setup(&server).await;
+    let owner_keys = generate_cloud_keypair();
+
+    Mock::given(method("POST"))
+        .and(path("/api/share/revoke"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"revoked": true})))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/share/entity/e-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "shares": [share_json(2, "carol@example.com", "accepted", Some(7))]
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/cloud/keys/public/1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "public_key": STANDARD.encode(owner_keys.public_bytes())
+        })))
+        .mount(&server)
+        .await;
+    mount_public_key(&server, 7).await;
+    Mock::given(method("POST"))
+        .and(path("/api/share/keys/store"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"success": true})))
+        .mount(&server)
+        .await;
+
+    // Device A revokes Bob's share and writes the next batch with the new key.
+    let original = *generate_random_key().as_bytes();
+    let registry_a = DekRegistry::new();
+    registry_a.insert("e-1".to_string(), DerivedKey::from_bytes(original)).await;
+    let envelopes_a = EnvelopeManager::new(api.clone());
+    let shares = ShareManager::new(api.clone());
+    let generation = shares
+        .revoke_share(&envelopes_a, &registry_a, "e-1", "bob@example.com")
+        .await
+        .unwrap()
+        .expect("rotation should complete");
+    let (_, new_dek) = registry_a.current("e-1").await.unwrap();
+    let batch = encrypt(&new_dek, b"next batch").unwrap();
+
+    // The server hands device B the envelope A sealed to the owner's account.
+    let stored = server.received_requests().await.unwrap();
+    let own_envelope = stored
+        .iter()
+        .filter(|r| r.url.path() == "/api/share/keys/store")
+        .map(|r| serde_json::from_slice::<serde_json::Value>(&r.body).unwrap())
+        .find(|body| body["recipient_user_id"] == 1)
+        .expect("owner envelope stored");
+    let mut body: serde_json::Value =
+        serde_json::from_str(own_envelope["encrypted_dek"].as_str().unwrap()).unwrap();
+    body["dek_generation"] = serde_json::json!(generation);
+    Mock::given(method("GET"))
+        .and(path("/api/share/keys/e-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .mount(&server)
+        .await;
+
+    // Device B only knows the original key until it sees the new generation.
+    let registry_b = DekRegistry::new();
+    registry_b.insert("e-1".to_string(), DerivedKey::from_bytes(original)).await;
+    assert!(registry_b.get_generation("e-1", generation).await.is_err());
+    let mut envelopes_b = EnvelopeManager::new(api);
+    envelopes_b.set_keypair(owner_keys);
+    let dek = registry_b
+        .fetch_generation(&envelopes_b, "e-1", generation)
+        .await
+        .unwrap();
+    assert_eq!(decrypt(&dek, &batch).unwrap(), b"next batch");
+    assert_eq!(registry_b.current_generation("e-1").await, Some(generation));
+}
+
+// ── Share keys ──────────────────────────────────────────────────
+
+#[tokio::test]
+async fn retrieve_and_open_dek_returns_generation() {
+    let server = MockServer::start().await;
+    let api = setup(&server).await;
+    let keypair = generate_cloud_keypair();
+    let dek = generate_random_key();
+    let sealed = seal_dek(dek.as_bytes(), &keypair.public).unwrap();
+
+    let mut body = serde_json::to_value(&sealed).unwrap();
+    body["dek_generation"] = serde_json::json!(2);
+    Mock::given(method("GET"))
+        .and(path("/api/share/keys/e-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .mount(&server)
+        .await;
+
+    let mut envelopes = EnvelopeManager::new(api);
+    envelopes.set_keypair(keypair);
+    let (generation, opened) = envelopes.retrieve_and_open_dek("e-1").await.unwrap();
+    assert_eq!(generation, 2);
+    assert_eq!(opened, dek.as_bytes());
+}
+
+#[tokio::test]
+async fn refresh_shared_keys_updates_and_prunes_registry() {
+    let server = MockServer::start().await;
+    let api = setup(&server).await;
+    let keypair = generate_cloud_keypair();
+    let dek = generate_random_key();
+    let sealed = seal_dek(dek.as_bytes(), &keypair.public).unwrap();
+    let mut body = serde_json::to_value(&sealed).unwrap();
+    body["dek_generation"] = serde_json::json!(1);
+
+    Mock::given(method("GET"))
+        .and(path("/api/share/received"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "shares": [{
+                "entity_id": "e-1",
+                "entity_type": "note",
+                "entity_name": null,
+                "owner_user_id": 5,
+                "workspace_id": "ws-1",
+                "permission": "read"
+            }]
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/share/keys/e-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .mount(&server)
+        .await;
+
+    let registry = DekRegistry::new();
+    registry.insert_shared("e-gone".to_string(), 0, generate_random_key()).await;
+    let mut envelopes = EnvelopeManager::new(api.clone());
+    envelopes.set_keypair(keypair);
+    let shares = ShareManager::new(api);
+
+    assert_eq!(shares.refresh_shared_keys(&envelopes, &registry).await.unwrap(), 1);
+    let (generation, current) = registry.current("e-1").await.unwrap();
+    assert_eq!(generation, 1);
+    assert_eq!(current.as_bytes(), dek.as_bytes());
+    assert!(registry.get("e-gone").await.is_err());
+
+    // Nothing changed on the server: no generation bump reported.
+    assert_eq!(shares.refresh_shared_keys(&envelopes, &registry).await.unwrap(), 0);
+}
@@ -111,6 +111,7 @@ fn share_info_roundtrip() {
         entity_type: "note".into(),
         entity_name: Some("My Note".into()),
         recipient_email: "bob@example.com".into(),
+        recipient_user_id: None,
         permission: SharePermission::Read,
         status: ShareStatus::Pending,
         created_at: Utc::now(),
@@ -327,3 +328,32 @@ fn pending_changes_roundtrip() {
     assert_eq!(de.entities.len(), 1);
     assert_eq!(de.entities[0].current_cursor, 10);
 }
+
+// --- EncryptedPayload ---
+
+#[test]
+fn encrypted_payload_generation_zero_matches_plain_format() {
+    let key = privstack_crypto::generate_random_key();
+    let data = privstack_crypto::encrypt(&key, b"batch").unwrap();
+    let plain = serde_json::to_value(&data).unwrap();
+
+    let payload = EncryptedPayload { dek_generation: 0, data: data.clone() };
+    assert_eq!(serde_json::to_value(&payload).unwrap(), plain);
+
+    let parsed: EncryptedPayload = serde_json::from_value(plain).unwrap();
+    assert_eq!(parsed.dek_generation, 0);
+    assert_eq!(privstack_crypto::decrypt(&key, &parsed.data).unwrap(), b"batch");
+}
+
+#[test]
+fn encrypted_payload_records_generation() {
+    let key = privstack_crypto::generate_random_key();
+    let payload = EncryptedPayload {
+        dek_generation: 3,
+        data: privstack_crypto::encrypt(&key, b"batch").unwrap(),
+    };
+    let json = serde_json::to_vec(&payload).unwrap();
+    let parsed: EncryptedPayload = serde_json::from_slice(&json).unwrap();
+    assert_eq!(parsed.dek_generation, 3);
+    assert_eq!(privstack_crypto::decrypt(&key, &parsed.data).unwrap(), b"batch");
+}
@@ -28,6 +28,10 @@ pub enum CryptoError {
     #[error("invalid nonce length: expected {expected}, got {actual}")]
     InvalidNonceLength { expected: usize, actual: usize },
 
+    /// Signature missing, malformed, or not matching the signed data.
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
+
     /// Serialization error.
     #[error("serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
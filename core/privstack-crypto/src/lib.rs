@@ -28,6 +28,7 @@ pub mod envelope;
 mod error;
 mod key;
 pub mod recovery;
+pub mod signing;
 
 pub use cipher::{
     decrypt, decrypt_string, encrypt, encrypt_string, EncryptedData, NONCE_SIZE, TAG_SIZE,
@@ -45,3 +46,6 @@ pub use envelope::{
 pub use error::{CryptoError, CryptoResult};
 pub use key::{derive_key, generate_random_key, DerivedKey, KdfParams, Salt, KEY_SIZE, SALT_SIZE};
 pub use recovery::{create_recovery_blob, create_recovery_blob_with_mnemonic, open_recovery_blob, reencrypt_recovery_blob, RecoveryBlob};
+pub use signing::{
+    verify_event, Authorship, IdentityKeyPair, IdentityPublicKey, SignatureMode,
+};
@@ -0,0 +1,207 @@
+//! Device identity keys and event signatures.
+//!
+//! Every device holds an Ed25519 identity key. Events are signed over
+//! `Event::signing_bytes()` and carry the author's public key, so any replica
+//! can check that an event was produced by the holder of that key. Binding the
+//! key to the event's `peer_id` (pinning) is the storage layer's job.
+
+use crate::error::{CryptoError, CryptoResult};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use privstack_types::{Event, EventSignature, PeerId, RedactionSeal};
+use zeroize::Zeroizing;
+
+/// Size of an identity secret or public key in bytes.
+pub const IDENTITY_KEY_SIZE: usize = 32;
+
+/// Size of an Ed25519 signature in bytes.
+pub const SIGNATURE_SIZE: usize = 64;
+
+/// How unsigned events are treated when they arrive or are stored.
+///
+/// Signed events are always verified; a bad signature is rejected in every mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignatureMode {
+    /// Unsigned events are rejected.
+    Required,
+    /// Unsigned events from devices that predate signing are accepted, but their
+    /// `peer_id` is unauthenticated and must not be trusted for authorization.
+    #[default]
+    LegacyMigration,
+}
+
+/// Result of checking an event's signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Authorship {
+    /// The signature is valid for the embedded author key.
+    Verified(IdentityPublicKey),
+    /// The event is a redacted copy whose seal is valid for the embedded
+    /// redactor key. The author is not authenticated.
+    Redacted(IdentityPublicKey),
+    /// The event is unsigned (accepted only in `SignatureMode::LegacyMigration`).
+    Unsigned,
+}
+
+/// A device's Ed25519 identity keypair. The secret key is zeroized on drop.
+pub struct IdentityKeyPair {
+    signing: SigningKey,
+}
+
+impl IdentityKeyPair {
+    /// Generates a new random identity key.
+    pub fn generate() -> Self {
+        Self {
+            signing: SigningKey::generate(&mut rand::rngs::OsRng),
+        }
+    }
+
+    /// Reconstructs an identity key from its 32-byte secret.
+    pub fn from_secret_bytes(bytes: &[u8]) -> CryptoResult<Self> {
+        let secret: [u8; IDENTITY_KEY_SIZE] =
+            bytes.try_into().map_err(|_| CryptoError::InvalidKeyLength {
+                expected: IDENTITY_KEY_SIZE,
+                actual: bytes.len(),
+            })?;
+        Ok(Self {
+            signing: SigningKey::from_bytes(&secret),
+        })
+    }
+
+    /// Returns the 32-byte secret for persistence.
+    pub fn secret_bytes(&self) -> Zeroizing<[u8; IDENTITY_KEY_SIZE]> {
+        Zeroizing::new(self.signing.to_bytes())
+    }
+
+    /// Returns the public half of the key.
+    pub fn public_key(&self) -> IdentityPublicKey {
+        IdentityPublicKey(self.signing.verifying_key().to_bytes())
+    }
+
+    /// Signs an arbitrary message.
+    pub fn sign(&self, message: &[u8]) -> [u8; SIGNATURE_SIZE] {
+        self.signing.sign(message).to_bytes()
+    }
+
+    /// Signs an event in place, replacing any existing signature.
+    /// The event's `peer_id` must be the device this key belongs to.
+    pub fn sign_event(&self, event: &mut Event) {
+        let signature = self.sign(&event.signing_bytes());
+        event.signature = Some(EventSignature {
+            public_key: self.public_key().to_base64(),
+            signature: STANDARD.encode(signature),
+        });
+    }
+
+    /// Seals a redacted copy of an event in place, replacing its author
+    /// signature (which no longer matches). `redactor` must be the device
+    /// this key belongs to.
+    pub fn seal_redaction(&self, redactor: PeerId, event: &mut Event) {
+        let signature = self.sign(&event.redaction_signing_bytes(&redactor));
+        event.signature = None;
+        event.redaction_seal = Some(RedactionSeal {
+            redactor,
+            signature: EventSignature {
+                public_key: self.public_key().to_base64(),
+                signature: STANDARD.encode(signature),
+            },
+        });
+    }
+}
+
+impl std::fmt::Debug for IdentityKeyPair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IdentityKeyPair")
+            .field("public_key", &self.public_key())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A device's Ed25519 identity public key.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IdentityPublicKey([u8; IDENTITY_KEY_SIZE]);
+
+impl IdentityPublicKey {
+    /// Creates a public key from raw bytes.
+    pub fn from_bytes(bytes: [u8; IDENTITY_KEY_SIZE]) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the raw key bytes.
+    pub fn as_bytes(&self) -> &[u8; IDENTITY_KEY_SIZE] {
+        &self.0
+    }
+
+    /// Encodes the key as base64.
+    pub fn to_base64(&self) -> String {
+        STANDARD.encode(self.0)
+    }
+
+    /// Decodes a base64-encoded key.
+    pub fn from_base64(encoded: &str) -> CryptoResult<Self> {
+        let bytes = STANDARD
+            .decode(encoded)
+            .map_err(|e| CryptoError::InvalidSignature(format!("invalid public key: {e}")))?;
+        let key: [u8; IDENTITY_KEY_SIZE] =
+            bytes.as_slice().try_into().map_err(|_| CryptoError::InvalidKeyLength {
+                expected: IDENTITY_KEY_SIZE,
+                actual: bytes.len(),
+            })?;
+        Ok(Self(key))
+    }
+
+    /// Verifies a signature over `message`.
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> CryptoResult<()> {
+        let key = VerifyingKey::from_bytes(&self.0)
+            .map_err(|e| CryptoError::InvalidSignature(format!("invalid public key: {e}")))?;
+        let signature = Signature::from_slice(signature)
+            .map_err(|e| CryptoError::InvalidSignature(e.to_string()))?;
+        key.verify_strict(message, &signature)
+            .map_err(|_| CryptoError::InvalidSignature("signature does not match".into()))
+    }
+}
+
+impl std::fmt::Debug for IdentityPublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "IdentityPublicKey({})", self.to_base64())
+    }
+}
+
+/// Checks an event's author signature, or the redaction seal of a redacted copy.
+///
+/// Returns the verified author (or redactor) key, or `Authorship::Unsigned` for
+/// an unsigned event when `mode` allows it. Fails if the signature is malformed
+/// or invalid, if a seal is on an unredacted event, or if the event is unsigned
+/// and `mode` is `SignatureMode::Required`.
+pub fn verify_event(event: &Event, mode: SignatureMode) -> CryptoResult<Authorship> {
+    if let Some(seal) = &event.redaction_seal {
+        if !event.is_redacted() {
+            return Err(CryptoError::InvalidSignature(format!(
+                "event {} is sealed but not redacted",
+                event.id
+            )));
+        }
+        let message = event.redaction_signing_bytes(&seal.redactor);
+        return verify_signature(&seal.signature, &message).map(Authorship::Redacted);
+    }
+    let Some(sig) = &event.signature else {
+        return match mode {
+            SignatureMode::Required => Err(CryptoError::InvalidSignature(format!(
+                "event {} is unsigned",
+                event.id
+            ))),
+            SignatureMode::LegacyMigration => Ok(Authorship::Unsigned),
+        };
+    };
+
+    verify_signature(sig, &event.signing_bytes()).map(Authorship::Verified)
+}
+
+/// Verifies `sig` over `message`, returning the key it was made with.
+fn verify_signature(sig: &EventSignature, message: &[u8]) -> CryptoResult<IdentityPublicKey> {
+    let public_key = IdentityPublicKey::from_base64(&sig.public_key)?;
+    let signature = STANDARD
+        .decode(&sig.signature)
+        .map_err(|e| CryptoError::InvalidSignature(format!("invalid signature encoding: {e}")))?;
+    public_key.verify(message, &signature)?;
+    Ok(public_key)
+}
@@ -0,0 +1,108 @@
+use privstack_crypto::signing::{
+    verify_event, Authorship, IdentityKeyPair, IdentityPublicKey, SignatureMode,
+};
+use privstack_crypto::CryptoError;
+use privstack_types::{EntityId, Event, EventId, EventPayload, PeerId};
+
+fn signed_event(key: &IdentityKeyPair) -> Event {
+    let mut event = Event::entity_created(EntityId::new(), PeerId::new(), "note", r#"{"t":1}"#);
+    key.sign_event(&mut event);
+    event
+}
+
+#[test]
+fn signed_event_verifies() {
+    let key = IdentityKeyPair::generate();
+    let event = signed_event(&key);
+    assert_eq!(
+        verify_event(&event, SignatureMode::Required).unwrap(),
+        Authorship::Verified(key.public_key())
+    );
+}
+
+#[test]
+fn tampered_payload_fails() {
+    let key = IdentityKeyPair::generate();
+    let mut event = signed_event(&key);
+    event.payload = EventPayload::EntityDeleted {
+        entity_type: "note".into(),
+    };
+    assert!(matches!(
+        verify_event(&event, SignatureMode::LegacyMigration),
+        Err(CryptoError::InvalidSignature(_))
+    ));
+}
+
+#[test]
+fn forged_author_fails() {
+    let key = IdentityKeyPair::generate();
+    let mut event = signed_event(&key);
+    event.peer_id = PeerId::new();
+    assert!(verify_event(&event, SignatureMode::LegacyMigration).is_err());
+}
+
+#[test]
+fn swapped_public_key_fails() {
+    let key = IdentityKeyPair::generate();
+    let other = IdentityKeyPair::generate();
+    let mut event = signed_event(&key);
+    event.signature.as_mut().unwrap().public_key = other.public_key().to_base64();
+    assert!(verify_event(&event, SignatureMode::LegacyMigration).is_err());
+}
+
+#[test]
+fn unsigned_event_depends_on_mode() {
+    let event = Event::entity_created(EntityId::new(), PeerId::new(), "note", "{}");
+    assert_eq!(
+        verify_event(&event, SignatureMode::LegacyMigration).unwrap(),
+        Authorship::Unsigned
+    );
+    assert!(verify_event(&event, SignatureMode::Required).is_err());
+}
+
+#[test]
+fn sealed_redacted_copy_verifies_for_the_redactor() {
+    let (author, relay) = (IdentityKeyPair::generate(), IdentityKeyPair::generate());
+    let relay_peer = PeerId::new();
+    let mut event = signed_event(&author);
+    event.payload = EventPayload::EntityCreated {
+        entity_type: "note".into(),
+        json_data: "{}".into(),
+    };
+    event.redacted_paths = vec!["/t".into()];
+    relay.seal_redaction(relay_peer, &mut event);
+    assert!(!event.is_signed());
+    assert_eq!(
+        verify_event(&event, SignatureMode::Required).unwrap(),
+        Authorship::Redacted(relay.public_key())
+    );
+
+    // The seal is bound to the redactor, the event ID and the redacted payload.
+    let mut forged = event.clone();
+    forged.redaction_seal.as_mut().unwrap().redactor = PeerId::new();
+    assert!(verify_event(&forged, SignatureMode::LegacyMigration).is_err());
+    let mut forged = event.clone();
+    forged.id = EventId::new();
+    assert!(verify_event(&forged, SignatureMode::LegacyMigration).is_err());
+    let mut forged = event.clone();
+    forged.redacted_paths.clear();
+    assert!(verify_event(&forged, SignatureMode::LegacyMigration).is_err());
+}
+
+#[test]
+fn identity_key_roundtrip() {
+    let key = IdentityKeyPair::generate();
+    let restored = IdentityKeyPair::from_secret_bytes(key.secret_bytes().as_slice()).unwrap();
+    assert_eq!(restored.public_key(), key.public_key());
+
+    let encoded = key.public_key().to_base64();
+    assert_eq!(IdentityPublicKey::from_base64(&encoded).unwrap(), key.public_key());
+}
+
+#[test]
+fn identity_key_rejects_wrong_length() {
+    assert!(matches!(
+        IdentityKeyPair::from_secret_bytes(&[0u8; 16]),
+        Err(CryptoError::InvalidKeyLength { expected: 32, actual: 16 })
+    ));
+}
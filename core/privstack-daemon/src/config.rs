@@ -0,0 +1,230 @@
+//! Daemon configuration and command-line parsing.
+//!
+//! The daemon shares the on-disk layout of the desktop app: every store lives
+//! next to a base database path (`data.vault.duckdb`, `data.entities.duckdb`,
+//! `data.events.duckdb`, `data.peer_id`, `data.keypair`). Pairing state, which
+//! the desktop app persists through its own settings, is kept in
+//! `data.pairing.json`.
+
+use crate::error::{DaemonError, DaemonResult};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Where the master password comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeySource {
+    /// Read the password from a file (trailing newline stripped).
+    KeyFile(PathBuf),
+    /// Prompt for the password on the controlling terminal.
+    Prompt,
+}
+
+/// Runtime configuration for the sync daemon.
+#[derive(Debug, Clone)]
+pub struct DaemonConfig {
+    /// Base database path. Store files are derived from it by extension.
+    pub db_path: PathBuf,
+    /// How to obtain the master password.
+    pub key_source: KeySource,
+    /// Path of the local control socket.
+    pub socket_path: PathBuf,
+    /// Device name announced to peers.
+    pub device_name: String,
+    /// libp2p listen addresses. Empty uses the transport defaults.
+    pub listen_addrs: Vec<String>,
+    /// Enable mDNS discovery on the local network.
+    pub enable_mdns: bool,
+    /// Enable Kademlia DHT discovery (only active while a sync code is set).
+    pub enable_dht: bool,
+    /// Interval between periodic syncs with known peers.
+    pub sync_interval: Duration,
+    /// Initialize the vault with the supplied password if it does not exist yet.
+    pub init_vault: bool,
+    /// Reject unsigned events instead of accepting them from pre-signing devices.
+    pub require_signatures: bool,
+}
+
+impl DaemonConfig {
+    /// Creates a configuration with defaults for the given database path.
+    pub fn new(db_path: impl Into<PathBuf>) -> Self {
+        let db_path = db_path.into();
+        let socket_path = db_path.with_extension("sock");
+        Self {
+            db_path,
+            key_source: KeySource::Prompt,
+            socket_path,
+            device_name: default_device_name(),
+            listen_addrs: Vec::new(),
+            enable_mdns: true,
+            enable_dht: true,
+            sync_interval: Duration::from_secs(30),
+            init_vault: false,
+            require_signatures: false,
+        }
+    }
+
+    /// Parses `run` options (everything after the subcommand).
+    pub fn from_args<I, S>(args: I) -> DaemonResult<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut db_path: Option<PathBuf> = None;
+        let mut key_source = KeySource::Prompt;
+        let mut socket_path: Option<PathBuf> = None;
+        let mut device_name: Option<String> = None;
+        let mut listen_addrs = Vec::new();
+        let mut enable_mdns = true;
+        let mut enable_dht = true;
+        let mut sync_interval: Option<Duration> = None;
+        let mut init_vault = false;
+        let mut require_signatures = false;
+
+        let mut args = args.into_iter().map(Into::into);
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| DaemonError::Config(format!("{name} requires a value")))
+            };
+            match arg.as_str() {
+                "--db" => db_path = Some(PathBuf::from(value("--db")?)),
+                "--keyfile" => key_source = KeySource::KeyFile(PathBuf::from(value("--keyfile")?)),
+                "--socket" => socket_path = Some(PathBuf::from(value("--socket")?)),
+                "--device-name" => device_name = Some(value("--device-name")?),
+                "--listen" => listen_addrs.push(value("--listen")?),
+                "--sync-interval" => {
+                    let raw = value("--sync-interval")?;
+                    let secs: u64 = raw.parse().map_err(|_| {
+                        DaemonError::Config(format!("invalid --sync-interval: {raw}"))
+                    })?;
+                    if secs == 0 {
+                        return Err(DaemonError::Config("--sync-interval must be positive".into()));
+                    }
+                    sync_interval = Some(Duration::from_secs(secs));
+                }
+                "--no-mdns" => enable_mdns = false,
+                "--no-dht" => enable_dht = false,
+                "--init" => init_vault = true,
+                "--require-signatures" => require_signatures = true,
+                other => return Err(DaemonError::Config(format!("unknown option: {other}"))),
+            }
+        }
+
+        let db_path = db_path.ok_or_else(|| DaemonError::Config("--db is required".into()))?;
+        let mut config = Self::new(db_path);
+        config.key_source = key_source;
+        if let Some(p) = socket_path {
+            config.socket_path = p;
+        }
+        if let Some(name) = device_name {
+            config.device_name = name;
+        }
+        if let Some(interval) = sync_interval {
+            config.sync_interval = interval;
+        }
+        config.listen_addrs = listen_addrs;
+        config.enable_mdns = enable_mdns;
+        config.enable_dht = enable_dht;
+        config.init_vault = init_vault;
+        config.require_signatures = require_signatures;
+        Ok(config)
+    }
+
+    /// Returns the path of a store file derived from the base database path.
+    pub fn store_path(&self, extension: &str) -> PathBuf {
+        self.db_path.with_extension(extension)
+    }
+
+    /// Returns the path where pairing state is persisted.
+    pub fn pairing_state_path(&self) -> PathBuf {
+        self.store_path("pairing.json")
+    }
+}
+
+/// Reads the master password according to the configured key source.
+pub fn read_password(source: &KeySource) -> DaemonResult<String> {
+    match source {
+        KeySource::KeyFile(path) => read_keyfile(path),
+        KeySource::Prompt => prompt_password("PrivStack master password: "),
+    }
+}
+
+/// Reads a password from a key file. Only the trailing line ending is
+/// stripped so passwords with inner or leading whitespace survive.
+pub fn read_keyfile(path: &Path) -> DaemonResult<String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| DaemonError::Key(format!("{}: {e}", path.display())))?;
+    let password = contents.trim_end_matches(['\r', '\n']).to_string();
+    if password.is_empty() {
+        return Err(DaemonError::Key(format!("{} is empty", path.display())));
+    }
+    Ok(password)
+}
+
+/// Prompts for a password on stdin with terminal echo disabled.
+fn prompt_password(prompt: &str) -> DaemonResult<String> {
+    use std::io::{BufRead, Write};
+
+    eprint!("{prompt}");
+    std::io::stderr().flush()?;
+
+    let _echo = EchoGuard::disable();
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    drop(_echo);
+    eprintln!();
+
+    let password = line.trim_end_matches(['\r', '\n']).to_string();
+    if password.is_empty() {
+        return Err(DaemonError::Key("no password entered".into()));
+    }
+    Ok(password)
+}
+
+/// Disables terminal echo on stdin for its lifetime (no-op when stdin is not a TTY).
+struct EchoGuard {
+    #[cfg(unix)]
+    original: Option<libc::termios>,
+}
+
+impl EchoGuard {
+    #[cfg(unix)]
+    fn disable() -> Self {
+        // SAFETY: tcgetattr/tcsetattr only read and write the termios struct we own.
+        unsafe {
+            let mut term: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut term) != 0 {
+                return Self { original: None };
+            }
+            let original = term;
+            term.c_lflag &= !libc::ECHO;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &term) != 0 {
+                return Self { original: None };
+            }
+            Self { original: Some(original) }
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn disable() -> Self {
+        Self {}
+    }
+}
+
+impl Drop for EchoGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(original) = self.original {
+            // SAFETY: restores the attributes captured in `disable`.
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &original);
+            }
+        }
+    }
+}
+
+fn default_device_name() -> String {
+    hostname::get()
+        .map(|h| format!("{} (daemon)", h.to_string_lossy()))
+        .unwrap_or_else(|_| "PrivStack Daemon".to_string())
+}
@@ -0,0 +1,204 @@
+//! Local control socket.
+//!
+//! A Unix domain socket speaking line-delimited JSON: each line is one
+//! `ControlRequest`, answered by exactly one `ControlResponse` line. The
+//! socket is created with owner-only permissions; anyone who can connect can
+//! approve pairing requests, so it must never be exposed beyond the local user.
+
+use crate::error::{DaemonError, DaemonResult};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tracing::{debug, warn};
+
+/// Maximum accepted size of a single request line.
+const MAX_REQUEST_BYTES: usize = 64 * 1024;
+
+/// A request sent to the daemon over the control socket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum ControlRequest {
+    /// Daemon and per-peer sync status.
+    Status,
+    /// Trusted peers.
+    Peers,
+    /// Generate a new sync code and start pairing with it.
+    PairingGenerate,
+    /// Join an existing sync code shown on another device.
+    PairingJoin { code: String },
+    /// Show the active sync code, if any.
+    PairingCode,
+    /// Stop pairing: clear the sync code and pending discoveries.
+    PairingClear,
+    /// Peers discovered with the active sync code, awaiting approval.
+    PairingDiscovered,
+    /// Approve a discovered peer.
+    PairingApprove { peer_id: String },
+    /// Reject a discovered peer.
+    PairingReject { peer_id: String },
+    /// Remove a trusted peer.
+    PairingRemove { peer_id: String },
+    /// Trigger an immediate sync with one peer, or all trusted peers.
+    SyncNow {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        peer_id: Option<String>,
+    },
+    /// Stop the daemon.
+    Shutdown,
+}
+
+/// The daemon's answer to a `ControlRequest`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ControlResponse {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ControlResponse {
+    pub fn ok() -> Self {
+        Self {
+            ok: true,
+            data: None,
+            error: None,
+        }
+    }
+
+    pub fn with_data(data: impl Serialize) -> Self {
+        match serde_json::to_value(data) {
+            Ok(value) => Self {
+                ok: true,
+                data: Some(value),
+                error: None,
+            },
+            Err(e) => Self::error(format!("failed to serialize response: {e}")),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            data: None,
+            error: Some(message.into()),
+        }
+    }
+}
+
+/// Handles control requests on behalf of the daemon.
+pub trait ControlHandler: Send + Sync + 'static {
+    fn handle(&self, request: ControlRequest) -> impl Future<Output = ControlResponse> + Send;
+}
+
+/// Binds the control socket, replacing a stale socket file left by a previous run.
+pub fn bind(path: &Path) -> DaemonResult<UnixListener> {
+    if path.exists() {
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(DaemonError::Control(format!(
+                "another daemon is already listening on {}",
+                path.display()
+            )));
+        }
+        std::fs::remove_file(path)?;
+    }
+    // Bind inside an owner-only directory and make the socket owner-only
+    // before moving it into place, so no other local user can connect in
+    // between. The process umask is left alone.
+    let staging = path.with_extension(format!("sock.{}", std::process::id()));
+    std::fs::DirBuilder::new().mode(0o700).create(&staging)?;
+    let result = bind_staged(path, &staging);
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+fn bind_staged(path: &Path, staging: &Path) -> DaemonResult<UnixListener> {
+    let staged = staging.join("control.sock");
+    let listener = UnixListener::bind(&staged)?;
+    std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+    std::fs::rename(&staged, path)?;
+    Ok(listener)
+}
+
+/// Accepts control connections until the listener fails.
+/// Each connection is served on its own task.
+pub async fn serve<H: ControlHandler>(listener: UnixListener, handler: Arc<H>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve_connection(stream, handler).await {
+                        debug!("[DAEMON] Control connection closed: {}", e);
+                    }
+                });
+            }
+            Err(e) => {
+                warn!("[DAEMON] Control socket accept failed: {}", e);
+                return;
+            }
+        }
+    }
+}
+
+async fn serve_connection<H: ControlHandler>(stream: UnixStream, handler: Arc<H>) -> DaemonResult<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        let n = (&mut reader)
+            .take(MAX_REQUEST_BYTES as u64 + 1)
+            .read_line(&mut line)
+            .await?;
+        if n == 0 {
+            return Ok(());
+        }
+        if n > MAX_REQUEST_BYTES {
+            write_line(&mut writer, &ControlResponse::error("request too large")).await?;
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<ControlRequest>(&line) {
+            Ok(request) => handler.handle(request).await,
+            Err(e) => ControlResponse::error(format!("invalid request: {e}")),
+        };
+        write_line(&mut writer, &response).await?;
+    }
+}
+
+async fn write_line<W, T>(writer: &mut W, value: &T) -> DaemonResult<()>
+where
+    W: AsyncWriteExt + Unpin,
+    T: Serialize,
+{
+    let mut bytes = serde_json::to_vec(value)?;
+    bytes.push(b'\n');
+    writer.write_all(&bytes).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Sends a single request to a running daemon and waits for the response.
+pub async fn send_request(path: &Path, request: &ControlRequest) -> DaemonResult<ControlResponse> {
+    let stream = UnixStream::connect(path).await.map_err(|e| {
+        DaemonError::Control(format!("cannot connect to {}: {e}", path.display()))
+    })?;
+    let (reader, mut writer) = stream.into_split();
+    write_line(&mut writer, request).await?;
+
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+    if line.is_empty() {
+        return Err(DaemonError::Control("daemon closed the connection".into()));
+    }
+    Ok(serde_json::from_str(&line)?)
+}
@@ -0,0 +1,492 @@
+//! Daemon lifecycle: open stores, unlock, run sync, serve the control socket.
+//!
+//! The daemon is an ordinary personal-sync peer that never goes offline. It
+//! applies every event it receives to its own stores, so devices that are
+//! never online at the same time still converge through it: each one syncs
+//! with the daemon, and the daemon forwards what it learned on the next cycle.
+
+use crate::config::DaemonConfig;
+use crate::control::{self, ControlHandler, ControlRequest, ControlResponse};
+use crate::error::{DaemonError, DaemonResult};
+use crate::identity::{
+    load_or_create_identity_key, load_or_create_keypair, load_or_create_peer_id,
+};
+use crate::status::DaemonStatus;
+use privstack_crypto::{DataEncryptor, IdentityKeyPair, SignatureMode};
+use privstack_storage::{EntityStore, EventStore};
+use privstack_sync::{
+    create_personal_orchestrator, OrchestratorConfig, OrchestratorHandle, P2pConfig,
+    P2pTransport, PairingManager, PersonalSyncPolicy, SyncCode, SyncCommand, SyncEvent,
+    SyncTransport,
+};
+use privstack_types::PeerId;
+use privstack_vault::{VaultError, VaultManager};
+use serde_json::json;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex as TokioMutex};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// How often pairing state (last-synced times, device names) is flushed to disk.
+const PAIRING_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Vault that holds the master key, matching the desktop app.
+const DEFAULT_VAULT_ID: &str = "default";
+
+/// Lifecycle signals sent from the control socket to the run loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DaemonSignal {
+    /// Restart the transport, e.g. after the sync code changed.
+    Restart,
+    /// Stop the daemon.
+    Shutdown,
+}
+
+/// A headless PrivStack sync peer.
+pub struct Daemon {
+    config: DaemonConfig,
+    peer_id: PeerId,
+    /// Key this daemon signs the events it creates with.
+    identity_key: Arc<IdentityKeyPair>,
+    vault: Arc<VaultManager>,
+    entity_store: Arc<EntityStore>,
+    event_store: Arc<EventStore>,
+    pairing: Arc<Mutex<PairingManager>>,
+    status: Arc<Mutex<DaemonStatus>>,
+}
+
+impl Daemon {
+    /// Opens the stores under the configured database path. The vault stays
+    /// locked until `unlock` is called.
+    pub fn open(config: DaemonConfig) -> DaemonResult<Self> {
+        if let Some(parent) = config.db_path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let peer_id = load_or_create_peer_id(&config.db_path)?;
+        let identity_key = Arc::new(load_or_create_identity_key(&config.db_path)?);
+
+        let vault = Arc::new(VaultManager::open(&config.store_path("vault.duckdb"))?);
+        let entity_store = EntityStore::open_with_encryptor(
+            &config.store_path("entities.duckdb"),
+            vault.clone() as Arc<dyn DataEncryptor>,
+        )?;
+        let signature_mode = if config.require_signatures {
+            SignatureMode::Required
+        } else {
+            SignatureMode::LegacyMigration
+        };
+        let event_store = EventStore::open(&config.store_path("events.duckdb"))?
+            .with_signature_mode(signature_mode);
+        event_store.register_author_key(&peer_id, &identity_key.public_key())?;
+        let pairing = load_pairing_state(&config.pairing_state_path())?;
+        let status = DaemonStatus::new(peer_id, config.device_name.clone());
+
+        info!("[DAEMON] Opened stores at {} (peer {})", config.db_path.display(), peer_id);
+
+        Ok(Self {
+            config,
+            peer_id,
+            identity_key,
+            vault,
+            entity_store: Arc::new(entity_store),
+            event_store: Arc::new(event_store),
+            pairing: Arc::new(Mutex::new(pairing)),
+            status: Arc::new(Mutex::new(status)),
+        })
+    }
+
+    /// Returns this daemon's PrivStack peer ID.
+    pub fn peer_id(&self) -> PeerId {
+        self.peer_id
+    }
+
+    /// Unlocks the vault with the master password. When `init_vault` is set
+    /// and no vault exists yet, it is created with this password instead.
+    pub fn unlock(&self, password: &str) -> DaemonResult<()> {
+        if !self.vault.is_initialized(DEFAULT_VAULT_ID) {
+            if !self.config.init_vault {
+                return Err(DaemonError::Config(
+                    "vault is not initialized; run with --init to create it".into(),
+                ));
+            }
+            self.vault.initialize(DEFAULT_VAULT_ID, password)?;
+            info!("[DAEMON] Initialized new vault");
+        } else {
+            self.vault.unlock_all(password).map_err(|e| match e {
+                VaultError::InvalidPassword => DaemonError::Key("incorrect master password".into()),
+                other => DaemonError::Vault(other),
+            })?;
+        }
+
+        let migrated = self.entity_store.migrate_unencrypted()?;
+        if migrated > 0 {
+            info!("[DAEMON] Encrypted {} legacy entities", migrated);
+        }
+        Ok(())
+    }
+
+    /// Runs until a shutdown request, SIGINT or SIGTERM.
+    pub async fn run(self) -> DaemonResult<()> {
+        let listener = control::bind(&self.config.socket_path)?;
+        info!("[DAEMON] Control socket at {}", self.config.socket_path.display());
+
+        let (signal_tx, mut signal_rx) = mpsc::channel(8);
+        let context = Arc::new(ControlContext {
+            pairing: self.pairing.clone(),
+            status: self.status.clone(),
+            entity_store: self.entity_store.clone(),
+            orchestrator: Mutex::new(None),
+            signals: signal_tx,
+            pairing_path: self.config.pairing_state_path(),
+        });
+        let control_task = tokio::spawn(control::serve(listener, context.clone()));
+
+        let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        let mut save_interval = tokio::time::interval(PAIRING_SAVE_INTERVAL);
+
+        let result = loop {
+            let mut session = match self.start_session().await {
+                Ok(s) => s,
+                Err(e) => break Err(e),
+            };
+            *context.orchestrator.lock().unwrap() = Some(session.handle.clone());
+
+            let restart = loop {
+                tokio::select! {
+                    Some(signal) = signal_rx.recv() => {
+                        break signal == DaemonSignal::Restart;
+                    }
+                    Some(event) = session.event_rx.recv() => {
+                        self.status.lock().unwrap().apply_sync_event(&event);
+                    }
+                    _ = save_interval.tick() => {
+                        self.save_pairing_state();
+                    }
+                    _ = tokio::signal::ctrl_c() => {
+                        info!("[DAEMON] Interrupted");
+                        break false;
+                    }
+                    _ = sigterm.recv() => {
+                        info!("[DAEMON] Terminated");
+                        break false;
+                    }
+                }
+            };
+
+            *context.orchestrator.lock().unwrap() = None;
+            session.stop().await;
+            self.status.lock().unwrap().sync_running = false;
+            self.save_pairing_state();
+
+            if !restart {
+                break Ok(());
+            }
+            info!("[DAEMON] Restarting sync");
+        };
+
+        control_task.abort();
+        let _ = std::fs::remove_file(&self.config.socket_path);
+        self.vault.lock_all();
+        info!("[DAEMON] Stopped");
+        result
+    }
+
+    async fn start_session(&self) -> DaemonResult<SyncSession> {
+        let mut p2p = P2pConfig {
+            device_name: self.config.device_name.clone(),
+            enable_mdns: self.config.enable_mdns,
+            enable_dht: self.config.enable_dht,
+            ..P2pConfig::default()
+        };
+        if !self.config.listen_addrs.is_empty() {
+            p2p.listen_addrs = self
+                .config
+                .listen_addrs
+                .iter()
+                .map(|a| {
+                    a.parse()
+                        .map_err(|e| DaemonError::Config(format!("invalid listen address {a}: {e}")))
+                })
+                .collect::<DaemonResult<_>>()?;
+        }
+        if let Some(code) = self.pairing.lock().unwrap().current_code()
+            && let Ok(hash) = hex::decode(&code.hash)
+        {
+            p2p.sync_code_hash = Some(hash);
+        }
+
+        let keypair = load_or_create_keypair(&self.config.db_path)?;
+        let mut transport = P2pTransport::with_keypair(self.peer_id, keypair, p2p)?;
+        let libp2p_peer_id = transport.libp2p_peer_id().to_string();
+        transport.start().await?;
+        let transport = Arc::new(TokioMutex::new(transport));
+
+        let orch_config = OrchestratorConfig {
+            sync_interval: self.config.sync_interval,
+            ..OrchestratorConfig::default()
+        };
+        let policy = PersonalSyncPolicy::new().with_event_store(self.event_store.clone());
+        // Apply the sync scopes saved with the pairing state
+        let scopes: Vec<_> = self
+            .pairing
+            .lock()
+            .unwrap()
+            .sync_scopes()
+            .iter()
+            .map(|(pid, scope)| (pid.clone(), scope.clone()))
+            .collect();
+        for (pid, scope) in scopes {
+            if let Ok(pid) = pid.parse::<PeerId>() {
+                policy.set_sync_scope(pid, scope).await;
+            }
+        }
+        let (handle, event_rx, command_rx, mut orchestrator) = create_personal_orchestrator(
+            self.peer_id,
+            self.entity_store.clone(),
+            self.event_store.clone(),
+            orch_config,
+            Arc::new(policy),
+            self.pairing.clone(),
+        );
+        orchestrator.set_identity_key(self.identity_key.clone());
+
+        let orch_transport = transport.clone();
+        let task = tokio::spawn(async move {
+            if let Err(e) = orchestrator.run(orch_transport, command_rx).await {
+                warn!("[DAEMON] Orchestrator error: {}", e);
+            }
+        });
+
+        {
+            let mut status = self.status.lock().unwrap();
+            status.sync_running = true;
+            status.libp2p_peer_id = Some(libp2p_peer_id.clone());
+        }
+        info!("[DAEMON] Sync running (libp2p peer {})", libp2p_peer_id);
+
+        Ok(SyncSession {
+            transport,
+            handle,
+            event_rx,
+            task,
+        })
+    }
+
+    fn save_pairing_state(&self) {
+        let pm = self.pairing.lock().unwrap();
+        if let Err(e) = save_pairing_state(&self.config.pairing_state_path(), &pm) {
+            warn!("[DAEMON] Failed to save pairing state: {}", e);
+        }
+    }
+}
+
+/// A running transport + orchestrator pair.
+struct SyncSession {
+    transport: Arc<TokioMutex<P2pTransport>>,
+    handle: OrchestratorHandle,
+    event_rx: mpsc::Receiver<SyncEvent>,
+    task: JoinHandle<()>,
+}
+
+impl SyncSession {
+    async fn stop(self) {
+        let _ = self.handle.shutdown().await;
+        let _ = self.task.await;
+        if let Err(e) = self.transport.lock().await.stop().await {
+            warn!("[DAEMON] Failed to stop transport: {}", e);
+        }
+    }
+}
+
+/// Control socket request handler.
+struct ControlContext {
+    pairing: Arc<Mutex<PairingManager>>,
+    status: Arc<Mutex<DaemonStatus>>,
+    entity_store: Arc<EntityStore>,
+    orchestrator: Mutex<Option<OrchestratorHandle>>,
+    signals: mpsc::Sender<DaemonSignal>,
+    pairing_path: std::path::PathBuf,
+}
+
+impl ControlContext {
+    /// Persists pairing state after a mutating request.
+    fn persist(&self) -> Result<(), ControlResponse> {
+        let pm = self.pairing.lock().unwrap();
+        save_pairing_state(&self.pairing_path, &pm)
+            .map_err(|e| ControlResponse::error(format!("failed to save pairing state: {e}")))
+    }
+
+    async fn restart_sync(&self) {
+        let _ = self.signals.send(DaemonSignal::Restart).await;
+    }
+
+    async fn sync_with(&self, peers: Vec<PeerId>) -> ControlResponse {
+        let handle = self.orchestrator.lock().unwrap().clone();
+        let Some(handle) = handle else {
+            return ControlResponse::error("sync is not running");
+        };
+        for peer_id in &peers {
+            if handle
+                .send(SyncCommand::SyncWithPeer { peer_id: *peer_id })
+                .await
+                .is_err()
+            {
+                return ControlResponse::error("sync is not running");
+            }
+        }
+        ControlResponse::with_data(json!({ "peers": peers.len() }))
+    }
+
+    async fn status(&self) -> ControlResponse {
+        let store = self.entity_store.clone();
+        let entity_count = tokio::task::spawn_blocking(move || store.list_all_entity_ids())
+            .await
+            .ok()
+            .and_then(Result::ok)
+            .map(|ids| ids.len());
+
+        let status = self.status.lock().unwrap().clone();
+        let pm = self.pairing.lock().unwrap();
+        ControlResponse::with_data(json!({
+            "daemon": status,
+            "entity_count": entity_count,
+            "trusted_peers": pm.trusted_peers().len(),
+            "pairing_active": pm.current_code().is_some(),
+        }))
+    }
+}
+
+impl ControlHandler for ControlContext {
+    async fn handle(&self, request: ControlRequest) -> ControlResponse {
+        match request {
+            ControlRequest::Status => self.status().await,
+            ControlRequest::Peers => {
+                let pm = self.pairing.lock().unwrap();
+                ControlResponse::with_data(pm.trusted_peers())
+            }
+            ControlRequest::PairingGenerate => {
+                let code = SyncCode::generate();
+                self.pairing.lock().unwrap().set_sync_code(code.clone());
+                if let Err(resp) = self.persist() {
+                    return resp;
+                }
+                self.restart_sync().await;
+                ControlResponse::with_data(json!({ "code": code.code }))
+            }
+            ControlRequest::PairingJoin { code } => {
+                let code = match SyncCode::from_input(&code) {
+                    Ok(c) => c,
+                    Err(e) => return ControlResponse::error(e.to_string()),
+                };
+                self.pairing.lock().unwrap().set_sync_code(code.clone());
+                if let Err(resp) = self.persist() {
+                    return resp;
+                }
+                self.restart_sync().await;
+                ControlResponse::with_data(json!({ "code": code.code }))
+            }
+            ControlRequest::PairingCode => {
+                let pm = self.pairing.lock().unwrap();
+                ControlResponse::with_data(json!({
+                    "code": pm.current_code().map(|c| c.code.clone()),
+                }))
+            }
+            ControlRequest::PairingClear => {
+                self.pairing.lock().unwrap().clear_sync_code();
+                if let Err(resp) = self.persist() {
+                    return resp;
+                }
+                self.restart_sync().await;
+                ControlResponse::ok()
+            }
+            ControlRequest::PairingDiscovered => {
+                let pm = self.pairing.lock().unwrap();
+                ControlResponse::with_data(pm.discovered_peers())
+            }
+            ControlRequest::PairingApprove { peer_id } => {
+                let approved = self.pairing.lock().unwrap().approve_peer(&peer_id);
+                let Some(trusted) = approved else {
+                    return ControlResponse::error(format!("no discovered peer {peer_id}"));
+                };
+                if let Err(resp) = self.persist() {
+                    return resp;
+                }
+                if let Ok(pid) = peer_id.parse::<PeerId>() {
+                    let _ = self.sync_with(vec![pid]).await;
+                }
+                ControlResponse::with_data(trusted)
+            }
+            ControlRequest::PairingReject { peer_id } => {
+                self.pairing.lock().unwrap().reject_peer(&peer_id);
+                if let Err(resp) = self.persist() {
+                    return resp;
+                }
+                ControlResponse::ok()
+            }
+            ControlRequest::PairingRemove { peer_id } => {
+                let removed = {
+                    let mut pm = self.pairing.lock().unwrap();
+                    let trusted = pm.is_trusted(&peer_id);
+                    pm.remove_trusted_peer(&peer_id);
+                    trusted
+                };
+                if !removed {
+                    return ControlResponse::error(format!("{peer_id} is not a trusted peer"));
+                }
+                if let Err(resp) = self.persist() {
+                    return resp;
+                }
+                ControlResponse::ok()
+            }
+            ControlRequest::SyncNow { peer_id } => {
+                let peers = match peer_id {
+                    Some(id) => match id.parse::<PeerId>() {
+                        Ok(pid) => vec![pid],
+                        Err(_) => return ControlResponse::error(format!("invalid peer id {id}")),
+                    },
+                    None => self
+                        .pairing
+                        .lock()
+                        .unwrap()
+                        .trusted_peers()
+                        .iter()
+                        .filter_map(|p| p.peer_id.parse().ok())
+                        .collect(),
+                };
+                self.sync_with(peers).await
+            }
+            ControlRequest::Shutdown => {
+                let _ = self.signals.send(DaemonSignal::Shutdown).await;
+                ControlResponse::ok()
+            }
+        }
+    }
+}
+
+/// Loads pairing state, starting empty when the file does not exist yet.
+fn load_pairing_state(path: &Path) -> DaemonResult<PairingManager> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => Ok(PairingManager::from_json(&json)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(PairingManager::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Writes pairing state atomically with owner-only permissions.
+fn save_pairing_state(path: &Path, pm: &PairingManager) -> DaemonResult<()> {
+    let json = pm.to_json()?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+    }
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
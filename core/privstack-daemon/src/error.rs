@@ -0,0 +1,34 @@
+//! Daemon error types.
+
+use thiserror::Error;
+
+/// Result type for daemon operations.
+pub type DaemonResult<T> = Result<T, DaemonError>;
+
+/// Errors that can occur while configuring or running the daemon.
+#[derive(Debug, Error)]
+pub enum DaemonError {
+    #[error("invalid configuration: {0}")]
+    Config(String),
+
+    #[error("failed to read key: {0}")]
+    Key(String),
+
+    #[error("storage error: {0}")]
+    Storage(#[from] privstack_storage::StorageError),
+
+    #[error("vault error: {0}")]
+    Vault(#[from] privstack_vault::VaultError),
+
+    #[error("sync error: {0}")]
+    Sync(#[from] privstack_sync::SyncError),
+
+    #[error("control protocol error: {0}")]
+    Control(String),
+
+    #[error("serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
@@ -0,0 +1,98 @@
+//! Persistent device identity (PrivStack peer ID, libp2p keypair and event
+//! signing key).
+//!
+//! Uses the same `<db>.peer_id` / `<db>.keypair` / `<db>.identity_key` files
+//! as the FFI layer, so a daemon pointed at an existing data directory keeps
+//! the identity its peers already trust.
+
+use crate::error::{DaemonError, DaemonResult};
+use privstack_crypto::IdentityKeyPair;
+use privstack_sync::Keypair;
+use privstack_types::PeerId;
+use std::path::Path;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Loads the peer ID stored next to the database, or generates and saves a new one.
+pub fn load_or_create_peer_id(db_path: &Path) -> DaemonResult<PeerId> {
+    let path = db_path.with_extension("peer_id");
+
+    if let Ok(contents) = std::fs::read_to_string(&path) {
+        if let Ok(uuid) = Uuid::parse_str(contents.trim()) {
+            return Ok(PeerId::from_uuid(uuid));
+        }
+        warn!("[DAEMON] Corrupt peer_id file at {}, generating new one", path.display());
+    }
+
+    let peer_id = PeerId::new();
+    std::fs::write(&path, peer_id.to_string())?;
+    info!("[DAEMON] Generated new peer ID {peer_id}");
+    Ok(peer_id)
+}
+
+/// Loads the libp2p keypair stored next to the database, or generates and saves a new one.
+/// A keypair file that cannot be read or decoded is an error: peers know
+/// this device by it, so it is never silently replaced.
+pub fn load_or_create_keypair(db_path: &Path) -> DaemonResult<Keypair> {
+    let path = db_path.with_extension("keypair");
+
+    if let Some(bytes) = read_existing(&path)? {
+        return Keypair::from_protobuf_encoding(&bytes).map_err(|e| {
+            DaemonError::Key(format!("corrupt keypair file at {}: {e}", path.display()))
+        });
+    }
+
+    let keypair = Keypair::generate_ed25519();
+    let bytes = keypair
+        .to_protobuf_encoding()
+        .map_err(|e| DaemonError::Key(format!("failed to encode keypair: {e}")))?;
+    write_private(&path, &bytes)?;
+    info!("[DAEMON] Generated new libp2p keypair at {}", path.display());
+    Ok(keypair)
+}
+
+/// Loads the Ed25519 key this device signs its events with, or generates and
+/// saves a new one. A key file that cannot be read or decoded is an error:
+/// the existing key is registered for this peer, and events signed by a
+/// replacement would fail verification.
+pub fn load_or_create_identity_key(db_path: &Path) -> DaemonResult<IdentityKeyPair> {
+    let path = db_path.with_extension("identity_key");
+
+    if let Some(bytes) = read_existing(&path)? {
+        return IdentityKeyPair::from_secret_bytes(&bytes).map_err(|e| {
+            DaemonError::Key(format!("corrupt identity key file at {}: {e}", path.display()))
+        });
+    }
+
+    let key = IdentityKeyPair::generate();
+    write_private(&path, key.secret_bytes().as_slice())?;
+    info!("[DAEMON] Generated new identity key at {}", path.display());
+    Ok(key)
+}
+
+/// Reads a key file, or returns `None` if there is none yet.
+fn read_existing(path: &Path) -> DaemonResult<Option<Vec<u8>>> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(DaemonError::Key(format!("failed to read {}: {e}", path.display()))),
+    }
+}
+
+/// Creates a new file readable only by the current user. The mode is set
+/// when the file is created, so the secret is never readable by others; an
+/// existing file is not overwritten.
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
@@ -0,0 +1,22 @@
+//! Headless always-on sync daemon for PrivStack.
+//!
+//! Runs the same personal P2P sync stack as the desktop app (`SyncOrchestrator`
+//! + `P2pTransport` + pairing gate) without a UI, so a home server can act as
+//! an always-online peer that laptops and phones converge through.
+//!
+//! The daemon is managed through a local control socket; see [`control`].
+
+#![cfg(unix)]
+
+pub mod config;
+pub mod control;
+mod daemon;
+pub mod error;
+pub mod identity;
+pub mod status;
+
+pub use config::{DaemonConfig, KeySource};
+pub use control::{ControlRequest, ControlResponse};
+pub use daemon::Daemon;
+pub use error::{DaemonError, DaemonResult};
+pub use status::{DaemonStatus, PeerActivity};
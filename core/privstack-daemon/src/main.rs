@@ -0,0 +1,164 @@
+//! `privstackd` — headless PrivStack sync daemon.
+//!
+//! ```text
+//! privstackd run --db <path> [--keyfile <file>] [--socket <path>] [--device-name <name>]
+//!                [--listen <multiaddr>]... [--sync-interval <secs>] [--no-mdns] [--no-dht] [--init]
+//!                [--require-signatures]
+//! privstackd ctl [--socket <path> | --db <path>] <command> [args]
+//! ```
+
+#[cfg(unix)]
+fn main() -> std::process::ExitCode {
+    use std::process::ExitCode;
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("run") => cli::run(&args[1..]),
+        Some("ctl") => cli::ctl(&args[1..]),
+        Some("-h") | Some("--help") | Some("help") => {
+            print!("{}", cli::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Some("-V") | Some("--version") => {
+            println!("privstackd {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        _ => {
+            eprint!("{}", cli::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("privstackd: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("privstackd is only supported on Unix platforms");
+    std::process::exit(1);
+}
+
+#[cfg(unix)]
+mod cli {
+    use privstack_daemon::config::read_password;
+    use privstack_daemon::control::send_request;
+    use privstack_daemon::{ControlRequest, Daemon, DaemonConfig, DaemonError, DaemonResult};
+    use std::path::PathBuf;
+    use std::process::ExitCode;
+
+    pub const USAGE: &str = "\
+Usage:
+  privstackd run --db <path> [options]
+      --keyfile <file>        read the master password from <file> instead of prompting
+      --socket <path>         control socket path (default: <db>.sock)
+      --device-name <name>    device name announced to peers
+      --listen <multiaddr>    libp2p listen address (repeatable)
+      --sync-interval <secs>  periodic sync interval (default: 30)
+      --no-mdns               disable local network discovery
+      --no-dht                disable DHT discovery
+      --init                  create the vault if it does not exist
+      --require-signatures    reject events that are not signed by their author
+
+  privstackd ctl [--socket <path> | --db <path>] <command>
+      status | peers | sync [<peer-id>] | shutdown
+      pairing generate | join <code> | code | clear
+      pairing discovered | approve <peer-id> | reject <peer-id> | remove <peer-id>
+";
+
+    pub fn run(args: &[String]) -> DaemonResult<ExitCode> {
+        let config = DaemonConfig::from_args(args.iter().cloned())?;
+
+        tracing_subscriber::fmt()
+            .with_env_filter(
+                tracing_subscriber::EnvFilter::try_from_default_env()
+                    .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+            )
+            .init();
+
+        let password = read_password(&config.key_source)?;
+        let daemon = Daemon::open(config)?;
+        daemon.unlock(&password)?;
+        drop(password);
+
+        let runtime = tokio::runtime::Runtime::new()?;
+        runtime.block_on(daemon.run())?;
+        Ok(ExitCode::SUCCESS)
+    }
+
+    pub fn ctl(args: &[String]) -> DaemonResult<ExitCode> {
+        let mut socket: Option<PathBuf> = None;
+        let mut rest = args;
+        while let Some(flag) = rest.first() {
+            match flag.as_str() {
+                "--socket" | "--db" => {
+                    let value = rest
+                        .get(1)
+                        .ok_or_else(|| DaemonError::Config(format!("{flag} requires a value")))?;
+                    socket = Some(if flag == "--db" {
+                        DaemonConfig::new(value).socket_path
+                    } else {
+                        PathBuf::from(value)
+                    });
+                    rest = &rest[2..];
+                }
+                _ => break,
+            }
+        }
+        let socket = socket
+            .ok_or_else(|| DaemonError::Config("ctl requires --socket or --db".into()))?;
+        let request = parse_command(rest)?;
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let response = runtime.block_on(send_request(&socket, &request))?;
+        println!("{}", serde_json::to_string_pretty(&response)?);
+        Ok(if response.ok {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        })
+    }
+
+    fn parse_command(args: &[String]) -> DaemonResult<ControlRequest> {
+        let words: Vec<&str> = args.iter().map(String::as_str).collect();
+        let request = match words.as_slice() {
+            ["status"] => ControlRequest::Status,
+            ["peers"] => ControlRequest::Peers,
+            ["sync"] => ControlRequest::SyncNow { peer_id: None },
+            ["sync", peer] => ControlRequest::SyncNow {
+                peer_id: Some(peer.to_string()),
+            },
+            ["shutdown"] => ControlRequest::Shutdown,
+            ["pairing", "generate"] => ControlRequest::PairingGenerate,
+            ["pairing", "join", code @ ..] if !code.is_empty() => ControlRequest::PairingJoin {
+                code: code.join(" "),
+            },
+            ["pairing", "code"] => ControlRequest::PairingCode,
+            ["pairing", "clear"] => ControlRequest::PairingClear,
+            ["pairing", "discovered"] => ControlRequest::PairingDiscovered,
+            ["pairing", "approve", peer] => ControlRequest::PairingApprove {
+                peer_id: peer.to_string(),
+            },
+            ["pairing", "reject", peer] => ControlRequest::PairingReject {
+                peer_id: peer.to_string(),
+            },
+            ["pairing", "remove", peer] => ControlRequest::PairingRemove {
+                peer_id: peer.to_string(),
+            },
+            _ => {
+                return Err(DaemonError::Config(format!(
+                    "unknown command: {}",
+                    words.join(" ")
+                )));
+            }
+        };
+        Ok(request)
+    }
+}
@@ -0,0 +1,100 @@
+//! Daemon status tracking, fed by the orchestrator's `SyncEvent` stream.
+
+use privstack_sync::SyncEvent;
+use privstack_types::PeerId;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Sync activity observed for a single peer since the daemon started.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PeerActivity {
+    pub device_name: Option<String>,
+    pub last_seen: Option<u64>,
+    pub last_sync_started: Option<u64>,
+    pub last_sync_completed: Option<u64>,
+    pub last_error: Option<String>,
+    pub syncs_completed: u64,
+    pub events_sent: u64,
+    pub events_received: u64,
+}
+
+/// Snapshot of daemon state reported over the control socket.
+#[derive(Debug, Clone, Serialize)]
+pub struct DaemonStatus {
+    pub peer_id: String,
+    pub device_name: String,
+    pub version: String,
+    pub started_at: u64,
+    /// Whether the P2P transport and orchestrator are currently running.
+    pub sync_running: bool,
+    /// libp2p peer ID of the running transport.
+    pub libp2p_peer_id: Option<String>,
+    /// Number of entity updates applied from peers.
+    pub entities_updated: u64,
+    pub peers: BTreeMap<String, PeerActivity>,
+}
+
+impl DaemonStatus {
+    pub fn new(peer_id: PeerId, device_name: impl Into<String>) -> Self {
+        Self {
+            peer_id: peer_id.to_string(),
+            device_name: device_name.into(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: now_secs(),
+            sync_running: false,
+            libp2p_peer_id: None,
+            entities_updated: 0,
+            peers: BTreeMap::new(),
+        }
+    }
+
+    /// Folds an orchestrator event into the status.
+    pub fn apply_sync_event(&mut self, event: &SyncEvent) {
+        let now = now_secs();
+        match event {
+            SyncEvent::PeerDiscovered { peer_id, device_name } => {
+                let peer = self.peer_mut(peer_id);
+                peer.last_seen = Some(now);
+                if device_name.is_some() {
+                    peer.device_name = device_name.clone();
+                }
+            }
+            SyncEvent::SyncStarted { peer_id } => {
+                let peer = self.peer_mut(peer_id);
+                peer.last_seen = Some(now);
+                peer.last_sync_started = Some(now);
+            }
+            SyncEvent::SyncCompleted {
+                peer_id,
+                events_sent,
+                events_received,
+            } => {
+                let peer = self.peer_mut(peer_id);
+                peer.last_seen = Some(now);
+                peer.last_sync_completed = Some(now);
+                peer.last_error = None;
+                peer.syncs_completed += 1;
+                peer.events_sent += *events_sent as u64;
+                peer.events_received += *events_received as u64;
+            }
+            SyncEvent::SyncFailed { peer_id, error } => {
+                self.peer_mut(peer_id).last_error = Some(error.clone());
+            }
+            SyncEvent::EntityUpdated { .. } => {
+                self.entities_updated += 1;
+            }
+        }
+    }
+
+    fn peer_mut(&mut self, peer_id: &PeerId) -> &mut PeerActivity {
+        self.peers.entry(peer_id.to_string()).or_default()
+    }
+}
+
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
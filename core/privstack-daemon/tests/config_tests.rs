@@ -0,0 +1,95 @@
+use privstack_daemon::config::read_keyfile;
+use privstack_daemon::{DaemonConfig, DaemonError, KeySource};
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn defaults_derive_from_db_path() {
+    let config = DaemonConfig::from_args(args(&["--db", "/srv/privstack/data"])).unwrap();
+    assert_eq!(config.db_path, PathBuf::from("/srv/privstack/data"));
+    assert_eq!(config.socket_path, PathBuf::from("/srv/privstack/data.sock"));
+    assert_eq!(config.key_source, KeySource::Prompt);
+    assert_eq!(
+        config.store_path("entities.duckdb"),
+        PathBuf::from("/srv/privstack/data.entities.duckdb")
+    );
+    assert_eq!(
+        config.pairing_state_path(),
+        PathBuf::from("/srv/privstack/data.pairing.json")
+    );
+    assert!(config.enable_mdns);
+    assert!(config.enable_dht);
+    assert!(!config.init_vault);
+    assert!(!config.require_signatures);
+}
+
+#[test]
+fn parses_all_options() {
+    let config = DaemonConfig::from_args(args(&[
+        "--db",
+        "data",
+        "--keyfile",
+        "/etc/privstack/key",
+        "--socket",
+        "/run/privstack.sock",
+        "--device-name",
+        "home-server",
+        "--listen",
+        "/ip4/0.0.0.0/udp/4001/quic-v1",
+        "--sync-interval",
+        "120",
+        "--no-mdns",
+        "--no-dht",
+        "--init",
+        "--require-signatures",
+    ]))
+    .unwrap();
+    assert_eq!(config.key_source, KeySource::KeyFile("/etc/privstack/key".into()));
+    assert_eq!(config.socket_path, PathBuf::from("/run/privstack.sock"));
+    assert_eq!(config.device_name, "home-server");
+    assert_eq!(config.listen_addrs, vec!["/ip4/0.0.0.0/udp/4001/quic-v1".to_string()]);
+    assert_eq!(config.sync_interval, Duration::from_secs(120));
+    assert!(!config.enable_mdns);
+    assert!(!config.enable_dht);
+    assert!(config.init_vault);
+    assert!(config.require_signatures);
+}
+
+#[test]
+fn rejects_missing_db_and_bad_values() {
+    assert!(matches!(
+        DaemonConfig::from_args(args(&["--no-mdns"])),
+        Err(DaemonError::Config(_))
+    ));
+    assert!(matches!(
+        DaemonConfig::from_args(args(&["--db"])),
+        Err(DaemonError::Config(_))
+    ));
+    assert!(matches!(
+        DaemonConfig::from_args(args(&["--db", "d", "--sync-interval", "0"])),
+        Err(DaemonError::Config(_))
+    ));
+    assert!(matches!(
+        DaemonConfig::from_args(args(&["--db", "d", "--bogus"])),
+        Err(DaemonError::Config(_))
+    ));
+}
+
+#[test]
+fn keyfile_strips_only_trailing_newline() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("key");
+    std::fs::write(&path, " secret pass \n").unwrap();
+    assert_eq!(read_keyfile(&path).unwrap(), " secret pass ");
+
+    std::fs::write(&path, "\n").unwrap();
+    assert!(matches!(read_keyfile(&path), Err(DaemonError::Key(_))));
+    assert!(matches!(
+        read_keyfile(&dir.path().join("missing")),
+        Err(DaemonError::Key(_))
+    ));
+}
@@ -0,0 +1,110 @@
+use privstack_daemon::control::{bind, send_request, serve, ControlHandler};
+use privstack_daemon::{ControlRequest, ControlResponse};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+/// Records requests and echoes them back.
+#[derive(Default)]
+struct EchoHandler {
+    seen: Mutex<Vec<ControlRequest>>,
+}
+
+impl ControlHandler for EchoHandler {
+    async fn handle(&self, request: ControlRequest) -> ControlResponse {
+        self.seen.lock().unwrap().push(request.clone());
+        match request {
+            ControlRequest::PairingApprove { .. } => ControlResponse::error("no discovered peer"),
+            other => ControlResponse::with_data(other),
+        }
+    }
+}
+
+#[test]
+fn request_wire_format() {
+    let json = serde_json::to_value(ControlRequest::PairingJoin {
+        code: "ALPHA-BRAVO-CHARLIE-DELTA".into(),
+    })
+    .unwrap();
+    assert_eq!(json, json!({"cmd": "pairing_join", "code": "ALPHA-BRAVO-CHARLIE-DELTA"}));
+
+    let json = serde_json::to_value(ControlRequest::SyncNow { peer_id: None }).unwrap();
+    assert_eq!(json, json!({"cmd": "sync_now"}));
+
+    let parsed: ControlRequest = serde_json::from_str(r#"{"cmd":"status"}"#).unwrap();
+    assert_eq!(parsed, ControlRequest::Status);
+}
+
+#[test]
+fn response_omits_empty_fields() {
+    let json = serde_json::to_string(&ControlResponse::ok()).unwrap();
+    assert_eq!(json, r#"{"ok":true}"#);
+    let err = ControlResponse::error("nope");
+    assert!(!err.ok);
+    assert_eq!(err.error.as_deref(), Some("nope"));
+}
+
+#[tokio::test]
+async fn socket_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ctl.sock");
+    let handler = Arc::new(EchoHandler::default());
+    let listener = bind(&path).unwrap();
+    let server = tokio::spawn(serve(listener, handler.clone()));
+
+    let resp = send_request(&path, &ControlRequest::Status).await.unwrap();
+    assert!(resp.ok);
+    assert_eq!(resp.data, Some(json!({"cmd": "status"})));
+
+    let resp = send_request(
+        &path,
+        &ControlRequest::PairingApprove {
+            peer_id: "p".into(),
+        },
+    )
+    .await
+    .unwrap();
+    assert!(!resp.ok);
+
+    assert_eq!(handler.seen.lock().unwrap().len(), 2);
+    server.abort();
+}
+
+#[tokio::test]
+async fn socket_is_owner_only_and_rejects_garbage() {
+    use std::os::unix::fs::PermissionsExt;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ctl.sock");
+    let listener = bind(&path).unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    // Only the socket is left behind, not the directory it was bound in.
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    let server = tokio::spawn(serve(listener, Arc::new(EchoHandler::default())));
+
+    let stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+    let (reader, mut writer) = stream.into_split();
+    writer.write_all(b"not json\n").await.unwrap();
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await.unwrap();
+    let resp: ControlResponse = serde_json::from_str(&line).unwrap();
+    assert!(!resp.ok);
+    assert!(resp.error.unwrap().starts_with("invalid request"));
+    server.abort();
+}
+
+#[tokio::test]
+async fn bind_replaces_stale_socket_but_not_live_one() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ctl.sock");
+
+    // Stale: socket file with no listener behind it
+    drop(bind(&path).unwrap());
+    assert!(path.exists());
+    let live = bind(&path).unwrap();
+
+    // Live: a second daemon must not steal the socket
+    assert!(bind(&path).is_err());
+    drop(live);
+}
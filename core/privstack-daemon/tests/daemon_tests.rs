@@ -0,0 +1,101 @@
+use privstack_daemon::identity::{
+    load_or_create_identity_key, load_or_create_keypair, load_or_create_peer_id,
+};
+use privstack_daemon::status::DaemonStatus;
+use privstack_daemon::{Daemon, DaemonConfig, DaemonError};
+use privstack_sync::SyncEvent;
+use privstack_types::PeerId;
+
+fn config(dir: &tempfile::TempDir) -> DaemonConfig {
+    DaemonConfig::new(dir.path().join("data"))
+}
+
+#[test]
+fn identity_is_stable_across_restarts() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("data");
+
+    let peer = load_or_create_peer_id(&db).unwrap();
+    assert_eq!(load_or_create_peer_id(&db).unwrap(), peer);
+
+    let kp = load_or_create_keypair(&db).unwrap();
+    let again = load_or_create_keypair(&db).unwrap();
+    assert_eq!(kp.public(), again.public());
+
+    let key = load_or_create_identity_key(&db).unwrap();
+    assert_eq!(load_or_create_identity_key(&db).unwrap().public_key(), key.public_key());
+}
+
+#[test]
+fn corrupt_keys_are_errors_not_replaced() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("data");
+    load_or_create_identity_key(&db).unwrap();
+    let key_path = db.with_extension("identity_key");
+    let mode = std::fs::metadata(&key_path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    std::fs::write(&key_path, b"garbage").unwrap();
+    assert!(matches!(load_or_create_identity_key(&db), Err(DaemonError::Key(_))));
+    assert_eq!(std::fs::read(&key_path).unwrap(), b"garbage");
+
+    std::fs::write(db.with_extension("keypair"), b"garbage").unwrap();
+    assert!(matches!(load_or_create_keypair(&db), Err(DaemonError::Key(_))));
+}
+
+#[test]
+fn unlock_requires_init_for_new_vault() {
+    let dir = tempfile::tempdir().unwrap();
+    let daemon = Daemon::open(config(&dir)).unwrap();
+    assert!(matches!(daemon.unlock("correct horse"), Err(DaemonError::Config(_))));
+}
+
+#[test]
+fn init_then_reopen_with_password() {
+    let dir = tempfile::tempdir().unwrap();
+    let peer = {
+        let mut cfg = config(&dir);
+        cfg.init_vault = true;
+        let daemon = Daemon::open(cfg).unwrap();
+        daemon.unlock("correct horse").unwrap();
+        daemon.peer_id()
+    };
+
+    let daemon = Daemon::open(config(&dir)).unwrap();
+    assert_eq!(daemon.peer_id(), peer);
+    assert!(matches!(daemon.unlock("wrong password"), Err(DaemonError::Key(_))));
+    daemon.unlock("correct horse").unwrap();
+}
+
+#[test]
+fn status_tracks_sync_events() {
+    let peer = PeerId::new();
+    let mut status = DaemonStatus::new(PeerId::new(), "home");
+    status.apply_sync_event(&SyncEvent::PeerDiscovered {
+        peer_id: peer,
+        device_name: Some("laptop".into()),
+    });
+    status.apply_sync_event(&SyncEvent::SyncFailed {
+        peer_id: peer,
+        error: "timeout".into(),
+    });
+    status.apply_sync_event(&SyncEvent::SyncCompleted {
+        peer_id: peer,
+        events_sent: 3,
+        events_received: 2,
+    });
+    status.apply_sync_event(&SyncEvent::SyncCompleted {
+        peer_id: peer,
+        events_sent: 1,
+        events_received: 0,
+    });
+
+    let activity = &status.peers[&peer.to_string()];
+    assert_eq!(activity.device_name.as_deref(), Some("laptop"));
+    assert_eq!(activity.syncs_completed, 2);
+    assert_eq!(activity.events_sent, 4);
+    assert_eq!(activity.events_received, 2);
+    assert!(activity.last_error.is_none());
+}
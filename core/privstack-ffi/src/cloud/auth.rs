@@ -8,10 +8,13 @@ use privstack_cloud::config::CloudConfig;
 use privstack_cloud::credential_manager::CredentialManager;
 use privstack_cloud::dek_registry::DekRegistry;
 use privstack_cloud::envelope::EnvelopeManager;
+use privstack_cloud::key_transparency::KeyPinStore;
 use privstack_cloud::s3_transport::S3Transport;
 use privstack_cloud::sharing::ShareManager;
 use privstack_crypto::envelope as crypto_env;
+use privstack_crypto::IdentityPublicKey;
 use std::ffi::{c_char, CString};
+use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::Mutex as TokioMutex;
 
@@ -19,7 +22,10 @@ use tokio::sync::Mutex as TokioMutex;
 ///
 /// `config_json` must be a JSON object with fields:
 /// `api_base_url`, `s3_bucket`, `s3_region`, optional `s3_endpoint_override`,
-/// optional `credential_refresh_margin_secs`, optional `poll_interval_secs`.
+/// optional `credential_refresh_margin_secs`, optional `poll_interval_secs`,
+/// optional `key_log_public_key`.
+///
+/// Recipient keys are pinned in a file next to the database (`<db>.key_pins`).
 ///
 /// # Safety
 /// - `config_json` must be a valid null-terminated UTF-8 string.
@@ -43,8 +49,29 @@ pub unsafe extern "C" fn privstack_cloudsync_configure(
         None => return PrivStackError::NotInitialized,
     };
 
+    let key_log_key = match config.key_log_public_key.as_deref() {
+        Some(encoded) => match IdentityPublicKey::from_base64(encoded) {
+            Ok(key) => Some(key),
+            Err(_) => return PrivStackError::InvalidArgument,
+        },
+        None => None,
+    };
+    let pins = if handle.db_path == ":memory:" {
+        KeyPinStore::in_memory()
+    } else {
+        match KeyPinStore::open(Path::new(&handle.db_path).with_extension("key_pins")) {
+            Ok(p) => p,
+            Err(e) => return cloud_err(&e),
+        }
+    };
+
     let api = Arc::new(CloudApiClient::new(config.clone()));
-    let envelope_mgr = Arc::new(TokioMutex::new(EnvelopeManager::new(api.clone())));
+    let mut envelope = EnvelopeManager::new(api.clone());
+    envelope.set_key_pins(Arc::new(pins));
+    if let Some(key) = key_log_key {
+        envelope.set_key_log_key(key);
+    }
+    let envelope_mgr = Arc::new(TokioMutex::new(envelope));
     let share_mgr = Arc::new(ShareManager::new(api.clone()));
 
     handle.cloud_api = Some(api);
@@ -2,11 +2,40 @@
 //!
 //! Binary data is passed via pointer + length. DEKs are 32-byte keys.
 //! Downloaded blob data must be freed via `privstack_cloudsync_free_blob_data`.
+//!
+//! File transfers stream chunk by chunk, resume after an interruption and
+//! report progress through an optional callback invoked on the calling thread.
 
 use super::{cloud_err, parse_cstr, write_json_out};
 use crate::{PrivStackError, HANDLE};
+use privstack_cloud::blob_sync::{BlobRef, ProgressFn};
+use privstack_cloud::blob_transfer::{BlobProgress, TransferDirection};
 use privstack_crypto::DerivedKey;
-use std::ffi::c_char;
+use std::ffi::{c_char, c_void};
+use std::path::Path;
+
+/// Progress callback for file transfers: `(bytes_done, bytes_total, user_data)`.
+pub type BlobProgressCallback = extern "C" fn(u64, u64, *mut c_void);
+
+/// Wraps an optional C progress callback as a Rust closure.
+fn progress_fn(
+    callback: Option<BlobProgressCallback>,
+    user_data: *mut c_void,
+) -> Option<impl Fn(&BlobProgress) + Sync> {
+    // Carried as an integer: the pointer is only handed back to the caller.
+    let user_data = user_data as usize;
+    callback.map(move |cb| {
+        move |p: &BlobProgress| cb(p.bytes_done, p.bytes_total, user_data as *mut c_void)
+    })
+}
+
+fn parse_direction(direction: &str) -> Option<TransferDirection> {
+    match direction {
+        "upload" => Some(TransferDirection::Upload),
+        "download" => Some(TransferDirection::Download),
+        _ => None,
+    }
+}
 
 /// Uploads a blob encrypted with the provided DEK.
 ///
@@ -130,6 +159,218 @@ pub unsafe extern "C" fn privstack_cloudsync_download_blob(
     }
 }
 
+/// Uploads a file as a blob encrypted with the provided DEK.
+///
+/// The file is read and uploaded one chunk at a time. If an earlier upload of
+/// the same blob from the same file was interrupted, it resumes.
+///
+/// # Safety
+/// - `workspace_id`, `blob_id`, `file_path` must be valid null-terminated UTF-8 strings.
+/// - `entity_id` may be null (optional association).
+/// - `dek_ptr` must point to exactly 32 bytes (DEK).
+/// - `progress` may be null; `user_data` is passed back to it unchanged.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_cloudsync_upload_blob_file(
+    workspace_id: *const c_char,
+    blob_id: *const c_char,
+    entity_id: *const c_char,
+    file_path: *const c_char,
+    dek_ptr: *const u8,
+    progress: Option<BlobProgressCallback>,
+    user_data: *mut c_void,
+) -> PrivStackError {
+    let ws_id = match unsafe { parse_cstr(workspace_id) } {
+        Ok(s) => s,
+        Err(e) => return e,
+    };
+    let b_id = match unsafe { parse_cstr(blob_id) } {
+        Ok(s) => s,
+        Err(e) => return e,
+    };
+    let ent_id = if entity_id.is_null() {
+        None
+    } else {
+        match unsafe { parse_cstr(entity_id) } {
+            Ok(s) => Some(s),
+            Err(e) => return e,
+        }
+    };
+    let path = match unsafe { parse_cstr(file_path) } {
+        Ok(s) => s,
+        Err(e) => return e,
+    };
+    if dek_ptr.is_null() {
+        return PrivStackError::NullPointer;
+    }
+
+    let dek_bytes: [u8; 32] = unsafe { std::ptr::read(dek_ptr as *const [u8; 32]) };
+    let dek = DerivedKey::from_bytes(dek_bytes);
+
+    let handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_ref() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let blob_mgr = match handle.cloud_blob_mgr.as_ref() {
+        Some(m) => m.clone(),
+        None => return PrivStackError::SyncNotRunning,
+    };
+
+    let user_id = match handle.cloud_user_id {
+        Some(id) => id,
+        None => return PrivStackError::CloudAuthError,
+    };
+
+    let blob = BlobRef {
+        user_id,
+        workspace_id: ws_id,
+        blob_id: b_id,
+        entity_id: ent_id,
+    };
+    let on_progress = progress_fn(progress, user_data);
+    let on_progress = on_progress.as_ref().map(|f| f as ProgressFn<'_>);
+    match handle.runtime.block_on(blob_mgr.upload_blob_file(
+        &blob,
+        Path::new(path),
+        &dek,
+        on_progress,
+    )) {
+        Ok(()) => PrivStackError::Ok,
+        Err(e) => cloud_err(&e),
+    }
+}
+
+/// Downloads and decrypts a blob into a file.
+///
+/// The blob is fetched and written one chunk at a time. If an earlier
+/// download of the same blob to the same file was interrupted, it resumes.
+///
+/// # Safety
+/// - `blob_id`, `s3_key`, `dest_path` must be valid null-terminated UTF-8 strings.
+/// - `dek_ptr` must point to exactly 32 bytes (DEK).
+/// - `progress` may be null; `user_data` is passed back to it unchanged.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_cloudsync_download_blob_file(
+    blob_id: *const c_char,
+    s3_key: *const c_char,
+    dest_path: *const c_char,
+    dek_ptr: *const u8,
+    progress: Option<BlobProgressCallback>,
+    user_data: *mut c_void,
+) -> PrivStackError {
+    let b_id = match unsafe { parse_cstr(blob_id) } {
+        Ok(s) => s,
+        Err(e) => return e,
+    };
+    let key = match unsafe { parse_cstr(s3_key) } {
+        Ok(s) => s,
+        Err(e) => return e,
+    };
+    let dest = match unsafe { parse_cstr(dest_path) } {
+        Ok(s) => s,
+        Err(e) => return e,
+    };
+    if dek_ptr.is_null() {
+        return PrivStackError::NullPointer;
+    }
+
+    let dek_bytes: [u8; 32] = unsafe { std::ptr::read(dek_ptr as *const [u8; 32]) };
+    let dek = DerivedKey::from_bytes(dek_bytes);
+
+    let handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_ref() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let blob_mgr = match handle.cloud_blob_mgr.as_ref() {
+        Some(m) => m.clone(),
+        None => return PrivStackError::SyncNotRunning,
+    };
+
+    let on_progress = progress_fn(progress, user_data);
+    let on_progress = on_progress.as_ref().map(|f| f as ProgressFn<'_>);
+    match handle.runtime.block_on(blob_mgr.download_blob_file(
+        b_id,
+        key,
+        Path::new(dest),
+        &dek,
+        on_progress,
+    )) {
+        Ok(()) => PrivStackError::Ok,
+        Err(e) => cloud_err(&e),
+    }
+}
+
+/// Cancels an interrupted blob transfer so the next attempt starts over.
+///
+/// `direction` is `"upload"` or `"download"`. Aborts the multipart upload
+/// of a cancelled upload.
+///
+/// # Safety
+/// - `blob_id`, `direction` must be valid null-terminated UTF-8 strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_cloudsync_cancel_blob_transfer(
+    blob_id: *const c_char,
+    direction: *const c_char,
+) -> PrivStackError {
+    let b_id = match unsafe { parse_cstr(blob_id) } {
+        Ok(s) => s,
+        Err(e) => return e,
+    };
+    let direction = match unsafe { parse_cstr(direction) } {
+        Ok(s) => match parse_direction(s) {
+            Some(d) => d,
+            None => return PrivStackError::InvalidArgument,
+        },
+        Err(e) => return e,
+    };
+
+    let handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_ref() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let blob_mgr = match handle.cloud_blob_mgr.as_ref() {
+        Some(m) => m.clone(),
+        None => return PrivStackError::SyncNotRunning,
+    };
+
+    match handle.runtime.block_on(blob_mgr.cancel_transfer(b_id, direction)) {
+        Ok(true) => PrivStackError::Ok,
+        Ok(false) => PrivStackError::NotFound,
+        Err(e) => cloud_err(&e),
+    }
+}
+
+/// Lists unfinished blob transfers as JSON.
+///
+/// # Safety
+/// - `out_json` must be a valid pointer. Result must be freed with `privstack_free_string`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_cloudsync_list_blob_transfers(
+    out_json: *mut *mut c_char,
+) -> PrivStackError {
+    if out_json.is_null() {
+        return PrivStackError::NullPointer;
+    }
+
+    let handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_ref() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let blob_mgr = match handle.cloud_blob_mgr.as_ref() {
+        Some(m) => m.clone(),
+        None => return PrivStackError::SyncNotRunning,
+    };
+
+    write_json_out(out_json, &blob_mgr.pending_transfers())
+}
+
 /// Frees blob data allocated by `privstack_cloudsync_download_blob`.
 ///
 /// # Safety
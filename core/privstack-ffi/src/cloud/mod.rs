@@ -62,7 +62,8 @@ pub(crate) fn cloud_err(e: &CloudError) -> PrivStackError {
         CloudError::AuthRequired | CloudError::AuthFailed(_) => PrivStackError::CloudAuthError,
         CloudError::QuotaExceeded { .. } => PrivStackError::QuotaExceeded,
         CloudError::ShareDenied(_) => PrivStackError::ShareDenied,
-        CloudError::Envelope(_) => PrivStackError::EnvelopeError,
+        CloudError::Envelope(_) | CloudError::KeyLog(_) => PrivStackError::EnvelopeError,
+        CloudError::RecipientKeyChanged { .. } => PrivStackError::RecipientKeyChanged,
         CloudError::CredentialExpired => PrivStackError::CloudSyncError,
         CloudError::LockContention(_) => PrivStackError::CloudSyncError,
         _ => PrivStackError::CloudSyncError,
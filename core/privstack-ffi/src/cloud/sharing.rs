@@ -85,7 +85,12 @@ pub unsafe extern "C" fn privstack_cloudsync_share_entity(
     }
 }
 
-/// Revokes a share for an entity.
+/// Revokes a share for an entity and rotates the entity DEK, re-sealing the
+/// new key to the remaining recipients.
+///
+/// Succeeds once the share is revoked. A rotation that cannot be sealed to
+/// every recipient leaves the current key in use and is retried when cloud
+/// sync starts.
 ///
 /// # Safety
 /// - `entity_id`, `recipient_email` must be valid null-terminated UTF-8 strings.
@@ -113,9 +118,25 @@ pub unsafe extern "C" fn privstack_cloudsync_revoke_share(
         Some(m) => m.clone(),
         None => return PrivStackError::NotInitialized,
     };
+    let env_mgr = match handle.cloud_envelope_mgr.as_ref() {
+        Some(m) => m.clone(),
+        None => return PrivStackError::NotInitialized,
+    };
+    let dek_registry = match handle.cloud_dek_registry.as_ref() {
+        Some(r) => r.clone(),
+        None => return PrivStackError::NotInitialized,
+    };
 
-    match handle.runtime.block_on(share_mgr.revoke_share(eid, email)) {
-        Ok(()) => PrivStackError::Ok,
+    let result = handle.runtime.block_on(async {
+        let env = env_mgr.lock().await;
+        share_mgr.revoke_share(&env, &dek_registry, eid, email).await
+    });
+    match result {
+        Ok(Some(_)) => PrivStackError::Ok,
+        Ok(None) => {
+            eprintln!("[FFI] revoke_share: share revoked, DEK rotation pending for {eid}");
+            PrivStackError::Ok
+        }
         Err(e) => cloud_err(&e),
     }
 }
@@ -144,10 +165,25 @@ pub unsafe extern "C" fn privstack_cloudsync_accept_share(
         None => return PrivStackError::NotInitialized,
     };
 
-    match handle.runtime.block_on(share_mgr.accept_share(token)) {
-        Ok(()) => PrivStackError::Ok,
-        Err(e) => cloud_err(&e),
+    if let Err(e) = handle.runtime.block_on(share_mgr.accept_share(token)) {
+        return cloud_err(&e);
     }
+
+    // Pick up the new entity's DEK now so it decrypts without waiting for
+    // the next sync start. The share itself is accepted either way.
+    if let (Some(env_mgr), Some(dek_registry)) = (
+        handle.cloud_envelope_mgr.as_ref(),
+        handle.cloud_dek_registry.as_ref(),
+    ) {
+        let result = handle.runtime.block_on(async {
+            let env = env_mgr.lock().await;
+            share_mgr.refresh_shared_keys(&env, dek_registry).await
+        });
+        if let Err(e) = result {
+            eprintln!("[FFI] Failed to fetch shared entity keys: {e}");
+        }
+    }
+    PrivStackError::Ok
 }
 
 /// Lists shares for an entity owned by the current user.
@@ -213,3 +249,180 @@ pub unsafe extern "C" fn privstack_cloudsync_get_shared_with_me(
         Err(e) => cloud_err(&e),
     }
 }
+
+/// Returns the safety number for the current user and a share recipient
+/// whose key is pinned, for comparison out of band.
+///
+/// Writes `{ "user_id", "safety_number", "verified" }` to `out_json`.
+///
+/// # Safety
+/// - `out_json` must be a valid pointer. Result must be freed with `privstack_free_string`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_cloudsync_get_safety_number(
+    user_id: i64,
+    out_json: *mut *mut c_char,
+) -> PrivStackError {
+    if out_json.is_null() {
+        return PrivStackError::NullPointer;
+    }
+
+    let handle = lock_handle();
+    let handle = match handle.as_ref() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let local_user_id = match handle.cloud_user_id {
+        Some(id) => id,
+        None => return PrivStackError::CloudAuthError,
+    };
+    let env_mgr = match handle.cloud_envelope_mgr.as_ref() {
+        Some(m) => m.clone(),
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let env = handle.runtime.block_on(env_mgr.lock());
+    let number = match env.safety_number(local_user_id, user_id) {
+        Ok(n) => n,
+        Err(e) => return cloud_err(&e),
+    };
+    let verified = env
+        .key_pins()
+        .and_then(|p| p.get(user_id))
+        .is_some_and(|pin| pin.verified);
+
+    write_json_out(
+        out_json,
+        &serde_json::json!({
+            "user_id": user_id,
+            "safety_number": number,
+            "verified": verified,
+        }),
+    )
+}
+
+/// Marks a recipient's pinned key as verified (or not) after the users
+/// compared safety numbers.
+#[unsafe(no_mangle)]
+pub extern "C" fn privstack_cloudsync_set_key_verified(
+    user_id: i64,
+    verified: bool,
+) -> PrivStackError {
+    let handle = lock_handle();
+    let handle = match handle.as_ref() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let env_mgr = match handle.cloud_envelope_mgr.as_ref() {
+        Some(m) => m.clone(),
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let env = handle.runtime.block_on(env_mgr.lock());
+    let Some(pins) = env.key_pins() else {
+        return PrivStackError::NotInitialized;
+    };
+    match pins.set_verified(user_id, verified) {
+        Ok(()) => PrivStackError::Ok,
+        Err(_) => PrivStackError::NotFound,
+    }
+}
+
+/// Returns the safety number for the current user and the key the server
+/// now serves for a share recipient, so a changed key can be compared out of
+/// band before it is accepted with `privstack_cloudsync_accept_key_change`.
+///
+/// Writes `{ "user_id", "safety_number" }` to `out_json`.
+///
+/// # Safety
+/// - `out_json` must be a valid pointer. Result must be freed with `privstack_free_string`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_cloudsync_get_pending_safety_number(
+    user_id: i64,
+    out_json: *mut *mut c_char,
+) -> PrivStackError {
+    if out_json.is_null() {
+        return PrivStackError::NullPointer;
+    }
+
+    let handle = lock_handle();
+    let handle = match handle.as_ref() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let local_user_id = match handle.cloud_user_id {
+        Some(id) => id,
+        None => return PrivStackError::CloudAuthError,
+    };
+    let env_mgr = match handle.cloud_envelope_mgr.as_ref() {
+        Some(m) => m.clone(),
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let result = handle.runtime.block_on(async {
+        let env = env_mgr.lock().await;
+        let key = env.pending_recipient_key(user_id).await?;
+        env.key_safety_number(local_user_id, user_id, &key)
+    });
+    match result {
+        Ok(number) => write_json_out(
+            out_json,
+            &serde_json::json!({
+                "user_id": user_id,
+                "safety_number": number,
+            }),
+        ),
+        Err(e) => cloud_err(&e),
+    }
+}
+
+/// Accepts a recipient's changed public key after the user confirmed it,
+/// replacing the pin. `safety_number` is the number the user approved (see
+/// `privstack_cloudsync_get_pending_safety_number`); the key is pinned only
+/// if the server still serves the key it was computed from, otherwise this
+/// returns `RecipientKeyChanged`. Sharing with the user fails with
+/// `RecipientKeyChanged` until a change is accepted.
+///
+/// # Safety
+/// - `safety_number` must be a valid null-terminated UTF-8 string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_cloudsync_accept_key_change(
+    user_id: i64,
+    safety_number: *const c_char,
+) -> PrivStackError {
+    let approved = match unsafe { parse_cstr(safety_number) } {
+        Ok(s) => s.split_whitespace().collect::<String>(),
+        Err(e) => return e,
+    };
+
+    let handle = lock_handle();
+    let handle = match handle.as_ref() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let local_user_id = match handle.cloud_user_id {
+        Some(id) => id,
+        None => return PrivStackError::CloudAuthError,
+    };
+    let env_mgr = match handle.cloud_envelope_mgr.as_ref() {
+        Some(m) => m.clone(),
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let result = handle.runtime.block_on(async {
+        let env = env_mgr.lock().await;
+        let key = env.pending_recipient_key(user_id).await?;
+        let number = env.key_safety_number(local_user_id, user_id, &key)?;
+        if number.split_whitespace().collect::<String>() != approved {
+            return Err(privstack_cloud::CloudError::RecipientKeyChanged { user_id });
+        }
+        env.accept_recipient_key(user_id, &key).await
+    });
+    match result {
+        Ok(()) => PrivStackError::Ok,
+        Err(e) => cloud_err(&e),
+    }
+}
@@ -3,6 +3,7 @@
 use super::{cloud_err, parse_cstr, write_json_out};
 use crate::{lock_handle, PrivStackError, HANDLE};
 use privstack_cloud::blob_sync::BlobSyncManager;
+use privstack_cloud::blob_transfer::TransferStateStore;
 use privstack_cloud::compaction;
 use privstack_cloud::credential_manager::CredentialManager;
 use privstack_cloud::s3_transport::S3Transport;
@@ -13,6 +14,7 @@ use privstack_model::{Entity, EntitySchema};
 use privstack_types::{EntityId, Event, EventPayload, HybridTimestamp};
 use std::collections::HashMap;
 use std::ffi::c_char;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
@@ -71,11 +73,20 @@ pub unsafe extern "C" fn privstack_cloudsync_start_sync(
     ));
 
     // Initialize blob sync manager (shares transport + cred_manager with sync engine)
-    let blob_mgr = Arc::new(BlobSyncManager::new(
+    let mut blob_mgr = BlobSyncManager::new(
         api.clone(),
         transport.clone(),
         cred_manager.clone(),
-    ));
+    );
+    // Resume state of interrupted blob transfers survives restarts.
+    if handle.db_path != ":memory:" {
+        let path = Path::new(&handle.db_path).with_extension("blob_transfers");
+        match TransferStateStore::open(path) {
+            Ok(store) => blob_mgr.set_transfer_store(Arc::new(store)),
+            Err(e) => eprintln!("[FFI] Failed to load blob transfer state: {e}"),
+        }
+    }
+    let blob_mgr = Arc::new(blob_mgr);
 
     let dek_registry = match handle.cloud_dek_registry.as_ref() {
         Some(r) => r.clone(),
@@ -89,10 +100,38 @@ pub unsafe extern "C" fn privstack_cloudsync_start_sync(
             let mut arr = [0u8; KEY_SIZE];
             arr.copy_from_slice(&key_bytes);
             let dek = DerivedKey::from_bytes(arr);
-            handle.runtime.block_on(dek_registry.set_default(dek));
+            handle.runtime.block_on(dek_registry.set_default(dek.clone()));
+
+            // Load per-entity DEKs (shared entities, rotated keys) cached
+            // under the same master key, so they decrypt offline.
+            if handle.db_path != ":memory:" {
+                let path = Path::new(&handle.db_path).with_extension("cloud_deks");
+                if let Err(e) = handle.runtime.block_on(dek_registry.open_persisted(path, dek)) {
+                    eprintln!("[FFI] Failed to load cached entity DEKs: {e}");
+                }
+            }
         }
     }
 
+    // Refresh shared entity keys in the background (rotations, revocations)
+    // and finish rotations that could not be sealed to everyone earlier.
+    if let (Some(share_mgr), Some(env_mgr)) = (
+        handle.cloud_share_mgr.clone(),
+        handle.cloud_envelope_mgr.clone(),
+    ) {
+        let registry = dek_registry.clone();
+        handle.runtime.spawn(async move {
+            let env = env_mgr.lock().await;
+            if let Err(e) = share_mgr.refresh_shared_keys(&env, &registry).await {
+                eprintln!("[FFI] Failed to refresh shared entity keys: {e}");
+            }
+            let rotated = share_mgr.retry_pending_rotations(&env, &registry).await;
+            if rotated > 0 {
+                eprintln!("[FFI] Completed {rotated} pending DEK rotations");
+            }
+        });
+    }
+
     let active_ws_id = ws_id.clone();
     let (event_tx, event_rx) = mpsc::channel(256);
 
@@ -108,6 +147,10 @@ pub unsafe extern "C" fn privstack_cloudsync_start_sync(
         Duration::from_secs(config.poll_interval_secs),
         handle.entity_store.clone(),
     );
+    engine.set_author_key_store(handle.event_store.clone());
+    if let Some(env_mgr) = handle.cloud_envelope_mgr.clone() {
+        engine.set_envelope_manager(env_mgr);
+    }
 
     handle.runtime.spawn(async move {
         engine.run().await;
@@ -270,7 +313,7 @@ pub unsafe extern "C" fn privstack_cloudsync_push_event(
         Err(_) => return PrivStackError::InvalidArgument,
     };
 
-    let event = Event::new(
+    let mut event = Event::new(
         parsed_entity_id,
         handle.peer_id,
         HybridTimestamp::now(),
@@ -279,6 +322,7 @@ pub unsafe extern "C" fn privstack_cloudsync_push_event(
             json_data: data,
         },
     );
+    handle.identity_key.sign_event(&mut event);
 
     match tx.blocking_send(event) {
         Ok(()) => PrivStackError::Ok,
@@ -313,7 +357,7 @@ pub unsafe extern "C" fn privstack_cloudsync_push_all_entities(
         None => return PrivStackError::SyncNotRunning,
     };
 
-    let ids = match handle.entity_store.list_all_entity_ids() {
+    let ids = match handle.entity_store.list_syncable_entity_ids() {
         Ok(ids) => ids,
         Err(_) => return PrivStackError::StorageError,
     };
@@ -339,7 +383,7 @@ pub unsafe extern "C" fn privstack_cloudsync_push_all_entities(
         };
 
         let json_data = entity.data.to_string();
-        let event = Event::new(
+        let mut event = Event::new(
             parsed_id,
             handle.peer_id,
             HybridTimestamp::now(),
@@ -348,6 +392,7 @@ pub unsafe extern "C" fn privstack_cloudsync_push_all_entities(
                 json_data,
             },
         );
+        handle.identity_key.sign_event(&mut event);
 
         if tx.blocking_send(event).is_ok() {
             pushed += 1;
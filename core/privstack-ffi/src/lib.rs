@@ -14,27 +14,34 @@ mod datasets;
 mod android_jni;
 
 use privstack_blobstore::BlobStore;
+use privstack_crypto::{IdentityKeyPair, IdentityPublicKey};
 use privstack_license::{
     Activation, ActivationStore, DeviceFingerprint, DeviceInfo, LicenseError, LicenseKey,
     LicensePlan, LicenseStatus,
 };
 use privstack_model::{Entity, EntitySchema, PluginDomainHandler};
 #[cfg(feature = "wasm-plugins")]
-use privstack_plugin_host::PluginHostManager;
+use privstack_plugin_host::{
+    is_local_only_setting, EntityChange, EntityChangeKind, PluginHostManager, CHANGE_SOURCE_HOST,
+    SETTINGS_ENTITY_TYPE,
+};
 use privstack_storage::{EntityStore, EventStore};
 use privstack_sync::{
     cloud::{CloudStorage, GoogleDriveConfig, GoogleDriveStorage, ICloudConfig, ICloudStorage},
-    create_personal_orchestrator,
+    create_enterprise_orchestrator, create_personal_orchestrator,
     pairing::{PairingManager, SyncCode},
-    Keypair, OrchestratorConfig, OrchestratorHandle, P2pConfig, P2pTransport,
-    PersonalSyncPolicy, SyncCommand, SyncConfig, SyncEngine, SyncEvent, SyncTransport,
+    policy_file::PolicyFileSource,
+    EnterpriseSyncPolicy, Keypair, OrchestratorConfig, OrchestratorHandle, P2pConfig,
+    P2pTransport, PersonalSyncPolicy, PolicyStore, SyncCommand, SyncConfig, SyncEngine,
+    SyncEvent, SyncScope, SyncTransport,
 };
 use privstack_types::{EntityId, Event, PeerId};
 use privstack_vault::VaultManager;
 use serde::{Deserialize, Serialize};
 use std::ffi::{c_char, c_int, CStr, CString};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::runtime::Runtime;
 use tokio::sync::{mpsc, Mutex as TokioMutex};
 use uuid::Uuid;
@@ -103,7 +110,7 @@ pub enum PrivStackError {
     InvalidArgument = 28,
     /// Cloud sync error (S3 transport, outbox, or orchestration failure).
     CloudSyncError = 29,
-    /// Cloud storage quota exceeded.
+    /// Storage quota exceeded (cloud storage or plugin settings).
     QuotaExceeded = 30,
     /// Share permission denied.
     ShareDenied = 31,
@@ -115,6 +122,8 @@ pub enum PrivStackError {
     RecoveryNotConfigured = 34,
     /// Invalid recovery mnemonic.
     InvalidRecoveryMnemonic = 35,
+    /// A share recipient's public key differs from the pinned one.
+    RecipientKeyChanged = 36,
     /// Unknown error.
     Unknown = 99,
 }
@@ -122,7 +131,9 @@ pub enum PrivStackError {
 /// Registry of entity schemas and optional domain handlers.
 pub struct EntityRegistry {
     schemas: std::collections::HashMap<String, EntitySchema>,
-    handlers: std::collections::HashMap<String, Box<dyn PluginDomainHandler>>,
+    handlers: std::collections::HashMap<String, Arc<dyn PluginDomainHandler>>,
+    /// Copy the P2P sync applicator reads, kept in step with the maps above.
+    domain: Arc<privstack_sync::DomainRegistry>,
 }
 
 impl EntityRegistry {
@@ -130,18 +141,27 @@ impl EntityRegistry {
         Self {
             schemas: std::collections::HashMap::new(),
             handlers: std::collections::HashMap::new(),
+            domain: Arc::new(privstack_sync::DomainRegistry::default()),
         }
     }
 
     fn register_schema(&mut self, schema: EntitySchema) {
+        self.domain.register_schema(schema.clone());
         self.schemas.insert(schema.entity_type.clone(), schema);
     }
 
-    #[allow(dead_code)]
-    fn register_handler(&mut self, entity_type: String, handler: Box<dyn PluginDomainHandler>) {
+    #[cfg_attr(not(feature = "wasm-plugins"), allow(dead_code))]
+    fn register_handler(&mut self, entity_type: String, handler: Arc<dyn PluginDomainHandler>) {
+        self.domain.register_handler(entity_type.clone(), handler.clone());
         self.handlers.insert(entity_type, handler);
     }
 
+    #[cfg_attr(not(feature = "wasm-plugins"), allow(dead_code))]
+    fn unregister_handler(&mut self, entity_type: &str) {
+        self.domain.unregister_handler(entity_type);
+        self.handlers.remove(entity_type);
+    }
+
     fn get_schema(&self, entity_type: &str) -> Option<&EntitySchema> {
         self.schemas.get(entity_type)
     }
@@ -158,6 +178,12 @@ impl EntityRegistry {
     fn clone_schemas(&self) -> std::collections::HashMap<String, EntitySchema> {
         self.schemas.clone()
     }
+
+    /// Live schemas and handlers for the P2P sync applicator; later
+    /// registrations show up in it.
+    fn domain_registry(&self) -> Arc<privstack_sync::DomainRegistry> {
+        Arc::clone(&self.domain)
+    }
 }
 
 /// Opaque handle to the PrivStack runtime.
@@ -169,6 +195,8 @@ pub struct PrivStackHandle {
     event_store: Arc<EventStore>,
     entity_registry: EntityRegistry,
     peer_id: PeerId,
+    /// Ed25519 key this device signs its events with.
+    identity_key: Arc<IdentityKeyPair>,
     runtime: Runtime,
     #[allow(dead_code)]
     sync_engine: SyncEngine,
@@ -177,6 +205,10 @@ pub struct PrivStackHandle {
     sync_event_rx: Option<mpsc::Receiver<SyncEvent>>,
     pairing_manager: Arc<std::sync::Mutex<PairingManager>>,
     personal_policy: Option<Arc<PersonalSyncPolicy>>,
+    /// Enterprise policy; when set, sync runs under it instead of the personal policy.
+    enterprise_policy: Option<Arc<EnterpriseSyncPolicy>>,
+    /// Re-applies the enterprise policy file when it changes.
+    enterprise_policy_watcher: Option<tokio::task::JoinHandle<()>>,
     device_name: String,
     google_drive: Option<GoogleDriveStorage>,
     icloud: Option<ICloudStorage>,
@@ -189,6 +221,12 @@ pub struct PrivStackHandle {
     // Wasm plugin host manager
     #[cfg(feature = "wasm-plugins")]
     plugin_host: PluginHostManager,
+    // Publisher keys accepted on .ppk install
+    #[cfg(feature = "wasm-plugins")]
+    trust_store: privstack_ppk::TrustStore,
+    // Packages installed from verified .ppk files, by plugin id
+    #[cfg(feature = "wasm-plugins")]
+    installed_packages: std::collections::HashMap<String, InstalledPackage>,
     // Cloud sync (S3-backed multi-device sync + sharing)
     cloud_api: Option<Arc<privstack_cloud::api_client::CloudApiClient>>,
     cloud_sync_handle: Option<privstack_cloud::sync_engine::CloudSyncHandle>,
@@ -432,11 +470,72 @@ fn load_or_create_keypair(db_path: &str) -> Keypair {
     keypair
 }
 
+/// Loads the device identity key used to sign events, or generates and saves a
+/// new one. The key file lives alongside the database (e.g. `data.identity_key`).
+/// For `:memory:` databases, a fresh key is generated every time.
+///
+/// An unreadable or corrupt key file, or a key that cannot be saved, is an
+/// error rather than a reason to use a fresh key: the stored key is the one
+/// registered for this peer, and events signed by another would not verify.
+fn load_or_create_identity_key(db_path: &str) -> Result<IdentityKeyPair, String> {
+    if db_path == ":memory:" {
+        return Ok(IdentityKeyPair::generate());
+    }
+
+    let key_path = Path::new(db_path).with_extension("identity_key");
+
+    match std::fs::read(&key_path) {
+        Ok(bytes) => {
+            let key = IdentityKeyPair::from_secret_bytes(&bytes)
+                .map_err(|e| format!("corrupt identity key file at {}: {e}", key_path.display()))?;
+            eprintln!("[FFI] Loaded existing identity key from {}", key_path.display());
+            return Ok(key);
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("failed to read {}: {e}", key_path.display())),
+    }
+
+    let key = IdentityKeyPair::generate();
+    write_private_file(&key_path, key.secret_bytes().as_slice())
+        .map_err(|e| format!("failed to persist identity key to {}: {e}", key_path.display()))?;
+    eprintln!("[FFI] Generated and saved new identity key to {}", key_path.display());
+    Ok(key)
+}
+
+/// Creates a new file readable only by the current user. The mode is set
+/// when the file is created, so the contents are never readable by others.
+fn write_private_file(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+/// Loads the identity key for `init`, logging why it could not be used.
+fn init_identity_key(db_path: &str) -> Result<Arc<IdentityKeyPair>, PrivStackError> {
+    load_or_create_identity_key(db_path).map(Arc::new).map_err(|e| {
+        eprintln!("[FFI] FAILED to load identity key: {e}");
+        PrivStackError::StorageError
+    })
+}
+
 /// Core init logic — sets up vault, blob, entity, event stores, runtime, sync engine.
 /// Used directly when wasm-plugins feature is disabled.
 #[cfg(not(feature = "wasm-plugins"))]
 fn init_core(path: &str) -> PrivStackError {
     let peer_id = load_or_create_peer_id(path);
+    let identity_key = match init_identity_key(path) {
+        Ok(key) => key,
+        Err(e) => return e,
+    };
 
     // Create vault manager (encrypted blob storage)
     let vault_db_path = if path == ":memory:" {
@@ -516,6 +615,11 @@ fn init_core(path: &str) -> PrivStackError {
         }
     };
 
+    // Bind this device's own key so the events it signs verify locally
+    if let Err(e) = event_store.register_author_key(&peer_id, &identity_key.public_key()) {
+        eprintln!("[FFI] Failed to register own identity key: {e:?}");
+    }
+
     let entity_store = Arc::new(entity_store);
     let event_store = Arc::new(event_store);
 
@@ -570,6 +674,7 @@ fn init_core(path: &str) -> PrivStackError {
         event_store,
         entity_registry,
         peer_id,
+        identity_key,
         runtime,
         sync_engine,
         p2p_transport: None,
@@ -577,6 +682,8 @@ fn init_core(path: &str) -> PrivStackError {
         sync_event_rx: None,
         pairing_manager: Arc::new(std::sync::Mutex::new(PairingManager::new())),
         personal_policy: None,
+        enterprise_policy: None,
+        enterprise_policy_watcher: None,
         device_name,
         google_drive: None,
         icloud: None,
@@ -595,10 +702,148 @@ fn init_core(path: &str) -> PrivStackError {
         cloud_user_id: None,
         cloud_active_workspace: None,
     });
+    restore_enterprise_policy(handle.as_mut().unwrap());
 
     PrivStackError::Ok
 }
 
+/// The built-in PrivStack root plus the signing keys the enterprise policy
+/// allows, which are trusted as publishers. Keys that are not 64 hex
+/// characters are skipped.
+#[cfg(feature = "wasm-plugins")]
+fn build_trust_store(plugin_host: &PluginHostManager) -> privstack_ppk::TrustStore {
+    let mut store = privstack_ppk::TrustStore::new();
+    for key_hex in &plugin_host.policy_engine().config().allowed_signing_keys {
+        let added = privstack_ppk::VerifyingKey::from_hex(key_hex).and_then(|key| {
+            store.add("Enterprise policy", key, privstack_ppk::TrustSource::Enterprise)
+        });
+        if let Err(e) = added {
+            eprintln!("[FFI] Policy signing key {} not trusted: {}", key_hex, e);
+        }
+    }
+    store
+}
+
+/// A package installed from a verified .ppk, as saved in the plugin trust file.
+#[cfg(feature = "wasm-plugins")]
+#[derive(Clone, Serialize, Deserialize)]
+struct InstalledPackage {
+    manifest: privstack_ppk::PpkManifest,
+    /// Hex SHA-256 of the package's Wasm, if it has one.
+    wasm_sha256: Option<String>,
+    /// Hex key that signed the package.
+    signing_key: String,
+    first_party: bool,
+}
+
+/// Plugin trust that outlives the process: the publishers the user added
+/// and the packages installed from verified .ppk files.
+#[cfg(feature = "wasm-plugins")]
+#[derive(Default, Serialize, Deserialize)]
+struct PluginTrustFile {
+    #[serde(default)]
+    publishers: Vec<privstack_ppk::TrustedPublisher>,
+    #[serde(default)]
+    packages: Vec<InstalledPackage>,
+}
+
+#[cfg(feature = "wasm-plugins")]
+fn plugin_trust_path(db_path: &str) -> Option<PathBuf> {
+    (db_path != ":memory:").then(|| Path::new(db_path).with_extension("plugin_trust.json"))
+}
+
+/// Applies what an installed package's manifest grants: its declared
+/// permissions, directory scopes, fetch hosts and event topics.
+#[cfg(feature = "wasm-plugins")]
+fn apply_package_manifest(
+    plugin_host: &mut PluginHostManager,
+    m: &privstack_ppk::PpkManifest,
+) -> Result<(), privstack_plugin_host::PluginHostError> {
+    plugin_host.set_declared_permissions(&m.id, m.permissions.clone());
+    plugin_host.set_filesystem_scopes(&m.id, m.filesystem.clone());
+
+    // Restrict fetch-url to the hosts the manifest declares; none means no
+    // outbound requests at all.
+    let policy = privstack_plugin_host::NetworkPolicy::for_hosts(m.network_hosts.clone());
+    plugin_host.set_network_policy(&m.id, policy);
+
+    // Only the topics the manifest declares can be published on.
+    let topics = m.event_topics.iter().map(|t| t.name.clone()).collect();
+    plugin_host.set_event_topics(&m.id, topics)
+}
+
+/// Restores the publishers the user trusts and the packages installed in
+/// earlier sessions, so their builds load again under the same signer and
+/// grants.
+#[cfg(feature = "wasm-plugins")]
+fn restore_plugin_trust(handle: &mut PrivStackHandle) {
+    let Some(path) = plugin_trust_path(&handle.db_path) else {
+        return;
+    };
+    let Ok(json) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    let file: PluginTrustFile = match serde_json::from_str(&json) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("[FFI] Failed to restore plugin trust from {}: {}", path.display(), e);
+            return;
+        }
+    };
+    for publisher in file.publishers {
+        let source = privstack_ppk::TrustSource::User;
+        if let Err(e) = handle.trust_store.add(&publisher.name, publisher.key, source) {
+            eprintln!("[FFI] Publisher {} not restored: {}", publisher.key.to_hex(), e);
+        }
+    }
+    for package in file.packages {
+        let m = &package.manifest;
+        if let Err(e) = apply_package_manifest(&mut handle.plugin_host, m) {
+            eprintln!("[FFI] Installed plugin {} not restored: {}", m.id, e);
+            continue;
+        }
+        if let Some(wasm_sha256) = &package.wasm_sha256 {
+            handle.plugin_host.trust_build_hash(
+                &m.id,
+                wasm_sha256.clone(),
+                package.signing_key.clone(),
+                package.first_party,
+            );
+        }
+        handle.installed_packages.insert(m.id.clone(), package);
+    }
+}
+
+/// Saves the publishers the user trusts and the installed packages. The
+/// file is replaced by a rename, so a crash never leaves half of it.
+#[cfg(feature = "wasm-plugins")]
+fn save_plugin_trust(handle: &PrivStackHandle) {
+    let Some(path) = plugin_trust_path(&handle.db_path) else {
+        return;
+    };
+    let mut packages: Vec<InstalledPackage> =
+        handle.installed_packages.values().cloned().collect();
+    packages.sort_by(|a, b| a.manifest.id.cmp(&b.manifest.id));
+    let file = PluginTrustFile {
+        publishers: handle
+            .trust_store
+            .publishers()
+            .iter()
+            .filter(|p| p.source == privstack_ppk::TrustSource::User)
+            .cloned()
+            .collect(),
+        packages,
+    };
+    let tmp_path = path.with_extension("json.tmp");
+    let saved = serde_json::to_vec_pretty(&file)
+        .map_err(std::io::Error::other)
+        .and_then(|json| std::fs::write(&tmp_path, json))
+        .and_then(|()| std::fs::rename(&tmp_path, &path));
+    if let Err(e) = saved {
+        eprintln!("[FFI] Failed to save plugin trust to {}: {}", path.display(), e);
+    }
+}
+
 /// Shared init logic with plugin host — accepts a closure to construct the PluginHostManager,
 /// so tests can inject a policy-free manager without touching the filesystem.
 #[cfg(feature = "wasm-plugins")]
@@ -607,6 +852,10 @@ where
     F: FnOnce(Arc<EntityStore>, Arc<EventStore>) -> PluginHostManager,
 {
     let peer_id = load_or_create_peer_id(path);
+    let identity_key = match init_identity_key(path) {
+        Ok(key) => key,
+        Err(e) => return e,
+    };
 
     // Create vault manager (encrypted blob storage)
     let vault_db_path = if path == ":memory:" {
@@ -686,15 +935,24 @@ where
         }
     };
 
+    // Bind this device's own key so the events it signs verify locally
+    if let Err(e) = event_store.register_author_key(&peer_id, &identity_key.public_key()) {
+        eprintln!("[FFI] Failed to register own identity key: {e:?}");
+    }
+
     let entity_store = Arc::new(entity_store);
     let event_store = Arc::new(event_store);
 
     let entity_registry = EntityRegistry::new();
 
-    let plugin_host = build_plugin_host(
+    let mut plugin_host = build_plugin_host(
         Arc::clone(&entity_store),
         Arc::clone(&event_store),
     );
+    if path != ":memory:" {
+        plugin_host.set_selection_staging_dir(Path::new(path).with_extension("selections"));
+    }
+    let trust_store = build_trust_store(&plugin_host);
 
     let runtime = match Runtime::new() {
         Ok(rt) => rt,
@@ -745,6 +1003,7 @@ where
         event_store,
         entity_registry,
         peer_id,
+        identity_key,
         runtime,
         sync_engine,
         p2p_transport: None,
@@ -752,6 +1011,8 @@ where
         sync_event_rx: None,
         pairing_manager: Arc::new(std::sync::Mutex::new(PairingManager::new())),
         personal_policy: None,
+        enterprise_policy: None,
+        enterprise_policy_watcher: None,
         device_name,
         google_drive: None,
         icloud: None,
@@ -760,6 +1021,8 @@ where
         blob_store,
         dataset_store,
         plugin_host,
+        trust_store,
+        installed_packages: std::collections::HashMap::new(),
         cloud_api: None,
         cloud_sync_handle: None,
         cloud_event_tx: None,
@@ -771,6 +1034,8 @@ where
         cloud_user_id: None,
         cloud_active_workspace: None,
     });
+    restore_enterprise_policy(handle.as_mut().unwrap());
+    restore_plugin_trust(handle.as_mut().unwrap());
 
     PrivStackError::Ok
 }
@@ -929,6 +1194,10 @@ pub extern "C" fn privstack_auth_lock() -> PrivStackError {
 
 /// Changes the master password for all vaults.
 ///
+/// Local stores and the cached cloud entity keys are re-encrypted under the
+/// new key. If the cloud keys cannot be re-encrypted the password is still
+/// changed and `StorageError` is returned; the cache keeps the old key.
+///
 /// # Safety
 /// - `old_password` and `new_password` must be valid null-terminated UTF-8 strings.
 #[unsafe(no_mangle)]
@@ -971,6 +1240,10 @@ pub unsafe extern "C" fn privstack_auth_change_password(
             {
                 let _ = handle.entity_store.re_encrypt_all(&old_kb, &new_kb);
                 let _ = handle.blob_store.re_encrypt_all(&old_kb, &new_kb);
+                if let Err(e) = rekey_cloud_deks(handle, &old_kb, &new_kb) {
+                    eprintln!("[FFI] change_password: {e}");
+                    return PrivStackError::StorageError;
+                }
             }
             PrivStackError::Ok
         }
@@ -978,6 +1251,33 @@ pub unsafe extern "C" fn privstack_auth_change_password(
     }
 }}
 
+/// Re-encrypts the cached cloud entity DEKs (`<db>.cloud_deks`) under a new
+/// vault key, whether or not cloud sync has opened them this session.
+/// On error the file is left intact under the old key.
+fn rekey_cloud_deks(handle: &PrivStackHandle, old_kb: &[u8], new_kb: &[u8]) -> Result<(), String> {
+    let to_key = |kb: &[u8]| {
+        <[u8; privstack_crypto::KEY_SIZE]>::try_from(kb)
+            .map(privstack_crypto::DerivedKey::from_bytes)
+            .map_err(|_| "vault key has an unexpected length".to_string())
+    };
+    let (old_key, new_key) = (to_key(old_kb)?, to_key(new_kb)?);
+    if let Some(registry) = handle.cloud_dek_registry.as_ref()
+        && registry.is_persisted()
+    {
+        return handle
+            .runtime
+            .block_on(registry.rekey(new_key))
+            .map_err(|e| format!("failed to re-encrypt cached cloud keys: {e}"));
+    }
+    if handle.db_path == ":memory:" {
+        return Ok(());
+    }
+    let path = Path::new(&handle.db_path).with_extension("cloud_deks");
+    privstack_cloud::dek_registry::DekRegistry::rekey_file(&path, &old_key, &new_key)
+        .map(|_| ())
+        .map_err(|e| format!("failed to re-encrypt cached cloud keys: {e}"))
+}
+
 // ============================================================================
 // Recovery Functions
 // ============================================================================
@@ -1071,6 +1371,10 @@ pub unsafe extern "C" fn privstack_auth_reset_with_recovery(
             // Re-encrypt entity and blob stores with new key
             let _ = handle.entity_store.re_encrypt_all(&old_kb, &new_kb);
             let _ = handle.blob_store.re_encrypt_all(&old_kb, &new_kb);
+            if let Err(e) = rekey_cloud_deks(handle, &old_kb, &new_kb) {
+                eprintln!("[FFI] reset_with_recovery: {e}");
+                return PrivStackError::StorageError;
+            }
             PrivStackError::Ok
         }
         Err(privstack_vault::VaultError::RecoveryNotConfigured) => {
@@ -1127,6 +1431,10 @@ pub unsafe extern "C" fn privstack_auth_reset_with_unified_recovery(
             // Re-encrypt entity and blob stores with new key
             let _ = handle.entity_store.re_encrypt_all(&old_kb, &new_kb);
             let _ = handle.blob_store.re_encrypt_all(&old_kb, &new_kb);
+            if let Err(e) = rekey_cloud_deks(handle, &old_kb, &new_kb) {
+                eprintln!("[FFI] reset_with_unified_recovery: {e}");
+                return PrivStackError::StorageError;
+            }
         }
         Err(privstack_vault::VaultError::RecoveryNotConfigured) => {
             return PrivStackError::RecoveryNotConfigured;
@@ -1820,10 +2128,59 @@ pub extern "C" fn privstack_sync_start() -> PrivStackError {
     let orch_entity_store = Arc::clone(&handle.entity_store);
     let orch_event_store = Arc::clone(&handle.event_store);
 
-    // Always use PersonalSyncPolicy + pairing gate
-    let policy = Arc::new(PersonalSyncPolicy::new());
+    // A managed device syncs under its enterprise policy instead of pairing
+    if let Some(policy) = handle.enterprise_policy.clone() {
+        eprintln!("[FFI SYNC] privstack_sync_start: using enterprise orchestrator");
+        let (orch_handle, event_rx, command_rx, orchestrator) = create_enterprise_orchestrator(
+            handle.peer_id,
+            orch_entity_store,
+            orch_event_store,
+            OrchestratorConfig::default(),
+            policy,
+        );
+        handle.personal_policy = None;
+        return start_orchestrator(handle, transport, orch_handle, event_rx, command_rx, orchestrator);
+    }
+
+    // Otherwise use PersonalSyncPolicy + pairing gate
+    let policy =
+        Arc::new(PersonalSyncPolicy::new().with_event_store(Arc::clone(&handle.event_store)));
     handle.personal_policy = Some(policy.clone());
 
+    // Seed rule-based sync scopes persisted with the pairing state
+    let scopes: Vec<(String, SyncScope)> = handle
+        .pairing_manager
+        .lock()
+        .unwrap()
+        .sync_scopes()
+        .iter()
+        .map(|(pid, scope)| (pid.clone(), scope.clone()))
+        .collect();
+    for (pid_str, scope) in scopes {
+        if let Ok(pid) = pid_str.parse::<PeerId>() {
+            handle.runtime.block_on(policy.set_sync_scope(pid, scope));
+        }
+    }
+
+    // Register plugin-owned entity types so plugin exclusion rules resolve
+    #[cfg(feature = "wasm-plugins")]
+    {
+        let plugin_ids: Vec<String> = handle
+            .plugin_host
+            .list_plugins()
+            .into_iter()
+            .map(|m| m.id.clone())
+            .collect();
+        for plugin_id in plugin_ids {
+            if let Ok(sandbox) = handle.plugin_host.get_plugin(&plugin_id) {
+                let types: Vec<String> = sandbox.declared_entity_types().iter().cloned().collect();
+                handle
+                    .runtime
+                    .block_on(policy.register_plugin_entity_types(&plugin_id, &types));
+            }
+        }
+    }
+
     let (orch_handle, event_rx, command_rx, orchestrator) = {
         eprintln!("[FFI SYNC] privstack_sync_start: using personal orchestrator with pairing");
         create_personal_orchestrator(
@@ -1836,6 +2193,22 @@ pub extern "C" fn privstack_sync_start() -> PrivStackError {
         )
     };
 
+    start_orchestrator(handle, transport, orch_handle, event_rx, command_rx, orchestrator)
+}
+
+/// Configures the orchestrator with this device's schemas and identity key,
+/// spawns it on the runtime, and records the running sync state.
+fn start_orchestrator(
+    handle: &mut PrivStackHandle,
+    transport: Arc<TokioMutex<P2pTransport>>,
+    orch_handle: OrchestratorHandle,
+    event_rx: mpsc::Receiver<SyncEvent>,
+    command_rx: mpsc::Receiver<SyncCommand>,
+    mut orchestrator: privstack_sync::SyncOrchestrator,
+) -> PrivStackError {
+    orchestrator.set_domain_registry(handle.entity_registry.domain_registry());
+    orchestrator.set_identity_key(Arc::clone(&handle.identity_key));
+
     let transport_clone = transport.clone();
     handle.runtime.spawn(async move {
         eprintln!("[FFI SYNC] Orchestrator task starting...");
@@ -1853,6 +2226,317 @@ pub extern "C" fn privstack_sync_start() -> PrivStackError {
     PrivStackError::Ok
 }
 
+/// How often the enterprise policy file is checked for changes.
+const ENTERPRISE_POLICY_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Enterprise policy settings saved by `privstack_sync_set_enterprise_policy`.
+#[derive(Serialize, Deserialize)]
+struct EnterprisePolicySettings {
+    /// Path of the TOML or JSON policy file.
+    path: String,
+    /// Base64 Ed25519 keys of the admins who may sign the policy file.
+    trusted_keys: Vec<String>,
+}
+
+/// The settings file lives alongside the database (e.g. `data.enterprise_policy.json`).
+fn enterprise_settings_path(db_path: &str) -> Option<PathBuf> {
+    (db_path != ":memory:").then(|| Path::new(db_path).with_extension("enterprise_policy.json"))
+}
+
+fn enterprise_policy_source(settings: &EnterprisePolicySettings) -> Result<PolicyFileSource, String> {
+    let mut source = PolicyFileSource::new(&settings.path);
+    for key in &settings.trusted_keys {
+        let key = IdentityPublicKey::from_base64(key)
+            .map_err(|e| format!("invalid trusted key {key}: {e}"))?;
+        source = source.with_trusted_key(key);
+    }
+    Ok(source)
+}
+
+/// Opens the enterprise policy with the state last applied to its store,
+/// applies the policy file if it verifies, and starts watching it for changes.
+fn open_enterprise_policy(
+    handle: &mut PrivStackHandle,
+    source: PolicyFileSource,
+) -> Result<(), String> {
+    let store = if handle.db_path == ":memory:" {
+        PolicyStore::open_in_memory()
+    } else {
+        PolicyStore::new(&Path::new(&handle.db_path).with_extension("policy.db").to_string_lossy())
+    }
+    .map_err(|e| e.to_string())?;
+    let policy = handle
+        .runtime
+        .block_on(EnterpriseSyncPolicy::load(Arc::new(store)))
+        .map_err(|e| e.to_string())?
+        .with_entity_store(Arc::clone(&handle.entity_store))
+        .with_author_keys(Arc::clone(&handle.event_store))
+        .with_redaction_signer(handle.peer_id, Arc::clone(&handle.identity_key));
+    let policy = Arc::new(policy);
+    for schema in handle.entity_registry.schemas.values() {
+        handle.runtime.block_on(policy.register_schema(schema));
+    }
+    // A file older than the one last applied is refused like an unverified one
+    match source.load() {
+        Ok(file) => match handle.runtime.block_on(policy.apply_policy_file(&file)) {
+            Ok(_) => {}
+            Err(e @ privstack_sync::SyncError::InvalidPolicy(_)) => {
+                eprintln!("[FFI SYNC] Enterprise policy file not applied: {}", e)
+            }
+            Err(e) => return Err(e.to_string()),
+        },
+        Err(e) => eprintln!("[FFI SYNC] Enterprise policy file not applied: {}", e),
+    }
+
+    if let Some(watcher) = handle.enterprise_policy_watcher.take() {
+        watcher.abort();
+    }
+    let _guard = handle.runtime.enter();
+    handle.enterprise_policy_watcher = Some(EnterpriseSyncPolicy::spawn_policy_file_watcher(
+        Arc::clone(&policy),
+        source,
+        ENTERPRISE_POLICY_POLL_INTERVAL,
+    ));
+    handle.enterprise_policy = Some(policy);
+    Ok(())
+}
+
+/// Hands a schema's sensitivity labels to the enterprise policy, if one is set.
+fn register_policy_schema(handle: &PrivStackHandle, schema: &EntitySchema) {
+    if let Some(policy) = &handle.enterprise_policy {
+        handle.runtime.block_on(policy.register_schema(schema));
+    }
+}
+
+/// Re-opens the enterprise policy saved by `privstack_sync_set_enterprise_policy`.
+/// A policy file that no longer verifies leaves the last applied policy in force.
+fn restore_enterprise_policy(handle: &mut PrivStackHandle) {
+    let Some(path) = enterprise_settings_path(&handle.db_path) else {
+        return;
+    };
+    let Ok(json) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    let opened = serde_json::from_str::<EnterprisePolicySettings>(&json)
+        .map_err(|e| e.to_string())
+        .and_then(|settings| enterprise_policy_source(&settings))
+        .and_then(|source| open_enterprise_policy(handle, source));
+    if let Err(e) = opened {
+        eprintln!("[FFI SYNC] Failed to restore enterprise policy: {}", e);
+    }
+}
+
+/// Puts this device under an enterprise sync policy file.
+///
+/// The file must be signed by one of the admin keys in `trusted_keys_json`
+/// (a JSON array of base64 Ed25519 public keys), in a detached `<file>.sig`.
+/// It is applied immediately and again whenever it changes; the setting is
+/// saved and restored by `privstack_init`. Takes effect the next time sync
+/// starts, replacing pairing-based sync.
+///
+/// # Safety
+/// - `path` and `trusted_keys_json` must be valid null-terminated UTF-8 strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_sync_set_enterprise_policy(
+    path: *const c_char,
+    trusted_keys_json: *const c_char,
+) -> PrivStackError { unsafe {
+    if path.is_null() || trusted_keys_json.is_null() {
+        return PrivStackError::NullPointer;
+    }
+    let (path, keys_json) = match (
+        CStr::from_ptr(path).to_str(),
+        CStr::from_ptr(trusted_keys_json).to_str(),
+    ) {
+        (Ok(p), Ok(k)) => (p, k),
+        _ => return PrivStackError::InvalidUtf8,
+    };
+    let settings = EnterprisePolicySettings {
+        path: path.to_string(),
+        trusted_keys: match serde_json::from_str(keys_json) {
+            Ok(keys) => keys,
+            Err(_) => return PrivStackError::JsonError,
+        },
+    };
+    if settings.trusted_keys.is_empty() {
+        return PrivStackError::InvalidArgument;
+    }
+
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let source = match enterprise_policy_source(&settings) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("[FFI SYNC] set_enterprise_policy: {}", e);
+            return PrivStackError::InvalidArgument;
+        }
+    };
+    // Refuse a file that does not verify rather than leave the device unmanaged
+    match source.load() {
+        Ok(_) => {}
+        Err(e @ privstack_sync::SyncError::Auth(_)) => {
+            eprintln!("[FFI SYNC] set_enterprise_policy: {}", e);
+            return PrivStackError::AuthError;
+        }
+        Err(e) => {
+            eprintln!("[FFI SYNC] set_enterprise_policy: {}", e);
+            return PrivStackError::InvalidArgument;
+        }
+    }
+    if let Err(e) = open_enterprise_policy(handle, source) {
+        eprintln!("[FFI SYNC] set_enterprise_policy: {}", e);
+        return PrivStackError::StorageError;
+    }
+
+    if let Some(settings_path) = enterprise_settings_path(&handle.db_path) {
+        let json = serde_json::to_string(&settings).unwrap_or_default();
+        if let Err(e) = std::fs::write(&settings_path, json) {
+            eprintln!("[FFI SYNC] set_enterprise_policy: failed to save settings: {}", e);
+            return PrivStackError::StorageError;
+        }
+    }
+    PrivStackError::Ok
+}}
+
+/// An org-wide policy change made from an admin device.
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum PolicyChangeRequest {
+    AddKnownPeer {
+        peer_id: String,
+        #[serde(default)]
+        identity_key: Option<String>,
+    },
+    RemoveKnownPeer {
+        peer_id: String,
+    },
+    AddTeamMember {
+        team_id: String,
+        peer_id: String,
+    },
+    RemoveTeamMember {
+        team_id: String,
+        peer_id: String,
+    },
+    SetDeviceLimit {
+        peer_id: String,
+        #[serde(default)]
+        max_devices: Option<usize>,
+    },
+}
+
+/// Applies `request` to the local policy as a signed policy event.
+async fn replicate_policy_change(
+    policy: &EnterpriseSyncPolicy,
+    author: PeerId,
+    signer: &IdentityKeyPair,
+    request: PolicyChangeRequest,
+) -> Result<Event, PrivStackError> {
+    let peer = |id: &str| id.parse::<PeerId>().map_err(|_| PrivStackError::InvalidArgument);
+    let team = |id: &str| {
+        id.parse::<Uuid>().map(privstack_sync::TeamId).map_err(|_| PrivStackError::InvalidArgument)
+    };
+    let result = match request {
+        PolicyChangeRequest::AddKnownPeer { peer_id, identity_key } => {
+            let key = match identity_key {
+                Some(k) => Some(
+                    IdentityPublicKey::from_base64(&k).map_err(|_| PrivStackError::InvalidArgument)?,
+                ),
+                None => None,
+            };
+            policy.replicate_add_known_peer(author, signer, peer(&peer_id)?, key).await
+        }
+        PolicyChangeRequest::RemoveKnownPeer { peer_id } => {
+            policy.replicate_remove_known_peer(author, signer, peer(&peer_id)?).await
+        }
+        PolicyChangeRequest::AddTeamMember { team_id, peer_id } => {
+            policy
+                .replicate_add_team_member(author, signer, team(&team_id)?, peer(&peer_id)?)
+                .await
+        }
+        PolicyChangeRequest::RemoveTeamMember { team_id, peer_id } => {
+            policy
+                .replicate_remove_team_member(author, signer, team(&team_id)?, peer(&peer_id)?)
+                .await
+        }
+        PolicyChangeRequest::SetDeviceLimit { peer_id, max_devices } => {
+            policy.replicate_device_limit(author, signer, peer(&peer_id)?, max_devices).await
+        }
+    };
+    result.map_err(|e| {
+        eprintln!("[FFI SYNC] policy change: {}", e);
+        PrivStackError::InvalidArgument
+    })
+}
+
+/// Changes org-wide policy (known peers, team membership, device limits)
+/// from this device and replicates the change to the organization.
+///
+/// `change_json` is an object tagged by `action`: `add_known_peer`
+/// (`peer_id`, optional base64 `identity_key`), `remove_known_peer`
+/// (`peer_id`), `add_team_member` / `remove_team_member` (`team_id`,
+/// `peer_id`) or `set_device_limit` (`peer_id`, optional `max_devices`).
+///
+/// Requires an enterprise policy and Admin on the policy entity; other
+/// devices reject policy events from anyone else. The signed event is stored
+/// and sent to peers if sync is running, otherwise on the next sync.
+///
+/// # Safety
+/// - `change_json` must be a valid null-terminated UTF-8 string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_sync_policy_change(
+    change_json: *const c_char,
+) -> PrivStackError { unsafe {
+    if change_json.is_null() {
+        return PrivStackError::NullPointer;
+    }
+    let json_str = match CStr::from_ptr(change_json).to_str() {
+        Ok(s) => s,
+        Err(_) => return PrivStackError::InvalidUtf8,
+    };
+    let request: PolicyChangeRequest = match serde_json::from_str(json_str) {
+        Ok(r) => r,
+        Err(_) => return PrivStackError::JsonError,
+    };
+
+    let handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_ref() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+    let Some(policy) = handle.enterprise_policy.as_ref() else {
+        return PrivStackError::InvalidArgument;
+    };
+
+    let event = handle.runtime.block_on(async {
+        let role = policy.resolve_role(&handle.peer_id, &privstack_sync::POLICY_ENTITY_ID).await;
+        if role.is_none_or(|r| r < privstack_sync::SyncRole::Admin) {
+            return Err(PrivStackError::AuthError);
+        }
+        replicate_policy_change(policy, handle.peer_id, &handle.identity_key, request).await
+    });
+    let event = match event {
+        Ok(e) => e,
+        Err(e) => return e,
+    };
+
+    if let Err(e) = handle.event_store.save_event(&event) {
+        eprintln!("[FFI SYNC] policy change: failed to save event to store: {:?}", e);
+        return PrivStackError::StorageError;
+    }
+    if let Some(orch_handle) = &handle.orchestrator_handle
+        && let Err(e) = handle.runtime.block_on(orch_handle.record_event(event))
+    {
+        // The change is applied and stored; the next sync sends it.
+        eprintln!("[FFI SYNC] policy change: failed to queue event: {:?}", e);
+    }
+    PrivStackError::Ok
+}}
+
 /// Stops the P2P sync transport and orchestrator.
 #[unsafe(no_mangle)]
 pub extern "C" fn privstack_sync_stop() -> PrivStackError {
@@ -1994,6 +2678,9 @@ pub unsafe extern "C" fn privstack_sync_poll_event(out_json: *mut *mut c_char) -
 
     match rx.try_recv() {
         Ok(event) => {
+            if let SyncEvent::EntityUpdated { entity_id } = &event {
+                handle.plugin_host.record_synced_entity(&entity_id.to_string());
+            }
             let dto = SyncEventDto::from(event);
             match serde_json::to_string(&dto) {
                 Ok(json) => {
@@ -2235,7 +2922,7 @@ pub unsafe extern "C" fn privstack_sync_record_event(
         Err(_) => return PrivStackError::InvalidUtf8,
     };
 
-    let event: Event = match serde_json::from_str(json_str) {
+    let mut event: Event = match serde_json::from_str(json_str) {
         Ok(e) => e,
         Err(_) => return PrivStackError::JsonError,
     };
@@ -2246,6 +2933,12 @@ pub unsafe extern "C" fn privstack_sync_record_event(
         None => return PrivStackError::NotInitialized,
     };
 
+    // Only events authored by this device are signed here; anything else must
+    // already carry its author's signature.
+    if !event.is_signed() && event.peer_id == handle.peer_id {
+        handle.identity_key.sign_event(&mut event);
+    }
+
     // Save to event store immediately (same rationale as privstack_sync_snapshot).
     if let Err(e) = handle.event_store.save_event(&event) {
         eprintln!("[FFI SYNC] record_event: failed to save event to store: {:?}", e);
@@ -2294,7 +2987,12 @@ pub unsafe extern "C" fn privstack_sync_poll_events(
     let mut events = Vec::new();
     loop {
         match rx.try_recv() {
-            Ok(event) => events.push(SyncEventDto::from(event)),
+            Ok(event) => {
+                if let SyncEvent::EntityUpdated { entity_id } = &event {
+                    handle.plugin_host.record_synced_entity(&entity_id.to_string());
+                }
+                events.push(SyncEventDto::from(event));
+            }
             Err(_) => break,
         }
     }
@@ -2348,6 +3046,27 @@ pub unsafe extern "C" fn privstack_sync_document(
     }
 }}
 
+/// Signs a locally made event, stores it and hands it to whichever sync is
+/// running (P2P and cloud). Without a running sync it goes out on the next one.
+fn record_local_event(handle: &PrivStackHandle, mut event: Event) -> Result<(), PrivStackError> {
+    handle.identity_key.sign_event(&mut event);
+    if let Err(e) = handle.event_store.save_event(&event) {
+        eprintln!("[FFI SYNC] failed to save local event: {:?}", e);
+        return Err(PrivStackError::StorageError);
+    }
+    if let Some(orch_handle) = &handle.orchestrator_handle
+        && let Err(e) = handle.runtime.block_on(orch_handle.record_event(event.clone()))
+    {
+        eprintln!("[FFI SYNC] failed to queue local event: {:?}", e);
+    }
+    if let Some(tx) = &handle.cloud_event_tx
+        && tx.blocking_send(event).is_err()
+    {
+        eprintln!("[FFI SYNC] failed to queue local event for cloud sync");
+    }
+    Ok(())
+}
+
 /// Records a full entity snapshot for sync.
 ///
 /// # Safety
@@ -2388,7 +3107,8 @@ pub unsafe extern "C" fn privstack_sync_snapshot(
         None => return PrivStackError::NotInitialized,
     };
 
-    let event = Event::full_snapshot(eid, handle.peer_id, etype_str, data_str);
+    let mut event = Event::full_snapshot(eid, handle.peer_id, etype_str, data_str);
+    handle.identity_key.sign_event(&mut event);
 
     // Save to event store immediately so it's visible even if a sync cycle is in
     // progress (periodic_sync holds the command loop, blocking RecordLocalEvent).
@@ -2830,6 +3550,13 @@ pub unsafe extern "C" fn privstack_pairing_load_state(json: *const c_char) -> Pr
 
     match PairingManager::from_json(json_str) {
         Ok(manager) => {
+            if let Some(policy) = &handle.personal_policy {
+                for (pid_str, scope) in manager.sync_scopes() {
+                    if let Ok(pid) = pid_str.parse::<PeerId>() {
+                        handle.runtime.block_on(policy.set_sync_scope(pid, scope.clone()));
+                    }
+                }
+            }
             *handle.pairing_manager.lock().unwrap() = manager;
             PrivStackError::Ok
         }
@@ -3037,30 +3764,133 @@ pub unsafe extern "C" fn privstack_list_shared_peers(
     }
 }}
 
-// ============================================================================
-// Cloud Sync Functions
-// ============================================================================
-
-/// Initializes Google Drive cloud storage.
+/// Sets the rule-based sync scope for a peer (personal sharing).
+/// The scope is persisted with the pairing state and applied to the running
+/// sync policy. An empty scope (`{}`) clears it.
+///
+/// Scope JSON: `{"include_types": [...], "exclude_types": [...],
+/// "exclude_tags": [...], "exclude_plugins": [...]}` (all fields optional).
 ///
 /// # Safety
-/// - `client_id` and `client_secret` must be valid null-terminated UTF-8 strings.
+/// - `peer_id` must be a valid null-terminated UTF-8 UUID string.
+/// - `scope_json` must be a valid null-terminated UTF-8 string.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn privstack_cloud_init_google_drive(
-    client_id: *const c_char,
-    client_secret: *const c_char,
+pub unsafe extern "C" fn privstack_sync_set_peer_scope(
+    peer_id: *const c_char,
+    scope_json: *const c_char,
 ) -> PrivStackError { unsafe {
-    if client_id.is_null() || client_secret.is_null() {
+    if peer_id.is_null() || scope_json.is_null() {
         return PrivStackError::NullPointer;
     }
 
-    let client_id_str = match CStr::from_ptr(client_id).to_str() {
-        Ok(s) => s.to_string(),
+    let pid_str = match CStr::from_ptr(peer_id).to_str() {
+        Ok(s) => s,
         Err(_) => return PrivStackError::InvalidUtf8,
     };
-
-    let client_secret_str = match CStr::from_ptr(client_secret).to_str() {
-        Ok(s) => s.to_string(),
+    let json_str = match CStr::from_ptr(scope_json).to_str() {
+        Ok(s) => s,
+        Err(_) => return PrivStackError::InvalidUtf8,
+    };
+
+    let pid: PeerId = match pid_str.parse() {
+        Ok(id) => id,
+        Err(_) => return PrivStackError::JsonError,
+    };
+    let scope: SyncScope = match serde_json::from_str(json_str) {
+        Ok(s) => s,
+        Err(_) => return PrivStackError::JsonError,
+    };
+
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    handle
+        .pairing_manager
+        .lock()
+        .unwrap()
+        .set_sync_scope(&pid.to_string(), scope.clone());
+
+    if let Some(policy) = &handle.personal_policy {
+        handle.runtime.block_on(policy.set_sync_scope(pid, scope));
+    }
+
+    PrivStackError::Ok
+}}
+
+/// Gets the rule-based sync scope for a peer as JSON (`{}` if none is set).
+///
+/// # Safety
+/// - `peer_id` must be a valid null-terminated UTF-8 UUID string.
+/// - `out_json` must be a valid pointer. Result must be freed with `privstack_free_string`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_sync_get_peer_scope(
+    peer_id: *const c_char,
+    out_json: *mut *mut c_char,
+) -> PrivStackError { unsafe {
+    if peer_id.is_null() || out_json.is_null() {
+        return PrivStackError::NullPointer;
+    }
+
+    let pid_str = match CStr::from_ptr(peer_id).to_str() {
+        Ok(s) => s,
+        Err(_) => return PrivStackError::InvalidUtf8,
+    };
+    let pid: PeerId = match pid_str.parse() {
+        Ok(id) => id,
+        Err(_) => return PrivStackError::JsonError,
+    };
+
+    let handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_ref() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let scope = handle
+        .pairing_manager
+        .lock()
+        .unwrap()
+        .sync_scope(&pid.to_string())
+        .cloned()
+        .unwrap_or_default();
+
+    match serde_json::to_string(&scope) {
+        Ok(json) => {
+            let c_str = CString::new(json).unwrap();
+            *out_json = c_str.into_raw();
+            PrivStackError::Ok
+        }
+        Err(_) => PrivStackError::JsonError,
+    }
+}}
+
+// ============================================================================
+// Cloud Sync Functions
+// ============================================================================
+
+/// Initializes Google Drive cloud storage.
+///
+/// # Safety
+/// - `client_id` and `client_secret` must be valid null-terminated UTF-8 strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_cloud_init_google_drive(
+    client_id: *const c_char,
+    client_secret: *const c_char,
+) -> PrivStackError { unsafe {
+    if client_id.is_null() || client_secret.is_null() {
+        return PrivStackError::NullPointer;
+    }
+
+    let client_id_str = match CStr::from_ptr(client_id).to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return PrivStackError::InvalidUtf8,
+    };
+
+    let client_secret_str = match CStr::from_ptr(client_secret).to_str() {
+        Ok(s) => s.to_string(),
         Err(_) => return PrivStackError::InvalidUtf8,
     };
 
@@ -4076,6 +4906,22 @@ pub(crate) fn check_license_writable(handle: &PrivStackHandle) -> Result<(), Pri
     }
 }
 
+/// Reports a write made through the shell's SDK to plugins observing
+/// entity changes.
+fn record_entity_change(
+    handle: &PrivStackHandle,
+    entity_id: &str,
+    entity_type: &str,
+    kind: EntityChangeKind,
+) {
+    handle.plugin_host.record_entity_change(EntityChange::new(
+        entity_id,
+        entity_type,
+        kind,
+        CHANGE_SOURCE_HOST,
+    ));
+}
+
 fn execute_generic(handle: &PrivStackHandle, req: &SdkRequest) -> SdkResponse {
     // Block write operations when license is not usable (expired trial, past grace period)
     let is_mutation = matches!(
@@ -4172,6 +5018,12 @@ fn execute_generic(handle: &PrivStackHandle, req: &SdkRequest) -> SdkResponse {
 
             match handle.entity_store.save_entity(&entity, schema) {
                 Ok(_) => {
+                    let kind = if req.action == "update" {
+                        EntityChangeKind::Updated
+                    } else {
+                        EntityChangeKind::Created
+                    };
+                    record_entity_change(handle, &entity.id, &entity.entity_type, kind);
                     if let Some(h) = handler {
                         h.on_after_load(&mut entity);
                     }
@@ -4237,7 +5089,10 @@ fn execute_generic(handle: &PrivStackHandle, req: &SdkRequest) -> SdkResponse {
                 None => return SdkResponse::err("missing_id", "Delete requires entity_id"),
             };
             match handle.entity_store.delete_entity(id) {
-                Ok(_) => SdkResponse::ok_empty(),
+                Ok(_) => {
+                    record_entity_change(handle, id, &req.entity_type, EntityChangeKind::Deleted);
+                    SdkResponse::ok_empty()
+                }
                 Err(e) => SdkResponse::err("storage_error", &format!("Delete failed: {e}")),
             }
         }
@@ -4247,7 +5102,10 @@ fn execute_generic(handle: &PrivStackHandle, req: &SdkRequest) -> SdkResponse {
                 None => return SdkResponse::err("missing_id", "Trash requires entity_id"),
             };
             match handle.entity_store.trash_entity(id) {
-                Ok(_) => SdkResponse::ok_empty(),
+                Ok(_) => {
+                    record_entity_change(handle, id, &req.entity_type, EntityChangeKind::Deleted);
+                    SdkResponse::ok_empty()
+                }
                 Err(e) => SdkResponse::err("storage_error", &format!("Trash failed: {e}")),
             }
         }
@@ -4257,7 +5115,10 @@ fn execute_generic(handle: &PrivStackHandle, req: &SdkRequest) -> SdkResponse {
                 None => return SdkResponse::err("missing_id", "Restore requires entity_id"),
             };
             match handle.entity_store.restore_entity(id) {
-                Ok(_) => SdkResponse::ok_empty(),
+                Ok(_) => {
+                    record_entity_change(handle, id, &req.entity_type, EntityChangeKind::Created);
+                    SdkResponse::ok_empty()
+                }
                 Err(e) => SdkResponse::err("storage_error", &format!("Restore failed: {e}")),
             }
         }
@@ -4402,6 +5263,12 @@ fn execute_generic(handle: &PrivStackHandle, req: &SdkRequest) -> SdkResponse {
 
                     match handle.entity_store.save_entity(&entity, schema) {
                         Ok(_) => {
+                            record_entity_change(
+                                handle,
+                                &entity.id,
+                                &entity.entity_type,
+                                EntityChangeKind::Updated,
+                            );
                             let mut result = entity.clone();
                             if let Some(h) = handler {
                                 h.on_after_load(&mut result);
@@ -4444,6 +5311,7 @@ pub unsafe extern "C" fn privstack_register_entity_type(schema_json: *const c_ch
         None => return -4,
     };
 
+    register_policy_schema(handle, &schema);
     handle.entity_registry.register_schema(schema);
     0
 }}
@@ -4508,7 +5376,52 @@ unsafe fn search_inner(query_json: *const c_char) -> SdkResponse {
 mod plugin_ffi {
 use super::*;
 
+/// Registers a loaded plugin's `entity-handler` hooks for its entity types,
+/// so SDK writes and sync merges go through them. Its schemas' sensitivity
+/// labels are handed to the enterprise policy as well.
+fn register_plugin_entity_handler(handle: &mut PrivStackHandle, plugin_id: &str) {
+    let Ok(sandbox) = handle.plugin_host.get_plugin(plugin_id) else {
+        return;
+    };
+    for schema in &sandbox.state().schemas {
+        if let Ok(schema) = schema.to_core_schema() {
+            register_policy_schema(handle, &schema);
+        }
+    }
+    let Some(handler) = sandbox.entity_handler() else {
+        return;
+    };
+    let types: Vec<String> = sandbox.declared_entity_types().iter().cloned().collect();
+    for entity_type in types {
+        handle.entity_registry.register_handler(entity_type, handler.clone());
+    }
+}
+
+/// Drops the hooks of plugins the host disabled after a crash. Called after
+/// every call into a plugin that may crash it.
+fn unregister_disabled_plugin_handlers(handle: &mut PrivStackHandle) {
+    for (plugin_id, entity_types) in handle.plugin_host.take_disabled_plugins() {
+        eprintln!("[FFI] Plugin {} disabled after a crash, dropping its entity hooks", plugin_id);
+        for entity_type in &entity_types {
+            handle.entity_registry.unregister_handler(entity_type);
+        }
+    }
+}
+
+/// Drops the hooks registered for a plugin's entity types.
+fn unregister_plugin_entity_handler(handle: &mut PrivStackHandle, plugin_id: &str) {
+    let types: Vec<String> = match handle.plugin_host.get_plugin(plugin_id) {
+        Ok(sandbox) => sandbox.declared_entity_types().iter().cloned().collect(),
+        Err(_) => return,
+    };
+    for entity_type in &types {
+        handle.entity_registry.unregister_handler(entity_type);
+    }
+}
+
 /// Loads a Wasm plugin into the plugin host manager.
+/// Permissions above Tier 1 in `permissions_json` only take effect if the
+/// plugin's installed package declares them.
 ///
 /// # Safety
 /// - `metadata_json` must be a valid null-terminated UTF-8 JSON string.
@@ -4579,6 +5492,7 @@ pub unsafe extern "C" fn privstack_plugin_unload(plugin_id: *const c_char) -> Pr
         None => return PrivStackError::NullPointer,
     };
 
+    unregister_plugin_entity_handler(handle, id);
     match handle.plugin_host.unload_plugin(id) {
         Ok(()) => PrivStackError::Ok,
         Err(privstack_plugin_host::PluginHostError::PluginNotFound(_)) => PrivStackError::PluginNotFound,
@@ -4830,6 +5744,7 @@ pub unsafe extern "C" fn privstack_plugin_search_items(
     };
 
     let results = handle.plugin_host.query_all_linkable_items(q, max_results as u32);
+    unregister_disabled_plugin_handlers(handle);
     let json = serde_json::to_string(&results).unwrap_or_else(|_| "[]".to_string());
     to_c_string(&json)
 }}
@@ -4859,7 +5774,9 @@ pub unsafe extern "C" fn privstack_plugin_navigate_to_item(
         None => return PrivStackError::NullPointer,
     };
 
-    match handle.plugin_host.navigate_to_item(pid, iid) {
+    let result = handle.plugin_host.navigate_to_item(pid, iid);
+    unregister_disabled_plugin_handlers(handle);
+    match result {
         Ok(()) => PrivStackError::Ok,
         Err(_) => PrivStackError::PluginError,
     }
@@ -4898,7 +5815,9 @@ pub unsafe extern "C" fn privstack_plugin_get_entity_view_data(
         None => return to_c_string(r#"{}"#),
     };
 
-    match handle.plugin_host.get_entity_view_data(pid, iid) {
+    let result = handle.plugin_host.get_entity_view_data(pid, iid);
+    unregister_disabled_plugin_handlers(handle);
+    match result {
         Ok(json) => to_c_string(&json),
         Err(e) => {
             eprintln!(
@@ -4914,8 +5833,15 @@ pub unsafe extern "C" fn privstack_plugin_get_entity_view_data(
 // Plugin Install / Update (P6.4)
 // ============================================================
 
-/// Installs a plugin from a .ppk file path. Validates the manifest and loads the Wasm module.
-/// Returns Ok on success, PluginError on failure.
+/// Installs a plugin from a .ppk file path. Verifies that a trusted publisher
+/// signed the package, validates the manifest and loads the Wasm module.
+/// The module's hash is saved with its signer and the manifest's grants, so
+/// the same bytes can later be loaded with `privstack_plugin_load_wasm`, in
+/// this session or after a restart; a package without one is loaded
+/// metadata-only. Installing the same package again is harmless, so the shell
+/// installs the signed .ppk of each bundled plugin at startup.
+/// Returns Ok on success, PluginPermissionDenied if the package is unsigned,
+/// untrusted or tampered with, PluginError on other failures.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn privstack_plugin_install_ppk(
     ppk_path: *const c_char,
@@ -4952,6 +5878,16 @@ pub unsafe extern "C" fn privstack_plugin_install_ppk(
         return PrivStackError::PluginError;
     }
 
+    // Only packages signed by a trusted publisher are installed. First-party
+    // status comes from the signing key, not the plugin id.
+    let (signing_key, is_first_party) = match handle.trust_store.verify(&package) {
+        Ok(signer) => (signer.key.to_hex(), signer.is_first_party()),
+        Err(e) => {
+            eprintln!("[FFI] install_ppk({}) rejected: {}", package.manifest.id, e);
+            return PrivStackError::PluginPermissionDenied;
+        }
+    };
+
     // Convert PpkManifest to WitPluginMetadata
     let m = &package.manifest;
     let category = match m.category.to_lowercase().as_str() {
@@ -5005,31 +5941,171 @@ pub unsafe extern "C" fn privstack_plugin_install_ppk(
                     searchable: f.searchable,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: f.sensitivity.clone(),
                 }
             }).collect(),
             merge_strategy,
         }
     }).collect();
 
-    // Determine resource limits and permissions based on first-party status
-    let is_first_party = m.is_first_party();
+    // Resource limits follow first-party status; permissions are what the
+    // manifest declares, minus what the policy denies.
     let resource_limits = if is_first_party {
         privstack_plugin_host::ResourceLimits::first_party()
     } else {
         privstack_plugin_host::ResourceLimits::third_party()
     };
-    let permissions = if is_first_party {
-        privstack_plugin_host::PermissionSet::default_first_party()
-    } else {
-        privstack_plugin_host::PermissionSet::default_third_party()
+    let permissions = privstack_plugin_host::PermissionSet::from_declared(&m.permissions);
+    if apply_package_manifest(&mut handle.plugin_host, m).is_err() {
+        return PrivStackError::PluginError;
+    }
+
+    let wasm_sha256 = package.wasm.as_ref().map(|wasm| {
+        handle.plugin_host.trust_build(&m.id, wasm, signing_key.clone(), is_first_party)
+    });
+    let installed = InstalledPackage {
+        manifest: m.clone(),
+        wasm_sha256,
+        signing_key,
+        first_party: is_first_party,
     };
+    handle.installed_packages.insert(m.id.clone(), installed);
+    save_plugin_trust(handle);
 
-    match handle.plugin_host.load_plugin(metadata, schemas, permissions, resource_limits) {
+    let loaded = match &package.wasm {
+        Some(wasm) => {
+            handle.plugin_host.load_plugin_from_wasm_bytes(wasm).map(|plugin_id| {
+                register_plugin_entity_handler(handle, &plugin_id);
+            })
+        }
+        None => handle.plugin_host.load_plugin(metadata, schemas, permissions, resource_limits),
+    };
+    match loaded {
         Ok(()) => PrivStackError::Ok,
-        Err(_) => PrivStackError::PluginError,
+        Err(privstack_plugin_host::PluginHostError::PolicyDenied(_)) => {
+            PrivStackError::PluginPermissionDenied
+        }
+        Err(e) => {
+            eprintln!("[FFI] install_ppk({}) failed to load: {:?}", m.id, e);
+            PrivStackError::PluginError
+        }
+    }
+}}
+
+/// Returns what installing a .ppk would grant the plugin, for the consent
+/// prompt: a JSON object with `always_granted`, `just_in_time`,
+/// `install_time` and `denied_by_policy` arrays of `{permission, description}`.
+/// Returns `{}` if the package cannot be read.
+///
+/// # Safety
+/// - `ppk_path` must be a valid null-terminated UTF-8 string.
+/// - The returned pointer must be freed with `privstack_free_string`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_ppk_consent_summary(
+    ppk_path: *const c_char,
+) -> *mut c_char { unsafe {
+    let handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_ref() {
+        Some(h) => h,
+        None => return to_c_string("{}"),
+    };
+
+    let path_str = match nullable_cstr_to_str(ppk_path) {
+        Some(s) => s,
+        None => return to_c_string("{}"),
+    };
+
+    let file = match std::fs::File::open(Path::new(path_str)) {
+        Ok(f) => f,
+        Err(_) => return to_c_string("{}"),
+    };
+
+    let reader = std::io::BufReader::new(file);
+    let package = match privstack_ppk::PpkPackage::open(reader) {
+        Ok(p) => p,
+        Err(_) => return to_c_string("{}"),
+    };
+
+    let summary = handle.plugin_host.consent_summary(&package.manifest.permissions);
+    to_c_string(&serde_json::to_string(&summary).unwrap_or_else(|_| "{}".into()))
+}}
+
+/// Trusts a publisher's Ed25519 key (64 hex characters) for .ppk installs.
+/// The key is saved and trusted again after a restart.
+///
+/// # Safety
+/// - `name` and `key_hex` must be valid null-terminated UTF-8 strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_trust_publisher(
+    name: *const c_char,
+    key_hex: *const c_char,
+) -> PrivStackError { unsafe {
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let (name, key_hex) = match (nullable_cstr_to_str(name), nullable_cstr_to_str(key_hex)) {
+        (Some(name), Some(key_hex)) => (name, key_hex),
+        _ => return PrivStackError::NullPointer,
+    };
+
+    let added = privstack_ppk::VerifyingKey::from_hex(key_hex)
+        .and_then(|key| handle.trust_store.add(name, key, privstack_ppk::TrustSource::User));
+    match added {
+        Ok(()) => {
+            save_plugin_trust(handle);
+            PrivStackError::Ok
+        }
+        Err(_) => PrivStackError::InvalidArgument,
+    }
+}}
+
+/// Stops trusting a publisher key. Plugins it signed stay installed. Returns
+/// NotFound if the key was not trusted; the built-in key cannot be removed.
+///
+/// # Safety
+/// - `key_hex` must be a valid null-terminated UTF-8 string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_untrust_publisher(key_hex: *const c_char) -> PrivStackError { unsafe {
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let key = match nullable_cstr_to_str(key_hex).map(privstack_ppk::VerifyingKey::from_hex) {
+        Some(Ok(key)) => key,
+        Some(Err(_)) => return PrivStackError::InvalidArgument,
+        None => return PrivStackError::NullPointer,
+    };
+
+    if handle.trust_store.remove(&key) {
+        save_plugin_trust(handle);
+        PrivStackError::Ok
+    } else {
+        PrivStackError::NotFound
     }
 }}
 
+/// Returns the trusted publishers as a JSON array of `{name, key, source}`
+/// objects; `source` is `builtin`, `enterprise` or `user`.
+///
+/// # Safety
+/// - The returned pointer must be freed with `privstack_free_string`.
+#[unsafe(no_mangle)]
+pub extern "C" fn privstack_plugin_trusted_publishers() -> *mut c_char {
+    let handle = HANDLE.lock().unwrap();
+    match handle.as_ref() {
+        Some(h) => {
+            let publishers = h.trust_store.publishers();
+            to_c_string(&serde_json::to_string(publishers).unwrap_or_else(|_| "[]".into()))
+        }
+        None => to_c_string("[]"),
+    }
+}
+
 // ============================================================
 // Plugin Wasm Runtime (Phase 4: real command routing)
 // ============================================================
@@ -5037,14 +6113,18 @@ pub unsafe extern "C" fn privstack_plugin_install_ppk(
 /// Loads a plugin from a .wasm component file path.
 /// Returns the plugin ID via out_plugin_id on success.
 ///
+/// The file must be the Wasm of a package installed with
+/// `privstack_plugin_install_ppk`, in this session or an earlier one; it then
+/// gets that package's declared permissions and first-party status. In dev
+/// mode other files load as unsigned third-party plugins. Anything else is
+/// PluginPermissionDenied.
+///
 /// # Safety
 /// - `wasm_path` must be a valid null-terminated UTF-8 file path.
-/// - `permissions_json` must be a valid null-terminated UTF-8 JSON string.
 /// - `out_plugin_id` receives a heap-allocated C string (free with `privstack_free_string`).
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn privstack_plugin_load_wasm(
     wasm_path: *const c_char,
-    permissions_json: *const c_char,
     out_plugin_id: *mut *mut c_char,
 ) -> PrivStackError { unsafe {
     let mut handle = HANDLE.lock().unwrap();
@@ -5058,24 +6138,11 @@ pub unsafe extern "C" fn privstack_plugin_load_wasm(
         None => return PrivStackError::NullPointer,
     };
 
-    let permissions: privstack_plugin_host::PermissionSet = if let Some(p_str) =
-        nullable_cstr_to_str(permissions_json)
-    {
-        serde_json::from_str(p_str).unwrap_or_else(|_| {
-            privstack_plugin_host::PermissionSet::default_first_party()
-        })
-    } else {
-        privstack_plugin_host::PermissionSet::default_first_party()
-    };
-
-    let resource_limits = privstack_plugin_host::ResourceLimits::first_party();
     let path = Path::new(path_str);
 
-    match handle
-        .plugin_host
-        .load_plugin_from_wasm(path, permissions, resource_limits)
-    {
+    match handle.plugin_host.load_plugin_from_wasm(path) {
         Ok(plugin_id) => {
+            register_plugin_entity_handler(handle, &plugin_id);
             if !out_plugin_id.is_null() {
                 *out_plugin_id = to_c_string(&plugin_id);
             }
@@ -5095,8 +6162,9 @@ pub unsafe extern "C" fn privstack_plugin_load_wasm(
 }}
 
 /// Loads multiple Wasm plugins in parallel (compilation is concurrent).
+/// Each file is admitted as by `privstack_plugin_load_wasm`.
 ///
-/// Input: JSON array `[{"path": "...", "permissions": {...}}, ...]`
+/// Input: JSON array `[{"path": "..."}, ...]`
 /// Output: JSON array `[{"plugin_id": "...", "error": null}, ...]`
 ///
 /// # Safety
@@ -5109,8 +6177,6 @@ pub unsafe extern "C" fn privstack_plugin_load_wasm_batch(
     #[derive(Deserialize)]
     struct BatchEntry {
         path: String,
-        #[serde(default)]
-        permissions: Option<serde_json::Value>,
     }
 
     #[derive(Serialize)]
@@ -5150,19 +6216,11 @@ pub unsafe extern "C" fn privstack_plugin_load_wasm_batch(
         }
     };
 
-    let tuples: Vec<_> = entries
-        .into_iter()
-        .map(|e| {
-            let perms: privstack_plugin_host::PermissionSet = e
-                .permissions
-                .and_then(|v| serde_json::from_value(v).ok())
-                .unwrap_or_else(privstack_plugin_host::PermissionSet::default_first_party);
-            let limits = privstack_plugin_host::ResourceLimits::first_party();
-            (std::path::PathBuf::from(e.path), perms, limits)
-        })
-        .collect();
-
-    let results = handle.plugin_host.load_plugins_from_wasm_parallel(tuples);
+    let paths = entries.into_iter().map(|e| std::path::PathBuf::from(e.path)).collect();
+    let results = handle.plugin_host.load_plugins_from_wasm_parallel(paths);
+    for plugin_id in results.iter().flatten() {
+        register_plugin_entity_handler(handle, plugin_id);
+    }
 
     let batch_results: Vec<BatchResult> = results
         .into_iter()
@@ -5221,7 +6279,9 @@ pub unsafe extern "C" fn privstack_plugin_send_command(
     };
     let args = nullable_cstr_to_str(args_json).unwrap_or("{}");
 
-    match handle.plugin_host.send_command(id, cmd, args) {
+    let result = handle.plugin_host.send_command(id, cmd, args);
+    unregister_disabled_plugin_handlers(handle);
+    match result {
         Ok(result_json) => to_c_string(&result_json),
         Err(e) => to_c_string(&format!(
             r#"{{"success":false,"error":"{}","error_code":23}}"#,
@@ -5230,8 +6290,8 @@ pub unsafe extern "C" fn privstack_plugin_send_command(
     }
 }}
 
-/// Fetch a URL on behalf of a plugin, checking its Network permission.
-/// Returns the response body bytes. Caller must free with `privstack_free_bytes`.
+/// Fetch a URL on behalf of a plugin, checking its Network permission and
+/// network policy. Returns the response body bytes. Caller must free with `privstack_free_bytes`.
 ///
 /// # Safety
 /// - `plugin_id` and `url` must be valid null-terminated UTF-8.
@@ -5282,87 +6342,598 @@ pub unsafe extern "C" fn privstack_plugin_fetch_url(
     }
 }}
 
-/// Gets the view state JSON from a plugin's `get_view_state()` export.
-/// Returns JSON string (caller must free with `privstack_free_string`).
+/// Delivers pending entity changes to plugins exporting `entity-observer`.
+/// The shell calls this on a timer; changes are only handed out once writes
+/// have paused for the debounce window. Returns the number of plugin calls.
+#[unsafe(no_mangle)]
+pub extern "C" fn privstack_plugin_deliver_entity_changes() -> c_int {
+    let mut handle = HANDLE.lock().unwrap();
+    match handle.as_mut() {
+        Some(h) => {
+            let calls = h.plugin_host.deliver_entity_changes();
+            unregister_disabled_plugin_handlers(h);
+            // Plugins' writes to synced settings replicate like any entity.
+            for change in h.plugin_host.take_synced_setting_changes() {
+                record_setting_event(h, &change.entity_id);
+            }
+            calls as c_int
+        }
+        None => 0,
+    }
+}
+
+/// Runs the scheduled jobs that are due on loaded plugins. The shell calls
+/// this on a timer (about once a minute is enough: the shortest interval is
+/// 60s). Returns the number of jobs run.
+#[unsafe(no_mangle)]
+pub extern "C" fn privstack_plugin_run_scheduled_jobs() -> c_int {
+    let mut handle = HANDLE.lock().unwrap();
+    match handle.as_mut() {
+        Some(h) => {
+            let count = h.plugin_host.run_due_jobs();
+            unregister_disabled_plugin_handlers(h);
+            count as c_int
+        }
+        None => 0,
+    }
+}
+
+/// Lists a plugin's scheduled jobs as a JSON array of
+/// `{job_id, schedule, next_run_at, last_run_at, consecutive_failures, last_error, ...}`
+/// objects. The plugin does not need to be loaded.
+///
+/// # Safety
+/// - `plugin_id` must be a valid null-terminated UTF-8 string.
+/// - The returned pointer must be freed with `privstack_free_string`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_list_jobs(plugin_id: *const c_char) -> *mut c_char { unsafe {
+    let handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_ref() {
+        Some(h) => h,
+        None => return to_c_string("[]"),
+    };
+
+    let id = match nullable_cstr_to_str(plugin_id) {
+        Some(s) => s,
+        None => return to_c_string("[]"),
+    };
+
+    match handle.plugin_host.scheduled_jobs(id) {
+        Ok(jobs) => to_c_string(&serde_json::to_string(&jobs).unwrap_or_else(|_| "[]".into())),
+        Err(e) => {
+            eprintln!("[FFI] plugin_list_jobs({}) failed: {}", id, e);
+            to_c_string("[]")
+        }
+    }
+}}
+
+/// Sets a plugin's outbound network policy (allowed host patterns, whether
+/// private/LAN targets were granted, response size cap, rate limit, timeout).
+/// Missing fields take their defaults, so a policy without `allowed_hosts`
+/// refuses every request. The policy is remembered across loads.
+///
+/// # Safety
+/// - `plugin_id` and `policy_json` must be valid null-terminated UTF-8 strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_set_network_policy(
+    plugin_id: *const c_char,
+    policy_json: *const c_char,
+) -> PrivStackError { unsafe {
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let (id, json) = match (nullable_cstr_to_str(plugin_id), nullable_cstr_to_str(policy_json)) {
+        (Some(id), Some(json)) => (id, json),
+        _ => return PrivStackError::NullPointer,
+    };
+
+    let policy: privstack_plugin_host::NetworkPolicy = match serde_json::from_str(json) {
+        Ok(p) => p,
+        Err(_) => return PrivStackError::JsonError,
+    };
+
+    handle.plugin_host.set_network_policy(id, policy);
+    PrivStackError::Ok
+}}
+
+/// Sets the event bus topics a plugin publishes, from its manifest's
+/// `event_topics` (a JSON array of topic names). Remembered across loads.
+/// Returns InvalidArgument for malformed topic names.
+///
+/// # Safety
+/// - `plugin_id` and `topics_json` must be valid null-terminated UTF-8 strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_set_event_topics(
+    plugin_id: *const c_char,
+    topics_json: *const c_char,
+) -> PrivStackError { unsafe {
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let (id, json) = match (nullable_cstr_to_str(plugin_id), nullable_cstr_to_str(topics_json)) {
+        (Some(id), Some(json)) => (id, json),
+        _ => return PrivStackError::NullPointer,
+    };
+
+    let topics: Vec<String> = match serde_json::from_str(json) {
+        Ok(t) => t,
+        Err(_) => return PrivStackError::JsonError,
+    };
+
+    match handle.plugin_host.set_event_topics(id, topics) {
+        Ok(()) => PrivStackError::Ok,
+        Err(_) => PrivStackError::InvalidArgument,
+    }
+}}
+
+/// Delivers events published on the inter-plugin event bus to plugins
+/// exporting `event-subscriber`. The shell calls this on a timer, like
+/// `privstack_plugin_deliver_entity_changes`. Returns the number of plugin calls.
+#[unsafe(no_mangle)]
+pub extern "C" fn privstack_plugin_deliver_events() -> c_int {
+    let mut handle = HANDLE.lock().unwrap();
+    match handle.as_mut() {
+        Some(h) => {
+            let count = h.plugin_host.deliver_events();
+            unregister_disabled_plugin_handlers(h);
+            count as c_int
+        }
+        None => 0,
+    }
+}
+
+/// Returns audited plugin actions (event subscriptions and refused
+/// publishes) as a JSON array of
+/// `{plugin_id, action, target, allowed, reason, timestamp}` objects, oldest first.
+///
+/// # Safety
+/// - The returned pointer must be freed with `privstack_free_string`.
+#[unsafe(no_mangle)]
+pub extern "C" fn privstack_plugin_audit_log() -> *mut c_char {
+    let handle = HANDLE.lock().unwrap();
+    match handle.as_ref() {
+        Some(h) => {
+            let entries = h.plugin_host.plugin_audit_log();
+            to_c_string(&serde_json::to_string(&entries).unwrap_or_else(|_| "[]".into()))
+        }
+        None => to_c_string("[]"),
+    }
+}
+
+/// Returns crash reports as a JSON array of
+/// `{plugin_id, operation, message, outcome, restart_error, timestamp}`
+/// objects, oldest first. `outcome` is `restarted`, `restart_failed` or
+/// `disabled`. Pass a null `plugin_id` for the reports of all plugins.
+///
+/// # Safety
+/// - `plugin_id` must be null or a valid null-terminated UTF-8 string.
+/// - The returned pointer must be freed with `privstack_free_string`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_crash_reports(plugin_id: *const c_char) -> *mut c_char { unsafe {
+    let handle = HANDLE.lock().unwrap();
+    match handle.as_ref() {
+        Some(h) => {
+            let reports = h.plugin_host.crash_reports(nullable_cstr_to_str(plugin_id));
+            to_c_string(&serde_json::to_string(&reports).unwrap_or_else(|_| "[]".into()))
+        }
+        None => to_c_string("[]"),
+    }
+}}
+
+/// Re-enables a plugin that was disabled after repeated crashes. The shell
+/// must load it again. Returns `PluginNotFound` if it was not disabled.
+///
+/// # Safety
+/// - `plugin_id` must be a valid null-terminated UTF-8 string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_reset_circuit_breaker(
+    plugin_id: *const c_char,
+) -> PrivStackError { unsafe {
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let id = match nullable_cstr_to_str(plugin_id) {
+        Some(s) => s,
+        None => return PrivStackError::NullPointer,
+    };
+
+    if handle.plugin_host.reset_circuit_breaker(id) {
+        PrivStackError::Ok
+    } else {
+        PrivStackError::PluginNotFound
+    }
+}}
+
+/// Turns plugin dev mode (hot reload) on or off. Turning it off stops all
+/// watches.
+#[unsafe(no_mangle)]
+pub extern "C" fn privstack_plugin_set_dev_mode(enabled: bool) -> PrivStackError {
+    let mut handle = HANDLE.lock().unwrap();
+    match handle.as_mut() {
+        Some(h) => {
+            h.plugin_host.set_dev_mode(enabled);
+            PrivStackError::Ok
+        }
+        None => PrivStackError::NotInitialized,
+    }
+}
+
+/// Watches a loaded plugin's `.wasm` file and reloads the plugin when it
+/// changes. Requires dev mode; plugins installed from a signed package
+/// cannot be watched.
+///
+/// # Safety
+/// - `plugin_id` and `wasm_path` must be valid null-terminated UTF-8 strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_watch(
+    plugin_id: *const c_char,
+    wasm_path: *const c_char,
+) -> PrivStackError { unsafe {
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let (id, path) = match (nullable_cstr_to_str(plugin_id), nullable_cstr_to_str(wasm_path)) {
+        (Some(id), Some(path)) => (id, path),
+        _ => return PrivStackError::NullPointer,
+    };
+
+    match handle.plugin_host.watch_plugin(id, std::path::PathBuf::from(path)) {
+        Ok(()) => PrivStackError::Ok,
+        Err(privstack_plugin_host::PluginHostError::PluginNotFound(_)) => PrivStackError::PluginNotFound,
+        Err(privstack_plugin_host::PluginHostError::PolicyDenied(_)) => {
+            PrivStackError::PluginPermissionDenied
+        }
+        Err(_) => PrivStackError::PluginError,
+    }
+}}
+
+/// Stops watching a plugin's `.wasm` file.
+///
+/// # Safety
+/// - `plugin_id` must be a valid null-terminated UTF-8 string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_unwatch(plugin_id: *const c_char) -> PrivStackError { unsafe {
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let id = match nullable_cstr_to_str(plugin_id) {
+        Some(s) => s,
+        None => return PrivStackError::NullPointer,
+    };
+
+    if handle.plugin_host.unwatch_plugin(id) {
+        PrivStackError::Ok
+    } else {
+        PrivStackError::PluginNotFound
+    }
+}}
+
+/// Reloads watched plugins whose `.wasm` file changed. Call periodically in
+/// dev mode. Returns a JSON array of `{plugin_id, wasm_path, error, timestamp}`
+/// reload events; `error` is null if the new build is running.
+///
+/// # Safety
+/// - The returned pointer must be freed with `privstack_free_string`.
+#[unsafe(no_mangle)]
+pub extern "C" fn privstack_plugin_poll_reloads() -> *mut c_char {
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => return to_c_string("[]"),
+    };
+
+    let events = handle.plugin_host.poll_dev_reloads();
+    for event in events.iter().filter(|event| event.error.is_none()) {
+        register_plugin_entity_handler(handle, &event.plugin_id);
+    }
+    to_c_string(&serde_json::to_string(&events).unwrap_or_else(|_| "[]".into()))
+}
+
+/// Gets the view state JSON from a plugin's `get_view_state()` export.
+/// Returns JSON string (caller must free with `privstack_free_string`).
+///
+/// # Safety
+/// - `plugin_id` must be a valid null-terminated UTF-8 string.
+/// - The returned pointer must be freed with `privstack_free_string`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_get_view_state(
+    plugin_id: *const c_char,
+) -> *mut c_char { unsafe {
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => {
+            return to_c_string(r#"{"components":{"type":"error","message":"Core not initialized"}}"#);
+        }
+    };
+
+    let id = match nullable_cstr_to_str(plugin_id) {
+        Some(s) => s,
+        None => {
+            return to_c_string(r#"{"components":{"type":"error","message":"Invalid plugin ID (null)"}}"#);
+        }
+    };
+
+    let result = handle.plugin_host.get_view_state(id);
+    unregister_disabled_plugin_handlers(handle);
+    match result {
+        Ok(json) => to_c_string(&json),
+        Err(e) => {
+            eprintln!("[privstack-ffi] get_view_state({}) failed: {:?}", id, e);
+            let msg = format!(
+                r#"{{"components":{{"type":"error","message":"get_view_state failed: {}"}}}}"#,
+                e.to_string().replace('"', "'")
+            );
+            to_c_string(&msg)
+        }
+    }
+}}
+
+/// Gets the raw view data JSON from a plugin's `get_view_data()` export.
+/// Used for host-side template evaluation. Returns JSON string (caller must
+/// free with `privstack_free_string`).
+///
+/// # Safety
+/// - `plugin_id` must be a valid null-terminated UTF-8 string.
+/// - The returned pointer must be freed with `privstack_free_string`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_get_view_data(
+    plugin_id: *const c_char,
+) -> *mut c_char { unsafe {
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => {
+            return to_c_string(r#"{}"#);
+        }
+    };
+
+    let id = match nullable_cstr_to_str(plugin_id) {
+        Some(s) => s,
+        None => {
+            return to_c_string(r#"{}"#);
+        }
+    };
+
+    let result = handle.plugin_host.get_view_data(id);
+    unregister_disabled_plugin_handlers(handle);
+    match result {
+        Ok(json) => to_c_string(&json),
+        Err(e) => {
+            eprintln!("[privstack-ffi] get_view_data({}) failed: {:?}", id, e);
+            to_c_string(r#"{}"#)
+        }
+    }
+}}
+
+/// Activates a plugin — calls its `activate()` export.
+/// Must be called after loading and entity type registration.
+///
+/// # Safety
+/// - `plugin_id` must be a valid null-terminated UTF-8 string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_activate(
+    plugin_id: *const c_char,
+) -> PrivStackError { unsafe {
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let id = match nullable_cstr_to_str(plugin_id) {
+        Some(s) => s,
+        None => return PrivStackError::NullPointer,
+    };
+
+    let result = handle.plugin_host.activate_plugin(id);
+    unregister_disabled_plugin_handlers(handle);
+    match result {
+        Ok(()) => PrivStackError::Ok,
+        Err(_) => PrivStackError::PluginError,
+    }
+}}
+
+/// Notifies a plugin that the user navigated to its view.
+///
+/// # Safety
+/// - `plugin_id` must be a valid null-terminated UTF-8 string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_navigated_to(
+    plugin_id: *const c_char,
+) -> PrivStackError { unsafe {
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let id = match nullable_cstr_to_str(plugin_id) {
+        Some(s) => s,
+        None => return PrivStackError::NullPointer,
+    };
+
+    let result = handle.plugin_host.notify_navigated_to(id);
+    unregister_disabled_plugin_handlers(handle);
+    match result {
+        Ok(()) => PrivStackError::Ok,
+        Err(_) => PrivStackError::PluginError,
+    }
+}}
+
+/// Notifies a plugin that the user navigated away from its view.
+///
+/// # Safety
+/// - `plugin_id` must be a valid null-terminated UTF-8 string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_navigated_from(
+    plugin_id: *const c_char,
+) -> PrivStackError { unsafe {
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let id = match nullable_cstr_to_str(plugin_id) {
+        Some(s) => s,
+        None => return PrivStackError::NullPointer,
+    };
+
+    let result = handle.plugin_host.notify_navigated_from(id);
+    unregister_disabled_plugin_handlers(handle);
+    match result {
+        Ok(()) => PrivStackError::Ok,
+        Err(_) => PrivStackError::PluginError,
+    }
+}}
+
+/// Updates the permission set for a loaded plugin at runtime.
+///
+/// # Safety
+/// - `plugin_id` must be a valid null-terminated UTF-8 string.
+/// - `permissions_json` must be a valid null-terminated UTF-8 JSON string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn privstack_plugin_update_permissions(
+    plugin_id: *const c_char,
+    permissions_json: *const c_char,
+) -> PrivStackError { unsafe {
+    let mut handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_mut() {
+        Some(h) => h,
+        None => return PrivStackError::NotInitialized,
+    };
+
+    let id = match nullable_cstr_to_str(plugin_id) {
+        Some(s) => s,
+        None => return PrivStackError::NullPointer,
+    };
+
+    let perms_str = match nullable_cstr_to_str(permissions_json) {
+        Some(s) => s,
+        None => return PrivStackError::NullPointer,
+    };
+
+    let permissions: privstack_plugin_host::PermissionSet = match serde_json::from_str(perms_str) {
+        Ok(p) => p,
+        Err(_) => return PrivStackError::JsonError,
+    };
+
+    match handle.plugin_host.update_plugin_permissions(id, permissions) {
+        Ok(()) => PrivStackError::Ok,
+        Err(privstack_plugin_host::PluginHostError::PluginNotFound(_)) => PrivStackError::PluginNotFound,
+        Err(_) => PrivStackError::PluginError,
+    }
+}}
+
+/// Uninstalls a plugin: unloads it if loaded and deletes its persisted settings.
 ///
 /// # Safety
 /// - `plugin_id` must be a valid null-terminated UTF-8 string.
-/// - The returned pointer must be freed with `privstack_free_string`.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn privstack_plugin_get_view_state(
-    plugin_id: *const c_char,
-) -> *mut c_char { unsafe {
+pub unsafe extern "C" fn privstack_plugin_uninstall(plugin_id: *const c_char) -> PrivStackError { unsafe {
     let mut handle = HANDLE.lock().unwrap();
     let handle = match handle.as_mut() {
         Some(h) => h,
-        None => {
-            return to_c_string(r#"{"components":{"type":"error","message":"Core not initialized"}}"#);
-        }
+        None => return PrivStackError::NotInitialized,
     };
 
     let id = match nullable_cstr_to_str(plugin_id) {
         Some(s) => s,
-        None => {
-            return to_c_string(r#"{"components":{"type":"error","message":"Invalid plugin ID (null)"}}"#);
-        }
+        None => return PrivStackError::NullPointer,
     };
 
-    match handle.plugin_host.get_view_state(id) {
-        Ok(json) => to_c_string(&json),
+    unregister_plugin_entity_handler(handle, id);
+    match handle.plugin_host.uninstall_plugin(id) {
+        Ok(_) => {
+            if handle.installed_packages.remove(id).is_some() {
+                save_plugin_trust(handle);
+            }
+            PrivStackError::Ok
+        }
         Err(e) => {
-            eprintln!("[privstack-ffi] get_view_state({}) failed: {:?}", id, e);
-            let msg = format!(
-                r#"{{"components":{{"type":"error","message":"get_view_state failed: {}"}}}}"#,
-                e.to_string().replace('"', "'")
-            );
-            to_c_string(&msg)
+            eprintln!("[FFI] plugin_uninstall({}) failed: {}", id, e);
+            PrivStackError::PluginError
         }
     }
 }}
 
-/// Gets the raw view data JSON from a plugin's `get_view_data()` export.
-/// Used for host-side template evaluation. Returns JSON string (caller must
-/// free with `privstack_free_string`).
+// ============================================================
+// Plugin Filesystem Grants
+// ============================================================
+
+/// Sets the directory scopes the user approved for a plugin at install.
+/// `grants_json` is a JSON array of `{host_path, guest_path, writable}` objects,
+/// one per manifest `filesystem` scope (guest path `/<scope name>`). A grant for a
+/// scope the installed manifest does not declare, or a writable grant for a
+/// read-only scope, returns InvalidArgument. An empty array removes all approved
+/// scopes. Grants are remembered and applied on load; applying them requires the
+/// plugin to hold the filesystem permission.
 ///
 /// # Safety
-/// - `plugin_id` must be a valid null-terminated UTF-8 string.
-/// - The returned pointer must be freed with `privstack_free_string`.
+/// - `plugin_id` and `grants_json` must be valid null-terminated UTF-8 strings.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn privstack_plugin_get_view_data(
+pub unsafe extern "C" fn privstack_plugin_set_directory_grants(
     plugin_id: *const c_char,
-) -> *mut c_char { unsafe {
+    grants_json: *const c_char,
+) -> PrivStackError { unsafe {
     let mut handle = HANDLE.lock().unwrap();
     let handle = match handle.as_mut() {
         Some(h) => h,
-        None => {
-            return to_c_string(r#"{}"#);
-        }
+        None => return PrivStackError::NotInitialized,
     };
 
-    let id = match nullable_cstr_to_str(plugin_id) {
-        Some(s) => s,
-        None => {
-            return to_c_string(r#"{}"#);
-        }
+    let (id, json) = match (nullable_cstr_to_str(plugin_id), nullable_cstr_to_str(grants_json)) {
+        (Some(id), Some(json)) => (id, json),
+        _ => return PrivStackError::NullPointer,
     };
 
-    match handle.plugin_host.get_view_data(id) {
-        Ok(json) => to_c_string(&json),
+    let grants: Vec<privstack_plugin_host::DirectoryGrant> = match serde_json::from_str(json) {
+        Ok(g) => g,
+        Err(_) => return PrivStackError::JsonError,
+    };
+
+    match handle.plugin_host.set_directory_grants(id, grants) {
+        Ok(()) => PrivStackError::Ok,
+        Err(privstack_plugin_host::PluginHostError::PermissionDenied { .. }) => {
+            PrivStackError::PluginPermissionDenied
+        }
+        Err(privstack_plugin_host::PluginHostError::InvalidGrant(_)) => PrivStackError::InvalidArgument,
         Err(e) => {
-            eprintln!("[privstack-ffi] get_view_data({}) failed: {:?}", id, e);
-            to_c_string(r#"{}"#)
+            eprintln!("[FFI] plugin_set_directory_grants({}) failed: {}", id, e);
+            PrivStackError::PluginError
         }
     }
 }}
 
-/// Activates a plugin — calls its `activate()` export.
-/// Must be called after loading and entity type registration.
+/// Grants a loaded plugin one-off access to a path the user picked in a file
+/// dialog. Directories are mounted as-is; a file is mounted read-only on its own,
+/// without the rest of its directory. The guest path of the selection is written to `out_guest_path`.
 ///
 /// # Safety
-/// - `plugin_id` must be a valid null-terminated UTF-8 string.
+/// - `plugin_id` and `selected_path` must be valid null-terminated UTF-8 strings.
+/// - `out_guest_path` receives a heap-allocated C string (free with `privstack_free_string`).
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn privstack_plugin_activate(
+pub unsafe extern "C" fn privstack_plugin_grant_selection(
     plugin_id: *const c_char,
+    selected_path: *const c_char,
+    writable: bool,
+    out_guest_path: *mut *mut c_char,
 ) -> PrivStackError { unsafe {
     let mut handle = HANDLE.lock().unwrap();
     let handle = match handle.as_mut() {
@@ -5370,102 +6941,166 @@ pub unsafe extern "C" fn privstack_plugin_activate(
         None => return PrivStackError::NotInitialized,
     };
 
-    let id = match nullable_cstr_to_str(plugin_id) {
-        Some(s) => s,
-        None => return PrivStackError::NullPointer,
+    let (id, path_str) = match (nullable_cstr_to_str(plugin_id), nullable_cstr_to_str(selected_path)) {
+        (Some(id), Some(path)) => (id, path),
+        _ => return PrivStackError::NullPointer,
     };
 
-    match handle.plugin_host.activate_plugin(id) {
-        Ok(()) => PrivStackError::Ok,
-        Err(_) => PrivStackError::PluginError,
+    match handle.plugin_host.grant_selection(id, Path::new(path_str), writable) {
+        Ok(guest_path) => {
+            if !out_guest_path.is_null() {
+                *out_guest_path = to_c_string(&guest_path);
+            }
+            PrivStackError::Ok
+        }
+        Err(privstack_plugin_host::PluginHostError::PluginNotFound(_)) => PrivStackError::PluginNotFound,
+        Err(privstack_plugin_host::PluginHostError::PermissionDenied { .. }) => {
+            PrivStackError::PluginPermissionDenied
+        }
+        Err(privstack_plugin_host::PluginHostError::InvalidGrant(_)) => PrivStackError::InvalidArgument,
+        Err(e) => {
+            eprintln!("[FFI] plugin_grant_selection({}) failed: {}", id, e);
+            PrivStackError::PluginError
+        }
     }
 }}
 
-/// Notifies a plugin that the user navigated to its view.
+// ============================================================
+// Plugin Settings
+// ============================================================
+
+/// Replicates a synced plugin setting: a snapshot of its current value, or a
+/// deletion once it is gone.
+fn record_setting_event(handle: &PrivStackHandle, entity_id: &str) {
+    let Ok(eid) = EntityId::parse(entity_id) else {
+        return;
+    };
+    let event = match handle.entity_store.get_entity(entity_id) {
+        Ok(Some(entity)) if is_local_only_setting(&entity) => return,
+        Ok(Some(entity)) => Event::full_snapshot(
+            eid,
+            handle.peer_id,
+            SETTINGS_ENTITY_TYPE,
+            entity.data.to_string(),
+        ),
+        Ok(None) => Event::entity_deleted(eid, handle.peer_id, SETTINGS_ENTITY_TYPE),
+        Err(e) => {
+            eprintln!("[FFI] plugin setting {} lookup failed: {}", entity_id, e);
+            return;
+        }
+    };
+    if let Err(e) = record_local_event(handle, event) {
+        eprintln!("[FFI] plugin setting {} not recorded for sync: {:?}", entity_id, e);
+    }
+}
+
+/// Lists a plugin's persisted settings as a JSON array of
+/// `{key, value, sync, modified_at}` objects. The plugin does not need to be loaded.
 ///
 /// # Safety
 /// - `plugin_id` must be a valid null-terminated UTF-8 string.
+/// - The returned pointer must be freed with `privstack_free_string`.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn privstack_plugin_navigated_to(
-    plugin_id: *const c_char,
-) -> PrivStackError { unsafe {
-    let mut handle = HANDLE.lock().unwrap();
-    let handle = match handle.as_mut() {
+pub unsafe extern "C" fn privstack_plugin_settings_list(plugin_id: *const c_char) -> *mut c_char { unsafe {
+    let handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_ref() {
         Some(h) => h,
-        None => return PrivStackError::NotInitialized,
+        None => return to_c_string("[]"),
     };
 
     let id = match nullable_cstr_to_str(plugin_id) {
         Some(s) => s,
-        None => return PrivStackError::NullPointer,
+        None => return to_c_string("[]"),
     };
 
-    match handle.plugin_host.notify_navigated_to(id) {
-        Ok(()) => PrivStackError::Ok,
-        Err(_) => PrivStackError::PluginError,
+    match handle.plugin_host.plugin_settings(id).list() {
+        Ok(settings) => to_c_string(&serde_json::to_string(&settings).unwrap_or_else(|_| "[]".into())),
+        Err(e) => {
+            eprintln!("[FFI] plugin_settings_list({}) failed: {}", id, e);
+            to_c_string("[]")
+        }
     }
 }}
 
-/// Notifies a plugin that the user navigated away from its view.
+/// Stores a plugin setting. `sync` marks it for replication to the user's other
+/// devices; a synced setting is recorded as an entity event like any other entity.
+/// Returns QuotaExceeded if the plugin's settings quota would be exceeded.
 ///
 /// # Safety
-/// - `plugin_id` must be a valid null-terminated UTF-8 string.
+/// - `plugin_id`, `key` and `value` must be valid null-terminated UTF-8 strings.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn privstack_plugin_navigated_from(
+pub unsafe extern "C" fn privstack_plugin_settings_set(
     plugin_id: *const c_char,
+    key: *const c_char,
+    value: *const c_char,
+    sync: bool,
 ) -> PrivStackError { unsafe {
-    let mut handle = HANDLE.lock().unwrap();
-    let handle = match handle.as_mut() {
+    let handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_ref() {
         Some(h) => h,
         None => return PrivStackError::NotInitialized,
     };
 
-    let id = match nullable_cstr_to_str(plugin_id) {
-        Some(s) => s,
-        None => return PrivStackError::NullPointer,
+    let (id, key, value) = match (
+        nullable_cstr_to_str(plugin_id),
+        nullable_cstr_to_str(key),
+        nullable_cstr_to_str(value),
+    ) {
+        (Some(id), Some(key), Some(value)) => (id, key, value),
+        _ => return PrivStackError::NullPointer,
     };
 
-    match handle.plugin_host.notify_navigated_from(id) {
-        Ok(()) => PrivStackError::Ok,
-        Err(_) => PrivStackError::PluginError,
+    let settings = handle.plugin_host.plugin_settings(id);
+    match settings.set_with_sync(key, value, sync) {
+        Ok(()) => {
+            if sync {
+                record_setting_event(handle, &settings.entity_id(key));
+            }
+            PrivStackError::Ok
+        }
+        Err(privstack_plugin_host::PluginHostError::ResourceLimitExceeded { .. }) => {
+            PrivStackError::QuotaExceeded
+        }
+        Err(e) => {
+            eprintln!("[FFI] plugin_settings_set({}, {}) failed: {}", id, key, e);
+            PrivStackError::StorageError
+        }
     }
 }}
 
-/// Updates the permission set for a loaded plugin at runtime.
+/// Deletes a plugin setting. Missing keys are not an error.
 ///
 /// # Safety
-/// - `plugin_id` must be a valid null-terminated UTF-8 string.
-/// - `permissions_json` must be a valid null-terminated UTF-8 JSON string.
+/// - `plugin_id` and `key` must be valid null-terminated UTF-8 strings.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn privstack_plugin_update_permissions(
+pub unsafe extern "C" fn privstack_plugin_settings_remove(
     plugin_id: *const c_char,
-    permissions_json: *const c_char,
+    key: *const c_char,
 ) -> PrivStackError { unsafe {
-    let mut handle = HANDLE.lock().unwrap();
-    let handle = match handle.as_mut() {
+    let handle = HANDLE.lock().unwrap();
+    let handle = match handle.as_ref() {
         Some(h) => h,
         None => return PrivStackError::NotInitialized,
     };
 
-    let id = match nullable_cstr_to_str(plugin_id) {
-        Some(s) => s,
-        None => return PrivStackError::NullPointer,
-    };
-
-    let perms_str = match nullable_cstr_to_str(permissions_json) {
-        Some(s) => s,
-        None => return PrivStackError::NullPointer,
-    };
-
-    let permissions: privstack_plugin_host::PermissionSet = match serde_json::from_str(perms_str) {
-        Ok(p) => p,
-        Err(_) => return PrivStackError::JsonError,
+    let (id, key) = match (nullable_cstr_to_str(plugin_id), nullable_cstr_to_str(key)) {
+        (Some(id), Some(key)) => (id, key),
+        _ => return PrivStackError::NullPointer,
     };
 
-    match handle.plugin_host.update_plugin_permissions(id, permissions) {
-        Ok(()) => PrivStackError::Ok,
-        Err(privstack_plugin_host::PluginHostError::PluginNotFound(_)) => PrivStackError::PluginNotFound,
-        Err(_) => PrivStackError::PluginError,
+    let settings = handle.plugin_host.plugin_settings(id);
+    let synced = matches!(settings.get(key), Ok(Some(s)) if s.sync);
+    match settings.remove(key) {
+        Ok(()) => {
+            if synced {
+                record_setting_event(handle, &settings.entity_id(key));
+            }
+            PrivStackError::Ok
+        }
+        Err(e) => {
+            eprintln!("[FFI] plugin_settings_remove({}, {}) failed: {}", id, key, e);
+            PrivStackError::StorageError
+        }
     }
 }}
 
@@ -5788,6 +7423,30 @@ mod tests {
         r
     }
 
+    #[test]
+    fn identity_key_is_owner_only_and_never_replaced() {
+        let dir = std::env::temp_dir()
+            .join("privstack-ffi-tests")
+            .join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("data.duckdb");
+        let db = db.to_str().unwrap();
+
+        let key = load_or_create_identity_key(db).unwrap();
+        let key_path = Path::new(db).with_extension("identity_key");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&key_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert_eq!(load_or_create_identity_key(db).unwrap().public_key(), key.public_key());
+
+        std::fs::write(&key_path, b"corrupt").unwrap();
+        assert!(load_or_create_identity_key(db).is_err());
+        assert_eq!(std::fs::read(&key_path).unwrap(), b"corrupt");
+    }
+
     #[test]
     fn version_returns_valid_string() {
         let version = privstack_version();
@@ -5994,6 +7653,122 @@ mod tests {
         assert_eq!(result, PrivStackError::NullPointer);
     }
 
+    #[test]
+    #[serial]
+    fn enterprise_policy_requires_a_trusted_signature() {
+        assert_eq!(test_init(), PrivStackError::Ok);
+        let dir = std::env::temp_dir()
+            .join("privstack-ffi-tests")
+            .join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        let policy_path = dir.join("sync-policy.toml");
+        let contents = "[default_roles]\nnote = \"Viewer\"";
+        std::fs::write(&policy_path, contents).unwrap();
+
+        let admin = IdentityKeyPair::generate();
+        let path = CString::new(policy_path.to_str().unwrap()).unwrap();
+        let keys = CString::new(format!("[\"{}\"]", admin.public_key().to_base64())).unwrap();
+        let no_keys = CString::new("[]").unwrap();
+        let set = |keys: &CString| unsafe {
+            privstack_sync_set_enterprise_policy(path.as_ptr(), keys.as_ptr())
+        };
+
+        assert_eq!(set(&no_keys), PrivStackError::InvalidArgument);
+        assert_eq!(set(&keys), PrivStackError::AuthError);
+
+        let source = PolicyFileSource::new(&policy_path);
+        std::fs::write(
+            source.signature_path(),
+            PolicyFileSource::sign(contents.as_bytes(), &admin),
+        )
+        .unwrap();
+        let register = |entity_type: &str| {
+            let schema = CString::new(format!(
+                r#"{{"entity_type":"{entity_type}","indexed_fields":[{{"field_path":"/amount","field_type":"text","searchable":false,"sensitivity":"financial"}}],"merge_strategy":"lww_document"}}"#
+            ))
+            .unwrap();
+            assert_eq!(unsafe { privstack_register_entity_type(schema.as_ptr()) }, 0);
+        };
+        register("ledger");
+        assert_eq!(set(&keys), PrivStackError::Ok);
+        register("payslip");
+        {
+            let handle = HANDLE.lock().unwrap();
+            let h = handle.as_ref().unwrap();
+            let policy = h.enterprise_policy.clone().unwrap();
+            let role = h
+                .runtime
+                .block_on(async { policy.type_default_roles.read().await.get("note").copied() });
+            assert_eq!(role, Some(privstack_sync::SyncRole::Viewer));
+            let sensitive = h.runtime.block_on(async { policy.sensitive_fields.read().await.clone() });
+            for entity_type in ["ledger", "payslip"] {
+                assert_eq!(
+                    sensitive.get(entity_type),
+                    Some(&vec![("/amount".to_string(), "financial".to_string())])
+                );
+            }
+        }
+
+        privstack_shutdown();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn policy_changes_need_admin_and_are_stored_signed() {
+        assert_eq!(test_init(), PrivStackError::Ok);
+        let (team, member) = (Uuid::new_v4(), PeerId::new());
+        let change = CString::new(format!(
+            r#"{{"action":"add_team_member","team_id":"{team}","peer_id":"{member}"}}"#
+        ))
+        .unwrap();
+        let apply = || unsafe { privstack_sync_policy_change(change.as_ptr()) };
+        assert_eq!(apply(), PrivStackError::InvalidArgument);
+
+        let dir = std::env::temp_dir()
+            .join("privstack-ffi-tests")
+            .join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        let policy_path = dir.join("sync-policy.toml");
+        std::fs::write(&policy_path, "").unwrap();
+        let admin = IdentityKeyPair::generate();
+        let source = PolicyFileSource::new(&policy_path);
+        std::fs::write(source.signature_path(), PolicyFileSource::sign(b"", &admin)).unwrap();
+        let path = CString::new(policy_path.to_str().unwrap()).unwrap();
+        let keys = CString::new(format!("[\"{}\"]", admin.public_key().to_base64())).unwrap();
+        assert_eq!(
+            unsafe { privstack_sync_set_enterprise_policy(path.as_ptr(), keys.as_ptr()) },
+            PrivStackError::Ok
+        );
+
+        // Peers would reject the event, so this device refuses to make it
+        assert_eq!(apply(), PrivStackError::AuthError);
+
+        {
+            let handle = HANDLE.lock().unwrap();
+            let h = handle.as_ref().unwrap();
+            let policy = h.enterprise_policy.clone().unwrap();
+            h.runtime.block_on(policy.grant_peer_role(
+                privstack_sync::POLICY_ENTITY_ID,
+                h.peer_id,
+                privstack_sync::SyncRole::Admin,
+            ));
+        }
+        assert_eq!(apply(), PrivStackError::Ok);
+        {
+            let handle = HANDLE.lock().unwrap();
+            let h = handle.as_ref().unwrap();
+            let policy = h.enterprise_policy.clone().unwrap();
+            let teams = h.runtime.block_on(async { policy.teams.read().await.clone() });
+            assert!(teams[&privstack_sync::TeamId(team)].contains(&member));
+            let events = h.event_store.get_events_for_entity(&privstack_sync::POLICY_ENTITY_ID).unwrap();
+            assert_eq!(events.len(), 1);
+            assert!(events[0].is_signed());
+        }
+
+        privstack_shutdown();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn pairing_generate_code_null() {
         let result = unsafe { privstack_pairing_generate_code(ptr::null_mut()) };
@@ -8332,6 +10107,7 @@ mod tests {
                 searchable: true,
                 vector_dim: None,
                 enum_options: None,
+                sensitivity: None,
             }],
             merge_strategy: privstack_model::MergeStrategy::LwwDocument,
         };
@@ -8363,6 +10139,20 @@ mod tests {
         assert!(reg.get_handler("note").is_none());
     }
 
+    #[test]
+    fn entity_registry_domain_registry_sees_later_schemas() {
+        let mut reg = EntityRegistry::new();
+        let domain = reg.domain_registry();
+        assert!(domain.schema("note").is_none());
+
+        reg.register_schema(EntitySchema {
+            entity_type: "note".to_string(),
+            indexed_fields: vec![],
+            merge_strategy: privstack_model::MergeStrategy::LwwDocument,
+        });
+        assert_eq!(domain.schema("note").unwrap().entity_type, "note");
+    }
+
     // ══════════════════════════════════════════════════════════════
     // Phase 7: PrivStackError exhaustive enum values
     // ══════════════════════════════════════════════════════════════
@@ -9111,6 +10901,57 @@ mod tests {
         assert_eq!(r, PrivStackError::JsonError);
     }
 
+    #[test]
+    fn set_peer_scope_invalid_uuid() {
+        let pid = CString::new("not-a-uuid").unwrap();
+        let scope = CString::new("{}").unwrap();
+        let r = unsafe { privstack_sync_set_peer_scope(pid.as_ptr(), scope.as_ptr()) };
+        assert_eq!(r, PrivStackError::JsonError);
+    }
+
+    #[test]
+    #[serial]
+    fn peer_scope_lifecycle() {
+        test_init();
+
+        let pid = CString::new(Uuid::new_v4().to_string()).unwrap();
+        let bad = CString::new("not json").unwrap();
+        let r = unsafe { privstack_sync_set_peer_scope(pid.as_ptr(), bad.as_ptr()) };
+        assert_eq!(r, PrivStackError::JsonError);
+
+        let scope = CString::new(r#"{"include_types":["task","note"],"exclude_tags":["private"]}"#).unwrap();
+        let r = unsafe { privstack_sync_set_peer_scope(pid.as_ptr(), scope.as_ptr()) };
+        assert_eq!(r, PrivStackError::Ok);
+
+        let mut out: *mut c_char = ptr::null_mut();
+        let r = unsafe { privstack_sync_get_peer_scope(pid.as_ptr(), &mut out) };
+        assert_eq!(r, PrivStackError::Ok);
+        let json = unsafe { CStr::from_ptr(out) }.to_str().unwrap().to_string();
+        unsafe { privstack_free_string(out) };
+        assert!(json.contains("task"));
+        assert!(json.contains("private"));
+
+        // Scope is persisted alongside the pairing state
+        let mut state: *mut c_char = ptr::null_mut();
+        let r = unsafe { privstack_pairing_save_state(&mut state) };
+        assert_eq!(r, PrivStackError::Ok);
+        let state_json = unsafe { CStr::from_ptr(state) }.to_str().unwrap().to_string();
+        unsafe { privstack_free_string(state) };
+        assert!(state_json.contains("sync_scopes"));
+
+        // Empty scope clears
+        let empty = CString::new("{}").unwrap();
+        let r = unsafe { privstack_sync_set_peer_scope(pid.as_ptr(), empty.as_ptr()) };
+        assert_eq!(r, PrivStackError::Ok);
+        let mut out: *mut c_char = ptr::null_mut();
+        let r = unsafe { privstack_sync_get_peer_scope(pid.as_ptr(), &mut out) };
+        assert_eq!(r, PrivStackError::Ok);
+        assert_eq!(unsafe { CStr::from_ptr(out) }.to_str().unwrap(), "{}");
+        unsafe { privstack_free_string(out) };
+
+        privstack_shutdown();
+    }
+
     #[test]
     #[serial]
     fn share_unshare_list_lifecycle() {
@@ -9812,17 +11653,15 @@ mod tests {
     fn plugin_load_wasm_not_initialized() {
         privstack_shutdown();
         let path = CString::new("/nonexistent.wasm").unwrap();
-        let perms = CString::new("{}").unwrap();
         let mut out_id: *mut c_char = ptr::null_mut();
-        let r = unsafe { privstack_plugin_load_wasm(path.as_ptr(), perms.as_ptr(), &mut out_id) };
+        let r = unsafe { privstack_plugin_load_wasm(path.as_ptr(), &mut out_id) };
         assert_eq!(r, PrivStackError::NotInitialized);
     }
 
     #[test]
     fn plugin_load_wasm_null_path() {
-        let perms = CString::new("{}").unwrap();
         let mut out_id: *mut c_char = ptr::null_mut();
-        let r = unsafe { privstack_plugin_load_wasm(ptr::null(), perms.as_ptr(), &mut out_id) };
+        let r = unsafe { privstack_plugin_load_wasm(ptr::null(), &mut out_id) };
         assert!(r == PrivStackError::NullPointer || r == PrivStackError::NotInitialized);
     }
 
@@ -9832,9 +11671,8 @@ mod tests {
         test_init();
 
         let wasm_path = CString::new("/tmp/nonexistent_test.wasm").unwrap();
-        let perms = CString::new("{}").unwrap();
         let mut out_id: *mut c_char = ptr::null_mut();
-        let r = unsafe { privstack_plugin_load_wasm(wasm_path.as_ptr(), perms.as_ptr(), &mut out_id) };
+        let r = unsafe { privstack_plugin_load_wasm(wasm_path.as_ptr(), &mut out_id) };
         assert_eq!(r, PrivStackError::PluginError);
 
         privstack_shutdown();
@@ -9842,15 +11680,105 @@ mod tests {
 
     #[test]
     #[serial]
-    fn plugin_load_wasm_null_permissions() {
+    fn plugin_load_wasm_unverified_build_denied() {
         test_init();
 
-        let wasm_path = CString::new("/tmp/nonexistent_test.wasm").unwrap();
+        let dir = std::env::temp_dir()
+            .join("privstack-ffi-tests")
+            .join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("unverified.wasm");
+        std::fs::write(&file, b"\0asm not from a package").unwrap();
+        let wasm_path = CString::new(file.to_str().unwrap()).unwrap();
         let mut out_id: *mut c_char = ptr::null_mut();
-        let r = unsafe { privstack_plugin_load_wasm(wasm_path.as_ptr(), ptr::null(), &mut out_id) };
-        // Null permissions should use default_first_party, then fail on file not found
+        let r = unsafe { privstack_plugin_load_wasm(wasm_path.as_ptr(), &mut out_id) };
+        // Outside dev mode only builds of installed packages load
+        assert_eq!(r, PrivStackError::PluginPermissionDenied);
+        assert!(out_id.is_null());
+
+        privstack_shutdown();
+    }
+
+    #[test]
+    #[serial]
+    fn installed_build_still_loads_after_restart() {
+        privstack_shutdown();
+        let dir = std::env::temp_dir()
+            .join("privstack-ffi-tests")
+            .join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("data.duckdb");
+        let init = || {
+            init_with_plugin_host_builder(db.to_str().unwrap(), |es, ev| {
+                PluginHostManager::with_policy(
+                    es,
+                    ev,
+                    PolicyEngine::with_config(PolicyConfig::default()),
+                )
+            })
+        };
+        assert_eq!(init(), PrivStackError::Ok);
+
+        let publisher = privstack_ppk::KeyPair::generate();
+        let key_hex = publisher.verifying_key.to_hex();
+        let name = CString::new("Weather Co").unwrap();
+        let key = CString::new(key_hex.clone()).unwrap();
+        let r = unsafe { privstack_plugin_trust_publisher(name.as_ptr(), key.as_ptr()) };
+        assert_eq!(r, PrivStackError::Ok);
+
+        // Not a real component: an admitted build gets as far as compilation
+        // and fails there, an unadmitted one is refused before.
+        let wasm = b"\0asm signed weather build".to_vec();
+        let manifest = privstack_ppk::PpkManifest {
+            id: "community.weather".into(),
+            name: "Weather".into(),
+            description: "".into(),
+            version: "1.0.0".into(),
+            author: "Weather Co".into(),
+            icon: None,
+            navigation_order: 1000,
+            category: "utility".into(),
+            can_disable: true,
+            is_experimental: false,
+            min_app_version: None,
+            permissions: vec![],
+            schemas: vec![],
+            filesystem: vec![],
+            network_hosts: vec![],
+            event_topics: vec![],
+        };
+        let ppk = privstack_ppk::PackageBuilder::new(manifest)
+            .wasm(wasm.clone())
+            .sign(&publisher.signing_key)
+            .build()
+            .unwrap();
+        let ppk_path = dir.join("weather.ppk");
+        std::fs::write(&ppk_path, ppk).unwrap();
+        let ppk_path = CString::new(ppk_path.to_str().unwrap()).unwrap();
+        let r = unsafe { privstack_plugin_install_ppk(ppk_path.as_ptr()) };
         assert_eq!(r, PrivStackError::PluginError);
 
+        privstack_shutdown();
+        assert_eq!(init(), PrivStackError::Ok);
+
+        let publishers = privstack_plugin_trusted_publishers();
+        let json = unsafe { CStr::from_ptr(publishers) }.to_str().unwrap().to_string();
+        unsafe { privstack_free_string(publishers) };
+        assert!(json.contains(&key_hex));
+
+        let load = |bytes: &[u8], file_name: &str| {
+            let file = dir.join(file_name);
+            std::fs::write(&file, bytes).unwrap();
+            let wasm_path = CString::new(file.to_str().unwrap()).unwrap();
+            let mut out_id: *mut c_char = ptr::null_mut();
+            unsafe { privstack_plugin_load_wasm(wasm_path.as_ptr(), &mut out_id) }
+        };
+        assert_eq!(load(&wasm, "weather.wasm"), PrivStackError::PluginError);
+        assert_eq!(
+            load(b"\0asm unsigned build", "other.wasm"),
+            PrivStackError::PluginPermissionDenied
+        );
+
         privstack_shutdown();
     }
 
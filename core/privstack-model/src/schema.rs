@@ -8,6 +8,17 @@ pub struct EntitySchema {
     pub merge_strategy: MergeStrategy,
 }
 
+impl EntitySchema {
+    /// Returns the `(field_path, label)` pairs of fields with a sensitivity label.
+    pub fn sensitive_fields(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.indexed_fields.iter().filter_map(|f| {
+            f.sensitivity
+                .as_deref()
+                .map(|label| (f.field_path.as_str(), label))
+        })
+    }
+}
+
 /// A field extracted from entity JSON for indexing/search.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndexedField {
@@ -23,6 +34,10 @@ pub struct IndexedField {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[serde(alias = "options")]
     pub enum_options: Option<Vec<String>>,
+    /// Sensitivity label (e.g. "financial", "personal"). Sync policies can
+    /// withhold labelled fields from peers with restricted roles.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sensitivity: Option<String>,
 }
 
 impl IndexedField {
@@ -33,9 +48,16 @@ impl IndexedField {
             searchable,
             vector_dim: None,
             enum_options: None,
+            sensitivity: None,
         }
     }
 
+    /// Marks the field with a sensitivity label.
+    pub fn with_sensitivity(mut self, label: &str) -> Self {
+        self.sensitivity = Some(label.into());
+        self
+    }
+
     /// Shorthand for a searchable text field.
     pub fn text(path: &str, searchable: bool) -> Self {
         Self::simple(path, FieldType::Text, searchable)
@@ -69,6 +91,7 @@ impl IndexedField {
             searchable: false,
             vector_dim: Some(dim),
             enum_options: None,
+            sensitivity: None,
         }
     }
 
@@ -100,6 +123,7 @@ impl IndexedField {
             searchable: false,
             vector_dim: None,
             enum_options: Some(options),
+            sensitivity: None,
         }
     }
 
@@ -465,3 +465,31 @@ fn schema_clone_is_independent() {
     assert_eq!(cloned.entity_type, "different");
     assert_eq!(cloned.indexed_fields.len(), 3);
 }
+
+// ── Sensitivity labels ──────────────────────────────────────────
+
+#[test]
+fn sensitive_fields_lists_labelled_paths() {
+    let schema = EntitySchema {
+        entity_type: "project".to_string(),
+        indexed_fields: vec![
+            IndexedField::text("/name", true),
+            IndexedField::decimal("/cost").with_sensitivity("financial"),
+            IndexedField::text("/notes", false).with_sensitivity("personal"),
+        ],
+        merge_strategy: MergeStrategy::LwwPerField,
+    };
+    let sensitive: Vec<(&str, &str)> = schema.sensitive_fields().collect();
+    assert_eq!(sensitive, vec![("/cost", "financial"), ("/notes", "personal")]);
+}
+
+#[test]
+fn sensitivity_serde_omitted_when_unset() {
+    let plain = serde_json::to_string(&IndexedField::text("/title", true)).unwrap();
+    assert!(!plain.contains("sensitivity"));
+
+    let labelled = IndexedField::decimal("/cost").with_sensitivity("financial");
+    let parsed: IndexedField =
+        serde_json::from_str(&serde_json::to_string(&labelled).unwrap()).unwrap();
+    assert_eq!(parsed.sensitivity.as_deref(), Some("financial"));
+}
@@ -6,6 +6,11 @@
 //! Uses `wasmtime::component::bindgen!` to produce:
 //! - Host import traits that we implement (sdk, settings, logger, etc.)
 //! - Guest export callable interfaces (plugin, linkable-item-provider, etc.)
+//! - The `agent` host import for components targeting `agent-plugin-world`
+//! - The optional `entity-handler` export, called on its own instance
+//! - The optional `entity-observer` export, called on the plugin's instance
+//! - The optional `scheduled-task` export, called on the plugin's instance
+//! - The optional `event-subscriber` export, called on the plugin's instance
 
 use wasmtime::component::bindgen;
 
@@ -17,3 +22,69 @@ bindgen!({
     // Trap on missing optional exports instead of panicking.
     trappable_imports: true,
 });
+
+/// Bindings for `agent-plugin-world`: the standard world plus the `agent`
+/// import. Shared interfaces are remapped onto the `plugin-world` bindings
+/// above, so both worlds use the same types and host implementations.
+pub mod agent_world {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "agent-plugin-world",
+        async: false,
+        trappable_imports: true,
+        with: {
+            "privstack:plugin/types": crate::bindings::privstack::plugin::types,
+            "privstack:plugin/sdk": crate::bindings::privstack::plugin::sdk,
+            "privstack:plugin/settings": crate::bindings::privstack::plugin::settings,
+            "privstack:plugin/logger": crate::bindings::privstack::plugin::logger,
+            "privstack:plugin/navigation": crate::bindings::privstack::plugin::navigation,
+            "privstack:plugin/vault": crate::bindings::privstack::plugin::vault,
+            "privstack:plugin/linking": crate::bindings::privstack::plugin::linking,
+            "privstack:plugin/dialogs": crate::bindings::privstack::plugin::dialogs,
+            "privstack:plugin/state-notify": crate::bindings::privstack::plugin::state_notify,
+            "privstack:plugin/network": crate::bindings::privstack::plugin::network,
+            "privstack:plugin/scheduler": crate::bindings::privstack::plugin::scheduler,
+            "privstack:plugin/events": crate::bindings::privstack::plugin::events,
+        },
+    });
+}
+
+/// Bindings for the optional `entity-handler` export. The world has no
+/// imports, so these bind against any component that exports the interface.
+pub mod entity_handler_exports {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "entity-handler-exports",
+        async: false,
+    });
+}
+
+/// Bindings for the optional `entity-observer` export, loaded from the
+/// plugin's main instance when the component exports the interface.
+pub mod entity_observer_exports {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "entity-observer-exports",
+        async: false,
+    });
+}
+
+/// Bindings for the optional `scheduled-task` export, loaded from the
+/// plugin's main instance when the component exports the interface.
+pub mod scheduled_task_exports {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "scheduled-task-exports",
+        async: false,
+    });
+}
+
+/// Bindings for the optional `event-subscriber` export, loaded from the
+/// plugin's main instance when the component exports the interface.
+pub mod event_subscriber_exports {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "event-subscriber-exports",
+        async: false,
+    });
+}
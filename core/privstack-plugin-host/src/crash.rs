@@ -0,0 +1,166 @@
+//! Crash reports and the per-plugin circuit breaker.
+//!
+//! A plugin call that traps (including running out of fuel) or is
+//! interrupted at its `call_timeout_ms` deadline counts as a crash. The manager re-instantiates the
+//! plugin after a crash, unless it crashed [`MAX_CRASHES_PER_WINDOW`] times
+//! within [`CRASH_WINDOW_SECS`]: then the breaker trips and the plugin stays
+//! disabled until the breaker is reset.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Crashes within [`CRASH_WINDOW_SECS`] that trip the breaker.
+pub const MAX_CRASHES_PER_WINDOW: usize = 3;
+/// Length of the sliding crash window.
+pub const CRASH_WINDOW_SECS: i64 = 5 * 60;
+/// Crash reports kept in memory; the oldest are dropped first.
+const CRASH_REPORT_CAPACITY: usize = 200;
+
+/// What the host did about a crash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrashOutcome {
+    /// The plugin was re-instantiated and its lifecycle replayed.
+    Restarted,
+    /// Re-instantiation failed; the plugin was disabled.
+    RestartFailed,
+    /// The circuit breaker tripped; the plugin was disabled.
+    Disabled,
+}
+
+/// One crash of a plugin call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub plugin_id: String,
+    /// The export that was called, e.g. `handle-command`.
+    pub operation: String,
+    pub message: String,
+    pub outcome: CrashOutcome,
+    /// Why the restart failed, for [`CrashOutcome::RestartFailed`].
+    pub restart_error: Option<String>,
+    /// Unix timestamp (seconds).
+    pub timestamp: i64,
+}
+
+/// Recent crash times, disabled plugins and crash reports.
+#[derive(Default)]
+pub struct CrashTracker {
+    recent: HashMap<String, VecDeque<i64>>,
+    disabled: HashMap<String, String>,
+    reports: VecDeque<CrashReport>,
+}
+
+impl CrashTracker {
+    /// Counts a crash at `now`. Returns `true` if it trips the breaker.
+    pub fn record_crash(&mut self, plugin_id: &str, now: i64) -> bool {
+        let recent = self.recent.entry(plugin_id.to_string()).or_default();
+        while recent.front().is_some_and(|&at| now - at >= CRASH_WINDOW_SECS) {
+            recent.pop_front();
+        }
+        recent.push_back(now);
+        recent.len() >= MAX_CRASHES_PER_WINDOW
+    }
+
+    pub fn disable(&mut self, plugin_id: &str, reason: String) {
+        self.disabled.insert(plugin_id.to_string(), reason);
+    }
+
+    /// Why the plugin is disabled, if it is.
+    pub fn disabled_reason(&self, plugin_id: &str) -> Option<&str> {
+        self.disabled.get(plugin_id).map(String::as_str)
+    }
+
+    /// Closes the breaker and forgets the crash count. Returns whether the
+    /// plugin was disabled. Reports are kept.
+    pub fn reset(&mut self, plugin_id: &str) -> bool {
+        self.recent.remove(plugin_id);
+        self.disabled.remove(plugin_id).is_some()
+    }
+
+    /// Drops everything recorded for the plugin, reports included.
+    pub fn forget_plugin(&mut self, plugin_id: &str) {
+        self.reset(plugin_id);
+        self.reports.retain(|report| report.plugin_id != plugin_id);
+    }
+
+    pub fn push_report(&mut self, report: CrashReport) {
+        if self.reports.len() == CRASH_REPORT_CAPACITY {
+            self.reports.pop_front();
+        }
+        self.reports.push_back(report);
+    }
+
+    /// Reports of one plugin, or of all if `plugin_id` is `None`, oldest first.
+    pub fn reports(&self, plugin_id: Option<&str>) -> Vec<CrashReport> {
+        self.reports
+            .iter()
+            .filter(|report| plugin_id.is_none_or(|id| report.plugin_id == id))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(plugin_id: &str) -> CrashReport {
+        CrashReport {
+            plugin_id: plugin_id.into(),
+            operation: "handle-command".into(),
+            message: "wasm trap: unreachable".into(),
+            outcome: CrashOutcome::Restarted,
+            restart_error: None,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn breaker_trips_on_crashes_within_window() {
+        let mut tracker = CrashTracker::default();
+        assert!(!tracker.record_crash("p1", 0));
+        assert!(!tracker.record_crash("p1", 10));
+        assert!(!tracker.record_crash("p2", 20));
+        assert!(tracker.record_crash("p1", 20));
+    }
+
+    #[test]
+    fn crashes_outside_window_are_forgotten() {
+        let mut tracker = CrashTracker::default();
+        assert!(!tracker.record_crash("p1", 0));
+        assert!(!tracker.record_crash("p1", 100));
+        assert!(!tracker.record_crash("p1", CRASH_WINDOW_SECS + 50));
+        assert!(tracker.record_crash("p1", CRASH_WINDOW_SECS + 60));
+    }
+
+    #[test]
+    fn reset_closes_breaker() {
+        let mut tracker = CrashTracker::default();
+        tracker.record_crash("p1", 0);
+        tracker.record_crash("p1", 1);
+        tracker.disable("p1", "crashed".into());
+        tracker.push_report(report("p1"));
+        assert_eq!(tracker.disabled_reason("p1"), Some("crashed"));
+
+        assert!(tracker.reset("p1"));
+        assert!(!tracker.reset("p1"));
+        assert_eq!(tracker.disabled_reason("p1"), None);
+        assert!(!tracker.record_crash("p1", 2));
+        assert_eq!(tracker.reports(Some("p1")).len(), 1);
+
+        tracker.forget_plugin("p1");
+        assert!(tracker.reports(None).is_empty());
+    }
+
+    #[test]
+    fn reports_are_filtered_and_bounded() {
+        let mut tracker = CrashTracker::default();
+        for _ in 0..CRASH_REPORT_CAPACITY {
+            tracker.push_report(report("p1"));
+        }
+        tracker.push_report(report("p2"));
+        assert_eq!(tracker.reports(None).len(), CRASH_REPORT_CAPACITY);
+        assert_eq!(tracker.reports(Some("p1")).len(), CRASH_REPORT_CAPACITY - 1);
+        assert_eq!(tracker.reports(Some("p2")).len(), 1);
+    }
+}
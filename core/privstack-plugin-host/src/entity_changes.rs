@@ -0,0 +1,269 @@
+//! Entity change notifications for plugins exporting `entity-observer`.
+//!
+//! Writes made through a plugin's `sdk` import, through the shell's SDK and
+//! by sync are recorded in one [`EntityChangeQueue`] shared by the manager
+//! and every sandbox. The queue coalesces repeated writes to the same entity
+//! and only releases a batch once writes have paused for the debounce
+//! window (or the oldest change has waited [`MAX_CHANGE_DELAY`]), so a burst
+//! of edits costs each observer one call instead of one per write.
+
+use crate::bindings::entity_observer_exports::EntityObserverExports;
+use crate::bindings::entity_observer_exports::exports::privstack::plugin::entity_observer as wit;
+use crate::sandbox::PluginState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+use wasmtime::component::{Component, Instance};
+use wasmtime::{Engine, Store};
+
+/// WIT interface name prefix of the `entity-observer` export.
+const ENTITY_OBSERVER_EXPORT: &str = "privstack:plugin/entity-observer";
+
+/// `source` of changes made through the shell's SDK.
+pub const CHANGE_SOURCE_HOST: &str = "host";
+/// `source` of changes applied by sync.
+pub const CHANGE_SOURCE_SYNC: &str = "sync";
+
+/// Quiet period after the last write before a batch is released.
+pub const DEFAULT_CHANGE_DEBOUNCE: Duration = Duration::from_millis(250);
+/// Longest a change waits under a steady stream of writes.
+pub const MAX_CHANGE_DELAY: Duration = Duration::from_secs(2);
+/// Changes passed to a single `on-entities-changed` call.
+pub const MAX_CHANGES_PER_CALL: usize = 100;
+/// Pending changes kept before new ones are dropped.
+const MAX_PENDING_CHANGES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityChangeKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl EntityChangeKind {
+    /// The kind a plugin should see for `self` followed by `next`.
+    fn then(self, next: Self) -> Self {
+        match (self, next) {
+            (_, Self::Deleted) => Self::Deleted,
+            (Self::Created, _) => Self::Created,
+            (Self::Deleted, Self::Created) => Self::Updated,
+            (_, next) => next,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntityChange {
+    pub entity_id: String,
+    pub entity_type: String,
+    pub kind: EntityChangeKind,
+    /// [`CHANGE_SOURCE_HOST`], [`CHANGE_SOURCE_SYNC`], or the id of the
+    /// plugin that wrote the entity. Plugins are not told of their own writes.
+    pub source: String,
+}
+
+impl EntityChange {
+    pub fn new(
+        entity_id: impl Into<String>,
+        entity_type: impl Into<String>,
+        kind: EntityChangeKind,
+        source: impl Into<String>,
+    ) -> Self {
+        Self {
+            entity_id: entity_id.into(),
+            entity_type: entity_type.into(),
+            kind,
+            source: source.into(),
+        }
+    }
+
+    fn to_wit(&self) -> wit::EntityChange {
+        wit::EntityChange {
+            entity_id: self.entity_id.clone(),
+            entity_type: self.entity_type.clone(),
+            kind: match self.kind {
+                EntityChangeKind::Created => wit::ChangeKind::Created,
+                EntityChangeKind::Updated => wit::ChangeKind::Updated,
+                EntityChangeKind::Deleted => wit::ChangeKind::Deleted,
+            },
+            source: self.source.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct PendingChanges {
+    changes: Vec<EntityChange>,
+    /// Position in `changes` of each (entity id, source).
+    index: HashMap<(String, String), usize>,
+    first_at: Option<Instant>,
+    last_at: Option<Instant>,
+    dropped: usize,
+}
+
+/// Changes waiting to be delivered to observers.
+pub struct EntityChangeQueue {
+    debounce: Duration,
+    pending: Mutex<PendingChanges>,
+}
+
+impl Default for EntityChangeQueue {
+    fn default() -> Self {
+        Self::new(DEFAULT_CHANGE_DEBOUNCE)
+    }
+}
+
+impl EntityChangeQueue {
+    pub fn new(debounce: Duration) -> Self {
+        Self { debounce, pending: Mutex::default() }
+    }
+
+    /// Records a change, merging it into a pending change to the same entity
+    /// from the same source.
+    pub fn push(&self, change: EntityChange) {
+        self.push_at(change, Instant::now());
+    }
+
+    fn push_at(&self, change: EntityChange, now: Instant) {
+        let mut pending = self.pending.lock().unwrap();
+        let key = (change.entity_id.clone(), change.source.clone());
+        if let Some(&i) = pending.index.get(&key) {
+            let existing = &mut pending.changes[i];
+            existing.kind = existing.kind.then(change.kind);
+        } else if pending.changes.len() >= MAX_PENDING_CHANGES {
+            pending.dropped += 1;
+            return;
+        } else {
+            let i = pending.changes.len();
+            pending.changes.push(change);
+            pending.index.insert(key, i);
+        }
+        pending.first_at.get_or_insert(now);
+        pending.last_at = Some(now);
+    }
+
+    /// Number of changes waiting.
+    pub fn len(&self) -> usize {
+        self.pending.lock().unwrap().changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Takes the pending changes, oldest first, if the batch is due.
+    pub fn take_ready(&self) -> Vec<EntityChange> {
+        self.take_ready_at(Instant::now())
+    }
+
+    fn take_ready_at(&self, now: Instant) -> Vec<EntityChange> {
+        let mut pending = self.pending.lock().unwrap();
+        let (Some(first_at), Some(last_at)) = (pending.first_at, pending.last_at) else {
+            return Vec::new();
+        };
+        let quiet = now.duration_since(last_at) >= self.debounce;
+        let overdue = now.duration_since(first_at) >= MAX_CHANGE_DELAY;
+        if !quiet && !overdue {
+            return Vec::new();
+        }
+        if pending.dropped > 0 {
+            warn!(dropped = pending.dropped, "Entity change queue overflowed");
+        }
+        std::mem::take(&mut *pending).changes
+    }
+}
+
+/// Loads the component's `entity-observer` export from its main instance,
+/// or returns `None` if it does not export one.
+pub(crate) fn bind_entity_observer(
+    engine: &Engine,
+    component: &Component,
+    store: &mut Store<PluginState>,
+    instance: &Instance,
+) -> wasmtime::Result<Option<EntityObserverExports>> {
+    let exported = component
+        .component_type()
+        .exports(engine)
+        .any(|(name, _)| name.starts_with(ENTITY_OBSERVER_EXPORT));
+    if !exported {
+        return Ok(None);
+    }
+    EntityObserverExports::new(store, instance).map(Some)
+}
+
+/// Calls `on-entities-changed` with one batch.
+pub(crate) fn call_on_entities_changed(
+    observer: &EntityObserverExports,
+    store: &mut Store<PluginState>,
+    changes: &[EntityChange],
+) -> wasmtime::Result<()> {
+    let changes: Vec<wit::EntityChange> = changes.iter().map(EntityChange::to_wit).collect();
+    observer
+        .privstack_plugin_entity_observer()
+        .call_on_entities_changed(store, &changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(id: &str, kind: EntityChangeKind, source: &str) -> EntityChange {
+        EntityChange::new(id, "note", kind, source)
+    }
+
+    #[test]
+    fn batch_waits_for_quiet_period() {
+        let queue = EntityChangeQueue::new(Duration::from_millis(100));
+        let start = Instant::now();
+        queue.push_at(change("a", EntityChangeKind::Created, "host"), start);
+        let later = start + Duration::from_millis(80);
+        queue.push_at(change("b", EntityChangeKind::Updated, "host"), later);
+
+        assert!(queue.take_ready_at(start + Duration::from_millis(150)).is_empty());
+        let batch = queue.take_ready_at(start + Duration::from_millis(180));
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[0].entity_id, "a");
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn steady_stream_is_flushed_after_max_delay() {
+        let queue = EntityChangeQueue::new(Duration::from_millis(100));
+        let start = Instant::now();
+        let mut now = start;
+        while now < start + MAX_CHANGE_DELAY {
+            queue.push_at(change("a", EntityChangeKind::Updated, "sync"), now);
+            assert!(queue.take_ready_at(now).is_empty());
+            now += Duration::from_millis(50);
+        }
+        assert_eq!(queue.take_ready_at(now).len(), 1);
+    }
+
+    #[test]
+    fn changes_coalesce_per_entity_and_source() {
+        let queue = EntityChangeQueue::new(Duration::ZERO);
+        queue.push(change("a", EntityChangeKind::Created, "host"));
+        queue.push(change("a", EntityChangeKind::Updated, "host"));
+        queue.push(change("a", EntityChangeKind::Updated, "notes"));
+        queue.push(change("b", EntityChangeKind::Updated, "sync"));
+        queue.push(change("b", EntityChangeKind::Deleted, "sync"));
+
+        let batch = queue.take_ready();
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch[0].kind, EntityChangeKind::Created);
+        assert_eq!(batch[1].source, "notes");
+        assert_eq!(batch[2].kind, EntityChangeKind::Deleted);
+    }
+
+    #[test]
+    fn kind_merging() {
+        use EntityChangeKind::*;
+        assert_eq!(Created.then(Updated), Created);
+        assert_eq!(Created.then(Deleted), Deleted);
+        assert_eq!(Deleted.then(Created), Updated);
+        assert_eq!(Updated.then(Updated), Updated);
+    }
+}
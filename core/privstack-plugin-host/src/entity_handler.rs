@@ -0,0 +1,378 @@
+//! Bridges a plugin's `entity-handler` export into a [`PluginDomainHandler`].
+//!
+//! The hooks run in their own instance of the plugin's component, separate
+//! from the instance driving the UI, so the sync applicator and the SDK write
+//! path can call them without going through the plugin manager. Each call
+//! gets the plugin's fuel budget. If the guest traps, the instance is thrown
+//! away (rebuilt on the next call) and the hook falls back to a fixed
+//! default: `validate` rejects, `merge` is last-writer-wins, `on-after-load`
+//! leaves the entity unchanged.
+//!
+//! Traps, including running out of fuel or memory, are deterministic: every
+//! peer running the same build on the same entities fails the same way and
+//! applies the same fallback, so replicas converge. A wall-clock timeout is
+//! not: a slow peer may fall back where a fast one did not, and their copies
+//! of the entity can diverge until it is next written. Hooks therefore get a
+//! deadline [`HOOK_TIMEOUT_FACTOR`] times the plugin's call timeout, so that
+//! fuel bounds them in practice and the deadline only guards against a
+//! stalled host. Timeouts still fall back (there is nothing else to return)
+//! but are counted separately and logged as a divergence risk.
+
+use crate::bindings::entity_handler_exports::{EntityHandlerExports, EntityHandlerExportsPre};
+use crate::error::PluginHostError;
+use crate::net_policy::NetworkPolicy;
+use crate::permissions::{Permission, PermissionSet};
+use crate::sandbox::{start_call, PluginState, ResourceLimits, TrackingLimiter};
+use privstack_model::{Entity, PluginDomainHandler};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+use wasmtime::component::{Component, Linker, ResourceTable};
+use wasmtime::{Engine, Store, Trap};
+use wasmtime_wasi::p2::WasiCtxBuilder;
+
+/// WIT interface name prefix of the `entity-handler` export.
+const ENTITY_HANDLER_EXPORT: &str = "privstack:plugin/entity-handler";
+
+/// Multiple of the plugin's call timeout allowed for each hook call.
+pub const HOOK_TIMEOUT_FACTOR: u64 = 10;
+
+/// Whether the component exports `entity-handler`.
+pub(crate) fn exports_entity_handler(engine: &Engine, component: &Component) -> bool {
+    component
+        .component_type()
+        .exports(engine)
+        .any(|(name, _)| name.starts_with(ENTITY_HANDLER_EXPORT))
+}
+
+struct HandlerInstance {
+    store: Store<PluginState>,
+    bindings: EntityHandlerExports,
+}
+
+/// A plugin's `entity-handler` export, usable wherever a
+/// [`PluginDomainHandler`] is expected.
+pub struct WasmEntityHandler {
+    plugin_id: String,
+    engine: Engine,
+    pre: EntityHandlerExportsPre<PluginState>,
+    resource_limits: ResourceLimits,
+    entity_store: Arc<privstack_storage::EntityStore>,
+    event_store: Arc<privstack_storage::EventStore>,
+    /// `None` until first use and after a failed call.
+    instance: Mutex<Option<HandlerInstance>>,
+    traps: AtomicU64,
+    timeouts: AtomicU64,
+}
+
+impl WasmEntityHandler {
+    /// Prepares the hooks of `component`, or returns `None` if it does not
+    /// export `entity-handler`. The instance itself is created on first use.
+    pub(crate) fn for_component(
+        engine: &Engine,
+        component: &Component,
+        linker: &Linker<PluginState>,
+        plugin_id: &str,
+        resource_limits: &ResourceLimits,
+        state: &PluginState,
+    ) -> Result<Option<Arc<Self>>, PluginHostError> {
+        if !exports_entity_handler(engine, component) {
+            return Ok(None);
+        }
+        let instance_pre = linker
+            .instantiate_pre(component)
+            .map_err(PluginHostError::Compilation)?;
+        let pre =
+            EntityHandlerExportsPre::new(instance_pre).map_err(PluginHostError::Compilation)?;
+        let resource_limits = ResourceLimits {
+            call_timeout_ms: resource_limits
+                .call_timeout_ms
+                .saturating_mul(HOOK_TIMEOUT_FACTOR),
+            ..resource_limits.clone()
+        };
+        Ok(Some(Arc::new(Self {
+            plugin_id: plugin_id.to_string(),
+            engine: engine.clone(),
+            pre,
+            resource_limits,
+            entity_store: Arc::clone(&state.entity_store),
+            event_store: Arc::clone(&state.event_store),
+            instance: Mutex::new(None),
+            traps: AtomicU64::new(0),
+            timeouts: AtomicU64::new(0),
+        })))
+    }
+
+    /// The plugin whose hooks these are.
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+
+    /// Number of hook calls that trapped and fell back.
+    pub fn trap_count(&self) -> u64 {
+        self.traps.load(Ordering::Relaxed)
+    }
+
+    /// Number of hook calls that hit their deadline and fell back. Each one
+    /// may have left this replica diverged from its peers.
+    pub fn timeout_count(&self) -> u64 {
+        self.timeouts.load(Ordering::Relaxed)
+    }
+
+    /// Hook instances may only log: they run outside any user action and
+    /// must not write entities or show UI.
+    fn instantiate(&self) -> wasmtime::Result<HandlerInstance> {
+        let mut permissions = PermissionSet::default_third_party();
+        for permission in [
+            Permission::Sdk,
+            Permission::Settings,
+            Permission::Navigation,
+            Permission::StateNotify,
+        ] {
+            permissions.deny(permission);
+        }
+        let state = PluginState {
+            plugin_id: self.plugin_id.clone(),
+            permissions,
+            declared_entity_types: HashSet::new(),
+            entity_store: Arc::clone(&self.entity_store),
+            event_store: Arc::clone(&self.event_store),
+            settings_quota_bytes: 0,
+            schemas: Vec::new(),
+            view_state: None,
+            state_dirty: false,
+            pending_navigation: None,
+            pending_commands: Vec::new(),
+            directory_grants: Vec::new(),
+            network_policy: NetworkPolicy::default(),
+            network_activity: Mutex::default(),
+            call_deadline: None,
+            entity_handler: None,
+            entity_changes: None,
+            event_bus: None,
+            limiter: TrackingLimiter::new(self.resource_limits.max_memory_bytes),
+            wasi_ctx: WasiCtxBuilder::new().build(),
+            resource_table: ResourceTable::new(),
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|s| &mut s.limiter);
+        start_call(&mut store, &self.resource_limits);
+        let bindings = self.pre.instantiate(&mut store)?;
+        Ok(HandlerInstance { store, bindings })
+    }
+
+    /// Runs one hook with a fresh fuel budget. Returns `None` if the
+    /// instance could not be created, the guest trapped or the call timed
+    /// out.
+    fn call<R>(
+        &self,
+        hook: &str,
+        f: impl FnOnce(&EntityHandlerExports, &mut Store<PluginState>) -> wasmtime::Result<R>,
+    ) -> Option<R> {
+        let mut guard = self.instance.lock().unwrap();
+        let result = run_hook(&mut guard, || self.instantiate(), |instance| {
+            start_call(&mut instance.store, &self.resource_limits);
+            f(&instance.bindings, &mut instance.store)
+        });
+        match result {
+            Ok(result) => Some(result),
+            Err(e) if is_timeout(&e) => {
+                warn!(
+                    plugin_id = %self.plugin_id,
+                    hook,
+                    "Entity handler timed out, replicas may diverge: {}",
+                    e
+                );
+                self.timeouts.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            Err(e) => {
+                warn!(plugin_id = %self.plugin_id, hook, "Entity handler trapped: {}", e);
+                self.traps.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+}
+
+/// Runs `f` on the instance in `slot`, creating it first if needed. A
+/// failed call discards the instance, since a trapped instance cannot be
+/// re-entered; the next call builds a fresh one.
+fn run_hook<I, R>(
+    slot: &mut Option<I>,
+    instantiate: impl FnOnce() -> wasmtime::Result<I>,
+    f: impl FnOnce(&mut I) -> wasmtime::Result<R>,
+) -> wasmtime::Result<R> {
+    let instance = match slot {
+        Some(instance) => instance,
+        None => slot.insert(instantiate()?),
+    };
+    let result = f(instance);
+    if result.is_err() {
+        *slot = None;
+    }
+    result
+}
+
+/// Whether a failed call hit its wall-clock deadline rather than trapping.
+fn is_timeout(error: &wasmtime::Error) -> bool {
+    error.downcast_ref::<Trap>() == Some(&Trap::Interrupt)
+}
+
+impl PluginDomainHandler for WasmEntityHandler {
+    fn validate(&self, entity: &Entity) -> Result<(), String> {
+        let json = serde_json::to_string(entity).map_err(|e| e.to_string())?;
+        let result = self.call("validate", |b, store| {
+            b.privstack_plugin_entity_handler()
+                .call_validate(store, &entity.entity_type, &json)
+        });
+        result.unwrap_or_else(|| {
+            Err(format!("validation hook of plugin '{}' failed", self.plugin_id))
+        })
+    }
+
+    fn on_after_load(&self, entity: &mut Entity) {
+        let Ok(json) = serde_json::to_string(&*entity) else {
+            return;
+        };
+        let result = self.call("on-after-load", |b, store| {
+            b.privstack_plugin_entity_handler()
+                .call_on_after_load(store, &entity.entity_type, &json)
+        });
+        if let Some(Some(data)) = result {
+            match serde_json::from_str(&data) {
+                Ok(data) => entity.data = data,
+                Err(e) => {
+                    warn!(plugin_id = %self.plugin_id, "on-after-load returned bad JSON: {}", e)
+                }
+            }
+        }
+    }
+
+    fn merge(&self, local: &Entity, remote: &Entity) -> Entity {
+        let merged = match (serde_json::to_string(local), serde_json::to_string(remote)) {
+            (Ok(local_json), Ok(remote_json)) => self.call("merge", |b, store| {
+                b.privstack_plugin_entity_handler().call_merge(
+                    store,
+                    &local.entity_type,
+                    &local_json,
+                    &remote_json,
+                )
+            }),
+            _ => None,
+        };
+        merged_or_fallback(&self.plugin_id, local, remote, merged)
+    }
+}
+
+/// The entity a `merge` hook produced, or the last-writer-wins fallback if
+/// the hook failed (`None`), rejected the merge or returned bad JSON.
+fn merged_or_fallback(
+    plugin_id: &str,
+    local: &Entity,
+    remote: &Entity,
+    merged: Option<Result<String, String>>,
+) -> Entity {
+    let data = match merged {
+        Some(Ok(json)) => serde_json::from_str(&json).map_err(|e| e.to_string()),
+        Some(Err(e)) => Err(e),
+        None => Err("hook failed".to_string()),
+    };
+    match data {
+        Ok(data) => Entity {
+            data,
+            modified_at: local.modified_at.max(remote.modified_at),
+            ..local.clone()
+        },
+        Err(e) => {
+            warn!(
+                plugin_id,
+                entity_id = %local.id,
+                "Custom merge failed, using last-writer-wins: {}",
+                e
+            );
+            if remote.modified_at >= local.modified_at {
+                remote.clone()
+            } else {
+                local.clone()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entity(data: serde_json::Value, modified_at: i64) -> Entity {
+        Entity {
+            id: "e1".into(),
+            entity_type: "note".into(),
+            data,
+            created_at: 1,
+            modified_at,
+            created_by: "peer".into(),
+        }
+    }
+
+    #[test]
+    fn trapped_instance_is_rebuilt_on_next_call() {
+        let mut slot = None;
+        let mut built = 0;
+        let mut instantiate = || {
+            built += 1;
+            Ok(built)
+        };
+        assert_eq!(run_hook(&mut slot, &mut instantiate, |i| Ok(*i)).unwrap(), 1);
+        assert_eq!(run_hook(&mut slot, &mut instantiate, |i| Ok(*i)).unwrap(), 1);
+
+        let trapped: wasmtime::Result<i32> = run_hook(&mut slot, &mut instantiate, |_| {
+            Err(Trap::UnreachableCodeReached.into())
+        });
+        assert!(trapped.is_err());
+        assert!(slot.is_none());
+        assert_eq!(run_hook(&mut slot, &mut instantiate, |i| Ok(*i)).unwrap(), 2);
+    }
+
+    #[test]
+    fn failed_instantiation_is_retried() {
+        let mut slot: Option<i32> = None;
+        let failed = run_hook(&mut slot, || Err(wasmtime::Error::msg("no")), |i| Ok(*i));
+        assert!(failed.is_err());
+        assert!(slot.is_none());
+        assert_eq!(run_hook(&mut slot, || Ok(7), |i| Ok(*i)).unwrap(), 7);
+    }
+
+    #[test]
+    fn only_the_deadline_counts_as_a_timeout() {
+        assert!(is_timeout(&Trap::Interrupt.into()));
+        for trap in [Trap::OutOfFuel, Trap::UnreachableCodeReached, Trap::MemoryOutOfBounds] {
+            assert!(!is_timeout(&trap.into()));
+        }
+        assert!(!is_timeout(&wasmtime::Error::msg("instantiation failed")));
+    }
+
+    #[test]
+    fn failed_merge_falls_back_to_last_writer_wins() {
+        let local = entity(json!({"title": "local"}), 10);
+        let remote = entity(json!({"title": "remote"}), 20);
+        for merged in [None, Some(Err("rejected".into())), Some(Ok("not json".into()))] {
+            let result = merged_or_fallback("p", &local, &remote, merged.clone());
+            assert_eq!(result.data, remote.data);
+            let result = merged_or_fallback("p", &remote, &local, merged);
+            assert_eq!(result.data, remote.data);
+        }
+    }
+
+    #[test]
+    fn successful_merge_keeps_hook_data() {
+        let local = entity(json!({"n": 1}), 10);
+        let remote = entity(json!({"n": 2}), 20);
+        let merged = merged_or_fallback("p", &local, &remote, Some(Ok(r#"{"n":3}"#.into())));
+        assert_eq!(merged.data, json!({"n": 3}));
+        assert_eq!(merged.modified_at, 20);
+        assert_eq!(merged.id, local.id);
+    }
+}
@@ -61,6 +61,21 @@ pub enum PluginHostError {
         timeout_ms: u64,
     },
 
+    #[error("plugin '{plugin_id}' is disabled: {reason}")]
+    PluginDisabled {
+        plugin_id: String,
+        reason: String,
+    },
+
+    #[error("invalid filesystem grant: {0}")]
+    InvalidGrant(String),
+
+    #[error("invalid scheduled job: {0}")]
+    InvalidJob(String),
+
+    #[error("invalid event: {0}")]
+    InvalidEvent(String),
+
     #[error("capability '{capability}' not supported by plugin '{plugin_id}'")]
     CapabilityNotSupported {
         plugin_id: String,
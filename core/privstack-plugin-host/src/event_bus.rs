@@ -0,0 +1,432 @@
+//! Inter-plugin publish/subscribe through the `events` import.
+//!
+//! A topic belongs to the plugin that publishes it and must be declared in
+//! that plugin's manifest; the shell passes the declared names to
+//! [`EventBus::set_topics`]. Subscribing to another plugin's topic needs
+//! `EventSubscribe` (Tier 2, just-in-time), and every attempt is written to
+//! the policy engine's [`PluginAuditLog`]. Published events are queued per
+//! subscriber and delivered later by the manager through the subscriber's
+//! `event-subscriber` export, so a publisher never runs inside another
+//! plugin's call.
+//!
+//! Subscriptions are held in memory and end when the subscriber unloads;
+//! plugins subscribe again in `initialize`. A topic may be subscribed to
+//! before its publisher is loaded.
+
+use crate::bindings::event_subscriber_exports::EventSubscriberExports;
+use crate::bindings::event_subscriber_exports::exports::privstack::plugin::event_subscriber as wit;
+use crate::error::PluginHostError;
+use crate::permissions::{Permission, PermissionSet};
+use crate::policy::PluginAuditLog;
+use crate::sandbox::PluginState;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+use wasmtime::component::{Component, Instance};
+use wasmtime::{Engine, Store};
+
+/// WIT interface name prefix of the `event-subscriber` export.
+const EVENT_SUBSCRIBER_EXPORT: &str = "privstack:plugin/event-subscriber";
+
+/// Longest topic name, in bytes.
+pub const MAX_TOPIC_LEN: usize = 64;
+/// Largest payload a single event may carry.
+pub const MAX_EVENT_PAYLOAD_BYTES: usize = 64 * 1024;
+/// Events passed to a single `on-events` call.
+pub const MAX_EVENTS_PER_CALL: usize = 100;
+/// Subscriptions a single plugin may hold.
+const MAX_SUBSCRIPTIONS_PER_PLUGIN: usize = 64;
+/// Undelivered events kept before new ones are dropped.
+const MAX_PENDING_EVENTS: usize = 10_000;
+
+/// Audit action for publishing to a topic the plugin did not declare.
+const AUDIT_PUBLISH: &str = "event-publish";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BusEvent {
+    pub publisher_id: String,
+    pub topic: String,
+    pub payload_json: String,
+}
+
+impl BusEvent {
+    fn to_wit(&self) -> wit::BusEvent {
+        wit::BusEvent {
+            publisher_id: self.publisher_id.clone(),
+            topic: self.topic.clone(),
+            payload_json: self.payload_json.clone(),
+        }
+    }
+}
+
+/// Checks a topic name: 1-64 characters of `[a-z0-9._-]`.
+pub fn validate_topic(topic: &str) -> Result<(), PluginHostError> {
+    let valid = !topic.is_empty()
+        && topic.len() <= MAX_TOPIC_LEN
+        && topic
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(PluginHostError::InvalidEvent(format!(
+            "topic '{}' must be 1-{} characters of [a-z0-9._-]",
+            topic, MAX_TOPIC_LEN
+        )))
+    }
+}
+
+#[derive(Default)]
+struct BusState {
+    /// Topics each plugin declared in its manifest.
+    topics: HashMap<String, BTreeSet<String>>,
+    /// Subscribers of each (publisher, topic).
+    subscribers: HashMap<(String, String), BTreeSet<String>>,
+    /// Events waiting for delivery, with the subscriber they are for.
+    pending: VecDeque<(String, BusEvent)>,
+    dropped: usize,
+}
+
+/// Topic registry, subscriptions and undelivered events, shared by the
+/// manager and every sandbox.
+pub struct EventBus {
+    audit_log: Arc<PluginAuditLog>,
+    state: Mutex<BusState>,
+}
+
+impl EventBus {
+    pub fn new(audit_log: Arc<PluginAuditLog>) -> Self {
+        Self {
+            audit_log,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Sets the topics a plugin declared in its manifest, replacing any
+    /// set before.
+    pub fn set_topics(&self, plugin_id: &str, topics: Vec<String>) -> Result<(), PluginHostError> {
+        for topic in &topics {
+            validate_topic(topic)?;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.topics.insert(plugin_id.to_string(), topics.into_iter().collect());
+        Ok(())
+    }
+
+    /// Topics a plugin declared, sorted.
+    pub fn topics(&self, plugin_id: &str) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state
+            .topics
+            .get(plugin_id)
+            .map(|topics| topics.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Queues `payload_json` for every subscriber of `topic`. Returns how
+    /// many subscribers it was queued for.
+    pub fn publish(
+        &self,
+        publisher_id: &str,
+        topic: &str,
+        payload_json: &str,
+    ) -> Result<usize, PluginHostError> {
+        let mut state = self.state.lock().unwrap();
+        let declared = state
+            .topics
+            .get(publisher_id)
+            .is_some_and(|topics| topics.contains(topic));
+        if !declared {
+            let reason = format!("topic '{}' is not declared in the manifest", topic);
+            self.audit_log
+                .record(publisher_id, AUDIT_PUBLISH, topic, Some(reason.clone()));
+            return Err(PluginHostError::InvalidEvent(reason));
+        }
+        if payload_json.len() > MAX_EVENT_PAYLOAD_BYTES {
+            return Err(PluginHostError::ResourceLimitExceeded {
+                plugin_id: publisher_id.to_string(),
+                detail: format!("event payload exceeds {} bytes", MAX_EVENT_PAYLOAD_BYTES),
+            });
+        }
+        serde_json::from_str::<serde_json::Value>(payload_json)
+            .map_err(|e| PluginHostError::InvalidEvent(format!("payload is not JSON: {}", e)))?;
+
+        let key = (publisher_id.to_string(), topic.to_string());
+        let subscribers: Vec<String> = state
+            .subscribers
+            .get(&key)
+            .map(|subscribers| subscribers.iter().cloned().collect())
+            .unwrap_or_default();
+        let event = BusEvent {
+            publisher_id: publisher_id.to_string(),
+            topic: topic.to_string(),
+            payload_json: payload_json.to_string(),
+        };
+        let mut queued = 0;
+        for subscriber in subscribers {
+            if state.pending.len() >= MAX_PENDING_EVENTS {
+                state.dropped += 1;
+                continue;
+            }
+            state.pending.push_back((subscriber, event.clone()));
+            queued += 1;
+        }
+        Ok(queued)
+    }
+
+    /// Subscribes `subscriber_id` to `topic` of `publisher_id` if
+    /// `permissions` hold `EventSubscribe`. Every attempt is audited.
+    pub fn subscribe(
+        &self,
+        subscriber_id: &str,
+        permissions: &PermissionSet,
+        publisher_id: &str,
+        topic: &str,
+    ) -> Result<(), PluginHostError> {
+        let target = format!("{}/{}", publisher_id, topic);
+        let result = self.try_subscribe(subscriber_id, permissions, publisher_id, topic);
+        let refused = result.as_ref().err().map(ToString::to_string);
+        self.audit_log.record(
+            subscriber_id,
+            Permission::EventSubscribe.interface_name(),
+            &target,
+            refused,
+        );
+        result
+    }
+
+    fn try_subscribe(
+        &self,
+        subscriber_id: &str,
+        permissions: &PermissionSet,
+        publisher_id: &str,
+        topic: &str,
+    ) -> Result<(), PluginHostError> {
+        if !permissions.is_granted(Permission::EventSubscribe) {
+            return Err(PluginHostError::PermissionDenied {
+                plugin_id: subscriber_id.to_string(),
+                permission: Permission::EventSubscribe.interface_name().to_string(),
+            });
+        }
+        validate_topic(topic)?;
+        let mut state = self.state.lock().unwrap();
+        let held = state
+            .subscribers
+            .values()
+            .filter(|subscribers| subscribers.contains(subscriber_id))
+            .count();
+        let key = (publisher_id.to_string(), topic.to_string());
+        let already = state
+            .subscribers
+            .get(&key)
+            .is_some_and(|subscribers| subscribers.contains(subscriber_id));
+        if !already && held >= MAX_SUBSCRIPTIONS_PER_PLUGIN {
+            return Err(PluginHostError::ResourceLimitExceeded {
+                plugin_id: subscriber_id.to_string(),
+                detail: format!("at most {} event subscriptions", MAX_SUBSCRIPTIONS_PER_PLUGIN),
+            });
+        }
+        state
+            .subscribers
+            .entry(key)
+            .or_default()
+            .insert(subscriber_id.to_string());
+        Ok(())
+    }
+
+    /// Ends a subscription. Returns false if there was none.
+    pub fn unsubscribe(&self, subscriber_id: &str, publisher_id: &str, topic: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let key = (publisher_id.to_string(), topic.to_string());
+        let Some(subscribers) = state.subscribers.get_mut(&key) else {
+            return false;
+        };
+        let removed = subscribers.remove(subscriber_id);
+        if subscribers.is_empty() {
+            state.subscribers.remove(&key);
+        }
+        removed
+    }
+
+    /// The (publisher, topic) pairs a plugin is subscribed to, sorted.
+    pub fn subscriptions(&self, subscriber_id: &str) -> Vec<(String, String)> {
+        let state = self.state.lock().unwrap();
+        let mut subscriptions: Vec<(String, String)> = state
+            .subscribers
+            .iter()
+            .filter(|(_, subscribers)| subscribers.contains(subscriber_id))
+            .map(|(key, _)| key.clone())
+            .collect();
+        subscriptions.sort();
+        subscriptions
+    }
+
+    /// Drops a plugin's subscriptions and the events waiting for it.
+    pub fn remove_subscriber(&self, subscriber_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.subscribers.retain(|_, subscribers| {
+            subscribers.remove(subscriber_id);
+            !subscribers.is_empty()
+        });
+        state.pending.retain(|(subscriber, _)| subscriber != subscriber_id);
+    }
+
+    /// Removes everything the bus holds for a plugin, including its topics.
+    pub fn forget_plugin(&self, plugin_id: &str) {
+        self.remove_subscriber(plugin_id);
+        self.state.lock().unwrap().topics.remove(plugin_id);
+    }
+
+    /// Number of events waiting for delivery.
+    pub fn pending_len(&self) -> usize {
+        self.state.lock().unwrap().pending.len()
+    }
+
+    /// Takes the undelivered events, oldest first, each paired with the
+    /// subscriber it is for.
+    pub fn take_pending(&self) -> Vec<(String, BusEvent)> {
+        let mut state = self.state.lock().unwrap();
+        if state.dropped > 0 {
+            warn!(dropped = state.dropped, "Event bus queue overflowed");
+            state.dropped = 0;
+        }
+        state.pending.drain(..).collect()
+    }
+}
+
+/// Loads the component's `event-subscriber` export from its main instance,
+/// or returns `None` if it does not export one.
+pub(crate) fn bind_event_subscriber(
+    engine: &Engine,
+    component: &Component,
+    store: &mut Store<PluginState>,
+    instance: &Instance,
+) -> wasmtime::Result<Option<EventSubscriberExports>> {
+    let exported = component
+        .component_type()
+        .exports(engine)
+        .any(|(name, _)| name.starts_with(EVENT_SUBSCRIBER_EXPORT));
+    if !exported {
+        return Ok(None);
+    }
+    EventSubscriberExports::new(store, instance).map(Some)
+}
+
+/// Calls `on-events` with one batch.
+pub(crate) fn call_on_events(
+    subscriber: &EventSubscriberExports,
+    store: &mut Store<PluginState>,
+    events: &[BusEvent],
+) -> wasmtime::Result<()> {
+    let events: Vec<wit::BusEvent> = events.iter().map(BusEvent::to_wit).collect();
+    subscriber
+        .privstack_plugin_event_subscriber()
+        .call_on_events(store, &events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bus() -> EventBus {
+        let bus = EventBus::new(Arc::new(PluginAuditLog::new(100)));
+        bus.set_topics("privstack.tasks", vec!["task-completed".into()]).unwrap();
+        bus
+    }
+
+    fn subscriber_permissions() -> PermissionSet {
+        let mut permissions = PermissionSet::default_third_party();
+        permissions.grant(Permission::EventSubscribe);
+        permissions
+    }
+
+    #[test]
+    fn published_events_are_queued_per_subscriber() {
+        let bus = bus();
+        let permissions = subscriber_permissions();
+        bus.subscribe("acme.timer", &permissions, "privstack.tasks", "task-completed")
+            .unwrap();
+        bus.subscribe("acme.stats", &permissions, "privstack.tasks", "task-completed")
+            .unwrap();
+
+        let queued = bus
+            .publish("privstack.tasks", "task-completed", r#"{"task_id":"t1"}"#)
+            .unwrap();
+        assert_eq!(queued, 2);
+        let pending = bus.take_pending();
+        assert_eq!(pending[0].0, "acme.stats");
+        assert_eq!(pending[1].0, "acme.timer");
+        assert_eq!(pending[1].1.payload_json, r#"{"task_id":"t1"}"#);
+        assert_eq!(bus.pending_len(), 0);
+    }
+
+    #[test]
+    fn publishing_requires_a_declared_topic_and_json() {
+        let bus = bus();
+        assert!(matches!(
+            bus.publish("privstack.tasks", "task-deleted", "{}"),
+            Err(PluginHostError::InvalidEvent(_))
+        ));
+        assert!(matches!(
+            bus.publish("acme.timer", "task-completed", "{}"),
+            Err(PluginHostError::InvalidEvent(_))
+        ));
+        assert!(bus.publish("privstack.tasks", "task-completed", "not json").is_err());
+        let big = format!("\"{}\"", "x".repeat(MAX_EVENT_PAYLOAD_BYTES));
+        assert!(matches!(
+            bus.publish("privstack.tasks", "task-completed", &big),
+            Err(PluginHostError::ResourceLimitExceeded { .. })
+        ));
+        // No subscribers is not an error
+        assert_eq!(bus.publish("privstack.tasks", "task-completed", "{}").unwrap(), 0);
+    }
+
+    #[test]
+    fn subscribing_requires_permission_and_is_audited() {
+        let bus = bus();
+        let denied = bus.subscribe(
+            "acme.timer",
+            &PermissionSet::default_third_party(),
+            "privstack.tasks",
+            "task-completed",
+        );
+        assert!(matches!(denied, Err(PluginHostError::PermissionDenied { .. })));
+        bus.subscribe("acme.timer", &subscriber_permissions(), "privstack.tasks", "task-completed")
+            .unwrap();
+
+        let log = bus.audit_log.entries();
+        assert_eq!(log.len(), 2);
+        assert!(!log[0].allowed);
+        assert!(log[1].allowed);
+        assert_eq!(log[1].action, "event-subscribe");
+        assert_eq!(log[1].target, "privstack.tasks/task-completed");
+    }
+
+    #[test]
+    fn unsubscribe_and_remove_subscriber() {
+        let bus = bus();
+        let permissions = subscriber_permissions();
+        bus.subscribe("acme.timer", &permissions, "privstack.tasks", "task-completed")
+            .unwrap();
+        bus.subscribe("acme.timer", &permissions, "privstack.notes", "note-shared")
+            .unwrap();
+        assert_eq!(bus.subscriptions("acme.timer").len(), 2);
+
+        assert!(bus.unsubscribe("acme.timer", "privstack.notes", "note-shared"));
+        assert!(!bus.unsubscribe("acme.timer", "privstack.notes", "note-shared"));
+
+        bus.publish("privstack.tasks", "task-completed", "{}").unwrap();
+        bus.remove_subscriber("acme.timer");
+        assert!(bus.subscriptions("acme.timer").is_empty());
+        assert_eq!(bus.pending_len(), 0);
+    }
+
+    #[test]
+    fn topic_names_are_validated() {
+        let bus = bus();
+        assert!(bus.set_topics("acme.timer", vec!["Bad Topic".into()]).is_err());
+        assert!(bus.set_topics("acme.timer", vec!["timer.started".into()]).is_ok());
+        assert_eq!(bus.topics("acme.timer"), ["timer.started"]);
+    }
+}
@@ -0,0 +1,296 @@
+//! Scoped filesystem access through WASI preopens.
+//!
+//! Plugins start with no preopened directories. With `Permission::Filesystem`
+//! granted, each [`DirectoryGrant`] mounts one host directory into the plugin
+//! at a guest path, read-only or read-write. Grants come from two places:
+//! manifest scopes the user approved at install (mounted at `/<scope>`), and
+//! one-off grants of a file dialog selection (mounted under [`SELECTION_ROOT`]).
+//!
+//! A preopen can only be a directory, so a selected file is staged alone in
+//! a private directory and that directory is mounted, never the file's
+//! parent. Staging directories live under the app data directory, readable
+//! only by the current user. Selections that are symlinks are refused.
+
+use crate::error::PluginHostError;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+use wasmtime_wasi::p2::{WasiCtx, WasiCtxBuilder};
+use wasmtime_wasi::{DirPerms, FilePerms};
+
+/// Guest directory under which one-off dialog selections are mounted.
+pub const SELECTION_ROOT: &str = "/selected";
+
+/// A host directory mounted into a plugin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectoryGrant {
+    /// Absolute path of the directory on the host.
+    pub host_path: PathBuf,
+    /// Absolute path at which the plugin sees it (e.g. `/vault`).
+    pub guest_path: String,
+    /// Whether the plugin may create, modify and delete files.
+    #[serde(default)]
+    pub writable: bool,
+}
+
+impl DirectoryGrant {
+    /// Checks that the host directory exists and the guest path is usable.
+    pub fn validate(&self) -> Result<(), PluginHostError> {
+        if !self.host_path.is_absolute() || !self.host_path.is_dir() {
+            return Err(PluginHostError::InvalidGrant(format!(
+                "granted path '{}' is not an existing absolute directory",
+                self.host_path.display()
+            )));
+        }
+        let valid_guest = self.guest_path.len() > 1
+            && self.guest_path.starts_with('/')
+            && !self
+                .guest_path
+                .split('/')
+                .any(|part| part == ".." || part == ".");
+        if !valid_guest {
+            return Err(PluginHostError::InvalidGrant(format!(
+                "invalid guest path '{}'",
+                self.guest_path
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Builds a WASI context with the given directories preopened. An empty
+/// list gives the default context: no filesystem, no network, no env.
+pub(crate) fn build_wasi_ctx(grants: &[DirectoryGrant]) -> Result<WasiCtx, PluginHostError> {
+    let mut builder = WasiCtxBuilder::new();
+    for grant in grants {
+        let (dir_perms, file_perms) = if grant.writable {
+            (DirPerms::all(), FilePerms::all())
+        } else {
+            (DirPerms::READ, FilePerms::READ)
+        };
+        builder
+            .preopened_dir(&grant.host_path, &grant.guest_path, dir_perms, file_perms)
+            .map_err(|e| {
+                PluginHostError::InvalidGrant(format!(
+                    "cannot preopen '{}': {}",
+                    grant.host_path.display(),
+                    e
+                ))
+            })?;
+    }
+    Ok(builder.build())
+}
+
+/// Host directory under `staging_dir` holding the staged file selections of
+/// `plugin_id`. The id is hex-encoded so distinct ids never share a
+/// directory.
+pub(crate) fn selection_staging_root(staging_dir: &Path, plugin_id: &str) -> PathBuf {
+    staging_dir.join(hex::encode(plugin_id))
+}
+
+/// Deletes the staged file selections of `plugin_id`.
+pub(crate) fn remove_staged_selections(staging_dir: &Path, plugin_id: &str) {
+    let root = selection_staging_root(staging_dir, plugin_id);
+    if root.exists()
+        && let Err(e) = std::fs::remove_dir_all(&root)
+    {
+        warn!(plugin_id, "Failed to remove staged selections: {}", e);
+    }
+}
+
+/// Creates `path` and any missing parents, accessible only by the current
+/// user.
+fn create_private_dir_all(path: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(path)
+}
+
+/// Grant for a file dialog selection. A selected directory is mounted as
+/// is. A selected file is linked (or copied) alone into a new directory
+/// under `staging_root`, which is mounted read-only. Returns the grant and
+/// the guest path of the selection itself. Symlinks are never followed, and
+/// file selections are refused without a staging root.
+pub fn selection_grant(
+    selected: &Path,
+    index: usize,
+    writable: bool,
+    staging_root: Option<&Path>,
+) -> Result<(DirectoryGrant, String), PluginHostError> {
+    let mount = format!("{}/{}", SELECTION_ROOT, index);
+    let invalid = || {
+        PluginHostError::InvalidGrant(format!("invalid selection '{}'", selected.display()))
+    };
+    let file_type = std::fs::symlink_metadata(selected)
+        .map_err(|_| invalid())?
+        .file_type();
+    if file_type.is_dir() {
+        let grant = DirectoryGrant {
+            host_path: selected.to_path_buf(),
+            guest_path: mount.clone(),
+            writable,
+        };
+        grant.validate()?;
+        return Ok((grant, mount));
+    }
+
+    let name = match selected.file_name() {
+        Some(name) if file_type.is_file() => name,
+        _ => return Err(invalid()),
+    };
+    let staging_root = staging_root.ok_or_else(|| {
+        PluginHostError::InvalidGrant(format!(
+            "cannot stage selection '{}': no staging directory",
+            selected.display()
+        ))
+    })?;
+    let staged = staging_root.join(uuid::Uuid::new_v4().simple().to_string());
+    let stage = || -> std::io::Result<()> {
+        create_private_dir_all(&staged)?;
+        let target = staged.join(name);
+        if std::fs::hard_link(selected, &target).is_err() {
+            std::fs::copy(selected, &target)?;
+        }
+        Ok(())
+    };
+    if let Err(e) = stage() {
+        let _ = std::fs::remove_dir_all(&staged);
+        return Err(PluginHostError::InvalidGrant(format!(
+            "cannot stage selection '{}': {}",
+            selected.display(),
+            e
+        )));
+    }
+    let grant = DirectoryGrant {
+        host_path: staged,
+        guest_path: mount.clone(),
+        writable: false,
+    };
+    grant.validate()?;
+    Ok((grant, format!("{}/{}", mount, name.to_string_lossy())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grant(host: &Path, guest: &str) -> DirectoryGrant {
+        DirectoryGrant {
+            host_path: host.to_path_buf(),
+            guest_path: guest.into(),
+            writable: false,
+        }
+    }
+
+    #[test]
+    fn validate_requires_existing_dir_and_clean_guest_path() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(grant(dir.path(), "/vault").validate().is_ok());
+        assert!(grant(dir.path(), "vault").validate().is_err());
+        assert!(grant(dir.path(), "/").validate().is_err());
+        assert!(grant(dir.path(), "/a/../b").validate().is_err());
+        assert!(grant(&dir.path().join("missing"), "/vault").validate().is_err());
+        assert!(grant(Path::new("relative"), "/vault").validate().is_err());
+    }
+
+    #[test]
+    fn build_wasi_ctx_with_preopens() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut rw = grant(dir.path(), "/out");
+        rw.writable = true;
+        assert!(build_wasi_ctx(&[grant(dir.path(), "/in"), rw]).is_ok());
+        assert!(build_wasi_ctx(&[]).is_ok());
+    }
+
+    #[test]
+    fn selection_of_file_mounts_only_that_file_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = tempfile::tempdir().unwrap();
+        let file = dir.path().join("photo.jpg");
+        std::fs::write(&file, b"x").unwrap();
+        std::fs::write(dir.path().join("diary.txt"), b"private").unwrap();
+
+        let (g, guest) = selection_grant(&file, 2, true, Some(staging.path())).unwrap();
+        assert_ne!(g.host_path, dir.path());
+        assert!(g.host_path.starts_with(staging.path()));
+        assert_eq!(g.guest_path, "/selected/2");
+        assert!(!g.writable);
+        assert_eq!(guest, "/selected/2/photo.jpg");
+        let staged: Vec<_> = std::fs::read_dir(&g.host_path)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(staged, vec![std::ffi::OsString::from("photo.jpg")]);
+        assert_eq!(std::fs::read(g.host_path.join("photo.jpg")).unwrap(), b"x");
+
+        let (g, guest) = selection_grant(dir.path(), 3, true, Some(staging.path())).unwrap();
+        assert_eq!(g.host_path, dir.path());
+        assert!(g.writable);
+        assert_eq!(guest, "/selected/3");
+
+        let missing = dir.path().join("missing");
+        assert!(selection_grant(&missing, 4, false, Some(staging.path())).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn staged_selections_are_private_to_the_user() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let data = tempfile::tempdir().unwrap();
+        let file = dir.path().join("photo.jpg");
+        std::fs::write(&file, b"x").unwrap();
+
+        let root = selection_staging_root(&data.path().join("selections"), "p1");
+        let (g, _) = selection_grant(&file, 0, false, Some(&root)).unwrap();
+        for path in [&g.host_path, &root] {
+            let mode = std::fs::metadata(path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700, "{}", path.display());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_selections_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = tempfile::tempdir().unwrap();
+        let secret = dir.path().join("secret");
+        std::fs::create_dir(&secret).unwrap();
+        std::fs::write(secret.join("key"), b"k").unwrap();
+        std::os::unix::fs::symlink(&secret, dir.path().join("dir-link")).unwrap();
+        std::os::unix::fs::symlink(secret.join("key"), dir.path().join("file-link")).unwrap();
+
+        for link in ["dir-link", "file-link"] {
+            let selected = dir.path().join(link);
+            assert!(selection_grant(&selected, 0, false, Some(staging.path())).is_err());
+        }
+        assert_eq!(std::fs::read_dir(staging.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn file_selections_need_a_staging_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("photo.jpg");
+        std::fs::write(&file, b"x").unwrap();
+        assert!(selection_grant(&file, 0, false, None).is_err());
+        assert!(selection_grant(dir.path(), 0, false, None).is_ok());
+    }
+
+    #[test]
+    fn staging_root_is_per_plugin_and_injective() {
+        let staging = Path::new("/data/selections");
+        let root = selection_staging_root(staging, "../evil/plugin");
+        assert_eq!(root.parent(), Some(staging));
+        let name = root.file_name().unwrap().to_str().unwrap();
+        assert!(name.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(
+            selection_staging_root(staging, "a.b"),
+            selection_staging_root(staging, "a_b")
+        );
+    }
+}
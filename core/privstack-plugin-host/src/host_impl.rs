@@ -3,10 +3,15 @@
 //! Each WIT import interface maps to a trait generated by `wasmtime::component::bindgen!`.
 //! With `trappable_imports: true`, all methods return `wasmtime::Result<T>`.
 
+use crate::bindings::agent_world::privstack::plugin::agent;
 use crate::bindings::privstack::plugin::*;
+use crate::entity_changes::EntityChangeKind;
+use crate::net_policy::FetchRequest;
 use crate::permissions::Permission;
-use crate::sandbox::PluginState;
-use privstack_model::Entity;
+use crate::plugin_settings::is_reserved_entity_type;
+use crate::scheduler::JobSchedule;
+use crate::sandbox::{PendingCommand, PluginState};
+use privstack_model::{Entity, PluginDomainHandler};
 use tracing::{debug, error, info, warn};
 
 // types::Host is an empty marker trait generated by wasmtime bindgen
@@ -20,6 +25,12 @@ impl sdk::Host for PluginState {
     fn send(&mut self, message: types::SdkMessage) -> wasmtime::Result<types::SdkResponse> {
         let entity_type = &message.entity_type;
 
+        if is_reserved_entity_type(entity_type) {
+            return Ok(error_response(
+                403,
+                format!("entity type '{}' is reserved", entity_type),
+            ));
+        }
         if !self.declared_entity_types.contains(entity_type) {
             warn!(
                 plugin_id = %self.plugin_id,
@@ -114,7 +125,8 @@ impl sdk::Host for PluginState {
             .entity_store
             .search(&query, types_refs.as_deref(), limit as usize)
         {
-            Ok(results) => {
+            Ok(mut results) => {
+                results.retain(|e| !is_reserved_entity_type(&e.entity_type));
                 let json = serde_json::to_string(&results).unwrap_or_else(|_| "[]".into());
                 types::SdkResponse {
                     success: true,
@@ -134,6 +146,39 @@ impl sdk::Host for PluginState {
 }
 
 impl PluginState {
+    /// Runs the plugin's `validate` hook, if it exports one. Returns the
+    /// error response for a rejected entity.
+    fn validate_entity(&self, entity: &Entity) -> Option<types::SdkResponse> {
+        let handler = self.entity_handler.as_ref()?;
+        handler
+            .validate(entity)
+            .err()
+            .map(|message| error_response(422, message))
+    }
+
+    /// Refuses access by id to a host-owned entity, such as another
+    /// plugin's settings or scheduled jobs. It is reported as missing so a
+    /// plugin cannot probe for them.
+    fn reject_reserved(&self, entity_id: &str) -> Option<types::SdkResponse> {
+        let entity = self.entity_store.get_entity(entity_id).ok().flatten()?;
+        if !is_reserved_entity_type(&entity.entity_type) {
+            return None;
+        }
+        warn!(
+            plugin_id = %self.plugin_id,
+            entity_id,
+            "Access to reserved entity denied"
+        );
+        Some(error_response(404, "not found".into()))
+    }
+
+    /// Type of an entity about to be removed, looked up only when there is
+    /// a change queue to report the removal to.
+    fn changed_entity_type(&self, entity_id: &str) -> Option<String> {
+        self.entity_changes.as_ref()?;
+        self.entity_store.get_entity(entity_id).ok().flatten().map(|e| e.entity_type)
+    }
+
     fn handle_create(&self, entity_type: &str, payload: Option<&str>) -> types::SdkResponse {
         let payload = match payload {
             Some(p) => p,
@@ -169,9 +214,13 @@ impl PluginState {
             modified_at: now,
             created_by: self.plugin_id.clone(),
         };
+        if let Some(rejected) = self.validate_entity(&entity) {
+            return rejected;
+        }
 
         match self.entity_store.save_entity_raw(&entity) {
             Ok(()) => {
+                self.record_entity_change(&id, entity_type, EntityChangeKind::Created);
                 let result = serde_json::json!({ "id": id });
                 types::SdkResponse {
                     success: true,
@@ -201,6 +250,9 @@ impl PluginState {
                 }
             }
         };
+        if let Some(rejected) = self.reject_reserved(id) {
+            return rejected;
+        }
 
         match self.entity_store.get_entity(id) {
             Ok(Some(entity)) => {
@@ -244,6 +296,9 @@ impl PluginState {
                 }
             }
         };
+        if let Some(rejected) = self.reject_reserved(id) {
+            return rejected;
+        }
         let payload = match payload {
             Some(p) => p,
             None => {
@@ -271,6 +326,7 @@ impl PluginState {
         let now = chrono::Utc::now().timestamp();
 
         // Merge update payload into existing entity to preserve unchanged fields
+        let mut kind = EntityChangeKind::Updated;
         let entity = match self.entity_store.get_entity(id) {
             Ok(Some(mut existing)) => {
                 match (existing.data.as_object_mut(), data) {
@@ -288,6 +344,7 @@ impl PluginState {
             }
             _ => {
                 // Entity doesn't exist — create new (backwards-compatible)
+                kind = EntityChangeKind::Created;
                 Entity {
                     id: id.to_string(),
                     entity_type: entity_type.to_string(),
@@ -298,14 +355,20 @@ impl PluginState {
                 }
             }
         };
+        if let Some(rejected) = self.validate_entity(&entity) {
+            return rejected;
+        }
 
         match self.entity_store.save_entity_raw(&entity) {
-            Ok(()) => types::SdkResponse {
-                success: true,
-                error_code: None,
-                error_message: None,
-                data: None,
-            },
+            Ok(()) => {
+                self.record_entity_change(id, &entity.entity_type, kind);
+                types::SdkResponse {
+                    success: true,
+                    error_code: None,
+                    error_message: None,
+                    data: None,
+                }
+            }
             Err(e) => types::SdkResponse {
                 success: false,
                 error_code: Some(500),
@@ -327,14 +390,23 @@ impl PluginState {
                 }
             }
         };
+        if let Some(rejected) = self.reject_reserved(id) {
+            return rejected;
+        }
 
+        let entity_type = self.changed_entity_type(id);
         match self.entity_store.delete_entity(id) {
-            Ok(()) => types::SdkResponse {
-                success: true,
-                error_code: None,
-                error_message: None,
-                data: None,
-            },
+            Ok(()) => {
+                if let Some(entity_type) = entity_type {
+                    self.record_entity_change(id, &entity_type, EntityChangeKind::Deleted);
+                }
+                types::SdkResponse {
+                    success: true,
+                    error_code: None,
+                    error_message: None,
+                    data: None,
+                }
+            }
             Err(e) => types::SdkResponse {
                 success: false,
                 error_code: Some(500),
@@ -419,13 +491,22 @@ impl PluginState {
                 }
             }
         };
+        if let Some(rejected) = self.reject_reserved(id) {
+            return rejected;
+        }
+        let entity_type = self.changed_entity_type(id);
         match self.entity_store.trash_entity(id) {
-            Ok(()) => types::SdkResponse {
-                success: true,
-                error_code: None,
-                error_message: None,
-                data: None,
-            },
+            Ok(()) => {
+                if let Some(entity_type) = entity_type {
+                    self.record_entity_change(id, &entity_type, EntityChangeKind::Deleted);
+                }
+                types::SdkResponse {
+                    success: true,
+                    error_code: None,
+                    error_message: None,
+                    data: None,
+                }
+            }
             Err(e) => types::SdkResponse {
                 success: false,
                 error_code: Some(500),
@@ -447,13 +528,22 @@ impl PluginState {
                 }
             }
         };
+        if let Some(rejected) = self.reject_reserved(id) {
+            return rejected;
+        }
+        let entity_type = self.changed_entity_type(id);
         match self.entity_store.restore_entity(id) {
-            Ok(()) => types::SdkResponse {
-                success: true,
-                error_code: None,
-                error_message: None,
-                data: None,
-            },
+            Ok(()) => {
+                if let Some(entity_type) = entity_type {
+                    self.record_entity_change(id, &entity_type, EntityChangeKind::Created);
+                }
+                types::SdkResponse {
+                    success: true,
+                    error_code: None,
+                    error_message: None,
+                    data: None,
+                }
+            }
             Err(e) => types::SdkResponse {
                 success: false,
                 error_code: Some(500),
@@ -709,29 +799,91 @@ impl PluginState {
 }
 
 // ============================================================
-// settings::Host — Plugin-scoped key-value settings (Tier 1)
+// settings::Host — Plugin-scoped persisted settings (Tier 1)
 // ============================================================
 
 impl settings::Host for PluginState {
     fn get(&mut self, key: String, default_value: String) -> wasmtime::Result<String> {
-        Ok(self
-            .settings
-            .get(&key)
-            .cloned()
-            .unwrap_or(default_value))
+        Ok(self.settings_get(&key, &default_value))
     }
 
     fn set(&mut self, key: String, value: String) -> wasmtime::Result<()> {
-        self.settings.insert(key, value);
+        self.settings_set(&key, &value);
         Ok(())
     }
 
     fn remove(&mut self, key: String) -> wasmtime::Result<()> {
-        self.settings.remove(&key);
+        self.settings_remove(&key);
         Ok(())
     }
 }
 
+// ============================================================
+// scheduler::Host — Persistent background jobs (Tier 1)
+// ============================================================
+
+impl scheduler::Host for PluginState {
+    fn schedule_interval(
+        &mut self,
+        job_id: String,
+        interval_secs: u32,
+    ) -> wasmtime::Result<Result<(), String>> {
+        Ok(self.schedule_job(&job_id, JobSchedule::Interval { secs: interval_secs }))
+    }
+
+    fn schedule_cron(
+        &mut self,
+        job_id: String,
+        expression: String,
+    ) -> wasmtime::Result<Result<(), String>> {
+        Ok(self.schedule_job(&job_id, JobSchedule::Cron { expression }))
+    }
+
+    fn cancel(&mut self, job_id: String) -> wasmtime::Result<bool> {
+        Ok(self.jobs().cancel(&job_id).unwrap_or_else(|e| {
+            warn!(plugin_id = %self.plugin_id, job_id = %job_id, "Job cancel failed: {}", e);
+            false
+        }))
+    }
+
+    fn list_jobs(&mut self) -> wasmtime::Result<Vec<String>> {
+        let jobs = self.jobs().list().unwrap_or_else(|e| {
+            warn!(plugin_id = %self.plugin_id, "Job list failed: {}", e);
+            Vec::new()
+        });
+        Ok(jobs.into_iter().map(|job| job.job_id).collect())
+    }
+}
+
+// ============================================================
+// events::Host — Inter-plugin event bus (publish Tier 1, subscribe Tier 2)
+// ============================================================
+
+impl events::Host for PluginState {
+    fn publish(
+        &mut self,
+        topic: String,
+        payload_json: String,
+    ) -> wasmtime::Result<Result<(), String>> {
+        Ok(self.publish_event(&topic, &payload_json).map_err(|e| {
+            warn!(plugin_id = %self.plugin_id, topic = %topic, "Event not published: {}", e);
+            e.to_string()
+        }))
+    }
+
+    fn subscribe(
+        &mut self,
+        publisher_id: String,
+        topic: String,
+    ) -> wasmtime::Result<Result<(), String>> {
+        Ok(self.subscribe_event(&publisher_id, &topic).map_err(|e| e.to_string()))
+    }
+
+    fn unsubscribe(&mut self, publisher_id: String, topic: String) -> wasmtime::Result<bool> {
+        Ok(self.unsubscribe_event(&publisher_id, &topic))
+    }
+}
+
 // ============================================================
 // logger::Host — Structured logging with plugin context (Tier 1)
 // ============================================================
@@ -951,62 +1103,219 @@ impl network::Host for PluginState {
             "Network fetch"
         );
 
-        let client = match reqwest::blocking::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .user_agent("PrivStack/1.0")
-            .build()
-        {
-            Ok(c) => c,
-            Err(e) => return Ok(Err(format!("failed to create HTTP client: {}", e))),
+        let headers: Vec<(String, String)> =
+            headers.into_iter().map(|h| (h.name, h.value)).collect();
+        let request = FetchRequest {
+            url: &url,
+            method: &method,
+            headers: &headers,
+            body,
+            deadline: self.call_deadline,
         };
+        match self.fetch(request) {
+            Ok(response) => Ok(Ok(network::HttpResponse {
+                status: response.status,
+                headers: response
+                    .headers
+                    .into_iter()
+                    .map(|(name, value)| network::HttpHeader { name, value })
+                    .collect(),
+                body: response.body,
+            })),
+            Err(e) => Ok(Err(e.to_string())),
+        }
+    }
+}
 
-        let method = match method.parse::<reqwest::Method>() {
-            Ok(m) => m,
-            Err(e) => return Ok(Err(format!("invalid HTTP method: {}", e))),
-        };
+// ============================================================
+// agent::Host — Cross-plugin reads and commands (agent-plugin-world)
+// ============================================================
+
+/// Commands an agent may queue during a single call.
+const MAX_PENDING_COMMANDS: usize = 16;
 
-        let mut request = client.request(method, &url);
+fn error_response(code: u32, message: String) -> types::SdkResponse {
+    types::SdkResponse {
+        success: false,
+        error_code: Some(code),
+        error_message: Some(message),
+        data: None,
+    }
+}
 
-        for header in &headers {
-            request = request.header(&header.name, &header.value);
+impl PluginState {
+    /// Checks every permission in `required`, as a 403 response on failure.
+    fn agent_check(&self, required: &[Permission]) -> Result<(), types::SdkResponse> {
+        for &permission in required {
+            if let Err(e) = self.check_permission(permission) {
+                warn!(plugin_id = %self.plugin_id, "Agent call denied: {}", e);
+                return Err(error_response(403, e.to_string()));
+            }
         }
+        Ok(())
+    }
 
-        if let Some(body_bytes) = body {
-            request = request.body(body_bytes);
+    /// Entity types the agent may read: its own, plus every stored type
+    /// when `cross-entity-read` is granted.
+    fn agent_readable_types(&self) -> Result<Vec<String>, types::SdkResponse> {
+        let mut readable: Vec<String> = self.declared_entity_types.iter().cloned().collect();
+        if self.permissions.is_granted(Permission::CrossEntityRead) {
+            let stored = self
+                .entity_store
+                .list_entity_types()
+                .map_err(|e| error_response(500, e.to_string()))?;
+            readable.extend(stored.into_iter().filter(|t| !is_reserved_entity_type(t)));
         }
+        readable.sort();
+        readable.dedup();
+        Ok(readable)
+    }
+}
 
-        let response = match request.send() {
-            Ok(r) => r,
-            Err(e) => return Ok(Err(format!("HTTP request failed: {}", e))),
+impl agent::Host for PluginState {
+    fn query_entities(
+        &mut self,
+        entity_type: String,
+        query: String,
+        limit: u32,
+    ) -> wasmtime::Result<types::SdkResponse> {
+        if is_reserved_entity_type(&entity_type) {
+            return Ok(error_response(
+                403,
+                format!("entity type '{}' is reserved", entity_type),
+            ));
+        }
+        let mut required = vec![Permission::Agent];
+        if !self.declared_entity_types.contains(&entity_type) {
+            required.push(Permission::CrossEntityRead);
+        }
+        if let Err(resp) = self.agent_check(&required) {
+            return Ok(resp);
+        }
+
+        // `query` is a JSON object of field filters; empty means no filter.
+        let filters: Vec<(String, serde_json::Value)> = if query.trim().is_empty() {
+            Vec::new()
+        } else {
+            match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&query) {
+                Ok(map) => map.into_iter().collect(),
+                Err(e) => {
+                    return Ok(error_response(400, format!("invalid query JSON: {}", e)));
+                }
+            }
         };
+        let limit = if limit == 0 { 100 } else { limit as usize };
 
-        let status = response.status().as_u16();
-        let resp_headers: Vec<network::HttpHeader> = response
-            .headers()
+        debug!(
+            plugin_id = %self.plugin_id,
+            entity_type = %entity_type,
+            "Agent query-entities"
+        );
+
+        Ok(match self
+            .entity_store
+            .query_entities(&entity_type, &filters, false, Some(limit))
+        {
+            Ok(entities) => types::SdkResponse {
+                success: true,
+                error_code: None,
+                error_message: None,
+                data: Some(serde_json::to_string(&entities).unwrap_or_else(|_| "[]".into())),
+            },
+            Err(e) => error_response(500, e.to_string()),
+        })
+    }
+
+    fn run_analytics(
+        &mut self,
+        sql: String,
+        params: Vec<String>,
+    ) -> wasmtime::Result<types::SdkResponse> {
+        if let Err(resp) = self.agent_check(&[Permission::Agent]) {
+            return Ok(resp);
+        }
+        if let Err(e) = privstack_storage::validate_select(&sql) {
+            return Ok(error_response(400, e.to_string()));
+        }
+        let readable = match self.agent_readable_types() {
+            Ok(readable) => readable,
+            Err(resp) => return Ok(resp),
+        };
+
+        // Only decrypt the types the query can reference; anything else
+        // stays out of the scratch database.
+        let lowered = sql.to_lowercase();
+        let tables: Vec<&str> = readable
             .iter()
-            .map(|(name, value)| network::HttpHeader {
-                name: name.to_string(),
-                value: value.to_str().unwrap_or("").to_string(),
-            })
+            .map(String::as_str)
+            .filter(|t| lowered.contains(&t.to_lowercase()))
             .collect();
 
-        let body_bytes = match response.bytes() {
-            Ok(b) => b.to_vec(),
-            Err(e) => return Ok(Err(format!("failed to read response body: {}", e))),
-        };
+        debug!(
+            plugin_id = %self.plugin_id,
+            tables = ?tables,
+            "Agent run-analytics"
+        );
 
-        Ok(Ok(network::HttpResponse {
-            status,
-            headers: resp_headers,
-            body: body_bytes,
-        }))
+        Ok(match self.entity_store.run_analytics(&tables, &sql, &params) {
+            Ok(rows) => types::SdkResponse {
+                success: true,
+                error_code: None,
+                error_message: None,
+                data: Some(serde_json::to_string(&rows).unwrap_or_else(|_| "[]".into())),
+            },
+            Err(e) => error_response(400, e.to_string()),
+        })
+    }
+
+    fn send_command(
+        &mut self,
+        target_plugin_id: String,
+        command: String,
+        args: String,
+    ) -> wasmtime::Result<types::SdkResponse> {
+        if let Err(resp) = self.agent_check(&[Permission::Agent, Permission::CrossPluginCommand]) {
+            return Ok(resp);
+        }
+        if target_plugin_id == self.plugin_id {
+            return Ok(error_response(400, "agent cannot send a command to itself".into()));
+        }
+        if self.pending_commands.len() >= MAX_PENDING_COMMANDS {
+            return Ok(error_response(
+                429,
+                format!("at most {} commands per call", MAX_PENDING_COMMANDS),
+            ));
+        }
+
+        info!(
+            plugin_id = %self.plugin_id,
+            target = %target_plugin_id,
+            command = %command,
+            "Agent command queued"
+        );
+
+        // Delivered by the manager once this call returns, so the target never
+        // runs re-entrantly inside the agent's store.
+        self.pending_commands.push(PendingCommand {
+            target_plugin_id,
+            command,
+            args,
+        });
+        Ok(types::SdkResponse {
+            success: true,
+            error_code: None,
+            error_message: None,
+            data: Some(r#"{"queued":true}"#.into()),
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::net_policy::NetworkPolicy;
     use crate::permissions::PermissionSet;
+    use crate::plugin_settings::SETTINGS_ENTITY_TYPE;
     use crate::sandbox::{PluginSandbox, ResourceLimits};
     use crate::wit_types::*;
     use std::sync::Arc;
@@ -1044,6 +1353,7 @@ mod tests {
                 searchable: true,
                 vector_dim: None,
                 enum_options: None,
+                sensitivity: None,
             }],
             merge_strategy: WitMergeStrategy::LwwPerField,
         }]
@@ -1208,6 +1518,50 @@ mod tests {
         assert!(read_resp.data.is_some());
     }
 
+    #[test]
+    fn sdk_send_writes_are_recorded_as_entity_changes() {
+        use crate::entity_changes::EntityChangeQueue;
+        use std::time::Duration;
+
+        let mut sandbox = make_state(PermissionSet::default_first_party());
+        let queue = Arc::new(EntityChangeQueue::new(Duration::ZERO));
+        sandbox.set_entity_change_queue(Arc::clone(&queue));
+        let state = sandbox.state_mut();
+
+        let create_msg = types::SdkMessage {
+            action: types::SdkAction::Create,
+            entity_type: "test_note".into(),
+            entity_id: None,
+            payload: Some(r#"{"title":"my note"}"#.into()),
+            parameters: vec![],
+            source: None,
+        };
+        let create_resp = sdk::Host::send(state, create_msg).unwrap();
+        let data: serde_json::Value =
+            serde_json::from_str(create_resp.data.as_ref().unwrap()).unwrap();
+        let id = data["id"].as_str().unwrap().to_string();
+
+        let changes = queue.take_ready();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].entity_id, id);
+        assert_eq!(changes[0].kind, EntityChangeKind::Created);
+        assert_eq!(changes[0].source, state.plugin_id);
+
+        let delete_msg = types::SdkMessage {
+            action: types::SdkAction::Delete,
+            entity_type: "test_note".into(),
+            entity_id: Some(id),
+            payload: None,
+            parameters: vec![],
+            source: None,
+        };
+        assert!(sdk::Host::send(state, delete_msg).unwrap().success);
+        let changes = queue.take_ready();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].entity_type, "test_note");
+        assert_eq!(changes[0].kind, EntityChangeKind::Deleted);
+    }
+
     #[test]
     fn sdk_send_update_no_entity_id() {
         let mut sandbox = make_state(PermissionSet::default_first_party());
@@ -1613,6 +1967,74 @@ mod tests {
         assert_eq!(val, "default");
     }
 
+    #[test]
+    fn sdk_send_cannot_reach_another_plugins_settings() {
+        let (es, ev) = test_stores();
+        let mut victim_meta = test_metadata();
+        victim_meta.id = "victim".into();
+        let mut victim = PluginSandbox::new(
+            victim_meta,
+            vec![],
+            PermissionSet::default_first_party(),
+            ResourceLimits::first_party(),
+            es.clone(),
+            ev.clone(),
+        )
+        .unwrap();
+        settings::Host::set(victim.state_mut(), "token".into(), "secret".into()).unwrap();
+        let setting_id = crate::plugin_settings::setting_entity_id("victim", "token");
+
+        let mut attacker = PluginSandbox::new(
+            test_metadata(),
+            test_schemas(),
+            PermissionSet::default_first_party(),
+            ResourceLimits::first_party(),
+            es,
+            ev,
+        )
+        .unwrap();
+        let state = attacker.state_mut();
+        for action in [
+            types::SdkAction::Read,
+            types::SdkAction::Update,
+            types::SdkAction::Delete,
+            types::SdkAction::Trash,
+            types::SdkAction::Restore,
+        ] {
+            let label = format!("{action:?}");
+            let msg = types::SdkMessage {
+                action,
+                entity_type: "test_note".into(),
+                entity_id: Some(setting_id.clone()),
+                payload: Some(r#"{"value":"stolen"}"#.into()),
+                parameters: vec![],
+                source: None,
+            };
+            let resp = sdk::Host::send(state, msg).unwrap();
+            assert!(!resp.success, "{label} reached a reserved entity");
+            assert_eq!(resp.error_code, Some(404));
+        }
+
+        let query = types::SdkMessage {
+            action: types::SdkAction::Query,
+            entity_type: SETTINGS_ENTITY_TYPE.into(),
+            entity_id: None,
+            payload: None,
+            parameters: vec![("plugin_id".into(), "victim".into())],
+            source: None,
+        };
+        let resp = sdk::Host::send(state, query).unwrap();
+        assert_eq!(resp.error_code, Some(403));
+
+        let resp = sdk::Host::search(state, "secret".into(), None, 10).unwrap();
+        assert_eq!(resp.data.as_deref(), Some("[]"));
+
+        assert_eq!(
+            settings::Host::get(victim.state_mut(), "token".into(), "".into()).unwrap(),
+            "secret"
+        );
+    }
+
     // ================================================================
     // logger::Host
     // ================================================================
@@ -1922,10 +2344,70 @@ mod tests {
         assert!(result.unwrap_err().contains("permission denied"));
     }
 
+    #[test]
+    fn network_fetch_url_denied_without_declared_hosts() {
+        let mut sandbox = make_state(PermissionSet::all_granted());
+        let state = sandbox.state_mut();
+        let result = network::Host::fetch_url(
+            state,
+            "https://example.com/".into(),
+            "GET".into(),
+            vec![],
+            None,
+        )
+        .unwrap();
+        assert!(result.unwrap_err().contains("allowlist"));
+    }
+
+    #[test]
+    fn network_fetch_url_blocks_private_targets() {
+        let mut sandbox = make_state(PermissionSet::all_granted());
+        let state = sandbox.state_mut();
+        state.network_policy = NetworkPolicy::for_hosts(vec!["*".into()]);
+        for url in ["http://127.0.0.1:1", "http://169.254.169.254/latest", "http://[::1]/"] {
+            let result =
+                network::Host::fetch_url(state, url.into(), "GET".into(), vec![], None).unwrap();
+            assert!(result.unwrap_err().contains("non-public address"), "{url}");
+        }
+        assert_eq!(state.network_activity.lock().unwrap().total(), 3);
+    }
+
+    #[test]
+    fn network_fetch_url_enforces_allowlist_and_rate_limit() {
+        let mut sandbox = make_state(PermissionSet::all_granted());
+        let state = sandbox.state_mut();
+        state.network_policy = NetworkPolicy {
+            allowed_hosts: vec!["api.example.com".into()],
+            max_requests_per_minute: 1,
+            ..NetworkPolicy::default()
+        };
+        let result = network::Host::fetch_url(
+            state,
+            "https://other.example.com/".into(),
+            "GET".into(),
+            vec![],
+            None,
+        )
+        .unwrap();
+        assert!(result.unwrap_err().contains("allowlist"));
+
+        let result = network::Host::fetch_url(
+            state,
+            "https://api.example.com/".into(),
+            "GET".into(),
+            vec![],
+            None,
+        )
+        .unwrap();
+        assert!(result.unwrap_err().contains("requests per minute"));
+    }
+
     #[test]
     fn network_fetch_url_bad_url_returns_error() {
         let mut sandbox = make_state(PermissionSet::all_granted());
         let state = sandbox.state_mut();
+        state.network_policy = NetworkPolicy::for_hosts(vec!["*".into()]);
+        state.network_policy.allow_private = true;
         // Use an unreachable URL to exercise the request-send error path
         let result = network::Host::fetch_url(
             state,
@@ -1943,6 +2425,8 @@ mod tests {
     fn network_fetch_url_with_headers_and_body() {
         let mut sandbox = make_state(PermissionSet::all_granted());
         let state = sandbox.state_mut();
+        state.network_policy = NetworkPolicy::for_hosts(vec!["*".into()]);
+        state.network_policy.allow_private = true;
         let result = network::Host::fetch_url(
             state,
             "http://127.0.0.1:1".into(),
@@ -2106,4 +2590,149 @@ mod tests {
         assert!(!resp.success);
         assert_eq!(resp.error_code, Some(501));
     }
+
+    // ================================================================
+    // agent::Host
+    // ================================================================
+
+    fn agent_perms(extra: &[Permission]) -> PermissionSet {
+        let mut perms = PermissionSet::default_first_party();
+        perms.grant(Permission::Agent);
+        for &p in extra {
+            perms.grant(p);
+        }
+        perms
+    }
+
+    fn seed(state: &PluginState, id: &str, entity_type: &str, data: serde_json::Value) {
+        state
+            .entity_store
+            .save_entity_raw(&Entity {
+                id: id.into(),
+                entity_type: entity_type.into(),
+                data,
+                created_at: 1,
+                modified_at: 1,
+                created_by: "other".into(),
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn agent_calls_require_agent_permission() {
+        let mut sandbox = make_state(PermissionSet::default_first_party());
+        let state = sandbox.state_mut();
+        let resp = agent::Host::query_entities(state, "test_note".into(), String::new(), 10).unwrap();
+        assert_eq!(resp.error_code, Some(403));
+        let resp = agent::Host::run_analytics(state, "SELECT 1".into(), vec![]).unwrap();
+        assert_eq!(resp.error_code, Some(403));
+    }
+
+    #[test]
+    fn agent_query_entities_filters_own_type() {
+        let mut sandbox = make_state(agent_perms(&[]));
+        let state = sandbox.state_mut();
+        seed(state, "n-1", "test_note", serde_json::json!({"title": "a"}));
+        seed(state, "n-2", "test_note", serde_json::json!({"title": "b"}));
+
+        let resp = agent::Host::query_entities(
+            state,
+            "test_note".into(),
+            r#"{"title":"b"}"#.into(),
+            10,
+        )
+        .unwrap();
+        assert!(resp.success);
+        let found: Vec<serde_json::Value> = serde_json::from_str(resp.data.as_deref().unwrap()).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0]["id"], "n-2");
+
+        let resp = agent::Host::query_entities(state, "test_note".into(), "[1]".into(), 10).unwrap();
+        assert_eq!(resp.error_code, Some(400));
+    }
+
+    #[test]
+    fn agent_query_entities_other_type_needs_cross_entity_read() {
+        let mut sandbox = make_state(agent_perms(&[]));
+        let resp = agent::Host::query_entities(sandbox.state_mut(), "task".into(), String::new(), 10)
+            .unwrap();
+        assert_eq!(resp.error_code, Some(403));
+
+        let mut sandbox = make_state(agent_perms(&[Permission::CrossEntityRead]));
+        let state = sandbox.state_mut();
+        seed(state, "t-1", "task", serde_json::json!({"title": "x"}));
+        let resp = agent::Host::query_entities(state, "task".into(), String::new(), 10).unwrap();
+        assert!(resp.success);
+    }
+
+    #[test]
+    fn agent_run_analytics_scopes_to_readable_types() {
+        let mut sandbox = make_state(agent_perms(&[]));
+        let state = sandbox.state_mut();
+        seed(state, "n-1", "test_note", serde_json::json!({"words": 3}));
+        seed(state, "t-1", "task", serde_json::json!({"hours": 2}));
+
+        let resp = agent::Host::run_analytics(
+            state,
+            "SELECT SUM(words) AS total FROM test_note WHERE id <> ?".into(),
+            vec!["none".into()],
+        )
+        .unwrap();
+        assert!(resp.success);
+        let rows: Vec<serde_json::Value> = serde_json::from_str(resp.data.as_deref().unwrap()).unwrap();
+        assert_eq!(rows[0]["total"], 3.0);
+
+        // Undeclared type without cross-entity-read is not loaded.
+        let resp = agent::Host::run_analytics(state, "SELECT * FROM task".into(), vec![]).unwrap();
+        assert!(!resp.success);
+
+        state.permissions.grant(Permission::CrossEntityRead);
+        let resp = agent::Host::run_analytics(state, "SELECT * FROM task".into(), vec![]).unwrap();
+        assert!(resp.success);
+    }
+
+    #[test]
+    fn agent_run_analytics_rejects_non_select() {
+        let mut sandbox = make_state(agent_perms(&[]));
+        let resp = agent::Host::run_analytics(
+            sandbox.state_mut(),
+            "DELETE FROM test_note".into(),
+            vec![],
+        )
+        .unwrap();
+        assert_eq!(resp.error_code, Some(400));
+    }
+
+    #[test]
+    fn agent_send_command_queues_for_manager() {
+        let mut sandbox = make_state(agent_perms(&[]));
+        let resp = agent::Host::send_command(
+            sandbox.state_mut(),
+            "other".into(),
+            "refresh".into(),
+            "{}".into(),
+        )
+        .unwrap();
+        assert_eq!(resp.error_code, Some(403));
+
+        let mut sandbox = make_state(agent_perms(&[Permission::CrossPluginCommand]));
+        let state = sandbox.state_mut();
+        let resp = agent::Host::send_command(state, "host-impl-test".into(), "x".into(), "{}".into())
+            .unwrap();
+        assert_eq!(resp.error_code, Some(400));
+        let resp = agent::Host::send_command(state, "other".into(), "refresh".into(), "{}".into())
+            .unwrap();
+        assert!(resp.success);
+
+        let queued = sandbox.take_pending_commands();
+        assert_eq!(
+            queued,
+            vec![PendingCommand {
+                target_plugin_id: "other".into(),
+                command: "refresh".into(),
+                args: "{}".into(),
+            }]
+        );
+        assert!(sandbox.take_pending_commands().is_empty());
+    }
 }
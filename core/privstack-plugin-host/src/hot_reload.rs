@@ -0,0 +1,139 @@
+//! Hot reload of plugins under development.
+//!
+//! In dev mode the manager watches the `.wasm` files of chosen plugins.
+//! Watching is polling-based: each poll compares the file's size and
+//! modification time with the last ones seen, and a change is acted on once
+//! the file has been stable for [`RELOAD_SETTLE_MS`], so a build that is
+//! still being written is not picked up.
+
+use crate::permissions::PermissionSet;
+use crate::sandbox::ResourceLimits;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long a changed `.wasm` file must stay unchanged before it is reloaded.
+pub const RELOAD_SETTLE_MS: u64 = 500;
+
+/// Outcome of a hot reload, queued for the shell.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginReloadEvent {
+    pub plugin_id: String,
+    pub wasm_path: String,
+    /// `None` if the new build is running, otherwise why it is not.
+    pub error: Option<String>,
+    /// Unix timestamp (seconds).
+    pub timestamp: i64,
+}
+
+/// Size and modification time of a watched file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileStamp {
+    fn read(path: &Path) -> Option<Self> {
+        let meta = std::fs::metadata(path).ok()?;
+        Some(Self {
+            len: meta.len(),
+            modified: meta.modified().ok(),
+        })
+    }
+}
+
+/// A plugin whose `.wasm` file is watched.
+pub(crate) struct WatchedPlugin {
+    pub wasm_path: PathBuf,
+    /// Used when the plugin is not loaded at reload time, e.g. after its
+    /// circuit breaker tripped.
+    pub permissions: PermissionSet,
+    pub resource_limits: ResourceLimits,
+    stamp: Option<FileStamp>,
+    changed_at: Option<Instant>,
+}
+
+impl WatchedPlugin {
+    pub fn new(
+        wasm_path: PathBuf,
+        permissions: PermissionSet,
+        resource_limits: ResourceLimits,
+    ) -> Self {
+        let stamp = FileStamp::read(&wasm_path);
+        Self {
+            wasm_path,
+            permissions,
+            resource_limits,
+            stamp,
+            changed_at: None,
+        }
+    }
+
+    /// Returns `true` once the file has changed and then stayed unchanged
+    /// for [`RELOAD_SETTLE_MS`]. A missing file is waited for.
+    pub fn poll(&mut self, now: Instant) -> bool {
+        let stamp = FileStamp::read(&self.wasm_path);
+        if stamp != self.stamp {
+            self.stamp = stamp;
+            self.changed_at = Some(now);
+            return false;
+        }
+        match self.changed_at {
+            Some(at)
+                if stamp.is_some()
+                    && now.duration_since(at) >= Duration::from_millis(RELOAD_SETTLE_MS) =>
+            {
+                self.changed_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watch(path: PathBuf) -> WatchedPlugin {
+        WatchedPlugin::new(path, PermissionSet::default_first_party(), ResourceLimits::first_party())
+    }
+
+    #[test]
+    fn change_is_reported_once_settled() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plugin.wasm");
+        std::fs::write(&path, b"v1").unwrap();
+        let mut watched = watch(path.clone());
+        let start = Instant::now();
+        let settle = Duration::from_millis(RELOAD_SETTLE_MS);
+
+        assert!(!watched.poll(start));
+        assert!(!watched.poll(start + settle));
+
+        std::fs::write(&path, b"version 2").unwrap();
+        assert!(!watched.poll(start));
+        assert!(!watched.poll(start + settle / 2));
+        assert!(watched.poll(start + settle));
+        assert!(!watched.poll(start + settle * 2));
+    }
+
+    #[test]
+    fn missing_file_is_waited_for() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plugin.wasm");
+        std::fs::write(&path, b"v1").unwrap();
+        let mut watched = watch(path.clone());
+        let start = Instant::now();
+        let settle = Duration::from_millis(RELOAD_SETTLE_MS);
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(!watched.poll(start));
+        assert!(!watched.poll(start + settle));
+
+        std::fs::write(&path, b"version 2").unwrap();
+        assert!(!watched.poll(start + settle));
+        assert!(watched.poll(start + settle * 2));
+    }
+}
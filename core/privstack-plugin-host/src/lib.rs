@@ -7,17 +7,43 @@
 //! CPU fuel budgets, and scoped entity-type access.
 
 pub mod bindings;
+mod crash;
+mod entity_changes;
+mod entity_handler;
 mod error;
+mod event_bus;
+mod filesystem;
 mod host_impl;
+mod hot_reload;
 mod manager;
+mod net_policy;
 mod permissions;
+mod plugin_settings;
 mod policy;
 mod sandbox;
+mod scheduler;
 mod wit_types;
 
+pub use crash::{
+    CrashOutcome, CrashReport, CrashTracker, CRASH_WINDOW_SECS, MAX_CRASHES_PER_WINDOW,
+};
+pub use entity_changes::{
+    EntityChange, EntityChangeKind, EntityChangeQueue, CHANGE_SOURCE_HOST, CHANGE_SOURCE_SYNC,
+};
+pub use entity_handler::WasmEntityHandler;
 pub use error::PluginHostError;
+pub use event_bus::{validate_topic, BusEvent, EventBus};
+pub use filesystem::{DirectoryGrant, SELECTION_ROOT};
+pub use hot_reload::{PluginReloadEvent, RELOAD_SETTLE_MS};
 pub use manager::PluginHostManager;
-pub use permissions::{Permission, PermissionSet, PermissionTier};
-pub use policy::{PolicyConfig, PolicyEngine, PolicyMode};
-pub use sandbox::{PluginResourceMetrics, PluginSandbox, ResourceLimits};
+pub use net_policy::{is_public_ip, NetworkPolicy, NetworkRequestRecord};
+pub use permissions::{ConsentItem, ConsentSummary, Permission, PermissionSet, PermissionTier};
+pub use plugin_settings::{
+    is_local_only_setting, setting_entity_id, PluginSetting, PluginSettings, SETTINGS_ENTITY_TYPE,
+};
+pub use policy::{PluginAuditEntry, PluginAuditLog, PolicyConfig, PolicyEngine, PolicyMode};
+pub use sandbox::{PendingCommand, PluginResourceMetrics, PluginSandbox, ResourceLimits};
+pub use scheduler::{
+    CronSchedule, JobSchedule, ScheduledJob, MIN_JOB_INTERVAL_SECS, SCHEDULED_JOB_ENTITY_TYPE,
+};
 pub use wit_types::*;
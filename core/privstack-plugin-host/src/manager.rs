@@ -4,24 +4,59 @@
 //! provides query/routing across plugins (e.g. linkable-item search,
 //! command palette aggregation).
 
+use crate::crash::{
+    CrashOutcome, CrashReport, CrashTracker, CRASH_WINDOW_SECS, MAX_CRASHES_PER_WINDOW,
+};
+use crate::entity_changes::{
+    EntityChange, EntityChangeKind, EntityChangeQueue, CHANGE_SOURCE_SYNC, MAX_CHANGES_PER_CALL,
+};
 use crate::error::PluginHostError;
-use crate::permissions::PermissionSet;
-use crate::policy::PolicyEngine;
-use crate::sandbox::{PluginSandbox, ResourceLimits};
+use crate::event_bus::{BusEvent, EventBus, MAX_EVENTS_PER_CALL};
+use crate::filesystem::{remove_staged_selections, DirectoryGrant, SELECTION_ROOT};
+use crate::hot_reload::{PluginReloadEvent, WatchedPlugin};
+use crate::net_policy::{FetchRequest, NetworkPolicy};
+use crate::permissions::{ConsentSummary, Permission, PermissionSet, PermissionTier};
+use crate::plugin_settings::{PluginSettings, SETTINGS_ENTITY_TYPE};
+use crate::policy::{PluginAuditEntry, PolicyEngine};
+use crate::sandbox::{read_wasm, wasm_sha256, PendingCommand, PluginSandbox, ResourceLimits};
+use crate::scheduler::{due_jobs, PluginJobs, ScheduledJob};
 use crate::wit_types::*;
+use privstack_ppk::PpkFilesystemScope;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, OnceLock};
-use tracing::{info, warn};
+use std::time::Instant;
+use tracing::{debug, info, warn};
 use wasmtime::Engine;
 
 /// Manages the lifecycle of all loaded plugins.
 /// Creates a shared Wasmtime engine configured for plugin sandboxing.
 fn create_shared_engine() -> Result<Engine, PluginHostError> {
-    let mut config = wasmtime::Config::new();
-    config.wasm_component_model(true);
-    config.consume_fuel(true);
-    Engine::new(&config).map_err(PluginHostError::Compilation)
+    crate::sandbox::new_engine()
+}
+
+/// How many agent-to-plugin hops a command chain may take. Stops agents
+/// from bouncing commands between each other forever.
+const MAX_AGENT_COMMAND_HOPS: usize = 4;
+
+/// Audit action of a command an agent plugin sent to another plugin; the
+/// audit target is `<target plugin>/<command>`.
+const AUDIT_AGENT_COMMAND: &str = "agent-command";
+
+/// A Wasm build whose package signature was verified at install time.
+#[derive(Debug, Clone)]
+struct TrustedBuild {
+    plugin_id: String,
+    /// Hex key that signed the package, for policy checks.
+    signing_key: String,
+    first_party: bool,
+}
+
+/// How an admitted build is sandboxed, and the trusted build it matched.
+struct BuildAdmission {
+    permissions: PermissionSet,
+    resource_limits: ResourceLimits,
+    trusted: Option<TrustedBuild>,
 }
 
 pub struct PluginHostManager {
@@ -31,6 +66,36 @@ pub struct PluginHostManager {
     event_store: Arc<privstack_storage::EventStore>,
     /// Shared Wasmtime engine for all plugins — lazily created on first WASM load.
     engine: OnceLock<Engine>,
+    /// Directory scopes the user approved, applied whenever the plugin loads.
+    directory_grants: HashMap<String, Vec<DirectoryGrant>>,
+    /// Directory scopes each installed plugin's manifest declares. Approved
+    /// grants must match one of them.
+    filesystem_scopes: HashMap<String, Vec<PpkFilesystemScope>>,
+    /// Network policies set by the shell, applied whenever the plugin loads.
+    network_policies: HashMap<String, NetworkPolicy>,
+    /// Wasm builds from verified packages, by SHA-256 of the bytes.
+    trusted_builds: HashMap<String, TrustedBuild>,
+    /// Permissions each installed plugin's manifest declares. Beyond Tier 1,
+    /// a plugin is never granted anything else.
+    declared_permissions: HashMap<String, Vec<Permission>>,
+    /// Entity writes waiting to be delivered to `entity-observer` plugins.
+    entity_changes: Arc<EntityChangeQueue>,
+    /// Writes to synced plugin settings, waiting for the shell to replicate them.
+    synced_settings: Vec<EntityChange>,
+    /// Inter-plugin topics, subscriptions and undelivered events.
+    event_bus: Arc<EventBus>,
+    /// Crash counts, tripped circuit breakers and crash reports.
+    crashes: CrashTracker,
+    /// Plugins disabled after a crash, with their declared entity types,
+    /// waiting for the shell to drop their `entity-handler` hooks.
+    disabled_plugins: Vec<(String, Vec<String>)>,
+    /// Whether `.wasm` files may be watched for hot reload.
+    dev_mode: bool,
+    /// Plugins whose `.wasm` files are watched, by plugin id.
+    dev_watches: HashMap<String, WatchedPlugin>,
+    /// App data directory under which selected files are staged. Without
+    /// one, only directory selections can be granted.
+    selection_staging_dir: Option<PathBuf>,
 }
 
 impl PluginHostManager {
@@ -45,13 +110,7 @@ impl PluginHostManager {
         entity_store: Arc<privstack_storage::EntityStore>,
         event_store: Arc<privstack_storage::EventStore>,
     ) -> Self {
-        Self {
-            plugins: HashMap::new(),
-            policy_engine: PolicyEngine::load(),
-            entity_store,
-            event_store,
-            engine: OnceLock::new(),
-        }
+        Self::with_policy(entity_store, event_store, PolicyEngine::load())
     }
 
     /// Creates a manager with a default unrestricted policy (no filesystem access).
@@ -68,12 +127,26 @@ impl PluginHostManager {
         event_store: Arc<privstack_storage::EventStore>,
         policy_engine: PolicyEngine,
     ) -> Self {
+        let event_bus = Arc::new(EventBus::new(Arc::clone(policy_engine.audit_log())));
         Self {
             plugins: HashMap::new(),
             policy_engine,
             entity_store,
             event_store,
             engine: OnceLock::new(),
+            directory_grants: HashMap::new(),
+            filesystem_scopes: HashMap::new(),
+            network_policies: HashMap::new(),
+            trusted_builds: HashMap::new(),
+            declared_permissions: HashMap::new(),
+            entity_changes: Arc::default(),
+            synced_settings: Vec::new(),
+            event_bus,
+            crashes: CrashTracker::default(),
+            disabled_plugins: Vec::new(),
+            dev_mode: false,
+            dev_watches: HashMap::new(),
+            selection_staging_dir: None,
         }
     }
 
@@ -92,7 +165,8 @@ impl PluginHostManager {
     ) -> Result<(), PluginHostError> {
         let plugin_id = metadata.id.clone();
 
-        // Policy check
+        // Policy check. No code is loaded, so there is no signed build to
+        // vouch for the plugin.
         if !self.policy_engine.is_plugin_allowed(&plugin_id, None) {
             return Err(PluginHostError::PolicyDenied(format!(
                 "plugin '{}' blocked by policy",
@@ -103,6 +177,7 @@ impl PluginHostManager {
         if self.plugins.contains_key(&plugin_id) {
             return Err(PluginHostError::PluginAlreadyLoaded(plugin_id));
         }
+        self.check_not_disabled(&plugin_id)?;
 
         let sandbox = PluginSandbox::new(
             metadata,
@@ -112,74 +187,96 @@ impl PluginHostManager {
             Arc::clone(&self.entity_store),
             Arc::clone(&self.event_store),
         )?;
+        let sandbox = self.prepare_sandbox(sandbox);
 
         info!(plugin_id = %plugin_id, "Plugin loaded (metadata-only)");
         self.plugins.insert(plugin_id, sandbox);
         Ok(())
     }
 
-    /// Loads a plugin from a compiled .wasm component file.
-    /// Instantiates the component, calls get_metadata/get_entity_schemas exports,
-    /// and detects capability interfaces.
-    pub fn load_plugin_from_wasm(
+    /// Loads a plugin from a compiled .wasm component file. The file must be
+    /// the build of a plugin installed from a signed package (see
+    /// [`Self::trust_build`]); it then runs under that package's signer,
+    /// declared permissions and first-party status. In dev mode an unknown
+    /// build loads as an unsigned third-party plugin instead.
+    pub fn load_plugin_from_wasm(&mut self, wasm_path: &Path) -> Result<String, PluginHostError> {
+        let wasm_bytes = read_wasm(wasm_path)?;
+        let admission = self.admit_build(&wasm_bytes, &wasm_path.display().to_string())?;
+        let sandbox = PluginSandbox::from_wasm_bytes(
+            &wasm_bytes,
+            Some(wasm_path),
+            self.engine(),
+            admission.permissions.clone(),
+            admission.resource_limits.clone(),
+            Arc::clone(&self.entity_store),
+            Arc::clone(&self.event_store),
+        )?;
+        self.register_build(sandbox, admission)
+    }
+
+    /// Loads a plugin from the bytes of a compiled component, as
+    /// [`Self::load_plugin_from_wasm`] does for a file.
+    pub fn load_plugin_from_wasm_bytes(
         &mut self,
-        wasm_path: &Path,
-        permissions: PermissionSet,
-        resource_limits: ResourceLimits,
+        wasm_bytes: &[u8],
     ) -> Result<String, PluginHostError> {
-        let sandbox = PluginSandbox::from_wasm_cached(
-            wasm_path,
+        let admission = self.admit_build(wasm_bytes, "component")?;
+        let sandbox = PluginSandbox::from_wasm_bytes(
+            wasm_bytes,
+            None,
             self.engine(),
-            permissions,
-            resource_limits,
+            admission.permissions.clone(),
+            admission.resource_limits.clone(),
             Arc::clone(&self.entity_store),
             Arc::clone(&self.event_store),
         )?;
-
-        let plugin_id = sandbox.metadata.id.clone();
-
-        // Policy check
-        if !self.policy_engine.is_plugin_allowed(&plugin_id, None) {
-            return Err(PluginHostError::PolicyDenied(format!(
-                "plugin '{}' blocked by policy",
-                plugin_id
-            )));
-        }
-
-        if self.plugins.contains_key(&plugin_id) {
-            return Err(PluginHostError::PluginAlreadyLoaded(plugin_id));
-        }
-
-        info!(plugin_id = %plugin_id, "Plugin loaded from Wasm component");
-        self.plugins.insert(plugin_id.clone(), sandbox);
-        Ok(plugin_id)
+        self.register_build(sandbox, admission)
     }
 
     /// Compiles multiple wasm plugins in parallel, then registers them sequentially.
+    /// Each file is admitted as by [`Self::load_plugin_from_wasm`].
     /// Returns a Vec with one Result per input entry — either the plugin ID or an error.
     pub fn load_plugins_from_wasm_parallel(
         &mut self,
-        entries: Vec<(PathBuf, PermissionSet, ResourceLimits)>,
+        wasm_paths: Vec<PathBuf>,
     ) -> Vec<Result<String, PluginHostError>> {
+        // Read and admit every file first, so the bytes that were checked
+        // are the bytes that get compiled.
+        type Admitted = (PathBuf, Vec<u8>, BuildAdmission);
+        let admitted: Vec<Result<Admitted, PluginHostError>> = wasm_paths
+            .into_iter()
+            .map(|path| {
+                let wasm_bytes = read_wasm(&path)?;
+                let admission = self.admit_build(&wasm_bytes, &path.display().to_string())?;
+                Ok((path, wasm_bytes, admission))
+            })
+            .collect();
+
         let engine = self.engine();
         let entity_store = &self.entity_store;
         let event_store = &self.event_store;
 
         // Parallel compilation via scoped threads (Engine is Clone+Send+Sync)
-        let sandboxes: Vec<Result<PluginSandbox, PluginHostError>> =
+        let sandboxes: Vec<Result<(PluginSandbox, BuildAdmission), PluginHostError>> =
             std::thread::scope(|s| {
-                let handles: Vec<_> = entries
-                    .iter()
-                    .map(|(path, perms, limits)| {
+                let handles: Vec<_> = admitted
+                    .into_iter()
+                    .map(|entry| {
                         let engine = engine.clone();
                         let es = Arc::clone(entity_store);
                         let ev = Arc::clone(event_store);
-                        let perms = perms.clone();
-                        let limits = limits.clone();
                         s.spawn(move || {
-                            PluginSandbox::from_wasm_cached(
-                                path, &engine, perms, limits, es, ev,
-                            )
+                            let (path, wasm_bytes, admission) = entry?;
+                            let sandbox = PluginSandbox::from_wasm_bytes(
+                                &wasm_bytes,
+                                Some(&path),
+                                &engine,
+                                admission.permissions.clone(),
+                                admission.resource_limits.clone(),
+                                es,
+                                ev,
+                            )?;
+                            Ok((sandbox, admission))
                         })
                     })
                     .collect();
@@ -198,41 +295,897 @@ impl PluginHostManager {
         sandboxes
             .into_iter()
             .map(|result| {
-                let sandbox = result?;
-                let plugin_id = sandbox.metadata.id.clone();
-
-                if !self.policy_engine.is_plugin_allowed(&plugin_id, None) {
-                    return Err(PluginHostError::PolicyDenied(format!(
-                        "plugin '{}' blocked by policy",
-                        plugin_id
-                    )));
+                let (sandbox, admission) = result?;
+                self.register_build(sandbox, admission)
+            })
+            .collect()
+    }
+
+    /// Decides how a component may run: as the verified build of an
+    /// installed plugin, or, in dev mode only, as an unsigned third-party
+    /// plugin. `source` names the component in errors.
+    fn admit_build(
+        &self,
+        wasm_bytes: &[u8],
+        source: &str,
+    ) -> Result<BuildAdmission, PluginHostError> {
+        match self.trusted_builds.get(&wasm_sha256(wasm_bytes)) {
+            Some(build) => {
+                let declared = self
+                    .declared_permissions
+                    .get(&build.plugin_id)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                let resource_limits = if build.first_party {
+                    ResourceLimits::first_party()
+                } else {
+                    ResourceLimits::third_party()
+                };
+                Ok(BuildAdmission {
+                    permissions: PermissionSet::from_declared(declared),
+                    resource_limits,
+                    trusted: Some(build.clone()),
+                })
+            }
+            None if self.dev_mode => Ok(BuildAdmission {
+                permissions: PermissionSet::default_third_party(),
+                resource_limits: ResourceLimits::third_party(),
+                trusted: None,
+            }),
+            None => Err(PluginHostError::PolicyDenied(format!(
+                "{} is not the build of a plugin installed from a signed package",
+                source
+            ))),
+        }
+    }
+
+    /// Registers a sandbox compiled from an admitted build: checks that it
+    /// is the plugin its package was signed for, then the policy, with the
+    /// package's signer if there is one.
+    fn register_build(
+        &mut self,
+        sandbox: PluginSandbox,
+        admission: BuildAdmission,
+    ) -> Result<String, PluginHostError> {
+        let plugin_id = sandbox.metadata.id.clone();
+        if let Some(build) = &admission.trusted
+            && build.plugin_id != plugin_id
+        {
+            return Err(PluginHostError::PolicyDenied(format!(
+                "build signed for plugin '{}' reports id '{}'",
+                build.plugin_id, plugin_id
+            )));
+        }
+        let signing_key = admission.trusted.as_ref().map(|b| b.signing_key.as_str());
+        if !self.policy_engine.is_plugin_allowed(&plugin_id, signing_key) {
+            return Err(PluginHostError::PolicyDenied(format!(
+                "plugin '{}' blocked by policy",
+                plugin_id
+            )));
+        }
+
+        if self.plugins.contains_key(&plugin_id) {
+            return Err(PluginHostError::PluginAlreadyLoaded(plugin_id));
+        }
+        self.check_not_disabled(&plugin_id)?;
+
+        let sandbox = self.prepare_sandbox(sandbox);
+        info!(
+            plugin_id = %plugin_id,
+            signed = admission.trusted.is_some(),
+            "Plugin loaded from Wasm component"
+        );
+        self.plugins.insert(plugin_id.clone(), sandbox);
+        Ok(plugin_id)
+    }
+
+    /// Unloads a plugin, calling dispose() if it's a Wasm component.
+    pub fn unload_plugin(&mut self, plugin_id: &str) -> Result<(), PluginHostError> {
+        match self.plugins.remove(plugin_id) {
+            Some(mut sandbox) => {
+                if sandbox.has_runtime() {
+                    if let Err(e) = sandbox.call_dispose() {
+                        warn!(plugin_id = %plugin_id, "dispose() failed during unload: {}", e);
+                    }
+                }
+                self.event_bus.remove_subscriber(plugin_id);
+                self.dev_watches.remove(plugin_id);
+                if let Some(dir) = &self.selection_staging_dir {
+                    remove_staged_selections(dir, plugin_id);
+                }
+                info!(plugin_id = %plugin_id, "Plugin unloaded");
+                Ok(())
+            }
+            None => Err(PluginHostError::PluginNotFound(plugin_id.to_string())),
+        }
+    }
+
+    /// Uninstalls a plugin: unloads it if loaded and wipes its persisted
+    /// settings, scheduled jobs, event topics, crash history, trusted builds,
+    /// declared permissions and directory scopes. Returns how many settings
+    /// were removed.
+    pub fn uninstall_plugin(&mut self, plugin_id: &str) -> Result<usize, PluginHostError> {
+        if self.plugins.contains_key(plugin_id) {
+            self.unload_plugin(plugin_id)?;
+        }
+        let removed = self.plugin_settings(plugin_id).clear()?;
+        let jobs = PluginJobs::new(&self.entity_store, plugin_id).clear()?;
+        self.event_bus.forget_plugin(plugin_id);
+        self.crashes.forget_plugin(plugin_id);
+        self.dev_watches.remove(plugin_id);
+        self.trusted_builds.retain(|_, build| build.plugin_id != plugin_id);
+        self.declared_permissions.remove(plugin_id);
+        self.filesystem_scopes.remove(plugin_id);
+        self.directory_grants.remove(plugin_id);
+        info!(plugin_id = %plugin_id, settings = removed, jobs, "Plugin uninstalled");
+        Ok(removed)
+    }
+
+    /// Records the Wasm of a package whose signature was verified at install
+    /// time: which plugin it is, the key that signed it (hex) and whether
+    /// that key is first-party. Only these exact bytes load under the
+    /// signer; earlier builds of the plugin stop being trusted. Returns the
+    /// build's hex SHA-256, for the shell to persist.
+    pub fn trust_build(
+        &mut self,
+        plugin_id: &str,
+        wasm_bytes: &[u8],
+        signing_key: String,
+        first_party: bool,
+    ) -> String {
+        let wasm_hash = wasm_sha256(wasm_bytes);
+        self.trust_build_hash(plugin_id, wasm_hash.clone(), signing_key, first_party);
+        wasm_hash
+    }
+
+    /// Restores a build recorded by [`Self::trust_build`] in an earlier
+    /// session, from its hex SHA-256.
+    pub fn trust_build_hash(
+        &mut self,
+        plugin_id: &str,
+        wasm_hash: String,
+        signing_key: String,
+        first_party: bool,
+    ) {
+        self.trusted_builds.retain(|_, build| build.plugin_id != plugin_id);
+        self.trusted_builds.insert(
+            wasm_hash,
+            TrustedBuild {
+                plugin_id: plugin_id.to_string(),
+                signing_key,
+                first_party,
+            },
+        );
+    }
+
+    /// Whether a build of the plugin was installed from a signed package.
+    fn is_signed(&self, plugin_id: &str) -> bool {
+        self.trusted_builds.values().any(|build| build.plugin_id == plugin_id)
+    }
+
+    // ================================================================
+    // Crash recovery
+    // ================================================================
+
+    /// Calls into a plugin, treating a trap (including fuel exhaustion) or a
+    /// call interrupted at its `call_timeout_ms` deadline as a crash. After a
+    /// crash the plugin is restarted, or disabled if its circuit breaker
+    /// trips.
+    fn call_plugin<T>(
+        &mut self,
+        plugin_id: &str,
+        operation: &str,
+        call: impl FnOnce(&mut PluginSandbox) -> Result<T, PluginHostError>,
+    ) -> Result<T, PluginHostError> {
+        self.check_not_disabled(plugin_id)?;
+        let sandbox = self.get_plugin_mut(plugin_id)?;
+        if !sandbox.has_runtime() {
+            return call(sandbox);
+        }
+        let result = call(sandbox);
+        if let Err(
+            e @ (PluginHostError::PluginCrashed { .. } | PluginHostError::Timeout { .. }),
+        ) = &result
+        {
+            self.handle_crash(plugin_id, operation, e.to_string());
+        }
+        // Anything else was refused by the host, not a failure of the guest.
+        result
+    }
+
+    /// Counts a crash, then restarts the plugin or, if its circuit breaker
+    /// trips or the restart fails, unloads and disables it.
+    fn handle_crash(&mut self, plugin_id: &str, operation: &str, message: String) {
+        let now = chrono::Utc::now().timestamp();
+        let (outcome, restart_error) = if self.crashes.record_crash(plugin_id, now) {
+            (CrashOutcome::Disabled, None)
+        } else {
+            match self.get_plugin_mut(plugin_id).and_then(PluginSandbox::restart) {
+                Ok(()) => (CrashOutcome::Restarted, None),
+                Err(e) => (CrashOutcome::RestartFailed, Some(e.to_string())),
+            }
+        };
+        warn!(
+            plugin_id = %plugin_id,
+            operation = %operation,
+            outcome = ?outcome,
+            "Plugin crashed: {}",
+            message
+        );
+        if outcome != CrashOutcome::Restarted {
+            // The store may be poisoned, so dispose() is not called.
+            if let Some(sandbox) = self.plugins.remove(plugin_id) {
+                let mut entity_types: Vec<String> =
+                    sandbox.declared_entity_types().iter().cloned().collect();
+                entity_types.sort();
+                self.disabled_plugins.push((plugin_id.to_string(), entity_types));
+            }
+            self.event_bus.remove_subscriber(plugin_id);
+            let reason = match &restart_error {
+                Some(e) => format!("restart after crash failed: {}", e),
+                None => format!(
+                    "crashed {} times within {} seconds",
+                    MAX_CRASHES_PER_WINDOW, CRASH_WINDOW_SECS
+                ),
+            };
+            self.crashes.disable(plugin_id, reason);
+        }
+        self.crashes.push_report(CrashReport {
+            plugin_id: plugin_id.to_string(),
+            operation: operation.to_string(),
+            message,
+            outcome,
+            restart_error,
+            timestamp: now,
+        });
+    }
+
+    /// Takes the plugins disabled after a crash since the last call, each
+    /// with the entity types it declared. The shell must unregister the
+    /// `entity-handler` hooks it holds for those types: the plugin is gone,
+    /// but a registered hook would keep running its code on every write and
+    /// merge.
+    pub fn take_disabled_plugins(&mut self) -> Vec<(String, Vec<String>)> {
+        std::mem::take(&mut self.disabled_plugins)
+    }
+
+    fn check_not_disabled(&self, plugin_id: &str) -> Result<(), PluginHostError> {
+        match self.crashes.disabled_reason(plugin_id) {
+            Some(reason) => Err(PluginHostError::PluginDisabled {
+                plugin_id: plugin_id.to_string(),
+                reason: reason.to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Crash reports of one plugin, or of all if `plugin_id` is `None`,
+    /// oldest first.
+    pub fn crash_reports(&self, plugin_id: Option<&str>) -> Vec<CrashReport> {
+        self.crashes.reports(plugin_id)
+    }
+
+    /// Why the plugin was disabled by its circuit breaker, if it was.
+    pub fn disabled_reason(&self, plugin_id: &str) -> Option<&str> {
+        self.crashes.disabled_reason(plugin_id)
+    }
+
+    /// Closes a plugin's circuit breaker so it can be loaded again. Returns
+    /// whether it was disabled.
+    pub fn reset_circuit_breaker(&mut self, plugin_id: &str) -> bool {
+        let was_disabled = self.crashes.reset(plugin_id);
+        if was_disabled {
+            info!(plugin_id = %plugin_id, "Circuit breaker reset");
+        }
+        was_disabled
+    }
+
+    // ================================================================
+    // Hot reload
+    // ================================================================
+
+    /// Turns dev mode on or off. Turning it off stops all watches.
+    pub fn set_dev_mode(&mut self, enabled: bool) {
+        self.dev_mode = enabled;
+        if !enabled {
+            self.dev_watches.clear();
+        }
+        info!(enabled, "Plugin dev mode changed");
+    }
+
+    pub fn is_dev_mode(&self) -> bool {
+        self.dev_mode
+    }
+
+    /// Watches a loaded plugin's `.wasm` file for hot reload. Requires dev
+    /// mode and a plugin that was not installed from a signed package. The
+    /// watch ends when the plugin is unloaded.
+    pub fn watch_plugin(
+        &mut self,
+        plugin_id: &str,
+        wasm_path: PathBuf,
+    ) -> Result<(), PluginHostError> {
+        if !self.dev_mode {
+            return Err(PluginHostError::PolicyDenied(
+                "hot reload requires dev mode".to_string(),
+            ));
+        }
+        self.check_reloadable(plugin_id)?;
+        let sandbox = self.get_plugin(plugin_id)?;
+        let watched = WatchedPlugin::new(
+            wasm_path,
+            sandbox.state().permissions.clone(),
+            sandbox.resource_limits.clone(),
+        );
+        info!(plugin_id = %plugin_id, path = %watched.wasm_path.display(), "Watching plugin");
+        self.dev_watches.insert(plugin_id.to_string(), watched);
+        Ok(())
+    }
+
+    /// Stops watching a plugin. Returns whether it was watched.
+    pub fn unwatch_plugin(&mut self, plugin_id: &str) -> bool {
+        self.dev_watches.remove(plugin_id).is_some()
+    }
+
+    /// Reloads every watched plugin whose `.wasm` file changed and has
+    /// settled. Returns one event per reload attempt.
+    pub fn poll_dev_reloads(&mut self) -> Vec<PluginReloadEvent> {
+        let now = Instant::now();
+        let changed: Vec<(String, PathBuf)> = self
+            .dev_watches
+            .iter_mut()
+            .filter_map(|(id, watched)| {
+                watched.poll(now).then(|| (id.clone(), watched.wasm_path.clone()))
+            })
+            .collect();
+        changed
+            .into_iter()
+            .map(|(plugin_id, wasm_path)| {
+                let error = self.reload_plugin(&plugin_id).err().map(|e| e.to_string());
+                match &error {
+                    Some(e) => warn!(plugin_id = %plugin_id, "Hot reload failed: {}", e),
+                    None => info!(plugin_id = %plugin_id, "Plugin hot reloaded"),
+                }
+                PluginReloadEvent {
+                    plugin_id,
+                    wasm_path: wasm_path.display().to_string(),
+                    error,
+                    timestamp: chrono::Utc::now().timestamp(),
+                }
+            })
+            .collect()
+    }
+
+    /// Swaps a watched plugin for a fresh sandbox of its current build.
+    /// `initialize()`/`activate()` are replayed on the new instance first;
+    /// only then does the old one get `on-shutdown()` and `dispose()`.
+    /// Permissions (still limited to the declared ones), directory grants
+    /// and event subscriptions carry over, and settings persist anyway. If
+    /// the build does not load or initialize, the old instance keeps
+    /// running. A plugin that is not loaded (e.g. restart after a crash
+    /// failed) is loaded and initialized, unless its circuit breaker tripped.
+    fn reload_plugin(&mut self, plugin_id: &str) -> Result<(), PluginHostError> {
+        self.check_reloadable(plugin_id)?;
+        let watched = self
+            .dev_watches
+            .get(plugin_id)
+            .ok_or_else(|| PluginHostError::PluginNotFound(plugin_id.to_string()))?;
+        let (permissions, resource_limits) = match self.plugins.get(plugin_id) {
+            Some(sandbox) => (
+                sandbox.state().permissions.clone(),
+                sandbox.resource_limits.clone(),
+            ),
+            None => (watched.permissions.clone(), watched.resource_limits.clone()),
+        };
+        let sandbox = PluginSandbox::from_wasm_cached(
+            &watched.wasm_path,
+            self.engine(),
+            permissions,
+            resource_limits,
+            Arc::clone(&self.entity_store),
+            Arc::clone(&self.event_store),
+        )?;
+        if sandbox.metadata.id != plugin_id {
+            return Err(PluginHostError::InitializationFailed(format!(
+                "{} now contains plugin '{}'",
+                watched.wasm_path.display(),
+                sandbox.metadata.id
+            )));
+        }
+
+        // The new build must come up before the old one is let go, so a
+        // broken build leaves the running plugin in place.
+        let (initialized, active, grants) = match self.plugins.get(plugin_id) {
+            Some(old) => (old.is_initialized(), old.is_active(), old.directory_grants().to_vec()),
+            None => (true, false, Vec::new()),
+        };
+        let mut sandbox = self.prepare_sandbox(sandbox);
+        if !grants.is_empty()
+            && let Err(e) = sandbox.set_directory_grants(grants)
+        {
+            warn!(plugin_id = %plugin_id, "Directory grants not carried over: {}", e);
+        }
+        if initialized && !sandbox.call_initialize()? {
+            return Err(PluginHostError::InitializationFailed(format!(
+                "plugin '{}' refused to initialize after reload",
+                plugin_id
+            )));
+        }
+        if active {
+            sandbox.call_activate()?;
+        }
+
+        if let Some(mut old) = self.plugins.insert(plugin_id.to_string(), sandbox) {
+            if old.has_shutdown_aware
+                && let Err(e) = old.call_on_shutdown()
+            {
+                warn!(plugin_id = %plugin_id, "on-shutdown() failed during reload: {}", e);
+            }
+            if let Err(e) = old.call_dispose() {
+                warn!(plugin_id = %plugin_id, "dispose() failed during reload: {}", e);
+            }
+        }
+        self.route_agent_commands(plugin_id, 0);
+        Ok(())
+    }
+
+    /// Whether a new build of the plugin may replace the running one. A
+    /// rebuilt file carries no verified signature, so it is checked as
+    /// unsigned, and a plugin installed from a signed package is never
+    /// replaced by one. A tripped circuit breaker stays tripped until it is
+    /// reset explicitly.
+    fn check_reloadable(&self, plugin_id: &str) -> Result<(), PluginHostError> {
+        if self.is_signed(plugin_id) {
+            return Err(PluginHostError::PolicyDenied(format!(
+                "plugin '{}' was installed from a signed package and cannot be hot reloaded",
+                plugin_id
+            )));
+        }
+        if !self.policy_engine.is_plugin_allowed(plugin_id, None) {
+            return Err(PluginHostError::PolicyDenied(format!(
+                "plugin '{}' blocked by policy",
+                plugin_id
+            )));
+        }
+        self.check_not_disabled(plugin_id)
+    }
+
+    // ================================================================
+    // Filesystem
+    // ================================================================
+
+    /// Records the directory scopes a plugin's manifest declares.
+    pub fn set_filesystem_scopes(&mut self, plugin_id: &str, scopes: Vec<PpkFilesystemScope>) {
+        self.filesystem_scopes.insert(plugin_id.to_string(), scopes);
+    }
+
+    /// Sets the directory scopes the user approved for a plugin. Each grant
+    /// must be mounted at a scope its manifest declares (`/<scope name>`),
+    /// and may only be writable if that scope is. They are remembered and
+    /// applied on every load; one-off selection grants of a loaded plugin
+    /// are kept.
+    pub fn set_directory_grants(
+        &mut self,
+        plugin_id: &str,
+        grants: Vec<DirectoryGrant>,
+    ) -> Result<(), PluginHostError> {
+        if let Some(grant) = grants.iter().find(|g| g.guest_path.starts_with(SELECTION_ROOT)) {
+            return Err(PluginHostError::InvalidGrant(format!(
+                "guest path '{}' is reserved for dialog selections",
+                grant.guest_path
+            )));
+        }
+        let scopes = self.filesystem_scopes.get(plugin_id).map_or(&[][..], Vec::as_slice);
+        for grant in &grants {
+            let scope = scopes.iter().find(|s| s.guest_path() == grant.guest_path);
+            if scope.is_none_or(|scope| grant.writable && !scope.writable) {
+                return Err(PluginHostError::InvalidGrant(format!(
+                    "guest path '{}' is not a {}filesystem scope declared by '{}'",
+                    grant.guest_path,
+                    if scope.is_some() { "writable " } else { "" },
+                    plugin_id
+                )));
+            }
+        }
+        if let Some(sandbox) = self.plugins.get_mut(plugin_id) {
+            let mut all = grants.clone();
+            all.extend(
+                sandbox
+                    .directory_grants()
+                    .iter()
+                    .filter(|g| g.guest_path.starts_with(SELECTION_ROOT))
+                    .cloned(),
+            );
+            sandbox.set_directory_grants(all)?;
+        }
+        if grants.is_empty() {
+            self.directory_grants.remove(plugin_id);
+        } else {
+            self.directory_grants.insert(plugin_id.to_string(), grants);
+        }
+        Ok(())
+    }
+
+    /// Sets the app data directory under which selected files are staged.
+    pub fn set_selection_staging_dir(&mut self, dir: PathBuf) {
+        self.selection_staging_dir = Some(dir);
+    }
+
+    /// Grants a loaded plugin one-off access to a file dialog selection.
+    /// Returns the path at which the plugin sees it.
+    pub fn grant_selection(
+        &mut self,
+        plugin_id: &str,
+        selected: &Path,
+        writable: bool,
+    ) -> Result<String, PluginHostError> {
+        let staging_dir = self.selection_staging_dir.clone();
+        self.get_plugin_mut(plugin_id)?
+            .grant_selection(selected, writable, staging_dir.as_deref())
+    }
+
+    /// Applies the remembered directory grants and network policy to a
+    /// freshly created sandbox, drops permissions it may not hold and
+    /// attaches the entity change queue.
+    fn prepare_sandbox(&self, mut sandbox: PluginSandbox) -> PluginSandbox {
+        let permissions = self.restrict_permissions(
+            &sandbox.metadata.id,
+            sandbox.state().permissions.clone(),
+        );
+        if let Err(e) = sandbox.update_permissions(permissions) {
+            warn!(plugin_id = %sandbox.metadata.id, "Permissions not applied: {}", e);
+        }
+        if let Some(grants) = self.directory_grants.get(&sandbox.metadata.id)
+            && let Err(e) = sandbox.set_directory_grants(grants.clone())
+        {
+            warn!(plugin_id = %sandbox.metadata.id, "Directory grants not applied: {}", e);
+        }
+        if let Some(policy) = self.network_policies.get(&sandbox.metadata.id) {
+            sandbox.set_network_policy(policy.clone());
+        }
+        sandbox.set_entity_change_queue(Arc::clone(&self.entity_changes));
+        sandbox.set_event_bus(Arc::clone(&self.event_bus));
+        sandbox
+    }
+
+    // ================================================================
+    // Permissions
+    // ================================================================
+
+    /// Records the permissions a plugin's manifest declares. From then on
+    /// the plugin is never granted an undeclared permission above Tier 1,
+    /// on load or at runtime. Applies to the loaded instance immediately.
+    pub fn set_declared_permissions(&mut self, plugin_id: &str, declared: Vec<Permission>) {
+        self.declared_permissions.insert(plugin_id.to_string(), declared);
+        let Some(current) = self.plugins.get(plugin_id).map(|s| s.state().permissions.clone())
+        else {
+            return;
+        };
+        let permissions = self.restrict_permissions(plugin_id, current);
+        if let Err(e) = self.apply_permissions(plugin_id, permissions) {
+            warn!(plugin_id = %plugin_id, "Declared permissions not applied: {}", e);
+        }
+    }
+
+    /// Replaces a loaded plugin's permissions. A plugin whose directory
+    /// grants cannot be revoked is unloaded rather than left with them.
+    fn apply_permissions(
+        &mut self,
+        plugin_id: &str,
+        permissions: PermissionSet,
+    ) -> Result<(), PluginHostError> {
+        let sandbox = self.get_plugin_mut(plugin_id)?;
+        if let Err(e) = sandbox.update_permissions(permissions) {
+            warn!(plugin_id = %plugin_id, "Unloading plugin whose grants could not be revoked");
+            self.unload_plugin(plugin_id)?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// What installing a plugin that declares `declared` would grant it,
+    /// grouped by tier, with the permissions this policy denies set apart.
+    pub fn consent_summary(&self, declared: &[Permission]) -> ConsentSummary {
+        ConsentSummary::new(declared, |permission| {
+            self.policy_engine.is_permission_denied_by_policy(permission)
+        })
+    }
+
+    /// Whether the plugin may be granted `permission`: the policy must not
+    /// deny it, and it must be Tier 1 or declared in the plugin's manifest.
+    /// A plugin installed without a manifest declares nothing, so it only
+    /// ever holds Tier 1.
+    fn check_grantable(
+        &self,
+        plugin_id: &str,
+        permission: Permission,
+    ) -> Result<(), PluginHostError> {
+        if self.policy_engine.is_permission_denied_by_policy(permission) {
+            return Err(PluginHostError::PolicyDenied(format!(
+                "permission '{}' is denied by policy",
+                permission.interface_name()
+            )));
+        }
+        let declared = self.declared_permissions.get(plugin_id).map_or(&[][..], Vec::as_slice);
+        if permission.tier() != PermissionTier::AlwaysGranted && !declared.contains(&permission) {
+            return Err(PluginHostError::PermissionDenied {
+                plugin_id: plugin_id.to_string(),
+                permission: permission.interface_name().to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Denies every permission the plugin may not be granted.
+    fn restrict_permissions(
+        &self,
+        plugin_id: &str,
+        mut permissions: PermissionSet,
+    ) -> PermissionSet {
+        for permission in Permission::ALL {
+            if self.check_grantable(plugin_id, permission).is_err() {
+                permissions.deny(permission);
+            }
+        }
+        permissions
+    }
+
+    // ================================================================
+    // Network
+    // ================================================================
+
+    /// Sets a plugin's outbound network policy: the host patterns from its
+    /// manifest plus any private-network access the user granted. It is
+    /// remembered and applied on every load.
+    pub fn set_network_policy(&mut self, plugin_id: &str, policy: NetworkPolicy) {
+        if let Some(sandbox) = self.plugins.get_mut(plugin_id) {
+            sandbox.set_network_policy(policy.clone());
+        }
+        self.network_policies.insert(plugin_id.to_string(), policy);
+    }
+
+    // ================================================================
+    // Entity change notifications
+    // ================================================================
+
+    /// Records a change made outside any plugin, e.g. through the shell's
+    /// SDK. Plugin writes through `sdk` are recorded by the sandbox.
+    pub fn record_entity_change(&self, change: EntityChange) {
+        self.entity_changes.push(change);
+    }
+
+    /// Records that sync updated an entity. Entities that no longer exist
+    /// are skipped, since their type is needed to pick the observers.
+    pub fn record_synced_entity(&self, entity_id: &str) {
+        match self.entity_store.get_entity(entity_id) {
+            Ok(Some(entity)) => self.entity_changes.push(EntityChange::new(
+                entity_id,
+                entity.entity_type,
+                EntityChangeKind::Updated,
+                CHANGE_SOURCE_SYNC,
+            )),
+            Ok(None) => debug!(entity_id = %entity_id, "Synced entity gone, not reported"),
+            Err(e) => warn!(entity_id = %entity_id, "Synced entity lookup failed: {}", e),
+        }
+    }
+
+    /// Delivers the pending changes once the debounce window has passed.
+    /// Each observer gets the changes it may see, in batches of at most
+    /// [`MAX_CHANGES_PER_CALL`]. Returns the number of calls made.
+    ///
+    /// Plugins' writes to synced settings are set aside for
+    /// [`Self::take_synced_setting_changes`].
+    pub fn deliver_entity_changes(&mut self) -> usize {
+        let (settings, changes): (Vec<_>, Vec<_>) = self
+            .entity_changes
+            .take_ready()
+            .into_iter()
+            .partition(|change| change.entity_type == SETTINGS_ENTITY_TYPE);
+        self.synced_settings.extend(settings);
+        if changes.is_empty() {
+            return 0;
+        }
+        let observers: Vec<String> = self
+            .plugins
+            .iter()
+            .filter(|(_, sandbox)| sandbox.has_entity_observer)
+            .map(|(id, _)| id.clone())
+            .collect();
+        let mut calls = 0;
+        for plugin_id in observers {
+            let Some(sandbox) = self.plugins.get(&plugin_id) else {
+                continue;
+            };
+            let visible: Vec<EntityChange> = changes
+                .iter()
+                .filter(|change| sandbox.state().observes(change))
+                .cloned()
+                .collect();
+            for batch in visible.chunks(MAX_CHANGES_PER_CALL) {
+                calls += 1;
+                let result = self.call_plugin(&plugin_id, "on-entities-changed", |sandbox| {
+                    sandbox.call_on_entities_changed(batch)
+                });
+                if let Err(e) = result {
+                    warn!(plugin_id = %plugin_id, "Entity change delivery failed: {}", e);
+                    break;
                 }
+            }
+            self.route_agent_commands(&plugin_id, 0);
+        }
+        calls
+    }
+
+    /// Writes plugins made to synced settings, for the shell to record as
+    /// entity events (see [`PluginSettings::set_with_sync`]).
+    pub fn take_synced_setting_changes(&mut self) -> Vec<EntityChange> {
+        std::mem::take(&mut self.synced_settings)
+    }
+
+    /// Number of changes waiting for delivery.
+    pub fn pending_entity_changes(&self) -> usize {
+        self.entity_changes.len()
+    }
+
+    // ================================================================
+    // Event bus
+    // ================================================================
+
+    /// Sets the topics a plugin declared in its manifest. Only these can be
+    /// published on; they are remembered across loads.
+    pub fn set_event_topics(
+        &mut self,
+        plugin_id: &str,
+        topics: Vec<String>,
+    ) -> Result<(), PluginHostError> {
+        self.event_bus.set_topics(plugin_id, topics)
+    }
 
-                if self.plugins.contains_key(&plugin_id) {
-                    return Err(PluginHostError::PluginAlreadyLoaded(plugin_id));
+    /// The (publisher, topic) pairs a loaded plugin is subscribed to.
+    pub fn event_subscriptions(&self, plugin_id: &str) -> Vec<(String, String)> {
+        self.event_bus.subscriptions(plugin_id)
+    }
+
+    /// Delivers published events to their subscribers through the
+    /// `event-subscriber` export, in publish order and in batches of at most
+    /// [`MAX_EVENTS_PER_CALL`]. Events for subscribers without the export
+    /// are dropped. Returns the number of calls made.
+    pub fn deliver_events(&mut self) -> usize {
+        let mut by_subscriber: Vec<(String, Vec<BusEvent>)> = Vec::new();
+        for (subscriber, event) in self.event_bus.take_pending() {
+            match by_subscriber.iter_mut().find(|(id, _)| *id == subscriber) {
+                Some((_, events)) => events.push(event),
+                None => by_subscriber.push((subscriber, vec![event])),
+            }
+        }
+        let mut calls = 0;
+        for (plugin_id, events) in by_subscriber {
+            let Some(sandbox) = self.plugins.get(&plugin_id) else {
+                continue;
+            };
+            if !sandbox.has_event_subscriber {
+                debug!(plugin_id = %plugin_id, "Subscriber has no event-subscriber export");
+                continue;
+            }
+            for batch in events.chunks(MAX_EVENTS_PER_CALL) {
+                calls += 1;
+                let result = self.call_plugin(&plugin_id, "on-events", |sandbox| {
+                    sandbox.call_on_events(batch)
+                });
+                if let Err(e) = result {
+                    warn!(plugin_id = %plugin_id, "Event delivery failed: {}", e);
+                    break;
                 }
+            }
+            self.route_agent_commands(&plugin_id, 0);
+        }
+        calls
+    }
+
+    /// Number of events waiting for delivery.
+    pub fn pending_events(&self) -> usize {
+        self.event_bus.pending_len()
+    }
+
+    /// Audited plugin actions (e.g. event subscriptions, agent commands), oldest first.
+    pub fn plugin_audit_log(&self) -> Vec<PluginAuditEntry> {
+        self.policy_engine.audit_log().entries()
+    }
+
+    // ================================================================
+    // Scheduled jobs
+    // ================================================================
+
+    /// Runs every job that is due, most overdue first. Jobs of plugins that
+    /// are not loaded wait until they are. A run fails if the plugin traps,
+    /// returns an error, or takes longer than its `call_timeout_ms` (the
+    /// first and last also count as crashes); failed jobs back off before
+    /// the next attempt. Returns the number of runs.
+    pub fn run_due_jobs(&mut self) -> usize {
+        let now = chrono::Utc::now().timestamp();
+        let due = match due_jobs(&self.entity_store, now) {
+            Ok(due) => due,
+            Err(e) => {
+                warn!("Scheduled job lookup failed: {}", e);
+                return 0;
+            }
+        };
+        let mut runs = 0;
+        for job in due {
+            if !self.plugins.contains_key(&job.plugin_id) {
+                continue;
+            }
+            runs += 1;
+            let result = self.call_plugin(&job.plugin_id, "on-scheduled", |sandbox| {
+                sandbox.call_on_scheduled(&job.job_id)
+            });
+            let result = match result {
+                Ok(result) => result,
+                Err(e) => Err(e.to_string()),
+            };
+            self.route_agent_commands(&job.plugin_id, 0);
+            self.finish_job_run(job, result, now);
+        }
+        runs
+    }
+
+    /// Stores the outcome of a run. If the plugin cancelled or replaced the
+    /// job while it ran, its new state is kept instead.
+    fn finish_job_run(&self, mut job: ScheduledJob, result: Result<(), String>, now: i64) {
+        let jobs = PluginJobs::new(&self.entity_store, &job.plugin_id);
+        match jobs.get(&job.job_id) {
+            Ok(Some(stored)) if stored.schedule == job.schedule => {}
+            Ok(_) => return,
+            Err(e) => {
+                warn!(
+                    plugin_id = %job.plugin_id,
+                    job_id = %job.job_id,
+                    "Job lookup failed: {}",
+                    e
+                );
+                return;
+            }
+        }
+        match result {
+            Ok(()) => job.record_success(now),
+            Err(e) => {
+                warn!(
+                    plugin_id = %job.plugin_id,
+                    job_id = %job.job_id,
+                    "Scheduled job failed: {}",
+                    e
+                );
+                job.record_failure(now, e);
+            }
+        }
+        if let Err(e) = jobs.save(&job) {
+            warn!(
+                plugin_id = %job.plugin_id,
+                job_id = %job.job_id,
+                "Job state not saved: {}",
+                e
+            );
+        }
+    }
 
-                info!(plugin_id = %plugin_id, "Plugin loaded from Wasm component (parallel)");
-                self.plugins.insert(plugin_id.clone(), sandbox);
-                Ok(plugin_id)
-            })
-            .collect()
+    /// A plugin's scheduled jobs and their run state. Works for plugins that
+    /// are not loaded.
+    pub fn scheduled_jobs(&self, plugin_id: &str) -> Result<Vec<ScheduledJob>, PluginHostError> {
+        PluginJobs::new(&self.entity_store, plugin_id).list()
     }
 
-    /// Unloads a plugin, calling dispose() if it's a Wasm component.
-    pub fn unload_plugin(&mut self, plugin_id: &str) -> Result<(), PluginHostError> {
-        match self.plugins.remove(plugin_id) {
-            Some(mut sandbox) => {
-                if sandbox.has_runtime() {
-                    if let Err(e) = sandbox.call_dispose() {
-                        warn!(plugin_id = %plugin_id, "dispose() failed during unload: {}", e);
-                    }
-                }
-                info!(plugin_id = %plugin_id, "Plugin unloaded");
-                Ok(())
-            }
-            None => Err(PluginHostError::PluginNotFound(plugin_id.to_string())),
-        }
+    // ================================================================
+    // Settings
+    // ================================================================
+
+    /// Persisted settings of a plugin, for the shell's settings UI. Works for
+    /// plugins that are not loaded; those get the third-party quota.
+    pub fn plugin_settings<'a>(&'a self, plugin_id: &'a str) -> PluginSettings<'a> {
+        let quota = self.plugins.get(plugin_id).map_or_else(
+            || ResourceLimits::third_party().max_settings_bytes,
+            |sandbox| sandbox.state().settings_quota_bytes,
+        );
+        PluginSettings::new(&self.entity_store, plugin_id, quota)
     }
 
     // ================================================================
@@ -294,86 +1247,128 @@ impl PluginHostManager {
     }
 
     /// Send a command to a plugin by calling its handle_command() export.
+    ///
+    /// Commands an agent plugin queued through `send-command` during the
+    /// call are routed afterwards, whatever the call's own outcome, and
+    /// recorded in the plugin audit log.
     pub fn send_command(
         &mut self,
         plugin_id: &str,
         command_name: &str,
         args: &str,
     ) -> Result<String, PluginHostError> {
-        let sandbox = self.get_plugin_mut(plugin_id)?;
-        sandbox.call_handle_command(command_name, args)
+        let result = self.call_plugin(plugin_id, "handle-command", |sandbox| {
+            sandbox.call_handle_command(command_name, args)
+        });
+        self.route_agent_commands(plugin_id, 0);
+        result
+    }
+
+    /// Delivers the commands `caller` queued, then whatever their targets
+    /// queue in turn, up to [`MAX_AGENT_COMMAND_HOPS`] deep.
+    fn route_agent_commands(&mut self, caller: &str, hops: usize) {
+        let queued = match self.plugins.get_mut(caller) {
+            Some(sandbox) => sandbox.take_pending_commands(),
+            None => return,
+        };
+        for command in queued {
+            let result = self.dispatch_agent_command(caller, &command, hops);
+            self.record_agent_command(caller, &command, result.err());
+        }
+    }
+
+    fn dispatch_agent_command(
+        &mut self,
+        caller: &str,
+        command: &PendingCommand,
+        hops: usize,
+    ) -> Result<(), PluginHostError> {
+        if hops >= MAX_AGENT_COMMAND_HOPS {
+            return Err(PluginHostError::ResourceLimitExceeded {
+                plugin_id: caller.to_string(),
+                detail: format!("agent command chain exceeded {} hops", MAX_AGENT_COMMAND_HOPS),
+            });
+        }
+        // Re-check: permissions may have been revoked since the command was queued.
+        let state = self.get_plugin(caller)?.state();
+        state.check_permission(Permission::Agent)?;
+        state.check_permission(Permission::CrossPluginCommand)?;
+
+        let result = self.call_plugin(&command.target_plugin_id, "handle-command", |target| {
+            target.call_handle_command(&command.command, &command.args)
+        });
+        self.route_agent_commands(&command.target_plugin_id, hops + 1);
+        result.map(|_| ())
+    }
+
+    fn record_agent_command(
+        &mut self,
+        caller: &str,
+        command: &PendingCommand,
+        error: Option<PluginHostError>,
+    ) {
+        self.policy_engine.audit_log().record(
+            caller,
+            AUDIT_AGENT_COMMAND,
+            &format!("{}/{}", command.target_plugin_id, command.command),
+            error.map(|e| e.to_string()),
+        );
     }
 
     /// Get the view state JSON from a plugin.
     pub fn get_view_state(&mut self, plugin_id: &str) -> Result<String, PluginHostError> {
-        let sandbox = self.get_plugin_mut(plugin_id)?;
-        sandbox.call_get_view_state()
+        self.call_plugin(plugin_id, "get-view-state", PluginSandbox::call_get_view_state)
     }
 
     /// Get the raw view data JSON from a plugin (for host-side template evaluation).
     pub fn get_view_data(&mut self, plugin_id: &str) -> Result<String, PluginHostError> {
-        let sandbox = self.get_plugin_mut(plugin_id)?;
-        sandbox.call_get_view_data()
+        self.call_plugin(plugin_id, "get-view-data", PluginSandbox::call_get_view_data)
     }
 
     /// Initialize a loaded plugin.
     pub fn initialize_plugin(&mut self, plugin_id: &str) -> Result<bool, PluginHostError> {
-        let sandbox = self.get_plugin_mut(plugin_id)?;
-        sandbox.call_initialize()
+        self.call_plugin(plugin_id, "initialize", PluginSandbox::call_initialize)
     }
 
     /// Activate a loaded plugin.
     pub fn activate_plugin(&mut self, plugin_id: &str) -> Result<(), PluginHostError> {
-        let sandbox = self.get_plugin_mut(plugin_id)?;
-        sandbox.call_activate()
+        self.call_plugin(plugin_id, "activate", PluginSandbox::call_activate)
     }
 
     /// Notify a plugin it was navigated to.
     pub fn notify_navigated_to(&mut self, plugin_id: &str) -> Result<(), PluginHostError> {
-        let sandbox = self.get_plugin_mut(plugin_id)?;
-        sandbox.call_on_navigated_to()
+        self.call_plugin(plugin_id, "on-navigated-to", PluginSandbox::call_on_navigated_to)
     }
 
     /// Notify a plugin it was navigated away from.
     pub fn notify_navigated_from(&mut self, plugin_id: &str) -> Result<(), PluginHostError> {
-        let sandbox = self.get_plugin_mut(plugin_id)?;
-        sandbox.call_on_navigated_from()
+        self.call_plugin(plugin_id, "on-navigated-from", PluginSandbox::call_on_navigated_from)
     }
 
-    /// Fetch a URL on behalf of a plugin, checking its Network permission first.
-    /// Returns the response body bytes on success.
+    /// Fetch a URL on behalf of a plugin under its Network permission and
+    /// network policy. Returns the response body bytes on success.
     pub fn fetch_url_for_plugin(
         &self,
         plugin_id: &str,
         url: &str,
     ) -> Result<Vec<u8>, PluginHostError> {
-        use crate::permissions::Permission;
-
         let sandbox = self.get_plugin(plugin_id)?;
-        sandbox.state().check_permission(Permission::Network)?;
-
-        let client = reqwest::blocking::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .user_agent("PrivStack/1.0")
-            .build()
-            .map_err(|e| PluginHostError::NetworkError(format!("http client: {e}")))?;
-
-        let resp = client
-            .get(url)
-            .header("Accept", "image/*,*/*;q=0.8")
-            .send()
-            .map_err(|e| PluginHostError::NetworkError(format!("fetch failed: {e}")))?;
-
-        if !resp.status().is_success() {
+        let headers = [("Accept".to_string(), "image/*,*/*;q=0.8".to_string())];
+        let resp = sandbox.state().fetch(FetchRequest {
+            url,
+            method: "GET",
+            headers: &headers,
+            body: None,
+            deadline: None,
+        })?;
+
+        if !(200..300).contains(&resp.status) {
             return Err(PluginHostError::NetworkError(format!(
                 "HTTP {} fetching {url}",
-                resp.status()
+                resp.status
             )));
         }
-
-        resp.bytes()
-            .map(|b| b.to_vec())
-            .map_err(|e| PluginHostError::NetworkError(format!("read body: {e}")))
+        Ok(resp.body)
     }
 
     // ================================================================
@@ -395,18 +1390,19 @@ impl PluginHostManager {
 
         let mut all_items = Vec::new();
         for plugin_id in plugin_ids {
-            if let Some(sandbox) = self.plugins.get_mut(&plugin_id) {
-                match sandbox.call_search_linkable_items(query, max_results) {
-                    Ok(items) => {
-                        for mut item in items {
-                            item.plugin_id = Some(plugin_id.clone());
-                            all_items.push(item);
-                        }
-                    }
-                    Err(e) => {
-                        warn!(plugin_id = %plugin_id, "Linkable item search failed: {}", e);
+            let result = self.call_plugin(&plugin_id, "search-items", |sandbox| {
+                sandbox.call_search_linkable_items(query, max_results)
+            });
+            match result {
+                Ok(items) => {
+                    for mut item in items {
+                        item.plugin_id = Some(plugin_id.clone());
+                        all_items.push(item);
                     }
                 }
+                Err(e) => {
+                    warn!(plugin_id = %plugin_id, "Linkable item search failed: {}", e);
+                }
             }
         }
         all_items
@@ -418,14 +1414,16 @@ impl PluginHostManager {
         plugin_id: &str,
         item_id: &str,
     ) -> Result<(), PluginHostError> {
-        let sandbox = self.get_plugin_mut(plugin_id)?;
+        let sandbox = self.get_plugin(plugin_id)?;
         if !sandbox.has_deep_link_target {
             return Err(PluginHostError::CapabilityNotSupported {
                 plugin_id: plugin_id.to_string(),
                 capability: "deep-link-target".to_string(),
             });
         }
-        sandbox.call_navigate_to_item(item_id)
+        self.call_plugin(plugin_id, "navigate-to-item", |sandbox| {
+            sandbox.call_navigate_to_item(item_id)
+        })
     }
 
     /// Navigate to a specific item and return its view data in one call.
@@ -439,7 +1437,7 @@ impl PluginHostManager {
         plugin_id: &str,
         item_id: &str,
     ) -> Result<String, PluginHostError> {
-        let sandbox = self.get_plugin_mut(plugin_id)?;
+        let sandbox = self.get_plugin(plugin_id)?;
         if !sandbox.has_deep_link_target {
             return Err(PluginHostError::CapabilityNotSupported {
                 plugin_id: plugin_id.to_string(),
@@ -447,9 +1445,11 @@ impl PluginHostManager {
             });
         }
         // Navigate to the entity
-        sandbox.call_navigate_to_item(item_id)?;
+        self.call_plugin(plugin_id, "navigate-to-item", |sandbox| {
+            sandbox.call_navigate_to_item(item_id)
+        })?;
         // Get and return the view data for that entity
-        sandbox.call_get_view_data()
+        self.call_plugin(plugin_id, "get-view-data", PluginSandbox::call_get_view_data)
     }
 
     /// Get metadata about all link providers across plugins.
@@ -490,14 +1490,20 @@ impl PluginHostManager {
             .collect()
     }
 
-    /// Updates permissions for a loaded plugin at runtime.
+    /// Updates permissions for a loaded plugin at runtime. Refuses a set
+    /// that grants a permission the policy denies or the manifest does not
+    /// declare.
     pub fn update_plugin_permissions(
         &mut self,
         plugin_id: &str,
         permissions: PermissionSet,
     ) -> Result<(), PluginHostError> {
-        let sandbox = self.get_plugin_mut(plugin_id)?;
-        sandbox.update_permissions(permissions);
+        self.get_plugin(plugin_id)?;
+        for &permission in permissions.granted_permissions() {
+            self.check_grantable(plugin_id, permission)?;
+        }
+        let permissions = self.restrict_permissions(plugin_id, permissions);
+        self.apply_permissions(plugin_id, permissions)?;
         info!(plugin_id = %plugin_id, "Plugin permissions updated at runtime");
         Ok(())
     }
@@ -542,6 +1548,7 @@ mod tests {
     use super::*;
     use crate::permissions::PermissionSet;
     use crate::policy::{PolicyConfig, PolicyMode};
+    use crate::scheduler::JobSchedule;
 
     fn test_stores() -> (
         Arc<privstack_storage::EntityStore>,
@@ -576,6 +1583,7 @@ mod tests {
                 searchable: true,
                 vector_dim: None,
                 enum_options: None,
+                sensitivity: None,
             }],
             merge_strategy: WitMergeStrategy::LwwPerField,
         }]
@@ -732,33 +1740,502 @@ mod tests {
         assert!(matches!(result, Err(PluginHostError::PolicyDenied(_))));
     }
 
+    #[test]
+    fn policy_sees_signer_of_the_loaded_build() {
+        let (es, ev) = test_stores();
+        let policy = PolicyEngine::with_config(PolicyConfig {
+            mode: PolicyMode::Allowlist,
+            allowed_signing_keys: vec!["trusted-key".into()],
+            ..PolicyConfig::default()
+        });
+        let mut mgr = PluginHostManager::with_policy(es, ev, policy);
+        let register = |mgr: &mut PluginHostManager, signed_for: &str, key: &str| {
+            let sandbox = PluginSandbox::new(
+                test_metadata("signed.plugin"),
+                test_schemas(),
+                PermissionSet::default_third_party(),
+                ResourceLimits::third_party(),
+                Arc::clone(&mgr.entity_store),
+                Arc::clone(&mgr.event_store),
+            )
+            .unwrap();
+            let admission = BuildAdmission {
+                permissions: PermissionSet::default_third_party(),
+                resource_limits: ResourceLimits::third_party(),
+                trusted: Some(TrustedBuild {
+                    plugin_id: signed_for.into(),
+                    signing_key: key.into(),
+                    first_party: false,
+                }),
+            };
+            mgr.register_build(sandbox, admission)
+        };
+
+        // The signer of another build does not vouch for this one.
+        mgr.trust_build("signed.plugin", b"other build", "trusted-key".into(), false);
+        let unsigned = mgr.load_plugin(
+            test_metadata("signed.plugin"),
+            test_schemas(),
+            PermissionSet::default_third_party(),
+            ResourceLimits::third_party(),
+        );
+        assert!(matches!(unsigned, Err(PluginHostError::PolicyDenied(_))));
+
+        let wrong_key = register(&mut mgr, "signed.plugin", "other-key");
+        assert!(matches!(wrong_key, Err(PluginHostError::PolicyDenied(_))));
+        let wrong_plugin = register(&mut mgr, "other.plugin", "trusted-key");
+        assert!(matches!(wrong_plugin, Err(PluginHostError::PolicyDenied(_))));
+        register(&mut mgr, "signed.plugin", "trusted-key").unwrap();
+    }
+
+    #[test]
+    fn only_trusted_builds_load_outside_dev_mode() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("p1.wasm");
+        std::fs::write(&path, b"not a component").unwrap();
+
+        assert!(matches!(
+            mgr.load_plugin_from_wasm(&path),
+            Err(PluginHostError::PolicyDenied(_))
+        ));
+
+        // Once admitted, the bytes get as far as compilation.
+        mgr.trust_build("p1", b"not a component", "ab".repeat(32), false);
+        assert!(matches!(
+            mgr.load_plugin_from_wasm(&path),
+            Err(PluginHostError::Compilation(_))
+        ));
+
+        std::fs::write(&path, b"modified after install").unwrap();
+        assert!(matches!(
+            mgr.load_plugin_from_wasm(&path),
+            Err(PluginHostError::PolicyDenied(_))
+        ));
+        mgr.set_dev_mode(true);
+        assert!(matches!(
+            mgr.load_plugin_from_wasm(&path),
+            Err(PluginHostError::Compilation(_))
+        ));
+    }
+
+    #[test]
+    fn trusted_builds_run_with_their_package_grants() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.set_declared_permissions("p1", vec![Permission::Network]);
+        mgr.trust_build("p1", b"build", "ab".repeat(32), false);
+
+        let admission = mgr.admit_build(b"build", "build").unwrap();
+        assert!(admission.permissions.is_granted(Permission::Network));
+        assert!(admission.permissions.is_denied(Permission::Filesystem));
+        assert_eq!(
+            admission.resource_limits.call_timeout_ms,
+            ResourceLimits::third_party().call_timeout_ms
+        );
+
+        mgr.trust_build("p1", b"new build", "ab".repeat(32), true);
+        assert!(mgr.admit_build(b"build", "build").is_err());
+        let admission = mgr.admit_build(b"new build", "new build").unwrap();
+        assert_eq!(
+            admission.resource_limits.max_memory_bytes,
+            ResourceLimits::first_party().max_memory_bytes
+        );
+    }
+
+    #[test]
+    fn trusted_build_restored_from_its_hash() {
+        let (es, ev) = test_stores();
+        let mut first = PluginHostManager::new_for_test(Arc::clone(&es), Arc::clone(&ev));
+        let hash = first.trust_build("p1", b"build", "ab".repeat(32), true);
+
+        // A later session knows the build only by the hash the shell saved.
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        assert!(mgr.admit_build(b"build", "build").is_err());
+        mgr.trust_build_hash("p1", hash, "ab".repeat(32), true);
+        let admission = mgr.admit_build(b"build", "build").unwrap();
+        assert_eq!(admission.trusted.unwrap().signing_key, "ab".repeat(32));
+        assert!(mgr.admit_build(b"other", "other").is_err());
+    }
+
+    // ================================================================
+    // send_command on metadata-only plugin (no Wasm runtime)
+    // ================================================================
+
+    #[test]
+    fn send_command_metadata_only_fails() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::default_first_party(),
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+
+        let result = mgr.send_command("p1", "do_thing", "{}");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn send_command_plugin_not_found() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+
+        let result = mgr.send_command("nonexistent", "cmd", "{}");
+        assert!(matches!(result, Err(PluginHostError::PluginNotFound(_))));
+    }
+
+    // ================================================================
+    // Filesystem grants
+    // ================================================================
+
+    fn fs_perms() -> PermissionSet {
+        let mut perms = PermissionSet::default_first_party();
+        perms.grant(Permission::Filesystem);
+        perms
+    }
+
+    fn vault_scope(writable: bool) -> Vec<PpkFilesystemScope> {
+        vec![PpkFilesystemScope {
+            name: "vault".into(),
+            description: String::new(),
+            writable,
+        }]
+    }
+
+    #[test]
+    fn directory_grants_require_filesystem_permission() {
+        let dir = tempfile::tempdir().unwrap();
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::default_first_party(),
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+        mgr.set_filesystem_scopes("p1", vault_scope(false));
+
+        let grant = DirectoryGrant {
+            host_path: dir.path().to_path_buf(),
+            guest_path: "/vault".into(),
+            writable: false,
+        };
+        let result = mgr.set_directory_grants("p1", vec![grant]);
+        assert!(matches!(result, Err(PluginHostError::PermissionDenied { .. })));
+        assert!(mgr.get_plugin("p1").unwrap().directory_grants().is_empty());
+    }
+
+    #[test]
+    fn approved_grants_applied_on_load_and_dropped_on_revoke() {
+        let dir = tempfile::tempdir().unwrap();
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        let grant = DirectoryGrant {
+            host_path: dir.path().to_path_buf(),
+            guest_path: "/vault".into(),
+            writable: true,
+        };
+        mgr.set_declared_permissions("p1", vec![Permission::Filesystem]);
+        mgr.set_filesystem_scopes("p1", vault_scope(true));
+        mgr.set_directory_grants("p1", vec![grant.clone()]).unwrap();
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            fs_perms(),
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+        assert_eq!(mgr.get_plugin("p1").unwrap().directory_grants(), &[grant]);
+
+        let guest = mgr.grant_selection("p1", dir.path(), false).unwrap();
+        assert_eq!(guest, "/selected/0");
+        assert_eq!(mgr.get_plugin("p1").unwrap().directory_grants().len(), 2);
+
+        mgr.update_plugin_permissions("p1", PermissionSet::default_first_party())
+            .unwrap();
+        assert!(mgr.get_plugin("p1").unwrap().directory_grants().is_empty());
+    }
+
+    #[test]
+    fn file_selections_staged_in_app_data_and_removed_on_unload() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = tempfile::tempdir().unwrap();
+        let file = dir.path().join("photo.jpg");
+        std::fs::write(&file, b"x").unwrap();
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.set_declared_permissions("p1", vec![Permission::Filesystem]);
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            fs_perms(),
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+        assert!(mgr.grant_selection("p1", &file, false).is_err());
+
+        let staging = data.path().join("selections");
+        mgr.set_selection_staging_dir(staging.clone());
+        let guest = mgr.grant_selection("p1", &file, false).unwrap();
+        assert_eq!(guest, "/selected/0/photo.jpg");
+        let grants = mgr.get_plugin("p1").unwrap().directory_grants();
+        assert!(grants[0].host_path.starts_with(&staging));
+
+        mgr.unload_plugin("p1").unwrap();
+        assert_eq!(std::fs::read_dir(&staging).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn selection_root_reserved_for_dialogs() {
+        let dir = tempfile::tempdir().unwrap();
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        let grant = DirectoryGrant {
+            host_path: dir.path().to_path_buf(),
+            guest_path: "/selected/0".into(),
+            writable: false,
+        };
+        let result = mgr.set_directory_grants("p1", vec![grant]);
+        assert!(matches!(result, Err(PluginHostError::InvalidGrant(_))));
+    }
+
+    #[test]
+    fn grants_must_match_a_declared_scope() {
+        let dir = tempfile::tempdir().unwrap();
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        let grant = |guest_path: &str, writable| DirectoryGrant {
+            host_path: dir.path().to_path_buf(),
+            guest_path: guest_path.into(),
+            writable,
+        };
+
+        // No manifest scopes: nothing can be granted.
+        let result = mgr.set_directory_grants("p1", vec![grant("/vault", false)]);
+        assert!(matches!(result, Err(PluginHostError::InvalidGrant(_))));
+
+        mgr.set_filesystem_scopes("p1", vault_scope(false));
+        let result = mgr.set_directory_grants("p1", vec![grant("/home", false)]);
+        assert!(matches!(result, Err(PluginHostError::InvalidGrant(_))));
+        let result = mgr.set_directory_grants("p1", vec![grant("/vault", true)]);
+        assert!(matches!(result, Err(PluginHostError::InvalidGrant(_))));
+        mgr.set_directory_grants("p1", vec![grant("/vault", false)]).unwrap();
+        mgr.set_directory_grants("p1", vec![]).unwrap();
+    }
+
+    // ================================================================
+    // Settings / uninstall
+    // ================================================================
+
+    #[test]
+    fn settings_visible_to_shell_and_wiped_on_uninstall() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::default_first_party(),
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+
+        mgr.get_plugin_mut("p1").unwrap().handle_settings_set("theme", "dark");
+        mgr.plugin_settings("p1").set_with_sync("layout", "grid", true).unwrap();
+        assert_eq!(
+            mgr.plugin_settings("p1").quota_bytes(),
+            ResourceLimits::first_party().max_settings_bytes
+        );
+        let listed = mgr.plugin_settings("p1").list().unwrap();
+        assert_eq!(listed.len(), 2);
+        assert!(listed.iter().any(|s| s.key == "layout" && s.sync));
+
+        assert_eq!(mgr.uninstall_plugin("p1").unwrap(), 2);
+        assert!(!mgr.is_loaded("p1"));
+        assert!(mgr.plugin_settings("p1").list().unwrap().is_empty());
+    }
+
+    // ================================================================
+    // Event bus
+    // ================================================================
+
+    #[test]
+    fn task_completion_reaches_subscribed_time_tracker() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.set_event_topics("tasks", vec!["task-completed".into()]).unwrap();
+        mgr.set_declared_permissions("tracker", vec![Permission::EventSubscribe]);
+        for id in ["tasks", "tracker"] {
+            mgr.load_plugin(
+                test_metadata(id),
+                test_schemas(),
+                PermissionSet::default_third_party(),
+                ResourceLimits::third_party(),
+            )
+            .unwrap();
+        }
+
+        let tracker = mgr.get_plugin("tracker").unwrap().state();
+        assert!(matches!(
+            tracker.subscribe_event("tasks", "task-completed"),
+            Err(PluginHostError::PermissionDenied { .. })
+        ));
+        // The user approves the just-in-time prompt
+        let mut granted = PermissionSet::default_third_party();
+        granted.grant(Permission::EventSubscribe);
+        mgr.update_plugin_permissions("tracker", granted).unwrap();
+        let tracker = mgr.get_plugin("tracker").unwrap().state();
+        tracker.subscribe_event("tasks", "task-completed").unwrap();
+
+        let tasks = mgr.get_plugin("tasks").unwrap().state();
+        tasks.publish_event("task-completed", r#"{"task_id":"t1"}"#).unwrap();
+        assert!(tasks.publish_event("task-deleted", "{}").is_err());
+        assert_eq!(mgr.pending_events(), 1);
+
+        let audit = mgr.plugin_audit_log();
+        let subscriptions: Vec<bool> = audit
+            .iter()
+            .filter(|e| e.action == "event-subscribe")
+            .map(|e| e.allowed)
+            .collect();
+        assert_eq!(subscriptions, [false, true]);
+        assert!(audit.iter().any(|e| e.action == "event-publish" && !e.allowed));
+
+        // Metadata-only sandboxes have no `event-subscriber` export to call.
+        assert_eq!(mgr.deliver_events(), 0);
+        assert_eq!(mgr.pending_events(), 0);
+
+        mgr.unload_plugin("tracker").unwrap();
+        assert!(mgr.event_subscriptions("tracker").is_empty());
+    }
+
+    // ================================================================
+    // Scheduled jobs
+    // ================================================================
+
+    fn make_due(mgr: &PluginHostManager, plugin_id: &str, job_id: &str) {
+        let jobs = PluginJobs::new(&mgr.entity_store, plugin_id);
+        let mut job = jobs.get(job_id).unwrap().unwrap();
+        job.next_run_at = 0;
+        jobs.save(&job).unwrap();
+    }
+
+    #[test]
+    fn failed_job_runs_back_off_and_unloaded_plugins_wait() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::default_first_party(),
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+        let state = mgr.get_plugin("p1").unwrap().state();
+        state.schedule_job("refresh", JobSchedule::Interval { secs: 300 }).unwrap();
+        assert!(state.schedule_job("fast", JobSchedule::Interval { secs: 5 }).is_err());
+        PluginJobs::new(&mgr.entity_store, "p2")
+            .schedule("refresh", JobSchedule::Interval { secs: 300 }, 0)
+            .unwrap();
+        assert_eq!(mgr.run_due_jobs(), 0);
+
+        make_due(&mgr, "p1", "refresh");
+        make_due(&mgr, "p2", "refresh");
+        let before = chrono::Utc::now().timestamp();
+        // The metadata-only sandbox has no `scheduled-task` export to call.
+        assert_eq!(mgr.run_due_jobs(), 1);
+
+        let job = &mgr.scheduled_jobs("p1").unwrap()[0];
+        assert_eq!(job.consecutive_failures, 1);
+        assert!(job.last_error.is_some());
+        assert!(job.next_run_at >= before + 60);
+        assert_eq!(mgr.scheduled_jobs("p2").unwrap()[0].next_run_at, 0);
+    }
+
+    #[test]
+    fn jobs_wiped_on_uninstall() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        PluginJobs::new(&mgr.entity_store, "p1")
+            .schedule("digest", JobSchedule::Cron { expression: "@daily".into() }, 0)
+            .unwrap();
+        mgr.uninstall_plugin("p1").unwrap();
+        assert!(mgr.scheduled_jobs("p1").unwrap().is_empty());
+    }
+
     // ================================================================
-    // send_command on metadata-only plugin (no Wasm runtime)
+    // Agent command routing
     // ================================================================
 
-    #[test]
-    fn send_command_metadata_only_fails() {
+    fn queue_command(mgr: &mut PluginHostManager, caller: &str, target: &str, command: &str) {
+        mgr.get_plugin_mut(caller)
+            .unwrap()
+            .state_mut()
+            .pending_commands
+            .push(PendingCommand {
+                target_plugin_id: target.into(),
+                command: command.into(),
+                args: "{}".into(),
+            });
+    }
+
+    fn agent_manager(agent_perms: PermissionSet) -> PluginHostManager {
         let (es, ev) = test_stores();
         let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.set_declared_permissions("agent", Permission::ALL.to_vec());
         mgr.load_plugin(
-            test_metadata("p1"),
+            test_metadata("agent"),
+            test_schemas(),
+            agent_perms,
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+        mgr.load_plugin(
+            test_metadata("target"),
             test_schemas(),
             PermissionSet::default_first_party(),
             ResourceLimits::first_party(),
         )
         .unwrap();
+        mgr
+    }
 
-        let result = mgr.send_command("p1", "do_thing", "{}");
-        assert!(result.is_err());
+    #[test]
+    fn agent_commands_routed_and_audited_after_send_command() {
+        let mut mgr = agent_manager(PermissionSet::all_granted());
+        queue_command(&mut mgr, "agent", "target", "refresh");
+        queue_command(&mut mgr, "agent", "missing", "refresh");
+
+        // Metadata-only plugins cannot run commands, but routing still happens.
+        assert!(mgr.send_command("agent", "run", "{}").is_err());
+
+        let log = mgr.plugin_audit_log();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].plugin_id, "agent");
+        assert_eq!(log[0].action, "agent-command");
+        assert_eq!(log[0].target, "target/refresh");
+        assert!(!log[0].allowed);
+        assert!(log[0].reason.as_deref().unwrap().contains("no Wasm runtime"));
+        assert!(log[1].reason.as_deref().unwrap().contains("plugin not found"));
+        assert!(mgr.get_plugin_mut("agent").unwrap().take_pending_commands().is_empty());
     }
 
     #[test]
-    fn send_command_plugin_not_found() {
-        let (es, ev) = test_stores();
-        let mut mgr = PluginHostManager::new_for_test(es, ev);
+    fn agent_commands_rechecked_against_current_permissions() {
+        let mut perms = PermissionSet::all_granted();
+        perms.deny(Permission::CrossPluginCommand);
+        let mut mgr = agent_manager(perms);
+        queue_command(&mut mgr, "agent", "target", "refresh");
 
-        let result = mgr.send_command("nonexistent", "cmd", "{}");
-        assert!(matches!(result, Err(PluginHostError::PluginNotFound(_))));
+        let _ = mgr.send_command("agent", "run", "{}");
+
+        let log = mgr.plugin_audit_log();
+        assert_eq!(log.len(), 1);
+        assert!(log[0].reason.as_deref().unwrap().contains("cross-plugin-command"));
     }
 
     // ================================================================
@@ -1006,8 +2483,10 @@ mod tests {
         // Initially no vault access
         assert!(mgr.get_plugin("p1").unwrap().check_vault_access().is_err());
 
-        mgr.update_plugin_permissions("p1", PermissionSet::all_granted())
-            .unwrap();
+        mgr.set_declared_permissions("p1", vec![Permission::Vault]);
+        let mut request = PermissionSet::default_first_party();
+        request.grant(Permission::Vault);
+        mgr.update_plugin_permissions("p1", request).unwrap();
 
         assert!(mgr.get_plugin("p1").unwrap().check_vault_access().is_ok());
     }
@@ -1022,6 +2501,91 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn undeclared_permissions_are_never_granted() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.set_declared_permissions("p1", vec![Permission::Vault]);
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::all_granted(),
+            ResourceLimits::third_party(),
+        )
+        .unwrap();
+
+        let granted = mgr.get_plugin("p1").unwrap().state().permissions.clone();
+        assert!(granted.is_granted(Permission::Sdk));
+        assert!(granted.is_granted(Permission::Vault));
+        assert!(!granted.is_granted(Permission::Network));
+
+        let mut request = PermissionSet::from_declared(&[Permission::Vault]);
+        request.grant(Permission::Network);
+        assert!(matches!(
+            mgr.update_plugin_permissions("p1", request),
+            Err(PluginHostError::PermissionDenied { .. })
+        ));
+        assert!(!mgr.get_plugin("p1").unwrap().state().permissions.is_granted(Permission::Network));
+
+        // Re-declaring applies to the loaded instance.
+        mgr.set_declared_permissions("p1", vec![]);
+        assert!(mgr.get_plugin("p1").unwrap().check_vault_access().is_err());
+    }
+
+    #[test]
+    fn plugins_without_a_manifest_hold_tier_one_only() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::all_granted(),
+            ResourceLimits::third_party(),
+        )
+        .unwrap();
+
+        let granted = mgr.get_plugin("p1").unwrap().state().permissions.clone();
+        assert!(granted.is_granted(Permission::Sdk));
+        assert!(!granted.is_granted(Permission::Network));
+        assert!(matches!(
+            mgr.update_plugin_permissions("p1", PermissionSet::all_granted()),
+            Err(PluginHostError::PermissionDenied { .. })
+        ));
+    }
+
+    #[test]
+    fn policy_denied_permissions_are_stripped() {
+        let (es, ev) = test_stores();
+        let policy = PolicyEngine::with_config(PolicyConfig {
+            denied_permissions: ["vault".to_string()].into_iter().collect(),
+            ..PolicyConfig::default()
+        });
+        let mut mgr = PluginHostManager::with_policy(es, ev, policy);
+        let declared = vec![Permission::Vault, Permission::Network];
+        let summary = mgr.consent_summary(&declared);
+        assert_eq!(summary.install_time.len(), 1);
+        assert_eq!(summary.denied_by_policy[0].permission, Permission::Vault);
+
+        mgr.set_declared_permissions("p1", declared.clone());
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::from_declared(&declared),
+            ResourceLimits::third_party(),
+        )
+        .unwrap();
+        let granted = mgr.get_plugin("p1").unwrap().state().permissions.clone();
+        assert!(granted.is_granted(Permission::Network));
+        assert!(granted.is_denied(Permission::Vault));
+
+        let mut request = granted;
+        request.grant(Permission::Vault);
+        assert!(matches!(
+            mgr.update_plugin_permissions("p1", request),
+            Err(PluginHostError::PolicyDenied(_))
+        ));
+    }
+
     // ================================================================
     // fetch_url_for_plugin permission check
     // ================================================================
@@ -1052,6 +2616,128 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn fetch_url_blocks_loopback_and_logs_request() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.set_declared_permissions("p1", vec![Permission::Network]);
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::all_granted(),
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+
+        let result = mgr.fetch_url_for_plugin("p1", "http://127.0.0.1:8080/admin");
+        assert!(matches!(result, Err(PluginHostError::NetworkError(_))));
+
+        let metrics = mgr.get_plugin_metrics("p1").unwrap();
+        assert_eq!(metrics.network_requests_total, 1);
+        let record = &metrics.network_recent_requests[0];
+        assert_eq!(record.url, "http://127.0.0.1:8080/admin");
+        assert!(record.error.as_deref().unwrap().contains("non-public"));
+    }
+
+    // ================================================================
+    // Entity change notifications
+    // ================================================================
+
+    #[test]
+    fn synced_entities_are_queued_with_their_type() {
+        let (es, ev) = test_stores();
+        es.save_entity_raw(&privstack_model::Entity {
+            id: "e1".into(),
+            entity_type: "test_item".into(),
+            data: serde_json::json!({ "title": "synced" }),
+            created_at: 0,
+            modified_at: 0,
+            created_by: "peer".into(),
+        })
+        .unwrap();
+        let mgr = PluginHostManager::new_for_test(es, ev);
+
+        mgr.record_synced_entity("e1");
+        mgr.record_synced_entity("missing");
+        assert_eq!(mgr.pending_entity_changes(), 1);
+    }
+
+    #[test]
+    fn observers_see_other_writers_changes_to_their_types() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::default_first_party(),
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+        let own = EntityChange::new("e1", "test_item", EntityChangeKind::Updated, "p1");
+        let synced = EntityChange::new("e1", "test_item", EntityChangeKind::Updated, "sync");
+        let foreign = EntityChange::new("e2", "other_item", EntityChangeKind::Created, "host");
+
+        let state = mgr.get_plugin("p1").unwrap().state();
+        assert!(!state.observes(&own));
+        assert!(state.observes(&synced));
+        assert!(!state.observes(&foreign));
+
+        mgr.set_declared_permissions("p1", Permission::ALL.to_vec());
+        mgr.update_plugin_permissions("p1", PermissionSet::all_granted()).unwrap();
+        assert!(mgr.get_plugin("p1").unwrap().state().observes(&foreign));
+    }
+
+    #[test]
+    fn plugin_writes_to_synced_settings_are_set_aside_for_the_shell() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.set_declared_permissions("p1", Permission::ALL.to_vec());
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::all_granted(),
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+        mgr.plugin_settings("p1").set_with_sync("layout", "grid", true).unwrap();
+
+        let sandbox = mgr.get_plugin_mut("p1").unwrap();
+        sandbox.handle_settings_set("layout", "list");
+        sandbox.handle_settings_set("theme", "dark");
+        assert_eq!(mgr.pending_entity_changes(), 1);
+        let setting_id = crate::plugin_settings::setting_entity_id("p1", "layout");
+        let change =
+            EntityChange::new(&setting_id, SETTINGS_ENTITY_TYPE, EntityChangeKind::Updated, "p2");
+        assert!(!mgr.get_plugin("p1").unwrap().state().observes(&change));
+
+        std::thread::sleep(crate::entity_changes::DEFAULT_CHANGE_DEBOUNCE * 2);
+        assert_eq!(mgr.deliver_entity_changes(), 0);
+        let synced = mgr.take_synced_setting_changes();
+        assert_eq!(synced.len(), 1);
+        assert_eq!(synced[0].entity_id, setting_id);
+        assert!(mgr.take_synced_setting_changes().is_empty());
+    }
+
+    #[test]
+    fn network_policy_is_reapplied_on_load() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        let policy = NetworkPolicy::for_hosts(vec!["api.example.com".into()]);
+        mgr.set_network_policy("p1", policy.clone());
+        mgr.set_declared_permissions("p1", vec![Permission::Network]);
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::all_granted(),
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+        assert_eq!(mgr.get_plugin("p1").unwrap().network_policy(), &policy);
+
+        let result = mgr.fetch_url_for_plugin("p1", "https://example.org/");
+        assert!(result.unwrap_err().to_string().contains("allowlist"));
+    }
+
     // ================================================================
     // unload nonexistent plugin
     // ================================================================
@@ -1132,4 +2818,205 @@ mod tests {
         assert_eq!(mgr.plugin_count(), 0);
         assert!(!mgr.is_loaded("anything"));
     }
+
+    // ================================================================
+    // Crash recovery
+    // ================================================================
+
+    #[test]
+    fn failed_restart_disables_plugin_until_reset() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        let load = |mgr: &mut PluginHostManager| {
+            mgr.load_plugin(
+                test_metadata("p1"),
+                test_schemas(),
+                PermissionSet::default_first_party(),
+                ResourceLimits::first_party(),
+            )
+        };
+        load(&mut mgr).unwrap();
+
+        // Metadata-only sandboxes have no component to restart from.
+        mgr.handle_crash("p1", "handle-command", "wasm trap: unreachable".into());
+        assert!(!mgr.is_loaded("p1"));
+        assert!(mgr.disabled_reason("p1").unwrap().contains("restart after crash failed"));
+        let reports = mgr.crash_reports(Some("p1"));
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].operation, "handle-command");
+        assert_eq!(reports[0].outcome, CrashOutcome::RestartFailed);
+        assert!(reports[0].restart_error.is_some());
+
+        assert!(matches!(load(&mut mgr), Err(PluginHostError::PluginDisabled { .. })));
+        assert!(matches!(
+            mgr.send_command("p1", "noop", "{}"),
+            Err(PluginHostError::PluginDisabled { .. })
+        ));
+
+        assert!(mgr.reset_circuit_breaker("p1"));
+        assert!(!mgr.reset_circuit_breaker("p1"));
+        load(&mut mgr).unwrap();
+        assert_eq!(mgr.crash_reports(None).len(), 1);
+    }
+
+    #[test]
+    fn repeated_crashes_trip_circuit_breaker() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::default_first_party(),
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+
+        let now = chrono::Utc::now().timestamp();
+        for _ in 1..MAX_CRASHES_PER_WINDOW {
+            assert!(!mgr.crashes.record_crash("p1", now));
+        }
+        mgr.handle_crash("p1", "on-events", "all fuel consumed".into());
+
+        assert!(!mgr.is_loaded("p1"));
+        assert!(mgr.disabled_reason("p1").unwrap().contains("crashed"));
+        let reports = mgr.crash_reports(None);
+        assert_eq!(reports[0].outcome, CrashOutcome::Disabled);
+        assert_eq!(reports[0].restart_error, None);
+
+        mgr.uninstall_plugin("p1").unwrap();
+        assert_eq!(mgr.disabled_reason("p1"), None);
+        assert!(mgr.crash_reports(None).is_empty());
+    }
+
+    #[test]
+    fn disabled_plugins_reported_once_with_their_entity_types() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        for id in ["p1", "p2"] {
+            mgr.load_plugin(
+                test_metadata(id),
+                test_schemas(),
+                PermissionSet::default_first_party(),
+                ResourceLimits::first_party(),
+            )
+            .unwrap();
+        }
+        assert!(mgr.take_disabled_plugins().is_empty());
+
+        mgr.handle_crash("p1", "handle-command", "wasm trap: unreachable".into());
+        assert_eq!(
+            mgr.take_disabled_plugins(),
+            vec![("p1".to_string(), vec!["test_item".to_string()])]
+        );
+        assert!(mgr.take_disabled_plugins().is_empty());
+        assert!(mgr.is_loaded("p2"));
+    }
+
+    // ================================================================
+    // Hot reload
+    // ================================================================
+
+    #[test]
+    fn watch_requires_dev_mode_and_ends_on_unload() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::default_first_party(),
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+        let path = PathBuf::from("/nonexistent/p1.wasm");
+
+        assert!(matches!(
+            mgr.watch_plugin("p1", path.clone()),
+            Err(PluginHostError::PolicyDenied(_))
+        ));
+        mgr.set_dev_mode(true);
+        assert!(matches!(
+            mgr.watch_plugin("missing", path.clone()),
+            Err(PluginHostError::PluginNotFound(_))
+        ));
+        mgr.watch_plugin("p1", path.clone()).unwrap();
+        assert!(mgr.poll_dev_reloads().is_empty());
+
+        mgr.unload_plugin("p1").unwrap();
+        assert!(!mgr.unwatch_plugin("p1"));
+    }
+
+    #[test]
+    fn broken_build_keeps_old_instance() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::default_first_party(),
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("p1.wasm");
+        std::fs::write(&path, b"not a component").unwrap();
+        mgr.set_dev_mode(true);
+        mgr.watch_plugin("p1", path).unwrap();
+
+        assert!(matches!(
+            mgr.reload_plugin("p1"),
+            Err(PluginHostError::Compilation(_))
+        ));
+        assert!(mgr.is_loaded("p1"));
+
+        mgr.set_dev_mode(false);
+        assert!(!mgr.unwatch_plugin("p1"));
+    }
+
+    #[test]
+    fn signed_plugins_are_not_hot_reloaded() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::default_first_party(),
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+        mgr.set_dev_mode(true);
+        mgr.watch_plugin("p1", PathBuf::from("/nonexistent/p1.wasm")).unwrap();
+
+        mgr.trust_build("p1", b"signed build", "ab".repeat(32), false);
+        assert!(matches!(
+            mgr.reload_plugin("p1"),
+            Err(PluginHostError::PolicyDenied(_))
+        ));
+        assert!(matches!(
+            mgr.watch_plugin("p1", PathBuf::from("/nonexistent/p1.wasm")),
+            Err(PluginHostError::PolicyDenied(_))
+        ));
+        assert!(mgr.is_loaded("p1"));
+    }
+
+    #[test]
+    fn reload_keeps_a_tripped_breaker() {
+        let (es, ev) = test_stores();
+        let mut mgr = PluginHostManager::new_for_test(es, ev);
+        mgr.load_plugin(
+            test_metadata("p1"),
+            test_schemas(),
+            PermissionSet::default_first_party(),
+            ResourceLimits::first_party(),
+        )
+        .unwrap();
+        mgr.set_dev_mode(true);
+        mgr.watch_plugin("p1", PathBuf::from("/nonexistent/p1.wasm")).unwrap();
+        mgr.crashes.disable("p1", "crashed".into());
+
+        assert!(matches!(
+            mgr.reload_plugin("p1"),
+            Err(PluginHostError::PluginDisabled { .. })
+        ));
+        assert_eq!(mgr.disabled_reason("p1"), Some("crashed"));
+    }
 }
@@ -0,0 +1,555 @@
+//! Outbound HTTP for plugins: host allowlists, SSRF protection, size caps,
+//! rate limits and a request log.
+//!
+//! Every `fetch-url` goes through [`fetch`], which:
+//! - only allows `http`/`https` URLs whose host matches the plugin's
+//!   [`NetworkPolicy::allowed_hosts`] patterns,
+//! - resolves the host and rejects loopback, link-local, private (RFC 1918),
+//!   CGNAT and other non-public addresses unless `allow_private` was granted,
+//!   then pins the connection to the checked addresses so a second DNS answer
+//!   cannot point it elsewhere,
+//! - follows redirects itself, checking and pinning every hop the same way,
+//! - stops reading the body past `max_response_bytes`.
+//!
+//! A policy with no allowed hosts refuses every request.
+
+use crate::error::PluginHostError;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Read;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+/// Redirect hops followed before giving up.
+const MAX_REDIRECTS: usize = 5;
+
+/// Requests kept in a plugin's request log.
+const REQUEST_LOG_CAPACITY: usize = 100;
+
+/// Outbound network rules for one plugin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkPolicy {
+    /// Host patterns: `example.com` (exact), `*.example.com` (subdomains)
+    /// or `*` (any public host). Empty allows nothing.
+    pub allowed_hosts: Vec<String>,
+    /// Allows loopback, link-local and private-range targets. Only set when
+    /// the user explicitly granted LAN access.
+    pub allow_private: bool,
+    /// Largest response body accepted, in bytes.
+    pub max_response_bytes: usize,
+    /// Requests allowed per rolling minute.
+    pub max_requests_per_minute: u32,
+    /// Total time allowed per request, in milliseconds.
+    pub timeout_ms: u64,
+}
+
+impl Default for NetworkPolicy {
+    /// No hosts allowed.
+    fn default() -> Self {
+        Self {
+            allowed_hosts: Vec::new(),
+            allow_private: false,
+            max_response_bytes: 10 * 1024 * 1024, // 10MB
+            max_requests_per_minute: 60,
+            timeout_ms: 15_000,
+        }
+    }
+}
+
+impl NetworkPolicy {
+    /// Policy limited to the host patterns a manifest declares.
+    pub fn for_hosts(patterns: Vec<String>) -> Self {
+        Self {
+            allowed_hosts: patterns,
+            ..Self::default()
+        }
+    }
+
+    /// Whether `host` matches one of the allowed patterns.
+    pub fn host_allowed(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        self.allowed_hosts.iter().any(|pattern| {
+            let pattern = pattern.to_ascii_lowercase();
+            match pattern.strip_prefix("*.") {
+                _ if pattern == "*" => true,
+                Some(suffix) => host
+                    .strip_suffix(suffix)
+                    .is_some_and(|rest| rest.ends_with('.') && rest.len() > 1),
+                None => host == pattern,
+            }
+        })
+    }
+}
+
+/// Whether `ip` is a publicly routable address.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_documentation()
+                || v4.is_multicast()
+                || a == 0
+                || a >= 240
+                || (a == 100 && (b & 0xc0) == 64) // 100.64.0.0/10 (CGNAT)
+                || (a == 192 && b == 0 && c == 0)) // 192.0.0.0/24
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(v4));
+            }
+            let segments = v6.segments();
+            let first = segments[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || segments[..6].iter().all(|&s| s == 0) // ::a.b.c.d (IPv4-compatible)
+                || (first == 0x0064 && segments[1] == 0xff9b) // 64:ff9b::/96, 64:ff9b:1::/48
+                || first == 0x2002 // 2002::/16 (6to4)
+                || (first & 0xfe00) == 0xfc00 // fc00::/7 (unique local)
+                || (first & 0xffc0) == 0xfe80 // fe80::/10 (link-local)
+                || (first & 0xffc0) == 0xfec0 // fec0::/10 (site-local)
+                || (first == 0x2001 && segments[1] == 0x0db8)) // documentation
+        }
+    }
+}
+
+/// A request made by a plugin, as shown in its metrics.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NetworkRequestRecord {
+    pub method: String,
+    pub url: String,
+    /// HTTP status, if a response arrived.
+    pub status: Option<u16>,
+    /// Response body bytes read.
+    pub bytes: usize,
+    /// Why the request was refused or failed.
+    pub error: Option<String>,
+    /// Unix timestamp (seconds).
+    pub timestamp: i64,
+}
+
+/// Rate-limit window and request log of one plugin.
+#[derive(Debug, Default)]
+pub struct NetworkActivity {
+    window: VecDeque<Instant>,
+    log: VecDeque<NetworkRequestRecord>,
+    total: u64,
+}
+
+impl NetworkActivity {
+    /// Takes a slot in the rolling one-minute window, if one is free.
+    pub fn try_acquire(&mut self, per_minute: u32) -> bool {
+        let now = Instant::now();
+        while self
+            .window
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= Duration::from_secs(60))
+        {
+            self.window.pop_front();
+        }
+        if self.window.len() >= per_minute as usize {
+            return false;
+        }
+        self.window.push_back(now);
+        true
+    }
+
+    pub fn record(&mut self, record: NetworkRequestRecord) {
+        if self.log.len() == REQUEST_LOG_CAPACITY {
+            self.log.pop_front();
+        }
+        self.log.push_back(record);
+        self.total += 1;
+    }
+
+    /// Most recent requests, oldest first.
+    pub fn recent(&self) -> Vec<NetworkRequestRecord> {
+        self.log.iter().cloned().collect()
+    }
+
+    /// Requests attempted since the plugin loaded.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+}
+
+/// A plugin's outbound request.
+pub struct FetchRequest<'a> {
+    pub url: &'a str,
+    pub method: &'a str,
+    pub headers: &'a [(String, String)],
+    pub body: Option<Vec<u8>>,
+    /// Gives up by this time even if the policy's timeout allows longer,
+    /// e.g. when the plugin call making the request times out.
+    pub deadline: Option<Instant>,
+}
+
+/// Response returned to the plugin.
+pub struct FetchResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Checks `url` against the policy and resolves its host to addresses the
+/// policy allows. Returns the host (if it is a domain) and its addresses.
+pub fn check_target(
+    policy: &NetworkPolicy,
+    url: &reqwest::Url,
+) -> Result<(Option<String>, Vec<SocketAddr>), PluginHostError> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(PluginHostError::NetworkError(format!(
+            "scheme '{}' is not allowed",
+            url.scheme()
+        )));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| PluginHostError::NetworkError("URL has no host".into()))?;
+    if !policy.host_allowed(host) {
+        return Err(PluginHostError::NetworkError(format!(
+            "host '{}' is not in the plugin's network allowlist",
+            host
+        )));
+    }
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    let literal = host.trim_start_matches('[').trim_end_matches(']');
+    let (domain, addrs) = match literal.parse::<IpAddr>() {
+        Ok(ip) => (None, vec![SocketAddr::new(ip, port)]),
+        Err(_) => {
+            let addrs: Vec<SocketAddr> = (host, port)
+                .to_socket_addrs()
+                .map_err(|e| PluginHostError::NetworkError(format!("resolve {host}: {e}")))?
+                .collect();
+            (Some(host.to_string()), addrs)
+        }
+    };
+    if addrs.is_empty() {
+        return Err(PluginHostError::NetworkError(format!("{host} did not resolve")));
+    }
+    if !policy.allow_private
+        && let Some(addr) = addrs.iter().find(|a| !is_public_ip(a.ip()))
+    {
+        return Err(PluginHostError::NetworkError(format!(
+            "{host} resolves to non-public address {}",
+            addr.ip()
+        )));
+    }
+    Ok((domain, addrs))
+}
+
+/// Headers dropped when a redirect leaves the original host.
+const CREDENTIAL_HEADERS: [&str; 3] = ["authorization", "cookie", "proxy-authorization"];
+
+/// Performs a plugin's request under `policy`. Rate limiting and logging
+/// are the caller's job (see [`NetworkActivity`]).
+pub fn fetch(
+    policy: &NetworkPolicy,
+    request: FetchRequest<'_>,
+) -> Result<FetchResponse, PluginHostError> {
+    let mut url = reqwest::Url::parse(request.url)
+        .map_err(|e| PluginHostError::NetworkError(format!("invalid URL: {e}")))?;
+    let mut method = request
+        .method
+        .parse::<reqwest::Method>()
+        .map_err(|e| PluginHostError::NetworkError(format!("invalid HTTP method: {e}")))?;
+    let mut headers = request.headers.to_vec();
+    let mut body = request.body;
+    let mut deadline = Instant::now() + Duration::from_millis(policy.timeout_ms);
+    if let Some(limit) = request.deadline {
+        deadline = deadline.min(limit);
+    }
+
+    let mut redirects = 0;
+    let response = loop {
+        let response = send_pinned(policy, &url, &method, &headers, body.clone(), deadline)?;
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .filter(|_| response.status().is_redirection());
+        let Some(location) = location else {
+            break response;
+        };
+        if redirects == MAX_REDIRECTS {
+            return Err(PluginHostError::NetworkError("too many redirects".into()));
+        }
+        redirects += 1;
+        let next = location
+            .to_str()
+            .ok()
+            .and_then(|location| url.join(location).ok())
+            .ok_or_else(|| PluginHostError::NetworkError("invalid redirect location".into()))?;
+        if !matches!(response.status().as_u16(), 307 | 308) && method != reqwest::Method::HEAD {
+            method = reqwest::Method::GET;
+            body = None;
+        }
+        if next.host_str() != url.host_str()
+            || next.port_or_known_default() != url.port_or_known_default()
+        {
+            headers.retain(|(name, _)| {
+                !CREDENTIAL_HEADERS.contains(&name.to_ascii_lowercase().as_str())
+            });
+        }
+        url = next;
+    };
+    let limit = policy.max_response_bytes;
+    if response.content_length().is_some_and(|len| len > limit as u64) {
+        return Err(PluginHostError::NetworkError(format!(
+            "response exceeds {limit} bytes"
+        )));
+    }
+
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_str().unwrap_or("").to_string()))
+        .collect();
+    let mut body = Vec::new();
+    response
+        .take(limit as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| PluginHostError::NetworkError(format!("failed to read response body: {e}")))?;
+    if body.len() > limit {
+        return Err(PluginHostError::NetworkError(format!(
+            "response exceeds {limit} bytes"
+        )));
+    }
+
+    Ok(FetchResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// Sends one request without following redirects, connecting only to the
+/// addresses [`check_target`] approved for `url`.
+fn send_pinned(
+    policy: &NetworkPolicy,
+    url: &reqwest::Url,
+    method: &reqwest::Method,
+    headers: &[(String, String)],
+    body: Option<Vec<u8>>,
+    deadline: Instant,
+) -> Result<reqwest::blocking::Response, PluginHostError> {
+    let (domain, addrs) = check_target(policy, url)?;
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+        return Err(PluginHostError::NetworkError("request timed out".into()));
+    }
+
+    let mut builder = reqwest::blocking::Client::builder()
+        .timeout(remaining)
+        .user_agent("PrivStack/1.0")
+        .redirect(reqwest::redirect::Policy::none());
+    if let Some(domain) = &domain {
+        // Connect to the addresses that were checked, not a fresh lookup.
+        builder = builder.resolve_to_addrs(domain, &addrs);
+    }
+    let client = builder
+        .build()
+        .map_err(|e| PluginHostError::NetworkError(format!("http client: {e}")))?;
+
+    let mut builder = client.request(method.clone(), url.clone());
+    for (name, value) in headers {
+        builder = builder.header(name, value);
+    }
+    if let Some(body) = body {
+        builder = builder.body(body);
+    }
+    builder
+        .send()
+        .map_err(|e| PluginHostError::NetworkError(format!("HTTP request failed: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(hosts: &[&str]) -> NetworkPolicy {
+        NetworkPolicy::for_hosts(hosts.iter().map(|h| h.to_string()).collect())
+    }
+
+    #[test]
+    fn host_patterns() {
+        let p = policy(&["api.example.com", "*.cdn.net"]);
+        assert!(p.host_allowed("api.example.com"));
+        assert!(p.host_allowed("API.Example.com."));
+        assert!(!p.host_allowed("example.com"));
+        assert!(!p.host_allowed("evilapi.example.com"));
+        assert!(p.host_allowed("img.cdn.net"));
+        assert!(!p.host_allowed("cdn.net"));
+        assert!(!p.host_allowed("evilcdn.net"));
+        assert!(policy(&["*"]).host_allowed("anything.org"));
+        assert!(!policy(&[]).host_allowed("anything.org"));
+    }
+
+    #[test]
+    fn non_public_addresses() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:127.0.0.1",
+            "::127.0.0.1",
+            "::a9fe:a9fe",
+            "64:ff9b::a9fe:a9fe",
+            "64:ff9b:1::1",
+            "2002:a9fe:a9fe::1",
+            "fec0::1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip} should be blocked");
+        }
+        assert!(is_public_ip("93.184.216.34".parse().unwrap()));
+        assert!(is_public_ip("2606:4700::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn default_policy_allows_no_host() {
+        let url = reqwest::Url::parse("https://93.184.216.34/").unwrap();
+        let err = check_target(&NetworkPolicy::default(), &url).unwrap_err();
+        assert!(err.to_string().contains("allowlist"));
+        assert!(check_target(&policy(&["*"]), &url).is_ok());
+    }
+
+    #[test]
+    fn check_target_blocks_private_unless_granted() {
+        let any = policy(&["*"]);
+        let url = reqwest::Url::parse("http://169.254.169.254/latest/meta-data").unwrap();
+        assert!(check_target(&any, &url).is_err());
+
+        let granted = NetworkPolicy {
+            allow_private: true,
+            ..any.clone()
+        };
+        let (domain, addrs) = check_target(&granted, &url).unwrap();
+        assert!(domain.is_none());
+        assert_eq!(addrs[0].port(), 80);
+
+        let ipv6 = reqwest::Url::parse("http://[::1]:8080/").unwrap();
+        assert!(check_target(&any, &ipv6).is_err());
+    }
+
+    /// Serves one canned HTTP response per accepted connection.
+    fn serve(responses: Vec<String>) -> u16 {
+        use std::io::Write;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        port
+    }
+
+    fn get(policy: &NetworkPolicy, url: &str) -> Result<FetchResponse, PluginHostError> {
+        fetch(
+            policy,
+            FetchRequest {
+                url,
+                method: "GET",
+                headers: &[],
+                body: None,
+                deadline: None,
+            },
+        )
+    }
+
+    #[test]
+    fn fetch_gives_up_at_the_request_deadline() {
+        let local = NetworkPolicy {
+            allow_private: true,
+            ..policy(&["127.0.0.1"])
+        };
+        let err = fetch(
+            &local,
+            FetchRequest {
+                url: "http://127.0.0.1:1/",
+                method: "GET",
+                headers: &[],
+                body: None,
+                deadline: Some(Instant::now()),
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err}");
+    }
+
+    #[test]
+    fn redirects_are_followed_and_checked_per_hop() {
+        let local = NetworkPolicy {
+            allow_private: true,
+            ..policy(&["127.0.0.1"])
+        };
+        let port = serve(vec![
+            "HTTP/1.1 302 Found\r\nLocation: /done\r\nContent-Length: 0\r\n\r\n".into(),
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok".into(),
+        ]);
+        let response = get(&local, &format!("http://127.0.0.1:{port}/start")).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"ok");
+
+        // A hop to a host outside the allowlist is refused.
+        let port = serve(vec![
+            "HTTP/1.1 302 Found\r\nLocation: http://localhost:1/\r\nContent-Length: 0\r\n\r\n"
+                .into(),
+        ]);
+        let err = get(&local, &format!("http://127.0.0.1:{port}/")).unwrap_err();
+        assert!(err.to_string().contains("allowlist"), "{err}");
+    }
+
+    #[test]
+    fn check_target_enforces_scheme_and_allowlist() {
+        let p = policy(&["example.com"]);
+        let file = reqwest::Url::parse("file:///etc/passwd").unwrap();
+        assert!(check_target(&p, &file).is_err());
+        let other = reqwest::Url::parse("https://127.0.0.1/").unwrap();
+        assert!(check_target(&p, &other).is_err());
+    }
+
+    #[test]
+    fn rate_limit_window() {
+        let mut activity = NetworkActivity::default();
+        assert!(activity.try_acquire(2));
+        assert!(activity.try_acquire(2));
+        assert!(!activity.try_acquire(2));
+    }
+
+    #[test]
+    fn request_log_is_bounded() {
+        let mut activity = NetworkActivity::default();
+        for i in 0..(REQUEST_LOG_CAPACITY + 5) {
+            activity.record(NetworkRequestRecord {
+                method: "GET".into(),
+                url: format!("https://example.com/{i}"),
+                status: Some(200),
+                bytes: 0,
+                error: None,
+                timestamp: 0,
+            });
+        }
+        let recent = activity.recent();
+        assert_eq!(recent.len(), REQUEST_LOG_CAPACITY);
+        assert_eq!(recent[0].url, "https://example.com/5");
+        assert_eq!(activity.total(), (REQUEST_LOG_CAPACITY + 5) as u64);
+    }
+}
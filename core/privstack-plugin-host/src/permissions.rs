@@ -1,79 +1,13 @@
 //! Capability-based permission model for plugin sandboxes.
 //!
-//! Four tiers:
-//! - Tier 1: Always granted (sdk, settings, logger, navigation)
-//! - Tier 2: Just-in-time prompted (linking, dialogs, vault)
-//! - Tier 3: Install-time reviewed (filesystem, network, agent)
-//! - Tier 4: Enterprise policy overrides
+//! The [`Permission`] vocabulary and its tiers live in `privstack-ppk`, so
+//! that manifests declare exactly what the host enforces. This module holds
+//! a plugin's granted set and the install-time consent summary.
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
-/// Individual permission a plugin may hold.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[serde(rename_all = "kebab-case")]
-pub enum Permission {
-    // Tier 1 — always granted
-    Sdk,
-    Settings,
-    Logger,
-    Navigation,
-    StateNotify,
-
-    // Tier 2 — just-in-time
-    Linking,
-    Dialogs,
-    Vault,
-    CrossEntityRead,
-    CrossPluginCommand,
-
-    // Tier 3 — install-time
-    Filesystem,
-    Network,
-    Agent,
-}
-
-impl Permission {
-    /// Returns the tier for this permission.
-    pub fn tier(&self) -> PermissionTier {
-        match self {
-            Self::Sdk | Self::Settings | Self::Logger | Self::Navigation | Self::StateNotify => {
-                PermissionTier::AlwaysGranted
-            }
-            Self::Linking | Self::Dialogs | Self::Vault | Self::CrossEntityRead | Self::CrossPluginCommand => {
-                PermissionTier::JustInTime
-            }
-            Self::Filesystem | Self::Network | Self::Agent => PermissionTier::InstallTime,
-        }
-    }
-
-    /// Returns the WIT interface name this permission gates.
-    pub fn interface_name(&self) -> &'static str {
-        match self {
-            Self::Sdk => "sdk",
-            Self::Settings => "settings",
-            Self::Logger => "logger",
-            Self::Navigation => "navigation",
-            Self::StateNotify => "state-notify",
-            Self::Linking => "linking",
-            Self::Dialogs => "dialogs",
-            Self::Vault => "vault",
-            Self::CrossEntityRead => "cross-entity-read",
-            Self::CrossPluginCommand => "cross-plugin-command",
-            Self::Filesystem => "filesystem",
-            Self::Network => "network",
-            Self::Agent => "agent",
-        }
-    }
-}
-
-/// Permission tier classification.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum PermissionTier {
-    AlwaysGranted,
-    JustInTime,
-    InstallTime,
-}
+pub use privstack_ppk::{Permission, PermissionTier};
 
 /// Set of permissions granted to a plugin.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -109,31 +43,32 @@ impl PermissionSet {
 
     /// Creates a set with all permissions granted (for testing).
     pub fn all_granted() -> Self {
-        let granted: HashSet<Permission> = [
-            Permission::Sdk,
-            Permission::Settings,
-            Permission::Logger,
-            Permission::Navigation,
-            Permission::StateNotify,
-            Permission::Linking,
-            Permission::Dialogs,
-            Permission::Vault,
-            Permission::CrossEntityRead,
-            Permission::CrossPluginCommand,
-            Permission::Filesystem,
-            Permission::Network,
-            Permission::Agent,
-        ]
-        .into_iter()
-        .collect();
-
         Self {
-            granted,
+            granted: Permission::ALL.into_iter().collect(),
             denied: HashSet::new(),
             pending_jit: HashSet::new(),
         }
     }
 
+    /// Creates the set for a plugin whose manifest declares `declared`.
+    /// Tier 1 is granted, declared Tier 3 permissions were approved by
+    /// installing and declared Tier 2 permissions wait for a JIT prompt.
+    /// Everything else is denied, so the plugin is never prompted for it.
+    pub fn from_declared(declared: &[Permission]) -> Self {
+        let mut set = Self::default_first_party();
+        for permission in Permission::ALL {
+            match permission.tier() {
+                PermissionTier::AlwaysGranted => {}
+                _ if !declared.contains(&permission) => set.deny(permission),
+                PermissionTier::JustInTime => {
+                    set.pending_jit.insert(permission);
+                }
+                PermissionTier::InstallTime => set.grant(permission),
+            }
+        }
+        set
+    }
+
     pub fn is_granted(&self, permission: Permission) -> bool {
         self.granted.contains(&permission)
     }
@@ -167,6 +102,59 @@ impl PermissionSet {
     }
 }
 
+/// A permission as shown in the install prompt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConsentItem {
+    pub permission: Permission,
+    pub description: &'static str,
+}
+
+impl From<Permission> for ConsentItem {
+    fn from(permission: Permission) -> Self {
+        Self {
+            permission,
+            description: permission.description(),
+        }
+    }
+}
+
+/// What installing a plugin grants it, grouped by tier.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConsentSummary {
+    /// Tier 1: held by every plugin, declared or not.
+    pub always_granted: Vec<ConsentItem>,
+    /// Tier 2: asked for the first time the plugin needs them.
+    pub just_in_time: Vec<ConsentItem>,
+    /// Tier 3: approved by installing the plugin.
+    pub install_time: Vec<ConsentItem>,
+    /// Declared, but denied by enterprise policy; never granted.
+    pub denied_by_policy: Vec<ConsentItem>,
+}
+
+impl ConsentSummary {
+    /// Groups the permissions a manifest declares, in tier order.
+    pub fn new(declared: &[Permission], denied_by_policy: impl Fn(Permission) -> bool) -> Self {
+        let mut summary = Self::default();
+        for permission in Permission::ALL {
+            let is_declared = declared.contains(&permission);
+            let group = match permission.tier() {
+                _ if denied_by_policy(permission) => {
+                    if !is_declared {
+                        continue;
+                    }
+                    &mut summary.denied_by_policy
+                }
+                PermissionTier::AlwaysGranted => &mut summary.always_granted,
+                _ if !is_declared => continue,
+                PermissionTier::JustInTime => &mut summary.just_in_time,
+                PermissionTier::InstallTime => &mut summary.install_time,
+            };
+            group.push(permission.into());
+        }
+        summary
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -215,58 +203,44 @@ mod tests {
         assert!(perms.is_granted(Permission::Filesystem));
     }
 
-    #[test]
-    fn permission_tiers() {
-        assert_eq!(Permission::Sdk.tier(), PermissionTier::AlwaysGranted);
-        assert_eq!(Permission::Vault.tier(), PermissionTier::JustInTime);
-        assert_eq!(Permission::Agent.tier(), PermissionTier::InstallTime);
-    }
-
-    // ================================================================
-    // interface_name for all permissions
-    // ================================================================
-
-    #[test]
-    fn all_permission_interface_names() {
-        assert_eq!(Permission::Sdk.interface_name(), "sdk");
-        assert_eq!(Permission::Settings.interface_name(), "settings");
-        assert_eq!(Permission::Logger.interface_name(), "logger");
-        assert_eq!(Permission::Navigation.interface_name(), "navigation");
-        assert_eq!(Permission::StateNotify.interface_name(), "state-notify");
-        assert_eq!(Permission::Linking.interface_name(), "linking");
-        assert_eq!(Permission::Dialogs.interface_name(), "dialogs");
-        assert_eq!(Permission::Vault.interface_name(), "vault");
-        assert_eq!(Permission::CrossEntityRead.interface_name(), "cross-entity-read");
-        assert_eq!(Permission::CrossPluginCommand.interface_name(), "cross-plugin-command");
-        assert_eq!(Permission::Filesystem.interface_name(), "filesystem");
-        assert_eq!(Permission::Network.interface_name(), "network");
-        assert_eq!(Permission::Agent.interface_name(), "agent");
-    }
-
     // ================================================================
-    // All tier classifications
+    // Manifest-declared permissions
     // ================================================================
 
     #[test]
-    fn all_tier1_permissions() {
-        assert_eq!(Permission::Settings.tier(), PermissionTier::AlwaysGranted);
-        assert_eq!(Permission::Logger.tier(), PermissionTier::AlwaysGranted);
-        assert_eq!(Permission::Navigation.tier(), PermissionTier::AlwaysGranted);
-        assert_eq!(Permission::StateNotify.tier(), PermissionTier::AlwaysGranted);
-    }
+    fn from_declared_grants_only_what_manifest_declares() {
+        let perms = PermissionSet::from_declared(&[Permission::Vault, Permission::Network]);
+        assert!(perms.is_granted(Permission::Sdk));
+        assert!(perms.is_granted(Permission::Network));
+        assert!(!perms.is_granted(Permission::Vault));
+        assert!(perms.needs_jit_prompt(Permission::Vault));
 
-    #[test]
-    fn all_tier2_permissions() {
-        assert_eq!(Permission::Linking.tier(), PermissionTier::JustInTime);
-        assert_eq!(Permission::Dialogs.tier(), PermissionTier::JustInTime);
-        assert_eq!(Permission::CrossEntityRead.tier(), PermissionTier::JustInTime);
-        assert_eq!(Permission::CrossPluginCommand.tier(), PermissionTier::JustInTime);
+        assert!(perms.is_denied(Permission::Linking));
+        assert!(!perms.needs_jit_prompt(Permission::Linking));
+        assert!(perms.is_denied(Permission::Filesystem));
+        assert_eq!(perms.granted_permissions().len(), 6);
     }
 
     #[test]
-    fn all_tier3_permissions() {
-        assert_eq!(Permission::Filesystem.tier(), PermissionTier::InstallTime);
-        assert_eq!(Permission::Network.tier(), PermissionTier::InstallTime);
+    fn consent_summary_groups_by_tier() {
+        let declared = [
+            Permission::Agent,
+            Permission::Vault,
+            Permission::Network,
+            Permission::EventSubscribe,
+        ];
+        let summary = ConsentSummary::new(&declared, |p| p == Permission::Network);
+        let names = |items: &[ConsentItem]| -> Vec<Permission> {
+            items.iter().map(|item| item.permission).collect()
+        };
+        assert_eq!(summary.always_granted.len(), 5);
+        assert_eq!(
+            names(&summary.just_in_time),
+            vec![Permission::Vault, Permission::EventSubscribe]
+        );
+        assert_eq!(names(&summary.install_time), vec![Permission::Agent]);
+        assert_eq!(names(&summary.denied_by_policy), vec![Permission::Network]);
+        assert_eq!(summary.install_time[0].description, Permission::Agent.description());
     }
 
     // ================================================================
@@ -292,7 +266,7 @@ mod tests {
         assert!(granted.contains(&Permission::Sdk));
         assert!(granted.contains(&Permission::Vault));
         assert!(granted.contains(&Permission::Agent));
-        assert_eq!(granted.len(), 13);
+        assert_eq!(granted.len(), 14);
     }
 
     #[test]
@@ -341,11 +315,4 @@ mod tests {
             perms.granted_permissions().len()
         );
     }
-
-    #[test]
-    fn permission_debug() {
-        let perm = Permission::Vault;
-        let debug_str = format!("{:?}", perm);
-        assert!(debug_str.contains("Vault"));
-    }
 }
@@ -0,0 +1,274 @@
+//! Persistent, per-plugin settings.
+//!
+//! Each setting is an entity of type [`SETTINGS_ENTITY_TYPE`] in the shared
+//! entity store, so values are encrypted at rest like any other entity and
+//! survive restarts. Settings are local-only by default. The shell can mark a
+//! setting for sync, and it then replicates like any other entity through
+//! snapshot events. Its entity id is derived from the plugin id and key, so
+//! every device writes the same entity.
+//!
+//! Entity types starting with [`RESERVED_ENTITY_TYPE_PREFIX`] belong to the
+//! host: plugins cannot declare them and agents cannot read them.
+
+use crate::error::PluginHostError;
+use privstack_model::Entity;
+use privstack_storage::EntityStore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Entity type under which plugin settings are stored.
+pub const SETTINGS_ENTITY_TYPE: &str = "__plugin_setting";
+
+/// Prefix of entity types owned by the host.
+pub const RESERVED_ENTITY_TYPE_PREFIX: &str = "__";
+
+/// Returns true for entity types plugins may not declare or read.
+pub fn is_reserved_entity_type(entity_type: &str) -> bool {
+    entity_type.starts_with(RESERVED_ENTITY_TYPE_PREFIX)
+}
+
+/// Entity id of a plugin's setting: a UUID derived from the plugin id and
+/// key, the same on every device.
+pub fn setting_entity_id(plugin_id: &str, key: &str) -> String {
+    let digest = Sha256::digest(format!("privstack-plugin-setting\0{plugin_id}\0{key}"));
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Uuid::from_bytes(bytes).to_string()
+}
+
+/// Returns true for a setting entity that must stay on this device.
+pub fn is_local_only_setting(entity: &Entity) -> bool {
+    entity.entity_type == SETTINGS_ENTITY_TYPE
+        && PluginSetting::from_entity(entity).is_none_or(|s| !s.sync)
+}
+
+/// A stored plugin setting.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginSetting {
+    pub key: String,
+    pub value: String,
+    /// Whether the setting replicates to the user's other devices.
+    pub sync: bool,
+    pub modified_at: i64,
+}
+
+impl PluginSetting {
+    /// Bytes counted against the plugin's settings quota.
+    pub fn size(&self) -> usize {
+        self.key.len() + self.value.len()
+    }
+
+    fn from_entity(entity: &Entity) -> Option<Self> {
+        Some(Self {
+            key: entity.data.get("key")?.as_str()?.to_string(),
+            value: entity.data.get("value")?.as_str()?.to_string(),
+            sync: !entity
+                .data
+                .get("local_only")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true),
+            modified_at: entity.modified_at,
+        })
+    }
+}
+
+/// Settings of one plugin, backed by the entity store.
+pub struct PluginSettings<'a> {
+    entity_store: &'a EntityStore,
+    plugin_id: &'a str,
+    quota_bytes: usize,
+}
+
+impl<'a> PluginSettings<'a> {
+    pub fn new(entity_store: &'a EntityStore, plugin_id: &'a str, quota_bytes: usize) -> Self {
+        Self {
+            entity_store,
+            plugin_id,
+            quota_bytes,
+        }
+    }
+
+    /// Maximum total size of this plugin's keys and values.
+    pub fn quota_bytes(&self) -> usize {
+        self.quota_bytes
+    }
+
+    /// Returns the stored setting for `key`.
+    pub fn get(&self, key: &str) -> Result<Option<PluginSetting>, PluginHostError> {
+        let entity = self
+            .entity_store
+            .get_entity(&self.entity_id(key))
+            .map_err(|e| PluginHostError::Storage(e.to_string()))?;
+        Ok(entity.as_ref().and_then(PluginSetting::from_entity))
+    }
+
+    /// Stores `value` under `key`, keeping the key's current sync flag
+    /// (local-only for new keys).
+    pub fn set(&self, key: &str, value: &str) -> Result<(), PluginHostError> {
+        let sync = self.get(key)?.is_some_and(|s| s.sync);
+        self.set_with_sync(key, value, sync)
+    }
+
+    /// Stores `value` under `key` with an explicit sync flag. Recording the
+    /// sync event for a synced setting is up to the caller.
+    pub fn set_with_sync(&self, key: &str, value: &str, sync: bool) -> Result<(), PluginHostError> {
+        let settings = self.list()?;
+        let existing = settings.iter().find(|s| s.key == key);
+        let usage: usize = settings.iter().map(PluginSetting::size).sum();
+        let new_usage = usage - existing.map_or(0, PluginSetting::size) + key.len() + value.len();
+        if new_usage > self.quota_bytes {
+            return Err(PluginHostError::ResourceLimitExceeded {
+                plugin_id: self.plugin_id.to_string(),
+                detail: format!(
+                    "settings quota of {} bytes exceeded ({} bytes)",
+                    self.quota_bytes, new_usage
+                ),
+            });
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let entity = Entity {
+            id: self.entity_id(key),
+            entity_type: SETTINGS_ENTITY_TYPE.to_string(),
+            data: serde_json::json!({
+                "plugin_id": self.plugin_id,
+                "key": key,
+                "value": value,
+                "local_only": !sync,
+            }),
+            created_at: now,
+            modified_at: now,
+            created_by: self.plugin_id.to_string(),
+        };
+        self.entity_store
+            .save_entity_raw(&entity)
+            .map_err(|e| PluginHostError::Storage(e.to_string()))
+    }
+
+    /// Deletes `key`. Missing keys are not an error.
+    pub fn remove(&self, key: &str) -> Result<(), PluginHostError> {
+        self.entity_store
+            .delete_entity(&self.entity_id(key))
+            .map_err(|e| PluginHostError::Storage(e.to_string()))
+    }
+
+    /// Returns every setting of the plugin, sorted by key.
+    pub fn list(&self) -> Result<Vec<PluginSetting>, PluginHostError> {
+        let filters = [(
+            "plugin_id".to_string(),
+            serde_json::Value::String(self.plugin_id.to_string()),
+        )];
+        let entities = self
+            .entity_store
+            .query_entities(SETTINGS_ENTITY_TYPE, &filters, false, None)
+            .map_err(|e| PluginHostError::Storage(e.to_string()))?;
+        let mut settings: Vec<PluginSetting> =
+            entities.iter().filter_map(PluginSetting::from_entity).collect();
+        settings.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(settings)
+    }
+
+    /// Total bytes used against the quota.
+    pub fn usage_bytes(&self) -> Result<usize, PluginHostError> {
+        Ok(self.list()?.iter().map(PluginSetting::size).sum())
+    }
+
+    /// Deletes every setting of the plugin. Returns how many were removed.
+    pub fn clear(&self) -> Result<usize, PluginHostError> {
+        let settings = self.list()?;
+        for setting in &settings {
+            self.remove(&setting.key)?;
+        }
+        Ok(settings.len())
+    }
+
+    /// Entity id of `key`'s setting (see [`setting_entity_id`]).
+    pub fn entity_id(&self, key: &str) -> String {
+        setting_entity_id(self.plugin_id, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> EntityStore {
+        EntityStore::open_in_memory().unwrap()
+    }
+
+    #[test]
+    fn set_get_remove_roundtrip() {
+        let store = store();
+        let settings = PluginSettings::new(&store, "p1", 1024);
+        assert!(settings.get("theme").unwrap().is_none());
+
+        settings.set("theme", "dark").unwrap();
+        let stored = settings.get("theme").unwrap().unwrap();
+        assert_eq!(stored.value, "dark");
+        assert!(!stored.sync);
+
+        settings.remove("theme").unwrap();
+        assert!(settings.get("theme").unwrap().is_none());
+    }
+
+    #[test]
+    fn settings_are_scoped_per_plugin() {
+        let store = store();
+        PluginSettings::new(&store, "p1", 1024).set("k", "one").unwrap();
+        PluginSettings::new(&store, "p2", 1024).set("k", "two").unwrap();
+
+        let p1 = PluginSettings::new(&store, "p1", 1024);
+        assert_eq!(p1.list().unwrap().len(), 1);
+        assert_eq!(p1.get("k").unwrap().unwrap().value, "one");
+
+        assert_eq!(p1.clear().unwrap(), 1);
+        assert!(p1.list().unwrap().is_empty());
+        let p2 = PluginSettings::new(&store, "p2", 1024);
+        assert_eq!(p2.get("k").unwrap().unwrap().value, "two");
+    }
+
+    #[test]
+    fn set_keeps_sync_flag() {
+        let store = store();
+        let settings = PluginSettings::new(&store, "p1", 1024);
+        settings.set_with_sync("k", "v1", true).unwrap();
+        settings.set("k", "v2").unwrap();
+        let stored = settings.get("k").unwrap().unwrap();
+        assert_eq!(stored.value, "v2");
+        assert!(stored.sync);
+
+        let entity = store.get_entity(&settings.entity_id("k")).unwrap().unwrap();
+        assert!(!is_local_only_setting(&entity));
+        settings.set_with_sync("k", "v3", false).unwrap();
+        let entity = store.get_entity(&settings.entity_id("k")).unwrap().unwrap();
+        assert!(is_local_only_setting(&entity));
+    }
+
+    #[test]
+    fn setting_ids_are_stable_entity_ids() {
+        let id = setting_entity_id("p1", "k");
+        assert_eq!(id, setting_entity_id("p1", "k"));
+        assert_ne!(id, setting_entity_id("p1", "k2"));
+        assert_ne!(id, setting_entity_id("p2", "k"));
+        assert!(uuid::Uuid::parse_str(&id).is_ok());
+    }
+
+    #[test]
+    fn quota_counts_replaced_value_once() {
+        let store = store();
+        let settings = PluginSettings::new(&store, "p1", 10);
+        settings.set("k", "12345").unwrap();
+        settings.set("k", "123456789").unwrap();
+        assert_eq!(settings.usage_bytes().unwrap(), 10);
+
+        let err = settings.set("x", "1").unwrap_err();
+        assert!(matches!(err, PluginHostError::ResourceLimitExceeded { .. }));
+        assert_eq!(settings.get("k").unwrap().unwrap().value, "123456789");
+    }
+
+    #[test]
+    fn reserved_entity_types() {
+        assert!(is_reserved_entity_type(SETTINGS_ENTITY_TYPE));
+        assert!(!is_reserved_entity_type("note"));
+    }
+}
@@ -1,12 +1,17 @@
 //! Enterprise policy engine — reads `~/.privstack/policy.toml` and enforces
 //! admin-managed plugin allowlists, permission overrides, and audit settings.
+//! Security-relevant plugin actions are recorded in its [`PluginAuditLog`].
 
 use crate::permissions::Permission;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use tracing::{info, warn};
 
+/// Entries kept in the plugin audit log when the policy sets no `max_entries`.
+const DEFAULT_AUDIT_CAPACITY: usize = 1_000;
+
 /// Enterprise policy mode.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -24,10 +29,17 @@ pub enum PolicyMode {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditConfig {
     pub enabled: bool,
+    /// `json`/`jsonl`, `cef`, or `syslog`.
     #[serde(default = "default_audit_format")]
     pub export_format: String,
     #[serde(default = "default_audit_path")]
     pub export_path: String,
+    /// Prune audit entries older than this many days. `None` keeps them forever.
+    #[serde(default)]
+    pub retention_days: Option<u32>,
+    /// Keep at most this many audit entries. `None` means no limit.
+    #[serde(default)]
+    pub max_entries: Option<usize>,
 }
 
 fn default_audit_format() -> String {
@@ -44,6 +56,8 @@ impl Default for AuditConfig {
             enabled: false,
             export_format: default_audit_format(),
             export_path: default_audit_path(),
+            retention_days: None,
+            max_entries: None,
         }
     }
 }
@@ -55,6 +69,8 @@ pub struct PolicyConfig {
     pub mode: PolicyMode,
     #[serde(default)]
     pub allowed_plugin_ids: Vec<String>,
+    /// Hex Ed25519 publisher keys. Packages they sign are allowed, and the
+    /// shell trusts them as publishers when installing `.ppk` files.
     #[serde(default)]
     pub allowed_signing_keys: Vec<String>,
     #[serde(default)]
@@ -79,10 +95,100 @@ impl Default for PolicyConfig {
     }
 }
 
+/// One audited plugin action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginAuditEntry {
+    pub plugin_id: String,
+    /// What was attempted, e.g. `event-subscribe`.
+    pub action: String,
+    /// What it was attempted on, e.g. `privstack.tasks/task-completed`.
+    pub target: String,
+    pub allowed: bool,
+    /// Why it was refused, if it was.
+    pub reason: Option<String>,
+    /// Unix timestamp (seconds).
+    pub timestamp: i64,
+}
+
+/// In-memory log of audited plugin actions, oldest first. Shared with the
+/// components that perform the actions; entries are also emitted on the
+/// `privstack::audit` tracing target.
+pub struct PluginAuditLog {
+    capacity: usize,
+    /// Entries older than this many seconds are dropped.
+    max_age_secs: Option<i64>,
+    entries: Mutex<VecDeque<PluginAuditEntry>>,
+}
+
+impl PluginAuditLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            max_age_secs: None,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// Drops entries older than `retention_days` (the policy's
+    /// `[policy.audit] retention_days`). `None` keeps them until evicted by capacity.
+    pub fn with_retention_days(mut self, retention_days: Option<u32>) -> Self {
+        self.max_age_secs = retention_days.map(|d| i64::from(d) * 24 * 60 * 60);
+        self
+    }
+
+    /// Removes entries that are past retention at `now` (Unix seconds).
+    /// Returns the number removed.
+    pub fn prune_expired(&self, now: i64) -> usize {
+        let Some(max_age) = self.max_age_secs else {
+            return 0;
+        };
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        // Entries are appended in time order, so expired ones form a prefix.
+        while entries.front().is_some_and(|e| e.timestamp < now - max_age) {
+            entries.pop_front();
+        }
+        before - entries.len()
+    }
+
+    /// Records an action, dropping the oldest entry when full.
+    pub fn record(&self, plugin_id: &str, action: &str, target: &str, refused: Option<String>) {
+        info!(
+            target: "privstack::audit",
+            plugin_id = %plugin_id,
+            action = %action,
+            target_name = %target,
+            allowed = refused.is_none(),
+            reason = ?refused,
+            "Plugin action audited"
+        );
+        let now = chrono::Utc::now().timestamp();
+        self.prune_expired(now);
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(PluginAuditEntry {
+            plugin_id: plugin_id.to_string(),
+            action: action.to_string(),
+            target: target.to_string(),
+            allowed: refused.is_none(),
+            reason: refused,
+            timestamp: now,
+        });
+    }
+
+    pub fn entries(&self) -> Vec<PluginAuditEntry> {
+        self.prune_expired(chrono::Utc::now().timestamp());
+        self.entries.lock().unwrap().iter().cloned().collect()
+    }
+}
+
 /// Enforces enterprise policy decisions.
 pub struct PolicyEngine {
     config: PolicyConfig,
     policy_path: Option<PathBuf>,
+    audit_log: Arc<PluginAuditLog>,
 }
 
 impl PolicyEngine {
@@ -96,50 +202,47 @@ impl PolicyEngine {
     pub fn load_from(policy_path: PathBuf) -> Self {
         if !policy_path.exists() {
             info!("No policy file found at {:?}, running unrestricted", policy_path);
-            return Self {
-                config: PolicyConfig::default(),
-                policy_path: None,
-            };
+            return Self::with_config(PolicyConfig::default());
         }
 
         match std::fs::read_to_string(&policy_path) {
             Ok(contents) => match toml::from_str::<PolicyFile>(&contents) {
                 Ok(file) => {
                     info!("Loaded enterprise policy from {:?}", policy_path);
-                    Self {
-                        config: file.into_config(),
-                        policy_path: Some(policy_path),
-                    }
+                    Self::with_config(file.into_config()).at_path(policy_path)
                 }
                 Err(e) => {
                     warn!(
                         "Failed to parse policy file {:?}: {}. Falling back to unrestricted mode.",
                         policy_path, e
                     );
-                    Self {
-                        config: PolicyConfig::default(),
-                        policy_path: Some(policy_path),
-                    }
+                    Self::with_config(PolicyConfig::default()).at_path(policy_path)
                 }
             },
             Err(e) => {
                 warn!("Failed to read policy file {:?}: {}", policy_path, e);
-                Self {
-                    config: PolicyConfig::default(),
-                    policy_path: Some(policy_path),
-                }
+                Self::with_config(PolicyConfig::default()).at_path(policy_path)
             }
         }
     }
 
     /// Creates a policy engine with explicit config (for testing).
     pub fn with_config(config: PolicyConfig) -> Self {
+        let capacity = config.audit.max_entries.unwrap_or(DEFAULT_AUDIT_CAPACITY);
+        let audit_log =
+            PluginAuditLog::new(capacity).with_retention_days(config.audit.retention_days);
         Self {
             config,
             policy_path: None,
+            audit_log: Arc::new(audit_log),
         }
     }
 
+    fn at_path(mut self, policy_path: PathBuf) -> Self {
+        self.policy_path = Some(policy_path);
+        self
+    }
+
     /// Check if a plugin is allowed to be installed.
     pub fn is_plugin_allowed(&self, plugin_id: &str, signing_key: Option<&str>) -> bool {
         match self.config.mode {
@@ -176,6 +279,11 @@ impl PolicyEngine {
         self.policy_path.is_some()
     }
 
+    /// The log of audited plugin actions.
+    pub fn audit_log(&self) -> &Arc<PluginAuditLog> {
+        &self.audit_log
+    }
+
     /// Returns the active policy config.
     pub fn config(&self) -> &PolicyConfig {
         &self.config
@@ -223,6 +331,8 @@ struct DeniedPermissions {
     linking: bool,
     #[serde(default)]
     dialogs: bool,
+    #[serde(default, rename = "event-subscribe")]
+    event_subscribe: bool,
 }
 
 impl PolicyFile {
@@ -246,6 +356,9 @@ impl PolicyFile {
         if self.policy.denied_permissions.dialogs {
             denied.insert("dialogs".to_string());
         }
+        if self.policy.denied_permissions.event_subscribe {
+            denied.insert("event-subscribe".to_string());
+        }
 
         PolicyConfig {
             mode: self.policy.mode,
@@ -509,6 +622,22 @@ export_path = "/var/log/privstack"
         assert!(audit.enabled);
         assert_eq!(audit.export_format, "csv");
         assert_eq!(audit.export_path, "/var/log/privstack");
+        assert_eq!(audit.retention_days, None);
+    }
+
+    #[test]
+    fn load_audit_retention_from_file() {
+        let engine = load_policy_from_str(r#"
+[policy.audit]
+enabled = true
+export_format = "cef"
+retention_days = 90
+max_entries = 100000
+"#);
+        let audit = engine.audit_config();
+        assert_eq!(audit.export_format, "cef");
+        assert_eq!(audit.retention_days, Some(90));
+        assert_eq!(audit.max_entries, Some(100_000));
     }
 
     #[test]
@@ -560,6 +689,7 @@ export_path = "/var/log/privstack"
                 enabled: true,
                 export_format: "csv".to_string(),
                 export_path: "/custom/path".to_string(),
+                ..Default::default()
             },
             ..Default::default()
         };
@@ -609,6 +739,7 @@ vault = true
 agent = true
 linking = true
 dialogs = true
+event-subscribe = true
 "#;
         let file: PolicyFile = toml::from_str(toml_str).unwrap();
         let config = file.into_config();
@@ -620,6 +751,7 @@ dialogs = true
         assert!(engine.is_permission_denied_by_policy(Permission::Agent));
         assert!(engine.is_permission_denied_by_policy(Permission::Linking));
         assert!(engine.is_permission_denied_by_policy(Permission::Dialogs));
+        assert!(engine.is_permission_denied_by_policy(Permission::EventSubscribe));
         // Tier 1 permissions are not deniable via policy
         assert!(!engine.is_permission_denied_by_policy(Permission::Sdk));
     }
@@ -649,4 +781,49 @@ dialogs = true
         let config = file.into_config();
         assert_eq!(config.mode, PolicyMode::Allowlist);
     }
+
+    // ================================================================
+    // Plugin audit log
+    // ================================================================
+
+    #[test]
+    fn audit_log_is_bounded_by_max_entries() {
+        let config = PolicyConfig {
+            audit: AuditConfig {
+                max_entries: Some(2),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let engine = PolicyEngine::with_config(config);
+        let log = engine.audit_log();
+        log.record("a.plugin", "event-subscribe", "b.plugin/x", None);
+        log.record("a.plugin", "event-subscribe", "b.plugin/y", Some("denied".into()));
+        log.record("a.plugin", "event-subscribe", "b.plugin/z", None);
+
+        let entries = log.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].target, "b.plugin/y");
+        assert!(!entries[0].allowed);
+        assert!(entries[1].allowed);
+    }
+
+    #[test]
+    fn audit_log_drops_entries_past_retention() {
+        let config = PolicyConfig {
+            audit: AuditConfig {
+                retention_days: Some(30),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let engine = PolicyEngine::with_config(config);
+        let log = engine.audit_log();
+        log.record("a.plugin", "event-subscribe", "b.plugin/x", None);
+
+        let now = chrono::Utc::now().timestamp();
+        assert_eq!(log.prune_expired(now + 29 * 24 * 60 * 60), 0);
+        assert_eq!(log.prune_expired(now + 31 * 24 * 60 * 60), 1);
+        assert!(log.entries().is_empty());
+    }
 }
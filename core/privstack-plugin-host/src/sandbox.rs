@@ -3,25 +3,45 @@
 //! Each `PluginSandbox` owns a `wasmtime::Store` with:
 //! - Memory isolation (configurable ceiling)
 //! - CPU fuel budgets (prevents infinite loops)
+//! - Wall-clock deadlines per call (epoch interruption)
 //! - Entity-type scoping (plugin can only CRUD its declared types)
 //! - Permission-gated host function access
 //!
 //! The sandbox compiles and instantiates a .wasm component, wiring up
 //! all host imports and detecting optional capability exports.
 
+use crate::bindings::agent_world::AgentPluginWorld;
 use crate::bindings::PluginWorld;
+use crate::bindings::entity_observer_exports::EntityObserverExports;
+use crate::bindings::event_subscriber_exports::EventSubscriberExports;
+use crate::bindings::scheduled_task_exports::ScheduledTaskExports;
+use crate::entity_changes::{
+    bind_entity_observer, call_on_entities_changed, EntityChange, EntityChangeKind,
+    EntityChangeQueue,
+};
+use crate::entity_handler::WasmEntityHandler;
+use crate::event_bus::{bind_event_subscriber, call_on_events, BusEvent, EventBus};
 use crate::error::PluginHostError;
+use crate::filesystem::{
+    build_wasi_ctx, selection_grant, selection_staging_root, DirectoryGrant, SELECTION_ROOT,
+};
+use crate::net_policy::{
+    self, FetchRequest, FetchResponse, NetworkActivity, NetworkPolicy, NetworkRequestRecord,
+};
 use crate::permissions::{Permission, PermissionSet};
+use crate::plugin_settings::{is_reserved_entity_type, PluginSettings, SETTINGS_ENTITY_TYPE};
+use crate::scheduler::{bind_scheduled_task, call_on_scheduled, JobSchedule, PluginJobs};
 use crate::wit_types::*;
 use serde::Serialize;
 use sha2::{Digest, Sha256};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 use wasmtime::component::{Component, Linker};
-use wasmtime::{Engine, ResourceLimiter, Store};
+use wasmtime::{Engine, ResourceLimiter, Store, Trap};
 use wasmtime::component::ResourceTable;
 use wasmtime_wasi::p2::{IoView, WasiCtx, WasiCtxBuilder, WasiView};
 
@@ -49,6 +69,10 @@ pub struct PluginResourceMetrics {
     pub entity_count: usize,
     /// Estimated disk usage in bytes for plugin entities.
     pub disk_usage_bytes: usize,
+    /// Outbound HTTP requests attempted since the plugin loaded.
+    pub network_requests_total: u64,
+    /// Most recent outbound HTTP requests, oldest first.
+    pub network_recent_requests: Vec<NetworkRequestRecord>,
 }
 
 /// Resource limits for a plugin sandbox.
@@ -62,6 +86,8 @@ pub struct ResourceLimits {
     pub call_timeout_ms: u64,
     /// Shutdown deadline in milliseconds for `dispose()`.
     pub shutdown_deadline_ms: u64,
+    /// Quota for persisted settings (keys plus values) in bytes.
+    pub max_settings_bytes: usize,
 }
 
 impl ResourceLimits {
@@ -71,6 +97,7 @@ impl ResourceLimits {
             fuel_per_call: 1_000_000_000,         // ~1 billion instructions
             call_timeout_ms: 5_000,
             shutdown_deadline_ms: 2_000,
+            max_settings_bytes: 1024 * 1024, // 1MB
         }
     }
 
@@ -80,10 +107,88 @@ impl ResourceLimits {
             fuel_per_call: 500_000_000,
             call_timeout_ms: 3_000,
             shutdown_deadline_ms: 2_000,
+            max_settings_bytes: 256 * 1024, // 256KB
         }
     }
 }
 
+/// How often a plugin engine's epoch advances. Call deadlines are rounded
+/// up to whole ticks.
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Creates a Wasmtime engine configured for plugin sandboxing: component
+/// model, fuel metering and epoch interruption. A background thread
+/// advances the epoch every [`EPOCH_TICK`] until the engine is dropped.
+pub(crate) fn new_engine() -> Result<Engine, PluginHostError> {
+    let mut config = wasmtime::Config::new();
+    config.wasm_component_model(true);
+    config.consume_fuel(true);
+    config.epoch_interruption(true);
+    let engine = Engine::new(&config).map_err(PluginHostError::Compilation)?;
+
+    let weak = engine.weak();
+    std::thread::Builder::new()
+        .name("plugin-epoch".into())
+        .spawn(move || {
+            while let Some(engine) = weak.upgrade() {
+                engine.increment_epoch();
+                drop(engine);
+                std::thread::sleep(EPOCH_TICK);
+            }
+        })
+        .map_err(|e| {
+            PluginHostError::InitializationFailed(format!("failed to start epoch ticker: {}", e))
+        })?;
+    Ok(engine)
+}
+
+/// Gives the store a fresh budget for one guest call: `fuel_per_call` fuel
+/// and `call_timeout_ms` of wall-clock time. A guest still running at the
+/// deadline traps with [`Trap::Interrupt`].
+pub(crate) fn start_call(store: &mut Store<PluginState>, limits: &ResourceLimits) {
+    store.set_fuel(limits.fuel_per_call).ok();
+    let ticks = limits.call_timeout_ms.div_ceil(EPOCH_TICK.as_millis() as u64);
+    store.set_epoch_deadline(ticks.max(1));
+    store.data_mut().call_deadline =
+        Some(Instant::now() + Duration::from_millis(limits.call_timeout_ms));
+}
+
+/// Reads a `.wasm` file.
+pub(crate) fn read_wasm(wasm_path: &Path) -> Result<Vec<u8>, PluginHostError> {
+    std::fs::read(wasm_path).map_err(|e| {
+        PluginHostError::InitializationFailed(format!(
+            "failed to read {}: {}",
+            wasm_path.display(),
+            e
+        ))
+    })
+}
+
+/// Hex SHA-256 of a component's bytes.
+pub(crate) fn wasm_sha256(wasm_bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(wasm_bytes))
+}
+
+/// Converts a failed guest call into [`PluginHostError::Timeout`] if it hit
+/// its deadline, or [`PluginHostError::PluginCrashed`] otherwise.
+fn guest_call_failed(
+    plugin_id: String,
+    operation: &str,
+    error: wasmtime::Error,
+    limits: &ResourceLimits,
+) -> PluginHostError {
+    if error.downcast_ref::<Trap>() == Some(&Trap::Interrupt) {
+        return PluginHostError::Timeout {
+            plugin_id,
+            timeout_ms: limits.call_timeout_ms,
+        };
+    }
+    PluginHostError::PluginCrashed {
+        plugin_id,
+        message: format!("{} failed: {}", operation, error),
+    }
+}
+
 /// A resource limiter that tracks actual memory usage.
 /// Wraps memory limit enforcement with allocation tracking.
 pub struct TrackingLimiter {
@@ -177,8 +282,8 @@ pub struct PluginState {
     pub entity_store: Arc<privstack_storage::EntityStore>,
     /// The event store handle for sync event recording.
     pub event_store: Arc<privstack_storage::EventStore>,
-    /// Plugin-scoped settings stored as key-value pairs.
-    pub settings: HashMap<String, String>,
+    /// Quota for this plugin's persisted settings, in bytes.
+    pub settings_quota_bytes: usize,
     /// Cached entity schemas from this plugin.
     pub schemas: Vec<WitEntitySchema>,
     /// Cached view state JSON.
@@ -187,8 +292,28 @@ pub struct PluginState {
     pub state_dirty: bool,
     /// Pending navigation request (set by navigation host import).
     pub pending_navigation: Option<String>,
+    /// Cross-plugin commands queued by the agent `send-command` import.
+    pub pending_commands: Vec<PendingCommand>,
     /// Wasmtime resource limiter with memory tracking.
     pub limiter: TrackingLimiter,
+    /// Host directories preopened in `wasi_ctx`.
+    pub directory_grants: Vec<DirectoryGrant>,
+    /// Hosts, address ranges and limits for `fetch-url`.
+    pub network_policy: NetworkPolicy,
+    /// Rate-limit window and request log. Behind a mutex so host-side
+    /// fetches can run with a shared borrow.
+    pub network_activity: Mutex<NetworkActivity>,
+    /// When the guest call in progress times out. Blocking host imports
+    /// such as `fetch-url` give up by then.
+    pub call_deadline: Option<Instant>,
+    /// The plugin's `entity-handler` hooks, if it exports them.
+    pub entity_handler: Option<Arc<WasmEntityHandler>>,
+    /// Where entity writes made through `sdk` are reported to observers.
+    /// Attached by the manager; `None` for standalone sandboxes.
+    pub entity_changes: Option<Arc<EntityChangeQueue>>,
+    /// The inter-plugin event bus. Attached by the manager; `None` for
+    /// standalone sandboxes.
+    pub event_bus: Option<Arc<EventBus>>,
     /// WASI context for wasm32-wasip1 imports.
     pub wasi_ctx: WasiCtx,
     /// Resource table required by WasiView.
@@ -231,13 +356,237 @@ impl PluginState {
             })
         }
     }
+
+    /// Whether this plugin may be told about `change`: another writer's
+    /// change to one of its entity types, or to any type but the host's own
+    /// with `CrossEntityRead`.
+    pub fn observes(&self, change: &EntityChange) -> bool {
+        change.source != self.plugin_id
+            && !is_reserved_entity_type(&change.entity_type)
+            && (self.declared_entity_types.contains(&change.entity_type)
+                || self.permissions.is_granted(Permission::CrossEntityRead))
+    }
+
+    /// Reports an entity this plugin wrote through `sdk`.
+    pub fn record_entity_change(&self, entity_id: &str, entity_type: &str, kind: EntityChangeKind) {
+        if let Some(queue) = &self.entity_changes {
+            queue.push(EntityChange::new(entity_id, entity_type, kind, self.plugin_id.as_str()));
+        }
+    }
+
+    /// This plugin's persisted settings.
+    pub fn settings(&self) -> PluginSettings<'_> {
+        PluginSettings::new(&self.entity_store, &self.plugin_id, self.settings_quota_bytes)
+    }
+
+    fn event_bus(&self) -> Result<&EventBus, PluginHostError> {
+        self.event_bus.as_deref().ok_or_else(|| PluginHostError::CapabilityNotSupported {
+            plugin_id: self.plugin_id.clone(),
+            capability: "events".into(),
+        })
+    }
+
+    /// Publishes on one of this plugin's declared topics.
+    pub fn publish_event(&self, topic: &str, payload_json: &str) -> Result<(), PluginHostError> {
+        self.event_bus()?.publish(&self.plugin_id, topic, payload_json).map(|_| ())
+    }
+
+    /// Subscribes to another plugin's topic. Requires `EventSubscribe`.
+    pub fn subscribe_event(&self, publisher_id: &str, topic: &str) -> Result<(), PluginHostError> {
+        self.event_bus()?
+            .subscribe(&self.plugin_id, &self.permissions, publisher_id, topic)
+    }
+
+    /// Ends a subscription. Returns false if there was none.
+    pub fn unsubscribe_event(&self, publisher_id: &str, topic: &str) -> bool {
+        self.event_bus
+            .as_ref()
+            .is_some_and(|bus| bus.unsubscribe(&self.plugin_id, publisher_id, topic))
+    }
+
+    /// This plugin's scheduled jobs.
+    pub fn jobs(&self) -> PluginJobs<'_> {
+        PluginJobs::new(&self.entity_store, &self.plugin_id)
+    }
+
+    /// Registers a job from the `scheduler` import, first run one period
+    /// from now.
+    pub fn schedule_job(&self, job_id: &str, schedule: JobSchedule) -> Result<(), String> {
+        let now = chrono::Utc::now().timestamp();
+        self.jobs().schedule(job_id, schedule, now).map(|_| ()).map_err(|e| {
+            warn!(plugin_id = %self.plugin_id, job_id = %job_id, "Job not scheduled: {}", e);
+            e.to_string()
+        })
+    }
+
+    /// Reads a setting, falling back to `default` when unset or unreadable.
+    pub fn settings_get(&self, key: &str, default: &str) -> String {
+        match self.settings().get(key) {
+            Ok(Some(setting)) => setting.value,
+            Ok(None) => default.to_string(),
+            Err(e) => {
+                warn!(plugin_id = %self.plugin_id, key = %key, "Settings read failed: {}", e);
+                default.to_string()
+            }
+        }
+    }
+
+    /// Writes a setting. The WIT import has no error channel, so failures
+    /// (e.g. an exceeded quota) are logged and the old value is kept.
+    /// Writes to a synced setting are reported as entity changes, which the
+    /// manager hands to the shell to replicate.
+    pub fn settings_set(&self, key: &str, value: &str) {
+        let settings = self.settings();
+        match settings.set(key, value) {
+            Ok(()) => {
+                if settings.get(key).ok().flatten().is_some_and(|s| s.sync) {
+                    self.record_entity_change(
+                        &settings.entity_id(key),
+                        SETTINGS_ENTITY_TYPE,
+                        EntityChangeKind::Updated,
+                    );
+                }
+            }
+            Err(e) => {
+                warn!(plugin_id = %self.plugin_id, key = %key, "Settings write failed: {}", e);
+            }
+        }
+    }
+
+    /// Deletes a setting, logging failures.
+    pub fn settings_remove(&self, key: &str) {
+        let settings = self.settings();
+        let synced = settings.get(key).ok().flatten().is_some_and(|s| s.sync);
+        match settings.remove(key) {
+            Ok(()) if synced => self.record_entity_change(
+                &settings.entity_id(key),
+                SETTINGS_ENTITY_TYPE,
+                EntityChangeKind::Deleted,
+            ),
+            Ok(()) => {}
+            Err(e) => {
+                warn!(plugin_id = %self.plugin_id, key = %key, "Settings remove failed: {}", e);
+            }
+        }
+    }
+
+    /// Performs an outbound HTTP request under the plugin's network policy
+    /// and rate limit, recording it in the request log.
+    pub fn fetch(&self, request: FetchRequest<'_>) -> Result<FetchResponse, PluginHostError> {
+        self.check_permission(Permission::Network)?;
+        let mut record = NetworkRequestRecord {
+            method: request.method.to_uppercase(),
+            url: request.url.to_string(),
+            status: None,
+            bytes: 0,
+            error: None,
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+
+        let acquired = self
+            .network_activity
+            .lock()
+            .unwrap()
+            .try_acquire(self.network_policy.max_requests_per_minute);
+        let result = if acquired {
+            net_policy::fetch(&self.network_policy, request)
+        } else {
+            Err(PluginHostError::ResourceLimitExceeded {
+                plugin_id: self.plugin_id.clone(),
+                detail: format!(
+                    "more than {} network requests per minute",
+                    self.network_policy.max_requests_per_minute
+                ),
+            })
+        };
+
+        match &result {
+            Ok(response) => {
+                record.status = Some(response.status);
+                record.bytes = response.body.len();
+            }
+            Err(e) => {
+                warn!(
+                    plugin_id = %self.plugin_id,
+                    url = %record.url,
+                    "Network fetch refused: {}",
+                    e
+                );
+                record.error = Some(e.to_string());
+            }
+        }
+        self.network_activity.lock().unwrap().record(record);
+        result
+    }
+}
+
+/// A command an agent plugin asked to send to another plugin.
+/// The manager routes it once the agent's current call returns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingCommand {
+    pub target_plugin_id: String,
+    pub command: String,
+    pub args: String,
+}
+
+/// Checks that every schema converts to the core format and that none
+/// claims a host-owned entity type.
+fn validate_schemas(plugin_id: &str, schemas: &[WitEntitySchema]) -> Result<(), PluginHostError> {
+    for schema in schemas {
+        if is_reserved_entity_type(&schema.entity_type) {
+            return Err(PluginHostError::InvalidSchema(format!(
+                "{}: entity type '{}' is reserved",
+                plugin_id, schema.entity_type
+            )));
+        }
+        schema
+            .to_core_schema()
+            .map_err(|e| PluginHostError::InvalidSchema(format!("{}: {}", plugin_id, e)))?;
+    }
+    Ok(())
+}
+
+/// WIT interface name prefix of the `agent` import.
+const AGENT_IMPORT: &str = "privstack:plugin/agent";
+
+/// Creates a linker with every host import the component needs.
+/// Components that import `agent` get the `agent-plugin-world` imports;
+/// everything else gets `plugin-world`. Returns whether it is an agent.
+fn link_host_imports(
+    engine: &Engine,
+    component: &Component,
+) -> Result<(Linker<PluginState>, bool), PluginHostError> {
+    let is_agent = component
+        .component_type()
+        .imports(engine)
+        .any(|(name, _)| name.starts_with(AGENT_IMPORT));
+
+    let mut linker = Linker::new(engine);
+    if is_agent {
+        AgentPluginWorld::add_to_linker(&mut linker, |state: &mut PluginState| state)
+            .map_err(PluginHostError::Compilation)?;
+    } else {
+        PluginWorld::add_to_linker(&mut linker, |state: &mut PluginState| state)
+            .map_err(PluginHostError::Compilation)?;
+    }
+
+    // Link WASI preview 2 (required for wasm32-wasip1 compiled components)
+    wasmtime_wasi::p2::add_to_linker_sync(&mut linker)
+        .map_err(PluginHostError::Compilation)?;
+
+    Ok((linker, is_agent))
 }
 
 /// Wasmtime runtime state — only present for real .wasm plugins.
 struct WasmRuntime {
-    _engine: Engine,
+    engine: Engine,
+    /// Kept to re-instantiate the plugin after a crash.
+    component: Component,
     store: Store<PluginState>,
     bindings: PluginWorld,
+    entity_observer: Option<EntityObserverExports>,
+    scheduled_task: Option<ScheduledTaskExports>,
+    event_subscriber: Option<EventSubscriberExports>,
 }
 
 /// A sandboxed plugin instance, either metadata-only or backed by a real Wasmtime component.
@@ -254,6 +603,14 @@ pub struct PluginSandbox {
     pub has_timer: bool,
     /// Whether the plugin exports `shutdown-aware`.
     pub has_shutdown_aware: bool,
+    /// Whether the plugin exports `entity-observer`.
+    pub has_entity_observer: bool,
+    /// Whether the plugin exports `scheduled-task`.
+    pub has_scheduled_task: bool,
+    /// Whether the plugin exports `event-subscriber`.
+    pub has_event_subscriber: bool,
+    /// Whether the plugin targets `agent-plugin-world` (imports `agent`).
+    pub is_agent: bool,
     /// Wasmtime runtime — None for metadata-only sandboxes.
     runtime: Option<WasmRuntime>,
     /// Plugin state — used directly for metadata-only sandboxes.
@@ -261,6 +618,10 @@ pub struct PluginSandbox {
     standalone_state: Option<PluginState>,
     /// Fuel consumed in the last plugin call (for metrics tracking).
     last_fuel_consumed: u64,
+    /// Whether `initialize()` returned true; replayed by [`Self::restart`].
+    initialized: bool,
+    /// Whether the plugin is between `activate()` and `deactivate()`.
+    active: bool,
 }
 
 impl PluginSandbox {
@@ -284,11 +645,7 @@ impl PluginSandbox {
         );
 
         // Validate schemas convert to core format
-        for schema in &schemas {
-            schema
-                .to_core_schema()
-                .map_err(|e| PluginHostError::InvalidSchema(format!("{}: {}", metadata.id, e)))?;
-        }
+        validate_schemas(&metadata.id, &schemas)?;
 
         let limiter = TrackingLimiter::new(resource_limits.max_memory_bytes);
 
@@ -298,11 +655,19 @@ impl PluginSandbox {
             declared_entity_types,
             entity_store,
             event_store,
-            settings: HashMap::new(),
+            settings_quota_bytes: resource_limits.max_settings_bytes,
             schemas: schemas.clone(),
             view_state: None,
             state_dirty: false,
             pending_navigation: None,
+            pending_commands: Vec::new(),
+            directory_grants: Vec::new(),
+            network_policy: NetworkPolicy::default(),
+            network_activity: Mutex::default(),
+            call_deadline: None,
+            entity_handler: None,
+            entity_changes: None,
+            event_bus: None,
             limiter,
             wasi_ctx: WasiCtxBuilder::new().build(),
             resource_table: ResourceTable::new(),
@@ -316,9 +681,15 @@ impl PluginSandbox {
             has_deep_link_target: false,
             has_timer: false,
             has_shutdown_aware: false,
+            has_entity_observer: false,
+            has_scheduled_task: false,
+            has_event_subscriber: false,
+            is_agent: false,
             runtime: None,
             standalone_state: Some(state),
             last_fuel_consumed: 0,
+            initialized: false,
+            active: false,
         })
     }
 
@@ -334,12 +705,7 @@ impl PluginSandbox {
     ) -> Result<Self, PluginHostError> {
         info!(path = %wasm_path.display(), "Loading Wasm component");
 
-        let mut config = wasmtime::Config::new();
-        config.wasm_component_model(true);
-        config.consume_fuel(true);
-
-        let engine = Engine::new(&config)
-            .map_err(|e| PluginHostError::Compilation(e))?;
+        let engine = new_engine()?;
 
         // Load and compile the component
         let wasm_bytes = std::fs::read(wasm_path).map_err(|e| {
@@ -353,14 +719,8 @@ impl PluginSandbox {
         let component = Component::new(&engine, &wasm_bytes)
             .map_err(|e| PluginHostError::Compilation(e))?;
 
-        // Create linker with all host imports
-        let mut linker = Linker::new(&engine);
-        PluginWorld::add_to_linker(&mut linker, |state: &mut PluginState| state)
-            .map_err(|e| PluginHostError::Compilation(e))?;
-
-        // Link WASI preview 2 (required for wasm32-wasip1 compiled components)
-        wasmtime_wasi::p2::add_to_linker_sync(&mut linker)
-            .map_err(|e| PluginHostError::Compilation(e))?;
+        // Create linker with all host imports (agent world if `agent` is imported)
+        let (linker, is_agent) = link_host_imports(&engine, &component)?;
 
         let limiter = TrackingLimiter::new(resource_limits.max_memory_bytes);
 
@@ -374,49 +734,54 @@ impl PluginSandbox {
             declared_entity_types: HashSet::new(),
             entity_store,
             event_store,
-            settings: HashMap::new(),
+            settings_quota_bytes: resource_limits.max_settings_bytes,
             schemas: Vec::new(),
             view_state: None,
             state_dirty: false,
             pending_navigation: None,
+            pending_commands: Vec::new(),
+            directory_grants: Vec::new(),
+            network_policy: NetworkPolicy::default(),
+            network_activity: Mutex::default(),
+            call_deadline: None,
+            entity_handler: None,
+            entity_changes: None,
+            event_bus: None,
             limiter,
             wasi_ctx,
             resource_table: ResourceTable::new(),
         };
 
         let mut store = Store::new(&engine, state);
-        store.set_fuel(resource_limits.fuel_per_call).ok();
+        start_call(&mut store, &resource_limits);
         store.limiter(|s| &mut s.limiter);
 
         // Instantiate
-        let bindings = PluginWorld::instantiate(&mut store, &component, &linker)
-            .map_err(|e| PluginHostError::Compilation(e))?;
+        let instance = linker
+            .instantiate(&mut store, &component)
+            .map_err(PluginHostError::Compilation)?;
+        let bindings = PluginWorld::new(&mut store, &instance)
+            .map_err(PluginHostError::Compilation)?;
 
         // Call get_metadata() to discover plugin identity
-        store.set_fuel(resource_limits.fuel_per_call).ok();
+        start_call(&mut store, &resource_limits);
         let wit_metadata = bindings
             .privstack_plugin_plugin()
             .call_get_metadata(&mut store)
             .map_err(|e| {
-                PluginHostError::PluginCrashed {
-                    plugin_id: "unknown".into(),
-                    message: format!("get_metadata() failed: {}", e),
-                }
+                guest_call_failed("unknown".into(), "get_metadata()", e, &resource_limits)
             })?;
 
         let metadata = convert_wit_metadata(&wit_metadata);
         store.data_mut().plugin_id = metadata.id.clone();
 
         // Call get_entity_schemas()
-        store.set_fuel(resource_limits.fuel_per_call).ok();
+        start_call(&mut store, &resource_limits);
         let wit_schemas = bindings
             .privstack_plugin_plugin()
             .call_get_entity_schemas(&mut store)
             .map_err(|e| {
-                PluginHostError::PluginCrashed {
-                    plugin_id: metadata.id.clone(),
-                    message: format!("get_entity_schemas() failed: {}", e),
-                }
+                guest_call_failed(metadata.id.clone(), "get_entity_schemas()", e, &resource_limits)
             })?;
 
         let schemas = convert_wit_schemas(&wit_schemas);
@@ -424,15 +789,32 @@ impl PluginSandbox {
             schemas.iter().map(|s| s.entity_type.clone()).collect();
 
         // Validate schemas
-        for schema in &schemas {
-            schema
-                .to_core_schema()
-                .map_err(|e| PluginHostError::InvalidSchema(format!("{}: {}", metadata.id, e)))?;
-        }
+        validate_schemas(&metadata.id, &schemas)?;
 
         store.data_mut().declared_entity_types = declared_entity_types;
         store.data_mut().schemas = schemas;
 
+        let entity_handler = WasmEntityHandler::for_component(
+            &engine,
+            &component,
+            &linker,
+            &metadata.id,
+            &resource_limits,
+            store.data(),
+        )?;
+        let has_entity_handler = entity_handler.is_some();
+        store.data_mut().entity_handler = entity_handler;
+
+        let entity_observer = bind_entity_observer(&engine, &component, &mut store, &instance)
+            .map_err(PluginHostError::Compilation)?;
+        let has_entity_observer = entity_observer.is_some();
+        let scheduled_task = bind_scheduled_task(&engine, &component, &mut store, &instance)
+            .map_err(PluginHostError::Compilation)?;
+        let has_scheduled_task = scheduled_task.is_some();
+        let event_subscriber = bind_event_subscriber(&engine, &component, &mut store, &instance)
+            .map_err(PluginHostError::Compilation)?;
+        let has_event_subscriber = event_subscriber.is_some();
+
         // All exports are required by the WIT world definition.
         // Plugins that don't need a capability provide stub implementations.
         let has_linkable_item_provider = true;
@@ -446,6 +828,11 @@ impl PluginSandbox {
             deep_link = has_deep_link_target,
             timer = has_timer,
             shutdown_aware = has_shutdown_aware,
+            entity_handler = has_entity_handler,
+            entity_observer = has_entity_observer,
+            scheduled_task = has_scheduled_task,
+            event_subscriber = has_event_subscriber,
+            agent = is_agent,
             "Wasm component loaded"
         );
 
@@ -457,13 +844,23 @@ impl PluginSandbox {
             has_deep_link_target,
             has_timer,
             has_shutdown_aware,
+            has_entity_observer,
+            has_scheduled_task,
+            has_event_subscriber,
+            is_agent,
             runtime: Some(WasmRuntime {
-                _engine: engine,
+                engine,
+                component,
                 store,
                 bindings,
+                entity_observer,
+                scheduled_task,
+                event_subscriber,
             }),
             standalone_state: None,
             last_fuel_consumed: 0,
+            initialized: false,
+            active: false,
         };
 
         // Cache link_type at load time to avoid reentrant calls later
@@ -488,18 +885,47 @@ impl PluginSandbox {
         entity_store: Arc<privstack_storage::EntityStore>,
         event_store: Arc<privstack_storage::EventStore>,
     ) -> Result<Self, PluginHostError> {
-        info!(path = %wasm_path.display(), "Loading Wasm component (cached)");
+        let wasm_bytes = read_wasm(wasm_path)?;
+        Self::from_wasm_bytes(
+            &wasm_bytes,
+            Some(wasm_path),
+            engine,
+            permissions,
+            resource_limits,
+            entity_store,
+            event_store,
+        )
+    }
 
-        let wasm_bytes = std::fs::read(wasm_path).map_err(|e| {
-            PluginHostError::InitializationFailed(format!(
-                "failed to read {}: {}",
-                wasm_path.display(),
-                e
-            ))
-        })?;
+    /// Create a sandbox from a component's bytes using a shared engine. With
+    /// `wasm_path`, the compiled component is cached next to it as
+    /// [`Self::from_wasm_cached`] does; the bytes, not the file, are what
+    /// runs.
+    pub fn from_wasm_bytes(
+        wasm_bytes: &[u8],
+        wasm_path: Option<&Path>,
+        engine: &Engine,
+        permissions: PermissionSet,
+        resource_limits: ResourceLimits,
+        entity_store: Arc<privstack_storage::EntityStore>,
+        event_store: Arc<privstack_storage::EventStore>,
+    ) -> Result<Self, PluginHostError> {
+        let Some(wasm_path) = wasm_path else {
+            let component =
+                Component::new(engine, wasm_bytes).map_err(PluginHostError::Compilation)?;
+            return Self::instantiate_component(
+                engine,
+                component,
+                permissions,
+                resource_limits,
+                entity_store,
+                event_store,
+            );
+        };
+        info!(path = %wasm_path.display(), "Loading Wasm component (cached)");
 
         // SHA-256 hash of wasm bytes for cache invalidation
-        let wasm_hash = hex::encode(Sha256::digest(&wasm_bytes));
+        let wasm_hash = wasm_sha256(wasm_bytes);
 
         let cwasm_path = wasm_path.with_extension("cwasm");
         let hash_path = wasm_path.with_extension("cwasm.sha256");
@@ -523,11 +949,11 @@ impl PluginSandbox {
                 }
             } else {
                 info!(path = %wasm_path.display(), "Cache hash mismatch, recompiling");
-                Self::compile_and_cache(engine, &wasm_bytes, &wasm_hash, &cwasm_path, &hash_path, wasm_path)?
+                Self::compile_and_cache(engine, wasm_bytes, &wasm_hash, &cwasm_path, &hash_path, wasm_path)?
             }
         } else {
             info!(path = %wasm_path.display(), "No cached component found, compiling");
-            Self::compile_and_cache(engine, &wasm_bytes, &wasm_hash, &cwasm_path, &hash_path, wasm_path)?
+            Self::compile_and_cache(engine, wasm_bytes, &wasm_hash, &cwasm_path, &hash_path, wasm_path)?
         };
 
         Self::instantiate_component(engine, component, permissions, resource_limits, entity_store, event_store)
@@ -577,11 +1003,7 @@ impl PluginSandbox {
         entity_store: Arc<privstack_storage::EntityStore>,
         event_store: Arc<privstack_storage::EventStore>,
     ) -> Result<Self, PluginHostError> {
-        let mut linker = Linker::new(engine);
-        PluginWorld::add_to_linker(&mut linker, |state: &mut PluginState| state)
-            .map_err(|e| PluginHostError::Compilation(e))?;
-        wasmtime_wasi::p2::add_to_linker_sync(&mut linker)
-            .map_err(|e| PluginHostError::Compilation(e))?;
+        let (linker, is_agent) = link_host_imports(engine, &component)?;
 
         let limiter = TrackingLimiter::new(resource_limits.max_memory_bytes);
 
@@ -593,63 +1015,85 @@ impl PluginSandbox {
             declared_entity_types: HashSet::new(),
             entity_store,
             event_store,
-            settings: HashMap::new(),
+            settings_quota_bytes: resource_limits.max_settings_bytes,
             schemas: Vec::new(),
             view_state: None,
             state_dirty: false,
             pending_navigation: None,
+            pending_commands: Vec::new(),
+            directory_grants: Vec::new(),
+            network_policy: NetworkPolicy::default(),
+            network_activity: Mutex::default(),
+            call_deadline: None,
+            entity_handler: None,
+            entity_changes: None,
+            event_bus: None,
             limiter,
             wasi_ctx,
             resource_table: ResourceTable::new(),
         };
 
         let mut store = Store::new(engine, state);
-        store.set_fuel(resource_limits.fuel_per_call).ok();
+        start_call(&mut store, &resource_limits);
         store.limiter(|s| &mut s.limiter);
 
-        let bindings = PluginWorld::instantiate(&mut store, &component, &linker)
-            .map_err(|e| PluginHostError::Compilation(e))?;
+        let instance = linker
+            .instantiate(&mut store, &component)
+            .map_err(PluginHostError::Compilation)?;
+        let bindings = PluginWorld::new(&mut store, &instance)
+            .map_err(PluginHostError::Compilation)?;
 
         // Call get_metadata()
-        store.set_fuel(resource_limits.fuel_per_call).ok();
+        start_call(&mut store, &resource_limits);
         let wit_metadata = bindings
             .privstack_plugin_plugin()
             .call_get_metadata(&mut store)
             .map_err(|e| {
-                PluginHostError::PluginCrashed {
-                    plugin_id: "unknown".into(),
-                    message: format!("get_metadata() failed: {}", e),
-                }
+                guest_call_failed("unknown".into(), "get_metadata()", e, &resource_limits)
             })?;
 
         let metadata = convert_wit_metadata(&wit_metadata);
         store.data_mut().plugin_id = metadata.id.clone();
 
         // Call get_entity_schemas()
-        store.set_fuel(resource_limits.fuel_per_call).ok();
+        start_call(&mut store, &resource_limits);
         let wit_schemas = bindings
             .privstack_plugin_plugin()
             .call_get_entity_schemas(&mut store)
             .map_err(|e| {
-                PluginHostError::PluginCrashed {
-                    plugin_id: metadata.id.clone(),
-                    message: format!("get_entity_schemas() failed: {}", e),
-                }
+                guest_call_failed(metadata.id.clone(), "get_entity_schemas()", e, &resource_limits)
             })?;
 
         let schemas = convert_wit_schemas(&wit_schemas);
         let declared_entity_types: HashSet<String> =
             schemas.iter().map(|s| s.entity_type.clone()).collect();
 
-        for schema in &schemas {
-            schema
-                .to_core_schema()
-                .map_err(|e| PluginHostError::InvalidSchema(format!("{}: {}", metadata.id, e)))?;
-        }
+        validate_schemas(&metadata.id, &schemas)?;
 
         store.data_mut().declared_entity_types = declared_entity_types;
         store.data_mut().schemas = schemas;
 
+        let entity_handler = WasmEntityHandler::for_component(
+            engine,
+            &component,
+            &linker,
+            &metadata.id,
+            &resource_limits,
+            store.data(),
+        )?;
+        let has_entity_handler = entity_handler.is_some();
+        store.data_mut().entity_handler = entity_handler;
+
+        let entity_observer = bind_entity_observer(engine, &component, &mut store, &instance)
+            .map_err(PluginHostError::Compilation)?;
+        let has_entity_observer = entity_observer.is_some();
+        let scheduled_task = bind_scheduled_task(engine, &component, &mut store, &instance)
+            .map_err(PluginHostError::Compilation)?;
+        let has_scheduled_task = scheduled_task.is_some();
+        let event_subscriber = bind_event_subscriber(engine, &component, &mut store, &instance)
+            .map_err(PluginHostError::Compilation)?;
+        let has_event_subscriber = event_subscriber.is_some();
+
         let has_linkable_item_provider = true;
         let has_deep_link_target = true;
         let has_timer = true;
@@ -661,6 +1105,11 @@ impl PluginSandbox {
             deep_link = has_deep_link_target,
             timer = has_timer,
             shutdown_aware = has_shutdown_aware,
+            entity_handler = has_entity_handler,
+            entity_observer = has_entity_observer,
+            scheduled_task = has_scheduled_task,
+            event_subscriber = has_event_subscriber,
+            agent = is_agent,
             "Wasm component loaded"
         );
 
@@ -672,13 +1121,23 @@ impl PluginSandbox {
             has_deep_link_target,
             has_timer,
             has_shutdown_aware,
+            has_entity_observer,
+            has_scheduled_task,
+            has_event_subscriber,
+            is_agent,
             runtime: Some(WasmRuntime {
-                _engine: engine.clone(),
+                engine: engine.clone(),
+                component,
                 store,
                 bindings,
+                entity_observer,
+                scheduled_task,
+                event_subscriber,
             }),
             standalone_state: None,
             last_fuel_consumed: 0,
+            initialized: false,
+            active: false,
         };
 
         if has_linkable_item_provider {
@@ -693,6 +1152,72 @@ impl PluginSandbox {
         self.runtime.is_some()
     }
 
+    /// Whether `initialize()` has returned true.
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// Whether the plugin was activated and not deactivated since.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Replaces the plugin's store with a fresh instance of the same compiled
+    /// component, for recovering from a trap that may have left the store
+    /// unusable. Permissions, directory grants, network policy and the
+    /// attached queues carry over; `initialize()` and `activate()` are
+    /// replayed if the plugin had reached them. On error the sandbox is left
+    /// as it was.
+    pub fn restart(&mut self) -> Result<(), PluginHostError> {
+        let grants = self.state_ref().directory_grants.clone();
+        self.reinstantiate(grants)?;
+        info!(plugin_id = %self.metadata.id, "Plugin restarted");
+        Ok(())
+    }
+
+    /// Replaces the store with a fresh instance mounting `grants`, carrying
+    /// over everything else as [`Self::restart`] does.
+    fn reinstantiate(&mut self, grants: Vec<DirectoryGrant>) -> Result<(), PluginHostError> {
+        let rt = self.runtime.as_ref().ok_or_else(|| PluginHostError::PluginCrashed {
+            plugin_id: self.metadata.id.clone(),
+            message: "no Wasm runtime (metadata-only sandbox)".into(),
+        })?;
+        let state = rt.store.data();
+        let mut fresh = Self::instantiate_component(
+            &rt.engine,
+            rt.component.clone(),
+            state.permissions.clone(),
+            self.resource_limits.clone(),
+            Arc::clone(&state.entity_store),
+            Arc::clone(&state.event_store),
+        )?;
+        if fresh.metadata.id != self.metadata.id {
+            return Err(PluginHostError::InitializationFailed(format!(
+                "restarted plugin reports id '{}' instead of '{}'",
+                fresh.metadata.id, self.metadata.id
+            )));
+        }
+        fresh.set_directory_grants(grants)?;
+        fresh.set_network_policy(state.network_policy.clone());
+        if let Some(queue) = &state.entity_changes {
+            fresh.set_entity_change_queue(Arc::clone(queue));
+        }
+        if let Some(bus) = &state.event_bus {
+            fresh.set_event_bus(Arc::clone(bus));
+        }
+        if self.initialized && !fresh.call_initialize()? {
+            return Err(PluginHostError::InitializationFailed(format!(
+                "plugin '{}' refused to initialize after restart",
+                self.metadata.id
+            )));
+        }
+        if self.active {
+            fresh.call_activate()?;
+        }
+        *self = fresh;
+        Ok(())
+    }
+
     // ================================================================
     // State accessors
     // ================================================================
@@ -761,6 +1286,7 @@ impl PluginSandbox {
             .entity_store
             .get_fuel_metrics(&self.metadata.id)
             .unwrap_or((0, 0, 0));
+        let activity = state.network_activity.lock().unwrap();
 
         PluginResourceMetrics {
             memory_used_bytes,
@@ -773,6 +1299,8 @@ impl PluginSandbox {
             fuel_history_count,
             entity_count,
             disk_usage_bytes,
+            network_requests_total: activity.total(),
+            network_recent_requests: activity.recent(),
         }
     }
 
@@ -822,121 +1350,120 @@ impl PluginSandbox {
 
     /// Call the plugin's `initialize()` export.
     pub fn call_initialize(&mut self) -> Result<bool, PluginHostError> {
-        let fuel = self.resource_limits.fuel_per_call;
+        let limits = self.resource_limits.clone();
         let pid = self.metadata.id.clone();
         let rt = self.runtime_mut()?;
-        rt.store.set_fuel(fuel).ok();
+        start_call(&mut rt.store, &limits);
         let result = rt
             .bindings
             .privstack_plugin_plugin()
             .call_initialize(&mut rt.store);
         self.track_fuel_consumption();
-        result.map_err(|e| PluginHostError::PluginCrashed {
-            plugin_id: pid,
-            message: format!("initialize() failed: {}", e),
-        })
+        let initialized = result.map_err(|e| guest_call_failed(pid, "initialize()", e, &limits))?;
+        self.initialized |= initialized;
+        Ok(initialized)
     }
 
     /// Call the plugin's `activate()` export.
     pub fn call_activate(&mut self) -> Result<(), PluginHostError> {
-        let fuel = self.resource_limits.fuel_per_call;
+        let limits = self.resource_limits.clone();
         let pid = self.metadata.id.clone();
         let rt = self.runtime_mut()?;
-        rt.store.set_fuel(fuel).ok();
+        start_call(&mut rt.store, &limits);
         let result = rt
             .bindings
             .privstack_plugin_plugin()
             .call_activate(&mut rt.store);
         self.track_fuel_consumption();
-        result.map_err(|e| PluginHostError::PluginCrashed {
-            plugin_id: pid,
-            message: format!("activate() failed: {}", e),
-        })
+        result.map_err(|e| guest_call_failed(pid, "activate()", e, &limits))?;
+        self.active = true;
+        Ok(())
     }
 
     /// Call the plugin's `deactivate()` export.
     pub fn call_deactivate(&mut self) -> Result<(), PluginHostError> {
-        let fuel = self.resource_limits.fuel_per_call;
+        let limits = self.resource_limits.clone();
         let pid = self.metadata.id.clone();
         let rt = self.runtime_mut()?;
-        rt.store.set_fuel(fuel).ok();
+        start_call(&mut rt.store, &limits);
         let result = rt
             .bindings
             .privstack_plugin_plugin()
             .call_deactivate(&mut rt.store);
         self.track_fuel_consumption();
-        result.map_err(|e| PluginHostError::PluginCrashed {
-            plugin_id: pid,
-            message: format!("deactivate() failed: {}", e),
-        })
+        result.map_err(|e| guest_call_failed(pid, "deactivate()", e, &limits))?;
+        self.active = false;
+        Ok(())
     }
 
     /// Call the plugin's `on_navigated_to()` export.
     pub fn call_on_navigated_to(&mut self) -> Result<(), PluginHostError> {
-        let fuel = self.resource_limits.fuel_per_call;
+        let limits = self.resource_limits.clone();
         let pid = self.metadata.id.clone();
         let rt = self.runtime_mut()?;
-        rt.store.set_fuel(fuel).ok();
+        start_call(&mut rt.store, &limits);
         let result = rt
             .bindings
             .privstack_plugin_plugin()
             .call_on_navigated_to(&mut rt.store);
         self.track_fuel_consumption();
-        result.map_err(|e| PluginHostError::PluginCrashed {
-            plugin_id: pid,
-            message: format!("on_navigated_to() failed: {}", e),
-        })
+        result.map_err(|e| guest_call_failed(pid, "on_navigated_to()", e, &limits))
     }
 
     /// Call the plugin's `on_navigated_from()` export.
     pub fn call_on_navigated_from(&mut self) -> Result<(), PluginHostError> {
-        let fuel = self.resource_limits.fuel_per_call;
+        let limits = self.resource_limits.clone();
         let pid = self.metadata.id.clone();
         let rt = self.runtime_mut()?;
-        rt.store.set_fuel(fuel).ok();
+        start_call(&mut rt.store, &limits);
         let result = rt
             .bindings
             .privstack_plugin_plugin()
             .call_on_navigated_from(&mut rt.store);
         self.track_fuel_consumption();
-        result.map_err(|e| PluginHostError::PluginCrashed {
-            plugin_id: pid,
-            message: format!("on_navigated_from() failed: {}", e),
-        })
+        result.map_err(|e| guest_call_failed(pid, "on_navigated_from()", e, &limits))
     }
 
     /// Call the plugin's `dispose()` export.
     pub fn call_dispose(&mut self) -> Result<(), PluginHostError> {
-        let fuel = self.resource_limits.fuel_per_call;
+        let limits = self.resource_limits.clone();
         let pid = self.metadata.id.clone();
         let rt = self.runtime_mut()?;
-        rt.store.set_fuel(fuel).ok();
+        start_call(&mut rt.store, &limits);
         let result = rt
             .bindings
             .privstack_plugin_plugin()
             .call_dispose(&mut rt.store);
         self.track_fuel_consumption();
-        result.map_err(|e| PluginHostError::PluginCrashed {
-            plugin_id: pid,
-            message: format!("dispose() failed: {}", e),
-        })
+        result.map_err(|e| guest_call_failed(pid, "dispose()", e, &limits))
+    }
+
+    /// Call the plugin's `on-shutdown()` export.
+    pub fn call_on_shutdown(&mut self) -> Result<(), PluginHostError> {
+        let limits = self.resource_limits.clone();
+        let pid = self.metadata.id.clone();
+        let rt = self.runtime_mut()?;
+        start_call(&mut rt.store, &limits);
+        let result = rt
+            .bindings
+            .privstack_plugin_shutdown_aware()
+            .call_on_shutdown(&mut rt.store);
+        self.track_fuel_consumption();
+        result.map_err(|e| guest_call_failed(pid, "on_shutdown()", e, &limits))
     }
 
     /// Call the plugin's `get_view_state()` export.
     pub fn call_get_view_state(&mut self) -> Result<String, PluginHostError> {
-        let fuel = self.resource_limits.fuel_per_call;
+        let limits = self.resource_limits.clone();
         let pid = self.metadata.id.clone();
         let rt = self.runtime_mut()?;
-        rt.store.set_fuel(fuel).ok();
+        start_call(&mut rt.store, &limits);
         let result = rt
             .bindings
             .privstack_plugin_plugin()
             .call_get_view_state(&mut rt.store);
         self.track_fuel_consumption();
-        let state = result.map_err(|e| PluginHostError::PluginCrashed {
-            plugin_id: pid,
-            message: format!("get_view_state() failed: {}", e),
-        })?;
+        let state = result.map_err(|e| guest_call_failed(pid, "get_view_state()", e, &limits))?;
         let rt = self.runtime.as_mut().expect("runtime exists");
         rt.store.data_mut().view_state = Some(state.clone());
         rt.store.data_mut().state_dirty = false;
@@ -946,19 +1473,16 @@ impl PluginSandbox {
     /// Call the plugin's `get_view_data()` export (template-data-provider capability).
     /// Returns raw JSON data model for host-side template evaluation.
     pub fn call_get_view_data(&mut self) -> Result<String, PluginHostError> {
-        let fuel = self.resource_limits.fuel_per_call;
+        let limits = self.resource_limits.clone();
         let pid = self.metadata.id.clone();
         let rt = self.runtime_mut()?;
-        rt.store.set_fuel(fuel).ok();
+        start_call(&mut rt.store, &limits);
         let result = rt
             .bindings
             .privstack_plugin_template_data_provider()
             .call_get_view_data(&mut rt.store);
         self.track_fuel_consumption();
-        result.map_err(|e| PluginHostError::PluginCrashed {
-            plugin_id: pid,
-            message: format!("get_view_data() failed: {}", e),
-        })
+        result.map_err(|e| guest_call_failed(pid, "get_view_data()", e, &limits))
     }
 
     /// Call the plugin's `handle_command()` export.
@@ -967,55 +1491,48 @@ impl PluginSandbox {
         name: &str,
         args: &str,
     ) -> Result<String, PluginHostError> {
-        let fuel = self.resource_limits.fuel_per_call;
+        let limits = self.resource_limits.clone();
         let pid = self.metadata.id.clone();
         let name_owned = name.to_string();
         let rt = self.runtime_mut()?;
-        rt.store.set_fuel(fuel).ok();
+        start_call(&mut rt.store, &limits);
         let result = rt
             .bindings
             .privstack_plugin_plugin()
             .call_handle_command(&mut rt.store, name, args);
         self.track_fuel_consumption();
-        result.map_err(|e| PluginHostError::PluginCrashed {
-            plugin_id: pid,
-            message: format!("handle_command('{}') failed: {}", name_owned, e),
+        result.map_err(|e| {
+            guest_call_failed(pid, &format!("handle_command('{}')", name_owned), e, &limits)
         })
     }
 
     /// Call the plugin's `get_navigation_item()` export.
     pub fn call_get_navigation_item(&mut self) -> Result<Option<WitNavigationItem>, PluginHostError> {
-        let fuel = self.resource_limits.fuel_per_call;
+        let limits = self.resource_limits.clone();
         let pid = self.metadata.id.clone();
         let rt = self.runtime_mut()?;
-        rt.store.set_fuel(fuel).ok();
+        start_call(&mut rt.store, &limits);
         let result = rt
             .bindings
             .privstack_plugin_plugin()
             .call_get_navigation_item(&mut rt.store);
         self.track_fuel_consumption();
-        let result = result.map_err(|e| PluginHostError::PluginCrashed {
-            plugin_id: pid,
-            message: format!("get_navigation_item() failed: {}", e),
-        })?;
+        let result = result.map_err(|e| guest_call_failed(pid, "get_navigation_item()", e, &limits))?;
         Ok(result.map(|n| convert_wit_nav_item(&n)))
     }
 
     /// Call the plugin's `get_commands()` export.
     pub fn call_get_commands(&mut self) -> Result<Vec<WitCommandDefinition>, PluginHostError> {
-        let fuel = self.resource_limits.fuel_per_call;
+        let limits = self.resource_limits.clone();
         let pid = self.metadata.id.clone();
         let rt = self.runtime_mut()?;
-        rt.store.set_fuel(fuel).ok();
+        start_call(&mut rt.store, &limits);
         let result = rt
             .bindings
             .privstack_plugin_plugin()
             .call_get_commands(&mut rt.store);
         self.track_fuel_consumption();
-        let cmds = result.map_err(|e| PluginHostError::PluginCrashed {
-            plugin_id: pid,
-            message: format!("get_commands() failed: {}", e),
-        })?;
+        let cmds = result.map_err(|e| guest_call_failed(pid, "get_commands()", e, &limits))?;
         Ok(cmds.into_iter().map(|c| convert_wit_command(&c)).collect())
     }
 
@@ -1029,37 +1546,31 @@ impl PluginSandbox {
         query: &str,
         max_results: u32,
     ) -> Result<Vec<WitLinkableItem>, PluginHostError> {
-        let fuel = self.resource_limits.fuel_per_call;
+        let limits = self.resource_limits.clone();
         let pid = self.metadata.id.clone();
         let rt = self.runtime_mut()?;
-        rt.store.set_fuel(fuel).ok();
+        start_call(&mut rt.store, &limits);
         let result = rt
             .bindings
             .privstack_plugin_linkable_item_provider()
             .call_search_items(&mut rt.store, query, max_results);
         self.track_fuel_consumption();
-        let items = result.map_err(|e| PluginHostError::PluginCrashed {
-            plugin_id: pid,
-            message: format!("linkable search failed: {}", e),
-        })?;
+        let items = result.map_err(|e| guest_call_failed(pid, "linkable search", e, &limits))?;
         Ok(items.into_iter().map(|i| convert_wit_linkable_item(&i)).collect())
     }
 
     /// Get the plugin's self-reported link type from the linkable-item-provider export.
     pub fn call_link_type(&mut self) -> Result<String, PluginHostError> {
-        let fuel = self.resource_limits.fuel_per_call;
+        let limits = self.resource_limits.clone();
         let pid = self.metadata.id.clone();
         let rt = self.runtime_mut()?;
-        rt.store.set_fuel(fuel).ok();
+        start_call(&mut rt.store, &limits);
         let result = rt
             .bindings
             .privstack_plugin_linkable_item_provider()
             .call_link_type(&mut rt.store);
         self.track_fuel_consumption();
-        result.map_err(|e| PluginHostError::PluginCrashed {
-            plugin_id: pid,
-            message: format!("link_type() failed: {}", e),
-        })
+        result.map_err(|e| guest_call_failed(pid, "link_type()", e, &limits))
     }
 
     /// Navigate to a specific item via the deep-link-target export.
@@ -1067,19 +1578,70 @@ impl PluginSandbox {
         &mut self,
         item_id: &str,
     ) -> Result<(), PluginHostError> {
-        let fuel = self.resource_limits.fuel_per_call;
+        let limits = self.resource_limits.clone();
         let pid = self.metadata.id.clone();
         let rt = self.runtime_mut()?;
-        rt.store.set_fuel(fuel).ok();
+        start_call(&mut rt.store, &limits);
         let result = rt
             .bindings
             .privstack_plugin_deep_link_target()
             .call_navigate_to_item(&mut rt.store, item_id);
         self.track_fuel_consumption();
-        result.map_err(|e| PluginHostError::PluginCrashed {
-            plugin_id: pid,
-            message: format!("navigate_to_item failed: {}", e),
-        })
+        result.map_err(|e| guest_call_failed(pid, "navigate_to_item", e, &limits))
+    }
+
+    /// Call the plugin's `on-entities-changed()` export with one batch.
+    /// Does nothing if the plugin does not export `entity-observer`.
+    pub fn call_on_entities_changed(
+        &mut self,
+        changes: &[EntityChange],
+    ) -> Result<(), PluginHostError> {
+        let limits = self.resource_limits.clone();
+        let pid = self.metadata.id.clone();
+        let rt = self.runtime_mut()?;
+        let Some(observer) = &rt.entity_observer else {
+            return Ok(());
+        };
+        start_call(&mut rt.store, &limits);
+        let result = call_on_entities_changed(observer, &mut rt.store, changes);
+        self.track_fuel_consumption();
+        result.map_err(|e| guest_call_failed(pid, "on_entities_changed", e, &limits))
+    }
+
+    /// Call the plugin's `on-scheduled()` export for one job. The inner
+    /// result is the plugin's own report of the run.
+    pub fn call_on_scheduled(
+        &mut self,
+        job_id: &str,
+    ) -> Result<Result<(), String>, PluginHostError> {
+        let limits = self.resource_limits.clone();
+        let pid = self.metadata.id.clone();
+        let rt = self.runtime_mut()?;
+        let Some(task) = &rt.scheduled_task else {
+            return Err(PluginHostError::CapabilityNotSupported {
+                plugin_id: pid,
+                capability: "scheduled-task".into(),
+            });
+        };
+        start_call(&mut rt.store, &limits);
+        let result = call_on_scheduled(task, &mut rt.store, job_id);
+        self.track_fuel_consumption();
+        result.map_err(|e| guest_call_failed(pid, "on_scheduled", e, &limits))
+    }
+
+    /// Call the plugin's `on-events()` export with one batch.
+    /// Does nothing if the plugin does not export `event-subscriber`.
+    pub fn call_on_events(&mut self, events: &[BusEvent]) -> Result<(), PluginHostError> {
+        let limits = self.resource_limits.clone();
+        let pid = self.metadata.id.clone();
+        let rt = self.runtime_mut()?;
+        let Some(subscriber) = &rt.event_subscriber else {
+            return Ok(());
+        };
+        start_call(&mut rt.store, &limits);
+        let result = call_on_events(subscriber, &mut rt.store, events);
+        self.track_fuel_consumption();
+        result.map_err(|e| guest_call_failed(pid, "on_events", e, &limits))
     }
 
     // ================================================================
@@ -1113,23 +1675,17 @@ impl PluginSandbox {
 
     /// Handle a settings get request.
     pub fn handle_settings_get(&self, key: &str, default: &str) -> String {
-        self.state_ref()
-            .settings
-            .get(key)
-            .cloned()
-            .unwrap_or_else(|| default.to_string())
+        self.state_ref().settings_get(key, default)
     }
 
     /// Handle a settings set request.
     pub fn handle_settings_set(&mut self, key: &str, value: &str) {
-        self.state_mut_ref()
-            .settings
-            .insert(key.to_string(), value.to_string());
+        self.state_ref().settings_set(key, value);
     }
 
     /// Handle a settings remove request.
     pub fn handle_settings_remove(&mut self, key: &str) {
-        self.state_mut_ref().settings.remove(key);
+        self.state_ref().settings_remove(key);
     }
 
     /// Check if a vault operation is permitted.
@@ -1153,8 +1709,127 @@ impl PluginSandbox {
     }
 
     /// Replaces the permission set at runtime (e.g. after user toggles in Settings).
-    pub fn update_permissions(&mut self, permissions: PermissionSet) {
+    /// Revoking `Permission::Filesystem` also drops every directory grant,
+    /// which fails if the plugin cannot be re-instantiated without them.
+    pub fn update_permissions(
+        &mut self,
+        permissions: PermissionSet,
+    ) -> Result<(), PluginHostError> {
+        let revoke_fs = !permissions.is_granted(Permission::Filesystem);
         self.state_mut_ref().permissions = permissions;
+        if revoke_fs && !self.state_ref().directory_grants.is_empty() {
+            self.set_directory_grants(Vec::new())?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the directories preopened for the plugin. Requires
+    /// `Permission::Filesystem` unless `grants` is empty. New directories
+    /// take effect for directories the guest looks up after the call.
+    ///
+    /// Descriptors the guest already opened live in its resource table, so
+    /// dropping or narrowing a grant never leaves one behind: once
+    /// `initialize()` has run the plugin is re-instantiated (see
+    /// [`Self::restart`]), and before that the table is cleared.
+    pub fn set_directory_grants(
+        &mut self,
+        grants: Vec<DirectoryGrant>,
+    ) -> Result<(), PluginHostError> {
+        if grants == self.state_ref().directory_grants {
+            return Ok(());
+        }
+        if !grants.is_empty() {
+            self.state_ref().check_permission(Permission::Filesystem)?;
+        }
+        let mut guest_paths = HashSet::new();
+        for grant in &grants {
+            grant.validate()?;
+            if !guest_paths.insert(grant.guest_path.as_str()) {
+                return Err(PluginHostError::InvalidGrant(format!(
+                    "guest path '{}' granted twice",
+                    grant.guest_path
+                )));
+            }
+        }
+        let wasi_ctx = build_wasi_ctx(&grants)?;
+
+        let count = grants.len();
+        let revokes = self
+            .state_ref()
+            .directory_grants
+            .iter()
+            .any(|old| !grants.contains(old));
+        if self.runtime.is_some() && self.initialized && revokes {
+            self.reinstantiate(grants)?;
+        } else {
+            let state = self.state_mut_ref();
+            state.wasi_ctx = wasi_ctx;
+            state.directory_grants = grants;
+            if revokes {
+                state.resource_table = ResourceTable::new();
+            }
+        }
+        info!(plugin_id = %self.metadata.id, count, "Directory grants updated");
+        Ok(())
+    }
+
+    /// Mounts a file dialog selection as a one-off preopen under
+    /// `/selected`, staging selected files under `staging_dir`. Returns the
+    /// guest path of the selection. The grant lasts until the plugin is
+    /// unloaded.
+    pub fn grant_selection(
+        &mut self,
+        selected: &Path,
+        writable: bool,
+        staging_dir: Option<&Path>,
+    ) -> Result<String, PluginHostError> {
+        let mut grants = self.state_ref().directory_grants.clone();
+        let index = grants
+            .iter()
+            .filter(|g| g.guest_path.starts_with(SELECTION_ROOT))
+            .count();
+        let staging_root = staging_dir.map(|dir| selection_staging_root(dir, &self.metadata.id));
+        let (grant, guest_path) =
+            selection_grant(selected, index, writable, staging_root.as_deref())?;
+        grants.push(grant);
+        self.set_directory_grants(grants)?;
+        Ok(guest_path)
+    }
+
+    /// Directories currently preopened for the plugin.
+    pub fn directory_grants(&self) -> &[DirectoryGrant] {
+        &self.state_ref().directory_grants
+    }
+
+    /// Replaces the plugin's outbound network policy.
+    pub fn set_network_policy(&mut self, policy: NetworkPolicy) {
+        info!(
+            plugin_id = %self.metadata.id,
+            hosts = ?policy.allowed_hosts,
+            allow_private = policy.allow_private,
+            "Network policy updated"
+        );
+        self.state_mut_ref().network_policy = policy;
+    }
+
+    /// The plugin's outbound network policy.
+    pub fn network_policy(&self) -> &NetworkPolicy {
+        &self.state_ref().network_policy
+    }
+
+    /// The plugin's `entity-handler` hooks, if its component exports them.
+    pub fn entity_handler(&self) -> Option<Arc<WasmEntityHandler>> {
+        self.state_ref().entity_handler.clone()
+    }
+
+    /// Reports the plugin's entity writes to `queue`.
+    pub fn set_entity_change_queue(&mut self, queue: Arc<EntityChangeQueue>) {
+        self.state_mut_ref().entity_changes = Some(queue);
+    }
+
+    /// Connects the plugin's `events` import to `bus`.
+    pub fn set_event_bus(&mut self, bus: Arc<EventBus>) {
+        self.state_mut_ref().event_bus = Some(bus);
     }
 
     /// Returns the plugin ID.
@@ -1171,6 +1846,11 @@ impl PluginSandbox {
     pub fn state_mut(&mut self) -> &mut PluginState {
         self.state_mut_ref()
     }
+
+    /// Drains the commands queued by the agent `send-command` import.
+    pub fn take_pending_commands(&mut self) -> Vec<PendingCommand> {
+        std::mem::take(&mut self.state_mut_ref().pending_commands)
+    }
 }
 
 // ============================================================
@@ -1269,6 +1949,7 @@ fn convert_wit_schemas(
                     searchable: f.searchable,
                     vector_dim: f.vector_dim,
                     enum_options: f.enum_options.clone(),
+                    sensitivity: f.sensitivity.clone(),
                 })
                 .collect(),
             merge_strategy: match s.merge_strategy {
@@ -1363,6 +2044,7 @@ mod tests {
                 searchable: true,
                 vector_dim: None,
                 enum_options: None,
+                sensitivity: None,
             }],
             merge_strategy: WitMergeStrategy::LwwPerField,
         }]
@@ -1457,6 +2139,61 @@ mod tests {
         assert_eq!(sandbox.handle_settings_get("key", "default"), "default");
     }
 
+    #[test]
+    fn settings_survive_sandbox_recreation() {
+        let (es, ev) = test_stores();
+        let make = || {
+            PluginSandbox::new(
+                test_metadata(),
+                test_schemas(),
+                PermissionSet::default_first_party(),
+                ResourceLimits::first_party(),
+                es.clone(),
+                ev.clone(),
+            )
+            .unwrap()
+        };
+
+        make().handle_settings_set("theme", "dark");
+        assert_eq!(make().handle_settings_get("theme", "light"), "dark");
+    }
+
+    #[test]
+    fn settings_over_quota_keep_old_value() {
+        let (es, ev) = test_stores();
+        let mut limits = ResourceLimits::first_party();
+        limits.max_settings_bytes = 8;
+        let mut sandbox = PluginSandbox::new(
+            test_metadata(),
+            test_schemas(),
+            PermissionSet::default_first_party(),
+            limits,
+            es,
+            ev,
+        )
+        .unwrap();
+
+        sandbox.handle_settings_set("k", "short");
+        sandbox.handle_settings_set("k", "much too long");
+        assert_eq!(sandbox.handle_settings_get("k", ""), "short");
+    }
+
+    #[test]
+    fn reserved_entity_type_rejected() {
+        let (es, ev) = test_stores();
+        let mut schemas = test_schemas();
+        schemas[0].entity_type = crate::plugin_settings::SETTINGS_ENTITY_TYPE.into();
+        let result = PluginSandbox::new(
+            test_metadata(),
+            schemas,
+            PermissionSet::default_first_party(),
+            ResourceLimits::first_party(),
+            es,
+            ev,
+        );
+        assert!(matches!(result, Err(PluginHostError::InvalidSchema(_))));
+    }
+
     #[test]
     fn vector_field_without_dim_accepted() {
         let (es, ev) = test_stores();
@@ -1468,6 +2205,7 @@ mod tests {
                 searchable: false,
                 vector_dim: None,
                 enum_options: None,
+                sensitivity: None,
             }],
             merge_strategy: WitMergeStrategy::LwwDocument,
         }];
@@ -1514,6 +2252,21 @@ mod tests {
         assert!(tp.call_timeout_ms <= fp.call_timeout_ms);
     }
 
+    #[test]
+    fn interrupted_calls_are_timeouts() {
+        let limits = ResourceLimits::third_party();
+        let interrupted =
+            guest_call_failed("p".into(), "activate()", Trap::Interrupt.into(), &limits);
+        assert!(matches!(
+            interrupted,
+            PluginHostError::Timeout { timeout_ms: 3_000, .. }
+        ));
+
+        let trapped =
+            guest_call_failed("p".into(), "activate()", Trap::OutOfFuel.into(), &limits);
+        assert!(matches!(trapped, PluginHostError::PluginCrashed { .. }));
+    }
+
     // ================================================================
     // Entity type access checks
     // ================================================================
@@ -1703,11 +2456,49 @@ mod tests {
 
         assert!(sandbox.check_vault_access().is_err());
 
-        sandbox.update_permissions(PermissionSet::all_granted());
+        sandbox.update_permissions(PermissionSet::all_granted()).unwrap();
         assert!(sandbox.check_vault_access().is_ok());
         assert!(sandbox.check_linking_access().is_ok());
     }
 
+    #[test]
+    fn revoking_a_grant_drops_descriptors_opened_under_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let (es, ev) = test_stores();
+        let mut sandbox = PluginSandbox::new(
+            test_metadata(),
+            test_schemas(),
+            PermissionSet::all_granted(),
+            ResourceLimits::first_party(),
+            es,
+            ev,
+        )
+        .unwrap();
+        let vault = DirectoryGrant {
+            host_path: dir.path().to_path_buf(),
+            guest_path: "/vault".into(),
+            writable: false,
+        };
+        sandbox.set_directory_grants(vec![vault.clone()]).unwrap();
+        // Stands in for a descriptor the guest opened under `/vault`.
+        let opened = sandbox.state_mut().resource_table.push(0u32).unwrap();
+
+        // Adding a grant keeps what the guest holds.
+        let inbox = DirectoryGrant {
+            guest_path: "/inbox".into(),
+            ..vault.clone()
+        };
+        sandbox.set_directory_grants(vec![vault, inbox]).unwrap();
+        assert!(sandbox.state_mut().resource_table.get(&opened).is_ok());
+
+        // Losing the filesystem permission revokes every grant.
+        sandbox
+            .update_permissions(PermissionSet::default_first_party())
+            .unwrap();
+        assert!(sandbox.directory_grants().is_empty());
+        assert!(sandbox.state_mut().resource_table.get(&opened).is_err());
+    }
+
     // ================================================================
     // is_state_dirty
     // ================================================================
@@ -1777,6 +2568,7 @@ mod tests {
                     searchable: true,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 }],
                 merge_strategy: WitMergeStrategy::LwwPerField,
             },
@@ -1788,6 +2580,7 @@ mod tests {
                     searchable: true,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 }],
                 merge_strategy: WitMergeStrategy::LwwDocument,
             },
@@ -2460,6 +3253,7 @@ mod tests {
                 } else {
                     None
                 },
+                sensitivity: None,
             }],
             merge_strategy: WitMergeStrategy::LwwPerField,
         }]
@@ -2600,6 +3394,7 @@ mod tests {
                 searchable: true,
                 vector_dim: None,
                 enum_options: None,
+                sensitivity: None,
             }],
             merge_strategy: WitMergeStrategy::Custom,
         }];
@@ -2647,6 +3442,7 @@ mod tests {
                     searchable: true,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 }],
                 merge_strategy: WitMergeStrategy::LwwPerField,
             },
@@ -2658,6 +3454,7 @@ mod tests {
                     searchable: true,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 }],
                 merge_strategy: WitMergeStrategy::LwwPerField,
             },
@@ -0,0 +1,558 @@
+//! Background jobs registered through the `scheduler` import.
+//!
+//! Each job is an entity of type [`SCHEDULED_JOB_ENTITY_TYPE`], so jobs are
+//! encrypted at rest and survive restarts. Jobs are local-only: every device
+//! runs its own. The manager runs due jobs through the plugin's
+//! `scheduled-task` export; a run that fails pushes the next one back
+//! exponentially (see [`failure_backoff_secs`]). Runs missed while the app
+//! was closed are not replayed — an overdue job runs once, then resumes its
+//! schedule.
+
+use crate::bindings::scheduled_task_exports::ScheduledTaskExports;
+use crate::error::PluginHostError;
+use crate::sandbox::PluginState;
+use chrono::{DateTime, Datelike, NaiveDate, Timelike};
+use privstack_model::Entity;
+use privstack_storage::EntityStore;
+use serde::{Deserialize, Serialize};
+use wasmtime::component::{Component, Instance};
+use wasmtime::{Engine, Store};
+
+/// Entity type under which scheduled jobs are stored.
+pub const SCHEDULED_JOB_ENTITY_TYPE: &str = "__plugin_scheduled_job";
+
+/// WIT interface name prefix of the `scheduled-task` export.
+const SCHEDULED_TASK_EXPORT: &str = "privstack:plugin/scheduled-task";
+
+/// Shortest interval a job may use.
+pub const MIN_JOB_INTERVAL_SECS: u32 = 60;
+/// Jobs a single plugin may register.
+pub const MAX_JOBS_PER_PLUGIN: usize = 32;
+/// Longest job id, in bytes.
+const MAX_JOB_ID_LEN: usize = 64;
+/// Retry delay after the first failure; doubles with each further one.
+const BASE_BACKOFF_SECS: i64 = 60;
+/// Upper bound of the retry delay.
+const MAX_BACKOFF_SECS: i64 = 6 * 60 * 60;
+/// How far ahead a cron expression is searched for its next match. Covers
+/// schedules that only fire on February 29th.
+const MAX_CRON_SEARCH_DAYS: u32 = 8 * 366;
+
+/// When a job runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobSchedule {
+    Interval { secs: u32 },
+    Cron { expression: String },
+}
+
+impl JobSchedule {
+    /// Checks the interval bound or parses the cron expression.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Interval { secs } if *secs < MIN_JOB_INTERVAL_SECS => Err(format!(
+                "interval must be at least {} seconds",
+                MIN_JOB_INTERVAL_SECS
+            )),
+            Self::Interval { .. } => Ok(()),
+            Self::Cron { expression } => CronSchedule::parse(expression).map(|_| ()),
+        }
+    }
+
+    /// The first regular run strictly after `after` (Unix seconds).
+    pub fn next_after(&self, after: i64) -> Option<i64> {
+        match self {
+            Self::Interval { secs } => Some(after + i64::from(*secs)),
+            Self::Cron { expression } => CronSchedule::parse(expression).ok()?.next_after(after),
+        }
+    }
+}
+
+/// A five-field cron expression (`minute hour day-of-month month
+/// day-of-week`), evaluated in UTC. Fields accept `*`, numbers, ranges
+/// (`1-5`), steps (`*/15`, `0-30/10`) and comma lists. Day-of-week runs
+/// 0-7 with both 0 and 7 meaning Sunday. As in classic cron, when both day
+/// fields are restricted a day matching either one fires. `@hourly`,
+/// `@daily`, `@weekly`, `@monthly` and `@yearly` are accepted as shorthands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("expected 5 cron fields, got {}", fields.len()));
+        };
+        let mut weekdays = parse_cron_field(weekday, 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        let schedule = Self {
+            minutes: parse_cron_field(minute, 0, 59)?,
+            hours: parse_cron_field(hour, 0, 23)?,
+            days: parse_cron_field(day, 1, 31)?,
+            months: parse_cron_field(month, 1, 12)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        };
+        if !schedule.can_fire() {
+            return Err(format!("cron expression '{}' never fires", expression));
+        }
+        Ok(schedule)
+    }
+
+    /// The first matching minute strictly after `after` (Unix seconds).
+    pub fn next_after(&self, after: i64) -> Option<i64> {
+        let start = DateTime::from_timestamp((after.div_euclid(60) + 1) * 60, 0)?.naive_utc();
+        let mut date = start.date();
+        for _ in 0..MAX_CRON_SEARCH_DAYS {
+            if self.matches_date(date) {
+                let first_day = date == start.date();
+                for hour in 0..24 {
+                    if !bit(self.hours, hour) || (first_day && hour < start.hour()) {
+                        continue;
+                    }
+                    let same_hour = first_day && hour == start.hour();
+                    for minute in 0..60 {
+                        if bit(self.minutes, minute) && !(same_hour && minute < start.minute()) {
+                            return Some(date.and_hms_opt(hour, minute, 0)?.and_utc().timestamp());
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !bit(self.months, date.month()) {
+            return false;
+        }
+        let day = bit(self.days, date.day());
+        let weekday = bit(self.weekdays, date.weekday().num_days_from_sunday());
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+
+    /// Rejects day/month combinations that never exist, like `30 2`.
+    fn can_fire(&self) -> bool {
+        const DAYS_IN_MONTH: [u32; 12] = [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+        if !self.any_weekday {
+            return true;
+        }
+        (1..=12).any(|month| {
+            bit(self.months, month)
+                && (1..=DAYS_IN_MONTH[month as usize - 1]).any(|day| bit(self.days, day))
+        })
+    }
+}
+
+fn bit(set: u64, n: u32) -> bool {
+    set & (1 << n) != 0
+}
+
+/// Parses one cron field into a bit set of the values it matches.
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let number = |s: &str| {
+        s.parse::<u32>()
+            .map_err(|_| format!("invalid value '{}' in cron field '{}'", s, field))
+    };
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match number(step)? {
+                0 => return Err(format!("zero step in cron field '{}'", field)),
+                step => (range, step),
+            },
+            None => (part, 1),
+        };
+        let (low, high) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((low, high)) => (number(low)?, number(high)?),
+            // `5/10` means "from 5 in steps of 10"
+            None if step > 1 => (number(range)?, max),
+            None => {
+                let value = number(range)?;
+                (value, value)
+            }
+        };
+        if low < min || high > max || low > high {
+            return Err(format!(
+                "cron field '{}' is outside {}-{}",
+                field, min, max
+            ));
+        }
+        for value in (low..=high).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+/// Delay before the next attempt after `failures` consecutive failures:
+/// one minute, doubling each time, capped at six hours.
+pub fn failure_backoff_secs(failures: u32) -> i64 {
+    let doublings = failures.saturating_sub(1).min(16);
+    (BASE_BACKOFF_SECS << doublings).min(MAX_BACKOFF_SECS)
+}
+
+/// A stored job and its run state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    pub plugin_id: String,
+    pub job_id: String,
+    pub schedule: JobSchedule,
+    /// Unix time (seconds) the job is next due.
+    pub next_run_at: i64,
+    pub last_run_at: Option<i64>,
+    /// Failed runs since the last successful one.
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+}
+
+impl ScheduledJob {
+    /// Whether the job should run at `now`.
+    pub fn is_due(&self, now: i64) -> bool {
+        self.next_run_at <= now
+    }
+
+    /// Records a successful run and moves on to the next regular time.
+    pub fn record_success(&mut self, now: i64) {
+        self.last_run_at = Some(now);
+        self.consecutive_failures = 0;
+        self.last_error = None;
+        self.next_run_at = self.schedule.next_after(now).unwrap_or(i64::MAX);
+    }
+
+    /// Records a failed run. The next run is the later of the regular
+    /// time and the backoff delay.
+    pub fn record_failure(&mut self, now: i64, error: String) {
+        self.last_run_at = Some(now);
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        self.last_error = Some(error);
+        let regular = self.schedule.next_after(now).unwrap_or(i64::MAX);
+        self.next_run_at = regular.max(now + failure_backoff_secs(self.consecutive_failures));
+    }
+
+    fn to_entity(&self) -> Result<Entity, PluginHostError> {
+        let mut data = serde_json::to_value(self)?;
+        if let Some(obj) = data.as_object_mut() {
+            obj.insert("local_only".into(), serde_json::Value::Bool(true));
+        }
+        let now = chrono::Utc::now().timestamp();
+        Ok(Entity {
+            id: job_entity_id(&self.plugin_id, &self.job_id),
+            entity_type: SCHEDULED_JOB_ENTITY_TYPE.to_string(),
+            data,
+            created_at: now,
+            modified_at: now,
+            created_by: self.plugin_id.clone(),
+        })
+    }
+
+    fn from_entity(entity: &Entity) -> Option<Self> {
+        serde_json::from_value(entity.data.clone()).ok()
+    }
+}
+
+fn job_entity_id(plugin_id: &str, job_id: &str) -> String {
+    format!("plugin-job:{}:{}", plugin_id, job_id)
+}
+
+fn validate_job_id(job_id: &str) -> Result<(), PluginHostError> {
+    let valid = !job_id.is_empty()
+        && job_id.len() <= MAX_JOB_ID_LEN
+        && job_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(PluginHostError::InvalidJob(format!(
+            "job id '{}' must be 1-{} characters of [A-Za-z0-9._-]",
+            job_id, MAX_JOB_ID_LEN
+        )))
+    }
+}
+
+/// Jobs of one plugin, backed by the entity store.
+pub struct PluginJobs<'a> {
+    entity_store: &'a EntityStore,
+    plugin_id: &'a str,
+}
+
+impl<'a> PluginJobs<'a> {
+    pub fn new(entity_store: &'a EntityStore, plugin_id: &'a str) -> Self {
+        Self {
+            entity_store,
+            plugin_id,
+        }
+    }
+
+    /// Registers `job_id`, replacing an existing job with the same id.
+    /// The first run is one period from `now`.
+    pub fn schedule(
+        &self,
+        job_id: &str,
+        schedule: JobSchedule,
+        now: i64,
+    ) -> Result<ScheduledJob, PluginHostError> {
+        validate_job_id(job_id)?;
+        schedule.validate().map_err(PluginHostError::InvalidJob)?;
+        let jobs = self.list()?;
+        if jobs.len() >= MAX_JOBS_PER_PLUGIN && !jobs.iter().any(|j| j.job_id == job_id) {
+            return Err(PluginHostError::ResourceLimitExceeded {
+                plugin_id: self.plugin_id.to_string(),
+                detail: format!("at most {} scheduled jobs", MAX_JOBS_PER_PLUGIN),
+            });
+        }
+        let next_run_at = schedule.next_after(now).ok_or_else(|| {
+            PluginHostError::InvalidJob(format!("job '{}' has no upcoming run", job_id))
+        })?;
+        let job = ScheduledJob {
+            plugin_id: self.plugin_id.to_string(),
+            job_id: job_id.to_string(),
+            schedule,
+            next_run_at,
+            last_run_at: None,
+            consecutive_failures: 0,
+            last_error: None,
+        };
+        self.save(&job)?;
+        Ok(job)
+    }
+
+    pub fn get(&self, job_id: &str) -> Result<Option<ScheduledJob>, PluginHostError> {
+        let entity = self
+            .entity_store
+            .get_entity(&job_entity_id(self.plugin_id, job_id))
+            .map_err(|e| PluginHostError::Storage(e.to_string()))?;
+        Ok(entity.as_ref().and_then(ScheduledJob::from_entity))
+    }
+
+    /// Stores the job's schedule and run state.
+    pub fn save(&self, job: &ScheduledJob) -> Result<(), PluginHostError> {
+        self.entity_store
+            .save_entity_raw(&job.to_entity()?)
+            .map_err(|e| PluginHostError::Storage(e.to_string()))
+    }
+
+    /// Removes `job_id`. Returns false if it did not exist.
+    pub fn cancel(&self, job_id: &str) -> Result<bool, PluginHostError> {
+        if self.get(job_id)?.is_none() {
+            return Ok(false);
+        }
+        self.entity_store
+            .delete_entity(&job_entity_id(self.plugin_id, job_id))
+            .map_err(|e| PluginHostError::Storage(e.to_string()))?;
+        Ok(true)
+    }
+
+    /// Every job of the plugin, sorted by id.
+    pub fn list(&self) -> Result<Vec<ScheduledJob>, PluginHostError> {
+        let filters = [(
+            "plugin_id".to_string(),
+            serde_json::Value::String(self.plugin_id.to_string()),
+        )];
+        let entities = self
+            .entity_store
+            .query_entities(SCHEDULED_JOB_ENTITY_TYPE, &filters, false, None)
+            .map_err(|e| PluginHostError::Storage(e.to_string()))?;
+        let mut jobs: Vec<ScheduledJob> =
+            entities.iter().filter_map(ScheduledJob::from_entity).collect();
+        jobs.sort_by(|a, b| a.job_id.cmp(&b.job_id));
+        Ok(jobs)
+    }
+
+    /// Removes every job of the plugin. Returns how many were removed.
+    pub fn clear(&self) -> Result<usize, PluginHostError> {
+        let jobs = self.list()?;
+        for job in &jobs {
+            self.cancel(&job.job_id)?;
+        }
+        Ok(jobs.len())
+    }
+}
+
+/// Jobs of all plugins due at `now`, most overdue first.
+pub fn due_jobs(
+    entity_store: &EntityStore,
+    now: i64,
+) -> Result<Vec<ScheduledJob>, PluginHostError> {
+    let entities = entity_store
+        .list_entities(SCHEDULED_JOB_ENTITY_TYPE, false, None, None)
+        .map_err(|e| PluginHostError::Storage(e.to_string()))?;
+    let mut due: Vec<ScheduledJob> = entities
+        .iter()
+        .filter_map(ScheduledJob::from_entity)
+        .filter(|job| job.is_due(now))
+        .collect();
+    due.sort_by_key(|job| job.next_run_at);
+    Ok(due)
+}
+
+/// Loads the component's `scheduled-task` export from its main instance,
+/// or returns `None` if it does not export one.
+pub(crate) fn bind_scheduled_task(
+    engine: &Engine,
+    component: &Component,
+    store: &mut Store<PluginState>,
+    instance: &Instance,
+) -> wasmtime::Result<Option<ScheduledTaskExports>> {
+    let exported = component
+        .component_type()
+        .exports(engine)
+        .any(|(name, _)| name.starts_with(SCHEDULED_TASK_EXPORT));
+    if !exported {
+        return Ok(None);
+    }
+    ScheduledTaskExports::new(store, instance).map(Some)
+}
+
+/// Calls `on-scheduled` for one job. The inner result is the plugin's own
+/// verdict on the run.
+pub(crate) fn call_on_scheduled(
+    task: &ScheduledTaskExports,
+    store: &mut Store<PluginState>,
+    job_id: &str,
+) -> wasmtime::Result<Result<(), String>> {
+    task.privstack_plugin_scheduled_task().call_on_scheduled(store, job_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(s: &str) -> i64 {
+        DateTime::parse_from_rfc3339(s).unwrap().timestamp()
+    }
+
+    fn next(expression: &str, after: &str) -> i64 {
+        CronSchedule::parse(expression).unwrap().next_after(ts(after)).unwrap()
+    }
+
+    #[test]
+    fn cron_next_run() {
+        assert_eq!(next("*/15 * * * *", "2025-03-10T10:07:30Z"), ts("2025-03-10T10:15:00Z"));
+        assert_eq!(next("0 9 * * 1-5", "2025-03-07T09:00:00Z"), ts("2025-03-10T09:00:00Z"));
+        assert_eq!(next("30 2 1 * *", "2025-01-31T12:00:00Z"), ts("2025-02-01T02:30:00Z"));
+        assert_eq!(next("@daily", "2025-12-31T23:59:59Z"), ts("2026-01-01T00:00:00Z"));
+        assert_eq!(next("0 0 29 2 *", "2025-03-01T00:00:00Z"), ts("2028-02-29T00:00:00Z"));
+    }
+
+    #[test]
+    fn cron_next_run_is_strictly_later() {
+        assert_eq!(next("0 * * * *", "2025-03-10T10:00:00Z"), ts("2025-03-10T11:00:00Z"));
+    }
+
+    #[test]
+    fn cron_day_fields_match_either_when_both_restricted() {
+        // The 13th, or any Friday
+        assert_eq!(next("0 0 13 * 5", "2025-06-01T00:00:00Z"), ts("2025-06-06T00:00:00Z"));
+        // Sunday as 7
+        assert_eq!(next("0 0 * * 7", "2025-06-01T00:00:00Z"), ts("2025-06-08T00:00:00Z"));
+    }
+
+    #[test]
+    fn cron_rejects_bad_expressions() {
+        for expression in ["* * * *", "60 * * * *", "*/0 * * * *", "a * * * *", "0 0 30 2 *"] {
+            assert!(CronSchedule::parse(expression).is_err(), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn interval_must_be_at_least_a_minute() {
+        assert!(JobSchedule::Interval { secs: 59 }.validate().is_err());
+        assert!(JobSchedule::Interval { secs: 60 }.validate().is_ok());
+    }
+
+    #[test]
+    fn failures_back_off_exponentially() {
+        assert_eq!(failure_backoff_secs(1), 60);
+        assert_eq!(failure_backoff_secs(3), 240);
+        assert_eq!(failure_backoff_secs(100), MAX_BACKOFF_SECS);
+
+        let mut job = ScheduledJob {
+            plugin_id: "p1".into(),
+            job_id: "sync".into(),
+            schedule: JobSchedule::Interval { secs: 60 },
+            next_run_at: 0,
+            last_run_at: None,
+            consecutive_failures: 0,
+            last_error: None,
+        };
+        for _ in 0..4 {
+            job.record_failure(1_000, "boom".into());
+        }
+        assert_eq!(job.next_run_at, 1_000 + 480);
+        job.record_success(2_000);
+        assert_eq!(job.consecutive_failures, 0);
+        assert_eq!(job.next_run_at, 2_060);
+    }
+
+    #[test]
+    fn jobs_persist_and_are_scoped_per_plugin() {
+        let store = EntityStore::open_in_memory().unwrap();
+        let jobs = PluginJobs::new(&store, "p1");
+        jobs.schedule("feeds", JobSchedule::Interval { secs: 600 }, 1_000).unwrap();
+        jobs.schedule("digest", JobSchedule::Cron { expression: "@daily".into() }, 1_000)
+            .unwrap();
+        PluginJobs::new(&store, "p2")
+            .schedule("feeds", JobSchedule::Interval { secs: 60 }, 1_000)
+            .unwrap();
+
+        let listed: Vec<String> = jobs.list().unwrap().into_iter().map(|j| j.job_id).collect();
+        assert_eq!(listed, ["digest", "feeds"]);
+
+        let due = due_jobs(&store, 1_600).unwrap();
+        assert_eq!(due.len(), 2);
+        assert_eq!(due[0].plugin_id, "p2");
+
+        assert!(jobs.cancel("feeds").unwrap());
+        assert!(!jobs.cancel("feeds").unwrap());
+        assert_eq!(jobs.clear().unwrap(), 1);
+        assert_eq!(PluginJobs::new(&store, "p2").list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn job_ids_and_limits_are_enforced() {
+        let store = EntityStore::open_in_memory().unwrap();
+        let jobs = PluginJobs::new(&store, "p1");
+        let every_minute = || JobSchedule::Interval { secs: 60 };
+        assert!(matches!(
+            jobs.schedule("bad id", every_minute(), 0),
+            Err(PluginHostError::InvalidJob(_))
+        ));
+        for i in 0..MAX_JOBS_PER_PLUGIN {
+            jobs.schedule(&format!("job-{}", i), every_minute(), 0).unwrap();
+        }
+        assert!(matches!(
+            jobs.schedule("one-more", every_minute(), 0),
+            Err(PluginHostError::ResourceLimitExceeded { .. })
+        ));
+        // Replacing an existing job is still allowed
+        jobs.schedule("job-0", every_minute(), 0).unwrap();
+    }
+}
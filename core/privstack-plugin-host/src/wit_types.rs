@@ -59,6 +59,8 @@ pub struct WitIndexedField {
     pub searchable: bool,
     pub vector_dim: Option<u16>,
     pub enum_options: Option<Vec<String>>,
+    #[serde(default)]
+    pub sensitivity: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -240,6 +242,7 @@ impl WitIndexedField {
             searchable: self.searchable,
             vector_dim: self.vector_dim,
             enum_options: self.enum_options.clone(),
+            sensitivity: self.sensitivity.clone(),
         })
     }
 }
@@ -283,6 +286,7 @@ mod tests {
                     searchable: true,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
                 WitIndexedField {
                     field_path: "/tags".into(),
@@ -290,6 +294,7 @@ mod tests {
                     searchable: true,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
             ],
             merge_strategy: WitMergeStrategy::LwwPerField,
@@ -308,12 +313,27 @@ mod tests {
             searchable: false,
             vector_dim: Some(384),
             enum_options: None,
+            sensitivity: None,
         };
         let core = field.to_core_field().unwrap();
         assert_eq!(core.field_type, privstack_model::FieldType::Vector);
         assert_eq!(core.vector_dim, Some(384));
     }
 
+    #[test]
+    fn sensitivity_label_carries_to_core() {
+        let field = WitIndexedField {
+            field_path: "/amount".into(),
+            field_type: WitFieldType::Decimal,
+            searchable: false,
+            vector_dim: None,
+            enum_options: None,
+            sensitivity: Some("financial".into()),
+        };
+        let core = field.to_core_field().unwrap();
+        assert_eq!(core.sensitivity.as_deref(), Some("financial"));
+    }
+
     // ================================================================
     // WitSdkResponse::permission_denied
     // ================================================================
@@ -412,6 +432,7 @@ mod tests {
                 searchable: false,
                 vector_dim: None,
                 enum_options: None,
+                sensitivity: None,
             };
             let core = field.to_core_field().unwrap();
             assert_eq!(core.field_type, expected_core, "Failed for {:?}", wit_type);
@@ -577,6 +598,7 @@ mod tests {
                 searchable: true,
                 vector_dim: None,
                 enum_options: Some(vec!["low".into(), "medium".into(), "high".into()]),
+                sensitivity: None,
             }],
             merge_strategy: WitMergeStrategy::LwwPerField,
         };
@@ -37,6 +37,7 @@ fn test_schemas() -> Vec<WitEntitySchema> {
             searchable: true,
             vector_dim: None,
             enum_options: None,
+            sensitivity: None,
         }],
         merge_strategy: WitMergeStrategy::LwwPerField,
     }]
@@ -204,7 +205,7 @@ fn update_permissions_grants_new_access() {
 
     let mut perms = PermissionSet::default_first_party();
     perms.grant(Permission::Vault);
-    sandbox.update_permissions(perms);
+    sandbox.update_permissions(perms).unwrap();
 
     assert!(sandbox.check_vault_access().is_ok());
 }
@@ -214,7 +215,7 @@ fn update_permissions_can_revoke_access() {
     let mut sandbox = make_sandbox(PermissionSet::all_granted());
     assert!(sandbox.check_vault_access().is_ok());
 
-    sandbox.update_permissions(PermissionSet::default_first_party());
+    sandbox.update_permissions(PermissionSet::default_first_party()).unwrap();
     assert!(sandbox.check_vault_access().is_err());
 }
 
@@ -234,6 +235,7 @@ fn multiple_entity_types_scoping() {
                 searchable: true,
                 vector_dim: None,
                 enum_options: None,
+                sensitivity: None,
             }],
             merge_strategy: WitMergeStrategy::LwwPerField,
         },
@@ -245,6 +247,7 @@ fn multiple_entity_types_scoping() {
                 searchable: true,
                 vector_dim: None,
                 enum_options: None,
+                sensitivity: None,
             }],
             merge_strategy: WitMergeStrategy::LwwDocument,
         },
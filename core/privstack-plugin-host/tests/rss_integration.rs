@@ -40,6 +40,7 @@ fn rss_schemas() -> Vec<WitEntitySchema> {
                     searchable: true,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
                 WitIndexedField {
                     field_path: "/url".into(),
@@ -47,6 +48,7 @@ fn rss_schemas() -> Vec<WitEntitySchema> {
                     searchable: false,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
                 WitIndexedField {
                     field_path: "/description".into(),
@@ -54,6 +56,7 @@ fn rss_schemas() -> Vec<WitEntitySchema> {
                     searchable: true,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
                 WitIndexedField {
                     field_path: "/tags".into(),
@@ -61,6 +64,7 @@ fn rss_schemas() -> Vec<WitEntitySchema> {
                     searchable: true,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
                 WitIndexedField {
                     field_path: "/last_fetched".into(),
@@ -68,6 +72,7 @@ fn rss_schemas() -> Vec<WitEntitySchema> {
                     searchable: false,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
                 WitIndexedField {
                     field_path: "/created_at".into(),
@@ -75,6 +80,7 @@ fn rss_schemas() -> Vec<WitEntitySchema> {
                     searchable: false,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
             ],
             merge_strategy: WitMergeStrategy::LwwPerField,
@@ -88,6 +94,7 @@ fn rss_schemas() -> Vec<WitEntitySchema> {
                     searchable: true,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
                 WitIndexedField {
                     field_path: "/summary".into(),
@@ -95,6 +102,7 @@ fn rss_schemas() -> Vec<WitEntitySchema> {
                     searchable: true,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
                 WitIndexedField {
                     field_path: "/author".into(),
@@ -102,6 +110,7 @@ fn rss_schemas() -> Vec<WitEntitySchema> {
                     searchable: true,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
                 WitIndexedField {
                     field_path: "/url".into(),
@@ -109,6 +118,7 @@ fn rss_schemas() -> Vec<WitEntitySchema> {
                     searchable: false,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
                 WitIndexedField {
                     field_path: "/feed_id".into(),
@@ -116,6 +126,7 @@ fn rss_schemas() -> Vec<WitEntitySchema> {
                     searchable: false,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
                 WitIndexedField {
                     field_path: "/read".into(),
@@ -123,6 +134,7 @@ fn rss_schemas() -> Vec<WitEntitySchema> {
                     searchable: false,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
                 WitIndexedField {
                     field_path: "/starred".into(),
@@ -130,6 +142,7 @@ fn rss_schemas() -> Vec<WitEntitySchema> {
                     searchable: false,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
                 WitIndexedField {
                     field_path: "/published_at".into(),
@@ -137,6 +150,7 @@ fn rss_schemas() -> Vec<WitEntitySchema> {
                     searchable: false,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
                 WitIndexedField {
                     field_path: "/fetched_at".into(),
@@ -144,6 +158,7 @@ fn rss_schemas() -> Vec<WitEntitySchema> {
                     searchable: false,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 },
             ],
             merge_strategy: WitMergeStrategy::LwwPerField,
@@ -426,6 +441,7 @@ fn rss_isolated_from_other_plugins() {
                     searchable: true,
                     vector_dim: None,
                     enum_options: None,
+                    sensitivity: None,
                 }],
                 merge_strategy: WitMergeStrategy::LwwPerField,
             }],
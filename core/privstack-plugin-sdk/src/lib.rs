@@ -65,7 +65,10 @@ pub use types::*;
 /// privstack_plugin_sdk::privstack_wasm_export!(MyPlugin, [LinkableItemProvider]);
 /// ```
 ///
-/// The plugin type must implement `Default` and `Plugin`.
+/// The plugin type must implement `Default` and `Plugin`. `EntityHandler`,
+/// `EntityObserver`, `ScheduledTask` and `EventSubscriber` are not part of
+/// `plugin-world`: listing one generates bindings for its export and adds
+/// it to the component; no stubs are generated for them.
 #[macro_export]
 macro_rules! privstack_wasm_export {
     // Entry: no capabilities
@@ -165,6 +168,7 @@ macro_rules! privstack_wasm_export {
                             searchable: f.searchable,
                             vector_dim: f.vector_dim,
                             enum_options: f.enum_options,
+                            sensitivity: f.sensitivity,
                         })
                         .collect(),
                     merge_strategy: match s.merge_strategy {
@@ -269,6 +273,10 @@ macro_rules! privstack_wasm_export {
             $crate::__pws_timer_impl!(PluginExports, $plugin_ty, [$($cap),*]);
             $crate::__pws_shutdown_impl!(PluginExports, $plugin_ty, [$($cap),*]);
             $crate::__pws_template_data_impl!(PluginExports, $plugin_ty, [$($cap),*]);
+            $crate::__pws_entity_handler_impl!(PluginExports, $plugin_ty, [$($cap),*]);
+            $crate::__pws_entity_observer_impl!(PluginExports, $plugin_ty, [$($cap),*]);
+            $crate::__pws_scheduled_task_impl!(PluginExports, $plugin_ty, [$($cap),*]);
+            $crate::__pws_event_subscriber_impl!(PluginExports, $plugin_ty, [$($cap),*]);
         }
 
         // Wire up the export! call
@@ -452,3 +460,158 @@ macro_rules! __pws_template_data_impl {
         }
     };
 }
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pws_entity_handler_impl {
+    // Found the flag — real delegation
+    ($exports:ident, $plugin_ty:ty, [EntityHandler $(, $rest:ident)*]) => {
+        mod wit_gen_entity_handler {
+            wit_bindgen::generate!({
+                path: "../wit",
+                world: "entity-handler-exports",
+                generate_all,
+            });
+        }
+        use wit_gen_entity_handler::exports::privstack::plugin::entity_handler as wit_entity_handler;
+
+        impl wit_entity_handler::Guest for $exports {
+            fn validate(entity_type: String, entity_json: String) -> Result<(), String> {
+                with_plugin(|p| $crate::EntityHandler::validate(p, &entity_type, &entity_json))
+            }
+            fn merge(
+                entity_type: String,
+                local_json: String,
+                remote_json: String,
+            ) -> Result<String, String> {
+                with_plugin(|p| {
+                    $crate::EntityHandler::merge(p, &entity_type, &local_json, &remote_json)
+                })
+            }
+            fn on_after_load(entity_type: String, entity_json: String) -> Option<String> {
+                with_plugin(|p| $crate::EntityHandler::on_after_load(p, &entity_type, &entity_json))
+            }
+        }
+        wit_gen_entity_handler::export!($exports with_types_in wit_gen_entity_handler);
+    };
+    // Skip non-matching flag, keep searching
+    ($exports:ident, $plugin_ty:ty, [$other:ident $(, $rest:ident)*]) => {
+        $crate::__pws_entity_handler_impl!($exports, $plugin_ty, [$($rest),*]);
+    };
+    // Empty list — not exported, nothing to implement
+    ($exports:ident, $plugin_ty:ty, []) => {};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pws_entity_observer_impl {
+    // Found the flag — real delegation
+    ($exports:ident, $plugin_ty:ty, [EntityObserver $(, $rest:ident)*]) => {
+        mod wit_gen_entity_observer {
+            wit_bindgen::generate!({
+                path: "../wit",
+                world: "entity-observer-exports",
+                generate_all,
+            });
+        }
+        use wit_gen_entity_observer::exports::privstack::plugin::entity_observer as wit_entity_observer;
+
+        impl wit_entity_observer::Guest for $exports {
+            fn on_entities_changed(changes: Vec<wit_entity_observer::EntityChange>) {
+                let changes: Vec<$crate::EntityChange> = changes
+                    .into_iter()
+                    .map(|c| $crate::EntityChange {
+                        entity_id: c.entity_id,
+                        entity_type: c.entity_type,
+                        kind: match c.kind {
+                            wit_entity_observer::ChangeKind::Created => {
+                                $crate::EntityChangeKind::Created
+                            }
+                            wit_entity_observer::ChangeKind::Updated => {
+                                $crate::EntityChangeKind::Updated
+                            }
+                            wit_entity_observer::ChangeKind::Deleted => {
+                                $crate::EntityChangeKind::Deleted
+                            }
+                        },
+                        source: c.source,
+                    })
+                    .collect();
+                with_plugin_mut(|p| $crate::EntityObserver::on_entities_changed(p, &changes))
+            }
+        }
+        wit_gen_entity_observer::export!($exports with_types_in wit_gen_entity_observer);
+    };
+    // Skip non-matching flag, keep searching
+    ($exports:ident, $plugin_ty:ty, [$other:ident $(, $rest:ident)*]) => {
+        $crate::__pws_entity_observer_impl!($exports, $plugin_ty, [$($rest),*]);
+    };
+    // Empty list — not exported, nothing to implement
+    ($exports:ident, $plugin_ty:ty, []) => {};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pws_scheduled_task_impl {
+    // Found the flag — real delegation
+    ($exports:ident, $plugin_ty:ty, [ScheduledTask $(, $rest:ident)*]) => {
+        mod wit_gen_scheduled_task {
+            wit_bindgen::generate!({
+                path: "../wit",
+                world: "scheduled-task-exports",
+                generate_all,
+            });
+        }
+        use wit_gen_scheduled_task::exports::privstack::plugin::scheduled_task as wit_scheduled_task;
+
+        impl wit_scheduled_task::Guest for $exports {
+            fn on_scheduled(job_id: String) -> Result<(), String> {
+                with_plugin_mut(|p| $crate::ScheduledTask::on_scheduled(p, &job_id))
+            }
+        }
+        wit_gen_scheduled_task::export!($exports with_types_in wit_gen_scheduled_task);
+    };
+    // Skip non-matching flag, keep searching
+    ($exports:ident, $plugin_ty:ty, [$other:ident $(, $rest:ident)*]) => {
+        $crate::__pws_scheduled_task_impl!($exports, $plugin_ty, [$($rest),*]);
+    };
+    // Empty list — not exported, nothing to implement
+    ($exports:ident, $plugin_ty:ty, []) => {};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pws_event_subscriber_impl {
+    // Found the flag — real delegation
+    ($exports:ident, $plugin_ty:ty, [EventSubscriber $(, $rest:ident)*]) => {
+        mod wit_gen_event_subscriber {
+            wit_bindgen::generate!({
+                path: "../wit",
+                world: "event-subscriber-exports",
+                generate_all,
+            });
+        }
+        use wit_gen_event_subscriber::exports::privstack::plugin::event_subscriber as wit_event_subscriber;
+
+        impl wit_event_subscriber::Guest for $exports {
+            fn on_events(events: Vec<wit_event_subscriber::BusEvent>) {
+                let events: Vec<$crate::BusEvent> = events
+                    .into_iter()
+                    .map(|e| $crate::BusEvent {
+                        publisher_id: e.publisher_id,
+                        topic: e.topic,
+                        payload_json: e.payload_json,
+                    })
+                    .collect();
+                with_plugin_mut(|p| $crate::EventSubscriber::on_events(p, &events))
+            }
+        }
+        wit_gen_event_subscriber::export!($exports with_types_in wit_gen_event_subscriber);
+    };
+    // Skip non-matching flag, keep searching
+    ($exports:ident, $plugin_ty:ty, [$other:ident $(, $rest:ident)*]) => {
+        $crate::__pws_event_subscriber_impl!($exports, $plugin_ty, [$($rest),*]);
+    };
+    // Empty list — not exported, nothing to implement
+    ($exports:ident, $plugin_ty:ty, []) => {};
+}
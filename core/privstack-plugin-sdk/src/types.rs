@@ -97,6 +97,8 @@ pub struct IndexedField {
     pub searchable: bool,
     pub vector_dim: Option<u16>,
     pub enum_options: Option<Vec<String>>,
+    #[serde(default)]
+    pub sensitivity: Option<String>,
 }
 
 impl IndexedField {
@@ -108,6 +110,7 @@ impl IndexedField {
             searchable,
             vector_dim: None,
             enum_options: None,
+            sensitivity: None,
         }
     }
 
@@ -119,6 +122,7 @@ impl IndexedField {
             searchable: true,
             vector_dim: None,
             enum_options: None,
+            sensitivity: None,
         }
     }
 
@@ -130,6 +134,7 @@ impl IndexedField {
             searchable: false,
             vector_dim: None,
             enum_options: None,
+            sensitivity: None,
         }
     }
 
@@ -141,6 +146,7 @@ impl IndexedField {
             searchable: false,
             vector_dim: None,
             enum_options: None,
+            sensitivity: None,
         }
     }
 
@@ -152,6 +158,7 @@ impl IndexedField {
             searchable: false,
             vector_dim: None,
             enum_options: None,
+            sensitivity: None,
         }
     }
 
@@ -163,6 +170,7 @@ impl IndexedField {
             searchable: false,
             vector_dim: Some(dim),
             enum_options: None,
+            sensitivity: None,
         }
     }
 
@@ -174,6 +182,7 @@ impl IndexedField {
             searchable: false,
             vector_dim: None,
             enum_options: None,
+            sensitivity: None,
         }
     }
 
@@ -185,6 +194,7 @@ impl IndexedField {
             searchable: false,
             vector_dim: None,
             enum_options: None,
+            sensitivity: None,
         }
     }
 
@@ -196,6 +206,7 @@ impl IndexedField {
             searchable: false,
             vector_dim: None,
             enum_options: None,
+            sensitivity: None,
         }
     }
 
@@ -207,6 +218,7 @@ impl IndexedField {
             searchable: false,
             vector_dim: None,
             enum_options: None,
+            sensitivity: None,
         }
     }
 
@@ -218,6 +230,7 @@ impl IndexedField {
             searchable: false,
             vector_dim: None,
             enum_options: Some(options),
+            sensitivity: None,
         }
     }
 
@@ -229,6 +242,7 @@ impl IndexedField {
             searchable: false,
             vector_dim: None,
             enum_options: None,
+            sensitivity: None,
         }
     }
 
@@ -240,8 +254,16 @@ impl IndexedField {
             searchable: false,
             vector_dim: None,
             enum_options: None,
+            sensitivity: None,
         }
     }
+
+    /// Marks the field with a sensitivity label (e.g. "financial"), so
+    /// enterprise policies can redact it from restricted peers.
+    pub fn with_sensitivity(mut self, label: &str) -> Self {
+        self.sensitivity = Some(label.into());
+        self
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -359,6 +381,41 @@ pub struct TimerResult {
     pub elapsed_ms: u64,
 }
 
+// ---- Entity changes ----
+
+/// Trashing an entity is reported as `Deleted`, restoring it as `Created`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityChangeKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntityChange {
+    pub entity_id: String,
+    pub entity_type: String,
+    pub kind: EntityChangeKind,
+    /// `"host"`, `"sync"`, or the id of the plugin that wrote the entity.
+    pub source: String,
+}
+
+/// An event another plugin published on a topic this plugin subscribed to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BusEvent {
+    pub publisher_id: String,
+    pub topic: String,
+    pub payload_json: String,
+}
+
+impl BusEvent {
+    /// Parses the payload into `T`.
+    pub fn payload<T: serde::de::DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_str(&self.payload_json)
+    }
+}
+
 // ---- Plugin Trait ----
 
 /// Core plugin contract. Every plugin must implement this.
@@ -425,6 +482,52 @@ pub trait TemplateDataProvider {
     }
 }
 
+/// Optional: validation and merge hooks for the plugin's entity types.
+/// The host calls these in a separate instance of the plugin, so they must
+/// depend only on their arguments. Entities arrive as JSON with `id`,
+/// `entity_type`, `data`, `created_at`, `modified_at` and `created_by`.
+pub trait EntityHandler {
+    /// Rejects a write by returning an error message.
+    fn validate(&self, _entity_type: &str, _entity_json: &str) -> Result<(), String> {
+        Ok(())
+    }
+    /// Merges two versions of an entity whose schema uses
+    /// `MergeStrategy::Custom`, returning the merged `data` as JSON.
+    /// An error makes the host fall back to last-writer-wins.
+    fn merge(
+        &self,
+        _entity_type: &str,
+        _local_json: &str,
+        _remote_json: &str,
+    ) -> Result<String, String> {
+        Err("merge not implemented".to_string())
+    }
+    /// Returns replacement `data` JSON for an entity just loaded.
+    fn on_after_load(&self, _entity_type: &str, _entity_json: &str) -> Option<String> {
+        None
+    }
+}
+
+/// Optional: told about entities changed by the host, by sync or by other
+/// plugins. Covers the plugin's own entity types, and every type if it holds
+/// `CrossEntityRead`. Changes arrive debounced, in batches.
+pub trait EntityObserver {
+    fn on_entities_changed(&mut self, changes: &[EntityChange]);
+}
+
+/// Optional: runs jobs the plugin registered through the host `scheduler`
+/// import. An error (or a trap, or overrunning the call timeout) makes the
+/// host retry the job with exponential backoff.
+pub trait ScheduledTask {
+    fn on_scheduled(&mut self, job_id: &str) -> Result<(), String>;
+}
+
+/// Optional: receives events from topics the plugin subscribed to through
+/// the host `events` import, in publish order.
+pub trait EventSubscriber {
+    fn on_events(&mut self, events: &[BusEvent]);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -534,6 +637,9 @@ mod tests {
         assert!(text.searchable);
         assert!(text.vector_dim.is_none());
         assert!(text.enum_options.is_none());
+        assert!(text.sensitivity.is_none());
+        let labelled = IndexedField::decimal("/cost").with_sensitivity("financial");
+        assert_eq!(labelled.sensitivity.as_deref(), Some("financial"));
 
         let tag = IndexedField::tag("/tags");
         assert_eq!(tag.field_type, FieldType::Tag);
@@ -971,4 +1077,115 @@ mod tests {
         let p = TestTemplateProvider;
         assert_eq!(p.get_view_data(), "{}");
     }
+
+    // ── EntityHandler trait defaults ─────────────────────────────────
+
+    struct TestEntityHandler;
+
+    impl EntityHandler for TestEntityHandler {}
+
+    #[test]
+    fn entity_handler_defaults() {
+        let h = TestEntityHandler;
+        assert!(h.validate("note", "{}").is_ok());
+        assert!(h.merge("note", "{}", "{}").is_err());
+        assert!(h.on_after_load("note", "{}").is_none());
+    }
+
+    // ── EntityObserver trait ─────────────────────────────────────────
+
+    #[derive(Default)]
+    struct TestObserver {
+        seen: Vec<EntityChange>,
+    }
+
+    impl EntityObserver for TestObserver {
+        fn on_entities_changed(&mut self, changes: &[EntityChange]) {
+            self.seen.extend_from_slice(changes);
+        }
+    }
+
+    #[test]
+    fn entity_observer_receives_changes() {
+        let mut o = TestObserver::default();
+        let change = EntityChange {
+            entity_id: "n1".into(),
+            entity_type: "note".into(),
+            kind: EntityChangeKind::Updated,
+            source: "sync".into(),
+        };
+        o.on_entities_changed(std::slice::from_ref(&change));
+        assert_eq!(o.seen, vec![change]);
+    }
+
+    #[test]
+    fn entity_change_kind_serde() {
+        let json = serde_json::to_string(&EntityChangeKind::Deleted).unwrap();
+        assert_eq!(json, "\"deleted\"");
+    }
+
+    // ── ScheduledTask trait ──────────────────────────────────────────
+
+    #[derive(Default)]
+    struct TestTask {
+        runs: u32,
+    }
+
+    impl ScheduledTask for TestTask {
+        fn on_scheduled(&mut self, job_id: &str) -> Result<(), String> {
+            match job_id {
+                "refresh" => {
+                    self.runs += 1;
+                    Ok(())
+                }
+                other => Err(format!("unknown job {}", other)),
+            }
+        }
+    }
+
+    #[test]
+    fn scheduled_task_dispatches_by_job_id() {
+        let mut t = TestTask::default();
+        assert!(t.on_scheduled("refresh").is_ok());
+        assert!(t.on_scheduled("other").is_err());
+        assert_eq!(t.runs, 1);
+    }
+
+    // ── EventSubscriber trait ────────────────────────────────────────
+
+    #[derive(Default)]
+    struct TimeTracker {
+        stopped: Vec<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct TaskCompleted {
+        task_id: String,
+    }
+
+    impl EventSubscriber for TimeTracker {
+        fn on_events(&mut self, events: &[BusEvent]) {
+            for event in events.iter().filter(|e| e.topic == "task-completed") {
+                if let Ok(done) = event.payload::<TaskCompleted>() {
+                    self.stopped.push(done.task_id);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn event_subscriber_parses_payloads() {
+        let mut tracker = TimeTracker::default();
+        let event = |topic: &str, payload: &str| BusEvent {
+            publisher_id: "privstack.tasks".into(),
+            topic: topic.into(),
+            payload_json: payload.into(),
+        };
+        tracker.on_events(&[
+            event("task-completed", r#"{"task_id":"t1"}"#),
+            event("task-created", r#"{"task_id":"t2"}"#),
+            event("task-completed", "not json"),
+        ]);
+        assert_eq!(tracker.stopped, ["t1"]);
+    }
 }
@@ -28,9 +28,15 @@ pub enum PpkError {
     #[error("package is not signed")]
     NotSigned,
 
+    #[error("package is not signed by a trusted publisher")]
+    UntrustedSigner,
+
     #[error("invalid public key")]
     InvalidPublicKey,
 
+    #[error("the built-in PrivStack key cannot be changed")]
+    BuiltinKey,
+
     #[error("manifest validation error: {0}")]
     ManifestInvalid(String),
 }
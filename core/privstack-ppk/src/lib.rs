@@ -10,17 +10,27 @@
 //!
 //! Signing: the content hash covers all files except `signature.bin`.
 //! First-party plugins are signed with the PrivStack key.
-//! Third-party plugins are signed with the developer's key.
+//! Third-party plugins are signed with the developer's key, which must be in
+//! the installing device's [`TrustStore`].
 
 mod error;
 mod manifest;
 mod package;
+mod permission;
 mod signing;
+mod trust;
 
 pub use error::PpkError;
-pub use manifest::{PpkManifest, PpkPermission, PpkEntitySchema, PpkIndexedField};
+pub use manifest::{
+    PpkEntitySchema, PpkEventTopic, PpkFilesystemScope, PpkIndexedField, PpkManifest,
+};
 pub use package::{PpkPackage, PackageBuilder, PackageEntry};
+pub use permission::{Permission, PermissionTier};
 pub use signing::{SigningKey, VerifyingKey, Signature, KeyPair};
+pub use trust::{
+    TrustSource, TrustStore, TrustedPublisher, DEV_ROOT_KEY, FIRST_PARTY_ID_PREFIX,
+    PRIVSTACK_ROOT_KEY,
+};
 
 #[cfg(test)]
 mod tests {
@@ -41,8 +51,11 @@ mod tests {
             can_disable: true,
             is_experimental: false,
             min_app_version: None,
-            permissions: vec![PpkPermission::EntityCrud, PpkPermission::ViewState],
+            permissions: vec![Permission::Sdk, Permission::StateNotify],
             schemas: vec![],
+            filesystem: vec![],
+            network_hosts: vec![],
+            event_topics: vec![],
         };
 
         let wasm_bytes = b"fake wasm module content";
@@ -79,8 +92,11 @@ mod tests {
             can_disable: true,
             is_experimental: false,
             min_app_version: None,
-            permissions: vec![PpkPermission::EntityCrud],
+            permissions: vec![Permission::Sdk],
             schemas: vec![],
+            filesystem: vec![],
+            network_hosts: vec![],
+            event_topics: vec![],
         };
 
         let wasm_bytes = b"signed wasm content";
@@ -115,6 +131,9 @@ mod tests {
             min_app_version: None,
             permissions: vec![],
             schemas: vec![],
+            filesystem: vec![],
+            network_hosts: vec![],
+            event_topics: vec![],
         };
 
         let wasm_bytes = b"tampered wasm";
@@ -148,6 +167,9 @@ mod tests {
             min_app_version: None,
             permissions: vec![],
             schemas: vec![],
+            filesystem: vec![],
+            network_hosts: vec![],
+            event_topics: vec![],
         };
 
         let ppk_bytes = PackageBuilder::new(manifest)
@@ -176,10 +198,10 @@ mod tests {
             is_experimental: false,
             min_app_version: Some("0.1.0".into()),
             permissions: vec![
-                PpkPermission::EntityCrud,
-                PpkPermission::EntityQuery,
-                PpkPermission::ViewState,
-                PpkPermission::CommandPalette,
+                Permission::Sdk,
+                Permission::StateNotify,
+                Permission::Vault,
+                Permission::Network,
             ],
             schemas: vec![
                 PpkEntitySchema {
@@ -189,16 +211,21 @@ mod tests {
                             field_path: "/title".into(),
                             field_type: "text".into(),
                             searchable: true,
+                            sensitivity: None,
                         },
                         PpkIndexedField {
                             field_path: "/url".into(),
                             field_type: "text".into(),
                             searchable: false,
+                            sensitivity: Some("personal".into()),
                         },
                     ],
                     merge_strategy: "lww_per_field".into(),
                 },
             ],
+            filesystem: vec![],
+            network_hosts: vec![],
+            event_topics: vec![],
         };
 
         let toml_str = toml::to_string_pretty(&manifest).expect("serialize");
@@ -206,10 +233,11 @@ mod tests {
 
         assert_eq!(manifest.id, parsed.id);
         assert_eq!(manifest.name, parsed.name);
-        assert_eq!(manifest.permissions.len(), parsed.permissions.len());
+        assert_eq!(manifest.permissions, parsed.permissions);
         assert_eq!(manifest.schemas.len(), parsed.schemas.len());
         assert_eq!(manifest.schemas[0].entity_type, parsed.schemas[0].entity_type);
         assert_eq!(manifest.schemas[0].indexed_fields.len(), parsed.schemas[0].indexed_fields.len());
+        assert_eq!(parsed.schemas[0].indexed_fields[1].sensitivity.as_deref(), Some("personal"));
     }
 
     #[test]
@@ -228,6 +256,9 @@ mod tests {
             min_app_version: None,
             permissions: vec![],
             schemas: vec![],
+            filesystem: vec![],
+            network_hosts: vec![],
+            event_topics: vec![],
         };
 
         let wasm = b"deterministic content";
@@ -263,6 +294,9 @@ mod tests {
             min_app_version: None,
             permissions: vec![],
             schemas: vec![],
+            filesystem: vec![],
+            network_hosts: vec![],
+            event_topics: vec![],
         };
 
         let ppk_bytes = PackageBuilder::new(manifest)
@@ -300,22 +334,83 @@ mod tests {
 
     #[test]
     fn permission_serde() {
-        let perms = vec![
-            PpkPermission::EntityCrud,
-            PpkPermission::EntityQuery,
-            PpkPermission::ViewState,
-            PpkPermission::CommandPalette,
-            PpkPermission::VaultAccess,
-            PpkPermission::CrossPluginLink,
-            PpkPermission::DialogDisplay,
-            PpkPermission::TimerAccess,
-            PpkPermission::NetworkAccess,
-        ];
-
-        for perm in &perms {
+        for perm in &Permission::ALL {
             let json = serde_json::to_string(perm).unwrap();
-            let parsed: PpkPermission = serde_json::from_str(&json).unwrap();
+            let parsed: Permission = serde_json::from_str(&json).unwrap();
             assert_eq!(*perm, parsed);
         }
+
+        // Manifests use the host's interface names.
+        let toml_str = r#"
+            id = "community.weather"
+            name = "Weather"
+            description = ""
+            version = "1.0.0"
+            author = ""
+            navigation_order = 1000
+            category = "information"
+            can_disable = true
+            is_experimental = false
+            permissions = ["sdk", "cross-entity-read", "network"]
+        "#;
+        let parsed: PpkManifest = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            parsed.permissions,
+            vec![Permission::Sdk, Permission::CrossEntityRead, Permission::Network]
+        );
+    }
+
+    #[test]
+    fn package_signed_with_legacy_permission_names_still_opens() {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let manifest = r#"
+            id = "community.pomodoro"
+            name = "Pomodoro"
+            description = ""
+            version = "1.0.0"
+            author = ""
+            navigation_order = 1000
+            category = "productivity"
+            can_disable = true
+            is_experimental = false
+            permissions = ["entity_crud", "view_state", "timer_access", "vault_access",
+                           "network_access"]
+            network_hosts = ["api.example.com"]
+        "#;
+        let write_zip = |signature: Option<&[u8]>| {
+            let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+            let options = SimpleFileOptions::default();
+            zip.start_file("manifest.toml", options).unwrap();
+            zip.write_all(manifest.as_bytes()).unwrap();
+            zip.start_file("plugin.wasm", options).unwrap();
+            zip.write_all(b"legacy wasm").unwrap();
+            if let Some(signature) = signature {
+                zip.start_file("signature.bin", options).unwrap();
+                zip.write_all(signature).unwrap();
+            }
+            zip.finish().unwrap().into_inner()
+        };
+
+        // Sign the original manifest bytes, as the old packer did.
+        let keypair = KeyPair::generate();
+        let unsigned = PpkPackage::open(Cursor::new(write_zip(None))).unwrap();
+        let signature = keypair.signing_key.sign(unsigned.content_hash().as_bytes());
+        let signed = write_zip(Some(&signature.to_bytes()));
+
+        let pkg = PpkPackage::open(Cursor::new(&signed)).expect("legacy manifest should parse");
+        assert!(pkg.verify(&keypair.verifying_key).is_ok());
+        assert!(pkg.manifest.validate().is_ok());
+        assert_eq!(
+            pkg.manifest.permissions,
+            vec![
+                Permission::Sdk,
+                Permission::StateNotify,
+                Permission::Sdk,
+                Permission::Vault,
+                Permission::Network,
+            ]
+        );
     }
 }
@@ -2,6 +2,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::Permission;
+
 /// Top-level plugin manifest embedded in every .ppk package.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PpkManifest {
@@ -27,36 +29,58 @@ pub struct PpkManifest {
     pub is_experimental: bool,
     /// Minimum app version required (semver).
     pub min_app_version: Option<String>,
-    /// Permissions requested by this plugin.
+    /// Permissions requested by this plugin. Tier 1 permissions are granted
+    /// whether declared or not; the plugin never gets an undeclared one of a
+    /// higher tier.
     #[serde(default)]
-    pub permissions: Vec<PpkPermission>,
+    pub permissions: Vec<Permission>,
     /// Entity schemas declared by this plugin.
     #[serde(default)]
     pub schemas: Vec<PpkEntitySchema>,
+    /// Directories the plugin asks to access (requires `filesystem`).
+    /// The user picks the actual directory for each scope at install time.
+    #[serde(default)]
+    pub filesystem: Vec<PpkFilesystemScope>,
+    /// Hosts the plugin may fetch from (requires `network`):
+    /// `api.example.com`, `*.example.com` for subdomains, or `*` for any
+    /// public host. With none, every request is refused.
+    #[serde(default)]
+    pub network_hosts: Vec<String>,
+    /// Event bus topics the plugin publishes. Other plugins subscribe to
+    /// them as `<plugin id>/<topic>`, after the user allows it.
+    #[serde(default)]
+    pub event_topics: Vec<PpkEventTopic>,
+}
+
+/// A directory scope declared in the manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PpkFilesystemScope {
+    /// Scope name (lowercase letters, digits, `-`, `_`). The approved
+    /// directory is mounted in the plugin at `/<name>`.
+    pub name: String,
+    /// Why the plugin needs the directory, shown in the install prompt.
+    #[serde(default)]
+    pub description: String,
+    /// Whether the plugin may create, modify and delete files in it.
+    #[serde(default)]
+    pub writable: bool,
 }
 
-/// Capability permission that a plugin can request.
+impl PpkFilesystemScope {
+    /// Path at which the approved directory is mounted in the plugin.
+    pub fn guest_path(&self) -> String {
+        format!("/{}", self.name)
+    }
+}
+
+/// An event bus topic declared in the manifest.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub enum PpkPermission {
-    /// Tier 1: CRUD on declared entity types.
-    EntityCrud,
-    /// Tier 1: Query own entity types.
-    EntityQuery,
-    /// Tier 1: Export view state JSON.
-    ViewState,
-    /// Tier 1: Register commands in the command palette.
-    CommandPalette,
-    /// Tier 2: Access encrypted vault storage (JIT prompt).
-    VaultAccess,
-    /// Tier 2: Link items across plugins (JIT prompt).
-    CrossPluginLink,
-    /// Tier 2: Show modal dialogs (JIT prompt).
-    DialogDisplay,
-    /// Tier 2: Access timer/pomodoro APIs (JIT prompt).
-    TimerAccess,
-    /// Tier 3: Network access (admin approval required).
-    NetworkAccess,
+pub struct PpkEventTopic {
+    /// Topic name (lowercase letters, digits, `.`, `-`, `_`; at most 64).
+    pub name: String,
+    /// What the events carry, shown when another plugin asks to subscribe.
+    #[serde(default)]
+    pub description: String,
 }
 
 /// Entity schema declared in the manifest.
@@ -79,6 +103,10 @@ pub struct PpkIndexedField {
     pub field_type: String,
     /// Whether this field is included in full-text search.
     pub searchable: bool,
+    /// Sensitivity label (e.g., "financial") used to redact the field
+    /// from peers whose role may not see it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sensitivity: Option<String>,
 }
 
 impl PpkManifest {
@@ -99,12 +127,94 @@ impl PpkManifest {
                 "id must use reverse-domain format (e.g., 'privstack.rss')".into(),
             ));
         }
+        self.validate_filesystem()?;
+        self.validate_network_hosts()?;
+        self.validate_event_topics()
+    }
+
+    fn validate_event_topics(&self) -> Result<(), crate::PpkError> {
+        let mut names = std::collections::HashSet::new();
+        for topic in &self.event_topics {
+            let valid_name = !topic.name.is_empty()
+                && topic.name.len() <= 64
+                && topic.name.chars().all(|c| {
+                    c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '-' | '_')
+                });
+            if !valid_name {
+                return Err(crate::PpkError::ManifestInvalid(format!(
+                    "invalid event topic '{}'",
+                    topic.name
+                )));
+            }
+            if !names.insert(topic.name.as_str()) {
+                return Err(crate::PpkError::ManifestInvalid(format!(
+                    "duplicate event topic '{}'",
+                    topic.name
+                )));
+            }
+        }
         Ok(())
     }
 
-    /// Returns true if this is a first-party PrivStack plugin.
-    pub fn is_first_party(&self) -> bool {
-        self.id.starts_with("privstack.")
+    fn validate_network_hosts(&self) -> Result<(), crate::PpkError> {
+        if self.network_hosts.is_empty() {
+            return Ok(());
+        }
+        if !self.permissions.contains(&Permission::Network) {
+            return Err(crate::PpkError::ManifestInvalid(
+                "network_hosts require the network permission".into(),
+            ));
+        }
+        for pattern in &self.network_hosts {
+            let host = pattern.strip_prefix("*.").unwrap_or(pattern);
+            let valid = pattern == "*"
+                || (!host.is_empty()
+                    && !host.starts_with('.')
+                    && !host.ends_with('.')
+                    && !host.contains("..")
+                    && host.chars().all(|c| {
+                        c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.'
+                    }));
+            if !valid {
+                return Err(crate::PpkError::ManifestInvalid(format!(
+                    "invalid network host pattern '{}'",
+                    pattern
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_filesystem(&self) -> Result<(), crate::PpkError> {
+        if self.filesystem.is_empty() {
+            return Ok(());
+        }
+        if !self.permissions.contains(&Permission::Filesystem) {
+            return Err(crate::PpkError::ManifestInvalid(
+                "filesystem scopes require the filesystem permission".into(),
+            ));
+        }
+        let mut names = std::collections::HashSet::new();
+        for scope in &self.filesystem {
+            let valid_name = !scope.name.is_empty()
+                && scope
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+            if !valid_name {
+                return Err(crate::PpkError::ManifestInvalid(format!(
+                    "invalid filesystem scope name '{}'",
+                    scope.name
+                )));
+            }
+            if !names.insert(scope.name.as_str()) {
+                return Err(crate::PpkError::ManifestInvalid(format!(
+                    "duplicate filesystem scope '{}'",
+                    scope.name
+                )));
+            }
+        }
+        Ok(())
     }
 }
 
@@ -128,6 +238,9 @@ mod tests {
             min_app_version: None,
             permissions: vec![],
             schemas: vec![],
+            filesystem: vec![],
+            network_hosts: vec![],
+            event_topics: vec![],
         };
         assert!(m.validate().is_ok());
     }
@@ -148,6 +261,9 @@ mod tests {
             min_app_version: None,
             permissions: vec![],
             schemas: vec![],
+            filesystem: vec![],
+            network_hosts: vec![],
+            event_topics: vec![],
         };
         assert!(m.validate().is_err());
     }
@@ -168,33 +284,119 @@ mod tests {
             min_app_version: None,
             permissions: vec![],
             schemas: vec![],
+            filesystem: vec![],
+            network_hosts: vec![],
+            event_topics: vec![],
         };
         assert!(m.validate().is_err());
     }
 
     #[test]
-    fn is_first_party() {
-        let m = PpkManifest {
-            id: "privstack.rss".into(),
-            name: "RSS".into(),
+    fn validate_filesystem_scopes() {
+        let scope = |name: &str| PpkFilesystemScope {
+            name: name.into(),
+            description: "".into(),
+            writable: false,
+        };
+        let mut m = PpkManifest {
+            id: "community.importer".into(),
+            name: "Importer".into(),
             description: "".into(),
             version: "1.0.0".into(),
-            author: "PrivStack".into(),
+            author: "".into(),
             icon: None,
-            navigation_order: 100,
+            navigation_order: 1000,
             category: "utility".into(),
             can_disable: true,
             is_experimental: false,
             min_app_version: None,
             permissions: vec![],
             schemas: vec![],
+            filesystem: vec![scope("vault")],
+            network_hosts: vec![],
+            event_topics: vec![],
         };
-        assert!(m.is_first_party());
+        // Scopes without the permission
+        assert!(m.validate().is_err());
+
+        m.permissions.push(Permission::Filesystem);
+        assert!(m.validate().is_ok());
+        assert_eq!(m.filesystem[0].guest_path(), "/vault");
 
-        let m2 = PpkManifest {
+        m.filesystem.push(scope("vault"));
+        assert!(m.validate().is_err());
+
+        m.filesystem = vec![scope("../etc")];
+        assert!(m.validate().is_err());
+    }
+
+    #[test]
+    fn validate_network_hosts() {
+        let mut m = PpkManifest {
             id: "community.weather".into(),
-            ..m
+            name: "Weather".into(),
+            description: "".into(),
+            version: "1.0.0".into(),
+            author: "".into(),
+            icon: None,
+            navigation_order: 1000,
+            category: "utility".into(),
+            can_disable: true,
+            is_experimental: false,
+            min_app_version: None,
+            permissions: vec![],
+            schemas: vec![],
+            filesystem: vec![],
+            network_hosts: vec!["api.weather.example".into(), "*.tiles.example".into()],
+            event_topics: vec![],
         };
-        assert!(!m2.is_first_party());
+        // Hosts without the permission
+        assert!(m.validate().is_err());
+
+        m.permissions.push(Permission::Network);
+        assert!(m.validate().is_ok());
+
+        for bad in ["", "*.", "Upper.example", "a..b", "http://x.example", "x.*.example"] {
+            m.network_hosts = vec![bad.into()];
+            assert!(m.validate().is_err(), "{bad}");
+        }
+        m.network_hosts = vec!["*".into()];
+        assert!(m.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_event_topics() {
+        let topic = |name: &str| PpkEventTopic {
+            name: name.into(),
+            description: "".into(),
+        };
+        let mut m = PpkManifest {
+            id: "privstack.tasks".into(),
+            name: "Tasks".into(),
+            description: "".into(),
+            version: "1.0.0".into(),
+            author: "".into(),
+            icon: None,
+            navigation_order: 100,
+            category: "productivity".into(),
+            can_disable: true,
+            is_experimental: false,
+            min_app_version: None,
+            permissions: vec![],
+            schemas: vec![],
+            filesystem: vec![],
+            network_hosts: vec![],
+            event_topics: vec![topic("task-completed"), topic("task.created")],
+        };
+        assert!(m.validate().is_ok());
+
+        m.event_topics.push(topic("task-completed"));
+        assert!(m.validate().is_err());
+
+        let long = "x".repeat(65);
+        for bad in ["", "Task", "tasks/completed", long.as_str()] {
+            m.event_topics = vec![topic(bad)];
+            assert!(m.validate().is_err(), "{bad}");
+        }
     }
 }
@@ -280,6 +280,9 @@ mod tests {
             min_app_version: None,
             permissions: vec![],
             schemas: vec![],
+            filesystem: vec![],
+            network_hosts: vec![],
+            event_topics: vec![],
         }
     }
 
@@ -0,0 +1,234 @@
+//! Capabilities a plugin can hold.
+//!
+//! This is the one permission vocabulary: manifests declare these names and
+//! the plugin host enforces them. Four tiers:
+//! - Tier 1: Always granted (sdk, settings, logger, navigation)
+//! - Tier 2: Just-in-time prompted (linking, dialogs, vault, event-subscribe)
+//! - Tier 3: Install-time reviewed (filesystem, network, agent)
+//! - Tier 4: Enterprise policy overrides
+
+use serde::{Deserialize, Serialize};
+
+/// Individual permission a plugin may hold.
+///
+/// Packages signed before this vocabulary existed declare the older
+/// snake_case names (`entity_crud`, `vault_access`, ...). Those still
+/// deserialize, each to its closest permission here; `timer_access` has no
+/// gated counterpart and maps to `sdk`. Serialization always writes the
+/// current names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Permission {
+    // Tier 1 — always granted
+    #[serde(alias = "entity_crud", alias = "entity_query", alias = "timer_access")]
+    Sdk,
+    Settings,
+    Logger,
+    #[serde(alias = "command_palette")]
+    Navigation,
+    #[serde(alias = "view_state")]
+    StateNotify,
+
+    // Tier 2 — just-in-time
+    #[serde(alias = "cross_plugin_link")]
+    Linking,
+    #[serde(alias = "dialog_display")]
+    Dialogs,
+    #[serde(alias = "vault_access")]
+    Vault,
+    CrossEntityRead,
+    CrossPluginCommand,
+    /// Subscribing to topics other plugins publish on the event bus.
+    EventSubscribe,
+
+    // Tier 3 — install-time
+    #[serde(alias = "filesystem_access")]
+    Filesystem,
+    #[serde(alias = "network_access")]
+    Network,
+    Agent,
+}
+
+impl Permission {
+    /// Every permission, in tier order.
+    pub const ALL: [Permission; 14] = [
+        Self::Sdk,
+        Self::Settings,
+        Self::Logger,
+        Self::Navigation,
+        Self::StateNotify,
+        Self::Linking,
+        Self::Dialogs,
+        Self::Vault,
+        Self::CrossEntityRead,
+        Self::CrossPluginCommand,
+        Self::EventSubscribe,
+        Self::Filesystem,
+        Self::Network,
+        Self::Agent,
+    ];
+
+    /// Returns the tier for this permission.
+    pub fn tier(&self) -> PermissionTier {
+        match self {
+            Self::Sdk | Self::Settings | Self::Logger | Self::Navigation | Self::StateNotify => {
+                PermissionTier::AlwaysGranted
+            }
+            Self::Linking
+            | Self::Dialogs
+            | Self::Vault
+            | Self::CrossEntityRead
+            | Self::CrossPluginCommand
+            | Self::EventSubscribe => PermissionTier::JustInTime,
+            Self::Filesystem | Self::Network | Self::Agent => PermissionTier::InstallTime,
+        }
+    }
+
+    /// Returns the WIT interface name this permission gates.
+    pub fn interface_name(&self) -> &'static str {
+        match self {
+            Self::Sdk => "sdk",
+            Self::Settings => "settings",
+            Self::Logger => "logger",
+            Self::Navigation => "navigation",
+            Self::StateNotify => "state-notify",
+            Self::Linking => "linking",
+            Self::Dialogs => "dialogs",
+            Self::Vault => "vault",
+            Self::CrossEntityRead => "cross-entity-read",
+            Self::CrossPluginCommand => "cross-plugin-command",
+            Self::EventSubscribe => "event-subscribe",
+            Self::Filesystem => "filesystem",
+            Self::Network => "network",
+            Self::Agent => "agent",
+        }
+    }
+
+    /// What the permission allows, worded for the install prompt.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Sdk => "Create, read, update and delete its own items",
+            Self::Settings => "Store its own settings",
+            Self::Logger => "Write to the application log",
+            Self::Navigation => "Open other views",
+            Self::StateNotify => "Refresh its views",
+            Self::Linking => "Link its items with items of other plugins",
+            Self::Dialogs => "Show dialogs",
+            Self::Vault => "Store secrets in the encrypted vault",
+            Self::CrossEntityRead => "Read items of other plugins",
+            Self::CrossPluginCommand => "Run commands of other plugins",
+            Self::EventSubscribe => "Receive events published by other plugins",
+            Self::Filesystem => "Access the folders you choose",
+            Self::Network => "Connect to the hosts it lists",
+            Self::Agent => "Act on your data through the assistant",
+        }
+    }
+}
+
+/// Permission tier classification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PermissionTier {
+    AlwaysGranted,
+    JustInTime,
+    InstallTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permission_tiers() {
+        assert_eq!(Permission::Sdk.tier(), PermissionTier::AlwaysGranted);
+        assert_eq!(Permission::Vault.tier(), PermissionTier::JustInTime);
+        assert_eq!(Permission::Agent.tier(), PermissionTier::InstallTime);
+    }
+
+    // ================================================================
+    // interface_name for all permissions
+    // ================================================================
+
+    #[test]
+    fn all_permission_interface_names() {
+        assert_eq!(Permission::Sdk.interface_name(), "sdk");
+        assert_eq!(Permission::Settings.interface_name(), "settings");
+        assert_eq!(Permission::Logger.interface_name(), "logger");
+        assert_eq!(Permission::Navigation.interface_name(), "navigation");
+        assert_eq!(Permission::StateNotify.interface_name(), "state-notify");
+        assert_eq!(Permission::Linking.interface_name(), "linking");
+        assert_eq!(Permission::Dialogs.interface_name(), "dialogs");
+        assert_eq!(Permission::Vault.interface_name(), "vault");
+        assert_eq!(Permission::CrossEntityRead.interface_name(), "cross-entity-read");
+        assert_eq!(Permission::CrossPluginCommand.interface_name(), "cross-plugin-command");
+        assert_eq!(Permission::EventSubscribe.interface_name(), "event-subscribe");
+        assert_eq!(Permission::Filesystem.interface_name(), "filesystem");
+        assert_eq!(Permission::Network.interface_name(), "network");
+        assert_eq!(Permission::Agent.interface_name(), "agent");
+    }
+
+    #[test]
+    fn serde_name_matches_interface_name() {
+        for permission in Permission::ALL {
+            let json = serde_json::to_string(&permission).unwrap();
+            assert_eq!(json, format!("\"{}\"", permission.interface_name()));
+            let parsed: Permission = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, permission);
+        }
+    }
+
+    #[test]
+    fn legacy_names_deserialize_to_current_permissions() {
+        let cases = [
+            ("entity_crud", Permission::Sdk),
+            ("entity_query", Permission::Sdk),
+            ("timer_access", Permission::Sdk),
+            ("command_palette", Permission::Navigation),
+            ("view_state", Permission::StateNotify),
+            ("cross_plugin_link", Permission::Linking),
+            ("dialog_display", Permission::Dialogs),
+            ("vault_access", Permission::Vault),
+            ("filesystem_access", Permission::Filesystem),
+            ("network_access", Permission::Network),
+        ];
+        for (legacy, expected) in cases {
+            let parsed: Permission = serde_json::from_str(&format!("\"{legacy}\"")).unwrap();
+            assert_eq!(parsed, expected, "{legacy}");
+            let json = serde_json::to_string(&parsed).unwrap();
+            assert_eq!(json, format!("\"{}\"", expected.interface_name()));
+        }
+    }
+
+    // ================================================================
+    // All tier classifications
+    // ================================================================
+
+    #[test]
+    fn all_tier1_permissions() {
+        assert_eq!(Permission::Settings.tier(), PermissionTier::AlwaysGranted);
+        assert_eq!(Permission::Logger.tier(), PermissionTier::AlwaysGranted);
+        assert_eq!(Permission::Navigation.tier(), PermissionTier::AlwaysGranted);
+        assert_eq!(Permission::StateNotify.tier(), PermissionTier::AlwaysGranted);
+    }
+
+    #[test]
+    fn all_tier2_permissions() {
+        assert_eq!(Permission::Linking.tier(), PermissionTier::JustInTime);
+        assert_eq!(Permission::Dialogs.tier(), PermissionTier::JustInTime);
+        assert_eq!(Permission::CrossEntityRead.tier(), PermissionTier::JustInTime);
+        assert_eq!(Permission::CrossPluginCommand.tier(), PermissionTier::JustInTime);
+        assert_eq!(Permission::EventSubscribe.tier(), PermissionTier::JustInTime);
+    }
+
+    #[test]
+    fn all_tier3_permissions() {
+        assert_eq!(Permission::Filesystem.tier(), PermissionTier::InstallTime);
+        assert_eq!(Permission::Network.tier(), PermissionTier::InstallTime);
+    }
+
+    #[test]
+    fn permission_debug() {
+        let perm = Permission::Vault;
+        let debug_str = format!("{:?}", perm);
+        assert!(debug_str.contains("Vault"));
+    }
+}
@@ -7,6 +7,7 @@ use ed25519_dalek::{
     Signature as DalekSignature,
 };
 use rand::rngs::OsRng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::PpkError;
 
@@ -14,6 +15,8 @@ use crate::PpkError;
 pub struct SigningKey(DalekSigningKey);
 
 /// Ed25519 verifying key (public). Used to verify .ppk package signatures.
+/// Serializes as lowercase hex, and deserializes from hex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct VerifyingKey(DalekVerifyingKey);
 
 /// Ed25519 signature bytes.
@@ -72,6 +75,20 @@ impl VerifyingKey {
         self.0.to_bytes()
     }
 
+    /// Parses a public key from 64 hex characters.
+    pub fn from_hex(hex_key: &str) -> Result<Self, PpkError> {
+        let bytes: [u8; 32] = hex::decode(hex_key.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(PpkError::InvalidPublicKey)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Returns the public key as lowercase hex.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.to_bytes())
+    }
+
     /// Verifies a signature against a message.
     pub fn verify(&self, message: &[u8], signature: &Signature) -> Result<(), PpkError> {
         self.0
@@ -80,6 +97,19 @@ impl VerifyingKey {
     }
 }
 
+impl Serialize for VerifyingKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for VerifyingKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex_key = String::deserialize(deserializer)?;
+        Self::from_hex(&hex_key).map_err(serde::de::Error::custom)
+    }
+}
+
 impl Signature {
     /// Creates a signature from raw 64-byte value.
     pub fn from_bytes(bytes: &[u8; 64]) -> Self {
@@ -132,6 +162,16 @@ mod tests {
         assert!(vk.verify(b"test", &sig).is_ok());
     }
 
+    #[test]
+    fn verifying_key_hex_roundtrip() {
+        let kp = KeyPair::generate();
+        let hex_key = kp.verifying_key.to_hex();
+        assert_eq!(hex_key.len(), 64);
+        assert_eq!(VerifyingKey::from_hex(&hex_key).unwrap(), kp.verifying_key);
+        assert!(VerifyingKey::from_hex("abcd").is_err());
+        assert!(VerifyingKey::from_hex("not hex").is_err());
+    }
+
     #[test]
     fn signature_bytes_roundtrip() {
         let kp = KeyPair::generate();
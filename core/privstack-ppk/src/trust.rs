@@ -0,0 +1,309 @@
+//! Publisher keys trusted to sign .ppk packages.
+//!
+//! Every package must be signed by a key in the [`TrustStore`]. The
+//! PrivStack root key is built in; packages it signs are first-party and
+//! are the only ones that may use the reserved `privstack.` id prefix.
+//! Enterprises (through policy) and users may add third-party publishers.
+
+use serde::{Deserialize, Serialize};
+
+use crate::package::PpkPackage;
+use crate::signing::VerifyingKey;
+use crate::PpkError;
+
+/// Public half of the PrivStack plugin signing key.
+///
+/// The private half is the release signing key. It is held offline by the
+/// PrivStack release maintainers, used only to sign first-party `.ppk`
+/// packages (including the plugins bundled with the app, which the shell
+/// installs from their `.ppk` at startup), and never committed to this
+/// repository or given to CI.
+///
+/// Release builds set the `PRIVSTACK_ROOT_KEY` environment variable to the
+/// public half (64 hex characters) at compile time; a malformed value fails
+/// the build. Without it the key is [`DEV_ROOT_KEY`], so a development build
+/// trusts no first-party package and bundled plugins load only in dev mode.
+pub const PRIVSTACK_ROOT_KEY: [u8; 32] = match option_env!("PRIVSTACK_ROOT_KEY") {
+    Some(hex_key) => decode_root_key(hex_key),
+    None => DEV_ROOT_KEY,
+};
+
+/// Root key of development builds: a placeholder whose private half was not
+/// kept, so nothing verifies against it.
+pub const DEV_ROOT_KEY: [u8; 32] = [
+    205, 198, 232, 166, 49, 61, 192, 119, 240, 209, 180, 205, 80, 137, 251, 40,
+    84, 56, 128, 34, 149, 130, 34, 13, 232, 204, 142, 87, 28, 56, 245, 134,
+];
+
+/// Decodes the compile-time root key; panics (failing the build) unless it
+/// is 64 hex characters.
+const fn decode_root_key(hex_key: &str) -> [u8; 32] {
+    const fn nibble(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            b'A'..=b'F' => c - b'A' + 10,
+            _ => panic!("PRIVSTACK_ROOT_KEY must be hex"),
+        }
+    }
+    let bytes = hex_key.as_bytes();
+    if bytes.len() != 64 {
+        panic!("PRIVSTACK_ROOT_KEY must be 64 hex characters");
+    }
+    let mut key = [0u8; 32];
+    let mut i = 0;
+    while i < 32 {
+        key[i] = (nibble(bytes[2 * i]) << 4) | nibble(bytes[2 * i + 1]);
+        i += 1;
+    }
+    key
+}
+
+/// Plugin id prefix reserved for packages signed by the PrivStack root.
+pub const FIRST_PARTY_ID_PREFIX: &str = "privstack.";
+
+/// Who added a publisher to the trust store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustSource {
+    /// The built-in PrivStack root.
+    Builtin,
+    /// An enterprise policy.
+    Enterprise,
+    /// The user.
+    User,
+}
+
+/// A publisher whose signatures are accepted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedPublisher {
+    pub name: String,
+    pub key: VerifyingKey,
+    pub source: TrustSource,
+}
+
+impl TrustedPublisher {
+    /// Whether packages signed by this publisher are first-party.
+    pub fn is_first_party(&self) -> bool {
+        self.source == TrustSource::Builtin
+    }
+}
+
+/// The set of trusted publisher keys.
+#[derive(Debug, Clone)]
+pub struct TrustStore {
+    publishers: Vec<TrustedPublisher>,
+}
+
+impl Default for TrustStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrustStore {
+    /// Creates a store that trusts only the PrivStack root.
+    pub fn new() -> Self {
+        let root = VerifyingKey::from_bytes(&PRIVSTACK_ROOT_KEY)
+            .expect("built-in PrivStack root key is a valid Ed25519 point");
+        Self {
+            publishers: vec![TrustedPublisher {
+                name: "PrivStack".to_string(),
+                key: root,
+                source: TrustSource::Builtin,
+            }],
+        }
+    }
+
+    /// Trusts a publisher key. Re-adding a known key renames it; the built-in
+    /// root cannot be replaced.
+    pub fn add(
+        &mut self,
+        name: &str,
+        key: VerifyingKey,
+        source: TrustSource,
+    ) -> Result<(), PpkError> {
+        if source == TrustSource::Builtin {
+            return Err(PpkError::BuiltinKey);
+        }
+        match self.publishers.iter_mut().find(|p| p.key == key) {
+            Some(existing) if existing.source == TrustSource::Builtin => {
+                return Err(PpkError::BuiltinKey);
+            }
+            Some(existing) => {
+                existing.name = name.to_string();
+                existing.source = source;
+            }
+            None => self.publishers.push(TrustedPublisher {
+                name: name.to_string(),
+                key,
+                source,
+            }),
+        }
+        Ok(())
+    }
+
+    /// Stops trusting a publisher key. Returns whether it was trusted; the
+    /// built-in root cannot be removed.
+    pub fn remove(&mut self, key: &VerifyingKey) -> bool {
+        let before = self.publishers.len();
+        self.publishers
+            .retain(|p| p.source == TrustSource::Builtin || p.key != *key);
+        self.publishers.len() != before
+    }
+
+    pub fn publishers(&self) -> &[TrustedPublisher] {
+        &self.publishers
+    }
+
+    /// Checks that the package is signed by a trusted publisher and that only
+    /// the PrivStack root signs `privstack.` ids. Returns the signer.
+    pub fn verify(&self, package: &PpkPackage) -> Result<&TrustedPublisher, PpkError> {
+        if package.signature.is_none() {
+            return Err(PpkError::NotSigned);
+        }
+        let signer = self
+            .publishers
+            .iter()
+            .find(|p| package.verify(&p.key).is_ok())
+            .ok_or(PpkError::UntrustedSigner)?;
+        if package.manifest.id.starts_with(FIRST_PARTY_ID_PREFIX) && !signer.is_first_party() {
+            return Err(PpkError::ManifestInvalid(format!(
+                "id prefix '{}' is reserved for first-party plugins",
+                FIRST_PARTY_ID_PREFIX
+            )));
+        }
+        Ok(signer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KeyPair, PackageBuilder, PpkManifest, SigningKey};
+    use std::io::Cursor;
+
+    fn package(id: &str, key: Option<&SigningKey>) -> PpkPackage {
+        let manifest = PpkManifest {
+            id: id.into(),
+            name: "Test".into(),
+            description: "".into(),
+            version: "1.0.0".into(),
+            author: "Test".into(),
+            icon: None,
+            navigation_order: 100,
+            category: "utility".into(),
+            can_disable: true,
+            is_experimental: false,
+            min_app_version: None,
+            permissions: vec![],
+            schemas: vec![],
+            filesystem: vec![],
+            network_hosts: vec![],
+            event_topics: vec![],
+        };
+        let mut builder = PackageBuilder::new(manifest).wasm(b"\0asm".to_vec());
+        if let Some(key) = key {
+            builder = builder.sign(key);
+        }
+        PpkPackage::open(Cursor::new(builder.build().unwrap())).unwrap()
+    }
+
+    /// A store whose built-in root is a test key.
+    fn store_with_root(root: &KeyPair) -> TrustStore {
+        TrustStore {
+            publishers: vec![TrustedPublisher {
+                name: "PrivStack".into(),
+                key: root.verifying_key,
+                source: TrustSource::Builtin,
+            }],
+        }
+    }
+
+    #[test]
+    fn builtin_root_is_trusted() {
+        let store = TrustStore::new();
+        assert_eq!(store.publishers().len(), 1);
+        assert!(store.publishers()[0].is_first_party());
+        assert_eq!(store.publishers()[0].key.to_bytes(), PRIVSTACK_ROOT_KEY);
+    }
+
+    #[test]
+    fn unsigned_and_untrusted_packages_rejected() {
+        let store = TrustStore::new();
+        let stranger = KeyPair::generate();
+        assert!(matches!(
+            store.verify(&package("community.weather", None)),
+            Err(PpkError::NotSigned)
+        ));
+        assert!(matches!(
+            store.verify(&package("community.weather", Some(&stranger.signing_key))),
+            Err(PpkError::UntrustedSigner)
+        ));
+    }
+
+    #[test]
+    fn first_party_follows_signing_key() {
+        let root = KeyPair::generate();
+        let publisher = KeyPair::generate();
+        let mut store = store_with_root(&root);
+        store.add("Weather Co", publisher.verifying_key, TrustSource::User).unwrap();
+
+        let signer = store.verify(&package("privstack.rss", Some(&root.signing_key))).unwrap();
+        assert!(signer.is_first_party());
+        let signer = store
+            .verify(&package("community.weather", Some(&publisher.signing_key)))
+            .unwrap();
+        assert!(!signer.is_first_party());
+        assert_eq!(signer.name, "Weather Co");
+
+        assert!(matches!(
+            store.verify(&package("privstack.fake", Some(&publisher.signing_key))),
+            Err(PpkError::ManifestInvalid(_))
+        ));
+    }
+
+    #[test]
+    fn add_and_remove_publishers() {
+        let root = KeyPair::generate();
+        let publisher = KeyPair::generate();
+        let mut store = store_with_root(&root);
+
+        assert!(store.add("Root", root.verifying_key, TrustSource::User).is_err());
+        assert!(store.add("X", publisher.verifying_key, TrustSource::Builtin).is_err());
+        store.add("Acme", publisher.verifying_key, TrustSource::User).unwrap();
+        store.add("Acme Corp", publisher.verifying_key, TrustSource::Enterprise).unwrap();
+        assert_eq!(store.publishers().len(), 2);
+        assert_eq!(store.publishers()[1].name, "Acme Corp");
+        assert_eq!(store.publishers()[1].source, TrustSource::Enterprise);
+
+        assert!(!store.remove(&root.verifying_key));
+        assert!(store.remove(&publisher.verifying_key));
+        assert!(!store.remove(&publisher.verifying_key));
+        assert_eq!(store.publishers().len(), 1);
+    }
+
+    #[test]
+    fn root_key_decodes_from_hex() {
+        let hex_key = hex::encode(DEV_ROOT_KEY);
+        assert_eq!(decode_root_key(&hex_key), DEV_ROOT_KEY);
+        assert_eq!(decode_root_key(&hex_key.to_uppercase()), DEV_ROOT_KEY);
+    }
+
+    #[test]
+    fn publishers_round_trip_through_json() {
+        let publisher = TrustedPublisher {
+            name: "Acme".into(),
+            key: KeyPair::generate().verifying_key,
+            source: TrustSource::User,
+        };
+        let json = serde_json::to_string(&publisher).unwrap();
+        assert!(json.contains(&publisher.key.to_hex()));
+        assert!(json.contains(r#""source":"user""#));
+        let back: TrustedPublisher = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, publisher);
+
+        let bad = json.replace(&publisher.key.to_hex(), "abcd");
+        assert!(serde_json::from_str::<TrustedPublisher>(&bad).is_err());
+    }
+}
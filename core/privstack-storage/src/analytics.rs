@@ -0,0 +1,212 @@
+//! Read-only analytical queries over decrypted entities.
+//!
+//! `data_json` is encrypted at rest, so SQL cannot look inside it. For an
+//! analytics query the requested entity types are decrypted into a scratch
+//! in-memory DuckDB database — one table per entity type — and the query runs
+//! there with external access disabled, so it can reach neither the real
+//! store nor the filesystem.
+//!
+//! Each table has `id`, `created_at`, `modified_at`, `created_by`, `data`
+//! (the full JSON document as text) and one column per top-level data field.
+//! Field columns are `DOUBLE` when every value is a number, `BOOLEAN` when
+//! every value is a boolean, and `VARCHAR` otherwise. Column names are
+//! case-insensitive, so a field whose name differs only in case from a fixed
+//! column or an earlier field (in byte order) is only reachable through `data`.
+
+use crate::entity_store::EntityStore;
+use crate::error::{StorageError, StorageResult};
+use duckdb::types::Value;
+use duckdb::{params_from_iter, Connection};
+use privstack_model::Entity;
+use std::collections::{BTreeMap, HashSet};
+
+/// Maximum rows an analytics query returns.
+pub const ANALYTICS_ROW_LIMIT: usize = 10_000;
+
+/// Columns every entity table has. Data fields with these names, in any
+/// case, are only reachable through `data`.
+const FIXED_COLUMNS: [&str; 5] = ["id", "created_at", "modified_at", "created_by", "data"];
+
+impl EntityStore {
+    /// Runs a single SELECT statement over decrypted views of `entity_types`.
+    ///
+    /// `params` bind to `?` placeholders in order. Returns one JSON object per
+    /// row, at most [`ANALYTICS_ROW_LIMIT`] rows.
+    pub fn run_analytics(
+        &self,
+        entity_types: &[&str],
+        sql: &str,
+        params: &[String],
+    ) -> StorageResult<Vec<serde_json::Value>> {
+        let sql = validate_select(sql)?;
+
+        let scratch = Connection::open_in_memory()?;
+        scratch.execute_batch("PRAGMA memory_limit='256MB'; PRAGMA threads=1;")?;
+        for entity_type in entity_types {
+            let entities = self.list_entities(entity_type, false, None, None)?;
+            load_table(&scratch, entity_type, &entities)?;
+        }
+        // Once locked, the query cannot turn file or network access back on.
+        scratch.execute_batch(
+            "SET enable_external_access = false; SET lock_configuration = true;",
+        )?;
+
+        let mut stmt =
+            scratch.prepare(&format!("SELECT * FROM ({sql}) AS q LIMIT {ANALYTICS_ROW_LIMIT}"))?;
+        let mut rows = stmt.query(params_from_iter(params.iter()))?;
+        let columns = rows.as_ref().map(|s| s.column_names()).unwrap_or_default();
+
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mut obj = serde_json::Map::with_capacity(columns.len());
+            for (i, name) in columns.iter().enumerate() {
+                obj.insert(name.clone(), value_to_json(row.get::<_, Value>(i)?));
+            }
+            out.push(serde_json::Value::Object(obj));
+        }
+        Ok(out)
+    }
+}
+
+/// Accepts a single SELECT (or `WITH ... SELECT`) statement and returns it
+/// without trailing semicolons.
+pub fn validate_select(sql: &str) -> StorageResult<&str> {
+    let sql = sql.trim().trim_end_matches(';').trim();
+    let upper = sql.to_uppercase();
+    if !upper.starts_with("SELECT") && !upper.starts_with("WITH") {
+        return Err(StorageError::InvalidData(
+            "only SELECT statements are allowed".to_string(),
+        ));
+    }
+    if sql.contains(';') {
+        return Err(StorageError::InvalidData(
+            "only a single statement is allowed".to_string(),
+        ));
+    }
+    Ok(sql)
+}
+
+/// Inferred SQL type of a data field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+    Boolean,
+    Number,
+    Text,
+}
+
+impl ColumnKind {
+    fn of(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Bool(_) => Self::Boolean,
+            serde_json::Value::Number(_) => Self::Number,
+            _ => Self::Text,
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        if self == other { self } else { Self::Text }
+    }
+
+    fn sql_type(self) -> &'static str {
+        match self {
+            Self::Boolean => "BOOLEAN",
+            Self::Number => "DOUBLE",
+            Self::Text => "VARCHAR",
+        }
+    }
+
+    fn to_value(self, value: Option<&serde_json::Value>) -> Value {
+        match (self, value) {
+            (_, None | Some(serde_json::Value::Null)) => Value::Null,
+            (Self::Boolean, Some(v)) => v.as_bool().map_or(Value::Null, Value::Boolean),
+            (Self::Number, Some(v)) => v.as_f64().map_or(Value::Null, Value::Double),
+            (Self::Text, Some(serde_json::Value::String(s))) => Value::Text(s.clone()),
+            (Self::Text, Some(v)) => Value::Text(v.to_string()),
+        }
+    }
+}
+
+/// Creates a table named after `entity_type` and fills it with `entities`.
+fn load_table(conn: &Connection, entity_type: &str, entities: &[Entity]) -> StorageResult<()> {
+    let mut fields: BTreeMap<String, ColumnKind> = BTreeMap::new();
+    for entity in entities {
+        let Some(obj) = entity.data.as_object() else {
+            continue;
+        };
+        for (key, value) in obj {
+            if value.is_null() {
+                continue;
+            }
+            let kind = ColumnKind::of(value);
+            fields
+                .entry(key.clone())
+                .and_modify(|k| *k = k.merge(kind))
+                .or_insert(kind);
+        }
+    }
+    let mut taken: HashSet<String> = FIXED_COLUMNS.iter().map(|c| c.to_string()).collect();
+    fields.retain(|name, _| taken.insert(name.to_lowercase()));
+
+    let mut columns = vec![
+        "\"id\" VARCHAR".to_string(),
+        "\"created_at\" BIGINT".to_string(),
+        "\"modified_at\" BIGINT".to_string(),
+        "\"created_by\" VARCHAR".to_string(),
+        "\"data\" VARCHAR".to_string(),
+    ];
+    columns.extend(
+        fields
+            .iter()
+            .map(|(name, kind)| format!("\"{}\" {}", quote_identifier(name), kind.sql_type())),
+    );
+    let table = quote_identifier(entity_type);
+    conn.execute_batch(&format!("CREATE TABLE \"{table}\" ({})", columns.join(", ")))?;
+
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let mut insert = conn.prepare(&format!("INSERT INTO \"{table}\" VALUES ({placeholders})"))?;
+    for entity in entities {
+        let mut row = vec![
+            Value::Text(entity.id.clone()),
+            Value::BigInt(entity.created_at),
+            Value::BigInt(entity.modified_at),
+            Value::Text(entity.created_by.clone()),
+            Value::Text(entity.data.to_string()),
+        ];
+        row.extend(
+            fields
+                .iter()
+                .map(|(name, kind)| kind.to_value(entity.data.get(name))),
+        );
+        insert.execute(params_from_iter(row))?;
+    }
+    Ok(())
+}
+
+/// Escapes a name for use inside a double-quoted identifier.
+fn quote_identifier(name: &str) -> String {
+    name.replace('"', "\"\"")
+}
+
+fn value_to_json(value: Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Boolean(b) => serde_json::Value::Bool(b),
+        Value::TinyInt(n) => n.into(),
+        Value::SmallInt(n) => n.into(),
+        Value::Int(n) => n.into(),
+        Value::BigInt(n) => n.into(),
+        Value::UTinyInt(n) => n.into(),
+        Value::USmallInt(n) => n.into(),
+        Value::UInt(n) => n.into(),
+        Value::UBigInt(n) => n.into(),
+        Value::HugeInt(n) => i64::try_from(n)
+            .map(serde_json::Value::from)
+            .unwrap_or_else(|_| serde_json::Value::String(n.to_string())),
+        Value::Float(f) => serde_json::Number::from_f64(f64::from(f))
+            .map_or(serde_json::Value::Null, serde_json::Value::Number),
+        Value::Double(f) => serde_json::Number::from_f64(f)
+            .map_or(serde_json::Value::Null, serde_json::Value::Number),
+        Value::Text(s) => serde_json::Value::String(s),
+        other => serde_json::Value::String(format!("{other:?}")),
+    }
+}
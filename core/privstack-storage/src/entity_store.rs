@@ -216,6 +216,33 @@ impl EntityStore {
         }
     }
 
+    /// Type of a single entity, without decrypting its data.
+    pub fn get_entity_type(&self, id: &str) -> StorageResult<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        match conn.query_row(
+            "SELECT entity_type FROM entities WHERE id = ?",
+            params![id],
+            |row| row.get::<_, String>(0),
+        ) {
+            Ok(entity_type) => Ok(Some(entity_type)),
+            Err(duckdb::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Distinct entity types that have at least one non-trashed entity.
+    pub fn list_entity_types(&self) -> StorageResult<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT entity_type FROM entities \
+             WHERE is_trashed = FALSE ORDER BY entity_type",
+        )?;
+        let types = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(types)
+    }
+
     /// List entities of a given type, ordered by modified_at DESC.
     pub fn list_entities(
         &self,
@@ -433,7 +460,6 @@ impl EntityStore {
     }
 
     /// Returns all entity IDs in the store (non-trashed).
-    /// Used by the sync orchestrator on first sync with a new peer.
     pub fn list_all_entity_ids(&self) -> StorageResult<Vec<String>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare("SELECT id FROM entities WHERE is_trashed = FALSE")?;
@@ -444,6 +470,21 @@ impl EntityStore {
         Ok(ids)
     }
 
+    /// Returns the IDs of entities that leave the device: non-trashed and
+    /// not marked `local_only`. Used by the sync orchestrator on first sync
+    /// with a new peer and when seeding cloud sync.
+    pub fn list_syncable_entity_ids(&self) -> StorageResult<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id FROM entities WHERE is_trashed = FALSE AND local_only = FALSE",
+        )?;
+        let ids: Vec<String> = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(ids)
+    }
+
     // ── Sync Ledger ──────────────────────────────────────────────
 
     /// Returns entity IDs that need syncing with a specific peer.
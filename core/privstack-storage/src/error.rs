@@ -32,6 +32,10 @@ pub enum StorageError {
     #[error("invalid data: {0}")]
     InvalidData(String),
 
+    /// Event signature missing, invalid, or not matching the pinned author key.
+    #[error("signature error: {0}")]
+    Signature(String),
+
     /// Encryption/decryption error.
     #[error("encryption error: {0}")]
     Encryption(String),
@@ -1,15 +1,30 @@
 //! Generic event store — persists sync events for entity replication.
+//!
+//! Every event is checked against its author signature before it is stored.
+//! Identity keys are bound to peers in `author_identity_keys` only through
+//! explicit registration (this device's own key, paired devices, registered
+//! known peers); event traffic never binds a key. A signed event is attributed
+//! to its `peer_id` only when it is signed with the key registered for that
+//! peer. A redacted copy is attributed to the peer that sealed it, under the
+//! same rule, and never replaces the original event.
 
-use crate::error::StorageResult;
+use crate::error::{StorageError, StorageResult};
 use duckdb::{params, Connection};
-use privstack_types::{EntityId, Event, EventId, HybridTimestamp, PeerId};
+use privstack_crypto::{verify_event, Authorship, IdentityPublicKey, SignatureMode};
+use privstack_types::{
+    EntityId, Event, EventId, EventSignature, HybridTimestamp, PeerId, RedactionSeal,
+};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
+const EVENT_COLUMNS: &str = "id, entity_id, peer_id, timestamp_wall, timestamp_logical, \
+    payload_json, dependencies_json, signature_json, redacted_json, redaction_seal_json";
+
 /// Persists sync events for CRDT replication.
 #[derive(Clone)]
 pub struct EventStore {
     conn: Arc<Mutex<Connection>>,
+    signature_mode: SignatureMode,
 }
 
 impl EventStore {
@@ -19,6 +34,7 @@ impl EventStore {
         initialize_event_schema(&conn)?;
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
+            signature_mode: SignatureMode::default(),
         })
     }
 
@@ -28,22 +44,140 @@ impl EventStore {
         initialize_event_schema(&conn)?;
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
+            signature_mode: SignatureMode::default(),
         })
     }
 
-    /// Saves an event.
+    /// Sets how unsigned events are treated. Defaults to
+    /// `SignatureMode::LegacyMigration` so stores holding pre-signing history
+    /// keep working; switch to `Required` once every device signs its events.
+    pub fn with_signature_mode(mut self, mode: SignatureMode) -> Self {
+        self.signature_mode = mode;
+        self
+    }
+
+    /// Returns how unsigned events are treated.
+    pub fn signature_mode(&self) -> SignatureMode {
+        self.signature_mode
+    }
+
+    /// Verifies an event's author: checks the signature and that the signing
+    /// key is the one registered for `event.peer_id`. Never registers a key.
+    /// For a redacted copy the seal is checked instead, against the key
+    /// registered for its redactor.
+    ///
+    /// Fails on an invalid signature, on a key that differs from the one
+    /// registered for the author, or on an unsigned event in `Required` mode.
+    /// A valid signature from an author with no registered key fails in
+    /// `Required` mode; in `LegacyMigration` it proves nothing about the
+    /// author, so the event is reported as `Authorship::Unsigned`.
+    pub fn verify_author(&self, event: &Event) -> StorageResult<Authorship> {
+        let authorship = verify_event(event, self.signature_mode)
+            .map_err(|e| StorageError::Signature(e.to_string()))?;
+        let (signer, key) = match (authorship, &event.redaction_seal) {
+            (Authorship::Verified(key), _) => (event.peer_id, key),
+            (Authorship::Redacted(key), Some(seal)) => (seal.redactor, key),
+            _ => return Ok(authorship),
+        };
+        match self.author_key(&signer)? {
+            Some(registered) if registered == key => Ok(authorship),
+            Some(_) => Err(StorageError::Signature(format!(
+                "event signed with a key not registered for {signer}"
+            ))),
+            None if self.signature_mode == SignatureMode::Required => Err(
+                StorageError::Signature(format!("no identity key registered for {signer}")),
+            ),
+            None => Ok(Authorship::Unsigned),
+        }
+    }
+
+    /// Registers `key` as the identity key of `peer_id`. Call this only for
+    /// keys learned through a trusted channel: this device's own key, a
+    /// paired device's handshake, or a known-peer registration.
+    ///
+    /// Registering the key already bound to the peer is a no-op; a different
+    /// key is refused so a bound identity cannot be replaced.
+    pub fn register_author_key(
+        &self,
+        peer_id: &PeerId,
+        key: &IdentityPublicKey,
+    ) -> StorageResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let encoded = key.to_base64();
+        conn.execute(
+            "INSERT OR IGNORE INTO author_identity_keys (peer_id, public_key) VALUES (?, ?)",
+            params![peer_id.to_string(), encoded],
+        )?;
+        let registered: String = conn.query_row(
+            "SELECT public_key FROM author_identity_keys WHERE peer_id = ?",
+            params![peer_id.to_string()],
+            |row| row.get(0),
+        )?;
+        if registered != encoded {
+            return Err(StorageError::Signature(format!(
+                "a different identity key is already registered for {peer_id}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns the identity key registered for a peer, if any.
+    pub fn author_key(&self, peer_id: &PeerId) -> StorageResult<Option<IdentityPublicKey>> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT public_key FROM author_identity_keys WHERE peer_id = ?",
+            params![peer_id.to_string()],
+            |row| row.get::<_, String>(0),
+        );
+        match result {
+            Ok(encoded) => IdentityPublicKey::from_base64(&encoded)
+                .map(Some)
+                .map_err(|e| StorageError::InvalidData(e.to_string())),
+            Err(duckdb::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Saves an event after verifying its author (see `verify_author`).
+    ///
+    /// A redacted copy is ignored if the event is already stored, and the
+    /// full event replaces a redacted copy stored earlier.
     pub fn save_event(&self, event: &Event) -> StorageResult<()> {
+        self.verify_author(event)?;
+
         let conn = self.conn.lock().unwrap();
         let payload_json = serde_json::to_string(&event.payload)?;
         let deps_json = serde_json::to_string(&event.dependencies)?;
+        let signature_json = event
+            .signature
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+        let redacted_json = if event.is_redacted() {
+            Some(serde_json::to_string(&event.redacted_paths)?)
+        } else {
+            None
+        };
+        let seal_json = event
+            .redaction_seal
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
 
+        if !event.is_redacted() {
+            conn.execute(
+                "DELETE FROM events WHERE id = ? AND redacted_json IS NOT NULL",
+                params![event.id.to_string()],
+            )?;
+        }
         conn.execute(
             r#"
             INSERT OR IGNORE INTO events (
                 id, entity_id, peer_id,
                 timestamp_wall, timestamp_logical,
-                payload_json, dependencies_json
-            ) VALUES (?, ?, ?, ?, ?, ?, ?)
+                payload_json, dependencies_json, signature_json, redacted_json,
+                redaction_seal_json
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             params![
                 event.id.to_string(),
@@ -53,6 +187,9 @@ impl EventStore {
                 event.timestamp.logical() as i32,
                 payload_json,
                 deps_json,
+                signature_json,
+                redacted_json,
+                seal_json,
             ],
         )?;
         Ok(())
@@ -61,10 +198,10 @@ impl EventStore {
     /// Gets events for an entity, ordered by timestamp.
     pub fn get_events_for_entity(&self, entity_id: &EntityId) -> StorageResult<Vec<Event>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, entity_id, peer_id, timestamp_wall, timestamp_logical, payload_json, dependencies_json \
-             FROM events WHERE entity_id = ? ORDER BY timestamp_wall, timestamp_logical"
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {EVENT_COLUMNS} FROM events WHERE entity_id = ? \
+             ORDER BY timestamp_wall, timestamp_logical"
+        ))?;
 
         let events = stmt
             .query_map(params![entity_id.to_string()], row_to_event)?
@@ -81,11 +218,11 @@ impl EventStore {
         since: &HybridTimestamp,
     ) -> StorageResult<Vec<Event>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, entity_id, peer_id, timestamp_wall, timestamp_logical, payload_json, dependencies_json \
-             FROM events WHERE peer_id = ? AND (timestamp_wall > ? OR (timestamp_wall = ? AND timestamp_logical > ?)) \
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {EVENT_COLUMNS} FROM events WHERE peer_id = ? \
+             AND (timestamp_wall > ? OR (timestamp_wall = ? AND timestamp_logical > ?)) \
              ORDER BY timestamp_wall, timestamp_logical"
-        )?;
+        ))?;
 
         let events = stmt
             .query_map(
@@ -136,6 +273,9 @@ fn row_to_event(row: &duckdb::Row<'_>) -> duckdb::Result<Event> {
     let logical: i32 = row.get(4)?;
     let payload_json: String = row.get(5)?;
     let deps_json: String = row.get(6)?;
+    let signature_json: Option<String> = row.get(7)?;
+    let redacted_json: Option<String> = row.get(8)?;
+    let seal_json: Option<String> = row.get(9)?;
 
     let id: EventId = id_str.parse().unwrap_or_default();
     let entity_id: EntityId = entity_id_str.parse().unwrap_or_default();
@@ -148,6 +288,13 @@ fn row_to_event(row: &duckdb::Row<'_>) -> duckdb::Result<Event> {
         },
     );
     let dependencies: Vec<EventId> = serde_json::from_str(&deps_json).unwrap_or_default();
+    let signature: Option<EventSignature> =
+        signature_json.and_then(|json| serde_json::from_str(&json).ok());
+    let redacted_paths: Vec<String> = redacted_json
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let redaction_seal: Option<RedactionSeal> =
+        seal_json.and_then(|json| serde_json::from_str(&json).ok());
 
     Ok(Event {
         id,
@@ -156,6 +303,9 @@ fn row_to_event(row: &duckdb::Row<'_>) -> duckdb::Result<Event> {
         timestamp,
         payload,
         dependencies,
+        signature,
+        redacted_paths,
+        redaction_seal,
     })
 }
 
@@ -169,8 +319,14 @@ fn initialize_event_schema(conn: &Connection) -> StorageResult<()> {
             timestamp_wall BIGINT NOT NULL,
             timestamp_logical INTEGER NOT NULL,
             payload_json TEXT NOT NULL,
-            dependencies_json TEXT NOT NULL DEFAULT '[]'
+            dependencies_json TEXT NOT NULL DEFAULT '[]',
+            signature_json TEXT,
+            redacted_json TEXT,
+            redaction_seal_json TEXT
         );
+        ALTER TABLE events ADD COLUMN IF NOT EXISTS signature_json TEXT;
+        ALTER TABLE events ADD COLUMN IF NOT EXISTS redacted_json TEXT;
+        ALTER TABLE events ADD COLUMN IF NOT EXISTS redaction_seal_json TEXT;
         CREATE INDEX IF NOT EXISTS idx_events_entity ON events(entity_id);
         CREATE INDEX IF NOT EXISTS idx_events_peer ON events(peer_id);
         CREATE INDEX IF NOT EXISTS idx_events_timestamp ON events(timestamp_wall, timestamp_logical);
@@ -181,6 +337,12 @@ fn initialize_event_schema(conn: &Connection) -> StorageResult<()> {
             last_seen_logical INTEGER NOT NULL,
             updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
         );
+
+        CREATE TABLE IF NOT EXISTS author_identity_keys (
+            peer_id VARCHAR PRIMARY KEY,
+            public_key VARCHAR NOT NULL,
+            registered_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
         "#,
     )?;
     Ok(())
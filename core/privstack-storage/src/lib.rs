@@ -10,13 +10,16 @@
 //! - Events are stored for sync protocol replication
 //! - Entity links support cross-plugin references
 //! - Schema migrations are handled automatically on startup
+//! - Read-only analytics run over decrypted copies in a scratch database
 
+mod analytics;
 mod error;
 pub mod entity_store;
 mod event_store;
 
 pub use entity_store::{EntityStore, scan_duckdb_file, scan_duckdb_connection, compact_duckdb_file};
 pub use event_store::EventStore;
+pub use analytics::{validate_select, ANALYTICS_ROW_LIMIT};
 pub use error::{StorageError, StorageResult};
 
 /// Open a DuckDB connection with stale WAL recovery and resource limits.
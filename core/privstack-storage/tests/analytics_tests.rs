@@ -0,0 +1,113 @@
+use privstack_model::Entity;
+use privstack_storage::{validate_select, EntityStore};
+
+fn task(id: &str, title: &str, hours: f64, done: bool) -> Entity {
+    Entity {
+        id: id.into(),
+        entity_type: "task".into(),
+        data: serde_json::json!({ "title": title, "hours": hours, "done": done }),
+        created_at: 1000,
+        modified_at: 1000,
+        created_by: "peer".into(),
+    }
+}
+
+fn store_with_tasks() -> EntityStore {
+    let store = EntityStore::open_in_memory().unwrap();
+    store.save_entity_raw(&task("t-1", "Write docs", 2.0, true)).unwrap();
+    store.save_entity_raw(&task("t-2", "Fix bug", 3.5, false)).unwrap();
+    store.save_entity_raw(&task("t-3", "Review", 1.0, true)).unwrap();
+    store
+        .save_entity_raw(&Entity {
+            id: "n-1".into(),
+            entity_type: "note".into(),
+            data: serde_json::json!({ "title": "secret" }),
+            created_at: 1000,
+            modified_at: 1000,
+            created_by: "peer".into(),
+        })
+        .unwrap();
+    store
+}
+
+// ── Validation ───────────────────────────────────────────────────
+
+#[test]
+fn validate_select_accepts_select_and_with() {
+    assert_eq!(validate_select("  SELECT 1; ").unwrap(), "SELECT 1");
+    assert!(validate_select("with t as (select 1) select * from t").is_ok());
+}
+
+#[test]
+fn validate_select_rejects_other_statements() {
+    assert!(validate_select("DELETE FROM task").is_err());
+    assert!(validate_select("ATTACH 'x.db'").is_err());
+    assert!(validate_select("SELECT 1; DROP TABLE task").is_err());
+}
+
+// ── Queries ──────────────────────────────────────────────────────
+
+#[test]
+fn analytics_aggregates_decrypted_fields() {
+    let store = store_with_tasks();
+    let rows = store
+        .run_analytics(
+            &["task"],
+            "SELECT done, SUM(hours) AS total FROM task GROUP BY done ORDER BY done",
+            &[],
+        )
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]["done"], false);
+    assert_eq!(rows[0]["total"], 3.5);
+    assert_eq!(rows[1]["total"], 3.0);
+}
+
+#[test]
+fn analytics_binds_parameters() {
+    let store = store_with_tasks();
+    let rows = store
+        .run_analytics(&["task"], "SELECT id FROM task WHERE title = ?", &["Fix bug".into()])
+        .unwrap();
+    assert_eq!(rows, vec![serde_json::json!({ "id": "t-2" })]);
+}
+
+#[test]
+fn analytics_only_sees_requested_types() {
+    let store = store_with_tasks();
+    assert!(store.run_analytics(&["task"], "SELECT * FROM note", &[]).is_err());
+    assert!(store.run_analytics(&["task"], "SELECT * FROM entities", &[]).is_err());
+}
+
+#[test]
+fn analytics_cannot_read_files() {
+    let store = store_with_tasks();
+    assert!(store
+        .run_analytics(&["task"], "SELECT * FROM read_csv('/etc/passwd')", &[])
+        .is_err());
+}
+
+#[test]
+fn analytics_skips_fields_that_differ_only_in_case() {
+    let store = EntityStore::open_in_memory().unwrap();
+    store
+        .save_entity_raw(&Entity {
+            id: "c-1".into(),
+            entity_type: "contact".into(),
+            data: serde_json::json!({ "ID": "x", "Name": "Ada", "name": "ada", "age": 36 }),
+            created_at: 1000,
+            modified_at: 1000,
+            created_by: "peer".into(),
+        })
+        .unwrap();
+    let rows = store
+        .run_analytics(&["contact"], "SELECT id AS i, name AS n, age AS a FROM contact", &[])
+        .unwrap();
+    assert_eq!(rows, vec![serde_json::json!({ "i": "c-1", "n": "Ada", "a": 36.0 })]);
+}
+
+#[test]
+fn list_entity_types_is_distinct_and_sorted() {
+    let store = store_with_tasks();
+    assert_eq!(store.list_entity_types().unwrap(), vec!["note", "task"]);
+}
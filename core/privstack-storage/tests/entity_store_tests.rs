@@ -51,6 +51,16 @@ fn get_nonexistent_returns_none() {
     assert!(result.is_none());
 }
 
+#[test]
+fn get_entity_type() {
+    let store = EntityStore::open_in_memory().unwrap();
+    let entity = test_entity("Typed");
+    store.save_entity(&entity, &test_schema()).unwrap();
+
+    assert_eq!(store.get_entity_type(&entity.id).unwrap().as_deref(), Some("bookmark"));
+    assert_eq!(store.get_entity_type("nonexistent-id").unwrap(), None);
+}
+
 #[test]
 fn save_entity_raw() {
     let store = EntityStore::open_in_memory().unwrap();
@@ -142,6 +152,22 @@ fn list_with_limit_and_offset() {
     assert_ne!(page[0].id, page2[0].id);
 }
 
+#[test]
+fn syncable_ids_skip_local_only_and_trashed() {
+    let store = EntityStore::open_in_memory().unwrap();
+    let shared = test_entity("Shared");
+    let mut local = test_entity("Local");
+    local.data["local_only"] = serde_json::json!(true);
+    let mut trashed = test_entity("Trashed");
+    trashed.data["is_trashed"] = serde_json::json!(true);
+    for e in [&shared, &local, &trashed] {
+        store.save_entity_raw(e).unwrap();
+    }
+
+    assert_eq!(store.list_syncable_entity_ids().unwrap(), vec![shared.id]);
+    assert_eq!(store.list_all_entity_ids().unwrap().len(), 2);
+}
+
 #[test]
 fn list_empty_type() {
     let store = EntityStore::open_in_memory().unwrap();
@@ -583,7 +609,7 @@ fn save_entity_with_body_field() {
         entity_type: "note".into(),
         indexed_fields: vec![
             IndexedField::text("/title", true),
-            IndexedField { field_path: "/body".into(), field_type: FieldType::Text, searchable: true, vector_dim: None, enum_options: None },
+            IndexedField { field_path: "/body".into(), field_type: FieldType::Text, searchable: true, vector_dim: None, enum_options: None, sensitivity: None },
         ],
         merge_strategy: MergeStrategy::LwwDocument,
     };
@@ -1000,6 +1026,7 @@ fn vector_field_no_vector_dim_defaults_to_zero() {
                 searchable: false,
                 vector_dim: None, // no dim specified
                 enum_options: None,
+                sensitivity: None,
             },
         ],
         merge_strategy: MergeStrategy::LwwDocument,
@@ -1,4 +1,5 @@
-use privstack_storage::EventStore;
+use privstack_crypto::{Authorship, IdentityKeyPair, SignatureMode};
+use privstack_storage::{EventStore, StorageError};
 use privstack_types::{EntityId, Event, EventPayload, HybridTimestamp, PeerId};
 
 fn make_event(entity_id: EntityId, peer_id: PeerId, wall: u64) -> Event {
@@ -183,3 +184,183 @@ fn save_event_with_dependencies() {
     assert_eq!(events.len(), 2);
     assert_eq!(events[1].dependencies.len(), 1);
 }
+
+#[test]
+fn redacted_paths_roundtrip() {
+    let store = EventStore::open_in_memory().unwrap();
+    let eid = EntityId::new();
+    let mut event = Event::full_snapshot(eid, PeerId::new(), "project", r#"{"name":"P"}"#);
+    event.redacted_paths = vec!["/cost".into()];
+
+    store.save_event(&event).unwrap();
+    let events = store.get_events_for_entity(&eid).unwrap();
+    assert_eq!(events[0].redacted_paths, vec!["/cost".to_string()]);
+}
+
+#[test]
+fn redacted_copies_never_replace_the_original() {
+    let store = EventStore::open_in_memory().unwrap();
+    let eid = EntityId::new();
+    let original = Event::full_snapshot(eid, PeerId::new(), "project", r#"{"name":"P","cost":1}"#);
+    let mut redacted = original.clone();
+    redacted.payload = EventPayload::FullSnapshot {
+        entity_type: "project".into(),
+        json_data: r#"{"name":"P"}"#.into(),
+    };
+    redacted.redacted_paths = vec!["/cost".into()];
+
+    // A redacted copy arriving after the original is ignored...
+    store.save_event(&original).unwrap();
+    store.save_event(&redacted).unwrap();
+    assert_eq!(store.get_events_for_entity(&eid).unwrap(), vec![original.clone()]);
+
+    // ...and the original replaces a copy stored before it.
+    let other = EventStore::open_in_memory().unwrap();
+    other.save_event(&redacted).unwrap();
+    other.save_event(&original).unwrap();
+    assert_eq!(other.get_events_for_entity(&eid).unwrap(), vec![original]);
+}
+
+#[test]
+fn sealed_redacted_copy_verifies_against_the_redactor() {
+    let store = EventStore::open_in_memory().unwrap().with_signature_mode(SignatureMode::Required);
+    let (author, relay) = (IdentityKeyPair::generate(), IdentityKeyPair::generate());
+    let (author_id, relay_id) = (PeerId::new(), PeerId::new());
+    store.register_author_key(&author_id, &author.public_key()).unwrap();
+
+    let mut event = Event::full_snapshot(EntityId::new(), author_id, "project", "{}");
+    author.sign_event(&mut event);
+    event.redacted_paths = vec!["/cost".into()];
+    relay.seal_redaction(relay_id, &mut event);
+
+    // The redactor's key must be registered, and the seal must be its own.
+    assert!(matches!(store.save_event(&event), Err(StorageError::Signature(_))));
+    store.register_author_key(&relay_id, &relay.public_key()).unwrap();
+    assert_eq!(store.verify_author(&event).unwrap(), Authorship::Redacted(relay.public_key()));
+    store.save_event(&event).unwrap();
+    let loaded = store.get_events_for_entity(&event.entity_id).unwrap();
+    assert_eq!(loaded[0].redaction_seal, event.redaction_seal);
+
+    let mut forged = event.clone();
+    author.seal_redaction(relay_id, &mut forged);
+    assert!(matches!(store.verify_author(&forged), Err(StorageError::Signature(_))));
+}
+
+// ── Author signatures ────────────────────────────────────────────
+
+#[test]
+fn signed_event_roundtrips_with_signature() {
+    let store = EventStore::open_in_memory().unwrap();
+    let key = IdentityKeyPair::generate();
+    let pid = PeerId::new();
+    store.register_author_key(&pid, &key.public_key()).unwrap();
+    let mut event = Event::entity_created(EntityId::new(), pid, "note", "{}");
+    key.sign_event(&mut event);
+
+    assert_eq!(store.verify_author(&event).unwrap(), Authorship::Verified(key.public_key()));
+    store.save_event(&event).unwrap();
+    let loaded = store.get_events_for_entity(&event.entity_id).unwrap();
+    assert_eq!(loaded[0].signature, event.signature);
+}
+
+#[test]
+fn save_rejects_tampered_event() {
+    let store = EventStore::open_in_memory().unwrap();
+    let key = IdentityKeyPair::generate();
+    let mut event = Event::entity_created(EntityId::new(), PeerId::new(), "note", "{}");
+    key.sign_event(&mut event);
+    event.payload = EventPayload::EntityDeleted {
+        entity_type: "note".into(),
+    };
+
+    assert!(matches!(store.save_event(&event), Err(StorageError::Signature(_))));
+    assert!(store.get_events_for_entity(&event.entity_id).unwrap().is_empty());
+}
+
+#[test]
+fn save_rejects_author_key_mismatch() {
+    let store = EventStore::open_in_memory().unwrap();
+    let pid = PeerId::new();
+    let eid = EntityId::new();
+    let key = IdentityKeyPair::generate();
+    store.register_author_key(&pid, &key.public_key()).unwrap();
+
+    let mut genuine = Event::entity_created(eid, pid, "note", "{}");
+    key.sign_event(&mut genuine);
+    store.save_event(&genuine).unwrap();
+
+    // Another device signs an event claiming the same author.
+    let mut forged = Event::entity_updated(eid, pid, "note", r#"{"x":1}"#);
+    IdentityKeyPair::generate().sign_event(&mut forged);
+    assert!(matches!(store.save_event(&forged), Err(StorageError::Signature(_))));
+    assert_eq!(store.get_events_for_entity(&eid).unwrap().len(), 1);
+}
+
+#[test]
+fn registered_key_blocks_first_forgery() {
+    let store = EventStore::open_in_memory().unwrap();
+    let pid = PeerId::new();
+    let key = IdentityKeyPair::generate();
+    store.register_author_key(&pid, &key.public_key()).unwrap();
+
+    let mut forged = Event::entity_created(EntityId::new(), pid, "note", "{}");
+    IdentityKeyPair::generate().sign_event(&mut forged);
+    assert!(store.save_event(&forged).is_err());
+}
+
+#[test]
+fn register_author_key_refuses_a_different_key() {
+    let store = EventStore::open_in_memory().unwrap();
+    let pid = PeerId::new();
+    let key = IdentityKeyPair::generate();
+    store.register_author_key(&pid, &key.public_key()).unwrap();
+    store.register_author_key(&pid, &key.public_key()).unwrap();
+
+    let other = IdentityKeyPair::generate().public_key();
+    assert!(matches!(
+        store.register_author_key(&pid, &other),
+        Err(StorageError::Signature(_))
+    ));
+    assert_eq!(store.author_key(&pid).unwrap(), Some(key.public_key()));
+}
+
+#[test]
+fn event_traffic_never_binds_author_keys() {
+    let pid = PeerId::new();
+    let mut event = Event::entity_created(EntityId::new(), pid, "note", "{}");
+    IdentityKeyPair::generate().sign_event(&mut event);
+
+    // Required mode rejects a signer nobody registered, and binds nothing.
+    let strict = EventStore::open_in_memory()
+        .unwrap()
+        .with_signature_mode(SignatureMode::Required);
+    assert!(matches!(strict.save_event(&event), Err(StorageError::Signature(_))));
+    assert_eq!(strict.author_key(&pid).unwrap(), None);
+
+    // Legacy mode accepts it as unauthenticated, still without binding.
+    let legacy = EventStore::open_in_memory().unwrap();
+    assert_eq!(legacy.verify_author(&event).unwrap(), Authorship::Unsigned);
+    legacy.save_event(&event).unwrap();
+    assert_eq!(legacy.author_key(&pid).unwrap(), None);
+
+    // So the real device can still register its own key afterwards.
+    let real = IdentityKeyPair::generate();
+    legacy.register_author_key(&pid, &real.public_key()).unwrap();
+    let mut forged = Event::entity_created(EntityId::new(), pid, "note", "{}");
+    IdentityKeyPair::generate().sign_event(&mut forged);
+    assert!(legacy.save_event(&forged).is_err());
+}
+
+#[test]
+fn required_mode_rejects_unsigned() {
+    let store = EventStore::open_in_memory()
+        .unwrap()
+        .with_signature_mode(SignatureMode::Required);
+    let event = Event::entity_created(EntityId::new(), PeerId::new(), "note", "{}");
+    assert!(matches!(store.save_event(&event), Err(StorageError::Signature(_))));
+
+    let legacy = EventStore::open_in_memory().unwrap();
+    assert_eq!(legacy.signature_mode(), SignatureMode::LegacyMigration);
+    assert_eq!(legacy.verify_author(&event).unwrap(), Authorship::Unsigned);
+    legacy.save_event(&event).unwrap();
+}
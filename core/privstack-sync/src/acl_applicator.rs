@@ -1,7 +1,8 @@
 //! ACL event handler — applies ACL-as-CRDT events to the enterprise policy.
 
 use crate::error::SyncError;
-use crate::policy::{EnterpriseSyncPolicy, SyncRole, TeamId};
+use crate::policy::{EnterpriseSyncPolicy, GrantWindow, SyncRole, TeamId};
+use crate::policy_crdt::is_policy_event;
 use async_trait::async_trait;
 use privstack_types::{EntityId, Event, EventPayload, PeerId};
 use std::sync::Arc;
@@ -34,11 +35,14 @@ impl AclEventHandler for AclApplicator {
                 entity_id,
                 peer_id,
                 role,
+                not_before,
+                expires_at,
             } => {
                 let eid: EntityId = entity_id.parse().map_err(|e| SyncError::Protocol(format!("{e}")))?;
                 let pid: PeerId = peer_id.parse().map_err(|e| SyncError::Protocol(format!("{e}")))?;
                 let r = parse_role(role)?;
-                self.policy.grant_peer_role(eid, pid, r).await;
+                let window = GrantWindow::new(*not_before, *expires_at);
+                self.policy.grant_peer_role_within(eid, pid, r, window).await;
                 Ok(true)
             }
             EventPayload::AclRevokePeer {
@@ -54,11 +58,14 @@ impl AclEventHandler for AclApplicator {
                 entity_id,
                 team_id,
                 role,
+                not_before,
+                expires_at,
             } => {
                 let eid: EntityId = entity_id.parse().map_err(|e| SyncError::Protocol(format!("{e}")))?;
                 let tid = parse_team_id(team_id)?;
                 let r = parse_role(role)?;
-                self.policy.grant_team_role(eid, tid, r).await;
+                let window = GrantWindow::new(*not_before, *expires_at);
+                self.policy.grant_team_role_within(eid, tid, r, window).await;
                 Ok(true)
             }
             EventPayload::AclRevokeTeam {
@@ -79,18 +86,9 @@ impl AclEventHandler for AclApplicator {
                 self.policy.set_default_role(eid, r).await;
                 Ok(true)
             }
-            EventPayload::TeamAddPeer { team_id, peer_id } => {
-                let tid = parse_team_id(team_id)?;
-                let pid: PeerId = peer_id.parse().map_err(|e| SyncError::Protocol(format!("{e}")))?;
-                self.policy.add_team_member(tid, pid).await;
-                Ok(true)
-            }
-            EventPayload::TeamRemovePeer { team_id, peer_id } => {
-                let tid = parse_team_id(team_id)?;
-                let pid: PeerId = peer_id.parse().map_err(|e| SyncError::Protocol(format!("{e}")))?;
-                self.policy.remove_team_member(tid, pid).await;
-                Ok(true)
-            }
+            // Team membership, known peers and device limits merge as
+            // replicated policy (add-wins / last-writer-wins)
+            payload if is_policy_event(payload) => self.policy.apply_policy_event(event).await,
             // Not an ACL event
             _ => Ok(false),
         }
@@ -106,9 +104,7 @@ pub fn is_acl_event(payload: &EventPayload) -> bool {
             | EventPayload::AclGrantTeam { .. }
             | EventPayload::AclRevokeTeam { .. }
             | EventPayload::AclSetDefault { .. }
-            | EventPayload::TeamAddPeer { .. }
-            | EventPayload::TeamRemovePeer { .. }
-    )
+    ) || is_policy_event(payload)
 }
 
 fn parse_role(s: &str) -> Result<SyncRole, SyncError> {
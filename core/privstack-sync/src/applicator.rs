@@ -4,9 +4,12 @@
 //! Plugin-specific domain logic (e.g., block-level CRDT merge for a rich-text
 //! editor) is delegated to the plugin's `PluginDomainHandler`.
 
+use crate::redaction::restore_redacted;
 use privstack_model::{Entity, EntitySchema, MergeStrategy, PluginDomainHandler};
 use privstack_storage::EntityStore;
 use privstack_types::{EntityId, Event, EventPayload, PeerId};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 use tracing::{debug, warn};
 
 /// Result type for applicator operations.
@@ -31,6 +34,54 @@ pub enum ApplicatorError {
     JsonParse(#[from] serde_json::Error),
 }
 
+/// Schemas and domain handlers the applicator uses for incoming events,
+/// keyed by entity type. Without one, every type merges as `LwwDocument`.
+///
+/// Shared behind an `Arc` and updated in place, so schemas and handlers
+/// registered after sync starts apply to the next incoming event.
+#[derive(Default)]
+pub struct DomainRegistry {
+    schemas: RwLock<HashMap<String, EntitySchema>>,
+    handlers: RwLock<HashMap<String, Arc<dyn PluginDomainHandler>>>,
+}
+
+impl DomainRegistry {
+    pub fn new(
+        schemas: HashMap<String, EntitySchema>,
+        handlers: HashMap<String, Arc<dyn PluginDomainHandler>>,
+    ) -> Self {
+        Self {
+            schemas: RwLock::new(schemas),
+            handlers: RwLock::new(handlers),
+        }
+    }
+
+    pub fn schema(&self, entity_type: &str) -> Option<EntitySchema> {
+        let schemas = self.schemas.read().unwrap_or_else(|e| e.into_inner());
+        schemas.get(entity_type).cloned()
+    }
+
+    pub fn handler(&self, entity_type: &str) -> Option<Arc<dyn PluginDomainHandler>> {
+        let handlers = self.handlers.read().unwrap_or_else(|e| e.into_inner());
+        handlers.get(entity_type).cloned()
+    }
+
+    pub fn register_schema(&self, schema: EntitySchema) {
+        let mut schemas = self.schemas.write().unwrap_or_else(|e| e.into_inner());
+        schemas.insert(schema.entity_type.clone(), schema);
+    }
+
+    pub fn register_handler(&self, entity_type: String, handler: Arc<dyn PluginDomainHandler>) {
+        let mut handlers = self.handlers.write().unwrap_or_else(|e| e.into_inner());
+        handlers.insert(entity_type, handler);
+    }
+
+    pub fn unregister_handler(&self, entity_type: &str) {
+        let mut handlers = self.handlers.write().unwrap_or_else(|e| e.into_inner());
+        handlers.remove(entity_type);
+    }
+}
+
 /// Applies sync events to the entity store using schema-driven merge.
 pub struct EventApplicator {
     /// Local peer ID for creating events.
@@ -76,6 +127,28 @@ impl EventApplicator {
         }
     }
 
+    /// Applies an event with the schema and handler `registry` holds for
+    /// its entity type.
+    pub fn apply_event_with_registry(
+        &self,
+        event: &Event,
+        store: &EntityStore,
+        registry: Option<&DomainRegistry>,
+    ) -> ApplicatorResult<bool> {
+        let entity_type = match &event.payload {
+            EventPayload::EntityCreated { entity_type, .. }
+            | EventPayload::EntityUpdated { entity_type, .. }
+            | EventPayload::EntityDeleted { entity_type }
+            | EventPayload::FullSnapshot { entity_type, .. } => Some(entity_type.as_str()),
+            _ => None,
+        };
+        let (schema, handler) = match (registry, entity_type) {
+            (Some(r), Some(t)) => (r.schema(t), r.handler(t)),
+            _ => (None, None),
+        };
+        self.apply_event(event, store, schema.as_ref(), handler.as_deref())
+    }
+
     fn apply_entity_created(
         &self,
         event: &Event,
@@ -84,7 +157,13 @@ impl EventApplicator {
         store: &EntityStore,
         schema: Option<&EntitySchema>,
     ) -> ApplicatorResult<bool> {
-        let data: serde_json::Value = serde_json::from_str(json_data)?;
+        let mut data: serde_json::Value = serde_json::from_str(json_data)?;
+        // A redacted copy must not erase fields this replica can see
+        if event.is_redacted()
+            && let Some(local) = store.get_entity(&event.entity_id.to_string())?
+        {
+            restore_redacted(&local.data, &mut data, &event.redacted_paths);
+        }
         let entity = Entity {
             id: event.entity_id.to_string(),
             entity_type: entity_type.to_string(),
@@ -126,7 +205,14 @@ impl EventApplicator {
         // Check if entity exists locally for merge
         let existing = store.get_entity(&event.entity_id.to_string())?;
         let merged = match existing {
-            Some(local) => self.merge_entities(&local, &remote_entity, schema, handler),
+            Some(local) => {
+                let mut merged = self.merge_entities(&local, &remote_entity, schema, handler);
+                // Fields withheld from the sender keep their local values
+                if event.is_redacted() {
+                    restore_redacted(&local.data, &mut merged.data, &event.redacted_paths);
+                }
+                merged
+            }
             None => remote_entity,
         };
 
@@ -0,0 +1,336 @@
+//! Tamper-evident audit log: hash chaining, filtered queries, export and retention.
+//!
+//! Every row `PolicyStore` writes to its `audit_log` table commits to the hash
+//! of the row before it. Editing, deleting or reordering rows breaks the chain,
+//! which `PolicyStore::verify_audit_chain` reports. Dropping the newest rows
+//! leaves a valid (shorter) chain, so exports carry each row's hash: comparing
+//! the current head against a previously exported one catches truncation.
+//!
+//! Retention pruning removes the oldest rows and records the hash of the last
+//! pruned row as the chain's new anchor, so the remaining rows still verify.
+
+use crate::error::SyncError;
+use crate::policy::{AuditAction, AuditDecision, AuditEntry};
+use privstack_types::{EntityId, PeerId};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// `prev_hash` of the first row in a chain that has never been pruned.
+pub const AUDIT_GENESIS_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// A persisted audit entry together with its position in the hash chain.
+#[derive(Debug, Clone)]
+pub struct AuditRecord {
+    /// Row ID (monotonically increasing).
+    pub id: i64,
+    pub entry: AuditEntry,
+    /// Hash of the preceding row (or the anchor / genesis hash).
+    pub prev_hash: String,
+    /// Hash of this row, hex-encoded SHA-256.
+    pub hash: String,
+}
+
+/// Computes a row hash over the stored column values and the previous hash.
+pub(crate) fn chain_hash(
+    prev_hash: &str,
+    peer: &str,
+    entity: Option<&str>,
+    action: &str,
+    decision: &str,
+    detail: &str,
+    timestamp: &str,
+) -> String {
+    // A JSON array keeps field boundaries unambiguous.
+    let fields = serde_json::json!([prev_hash, peer, entity, action, decision, detail, timestamp]);
+    hex::encode(Sha256::digest(fields.to_string().as_bytes()))
+}
+
+/// Milliseconds since the Unix epoch, as stored in the `timestamp` column.
+pub(crate) fn to_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+// ── Verification ─────────────────────────────────────────────────
+
+/// Why a row failed chain verification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditBreakReason {
+    /// The row's contents no longer match its hash.
+    Modified,
+    /// The row does not link to the row before it (rows removed, inserted or reordered).
+    Unlinked,
+}
+
+/// First row at which the audit chain stops verifying.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditChainBreak {
+    pub row_id: i64,
+    pub reason: AuditBreakReason,
+}
+
+/// Result of `PolicyStore::verify_audit_chain`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditVerification {
+    /// Rows verified before the first break (all rows when intact).
+    pub verified: usize,
+    /// Hash of the last verified row — record it externally to detect truncation.
+    pub head_hash: String,
+    pub first_break: Option<AuditChainBreak>,
+}
+
+impl AuditVerification {
+    /// Returns true if every row verified.
+    pub fn is_intact(&self) -> bool {
+        self.first_break.is_none()
+    }
+}
+
+// ── Queries ──────────────────────────────────────────────────────
+
+/// Filter for audit log queries. All set criteria must match.
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub peer: Option<PeerId>,
+    pub entity: Option<EntityId>,
+    pub action: Option<AuditAction>,
+    pub decision: Option<AuditDecision>,
+    /// Inclusive lower bound on the entry timestamp.
+    pub since: Option<SystemTime>,
+    /// Exclusive upper bound on the entry timestamp.
+    pub until: Option<SystemTime>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+    /// Return the newest entries first (default: chronological).
+    pub newest_first: bool,
+}
+
+impl AuditQuery {
+    /// Creates a query matching every entry, oldest first.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_peer(mut self, peer: PeerId) -> Self {
+        self.peer = Some(peer);
+        self
+    }
+
+    pub fn with_entity(mut self, entity: EntityId) -> Self {
+        self.entity = Some(entity);
+        self
+    }
+
+    pub fn with_action(mut self, action: AuditAction) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    pub fn with_decision(mut self, decision: AuditDecision) -> Self {
+        self.decision = Some(decision);
+        self
+    }
+
+    pub fn since(mut self, time: SystemTime) -> Self {
+        self.since = Some(time);
+        self
+    }
+
+    pub fn until(mut self, time: SystemTime) -> Self {
+        self.until = Some(time);
+        self
+    }
+
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn newest_first(mut self) -> Self {
+        self.newest_first = true;
+        self
+    }
+}
+
+// ── Retention ────────────────────────────────────────────────────
+
+/// Retention rules applied by `PolicyStore::apply_audit_retention`.
+///
+/// Pruning always removes a prefix of the log (oldest rows first) so the
+/// remaining chain stays verifiable. Export rows before pruning them if they
+/// must be kept elsewhere.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditRetention {
+    /// Remove entries older than this.
+    pub max_age: Option<Duration>,
+    /// Keep at most this many entries.
+    pub max_entries: Option<usize>,
+}
+
+impl AuditRetention {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Builds rules from a retention period in days (as configured in the
+    /// enterprise policy's `[policy.audit]` section).
+    pub fn from_config(retention_days: Option<u32>, max_entries: Option<usize>) -> Self {
+        Self {
+            max_age: retention_days.map(|d| Duration::from_secs(u64::from(d) * 24 * 60 * 60)),
+            max_entries,
+        }
+    }
+
+    /// Returns true if no rule is set (nothing is ever pruned).
+    pub fn is_unbounded(&self) -> bool {
+        self.max_age.is_none() && self.max_entries.is_none()
+    }
+}
+
+// ── Export ───────────────────────────────────────────────────────
+
+/// Export format for the audit log.
+///
+/// Parsed from the enterprise policy's `[policy.audit] export_format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditExportFormat {
+    /// One JSON object per line (`"json"` or `"jsonl"`).
+    Jsonl,
+    /// ArcSight Common Event Format, one event per line (`"cef"`).
+    Cef,
+    /// CEF wrapped in RFC 5424 syslog lines (`"syslog"`).
+    Syslog,
+}
+
+impl FromStr for AuditExportFormat {
+    type Err = SyncError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "json" | "jsonl" => Ok(Self::Jsonl),
+            "cef" => Ok(Self::Cef),
+            "syslog" => Ok(Self::Syslog),
+            other => Err(SyncError::Storage(format!(
+                "unsupported audit export format: {other}"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for AuditExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Jsonl => write!(f, "jsonl"),
+            Self::Cef => write!(f, "cef"),
+            Self::Syslog => write!(f, "syslog"),
+        }
+    }
+}
+
+impl AuditExportFormat {
+    /// Formats one record as a single line (without the trailing newline).
+    pub fn format_record(&self, record: &AuditRecord) -> String {
+        match self {
+            Self::Jsonl => format_jsonl(record),
+            Self::Cef => format_cef(record),
+            Self::Syslog => format_syslog(record),
+        }
+    }
+}
+
+fn format_jsonl(record: &AuditRecord) -> String {
+    let entry = &record.entry;
+    serde_json::json!({
+        "id": record.id,
+        "timestamp": to_millis(entry.timestamp),
+        "peer": entry.peer.to_string(),
+        "entity": entry.entity.map(|e| e.to_string()),
+        "action": entry.action.to_string(),
+        "decision": format!("{:?}", entry.decision),
+        "detail": entry.detail,
+        "prev_hash": record.prev_hash,
+        "hash": record.hash,
+    })
+    .to_string()
+}
+
+/// CEF severity (0-10) for a decision.
+fn cef_severity(decision: &AuditDecision) -> u8 {
+    match decision {
+        AuditDecision::Allowed => 1,
+        AuditDecision::Filtered => 3,
+        AuditDecision::Denied => 6,
+    }
+}
+
+fn format_cef(record: &AuditRecord) -> String {
+    let entry = &record.entry;
+    let action = entry.action.to_string();
+    let decision = format!("{:?}", entry.decision);
+    let mut extension = format!(
+        "rt={} suser={} act={} msg={} cs1Label=prevHash cs1={} cs2Label=hash cs2={} cn1Label=rowId cn1={}",
+        to_millis(entry.timestamp),
+        cef_extension_escape(&entry.peer.to_string()),
+        decision,
+        cef_extension_escape(&entry.detail),
+        record.prev_hash,
+        record.hash,
+        record.id,
+    );
+    if let Some(entity) = entry.entity {
+        extension.push_str(&format!(" cs3Label=entity cs3={entity}"));
+    }
+    format!(
+        "CEF:0|PrivStack|privstack-sync|{}|{}|{}|{}|{}",
+        cef_header_escape(env!("CARGO_PKG_VERSION")),
+        cef_header_escape(&action),
+        cef_header_escape(&format!("{action} {decision}")),
+        cef_severity(&entry.decision),
+        extension,
+    )
+}
+
+fn format_syslog(record: &AuditRecord) -> String {
+    // Facility 10 (security/authorization); warning for denials, notice otherwise.
+    let severity = match record.entry.decision {
+        AuditDecision::Denied => 4,
+        _ => 5,
+    };
+    let timestamp = chrono::DateTime::<chrono::Utc>::from(record.entry.timestamp)
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    format!(
+        "<{}>1 {} - privstack-sync - audit - {}",
+        10 * 8 + severity,
+        timestamp,
+        format_cef(record)
+    )
+}
+
+fn cef_header_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+fn cef_extension_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n")
+}
@@ -4,13 +4,15 @@
 //! The orchestrator handles all I/O (sending/receiving via transport).
 
 use crate::acl_applicator::AclEventHandler;
-use crate::applicator::EventApplicator;
+use crate::applicator::{DomainRegistry, EventApplicator};
 use crate::policy::{AllowAllPolicy, SyncPolicy};
 use crate::protocol::{
-    EventAckMessage, EventBatchMessage, HelloAckMessage, HelloMessage, SyncMessage,
-    SyncRequestMessage, SyncStateMessage, MAX_BATCH_SIZE, PROTOCOL_VERSION,
+    Capability, ErrorMessage, EventAckMessage, EventBatchMessage, HelloAckMessage, HelloMessage,
+    NegotiatedProtocol, SyncMessage, SyncRequestMessage, SyncStateMessage, VersionRange,
+    MAX_BATCH_SIZE,
 };
 use crate::state::{PeerSyncStatus, SyncState};
+use privstack_crypto::{Authorship, IdentityPublicKey};
 use privstack_storage::{EntityStore, EventStore};
 use privstack_types::{EntityId, Event, EventId, PeerId};
 use std::collections::{HashMap, HashSet};
@@ -27,6 +29,10 @@ pub struct SyncConfig {
     pub batch_size: usize,
     /// Timeout for sync operations (ms).
     pub timeout_ms: u64,
+    /// Protocol versions offered during the handshake.
+    pub protocol_versions: VersionRange,
+    /// Optional protocol features offered during the handshake.
+    pub capabilities: Vec<Capability>,
 }
 
 impl Default for SyncConfig {
@@ -35,6 +41,8 @@ impl Default for SyncConfig {
             device_name: "PrivStack Device".to_string(),
             batch_size: MAX_BATCH_SIZE,
             timeout_ms: 30_000,
+            protocol_versions: VersionRange::supported(),
+            capabilities: Capability::SUPPORTED.to_vec(),
         }
     }
 }
@@ -55,6 +63,10 @@ pub struct SyncEngine {
     policy: Arc<dyn SyncPolicy>,
     /// Optional ACL event handler for ACL-as-CRDT propagation.
     acl_handler: Option<Arc<dyn AclEventHandler>>,
+    /// Schemas and domain handlers for merging incoming entity events.
+    domain_registry: Option<Arc<DomainRegistry>>,
+    /// Identity key advertised in handshakes so paired peers can register it.
+    identity_key: Option<IdentityPublicKey>,
 }
 
 impl SyncEngine {
@@ -77,6 +89,8 @@ impl SyncEngine {
             peer_known_ids: Arc::new(RwLock::new(HashMap::new())),
             policy,
             acl_handler: None,
+            domain_registry: None,
+            identity_key: None,
         }
     }
 
@@ -85,6 +99,22 @@ impl SyncEngine {
         self.acl_handler = Some(handler);
     }
 
+    /// Sets the schemas and domain handlers used to merge incoming events.
+    pub fn set_domain_registry(&mut self, registry: Arc<DomainRegistry>) {
+        self.domain_registry = Some(registry);
+    }
+
+    /// Sets the identity key this device signs its events with, advertised in
+    /// `Hello` / `HelloAck` so paired peers can register it.
+    pub fn set_identity_key(&mut self, key: IdentityPublicKey) {
+        self.identity_key = Some(key);
+    }
+
+    /// Returns the registry set with [`Self::set_domain_registry`].
+    pub fn domain_registry(&self) -> Option<&Arc<DomainRegistry>> {
+        self.domain_registry.as_ref()
+    }
+
     /// Returns a reference to the policy.
     pub fn policy(&self) -> &Arc<dyn SyncPolicy> {
         &self.policy
@@ -110,13 +140,27 @@ impl SyncEngine {
     /// Produces a Hello message to send to a peer.
     pub fn make_hello(&self, entity_ids: Vec<EntityId>) -> SyncMessage {
         let hello = HelloMessage::new(self.peer_id, &self.config.device_name)
-            .with_entities(entity_ids);
+            .with_protocol(self.config.protocol_versions, self.config.capabilities.clone())
+            .with_entities(entity_ids)
+            .with_identity_key(self.identity_key.map(|k| k.to_base64()));
         SyncMessage::Hello(hello)
     }
 
     /// Produces a HelloAck (accept) response.
     pub fn make_hello_accept(&self) -> SyncMessage {
-        SyncMessage::HelloAck(HelloAckMessage::accept(self.peer_id, &self.config.device_name))
+        SyncMessage::HelloAck(
+            HelloAckMessage::accept(self.peer_id, &self.config.device_name)
+                .with_identity_key(self.identity_key.map(|k| k.to_base64())),
+        )
+    }
+
+    /// Produces a HelloAck (accept) response carrying the negotiated protocol.
+    fn make_hello_accept_with(&self, negotiated: &NegotiatedProtocol) -> SyncMessage {
+        SyncMessage::HelloAck(
+            HelloAckMessage::accept(self.peer_id, &self.config.device_name)
+                .with_protocol(self.config.protocol_versions, negotiated)
+                .with_identity_key(self.identity_key.map(|k| k.to_base64())),
+        )
     }
 
     /// Produces a HelloAck (reject) response.
@@ -124,18 +168,22 @@ impl SyncEngine {
         SyncMessage::HelloAck(HelloAckMessage::reject(self.peer_id, reason))
     }
 
-    /// Produces a SyncRequest message for the given entities, including
-    /// our known event IDs so the responder can compute a reverse delta.
+    /// Produces a SyncRequest message for the given entities. If the peer
+    /// agreed to [`Capability::DeltaEvents`], it includes our known event
+    /// IDs so the responder can compute a reverse delta.
     pub async fn make_sync_request(
         &self,
+        peer_id: &PeerId,
         entity_ids: Vec<EntityId>,
         event_store: &Arc<EventStore>,
     ) -> SyncMessage {
         let mut known_event_ids = HashMap::new();
-        for eid in &entity_ids {
-            let ids = self.known_event_ids_from_store(eid, event_store).await;
-            if !ids.is_empty() {
-                known_event_ids.insert(*eid, ids.into_iter().collect());
+        if self.peer_supports(peer_id, Capability::DeltaEvents).await {
+            for eid in &entity_ids {
+                let ids = self.known_event_ids_from_store(eid, event_store).await;
+                if !ids.is_empty() {
+                    known_event_ids.insert(*eid, ids.into_iter().collect());
+                }
             }
         }
         SyncMessage::SyncRequest(SyncRequestMessage {
@@ -189,13 +237,18 @@ impl SyncEngine {
 
     /// Handles a Hello message from a remote peer.
     /// Returns the response to send back.
+    /// Picks the highest protocol version and the feature set both peers share.
     pub async fn handle_hello(&self, hello: &HelloMessage) -> SyncMessage {
-        if hello.version != PROTOCOL_VERSION {
-            return self.make_hello_reject(format!(
-                "version mismatch: expected {PROTOCOL_VERSION}, got {}",
-                hello.version
-            ));
-        }
+        let ours = self.config.protocol_versions;
+        let theirs = hello.version_range();
+        let Some(negotiated) = NegotiatedProtocol::negotiate(
+            &ours,
+            &self.config.capabilities,
+            &theirs,
+            hello.advertised_capabilities(),
+        ) else {
+            return self.make_hello_reject(ErrorMessage::no_common_version(&ours, &theirs).message);
+        };
 
         // Policy gate: handshake
         if let Err(e) = self.policy.on_handshake(&self.peer_id, &hello.peer_id).await {
@@ -214,9 +267,68 @@ impl SyncEngine {
         let mut status = PeerSyncStatus::new(hello.peer_id, &hello.device_name);
         status.shared_entities = hello.entity_ids.clone();
         status.connected = true;
+        status.protocol = Some(negotiated.clone());
         self.peers.write().await.insert(hello.peer_id, status);
 
-        self.make_hello_accept()
+        debug!(
+            "Negotiated protocol v{} with {} ({:?})",
+            negotiated.version, hello.peer_id, negotiated.capabilities
+        );
+        self.make_hello_accept_with(&negotiated)
+    }
+
+    /// Handles the HelloAck answering our Hello.
+    /// Validates the version the responder picked and records the agreed
+    /// protocol for the peer. Returns the rejection reason on failure.
+    pub async fn handle_hello_ack(
+        &self,
+        peer_id: &PeerId,
+        ack: &HelloAckMessage,
+    ) -> Result<NegotiatedProtocol, String> {
+        if !ack.accepted {
+            return Err(ack.reason.clone().unwrap_or_else(|| "rejected".to_string()));
+        }
+
+        let ours = self.config.protocol_versions;
+        let theirs = ack.version_range();
+        if !theirs.contains(ack.version) {
+            return Err(ErrorMessage::no_common_version(&ours, &theirs).message);
+        }
+        let negotiated = NegotiatedProtocol::negotiate(
+            &ours,
+            &self.config.capabilities,
+            &VersionRange::new(ack.version, ack.version),
+            ack.advertised_capabilities(),
+        )
+        .ok_or_else(|| ErrorMessage::no_common_version(&ours, &theirs).message)?;
+
+        let mut peers = self.peers.write().await;
+        let status = peers
+            .entry(*peer_id)
+            .or_insert_with(|| PeerSyncStatus::new(*peer_id, &ack.device_name));
+        status.device_name = ack.device_name.clone();
+        status.connected = true;
+        status.protocol = Some(negotiated.clone());
+
+        Ok(negotiated)
+    }
+
+    /// Returns the protocol agreed with a peer in the last handshake.
+    pub async fn peer_protocol(&self, peer_id: &PeerId) -> Option<NegotiatedProtocol> {
+        self.peers
+            .read()
+            .await
+            .get(peer_id)
+            .and_then(|p| p.protocol.clone())
+    }
+
+    /// Whether a capability may be used with a peer. A peer without a
+    /// recorded handshake is treated as predating negotiation.
+    pub async fn peer_supports(&self, peer_id: &PeerId, capability: Capability) -> bool {
+        match self.peer_protocol(peer_id).await {
+            Some(protocol) => protocol.supports(capability),
+            None => Capability::LEGACY.contains(&capability),
+        }
     }
 
     /// Handles a SyncRequest from a remote peer.
@@ -237,7 +349,7 @@ impl SyncEngine {
             Ok(ids) => ids,
             Err(e) => {
                 warn!("Policy denied sync request from {}: {}", peer_id, e);
-                return SyncMessage::Error(crate::protocol::ErrorMessage::new(
+                return SyncMessage::Error(ErrorMessage::new(
                     403,
                     e.to_string(),
                 ));
@@ -246,7 +358,9 @@ impl SyncEngine {
 
         // Store the initiator's known event IDs so handle_event_batch can
         // compute which of our events they're missing.
-        if !request.known_event_ids.is_empty() {
+        if !request.known_event_ids.is_empty()
+            && self.peer_supports(peer_id, Capability::DeltaEvents).await
+        {
             let mut peer_ids = self.peer_known_ids.write().await;
             let entry = peer_ids.entry(*peer_id).or_default();
             for (eid, ids) in &request.known_event_ids {
@@ -352,8 +466,8 @@ impl SyncEngine {
     }
 
     /// Handles a received event batch — applies events and returns an ack.
-    /// If the initiator sent known_event_ids, the ack includes events the
-    /// initiator is missing (bidirectional sync).
+    /// If the peer agreed to [`Capability::DeltaEvents`], the ack includes
+    /// events the initiator is missing (bidirectional sync).
     pub async fn handle_event_batch(
         &self,
         peer_id: &PeerId,
@@ -361,10 +475,14 @@ impl SyncEngine {
         entity_store: &Arc<EntityStore>,
         event_store: &Arc<EventStore>,
     ) -> (SyncMessage, Vec<EntityId>) {
+        // Drop events whose author signature does not verify before any
+        // policy sees them; policy attributes signed events to their author.
+        let verified_events = self.verify_authors(peer_id, &batch.events, event_store).await;
+
         // Policy gate: filter incoming events
         let allowed_events = match self
             .policy
-            .on_event_receive(peer_id, &batch.entity_id, &batch.events)
+            .on_event_receive(peer_id, &batch.entity_id, &verified_events)
             .await
         {
             Ok(evts) => evts,
@@ -426,9 +544,10 @@ impl SyncEngine {
             let es = entity_store.clone();
             let ev = event.clone();
             let app_peer = self.peer_id;
+            let registry = self.domain_registry.clone();
             let apply_result = tokio::task::spawn_blocking(move || {
                 let applicator = EventApplicator::new(app_peer);
-                applicator.apply_event(&ev, &es, None, None)
+                applicator.apply_event_with_registry(&ev, &es, registry.as_deref())
             })
             .await;
 
@@ -476,7 +595,7 @@ impl SyncEngine {
 
         // Compute reverse delta: events we have that the initiator is missing.
         let mut reverse_events = Vec::new();
-        if batch.is_final {
+        if batch.is_final && self.peer_supports(peer_id, Capability::DeltaEvents).await {
             // Build the full set of IDs the initiator knows: their declared known_event_ids
             // plus everything they just sent us in this (and prior) batches.
             let peer_ids = self.peer_known_ids.read().await;
@@ -556,6 +675,48 @@ impl SyncEngine {
         (SyncMessage::EventAck(ack), updated_entities.into_iter().collect())
     }
 
+    /// Returns the events whose author checks out (see `EventStore::verify_author`),
+    /// logging and dropping the rest.
+    ///
+    /// Events signed or sealed by a peer with no registered key (accepted only
+    /// in `SignatureMode::LegacyMigration`) come back without their signature
+    /// and seal, so policy attributes them to the sending peer like any
+    /// unsigned event.
+    pub async fn verify_authors(
+        &self,
+        peer_id: &PeerId,
+        events: &[Event],
+        event_store: &Arc<EventStore>,
+    ) -> Vec<Event> {
+        let store = event_store.clone();
+        let evs = events.to_vec();
+        let sender = *peer_id;
+        tokio::task::spawn_blocking(move || {
+            evs.into_iter()
+                .filter_map(|mut ev| match store.verify_author(&ev) {
+                    Ok(Authorship::Verified(_) | Authorship::Redacted(_)) => Some(ev),
+                    Ok(Authorship::Unsigned) => {
+                        ev.signature = None;
+                        ev.redaction_seal = None;
+                        Some(ev)
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Rejected event {} from {} (author {}): {}",
+                            ev.id, sender, ev.peer_id, e
+                        );
+                        None
+                    }
+                })
+                .collect()
+        })
+        .await
+        .unwrap_or_else(|e| {
+            warn!("spawn_blocking panicked verifying events: {}", e);
+            Vec::new()
+        })
+    }
+
     /// Records a local event into the sync state.
     pub async fn record_local_event(&self, event: &Event) {
         self.state.write().await.record_event(event.entity_id, event);
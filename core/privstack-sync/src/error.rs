@@ -47,4 +47,8 @@ pub enum SyncError {
     /// Policy denied the operation.
     #[error("policy denied: {reason}")]
     PolicyDenied { reason: String },
+
+    /// An enterprise policy file failed to parse or validate.
+    #[error("invalid policy file: {0}")]
+    InvalidPolicy(String),
 }
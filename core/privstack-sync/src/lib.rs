@@ -43,6 +43,7 @@
 
 pub mod acl_applicator;
 pub mod applicator;
+pub mod audit;
 pub mod cloud;
 mod engine;
 mod error;
@@ -50,13 +51,23 @@ mod orchestrator;
 pub mod p2p;
 pub mod pairing;
 pub mod policy;
+pub mod policy_crdt;
+pub mod policy_file;
 pub mod policy_store;
 pub mod protocol;
+pub mod redaction;
 pub mod state;
+pub mod sync_scope;
 pub mod transport;
 
 pub use acl_applicator::{AclApplicator, AclEventHandler};
-pub use applicator::{create_event, ApplicatorError, ApplicatorResult, EventApplicator};
+pub use applicator::{
+    create_event, ApplicatorError, ApplicatorResult, DomainRegistry, EventApplicator,
+};
+pub use audit::{
+    AuditBreakReason, AuditChainBreak, AuditExportFormat, AuditQuery, AuditRecord,
+    AuditRetention, AuditVerification,
+};
 pub use orchestrator::{
     create_enterprise_orchestrator, create_orchestrator, create_orchestrator_with_pairing,
     create_orchestrator_with_policy, create_personal_orchestrator, OrchestratorConfig,
@@ -66,16 +77,24 @@ pub use orchestrator::{
 pub use engine::{SyncConfig, SyncEngine};
 pub use error::{SyncError, SyncResult};
 pub use policy::{
-    AllowAllPolicy, AuditAction, AuditDecision, AuditEntry, DeviceId, EntityAcl,
-    EnterpriseSyncPolicy, PersonalSyncPolicy, SyncPolicy, SyncRole, TeamId,
+    event_author, AllowAllPolicy, AuditAction, AuditDecision, AuditEntry, DeviceId, EntityAcl,
+    EnterpriseSyncPolicy, GrantWindow, PersonalSyncPolicy, SyncPolicy, SyncRole, TeamId,
+};
+pub use policy_file::{
+    EnterprisePolicyFile, PolicyDiff, PolicyFileFormat, PolicyFileSource, TeamSpec,
+    POLICY_FILE_VERSION,
 };
+pub use policy_crdt::{is_policy_event, PolicyChange, PolicyCrdt, POLICY_ENTITY_ID};
 pub use policy_store::PolicyStore;
 pub use protocol::{
-    ErrorMessage, EventAckMessage, EventBatchMessage, EventNotifyMessage, HelloAckMessage,
-    HelloMessage, SubscribeMessage, SyncMessage, SyncRequestMessage, SyncStateMessage,
-    MAX_BATCH_SIZE, PROTOCOL_VERSION,
+    Capability, ErrorMessage, EventAckMessage, EventBatchMessage, EventNotifyMessage,
+    HelloAckMessage, HelloMessage, NegotiatedProtocol, SubscribeMessage, SyncMessage,
+    SyncRequestMessage, SyncStateMessage, VersionRange, MAX_BATCH_SIZE, MIN_PROTOCOL_VERSION,
+    PROTOCOL_VERSION,
 };
+pub use redaction::{redact_event, restore_redacted, RedactionRules};
 pub use state::{EntitySyncState, PeerSyncStatus, SyncState};
+pub use sync_scope::{EntityMeta, SyncScope};
 pub use transport::{
     DiscoveredPeer, DiscoveryMethod, IncomingSyncRequest, ResponseToken, SyncTransport,
 };
@@ -9,12 +9,14 @@
 
 use crate::engine::SyncEngine;
 use crate::pairing::PairingManager;
-use crate::policy::{PersonalSyncPolicy, SyncPolicy};
+use crate::policy::{EnterpriseSyncPolicy, PersonalSyncPolicy, SyncPolicy};
 use crate::protocol::{
-    ErrorMessage, SyncMessage, SyncStateMessage, PROTOCOL_VERSION,
+    Capability, ErrorMessage, SyncMessage, SyncStateMessage,
 };
+use crate::sync_scope::EntityMeta;
 use crate::transport::SyncTransport;
 use crate::{SyncConfig, SyncError, SyncResult};
+use privstack_crypto::{IdentityKeyPair, IdentityPublicKey};
 use privstack_storage::{EntityStore, EventStore};
 use privstack_types::{EntityId, Event, EventId, PeerId};
 use std::collections::HashSet;
@@ -161,9 +163,27 @@ pub struct SyncOrchestrator {
     pairing_manager: Option<Arc<std::sync::Mutex<PairingManager>>>,
     /// Optional personal sync policy for per-peer entity sharing.
     personal_policy: Option<Arc<PersonalSyncPolicy>>,
+    /// Key this device signs the events it creates with.
+    identity_key: Option<Arc<IdentityKeyPair>>,
+    /// Enterprise policy maintained on each sync tick.
+    enterprise_policy: Option<Arc<EnterpriseSyncPolicy>>,
 }
 
 impl SyncOrchestrator {
+    /// Sets the schemas and domain handlers used to merge incoming events
+    /// (e.g. plugin `MergeStrategy::Custom` handlers). Call before `run`.
+    pub fn set_domain_registry(&mut self, registry: Arc<crate::applicator::DomainRegistry>) {
+        self.engine.set_domain_registry(registry);
+    }
+
+    /// Sets the key this device signs its events with. Snapshot events and
+    /// unsigned local events authored by this device are signed with it, and
+    /// its public half is advertised to paired peers. Call before `run`.
+    pub fn set_identity_key(&mut self, key: Arc<IdentityKeyPair>) {
+        self.engine.set_identity_key(key.public_key());
+        self.identity_key = Some(key);
+    }
+
     /// Runs the orchestrator event loop with a transport.
     pub async fn run(
         mut self,
@@ -180,8 +200,9 @@ impl SyncOrchestrator {
             let entity_store = self.entity_store.clone();
             let event_store = self.event_store.clone();
             let peer_id = self.engine.peer_id();
+            let identity_key = self.identity_key.clone();
             match tokio::task::spawn_blocking(move || {
-                let ids = entity_store.list_all_entity_ids()?;
+                let ids = entity_store.list_syncable_entity_ids()?;
                 let mut needs_snapshot: Vec<(EntityId, String, String)> = Vec::new();
                 for id_str in &ids {
                     if let Ok(eid) = id_str.parse::<EntityId>() {
@@ -199,7 +220,10 @@ impl SyncOrchestrator {
                 // Create snapshot events for entities without events
                 let snapshot_count = needs_snapshot.len();
                 for (eid, etype, data) in needs_snapshot {
-                    let event = Event::full_snapshot(eid, peer_id, &etype, &data);
+                    let mut event = Event::full_snapshot(eid, peer_id, &etype, &data);
+                    if let Some(key) = &identity_key {
+                        key.sign_event(&mut event);
+                    }
                     if let Err(e) = event_store.save_event(&event) {
                         eprintln!("[SYNC] Failed to create snapshot event for {}: {}", eid, e);
                     }
@@ -282,6 +306,7 @@ impl SyncOrchestrator {
                 _ = sync_interval.tick() => {
                     debug!("[SYNC] Sync interval tick");
                     self.periodic_sync(&transport).await;
+                    self.run_policy_maintenance().await;
                 }
             }
         }
@@ -289,7 +314,22 @@ impl SyncOrchestrator {
         Ok(())
     }
 
-    async fn handle_local_event(&self, event: Event) {
+    /// Sweeps expired grants and prunes the audit log of the enterprise policy.
+    async fn run_policy_maintenance(&self) {
+        if let Some(policy) = &self.enterprise_policy {
+            policy.run_maintenance().await;
+        }
+    }
+
+    async fn handle_local_event(&self, mut event: Event) {
+        // Sign events this device authored that arrive unsigned
+        if !event.is_signed()
+            && event.peer_id == self.engine.peer_id()
+            && let Some(key) = &self.identity_key
+        {
+            key.sign_event(&mut event);
+        }
+
         // Save to event store on a blocking thread.
         // Note: the FFI layer also saves directly for immediacy; the duplicate
         // INSERT OR IGNORE here is harmless.
@@ -324,9 +364,10 @@ impl SyncOrchestrator {
             let peer_id = self.engine.peer_id();
             let es = self.entity_store.clone();
             let ev = event.clone();
+            let registry = self.engine.domain_registry().cloned();
             let _ = tokio::task::spawn_blocking(move || {
                 let applicator = crate::applicator::EventApplicator::new(peer_id);
-                applicator.apply_event(&ev, &es, None, None)
+                applicator.apply_event_with_registry(&ev, &es, registry.as_deref())
             }).await;
         }
 
@@ -356,6 +397,47 @@ impl SyncOrchestrator {
         }
     }
 
+    /// Registers the identity key a peer advertised in an accepted handshake
+    /// as the author key of `author` (the peer ID its events carry). Only
+    /// paired peers qualify: the transport peer must be trusted in the
+    /// pairing manager, so open-mode and unapproved peers never bind a key.
+    /// A peer may only bind a key for itself: a claimed `author` other than
+    /// the transport peer is refused, or any paired device could sign
+    /// events as another peer.
+    async fn register_paired_identity_key(
+        &self,
+        transport_peer: PeerId,
+        author: PeerId,
+        key: Option<&str>,
+    ) {
+        let Some(encoded) = key else {
+            return;
+        };
+        if self.pairing_manager.is_none() || !self.is_peer_trusted_sync(&transport_peer) {
+            return;
+        }
+        if author != transport_peer {
+            warn!(
+                "[SYNC] Peer {} claimed to be {}; identity key not registered",
+                transport_peer, author
+            );
+            return;
+        }
+        let key = match IdentityPublicKey::from_base64(encoded) {
+            Ok(key) => key,
+            Err(e) => {
+                warn!("[SYNC] Peer {} sent an invalid identity key: {}", transport_peer, e);
+                return;
+            }
+        };
+        let store = self.event_store.clone();
+        match tokio::task::spawn_blocking(move || store.register_author_key(&author, &key)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("[SYNC] Identity key of {} not registered: {}", author, e),
+            Err(e) => warn!("[SYNC] spawn_blocking panicked registering identity key: {}", e),
+        }
+    }
+
     async fn check_for_new_peers(&mut self, transport: &Arc<TokioMutex<dyn SyncTransport>>) {
         let transport_guard = transport.lock().await;
         let discovered = transport_guard.discovered_peers_async().await;
@@ -488,6 +570,40 @@ impl SyncOrchestrator {
                     policy.shared_entities(&peer_id).await.into_iter().collect();
                 entity_ids.retain(|eid| peer_entities.contains(eid));
             }
+
+            // Rule-based sync scope: evaluate against current entity metadata.
+            if policy.has_sync_scope(&peer_id).await && !entity_ids.is_empty() {
+                let store = self.entity_store.clone();
+                let ids = entity_ids.clone();
+                let metas = tokio::task::spawn_blocking(move || {
+                    ids.into_iter()
+                        .map(|eid| {
+                            let meta = store
+                                .get_entity(&eid.to_string())
+                                .ok()
+                                .flatten()
+                                .map(|e| EntityMeta::from_entity(&e));
+                            (eid, meta)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .await
+                .unwrap_or_default();
+
+                let mut in_scope = Vec::with_capacity(metas.len());
+                for (eid, meta) in metas {
+                    // Entities without a row (e.g. pending deletes) are left to
+                    // the event-level scope check in on_event_send.
+                    let allowed = match &meta {
+                        Some(m) => policy.scope_allows(&peer_id, m).await,
+                        None => true,
+                    };
+                    if allowed {
+                        in_scope.push(eid);
+                    }
+                }
+                entity_ids = in_scope;
+            }
         }
 
         if entity_ids.is_empty() {
@@ -528,23 +644,28 @@ impl SyncOrchestrator {
 
         match hello_response {
             Ok(SyncMessage::HelloAck(ack)) => {
-                if !ack.accepted {
-                    warn!("[SYNC] Peer {} rejected: {:?}", peer_id, ack.reason);
-                    let _ = self.event_tx.send(SyncEvent::SyncFailed {
-                        peer_id,
-                        error: ack.reason.unwrap_or_else(|| "rejected".to_string()),
-                    }).await;
-                    return;
-                }
-                if ack.version != PROTOCOL_VERSION {
-                    warn!("[SYNC] Version mismatch with peer {}", peer_id);
-                    let _ = self.event_tx.send(SyncEvent::SyncFailed {
-                        peer_id,
-                        error: format!("version mismatch: expected {PROTOCOL_VERSION}, got {}", ack.version),
-                    }).await;
-                    return;
+                match self.engine.handle_hello_ack(&peer_id, &ack).await {
+                    Ok(negotiated) => {
+                        self.register_paired_identity_key(
+                            peer_id,
+                            ack.peer_id,
+                            ack.identity_key.as_deref(),
+                        )
+                        .await;
+                        info!(
+                            "[SYNC] Handshake accepted by peer {} ({}), protocol v{}",
+                            peer_id, ack.device_name, negotiated.version
+                        );
+                    }
+                    Err(reason) => {
+                        warn!("[SYNC] Peer {} rejected: {}", peer_id, reason);
+                        let _ = self.event_tx.send(SyncEvent::SyncFailed {
+                            peer_id,
+                            error: reason,
+                        }).await;
+                        return;
+                    }
                 }
-                info!("[SYNC] Handshake accepted by peer {} ({})", peer_id, ack.device_name);
             }
             Ok(other) => {
                 warn!("[SYNC] Unexpected response to Hello: {:?}", other);
@@ -564,8 +685,13 @@ impl SyncOrchestrator {
             }
         }
 
-        // Step 2: Request their sync state (include our known event IDs for bidirectional sync)
-        let sync_req = self.engine.make_sync_request(entity_ids.clone(), &self.event_store).await;
+        // Step 2: Request their sync state (include our known event IDs for
+        // bidirectional sync if the peer agreed to deltas)
+        let sync_req = self
+            .engine
+            .make_sync_request(&peer_id, entity_ids.clone(), &self.event_store)
+            .await;
+        let deltas = self.engine.peer_supports(&peer_id, Capability::DeltaEvents).await;
         let state_response = {
             let tg = transport.lock().await;
             tg.send_request(&peer_id, sync_req).await
@@ -627,6 +753,11 @@ impl SyncOrchestrator {
                     entities_skipped += 1;
                     continue;
                 }
+                if !deltas {
+                    // The peer pushes its events when it syncs with us.
+                    entities_skipped += 1;
+                    continue;
+                }
                 // Peer has events we don't — send empty batch to trigger reverse delta
             }
 
@@ -718,11 +849,20 @@ impl SyncOrchestrator {
     /// Applies a remote event (from sync) to local stores.
     /// The `sender` is the peer that sent us this event, used for policy gating.
     async fn apply_remote_event(&self, sender: &PeerId, event: &Event) -> Result<bool, String> {
+        // Author check: drop events whose signature does not verify
+        let verified = self
+            .engine
+            .verify_authors(sender, std::slice::from_ref(event), &self.event_store)
+            .await;
+        if verified.is_empty() {
+            return Ok(false);
+        }
+
         // Policy gate: check if we should accept this event from sender
         match self
             .engine
             .policy()
-            .on_event_receive(sender, &event.entity_id, &[event.clone()])
+            .on_event_receive(sender, &event.entity_id, &verified)
             .await
         {
             Ok(allowed) if allowed.is_empty() => {
@@ -745,10 +885,11 @@ impl SyncOrchestrator {
         let peer_id = self.engine.peer_id();
         let es = self.entity_store.clone();
         let ev = event.clone();
+        let registry = self.engine.domain_registry().cloned();
 
         let apply_result = tokio::task::spawn_blocking(move || {
             let applicator = crate::applicator::EventApplicator::new(peer_id);
-            applicator.apply_event(&ev, &es, None, None)
+            applicator.apply_event_with_registry(&ev, &es, registry.as_deref())
         })
         .await
         .map_err(|e| format!("spawn_blocking panicked: {e}"))?;
@@ -813,7 +954,16 @@ impl SyncOrchestrator {
         let response = match request.message {
             SyncMessage::Hello(ref hello) => {
                 info!("[SYNC] Received Hello from {} ({})", hello.peer_id, hello.device_name);
-                self.engine.handle_hello(hello).await
+                let response = self.engine.handle_hello(hello).await;
+                if matches!(&response, SyncMessage::HelloAck(ack) if ack.accepted) {
+                    self.register_paired_identity_key(
+                        peer_id,
+                        hello.peer_id,
+                        hello.identity_key.as_deref(),
+                    )
+                    .await;
+                }
+                response
             }
 
             SyncMessage::SyncRequest(ref req) => {
@@ -831,6 +981,10 @@ impl SyncOrchestrator {
                 ).await;
 
                 for eid in &updated_entities {
+                    // Track entities learned from peers so they are forwarded even
+                    // when this node started with an empty store (relay peers).
+                    self.shared_entities.insert(*eid);
+
                     // Invalidate sync ledger so received events propagate to other peers.
                     // EventApplicator sets modified_at to the event's wall_time which may
                     // be older than the sync ledger's synced_at, so explicit invalidation
@@ -915,6 +1069,8 @@ pub fn create_orchestrator_with_policy(
         event_tx,
         pairing_manager: None,
         personal_policy: None,
+        identity_key: None,
+        enterprise_policy: None,
     };
 
     (handle, event_rx, command_rx, orchestrator)
@@ -956,6 +1112,8 @@ pub fn create_orchestrator_with_pairing(
         event_tx,
         pairing_manager: Some(pairing_manager),
         personal_policy: None,
+        identity_key: None,
+        enterprise_policy: None,
     };
 
     (handle, event_rx, command_rx, orchestrator)
@@ -994,6 +1152,8 @@ pub fn create_personal_orchestrator(
         event_tx,
         pairing_manager: Some(pairing_manager),
         personal_policy: Some(policy),
+        identity_key: None,
+        enterprise_policy: None,
     };
 
     (handle, event_rx, command_rx, orchestrator)
@@ -1015,7 +1175,7 @@ pub fn create_enterprise_orchestrator(
 ) {
     let mut engine = SyncEngine::with_policy(peer_id, SyncConfig::default(), policy.clone());
 
-    let acl_applicator = Arc::new(crate::acl_applicator::AclApplicator::new(policy));
+    let acl_applicator = Arc::new(crate::acl_applicator::AclApplicator::new(policy.clone()));
     engine.set_acl_handler(acl_applicator);
 
     let (command_tx, command_rx) = mpsc::channel(32);
@@ -1035,6 +1195,8 @@ pub fn create_enterprise_orchestrator(
         event_tx,
         pairing_manager: None,
         personal_policy: None,
+        identity_key: None,
+        enterprise_policy: Some(policy),
     };
 
     (handle, event_rx, command_rx, orchestrator)
@@ -6,6 +6,7 @@
 //! 3. Discovered devices must be approved before syncing
 //! 4. Approved devices become "trusted peers" that auto-sync
 
+use crate::sync_scope::SyncScope;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
@@ -193,6 +194,9 @@ pub struct PairingManager {
     discovered_peers: HashMap<String, DiscoveredPeerInfo>,
     /// Fully trusted peers (persisted)
     trusted_peers: HashMap<String, TrustedPeer>,
+    /// Rule-based sync scopes keyed by peer ID (persisted)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    sync_scopes: HashMap<String, SyncScope>,
 }
 
 impl PairingManager {
@@ -290,9 +294,34 @@ impl PairingManager {
         self.trusted_peers.contains_key(peer_id)
     }
 
-    /// Removes a trusted peer.
+    /// Removes a trusted peer (and any sync scope configured for it).
     pub fn remove_trusted_peer(&mut self, peer_id: &str) {
         self.trusted_peers.remove(peer_id);
+        self.sync_scopes.remove(peer_id);
+    }
+
+    /// Gets the sync scope configured for a peer.
+    pub fn sync_scope(&self, peer_id: &str) -> Option<&SyncScope> {
+        self.sync_scopes.get(peer_id)
+    }
+
+    /// Sets the sync scope for a peer. An empty scope clears it.
+    pub fn set_sync_scope(&mut self, peer_id: &str, scope: SyncScope) {
+        if scope.is_empty() {
+            self.sync_scopes.remove(peer_id);
+        } else {
+            self.sync_scopes.insert(peer_id.to_string(), scope);
+        }
+    }
+
+    /// Removes the sync scope for a peer.
+    pub fn clear_sync_scope(&mut self, peer_id: &str) {
+        self.sync_scopes.remove(peer_id);
+    }
+
+    /// Gets all configured sync scopes, keyed by peer ID.
+    pub fn sync_scopes(&self) -> &HashMap<String, SyncScope> {
+        &self.sync_scopes
     }
 
     /// Updates a trusted peer's addresses.
@@ -4,13 +4,21 @@
 //! `AllowAllPolicy` is the default (backward-compatible, no restrictions).
 //! `EnterpriseSyncPolicy` enforces ACLs, team membership, device limits, and audit trails.
 
+use crate::audit::{AuditExportFormat, AuditQuery, AuditRetention};
 use crate::error::SyncError;
+use crate::policy_crdt::{PolicyCrdt, POLICY_ENTITY_ID};
 use crate::policy_store::PolicyStore;
+use crate::redaction::{payload_entity_type, redact_event, RedactionRules};
+use crate::sync_scope::{EntityMeta, SyncScope};
 use async_trait::async_trait;
-use privstack_types::{EntityId, Event, EventPayload, PeerId};
+use privstack_crypto::IdentityKeyPair;
+use privstack_model::EntitySchema;
+use privstack_storage::{EntityStore, EventStore};
+use privstack_types::{EntityId, Event, EventId, EventPayload, PeerId};
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 
 /// Unique identifier for a team.
@@ -77,6 +85,47 @@ impl fmt::Display for SyncRole {
     }
 }
 
+/// Validity window of an ACL grant, in milliseconds since the Unix epoch.
+/// A grant with neither bound is permanent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GrantWindow {
+    /// The grant takes effect at this time (inclusive).
+    pub not_before: Option<u64>,
+    /// The grant lapses at this time (exclusive).
+    pub expires_at: Option<u64>,
+}
+
+impl GrantWindow {
+    pub fn new(not_before: Option<u64>, expires_at: Option<u64>) -> Self {
+        Self { not_before, expires_at }
+    }
+
+    /// A grant that lapses at `expires_at`.
+    pub fn until(expires_at: u64) -> Self {
+        Self::new(None, Some(expires_at))
+    }
+
+    /// Returns true if the grant has no time bounds.
+    pub fn is_permanent(&self) -> bool {
+        self.not_before.is_none() && self.expires_at.is_none()
+    }
+
+    /// Returns true if the grant is in effect at `now_ms`.
+    pub fn is_active_at(&self, now_ms: u64) -> bool {
+        self.not_before.is_none_or(|t| now_ms >= t) && !self.is_expired_at(now_ms)
+    }
+
+    /// Returns true if the grant has lapsed by `now_ms`.
+    pub fn is_expired_at(&self, now_ms: u64) -> bool {
+        self.expires_at.is_some_and(|t| now_ms >= t)
+    }
+}
+
+/// Current time in milliseconds since the Unix epoch.
+pub(crate) fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
 /// Access control list for a single entity.
 pub struct EntityAcl {
     pub entity_id: EntityId,
@@ -86,6 +135,10 @@ pub struct EntityAcl {
     pub peer_roles: HashMap<PeerId, SyncRole>,
     /// Per-team role grants.
     pub team_roles: HashMap<TeamId, SyncRole>,
+    /// Validity windows of time-bounded peer grants. Grants without an entry are permanent.
+    pub peer_windows: HashMap<PeerId, GrantWindow>,
+    /// Validity windows of time-bounded team grants. Grants without an entry are permanent.
+    pub team_windows: HashMap<TeamId, GrantWindow>,
 }
 
 impl EntityAcl {
@@ -95,6 +148,8 @@ impl EntityAcl {
             default_role: None,
             peer_roles: HashMap::new(),
             team_roles: HashMap::new(),
+            peer_windows: HashMap::new(),
+            team_windows: HashMap::new(),
         }
     }
 
@@ -112,6 +167,50 @@ impl EntityAcl {
         self.team_roles.insert(team, role);
         self
     }
+
+    /// Adds a peer grant that is only in effect within `window`.
+    pub fn with_peer_grant(mut self, peer: PeerId, role: SyncRole, window: GrantWindow) -> Self {
+        self.set_peer_grant(peer, role, window);
+        self
+    }
+
+    /// Adds a team grant that is only in effect within `window`.
+    pub fn with_team_grant(mut self, team: TeamId, role: SyncRole, window: GrantWindow) -> Self {
+        self.set_team_grant(team, role, window);
+        self
+    }
+
+    fn set_peer_grant(&mut self, peer: PeerId, role: SyncRole, window: GrantWindow) {
+        self.peer_roles.insert(peer, role);
+        if window.is_permanent() {
+            self.peer_windows.remove(&peer);
+        } else {
+            self.peer_windows.insert(peer, window);
+        }
+    }
+
+    fn set_team_grant(&mut self, team: TeamId, role: SyncRole, window: GrantWindow) {
+        self.team_roles.insert(team, role);
+        if window.is_permanent() {
+            self.team_windows.remove(&team);
+        } else {
+            self.team_windows.insert(team, window);
+        }
+    }
+
+    /// Returns the peer's own grant if it is in effect at `now_ms`.
+    fn active_peer_role(&self, peer: &PeerId, now_ms: u64) -> Option<SyncRole> {
+        let role = *self.peer_roles.get(peer)?;
+        let active = self.peer_windows.get(peer).is_none_or(|w| w.is_active_at(now_ms));
+        active.then_some(role)
+    }
+
+    /// Returns the team's grant if it is in effect at `now_ms`.
+    fn active_team_role(&self, team: &TeamId, now_ms: u64) -> Option<SyncRole> {
+        let role = *self.team_roles.get(team)?;
+        let active = self.team_windows.get(team).is_none_or(|w| w.is_active_at(now_ms));
+        active.then_some(role)
+    }
 }
 
 /// Action recorded in the audit log.
@@ -122,6 +221,7 @@ pub enum AuditAction {
     EventSend,
     EventReceive,
     DeviceRegister,
+    GrantExpired,
 }
 
 impl fmt::Display for AuditAction {
@@ -132,6 +232,7 @@ impl fmt::Display for AuditAction {
             AuditAction::EventSend => write!(f, "event_send"),
             AuditAction::EventReceive => write!(f, "event_receive"),
             AuditAction::DeviceRegister => write!(f, "device_register"),
+            AuditAction::GrantExpired => write!(f, "grant_expired"),
         }
     }
 }
@@ -177,6 +278,9 @@ pub trait SyncPolicy: Send + Sync {
     ) -> Result<Vec<Event>, SyncError>;
 
     /// Called when receiving events from a peer. Returns the subset of events to apply.
+    ///
+    /// Signatures have already been verified when this runs; use [`event_author`]
+    /// to find the peer whose rights an event should be checked against.
     async fn on_event_receive(
         &self,
         peer: &PeerId,
@@ -200,6 +304,23 @@ pub trait SyncPolicy: Send + Sync {
     }
 }
 
+/// Returns the peer an incoming event is attributed to for authorization.
+///
+/// Signed events reach policy hooks only after their signature was verified
+/// against the author's registered identity key, so they are attributed to
+/// `event.peer_id`. Sealed redacted copies are verified the same way against
+/// their redactor and attributed to it. Unsigned legacy events carry an
+/// unauthenticated `peer_id` and are attributed to the peer that delivered them.
+pub fn event_author(event: &Event, sender: &PeerId) -> PeerId {
+    if let Some(seal) = &event.redaction_seal {
+        seal.redactor
+    } else if event.is_signed() {
+        event.peer_id
+    } else {
+        *sender
+    }
+}
+
 // ── AllowAllPolicy ──────────────────────────────────────────────
 
 /// Default policy that permits everything. Backward-compatible with pre-policy behavior.
@@ -252,10 +373,33 @@ pub struct EnterpriseSyncPolicy {
     pub active_devices: Arc<RwLock<HashMap<PeerId, HashSet<DeviceId>>>>,
     /// Peers allowed to connect (if empty, membership check is skipped for handshake).
     pub known_peers: Arc<RwLock<HashSet<PeerId>>>,
+    /// Default role per entity type, used when an entity's ACL grants nothing.
+    pub type_default_roles: Arc<RwLock<HashMap<String, SyncRole>>>,
+    /// Entity type of each entity, registered explicitly or looked up in the
+    /// local entity store.
+    pub entity_types: Arc<RwLock<HashMap<EntityId, String>>>,
+    /// Retention rules applied to the persisted audit log.
+    pub audit_retention: Arc<RwLock<AuditRetention>>,
+    /// Format used by [`Self::export_audit_log`].
+    pub audit_export_format: Arc<RwLock<AuditExportFormat>>,
+    /// Fields withheld from peers by role.
+    pub redaction: Arc<RwLock<RedactionRules>>,
+    /// Sensitive `(field_path, label)` pairs per entity type, from registered schemas.
+    pub sensitive_fields: Arc<RwLock<HashMap<String, Vec<(String, String)>>>>,
     /// Audit log (in-memory).
     pub audit_log: Arc<RwLock<Vec<AuditEntry>>>,
+    /// Merge state for replicated known peers, team membership and device limits.
+    pub(crate) policy_crdt: Arc<RwLock<PolicyCrdt>>,
+    /// Serial and content digest of the last applied policy file.
+    pub(crate) applied_policy_file: Arc<RwLock<Option<(u64, String)>>>,
     /// Optional persistent store for audit + state.
     store: Option<Arc<PolicyStore>>,
+    /// Event store that identity keys from known-peer registrations go to.
+    author_keys: Option<Arc<EventStore>>,
+    /// Local entity store that entity types are looked up in.
+    entity_store: Option<Arc<EntityStore>>,
+    /// This device's peer ID and identity key, used to seal redacted copies.
+    redaction_signer: Option<(PeerId, Arc<IdentityKeyPair>)>,
     /// Maximum in-memory audit log entries before trimming.
     max_in_memory_log: usize,
 }
@@ -268,8 +412,19 @@ impl EnterpriseSyncPolicy {
             device_limits: Arc::new(RwLock::new(HashMap::new())),
             active_devices: Arc::new(RwLock::new(HashMap::new())),
             known_peers: Arc::new(RwLock::new(HashSet::new())),
+            type_default_roles: Arc::new(RwLock::new(HashMap::new())),
+            entity_types: Arc::new(RwLock::new(HashMap::new())),
+            audit_retention: Arc::new(RwLock::new(AuditRetention::default())),
+            audit_export_format: Arc::new(RwLock::new(AuditExportFormat::Jsonl)),
+            redaction: Arc::new(RwLock::new(RedactionRules::default())),
+            sensitive_fields: Arc::new(RwLock::new(HashMap::new())),
             audit_log: Arc::new(RwLock::new(Vec::new())),
+            policy_crdt: Arc::new(RwLock::new(PolicyCrdt::new())),
+            applied_policy_file: Arc::new(RwLock::new(None)),
             store: None,
+            author_keys: None,
+            entity_store: None,
+            redaction_signer: None,
             max_in_memory_log: 10_000,
         }
     }
@@ -280,6 +435,33 @@ impl EnterpriseSyncPolicy {
         self
     }
 
+    /// Registers the identity keys carried by `KnownPeerAdd` events in
+    /// `event_store`, so the admitted peers' signed events verify.
+    pub fn with_author_keys(mut self, event_store: Arc<EventStore>) -> Self {
+        self.author_keys = Some(event_store);
+        self
+    }
+
+    /// Seals the redacted copies this device sends with `key`, so receivers
+    /// that require signatures accept them. `peer_id` must be this device's.
+    /// Without a signer, redacted copies are sent unsigned.
+    pub fn with_redaction_signer(mut self, peer_id: PeerId, key: Arc<IdentityKeyPair>) -> Self {
+        self.redaction_signer = Some((peer_id, key));
+        self
+    }
+
+    /// Resolves entity types for per-type default roles from `entity_store`.
+    /// Without it, only types registered with [`Self::register_entity_type`] apply.
+    pub fn with_entity_store(mut self, entity_store: Arc<EntityStore>) -> Self {
+        self.entity_store = Some(entity_store);
+        self
+    }
+
+    /// Returns the event store set with [`Self::with_author_keys`].
+    pub(crate) fn author_keys(&self) -> Option<&Arc<EventStore>> {
+        self.author_keys.as_ref()
+    }
+
     /// Sets the maximum number of in-memory audit log entries.
     pub fn with_max_in_memory_log(mut self, max: usize) -> Self {
         self.max_in_memory_log = max;
@@ -312,10 +494,10 @@ impl EnterpriseSyncPolicy {
         let policy = Self::new().with_store(store.clone());
 
         // Load ACLs (peer roles)
-        for (entity_id, peer_id, role) in store.load_acls()? {
+        for (entity_id, peer_id, role, window) in store.load_acl_grants()? {
             let mut acls = policy.acls.write().await;
             let acl = acls.entry(entity_id).or_insert_with(|| EntityAcl::new(entity_id));
-            acl.peer_roles.insert(peer_id, role);
+            acl.set_peer_grant(peer_id, role, window);
         }
 
         // Load default roles
@@ -326,13 +508,13 @@ impl EnterpriseSyncPolicy {
         }
 
         // Load team roles
-        for (entity_id, team_id_str, role) in store.load_team_roles()? {
+        for (entity_id, team_id_str, role, window) in store.load_team_grants()? {
             let team_id = TeamId(uuid::Uuid::parse_str(&team_id_str).map_err(|e| {
                 SyncError::Storage(format!("invalid team_id: {e}"))
             })?);
             let mut acls = policy.acls.write().await;
             let acl = acls.entry(entity_id).or_insert_with(|| EntityAcl::new(entity_id));
-            acl.team_roles.insert(team_id, role);
+            acl.set_team_grant(team_id, role, window);
         }
 
         // Load team memberships
@@ -354,6 +536,14 @@ impl EnterpriseSyncPolicy {
             policy.known_peers.write().await.insert(peer_id);
         }
 
+        // Load per-type default roles and known entity types
+        for (entity_type, role) in store.load_type_default_roles()? {
+            policy.type_default_roles.write().await.insert(entity_type, role);
+        }
+        for (entity_id, entity_type) in store.load_entity_types()? {
+            policy.entity_types.write().await.insert(entity_id, entity_type);
+        }
+
         // Load active devices
         for (peer_id, device_id_str) in store.load_active_devices()? {
             let device_id = DeviceId(uuid::Uuid::parse_str(&device_id_str).map_err(|e| {
@@ -368,6 +558,11 @@ impl EnterpriseSyncPolicy {
                 .insert(device_id);
         }
 
+        *policy.applied_policy_file.write().await = store.load_policy_file_state()?;
+
+        // Rebuild the merge state of replicated policy
+        policy.restore_policy_events().await?;
+
         Ok(policy)
     }
 
@@ -379,14 +574,27 @@ impl EnterpriseSyncPolicy {
         entity_id: EntityId,
         peer_id: PeerId,
         role: SyncRole,
+    ) {
+        self.grant_peer_role_within(entity_id, peer_id, role, GrantWindow::default())
+            .await;
+    }
+
+    /// Grants a peer a role on an entity that is only in effect within `window`,
+    /// replacing any existing grant. Persists to store if attached.
+    pub async fn grant_peer_role_within(
+        &self,
+        entity_id: EntityId,
+        peer_id: PeerId,
+        role: SyncRole,
+        window: GrantWindow,
     ) {
         {
             let mut acls = self.acls.write().await;
             let acl = acls.entry(entity_id).or_insert_with(|| EntityAcl::new(entity_id));
-            acl.peer_roles.insert(peer_id, role);
+            acl.set_peer_grant(peer_id, role, window);
         }
         if let Some(store) = &self.store {
-            let _ = store.save_acl(&entity_id, &peer_id, role);
+            let _ = store.save_acl_grant(&entity_id, &peer_id, role, &window);
         }
     }
 
@@ -396,6 +604,7 @@ impl EnterpriseSyncPolicy {
             let mut acls = self.acls.write().await;
             if let Some(acl) = acls.get_mut(&entity_id) {
                 acl.peer_roles.remove(&peer_id);
+                acl.peer_windows.remove(&peer_id);
             }
         }
         if let Some(store) = &self.store {
@@ -409,14 +618,27 @@ impl EnterpriseSyncPolicy {
         entity_id: EntityId,
         team_id: TeamId,
         role: SyncRole,
+    ) {
+        self.grant_team_role_within(entity_id, team_id, role, GrantWindow::default())
+            .await;
+    }
+
+    /// Grants a team a role on an entity that is only in effect within `window`,
+    /// replacing any existing grant. Persists to store if attached.
+    pub async fn grant_team_role_within(
+        &self,
+        entity_id: EntityId,
+        team_id: TeamId,
+        role: SyncRole,
+        window: GrantWindow,
     ) {
         {
             let mut acls = self.acls.write().await;
             let acl = acls.entry(entity_id).or_insert_with(|| EntityAcl::new(entity_id));
-            acl.team_roles.insert(team_id, role);
+            acl.set_team_grant(team_id, role, window);
         }
         if let Some(store) = &self.store {
-            let _ = store.save_team_role(&entity_id, &team_id.0.to_string(), role);
+            let _ = store.save_team_grant(&entity_id, &team_id.0.to_string(), role, &window);
         }
     }
 
@@ -426,6 +648,7 @@ impl EnterpriseSyncPolicy {
             let mut acls = self.acls.write().await;
             if let Some(acl) = acls.get_mut(&entity_id) {
                 acl.team_roles.remove(&team_id);
+                acl.team_windows.remove(&team_id);
             }
         }
         if let Some(store) = &self.store {
@@ -496,29 +719,209 @@ impl EnterpriseSyncPolicy {
         }
     }
 
+    /// Removes a peer's device limit. Persists to store if attached.
+    pub async fn remove_device_limit(&self, peer_id: PeerId) {
+        self.device_limits.write().await.remove(&peer_id);
+        if let Some(store) = &self.store {
+            let _ = store.remove_device_limit(&peer_id);
+        }
+    }
+
+    /// Sets the default role for every entity of `entity_type`, or clears it
+    /// with `None`. Persists to store if attached.
+    pub async fn set_type_default_role(&self, entity_type: &str, role: Option<SyncRole>) {
+        {
+            let mut defaults = self.type_default_roles.write().await;
+            match role {
+                Some(r) => {
+                    defaults.insert(entity_type.to_string(), r);
+                }
+                None => {
+                    defaults.remove(entity_type);
+                }
+            }
+        }
+        if let Some(store) = &self.store {
+            match role {
+                Some(r) => { let _ = store.save_type_default_role(entity_type, r); }
+                None => { let _ = store.remove_type_default_role(entity_type); }
+            }
+        }
+    }
+
+    /// Records the type of an entity, replacing any previous one. Persists to store if attached.
+    pub async fn register_entity_type(&self, entity_id: EntityId, entity_type: &str) {
+        self.entity_types
+            .write()
+            .await
+            .insert(entity_id, entity_type.to_string());
+        if let Some(store) = &self.store {
+            let _ = store.save_entity_type(&entity_id, entity_type);
+        }
+    }
+
+    /// Returns an entity's type as registered or stored locally. Types named
+    /// in sync traffic are never used, so a peer cannot move an entity under a
+    /// more permissive type default.
+    async fn local_entity_type(&self, entity_id: &EntityId) -> Option<String> {
+        if let Some(entity_type) = self.entity_types.read().await.get(entity_id) {
+            return Some(entity_type.clone());
+        }
+        let store = self.entity_store.as_ref()?;
+        let entity_type = match store.get_entity_type(&entity_id.to_string()) {
+            Ok(entity_type) => entity_type?,
+            Err(e) => {
+                tracing::warn!("Failed to look up type of entity {}: {}", entity_id, e);
+                return None;
+            }
+        };
+        self.entity_types
+            .write()
+            .await
+            .insert(*entity_id, entity_type.clone());
+        Some(entity_type)
+    }
+
+    /// Replaces the field redaction rules.
+    pub async fn set_redaction_rules(&self, rules: RedactionRules) {
+        *self.redaction.write().await = rules;
+    }
+
+    /// Records the sensitivity labels declared by an entity schema.
+    pub async fn register_schema(&self, schema: &EntitySchema) {
+        let fields: Vec<(String, String)> = schema
+            .sensitive_fields()
+            .map(|(path, label)| (path.to_string(), label.to_string()))
+            .collect();
+        let mut sensitive = self.sensitive_fields.write().await;
+        if fields.is_empty() {
+            sensitive.remove(&schema.entity_type);
+        } else {
+            sensitive.insert(schema.entity_type.clone(), fields);
+        }
+    }
+
+    /// Strips the fields `role` may not see from each event's entity payload.
+    /// Returns the events and how many of them were redacted.
+    async fn redact_for_role(&self, role: SyncRole, events: &[Event]) -> (Vec<Event>, usize) {
+        let rules = self.redaction.read().await;
+        if rules.is_empty() {
+            return (events.to_vec(), 0);
+        }
+        let sensitive = self.sensitive_fields.read().await;
+        let mut redacted_count = 0;
+        let out = events
+            .iter()
+            .map(|event| {
+                let Some(entity_type) = payload_entity_type(&event.payload) else {
+                    return event.clone();
+                };
+                let fields = sensitive.get(entity_type).map(Vec::as_slice).unwrap_or_default();
+                let mut redacted =
+                    redact_event(event, &rules.hidden_paths(role, entity_type, fields));
+                if redacted.redacted_paths.len() > event.redacted_paths.len() {
+                    redacted_count += 1;
+                    if let Some((peer_id, key)) = &self.redaction_signer {
+                        key.seal_redaction(*peer_id, &mut redacted);
+                    }
+                }
+                redacted
+            })
+            .collect();
+        (out, redacted_count)
+    }
+
+    /// Replaces the audit retention rules and prunes the attached store with them.
+    pub async fn set_audit_retention(&self, retention: AuditRetention) -> Result<(), SyncError> {
+        if let Some(store) = &self.store {
+            store.apply_audit_retention(&retention, SystemTime::now())?;
+        }
+        *self.audit_retention.write().await = retention;
+        Ok(())
+    }
+
+    /// Sets the format [`Self::export_audit_log`] writes.
+    pub async fn set_audit_export_format(&self, format: AuditExportFormat) {
+        *self.audit_export_format.write().await = format;
+    }
+
+    /// Prunes the attached store with the current retention rules.
+    /// Returns the number of entries removed; no-op if no store is attached.
+    pub async fn prune_audit_log(&self) -> Result<usize, SyncError> {
+        let Some(store) = &self.store else {
+            return Ok(0);
+        };
+        let retention = self.audit_retention.read().await.clone();
+        store.apply_audit_retention(&retention, SystemTime::now())
+    }
+
+    /// Writes the persisted entries matching `query` to `out` in the configured
+    /// export format. Returns the number of entries written.
+    pub async fn export_audit_log(
+        &self,
+        query: &AuditQuery,
+        out: &mut (dyn std::io::Write + Send),
+    ) -> Result<usize, SyncError> {
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| SyncError::Storage("no policy store attached".into()))?;
+        let format = *self.audit_export_format.read().await;
+        store.export_audit_log(query, format, out)
+    }
+
     /// Resolve the effective role for a peer on a given entity.
-    /// Peer-specific role takes precedence over team role, which takes precedence over default.
+    /// Peer-specific role takes precedence over team role, then the entity's
+    /// default role, then the default role for its entity type.
     pub async fn resolve_role(&self, peer: &PeerId, entity: &EntityId) -> Option<SyncRole> {
+        self.resolve_role_at(peer, entity, now_millis()).await
+    }
+
+    /// Resolves the effective role at `now_ms`. Grants outside their validity
+    /// window are ignored, as if they did not exist.
+    pub async fn resolve_role_at(
+        &self,
+        peer: &PeerId,
+        entity: &EntityId,
+        now_ms: u64,
+    ) -> Option<SyncRole> {
         let acls = self.acls.read().await;
-        let acl = match acls.get(entity) {
-            Some(a) => a,
-            None => return None,
-        };
+        if let Some(acl) = acls.get(entity)
+            && let Some(role) = self.resolve_acl_role(acl, peer, now_ms).await
+        {
+            return Some(role);
+        }
+        drop(acls);
 
+        let entity_type = self.local_entity_type(entity).await?;
+        self.type_default_roles.read().await.get(&entity_type).copied()
+    }
+
+    /// Resolves a role from a single entity's ACL: peer grant, then the
+    /// highest team grant, then the entity default.
+    async fn resolve_acl_role(
+        &self,
+        acl: &EntityAcl,
+        peer: &PeerId,
+        now_ms: u64,
+    ) -> Option<SyncRole> {
         // Peer-specific override takes precedence
-        if let Some(&role) = acl.peer_roles.get(peer) {
+        if let Some(role) = acl.active_peer_role(peer, now_ms) {
             return Some(role);
         }
 
         // Team role (take highest)
         let teams = self.teams.read().await;
         let mut best_team_role: Option<SyncRole> = None;
-        for (team_id, role) in &acl.team_roles {
+        for team_id in acl.team_roles.keys() {
+            let Some(role) = acl.active_team_role(team_id, now_ms) else {
+                continue;
+            };
             if let Some(members) = teams.get(team_id) {
                 if members.contains(peer) {
                     best_team_role = Some(match best_team_role {
-                        Some(existing) if existing > *role => existing,
-                        _ => *role,
+                        Some(existing) if existing > role => existing,
+                        _ => role,
                     });
                 }
             }
@@ -530,6 +933,94 @@ impl EnterpriseSyncPolicy {
         acl.default_role
     }
 
+    /// Removes every grant that has expired by `now_ms`, from memory and the
+    /// attached store, and records each removal in the audit log.
+    /// Returns the number of grants removed.
+    pub async fn sweep_expired_grants(&self, now_ms: u64) -> usize {
+        let mut expired_peers: Vec<(EntityId, PeerId, SyncRole, u64)> = Vec::new();
+        let mut expired_teams: Vec<(EntityId, TeamId, SyncRole, u64)> = Vec::new();
+        {
+            let mut acls = self.acls.write().await;
+            for (entity_id, acl) in acls.iter_mut() {
+                let lapsed: Vec<(PeerId, u64)> = acl
+                    .peer_windows
+                    .iter()
+                    .filter(|(_, w)| w.is_expired_at(now_ms))
+                    .map(|(p, w)| (*p, w.expires_at.unwrap_or_default()))
+                    .collect();
+                for (peer, expires_at) in lapsed {
+                    acl.peer_windows.remove(&peer);
+                    if let Some(role) = acl.peer_roles.remove(&peer) {
+                        expired_peers.push((*entity_id, peer, role, expires_at));
+                    }
+                }
+
+                let lapsed: Vec<(TeamId, u64)> = acl
+                    .team_windows
+                    .iter()
+                    .filter(|(_, w)| w.is_expired_at(now_ms))
+                    .map(|(t, w)| (*t, w.expires_at.unwrap_or_default()))
+                    .collect();
+                for (team, expires_at) in lapsed {
+                    acl.team_windows.remove(&team);
+                    if let Some(role) = acl.team_roles.remove(&team) {
+                        expired_teams.push((*entity_id, team, role, expires_at));
+                    }
+                }
+            }
+        }
+
+        for (entity_id, peer_id, role, expires_at) in &expired_peers {
+            if let Some(store) = &self.store {
+                let _ = store.remove_acl(entity_id, peer_id);
+            }
+            self.log(
+                *peer_id,
+                Some(*entity_id),
+                AuditAction::GrantExpired,
+                AuditDecision::Denied,
+                format!("peer grant role={} expired at {}", role, expires_at),
+            )
+            .await;
+        }
+
+        // Team grants have no single peer; attribute the entry to every member.
+        let teams = self.teams.read().await.clone();
+        for (entity_id, team_id, role, expires_at) in &expired_teams {
+            if let Some(store) = &self.store {
+                let _ = store.remove_team_role(entity_id, &team_id.0.to_string());
+            }
+            for member in teams.get(team_id).into_iter().flatten() {
+                self.log(
+                    *member,
+                    Some(*entity_id),
+                    AuditAction::GrantExpired,
+                    AuditDecision::Denied,
+                    format!("team {} grant role={} expired at {}", team_id, role, expires_at),
+                )
+                .await;
+            }
+        }
+
+        expired_peers.len() + expired_teams.len()
+    }
+
+    /// Sweeps expired grants and prunes the audit log. The enterprise
+    /// orchestrator calls this on every sync tick. Expired grants stop
+    /// resolving immediately regardless; sweeping removes them from storage
+    /// and records the expiry in the audit log.
+    pub async fn run_maintenance(&self) {
+        let removed = self.sweep_expired_grants(now_millis()).await;
+        if removed > 0 {
+            tracing::info!("Removed {} expired ACL grants", removed);
+        }
+        match self.prune_audit_log().await {
+            Ok(0) => {}
+            Ok(pruned) => tracing::info!("Pruned {} audit log entries", pruned),
+            Err(e) => tracing::warn!("Failed to prune audit log: {}", e),
+        }
+    }
+
     /// Check if a peer can register a new device. Returns error if limit exceeded.
     pub async fn check_device_limit(
         &self,
@@ -590,6 +1081,89 @@ impl EnterpriseSyncPolicy {
         active.remove(peer);
     }
 
+    /// Returns the events `author` may write to `entity`: ACL events need
+    /// Admin+ on their target entity, everything else needs Editor+.
+    async fn authorize_writes(
+        &self,
+        author: &PeerId,
+        entity: &EntityId,
+        events: &[Event],
+    ) -> Vec<Event> {
+        // For ACL events, check Admin+ authority on the target entity
+        let mut filtered_events = Vec::new();
+        for event in events {
+            if crate::acl_applicator::is_acl_event(&event.payload) {
+                // Extract the target entity from the ACL payload
+                let target_entity = acl_target_entity(&event.payload);
+                let check_entity = target_entity.as_ref().unwrap_or(entity);
+                let acl_role = self.resolve_role(author, check_entity).await;
+                match acl_role {
+                    Some(r) if r >= SyncRole::Admin => {
+                        filtered_events.push(event.clone());
+                    }
+                    _ => {
+                        self.log(
+                            *author,
+                            Some(*check_entity),
+                            AuditAction::EventReceive,
+                            AuditDecision::Denied,
+                            format!("ACL event requires Admin+, author has {:?}", acl_role),
+                        )
+                        .await;
+                    }
+                }
+                continue;
+            }
+            filtered_events.push(event.clone());
+        }
+
+        // Now apply normal role-based filtering on non-ACL events
+        let events = &filtered_events;
+        let role = self.resolve_role(author, entity).await;
+        match role {
+            Some(r) if r >= SyncRole::Editor => {
+                // Editor and above can write
+                self.log(
+                    *author,
+                    Some(*entity),
+                    AuditAction::EventReceive,
+                    AuditDecision::Allowed,
+                    format!("role={}, count={}", r, events.len()),
+                )
+                .await;
+                // Filter events: only accept events for the correct entity
+                events
+                    .iter()
+                    .filter(|e| e.entity_id == *entity)
+                    .cloned()
+                    .collect()
+            }
+            Some(r) => {
+                // Viewer — strip all write events
+                self.log(
+                    *author,
+                    Some(*entity),
+                    AuditAction::EventReceive,
+                    AuditDecision::Filtered,
+                    format!("role={}, viewer cannot write, stripped {} events", r, events.len()),
+                )
+                .await;
+                Vec::new()
+            }
+            None => {
+                self.log(
+                    *author,
+                    Some(*entity),
+                    AuditAction::EventReceive,
+                    AuditDecision::Denied,
+                    format!("no role, stripped {} events", events.len()),
+                )
+                .await;
+                Vec::new()
+            }
+        }
+    }
+
     async fn log(
         &self,
         peer: PeerId,
@@ -703,24 +1277,34 @@ impl SyncPolicy for EnterpriseSyncPolicy {
     ) -> Result<Vec<Event>, SyncError> {
         let role = self.resolve_role(peer, entity).await;
         // Send events to the peer only if they have at least Viewer access (they can read)
-        let decision = if role.is_some() {
-            AuditDecision::Allowed
+        let Some(r) = role else {
+            self.log(
+                *peer,
+                Some(*entity),
+                AuditAction::EventSend,
+                AuditDecision::Denied,
+                format!("role={:?}, count={}", role, events.len()),
+            )
+            .await;
+            return Ok(Vec::new());
+        };
+
+        // Withhold fields this role may not see
+        let (sent, redacted) = self.redact_for_role(r, events).await;
+        let detail = if redacted > 0 {
+            format!("role={:?}, count={}, redacted={}", role, events.len(), redacted)
         } else {
-            AuditDecision::Denied
+            format!("role={:?}, count={}", role, events.len())
         };
         self.log(
             *peer,
             Some(*entity),
             AuditAction::EventSend,
-            decision.clone(),
-            format!("role={:?}, count={}", role, events.len()),
+            AuditDecision::Allowed,
+            detail,
         )
         .await;
-        if role.is_some() {
-            Ok(events.to_vec())
-        } else {
-            Ok(Vec::new())
-        }
+        Ok(sent)
     }
 
     async fn on_device_check(
@@ -766,80 +1350,54 @@ impl SyncPolicy for EnterpriseSyncPolicy {
         entity: &EntityId,
         events: &[Event],
     ) -> Result<Vec<Event>, SyncError> {
-        // For ACL events, check Admin+ authority on the target entity
-        let mut filtered_events = Vec::new();
+        // Authorize each author's events against that author's role, keeping
+        // the batch order.
+        // An empty batch is still audited against the sender.
+        let mut authors: Vec<PeerId> = Vec::new();
+        if events.is_empty() {
+            authors.push(*peer);
+        }
         for event in events {
-            if crate::acl_applicator::is_acl_event(&event.payload) {
-                // Extract the target entity from the ACL payload
-                let target_entity = acl_target_entity(&event.payload);
-                let check_entity = target_entity.as_ref().unwrap_or(entity);
-                let acl_role = self.resolve_role(peer, check_entity).await;
-                match acl_role {
-                    Some(r) if r >= SyncRole::Admin => {
-                        filtered_events.push(event.clone());
-                    }
-                    _ => {
-                        self.log(
-                            *peer,
-                            Some(*check_entity),
-                            AuditAction::EventReceive,
-                            AuditDecision::Denied,
-                            format!("ACL event requires Admin+, peer has {:?}", acl_role),
-                        )
-                        .await;
-                    }
-                }
-                continue;
+            let author = event_author(event, peer);
+            if !authors.contains(&author) {
+                authors.push(author);
             }
-            filtered_events.push(event.clone());
         }
 
-        // Now apply normal role-based filtering on non-ACL events
-        let events = &filtered_events;
-        let role = self.resolve_role(peer, entity).await;
-        match role {
-            Some(r) if r >= SyncRole::Editor => {
-                // Editor and above can write
-                self.log(
-                    *peer,
-                    Some(*entity),
-                    AuditAction::EventReceive,
-                    AuditDecision::Allowed,
-                    format!("role={}, count={}", r, events.len()),
-                )
-                .await;
-                // Filter events: only accept events for the correct entity
-                let filtered: Vec<Event> = events
-                    .iter()
-                    .filter(|e| e.entity_id == *entity)
-                    .cloned()
-                    .collect();
-                Ok(filtered)
-            }
-            Some(r) => {
-                // Viewer — strip all write events
-                self.log(
-                    *peer,
-                    Some(*entity),
-                    AuditAction::EventReceive,
-                    AuditDecision::Filtered,
-                    format!("role={}, viewer cannot write, stripped {} events", r, events.len()),
-                )
-                .await;
-                Ok(Vec::new())
-            }
-            None => {
+        let mut accepted: HashSet<EventId> = HashSet::new();
+        for author in authors {
+            let authored: Vec<Event> = events
+                .iter()
+                .filter(|e| event_author(e, peer) == author)
+                .cloned()
+                .collect();
+
+            // Relaying another author's events requires access to the entity.
+            if author != *peer && self.resolve_role(peer, entity).await.is_none() {
                 self.log(
                     *peer,
                     Some(*entity),
                     AuditAction::EventReceive,
                     AuditDecision::Denied,
-                    format!("no role, stripped {} events", events.len()),
+                    format!("no role, cannot relay {} events from {}", authored.len(), author),
                 )
                 .await;
-                Ok(Vec::new())
+                continue;
             }
+
+            accepted.extend(
+                self.authorize_writes(&author, entity, &authored)
+                    .await
+                    .iter()
+                    .map(|e| e.id),
+            );
         }
+
+        Ok(events
+            .iter()
+            .filter(|e| accepted.contains(&e.id))
+            .cloned()
+            .collect())
     }
 }
 
@@ -847,18 +1405,35 @@ impl SyncPolicy for EnterpriseSyncPolicy {
 
 /// Lightweight sharing policy for non-enterprise personal users.
 /// Tracks which entities are shared with which peers — no teams, no device limits, no audit log.
+/// Optional per-peer `SyncScope` rules further restrict what is sent by type, tag or plugin.
 pub struct PersonalSyncPolicy {
     /// peer → set of entities shared with that peer.
     peer_entities: RwLock<HashMap<PeerId, HashSet<EntityId>>>,
+    /// peer → rule-based sync scope.
+    peer_scopes: RwLock<HashMap<PeerId, SyncScope>>,
+    /// entity type → owning plugin ID (for plugin exclusion rules).
+    plugin_entity_types: RwLock<HashMap<String, String>>,
+    /// Event history, used to find the tags of deleted entities.
+    event_store: Option<Arc<EventStore>>,
 }
 
 impl PersonalSyncPolicy {
     pub fn new() -> Self {
         Self {
             peer_entities: RwLock::new(HashMap::new()),
+            peer_scopes: RwLock::new(HashMap::new()),
+            plugin_entity_types: RwLock::new(HashMap::new()),
+            event_store: None,
         }
     }
 
+    /// Looks up the tags of deleted entities in `event_store`. Without it,
+    /// deletes are never sent to peers whose scope excludes tags.
+    pub fn with_event_store(mut self, event_store: Arc<EventStore>) -> Self {
+        self.event_store = Some(event_store);
+        self
+    }
+
     /// Share an entity with a specific peer.
     pub async fn share(&self, entity_id: EntityId, peer_id: PeerId) {
         self.peer_entities
@@ -901,6 +1476,76 @@ impl PersonalSyncPolicy {
     pub async fn has_selective_sharing(&self) -> bool {
         !self.peer_entities.read().await.is_empty()
     }
+
+    /// Sets the sync scope for a peer. An empty scope clears it.
+    pub async fn set_sync_scope(&self, peer_id: PeerId, scope: SyncScope) {
+        let mut scopes = self.peer_scopes.write().await;
+        if scope.is_empty() {
+            scopes.remove(&peer_id);
+        } else {
+            scopes.insert(peer_id, scope);
+        }
+    }
+
+    /// Removes the sync scope for a peer.
+    pub async fn clear_sync_scope(&self, peer_id: &PeerId) {
+        self.peer_scopes.write().await.remove(peer_id);
+    }
+
+    /// Returns the sync scope configured for a peer, if any.
+    pub async fn sync_scope(&self, peer_id: &PeerId) -> Option<SyncScope> {
+        self.peer_scopes.read().await.get(peer_id).cloned()
+    }
+
+    /// Returns true if a sync scope is configured for the peer.
+    pub async fn has_sync_scope(&self, peer_id: &PeerId) -> bool {
+        self.peer_scopes.read().await.contains_key(peer_id)
+    }
+
+    /// Registers the entity types owned by a plugin, replacing any previous
+    /// registration for that plugin.
+    pub async fn register_plugin_entity_types(&self, plugin_id: &str, entity_types: &[String]) {
+        let mut map = self.plugin_entity_types.write().await;
+        map.retain(|_, owner| owner != plugin_id);
+        for et in entity_types {
+            map.insert(et.clone(), plugin_id.to_string());
+        }
+    }
+
+    /// Evaluates a peer's sync scope against entity metadata.
+    /// Returns true when no scope is configured for the peer.
+    pub async fn scope_allows(&self, peer_id: &PeerId, meta: &EntityMeta) -> bool {
+        let scopes = self.peer_scopes.read().await;
+        let scope = match scopes.get(peer_id) {
+            Some(s) => s,
+            None => return true,
+        };
+        let plugin_types = self.plugin_entity_types.read().await;
+        scope.allows(meta, &plugin_types)
+    }
+
+    async fn scope_excludes_tags(&self, peer_id: &PeerId) -> bool {
+        self.peer_scopes
+            .read()
+            .await
+            .get(peer_id)
+            .is_some_and(|scope| !scope.exclude_tags.is_empty())
+    }
+
+    /// Tags the entity carried before it was deleted, from its event history.
+    /// `None` if they cannot be determined.
+    async fn tags_before_delete(&self, entity: &EntityId) -> Option<Vec<String>> {
+        let store = self.event_store.clone()?;
+        let eid = *entity;
+        let history = tokio::task::spawn_blocking(move || store.get_events_for_entity(&eid))
+            .await
+            .ok()?
+            .ok()?;
+        if !EntityMeta::carries_data(&history) {
+            return None;
+        }
+        EntityMeta::from_events(&history).map(|meta| meta.tags)
+    }
 }
 
 impl Default for PersonalSyncPolicy {
@@ -947,17 +1592,28 @@ impl SyncPolicy for PersonalSyncPolicy {
         entity: &EntityId,
         events: &[Event],
     ) -> Result<Vec<Event>, SyncError> {
-        let map = self.peer_entities.read().await;
-        // No selective sharing configured → allow all events
-        if map.is_empty() {
-            return Ok(events.to_vec());
+        {
+            let map = self.peer_entities.read().await;
+            // No selective sharing configured → allow all events
+            if !map.is_empty() && !map.get(peer).is_some_and(|s| s.contains(entity)) {
+                return Ok(Vec::new());
+            }
         }
-        let allowed = map.get(peer).is_some_and(|s| s.contains(entity));
-        if allowed {
-            Ok(events.to_vec())
-        } else {
-            Ok(Vec::new())
+        // Rule-based scope, evaluated against the newest entity data being sent.
+        // A delete-only batch is checked against the tags from history, and
+        // withheld if they are unknown.
+        if let Some(mut meta) = EntityMeta::from_events(events) {
+            if !EntityMeta::carries_data(events) && self.scope_excludes_tags(peer).await {
+                match self.tags_before_delete(entity).await {
+                    Some(tags) => meta.tags = tags,
+                    None => return Ok(Vec::new()),
+                }
+            }
+            if !self.scope_allows(peer, &meta).await {
+                return Ok(Vec::new());
+            }
         }
+        Ok(events.to_vec())
     }
 
     async fn on_event_receive(
@@ -994,6 +1650,12 @@ fn acl_target_entity(payload: &EventPayload) -> Option<EntityId> {
         | EventPayload::AclGrantTeam { entity_id, .. }
         | EventPayload::AclRevokeTeam { entity_id, .. }
         | EventPayload::AclSetDefault { entity_id, .. } => Some(entity_id.as_str()),
+        // Org-wide policy needs Admin+ on the policy entity
+        EventPayload::KnownPeerAdd { .. }
+        | EventPayload::KnownPeerRemove { .. }
+        | EventPayload::TeamAddPeer { .. }
+        | EventPayload::TeamRemovePeer { .. }
+        | EventPayload::DeviceLimitSet { .. } => return Some(POLICY_ENTITY_ID),
         _ => None,
     };
     id_str.and_then(|s| s.parse::<EntityId>().ok())
@@ -0,0 +1,425 @@
+//! Replicated organization policy: known peers, team membership and device limits.
+//!
+//! Admin devices change these through policy events (`KnownPeerAdd`,
+//! `KnownPeerRemove`, `TeamAddPeer`, `TeamRemovePeer`, `DeviceLimitSet`)
+//! that sync like any other event, so every device converges on the same
+//! policy whatever order the events arrive in:
+//!
+//! - Membership (known peers, team members) is an observed-remove set. Each
+//!   add is tagged with its event ID and a remove deletes only the adds its
+//!   author had seen, so an add concurrent with a remove wins. A remove that
+//!   lists no adds (its author had seen none, e.g. the member came from a
+//!   policy file) deletes every add up to its timestamp instead, so replicas
+//!   still agree whatever order the events arrive in.
+//! - Device limits are last-writer-wins registers ordered by event timestamp,
+//!   ties broken by event ID.
+//!
+//! Org-wide events are carried on [`POLICY_ENTITY_ID`]; receivers accept them
+//! only from peers holding Admin or higher on that entity. Grant members at
+//! least Viewer on it so policy events reach them.
+
+use crate::error::SyncError;
+use crate::policy::{EnterpriseSyncPolicy, TeamId};
+use privstack_crypto::{IdentityKeyPair, IdentityPublicKey};
+use privstack_types::{EntityId, Event, EventPayload, HybridTimestamp, PeerId};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// Entity that org-wide policy events are attached to.
+pub const POLICY_ENTITY_ID: EntityId =
+    EntityId::from_uuid(uuid::Uuid::from_u128(0x7072_6976_7374_7000_8000_0000_0000_0001));
+
+/// Returns true for events that change replicated organization policy.
+pub fn is_policy_event(payload: &EventPayload) -> bool {
+    matches!(
+        payload,
+        EventPayload::KnownPeerAdd { .. }
+            | EventPayload::KnownPeerRemove { .. }
+            | EventPayload::DeviceLimitSet { .. }
+            | EventPayload::TeamAddPeer { .. }
+            | EventPayload::TeamRemovePeer { .. }
+    )
+}
+
+/// Observed-remove set keyed by add-event ID.
+#[derive(Debug, Clone)]
+struct OrSet<T> {
+    /// Live adds of each item: tag → add timestamp.
+    tags: HashMap<T, HashMap<String, HybridTimestamp>>,
+    /// Tags removed so far, so an add arriving after its remove stays removed.
+    removed: HashSet<String>,
+    /// Latest untagged remove of each item; adds at or before it are removed.
+    tombstones: HashMap<T, HybridTimestamp>,
+}
+
+impl<T> Default for OrSet<T> {
+    fn default() -> Self {
+        Self {
+            tags: HashMap::new(),
+            removed: HashSet::new(),
+            tombstones: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone> OrSet<T> {
+    fn add(&mut self, item: T, tag: &str, timestamp: HybridTimestamp) {
+        if self.removed.contains(tag)
+            || self.tombstones.get(&item).is_some_and(|t| timestamp <= *t)
+        {
+            return;
+        }
+        self.tags.entry(item).or_default().insert(tag.to_string(), timestamp);
+    }
+
+    /// Removes the `observed` adds of `item`. An empty list removes every add
+    /// of `item` made at or before `timestamp`, including adds that arrive
+    /// later.
+    fn remove(&mut self, item: &T, observed: &[String], timestamp: HybridTimestamp) {
+        if observed.is_empty() {
+            let tombstone = self.tombstones.entry(item.clone()).or_insert(timestamp);
+            *tombstone = (*tombstone).max(timestamp);
+            let tombstone = *tombstone;
+            if let Some(tags) = self.tags.get_mut(item) {
+                tags.retain(|_, added| *added > tombstone);
+            }
+        } else {
+            if let Some(tags) = self.tags.get_mut(item) {
+                for tag in observed {
+                    tags.remove(tag);
+                }
+            }
+            self.removed.extend(observed.iter().cloned());
+        }
+        if self.tags.get(item).is_some_and(HashMap::is_empty) {
+            self.tags.remove(item);
+        }
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        self.tags.contains_key(item)
+    }
+
+    /// Live add tags of `item`, sorted.
+    fn tags(&self, item: &T) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .tags
+            .get(item)
+            .map(|t| t.keys().cloned().collect())
+            .unwrap_or_default();
+        tags.sort();
+        tags
+    }
+}
+
+/// Last-writer-wins device limit.
+#[derive(Debug, Clone)]
+struct LimitRegister {
+    timestamp: HybridTimestamp,
+    event_id: String,
+    max_devices: Option<usize>,
+}
+
+/// Effect of a policy event on the materialized policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyChange {
+    KnownPeer { peer: PeerId, present: bool },
+    TeamMember { team: TeamId, peer: PeerId, present: bool },
+    DeviceLimit { peer: PeerId, max_devices: Option<usize> },
+}
+
+/// Merge state for replicated organization policy.
+#[derive(Debug, Clone, Default)]
+pub struct PolicyCrdt {
+    known_peers: OrSet<PeerId>,
+    team_members: OrSet<(TeamId, PeerId)>,
+    device_limits: HashMap<PeerId, LimitRegister>,
+}
+
+impl PolicyCrdt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merges a policy event. Returns the resulting state of the item it
+    /// touched, or `None` if the event is not a policy event or lost to a
+    /// newer device limit.
+    pub fn apply(&mut self, event: &Event) -> Result<Option<PolicyChange>, SyncError> {
+        let tag = event.id.to_string();
+        let change = match &event.payload {
+            EventPayload::KnownPeerAdd { peer_id, .. } => {
+                let peer = parse_peer(peer_id)?;
+                self.known_peers.add(peer, &tag, event.timestamp);
+                self.known_peer_change(peer)
+            }
+            EventPayload::KnownPeerRemove { peer_id, observed } => {
+                let peer = parse_peer(peer_id)?;
+                self.known_peers.remove(&peer, observed, event.timestamp);
+                self.known_peer_change(peer)
+            }
+            EventPayload::TeamAddPeer { team_id, peer_id } => {
+                let (team, peer) = (parse_team(team_id)?, parse_peer(peer_id)?);
+                self.team_members.add((team, peer), &tag, event.timestamp);
+                self.team_member_change(team, peer)
+            }
+            EventPayload::TeamRemovePeer {
+                team_id,
+                peer_id,
+                observed,
+            } => {
+                let (team, peer) = (parse_team(team_id)?, parse_peer(peer_id)?);
+                self.team_members.remove(&(team, peer), observed, event.timestamp);
+                self.team_member_change(team, peer)
+            }
+            EventPayload::DeviceLimitSet {
+                peer_id,
+                max_devices,
+            } => {
+                let peer = parse_peer(peer_id)?;
+                if max_devices == &Some(0) {
+                    return Err(SyncError::Protocol(format!(
+                        "device limit for {peer} must be at least 1"
+                    )));
+                }
+                let newer = self.device_limits.get(&peer).is_none_or(|current| {
+                    (event.timestamp, tag.as_str())
+                        > (current.timestamp, current.event_id.as_str())
+                });
+                if !newer {
+                    return Ok(None);
+                }
+                self.device_limits.insert(
+                    peer,
+                    LimitRegister {
+                        timestamp: event.timestamp,
+                        event_id: tag,
+                        max_devices: *max_devices,
+                    },
+                );
+                PolicyChange::DeviceLimit {
+                    peer,
+                    max_devices: *max_devices,
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(change))
+    }
+
+    /// IDs of the known-peer adds a removal of `peer` should cover.
+    pub fn known_peer_tags(&self, peer: &PeerId) -> Vec<String> {
+        self.known_peers.tags(peer)
+    }
+
+    /// IDs of the team-member adds a removal of `peer` from `team` should cover.
+    pub fn team_member_tags(&self, team: TeamId, peer: PeerId) -> Vec<String> {
+        self.team_members.tags(&(team, peer))
+    }
+
+    fn known_peer_change(&self, peer: PeerId) -> PolicyChange {
+        PolicyChange::KnownPeer {
+            peer,
+            present: self.known_peers.contains(&peer),
+        }
+    }
+
+    fn team_member_change(&self, team: TeamId, peer: PeerId) -> PolicyChange {
+        PolicyChange::TeamMember {
+            team,
+            peer,
+            present: self.team_members.contains(&(team, peer)),
+        }
+    }
+}
+
+fn parse_peer(s: &str) -> Result<PeerId, SyncError> {
+    s.parse()
+        .map_err(|e| SyncError::Protocol(format!("invalid peer_id: {e}")))
+}
+
+fn parse_team(s: &str) -> Result<TeamId, SyncError> {
+    uuid::Uuid::parse_str(s)
+        .map(TeamId)
+        .map_err(|e| SyncError::Protocol(format!("invalid team_id: {e}")))
+}
+
+impl EnterpriseSyncPolicy {
+    /// Merges a replicated policy event and updates the materialized policy.
+    /// The event is persisted to the attached store so the merge state
+    /// survives restarts. Returns `Ok(false)` for non-policy events.
+    ///
+    /// A `KnownPeerAdd` carrying an identity key registers it as the admitted
+    /// peer's author key (see [`Self::with_author_keys`]).
+    pub async fn apply_policy_event(&self, event: &Event) -> Result<bool, SyncError> {
+        if !is_policy_event(&event.payload) {
+            return Ok(false);
+        }
+        let identity_key = match &event.payload {
+            EventPayload::KnownPeerAdd {
+                peer_id,
+                identity_key: Some(encoded),
+            } => {
+                let key = IdentityPublicKey::from_base64(encoded).map_err(|e| {
+                    SyncError::Protocol(format!("invalid identity key for {peer_id}: {e}"))
+                })?;
+                Some((parse_peer(peer_id)?, key))
+            }
+            _ => None,
+        };
+        let change = self.policy_crdt.write().await.apply(event)?;
+        if let (Some((peer, key)), Some(event_store)) = (identity_key, self.author_keys())
+            && let Err(e) = event_store.register_author_key(&peer, &key)
+        {
+            tracing::warn!("Identity key of known peer {} not registered: {}", peer, e);
+        }
+        if let Some(store) = self.store() {
+            store.save_policy_event(event)?;
+        }
+        match change {
+            Some(PolicyChange::KnownPeer { peer, present: true }) => self.add_known_peer(peer).await,
+            Some(PolicyChange::KnownPeer { peer, present: false }) => {
+                self.remove_known_peer(peer).await
+            }
+            Some(PolicyChange::TeamMember { team, peer, present: true }) => {
+                self.add_team_member(team, peer).await
+            }
+            Some(PolicyChange::TeamMember { team, peer, present: false }) => {
+                self.remove_team_member(team, peer).await
+            }
+            Some(PolicyChange::DeviceLimit { peer, max_devices: Some(max) }) => {
+                self.set_device_limit(peer, max).await
+            }
+            Some(PolicyChange::DeviceLimit { peer, max_devices: None }) => {
+                self.remove_device_limit(peer).await
+            }
+            None => {}
+        }
+        Ok(true)
+    }
+
+    /// Replays persisted policy events into the merge state without touching
+    /// the materialized policy (which the store already holds).
+    pub(crate) async fn restore_policy_events(&self) -> Result<(), SyncError> {
+        let Some(store) = self.store() else {
+            return Ok(());
+        };
+        let mut crdt = self.policy_crdt.write().await;
+        for event in store.load_policy_events()? {
+            crdt.apply(&event)?;
+        }
+        Ok(())
+    }
+
+    /// Admits `peer` org-wide, registering `identity_key` as its author key
+    /// on every device when given. Applies the change locally and returns the
+    /// signed event to sync to other devices.
+    pub async fn replicate_add_known_peer(
+        &self,
+        author: PeerId,
+        signer: &IdentityKeyPair,
+        peer: PeerId,
+        identity_key: Option<IdentityPublicKey>,
+    ) -> Result<Event, SyncError> {
+        self.emit_policy_event(
+            author,
+            signer,
+            EventPayload::KnownPeerAdd {
+                peer_id: peer.to_string(),
+                identity_key: identity_key.map(|k| k.to_base64()),
+            },
+        )
+        .await
+    }
+
+    /// Removes `peer` from the known peers org-wide.
+    pub async fn replicate_remove_known_peer(
+        &self,
+        author: PeerId,
+        signer: &IdentityKeyPair,
+        peer: PeerId,
+    ) -> Result<Event, SyncError> {
+        let observed = self.policy_crdt.read().await.known_peer_tags(&peer);
+        self.emit_policy_event(
+            author,
+            signer,
+            EventPayload::KnownPeerRemove {
+                peer_id: peer.to_string(),
+                observed,
+            },
+        )
+        .await
+    }
+
+    /// Adds `peer` to `team` org-wide.
+    pub async fn replicate_add_team_member(
+        &self,
+        author: PeerId,
+        signer: &IdentityKeyPair,
+        team: TeamId,
+        peer: PeerId,
+    ) -> Result<Event, SyncError> {
+        self.emit_policy_event(
+            author,
+            signer,
+            EventPayload::TeamAddPeer {
+                team_id: team.0.to_string(),
+                peer_id: peer.to_string(),
+            },
+        )
+        .await
+    }
+
+    /// Removes `peer` from `team` org-wide.
+    pub async fn replicate_remove_team_member(
+        &self,
+        author: PeerId,
+        signer: &IdentityKeyPair,
+        team: TeamId,
+        peer: PeerId,
+    ) -> Result<Event, SyncError> {
+        let observed = self.policy_crdt.read().await.team_member_tags(team, peer);
+        self.emit_policy_event(
+            author,
+            signer,
+            EventPayload::TeamRemovePeer {
+                team_id: team.0.to_string(),
+                peer_id: peer.to_string(),
+                observed,
+            },
+        )
+        .await
+    }
+
+    /// Sets (or with `None` clears) `peer`'s device limit org-wide.
+    pub async fn replicate_device_limit(
+        &self,
+        author: PeerId,
+        signer: &IdentityKeyPair,
+        peer: PeerId,
+        max_devices: Option<usize>,
+    ) -> Result<Event, SyncError> {
+        self.emit_policy_event(
+            author,
+            signer,
+            EventPayload::DeviceLimitSet {
+                peer_id: peer.to_string(),
+                max_devices,
+            },
+        )
+        .await
+    }
+
+    /// Creates a policy event authored by `author`, signs it with `signer`
+    /// (the author device's identity key) and applies it locally.
+    async fn emit_policy_event(
+        &self,
+        author: PeerId,
+        signer: &IdentityKeyPair,
+        payload: EventPayload,
+    ) -> Result<Event, SyncError> {
+        let mut event = Event::new(POLICY_ENTITY_ID, author, HybridTimestamp::now(), payload);
+        signer.sign_event(&mut event);
+        self.apply_policy_event(&event).await?;
+        Ok(event)
+    }
+}
@@ -0,0 +1,626 @@
+//! Declarative enterprise sync policy files.
+//!
+//! IT admins describe known peers, teams, per-type default roles, device
+//! limits and audit settings in a TOML or JSON file instead of configuring
+//! `EnterpriseSyncPolicy` call by call:
+//!
+//! ```toml
+//! version = 1
+//! serial = 42
+//! known_peers = ["0190c1d2-...", "0190c1d3-..."]
+//!
+//! [teams.engineering]
+//! id = "7d4e5f60-..."
+//! members = ["0190c1d2-..."]
+//!
+//! [default_roles]
+//! note = "Viewer"
+//!
+//! [device_limits]
+//! "0190c1d2-..." = 3
+//!
+//! [audit]
+//! retention_days = 365
+//! export_format = "cef"
+//!
+//! [redaction.viewer]
+//! labels = ["financial"]
+//! paths = { invoice = ["/cost"] }
+//! ```
+//!
+//! Redaction rules may only target the Viewer role (see `crate::redaction`).
+//!
+//! The file is authoritative for default roles, device limits, audit
+//! retention and export format, and redaction rules: anything it does not list
+//! is removed when it is applied. Known peers and teams are only managed when
+//! listed; write `known_peers = []` to admit every peer and list a team with
+//! `members = []` to empty it.
+//!
+//! The file must be accompanied by a detached `<file>.sig` holding a base64
+//! Ed25519 signature over its bytes by one of the trusted admin keys.
+//!
+//! `serial` must be raised whenever the file changes. A file whose serial is
+//! lower than the last one applied, or which reuses that serial for different
+//! contents, is rejected, so an older signed file (one that still lists a
+//! revoked peer, say) cannot be rolled back into place.
+
+use crate::audit::{AuditExportFormat, AuditRetention};
+use crate::error::SyncError;
+use crate::policy::{EnterpriseSyncPolicy, SyncRole, TeamId};
+use crate::redaction::RedactionRules;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use privstack_crypto::{IdentityKeyPair, IdentityPublicKey};
+use privstack_types::PeerId;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Highest policy file `version` this build understands.
+pub const POLICY_FILE_VERSION: u32 = 1;
+
+/// Serialization format of a policy file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyFileFormat {
+    Toml,
+    Json,
+}
+
+impl PolicyFileFormat {
+    /// Picks the format from the file extension (`.json` is JSON, anything else TOML).
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Toml,
+        }
+    }
+}
+
+/// A team as declared in the policy file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TeamSpec {
+    /// Key of the team's table in the file (for diagnostics).
+    pub name: String,
+    pub id: TeamId,
+    pub members: HashSet<PeerId>,
+}
+
+/// A parsed and validated enterprise policy file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnterprisePolicyFile {
+    /// Monotonic revision number set by the admin (0 if the file has none).
+    pub serial: u64,
+    /// Hex SHA-256 of the file contents.
+    pub digest: String,
+    /// `None` if the file does not list known peers.
+    pub known_peers: Option<HashSet<PeerId>>,
+    /// Teams ordered by name.
+    pub teams: Vec<TeamSpec>,
+    /// Default role per entity type.
+    pub default_roles: HashMap<String, SyncRole>,
+    pub device_limits: HashMap<PeerId, usize>,
+    pub audit_retention: AuditRetention,
+    pub audit_export_format: AuditExportFormat,
+    pub redaction: RedactionRules,
+}
+
+impl EnterprisePolicyFile {
+    /// Parses and validates policy file contents.
+    pub fn parse(contents: &str, format: PolicyFileFormat) -> Result<Self, SyncError> {
+        let raw: RawPolicyFile = match format {
+            PolicyFileFormat::Toml => {
+                toml::from_str(contents).map_err(|e| invalid(format!("{e}")))?
+            }
+            PolicyFileFormat::Json => {
+                serde_json::from_str(contents).map_err(|e| invalid(format!("{e}")))?
+            }
+        };
+        let digest = hex::encode(Sha256::digest(contents.as_bytes()));
+        raw.validate(digest)
+    }
+}
+
+// ── Raw file structure ──────────────────────────────────────────
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPolicyFile {
+    #[serde(default = "default_version")]
+    version: u32,
+    #[serde(default)]
+    serial: u64,
+    #[serde(default)]
+    known_peers: Option<Vec<String>>,
+    #[serde(default)]
+    teams: BTreeMap<String, RawTeam>,
+    #[serde(default)]
+    default_roles: BTreeMap<String, String>,
+    #[serde(default)]
+    device_limits: BTreeMap<String, usize>,
+    #[serde(default)]
+    audit: RawAudit,
+    #[serde(default)]
+    redaction: BTreeMap<String, RawRedaction>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawTeam {
+    id: String,
+    #[serde(default)]
+    members: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct RawAudit {
+    #[serde(default)]
+    retention_days: Option<u32>,
+    #[serde(default)]
+    max_entries: Option<usize>,
+    #[serde(default)]
+    export_format: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRedaction {
+    #[serde(default)]
+    labels: Vec<String>,
+    /// Entity type → JSON pointers.
+    #[serde(default)]
+    paths: BTreeMap<String, Vec<String>>,
+}
+
+fn default_version() -> u32 {
+    POLICY_FILE_VERSION
+}
+
+impl RawPolicyFile {
+    fn validate(self, digest: String) -> Result<EnterprisePolicyFile, SyncError> {
+        if self.version == 0 || self.version > POLICY_FILE_VERSION {
+            return Err(invalid(format!(
+                "unsupported version {} (expected {})",
+                self.version, POLICY_FILE_VERSION
+            )));
+        }
+
+        let known_peers = self
+            .known_peers
+            .map(|peers| {
+                peers
+                    .iter()
+                    .map(|p| parse_peer(p, "known_peers"))
+                    .collect::<Result<HashSet<_>, _>>()
+            })
+            .transpose()?;
+        // An empty known_peers list admits every peer, so only check
+        // references against it when it is set.
+        let check_known = |peer: &PeerId, context: &str| {
+            if let Some(known) = &known_peers
+                && !known.is_empty()
+                && !known.contains(peer)
+            {
+                return Err(invalid(format!("{context}: {peer} is not in known_peers")));
+            }
+            Ok(())
+        };
+
+        let mut teams = Vec::with_capacity(self.teams.len());
+        let mut team_ids = HashSet::new();
+        for (name, raw) in self.teams {
+            let context = format!("teams.{name}");
+            let id = uuid::Uuid::parse_str(&raw.id)
+                .map(TeamId)
+                .map_err(|e| invalid(format!("{context}.id: {e}")))?;
+            if !team_ids.insert(id) {
+                return Err(invalid(format!("{context}: team id {id} is used by another team")));
+            }
+            let mut members = HashSet::new();
+            for member in &raw.members {
+                let peer = parse_peer(member, &context)?;
+                check_known(&peer, &context)?;
+                members.insert(peer);
+            }
+            teams.push(TeamSpec { name, id, members });
+        }
+
+        let mut default_roles = HashMap::new();
+        for (entity_type, role) in self.default_roles {
+            let role = parse_role(&role)
+                .ok_or_else(|| invalid(format!("default_roles.{entity_type}: unknown role {role:?}")))?;
+            default_roles.insert(entity_type, role);
+        }
+
+        let mut device_limits = HashMap::new();
+        for (peer, limit) in self.device_limits {
+            let peer = parse_peer(&peer, "device_limits")?;
+            if limit == 0 {
+                return Err(invalid(format!("device_limits: limit for {peer} must be at least 1")));
+            }
+            check_known(&peer, "device_limits")?;
+            device_limits.insert(peer, limit);
+        }
+
+        let audit_export_format: AuditExportFormat = match &self.audit.export_format {
+            Some(format) => format
+                .parse()
+                .map_err(|_| invalid(format!("audit.export_format: unsupported format {format:?}")))?,
+            None => AuditExportFormat::Jsonl,
+        };
+
+        let mut redaction = RedactionRules::new();
+        for (role_name, raw) in self.redaction {
+            let context = format!("redaction.{role_name}");
+            let role = parse_role(&role_name)
+                .ok_or_else(|| invalid(format!("{context}: unknown role {role_name:?}")))?;
+            if role > SyncRole::Viewer {
+                return Err(invalid(format!(
+                    "{context}: only the Viewer role can have fields redacted"
+                )));
+            }
+            for label in &raw.labels {
+                redaction = redaction.hide_label(role, label);
+            }
+            for (entity_type, pointers) in &raw.paths {
+                for pointer in pointers {
+                    if !pointer.starts_with('/') {
+                        return Err(invalid(format!(
+                            "{context}.paths.{entity_type}: {pointer:?} is not a JSON pointer"
+                        )));
+                    }
+                    redaction = redaction.hide_path(role, entity_type, pointer);
+                }
+            }
+        }
+
+        Ok(EnterprisePolicyFile {
+            serial: self.serial,
+            digest,
+            known_peers,
+            teams,
+            default_roles,
+            device_limits,
+            audit_retention: AuditRetention::from_config(
+                self.audit.retention_days,
+                self.audit.max_entries,
+            ),
+            audit_export_format,
+            redaction,
+        })
+    }
+}
+
+fn invalid(reason: String) -> SyncError {
+    SyncError::InvalidPolicy(reason)
+}
+
+fn parse_peer(value: &str, context: &str) -> Result<PeerId, SyncError> {
+    value
+        .parse()
+        .map_err(|e| invalid(format!("{context}: invalid peer id {value:?}: {e}")))
+}
+
+fn parse_role(s: &str) -> Option<SyncRole> {
+    match s.to_ascii_lowercase().as_str() {
+        "viewer" => Some(SyncRole::Viewer),
+        "editor" => Some(SyncRole::Editor),
+        "admin" => Some(SyncRole::Admin),
+        "owner" => Some(SyncRole::Owner),
+        _ => None,
+    }
+}
+
+// ── Loading and signatures ──────────────────────────────────────
+
+/// Where a policy file lives and which admin keys may sign it.
+#[derive(Debug, Clone)]
+pub struct PolicyFileSource {
+    pub path: PathBuf,
+    /// The file must carry a valid signature from one of these keys. A source
+    /// without trusted keys loads nothing.
+    pub trusted_keys: Vec<IdentityPublicKey>,
+}
+
+impl PolicyFileSource {
+    /// Creates a source with no trusted keys; add them with [`Self::with_trusted_key`].
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            trusted_keys: Vec::new(),
+        }
+    }
+
+    /// Accepts files signed by `key`.
+    pub fn with_trusted_key(mut self, key: IdentityPublicKey) -> Self {
+        self.trusted_keys.push(key);
+        self
+    }
+
+    /// Path of the detached signature (`<file>.sig`).
+    pub fn signature_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".sig");
+        PathBuf::from(path)
+    }
+
+    /// Signs policy file contents, returning the text to store in `<file>.sig`.
+    pub fn sign(contents: &[u8], key: &IdentityKeyPair) -> String {
+        STANDARD.encode(key.sign(contents))
+    }
+
+    /// Reads, verifies and parses the policy file.
+    pub fn load(&self) -> Result<EnterprisePolicyFile, SyncError> {
+        let contents = read_file(&self.path)?;
+        self.verify_signature(&contents)?;
+        let text = String::from_utf8(contents)
+            .map_err(|_| invalid(format!("{:?} is not valid UTF-8", self.path)))?;
+        EnterprisePolicyFile::parse(&text, PolicyFileFormat::from_path(&self.path))
+    }
+
+    /// Hash over the file and its signature, used to detect changes.
+    fn fingerprint(&self) -> Result<String, SyncError> {
+        let mut hasher = Sha256::new();
+        hasher.update(read_file(&self.path)?);
+        if let Ok(signature) = std::fs::read(self.signature_path()) {
+            hasher.update(signature);
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    fn verify_signature(&self, contents: &[u8]) -> Result<(), SyncError> {
+        if self.trusted_keys.is_empty() {
+            return Err(SyncError::Auth(
+                "no trusted keys configured for the policy file".into(),
+            ));
+        }
+        let encoded = std::fs::read_to_string(self.signature_path())
+            .map_err(|e| SyncError::Auth(format!("policy file signature unavailable: {e}")))?;
+        let signature = STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| SyncError::Auth(format!("invalid policy file signature encoding: {e}")))?;
+        if self
+            .trusted_keys
+            .iter()
+            .any(|key| key.verify(contents, &signature).is_ok())
+        {
+            Ok(())
+        } else {
+            Err(SyncError::Auth(
+                "policy file is not signed by a trusted key".into(),
+            ))
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, SyncError> {
+    std::fs::read(path).map_err(|e| SyncError::Storage(format!("failed to read {path:?}: {e}")))
+}
+
+// ── Diff and apply ──────────────────────────────────────────────
+
+/// Changes needed to bring an `EnterpriseSyncPolicy` in line with a policy file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PolicyDiff {
+    pub known_peers_added: Vec<PeerId>,
+    pub known_peers_removed: Vec<PeerId>,
+    pub team_members_added: Vec<(TeamId, PeerId)>,
+    pub team_members_removed: Vec<(TeamId, PeerId)>,
+    pub default_roles_set: Vec<(String, SyncRole)>,
+    pub default_roles_removed: Vec<String>,
+    pub device_limits_set: Vec<(PeerId, usize)>,
+    pub device_limits_removed: Vec<PeerId>,
+    /// New audit retention rules, if they changed.
+    pub audit_retention: Option<AuditRetention>,
+    /// New audit export format, if it changed.
+    pub audit_export_format: Option<AuditExportFormat>,
+    /// New redaction rules, if they changed.
+    pub redaction: Option<RedactionRules>,
+}
+
+impl PolicyDiff {
+    /// Returns true if the policy already matches the file.
+    pub fn is_empty(&self) -> bool {
+        self.change_count() == 0
+    }
+
+    /// Number of individual changes.
+    pub fn change_count(&self) -> usize {
+        self.known_peers_added.len()
+            + self.known_peers_removed.len()
+            + self.team_members_added.len()
+            + self.team_members_removed.len()
+            + self.default_roles_set.len()
+            + self.default_roles_removed.len()
+            + self.device_limits_set.len()
+            + self.device_limits_removed.len()
+            + usize::from(self.audit_retention.is_some())
+            + usize::from(self.audit_export_format.is_some())
+            + usize::from(self.redaction.is_some())
+    }
+}
+
+impl EnterpriseSyncPolicy {
+    /// Computes the changes `apply_policy_file` would make.
+    pub async fn diff_policy_file(&self, file: &EnterprisePolicyFile) -> PolicyDiff {
+        let mut diff = PolicyDiff::default();
+
+        if let Some(file_peers) = &file.known_peers {
+            let known = self.known_peers.read().await;
+            diff.known_peers_added = file_peers.difference(&known).copied().collect();
+            diff.known_peers_removed = known.difference(file_peers).copied().collect();
+        }
+
+        {
+            let teams = self.teams.read().await;
+            let empty = HashSet::new();
+            for team in &file.teams {
+                let current = teams.get(&team.id).unwrap_or(&empty);
+                diff.team_members_added
+                    .extend(team.members.difference(current).map(|p| (team.id, *p)));
+                diff.team_members_removed
+                    .extend(current.difference(&team.members).map(|p| (team.id, *p)));
+            }
+        }
+
+        {
+            let defaults = self.type_default_roles.read().await;
+            for (entity_type, role) in &file.default_roles {
+                if defaults.get(entity_type) != Some(role) {
+                    diff.default_roles_set.push((entity_type.clone(), *role));
+                }
+            }
+            diff.default_roles_removed = defaults
+                .keys()
+                .filter(|t| !file.default_roles.contains_key(*t))
+                .cloned()
+                .collect();
+            diff.default_roles_set.sort();
+            diff.default_roles_removed.sort();
+        }
+
+        {
+            let limits = self.device_limits.read().await;
+            for (peer, limit) in &file.device_limits {
+                if limits.get(peer) != Some(limit) {
+                    diff.device_limits_set.push((*peer, *limit));
+                }
+            }
+            diff.device_limits_removed = limits
+                .keys()
+                .filter(|p| !file.device_limits.contains_key(*p))
+                .copied()
+                .collect();
+        }
+
+        if *self.audit_retention.read().await != file.audit_retention {
+            diff.audit_retention = Some(file.audit_retention.clone());
+        }
+
+        if *self.audit_export_format.read().await != file.audit_export_format {
+            diff.audit_export_format = Some(file.audit_export_format);
+        }
+
+        if *self.redaction.read().await != file.redaction {
+            diff.redaction = Some(file.redaction.clone());
+        }
+
+        diff
+    }
+
+    /// Brings the policy in line with `file`, persisting every change to the
+    /// attached store. Returns the changes that were made.
+    ///
+    /// Fails without changing anything if `file` is older than the last
+    /// applied policy file (see the module docs on `serial`).
+    pub async fn apply_policy_file(
+        &self,
+        file: &EnterprisePolicyFile,
+    ) -> Result<PolicyDiff, SyncError> {
+        let mut applied = self.applied_policy_file.write().await;
+        if let Some((serial, digest)) = applied.as_ref() {
+            if file.serial < *serial {
+                return Err(invalid(format!(
+                    "serial {} is older than the applied serial {serial}",
+                    file.serial
+                )));
+            }
+            if file.serial == *serial && file.digest != *digest {
+                return Err(invalid(format!(
+                    "serial {serial} was already applied with different contents"
+                )));
+            }
+        }
+
+        let diff = self.diff_policy_file(file).await;
+
+        for peer in &diff.known_peers_added {
+            self.add_known_peer(*peer).await;
+        }
+        for peer in &diff.known_peers_removed {
+            self.remove_known_peer(*peer).await;
+        }
+        for (team, peer) in &diff.team_members_added {
+            self.add_team_member(*team, *peer).await;
+        }
+        for (team, peer) in &diff.team_members_removed {
+            self.remove_team_member(*team, *peer).await;
+        }
+        for (entity_type, role) in &diff.default_roles_set {
+            self.set_type_default_role(entity_type, Some(*role)).await;
+        }
+        for entity_type in &diff.default_roles_removed {
+            self.set_type_default_role(entity_type, None).await;
+        }
+        for (peer, limit) in &diff.device_limits_set {
+            self.set_device_limit(*peer, *limit).await;
+        }
+        for peer in &diff.device_limits_removed {
+            self.remove_device_limit(*peer).await;
+        }
+        if let Some(retention) = &diff.audit_retention {
+            self.set_audit_retention(retention.clone()).await?;
+        }
+        if let Some(format) = diff.audit_export_format {
+            self.set_audit_export_format(format).await;
+        }
+        if let Some(rules) = &diff.redaction {
+            self.set_redaction_rules(rules.clone()).await;
+        }
+
+        if let Some(store) = self.store() {
+            store.save_policy_file_state(file.serial, &file.digest)?;
+        }
+        *applied = Some((file.serial, file.digest.clone()));
+
+        Ok(diff)
+    }
+
+    /// Spawns a background task that loads `source` immediately and then
+    /// re-applies it whenever the file or its signature changes, checking every
+    /// `interval`. A file that fails to load or verify is logged and ignored,
+    /// leaving the previously applied policy in place.
+    pub fn spawn_policy_file_watcher(
+        policy: Arc<Self>,
+        source: PolicyFileSource,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut last_seen: Option<String> = None;
+            loop {
+                ticker.tick().await;
+                let current = match source.fingerprint() {
+                    Ok(fingerprint) => fingerprint,
+                    Err(e) => format!("unreadable: {e}"),
+                };
+                if last_seen.as_ref() == Some(&current) {
+                    continue;
+                }
+                last_seen = Some(current);
+
+                match source.load() {
+                    Ok(file) => match policy.apply_policy_file(&file).await {
+                        Ok(diff) => tracing::info!(
+                            "Applied enterprise policy {:?}: {} changes",
+                            source.path,
+                            diff.change_count()
+                        ),
+                        Err(e) => tracing::warn!(
+                            "Failed to apply enterprise policy {:?}: {}",
+                            source.path,
+                            e
+                        ),
+                    },
+                    Err(e) => tracing::warn!(
+                        "Rejected enterprise policy {:?}: {}. Keeping current policy.",
+                        source.path,
+                        e
+                    ),
+                }
+            }
+        })
+    }
+}
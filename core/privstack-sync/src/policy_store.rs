@@ -1,12 +1,22 @@
 //! Persistent storage for enterprise sync policy state (ACLs, teams, audit log).
 //!
 //! Uses a separate SQLite file so policy data is isolated from entity/event stores.
+//! Audit rows are hash-chained; see [`crate::audit`].
 
+use crate::audit::{
+    chain_hash, to_millis, AuditBreakReason, AuditChainBreak, AuditExportFormat, AuditQuery,
+    AuditRecord, AuditRetention, AuditVerification, AUDIT_GENESIS_HASH,
+};
 use crate::error::SyncError;
-use crate::policy::{AuditDecision, AuditEntry, AuditAction, SyncRole};
-use privstack_types::{EntityId, PeerId};
-use rusqlite::{params, Connection};
+use crate::policy::{AuditDecision, AuditEntry, AuditAction, GrantWindow, SyncRole};
+use privstack_types::{EntityId, Event, PeerId};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::io::Write;
 use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+const AUDIT_COLUMNS: &str = "id, peer, entity, action, decision, detail, timestamp, prev_hash, hash";
 
 /// Persistent store for policy state backed by SQLite.
 pub struct PolicyStore {
@@ -47,13 +57,23 @@ impl PolicyStore {
                 action TEXT NOT NULL,
                 decision TEXT NOT NULL,
                 detail TEXT NOT NULL,
-                timestamp TEXT NOT NULL
+                timestamp TEXT NOT NULL,
+                prev_hash TEXT,
+                hash TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS audit_anchor (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                hash TEXT NOT NULL,
+                pruned INTEGER NOT NULL
             );
 
             CREATE TABLE IF NOT EXISTS acls (
                 entity_id TEXT NOT NULL,
                 peer_id TEXT NOT NULL,
                 role TEXT NOT NULL,
+                not_before INTEGER,
+                expires_at INTEGER,
                 UNIQUE(entity_id, peer_id)
             );
 
@@ -62,10 +82,22 @@ impl PolicyStore {
                 default_role TEXT NOT NULL
             );
 
+            CREATE TABLE IF NOT EXISTS type_default_roles (
+                entity_type TEXT PRIMARY KEY,
+                role TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS entity_types (
+                entity_id TEXT PRIMARY KEY,
+                entity_type TEXT NOT NULL
+            );
+
             CREATE TABLE IF NOT EXISTS acl_team_roles (
                 entity_id TEXT NOT NULL,
                 team_id TEXT NOT NULL,
                 role TEXT NOT NULL,
+                not_before INTEGER,
+                expires_at INTEGER,
                 UNIQUE(entity_id, team_id)
             );
 
@@ -84,38 +116,56 @@ impl PolicyStore {
                 peer_id TEXT PRIMARY KEY
             );
 
+            CREATE TABLE IF NOT EXISTS policy_events (
+                event_id TEXT PRIMARY KEY,
+                event_json TEXT NOT NULL
+            );
+
             CREATE TABLE IF NOT EXISTS active_devices (
                 peer_id TEXT NOT NULL,
                 device_id TEXT NOT NULL,
                 UNIQUE(peer_id, device_id)
             );
+
+            CREATE TABLE IF NOT EXISTS policy_file_state (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                serial INTEGER NOT NULL,
+                digest TEXT NOT NULL
+            );
             ",
         )
         .map_err(|e| SyncError::Storage(format!("failed to init policy schema: {e}")))?;
-        Ok(())
+        for table in ["acls", "acl_team_roles"] {
+            add_column_if_missing(&conn, table, "not_before", "INTEGER")?;
+            add_column_if_missing(&conn, table, "expires_at", "INTEGER")?;
+        }
+        migrate_audit_chain(&conn)
     }
 
     // ── Audit log ────────────────────────────────────────────────
 
-    /// Saves an audit entry to the database.
+    /// Saves an audit entry to the database, chaining it to the previous entry.
     pub fn save_audit_entry(&self, entry: &AuditEntry) -> Result<(), SyncError> {
         let conn = self.conn.lock().unwrap();
-        let ts = entry
-            .timestamp
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis()
-            .to_string();
+        let ts = to_millis(entry.timestamp).to_string();
+        let peer = entry.peer.to_string();
+        let entity = entry.entity.map(|e| e.to_string());
+        let action = entry.action.to_string();
+        let decision = format!("{:?}", entry.decision);
+
+        let prev_hash = audit_head(&conn)?;
+        let hash = chain_hash(
+            &prev_hash,
+            &peer,
+            entity.as_deref(),
+            &action,
+            &decision,
+            &entry.detail,
+            &ts,
+        );
         conn.execute(
-            "INSERT INTO audit_log (peer, entity, action, decision, detail, timestamp) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![
-                entry.peer.to_string(),
-                entry.entity.map(|e| e.to_string()),
-                entry.action.to_string(),
-                format!("{:?}", entry.decision),
-                entry.detail,
-                ts,
-            ],
+            "INSERT INTO audit_log (peer, entity, action, decision, detail, timestamp, prev_hash, hash) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![peer, entity, action, decision, entry.detail, ts, prev_hash, hash],
         )
         .map_err(|e| SyncError::Storage(format!("failed to save audit entry: {e}")))?;
         Ok(())
@@ -182,14 +232,213 @@ impl PolicyStore {
         Ok(count as usize)
     }
 
+    /// Returns the audit entries matching `query`, with their chain hashes.
+    pub fn query_audit_log(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>, SyncError> {
+        let mut clauses: Vec<&str> = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+        if let Some(peer) = query.peer {
+            clauses.push("peer = ?");
+            values.push(Value::Text(peer.to_string()));
+        }
+        if let Some(entity) = query.entity {
+            clauses.push("entity = ?");
+            values.push(Value::Text(entity.to_string()));
+        }
+        if let Some(action) = &query.action {
+            clauses.push("action = ?");
+            values.push(Value::Text(action.to_string()));
+        }
+        if let Some(decision) = &query.decision {
+            clauses.push("decision = ?");
+            values.push(Value::Text(format!("{decision:?}")));
+        }
+        if let Some(since) = query.since {
+            clauses.push("CAST(timestamp AS INTEGER) >= ?");
+            values.push(Value::Integer(to_millis(since) as i64));
+        }
+        if let Some(until) = query.until {
+            clauses.push("CAST(timestamp AS INTEGER) < ?");
+            values.push(Value::Integer(to_millis(until) as i64));
+        }
+
+        let mut sql = format!("SELECT {AUDIT_COLUMNS} FROM audit_log");
+        if !clauses.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&clauses.join(" AND "));
+        }
+        sql.push_str(if query.newest_first { " ORDER BY id DESC" } else { " ORDER BY id ASC" });
+        // SQLite needs a LIMIT before OFFSET; -1 means no limit.
+        sql.push_str(" LIMIT ? OFFSET ?");
+        values.push(Value::Integer(query.limit.map_or(-1, |l| l as i64)));
+        values.push(Value::Integer(query.offset as i64));
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| SyncError::Storage(format!("failed to prepare audit query: {e}")))?;
+        let rows = stmt
+            .query_map(params_from_iter(values.iter()), AuditRow::from_row)
+            .map_err(|e| SyncError::Storage(format!("failed to query audit log: {e}")))?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            let row = row.map_err(|e| SyncError::Storage(format!("failed to read audit row: {e}")))?;
+            result.push(row.into_record()?);
+        }
+        Ok(result)
+    }
+
+    /// Walks the audit chain from its anchor and reports the first row that
+    /// was modified or does not link to its predecessor.
+    pub fn verify_audit_chain(&self) -> Result<AuditVerification, SyncError> {
+        let conn = self.conn.lock().unwrap();
+        let mut expected_prev = audit_anchor(&conn)?.unwrap_or_else(|| AUDIT_GENESIS_HASH.to_string());
+        let mut stmt = conn
+            .prepare(&format!("SELECT {AUDIT_COLUMNS} FROM audit_log ORDER BY id ASC"))
+            .map_err(|e| SyncError::Storage(format!("failed to prepare audit query: {e}")))?;
+        let rows = stmt
+            .query_map([], AuditRow::from_row)
+            .map_err(|e| SyncError::Storage(format!("failed to query audit log: {e}")))?;
+
+        let mut verified = 0;
+        for row in rows {
+            let row = row.map_err(|e| SyncError::Storage(format!("failed to read audit row: {e}")))?;
+            let reason = if row.prev_hash.as_deref() != Some(expected_prev.as_str()) {
+                Some(AuditBreakReason::Unlinked)
+            } else if row.hash.as_deref() != Some(row.computed_hash().as_str()) {
+                Some(AuditBreakReason::Modified)
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Ok(AuditVerification {
+                    verified,
+                    head_hash: expected_prev,
+                    first_break: Some(AuditChainBreak { row_id: row.id, reason }),
+                });
+            }
+            expected_prev = row.hash.unwrap_or_default();
+            verified += 1;
+        }
+        Ok(AuditVerification {
+            verified,
+            head_hash: expected_prev,
+            first_break: None,
+        })
+    }
+
+    /// Writes the entries matching `query` to `out`, one per line.
+    /// Returns the number of entries written.
+    pub fn export_audit_log(
+        &self,
+        query: &AuditQuery,
+        format: AuditExportFormat,
+        out: &mut dyn Write,
+    ) -> Result<usize, SyncError> {
+        let records = self.query_audit_log(query)?;
+        for record in &records {
+            writeln!(out, "{}", format.format_record(record))
+                .map_err(|e| SyncError::Storage(format!("failed to write audit export: {e}")))?;
+        }
+        Ok(records.len())
+    }
+
+    /// Prunes the oldest audit entries according to `retention`, relative to `now`.
+    ///
+    /// The hash of the last pruned row becomes the chain anchor, so the rows
+    /// that remain still verify. Returns the number of rows removed.
+    pub fn apply_audit_retention(
+        &self,
+        retention: &AuditRetention,
+        now: SystemTime,
+    ) -> Result<usize, SyncError> {
+        if retention.is_unbounded() {
+            return Ok(0);
+        }
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn
+            .transaction()
+            .map_err(|e| SyncError::Storage(format!("failed to begin retention: {e}")))?;
+
+        let mut cutoff: Option<i64> = None;
+        if let Some(max_age) = retention.max_age {
+            let oldest_kept = to_millis(now.checked_sub(max_age).unwrap_or(std::time::UNIX_EPOCH));
+            let id: Option<i64> = tx
+                .query_row(
+                    "SELECT MAX(id) FROM audit_log WHERE CAST(timestamp AS INTEGER) < ?1",
+                    params![oldest_kept as i64],
+                    |row| row.get(0),
+                )
+                .map_err(|e| SyncError::Storage(format!("failed to apply audit retention: {e}")))?;
+            cutoff = cutoff.max(id);
+        }
+        if let Some(max_entries) = retention.max_entries {
+            let id: Option<i64> = tx
+                .query_row(
+                    "SELECT id FROM audit_log ORDER BY id DESC LIMIT 1 OFFSET ?1",
+                    params![max_entries as i64],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| SyncError::Storage(format!("failed to apply audit retention: {e}")))?;
+            cutoff = cutoff.max(id);
+        }
+        let Some(cutoff) = cutoff else {
+            return Ok(0);
+        };
+
+        let anchor_hash: String = tx
+            .query_row("SELECT hash FROM audit_log WHERE id = ?1", params![cutoff], |row| row.get(0))
+            .map_err(|e| SyncError::Storage(format!("failed to apply audit retention: {e}")))?;
+        let removed = tx
+            .execute("DELETE FROM audit_log WHERE id <= ?1", params![cutoff])
+            .map_err(|e| SyncError::Storage(format!("failed to prune audit log: {e}")))?;
+        tx.execute(
+            "INSERT INTO audit_anchor (id, hash, pruned) VALUES (1, ?1, ?2)
+             ON CONFLICT(id) DO UPDATE SET hash = excluded.hash, pruned = pruned + excluded.pruned",
+            params![anchor_hash, removed as i64],
+        )
+        .map_err(|e| SyncError::Storage(format!("failed to save audit anchor: {e}")))?;
+        tx.commit()
+            .map_err(|e| SyncError::Storage(format!("failed to commit retention: {e}")))?;
+        Ok(removed)
+    }
+
+    /// Returns the number of audit entries removed by retention so far.
+    pub fn audit_pruned_count(&self) -> Result<usize, SyncError> {
+        let conn = self.conn.lock().unwrap();
+        let pruned: Option<i64> = conn
+            .query_row("SELECT pruned FROM audit_anchor WHERE id = 1", [], |row| row.get(0))
+            .optional()
+            .map_err(|e| SyncError::Storage(format!("failed to read audit anchor: {e}")))?;
+        Ok(pruned.unwrap_or(0) as usize)
+    }
+
     // ── ACL persistence ──────────────────────────────────────────
 
     /// Saves a peer-level ACL entry.
     pub fn save_acl(&self, entity_id: &EntityId, peer_id: &PeerId, role: SyncRole) -> Result<(), SyncError> {
+        self.save_acl_grant(entity_id, peer_id, role, &GrantWindow::default())
+    }
+
+    /// Saves a peer-level ACL entry with its validity window.
+    pub fn save_acl_grant(
+        &self,
+        entity_id: &EntityId,
+        peer_id: &PeerId,
+        role: SyncRole,
+        window: &GrantWindow,
+    ) -> Result<(), SyncError> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT OR REPLACE INTO acls (entity_id, peer_id, role) VALUES (?1, ?2, ?3)",
-            params![entity_id.to_string(), peer_id.to_string(), format!("{role}")],
+            "INSERT OR REPLACE INTO acls (entity_id, peer_id, role, not_before, expires_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                entity_id.to_string(),
+                peer_id.to_string(),
+                format!("{role}"),
+                window.not_before.map(|t| t as i64),
+                window.expires_at.map(|t| t as i64),
+            ],
         )
         .map_err(|e| SyncError::Storage(format!("failed to save acl: {e}")))?;
         Ok(())
@@ -233,6 +482,33 @@ impl PolicyStore {
         Ok(result)
     }
 
+    /// Loads all peer-level ACLs with their validity windows.
+    pub fn load_acl_grants(&self) -> Result<Vec<(EntityId, PeerId, SyncRole, GrantWindow)>, SyncError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT entity_id, peer_id, role, not_before, expires_at FROM acls")
+            .map_err(|e| SyncError::Storage(format!("failed to prepare acl query: {e}")))?;
+        let rows = stmt
+            .query_map([], |row| {
+                let eid: String = row.get(0)?;
+                let pid: String = row.get(1)?;
+                let role: String = row.get(2)?;
+                let window = window_from_row(row.get(3)?, row.get(4)?);
+                Ok((eid, pid, role, window))
+            })
+            .map_err(|e| SyncError::Storage(format!("failed to query acls: {e}")))?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            let (eid, pid, role_str, window) =
+                row.map_err(|e| SyncError::Storage(format!("failed to read acl row: {e}")))?;
+            let entity_id: EntityId = eid.parse().map_err(|e| SyncError::Storage(format!("{e}")))?;
+            let peer_id: PeerId = pid.parse().map_err(|e| SyncError::Storage(format!("{e}")))?;
+            result.push((entity_id, peer_id, parse_sync_role(&role_str), window));
+        }
+        Ok(result)
+    }
+
     /// Saves a default role for an entity.
     pub fn save_default_role(&self, entity_id: &EntityId, role: SyncRole) -> Result<(), SyncError> {
         let conn = self.conn.lock().unwrap();
@@ -278,17 +554,112 @@ impl PolicyStore {
         Ok(result)
     }
 
+    /// Saves the default role for every entity of a type.
+    pub fn save_type_default_role(&self, entity_type: &str, role: SyncRole) -> Result<(), SyncError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO type_default_roles (entity_type, role) VALUES (?1, ?2)",
+            params![entity_type, format!("{role}")],
+        )
+        .map_err(|e| SyncError::Storage(format!("failed to save type default role: {e}")))?;
+        Ok(())
+    }
+
+    /// Removes the default role for an entity type.
+    pub fn remove_type_default_role(&self, entity_type: &str) -> Result<(), SyncError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM type_default_roles WHERE entity_type = ?1",
+            params![entity_type],
+        )
+        .map_err(|e| SyncError::Storage(format!("failed to remove type default role: {e}")))?;
+        Ok(())
+    }
+
+    /// Loads all per-type default roles. Returns (entity_type, role) tuples.
+    pub fn load_type_default_roles(&self) -> Result<Vec<(String, SyncRole)>, SyncError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT entity_type, role FROM type_default_roles")
+            .map_err(|e| SyncError::Storage(format!("{e}")))?;
+        let rows = stmt
+            .query_map([], |row| {
+                let entity_type: String = row.get(0)?;
+                let role: String = row.get(1)?;
+                Ok((entity_type, role))
+            })
+            .map_err(|e| SyncError::Storage(format!("{e}")))?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            let (entity_type, role_str) = row.map_err(|e| SyncError::Storage(format!("{e}")))?;
+            result.push((entity_type, parse_sync_role(&role_str)));
+        }
+        Ok(result)
+    }
+
+    /// Records the type of an entity.
+    pub fn save_entity_type(&self, entity_id: &EntityId, entity_type: &str) -> Result<(), SyncError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO entity_types (entity_id, entity_type) VALUES (?1, ?2)",
+            params![entity_id.to_string(), entity_type],
+        )
+        .map_err(|e| SyncError::Storage(format!("failed to save entity type: {e}")))?;
+        Ok(())
+    }
+
+    /// Loads all recorded entity types. Returns (entity_id, entity_type) tuples.
+    pub fn load_entity_types(&self) -> Result<Vec<(EntityId, String)>, SyncError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT entity_id, entity_type FROM entity_types")
+            .map_err(|e| SyncError::Storage(format!("{e}")))?;
+        let rows = stmt
+            .query_map([], |row| {
+                let eid: String = row.get(0)?;
+                let entity_type: String = row.get(1)?;
+                Ok((eid, entity_type))
+            })
+            .map_err(|e| SyncError::Storage(format!("{e}")))?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            let (eid, entity_type) = row.map_err(|e| SyncError::Storage(format!("{e}")))?;
+            let entity_id: EntityId = eid.parse().map_err(|e| SyncError::Storage(format!("{e}")))?;
+            result.push((entity_id, entity_type));
+        }
+        Ok(result)
+    }
+
     /// Saves a team-level ACL entry.
     pub fn save_team_role(
         &self,
         entity_id: &EntityId,
         team_id: &str,
         role: SyncRole,
+    ) -> Result<(), SyncError> {
+        self.save_team_grant(entity_id, team_id, role, &GrantWindow::default())
+    }
+
+    /// Saves a team-level ACL entry with its validity window.
+    pub fn save_team_grant(
+        &self,
+        entity_id: &EntityId,
+        team_id: &str,
+        role: SyncRole,
+        window: &GrantWindow,
     ) -> Result<(), SyncError> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT OR REPLACE INTO acl_team_roles (entity_id, team_id, role) VALUES (?1, ?2, ?3)",
-            params![entity_id.to_string(), team_id, format!("{role}")],
+            "INSERT OR REPLACE INTO acl_team_roles (entity_id, team_id, role, not_before, expires_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                entity_id.to_string(),
+                team_id,
+                format!("{role}"),
+                window.not_before.map(|t| t as i64),
+                window.expires_at.map(|t| t as i64),
+            ],
         )
         .map_err(|e| SyncError::Storage(format!("{e}")))?;
         Ok(())
@@ -329,6 +700,31 @@ impl PolicyStore {
         Ok(result)
     }
 
+    /// Loads all team-level ACLs with their validity windows.
+    pub fn load_team_grants(&self) -> Result<Vec<(EntityId, String, SyncRole, GrantWindow)>, SyncError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT entity_id, team_id, role, not_before, expires_at FROM acl_team_roles")
+            .map_err(|e| SyncError::Storage(format!("{e}")))?;
+        let rows = stmt
+            .query_map([], |row| {
+                let eid: String = row.get(0)?;
+                let tid: String = row.get(1)?;
+                let role: String = row.get(2)?;
+                let window = window_from_row(row.get(3)?, row.get(4)?);
+                Ok((eid, tid, role, window))
+            })
+            .map_err(|e| SyncError::Storage(format!("{e}")))?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            let (eid, tid, role_str, window) = row.map_err(|e| SyncError::Storage(format!("{e}")))?;
+            let entity_id: EntityId = eid.parse().map_err(|e| SyncError::Storage(format!("{e}")))?;
+            result.push((entity_id, tid, parse_sync_role(&role_str), window));
+        }
+        Ok(result)
+    }
+
     // ── Team membership ──────────────────────────────────────────
 
     /// Saves a team membership entry.
@@ -389,6 +785,17 @@ impl PolicyStore {
         Ok(())
     }
 
+    /// Removes a peer's device limit.
+    pub fn remove_device_limit(&self, peer_id: &PeerId) -> Result<(), SyncError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM device_limits WHERE peer_id = ?1",
+            params![peer_id.to_string()],
+        )
+        .map_err(|e| SyncError::Storage(format!("{e}")))?;
+        Ok(())
+    }
+
     /// Loads all device limits. Returns (peer_id, max_devices) tuples.
     pub fn load_device_limits(&self) -> Result<Vec<(PeerId, usize)>, SyncError> {
         let conn = self.conn.lock().unwrap();
@@ -458,6 +865,67 @@ impl PolicyStore {
         Ok(result)
     }
 
+    // ── Replicated policy events ─────────────────────────────────
+
+    /// Saves a replicated policy event (ignored if already saved).
+    pub fn save_policy_event(&self, event: &Event) -> Result<(), SyncError> {
+        let json = serde_json::to_string(event)
+            .map_err(|e| SyncError::Storage(format!("failed to serialize policy event: {e}")))?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO policy_events (event_id, event_json) VALUES (?1, ?2)",
+            params![event.id.to_string(), json],
+        )
+        .map_err(|e| SyncError::Storage(format!("failed to save policy event: {e}")))?;
+        Ok(())
+    }
+
+    /// Loads all replicated policy events in the order they were saved.
+    pub fn load_policy_events(&self) -> Result<Vec<Event>, SyncError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT event_json FROM policy_events ORDER BY rowid")
+            .map_err(|e| SyncError::Storage(format!("{e}")))?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| SyncError::Storage(format!("{e}")))?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            let json = row.map_err(|e| SyncError::Storage(format!("{e}")))?;
+            let event: Event = serde_json::from_str(&json)
+                .map_err(|e| SyncError::Storage(format!("invalid policy event: {e}")))?;
+            result.push(event);
+        }
+        Ok(result)
+    }
+
+    // ── Policy file ──────────────────────────────────────────────
+
+    /// Records the serial and content digest of the last applied policy file.
+    pub fn save_policy_file_state(&self, serial: u64, digest: &str) -> Result<(), SyncError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO policy_file_state (id, serial, digest) VALUES (1, ?1, ?2)
+             ON CONFLICT(id) DO UPDATE SET serial = excluded.serial, digest = excluded.digest",
+            params![serial as i64, digest],
+        )
+        .map_err(|e| SyncError::Storage(format!("failed to save policy file state: {e}")))?;
+        Ok(())
+    }
+
+    /// Loads the serial and content digest of the last applied policy file.
+    pub fn load_policy_file_state(&self) -> Result<Option<(u64, String)>, SyncError> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT serial, digest FROM policy_file_state WHERE id = 1",
+            [],
+            |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, String>(1)?)),
+        )
+        .optional()
+        .map_err(|e| SyncError::Storage(format!("failed to read policy file state: {e}")))
+    }
+
     // ── Active devices ───────────────────────────────────────────
 
     /// Saves active devices for a peer.
@@ -504,6 +972,155 @@ impl PolicyStore {
     }
 }
 
+/// A raw `audit_log` row.
+struct AuditRow {
+    id: i64,
+    peer: String,
+    entity: Option<String>,
+    action: String,
+    decision: String,
+    detail: String,
+    timestamp: String,
+    prev_hash: Option<String>,
+    hash: Option<String>,
+}
+
+impl AuditRow {
+    fn from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            peer: row.get(1)?,
+            entity: row.get(2)?,
+            action: row.get(3)?,
+            decision: row.get(4)?,
+            detail: row.get(5)?,
+            timestamp: row.get(6)?,
+            prev_hash: row.get(7)?,
+            hash: row.get(8)?,
+        })
+    }
+
+    /// Hash of the stored column values, chained to the stored `prev_hash`.
+    fn computed_hash(&self) -> String {
+        chain_hash(
+            self.prev_hash.as_deref().unwrap_or_default(),
+            &self.peer,
+            self.entity.as_deref(),
+            &self.action,
+            &self.decision,
+            &self.detail,
+            &self.timestamp,
+        )
+    }
+
+    fn into_record(self) -> Result<AuditRecord, SyncError> {
+        let peer: PeerId = self
+            .peer
+            .parse()
+            .map_err(|e| SyncError::Storage(format!("invalid peer_id in audit: {e}")))?;
+        let entity: Option<EntityId> = match &self.entity {
+            Some(s) => Some(
+                s.parse()
+                    .map_err(|e| SyncError::Storage(format!("invalid entity_id in audit: {e}")))?,
+            ),
+            None => None,
+        };
+        let ts_millis: u64 = self.timestamp.parse().unwrap_or(0);
+        Ok(AuditRecord {
+            id: self.id,
+            entry: AuditEntry {
+                peer,
+                entity,
+                action: parse_audit_action(&self.action),
+                decision: parse_audit_decision(&self.decision),
+                detail: self.detail,
+                timestamp: std::time::UNIX_EPOCH + std::time::Duration::from_millis(ts_millis),
+            },
+            prev_hash: self.prev_hash.unwrap_or_default(),
+            hash: self.hash.unwrap_or_default(),
+        })
+    }
+}
+
+/// Returns the hash the next audit row must link to.
+fn audit_head(conn: &Connection) -> Result<String, SyncError> {
+    let last: Option<Option<String>> = conn
+        .query_row("SELECT hash FROM audit_log ORDER BY id DESC LIMIT 1", [], |row| row.get(0))
+        .optional()
+        .map_err(|e| SyncError::Storage(format!("failed to read audit head: {e}")))?;
+    match last {
+        Some(hash) => Ok(hash.unwrap_or_default()),
+        None => Ok(audit_anchor(conn)?.unwrap_or_else(|| AUDIT_GENESIS_HASH.to_string())),
+    }
+}
+
+/// Returns the hash of the last row removed by retention, if any.
+fn audit_anchor(conn: &Connection) -> Result<Option<String>, SyncError> {
+    conn.query_row("SELECT hash FROM audit_anchor WHERE id = 1", [], |row| row.get(0))
+        .optional()
+        .map_err(|e| SyncError::Storage(format!("failed to read audit anchor: {e}")))
+}
+
+fn window_from_row(not_before: Option<i64>, expires_at: Option<i64>) -> GrantWindow {
+    GrantWindow::new(not_before.map(|t| t as u64), expires_at.map(|t| t as u64))
+}
+
+/// Adds a column to a table created by an older schema version.
+/// Returns true if the column was added.
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    decl: &str,
+) -> Result<bool, SyncError> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+            params![table, column],
+            |row| row.get::<_, i64>(0),
+        )
+        .map(|n| n > 0)
+        .map_err(|e| SyncError::Storage(format!("failed to inspect {table} schema: {e}")))?;
+    if exists {
+        return Ok(false);
+    }
+    conn.execute(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl}"), [])
+        .map_err(|e| SyncError::Storage(format!("failed to migrate {table} schema: {e}")))?;
+    Ok(true)
+}
+
+/// Adds the hash columns to audit logs created before chaining and chains the
+/// existing rows in ID order. Runs only when the columns are missing, so
+/// clearing a hash later cannot get a row silently re-chained.
+fn migrate_audit_chain(conn: &Connection) -> Result<(), SyncError> {
+    add_column_if_missing(conn, "audit_log", "prev_hash", "TEXT")?;
+    if !add_column_if_missing(conn, "audit_log", "hash", "TEXT")? {
+        return Ok(());
+    }
+
+    let rows: Vec<AuditRow> = {
+        let mut stmt = conn
+            .prepare(&format!("SELECT {AUDIT_COLUMNS} FROM audit_log ORDER BY id ASC"))
+            .map_err(|e| SyncError::Storage(format!("failed to prepare audit query: {e}")))?;
+        stmt.query_map([], AuditRow::from_row)
+            .map_err(|e| SyncError::Storage(format!("failed to query audit log: {e}")))?
+            .collect::<Result<_, _>>()
+            .map_err(|e| SyncError::Storage(format!("failed to read audit row: {e}")))?
+    };
+    let mut prev_hash = AUDIT_GENESIS_HASH.to_string();
+    for mut row in rows {
+        row.prev_hash = Some(prev_hash);
+        let hash = row.computed_hash();
+        conn.execute(
+            "UPDATE audit_log SET prev_hash = ?1, hash = ?2 WHERE id = ?3",
+            params![row.prev_hash, hash, row.id],
+        )
+        .map_err(|e| SyncError::Storage(format!("failed to chain audit row: {e}")))?;
+        prev_hash = hash;
+    }
+    Ok(())
+}
+
 fn parse_audit_action(s: &str) -> AuditAction {
     match s {
         "handshake" => AuditAction::Handshake,
@@ -511,6 +1128,7 @@ fn parse_audit_action(s: &str) -> AuditAction {
         "event_send" => AuditAction::EventSend,
         "event_receive" => AuditAction::EventReceive,
         "device_register" => AuditAction::DeviceRegister,
+        "grant_expired" => AuditAction::GrantExpired,
         _ => AuditAction::Handshake, // fallback
     }
 }
@@ -11,14 +11,137 @@
 use privstack_crdt::VectorClock;
 use privstack_types::{EntityId, Event, EventId, PeerId};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
-/// Protocol version for compatibility checking.
+/// Highest protocol version this build speaks.
 pub const PROTOCOL_VERSION: u32 = 1;
 
+/// Lowest protocol version this build still accepts.
+///
+/// Raise this only once every supported client speaks the newer version;
+/// until then peers negotiate down to the highest version both sides share.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
 /// Maximum number of events to send in a single batch.
 pub const MAX_BATCH_SIZE: usize = 100;
 
+/// An inclusive range of protocol versions a peer can speak.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionRange {
+    /// Lowest supported version.
+    pub min: u32,
+    /// Highest supported version.
+    pub max: u32,
+}
+
+impl VersionRange {
+    /// Creates a range, swapping the bounds if they are reversed.
+    pub fn new(min: u32, max: u32) -> Self {
+        Self {
+            min: min.min(max),
+            max: min.max(max),
+        }
+    }
+
+    /// The range supported by this build.
+    pub fn supported() -> Self {
+        Self::new(MIN_PROTOCOL_VERSION, PROTOCOL_VERSION)
+    }
+
+    /// Whether `version` falls inside the range.
+    pub fn contains(&self, version: u32) -> bool {
+        (self.min..=self.max).contains(&version)
+    }
+
+    /// Returns the highest version both ranges contain, if any.
+    pub fn highest_common(&self, other: &VersionRange) -> Option<u32> {
+        let max = self.max.min(other.max);
+        (max >= self.min.max(other.min)).then_some(max)
+    }
+}
+
+impl Default for VersionRange {
+    fn default() -> Self {
+        Self::supported()
+    }
+}
+
+impl std::fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.min == self.max {
+            write!(f, "{}", self.min)
+        } else {
+            write!(f, "{}-{}", self.min, self.max)
+        }
+    }
+}
+
+/// Optional protocol features a peer may advertise during the handshake.
+///
+/// A feature is only used with a peer when both sides advertise it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Compressed event payloads.
+    Compression,
+    /// Binary message encoding instead of JSON.
+    BinaryCodec,
+    /// Bidirectional event-ID deltas (`known_event_ids` in requests and acks).
+    DeltaEvents,
+    /// Real-time `Subscribe` / `EventNotify` updates.
+    Subscriptions,
+    /// Set reconciliation instead of full event-ID exchange.
+    Reconciliation,
+    /// A capability introduced by a newer build. Never selected.
+    #[serde(other)]
+    Unknown,
+}
+
+impl Capability {
+    /// Capabilities implemented by this build.
+    pub const SUPPORTED: &'static [Capability] = &[Capability::DeltaEvents];
+
+    /// Capabilities of peers that predate negotiation: they always exchange
+    /// event-ID deltas.
+    pub const LEGACY: &'static [Capability] = &[Capability::DeltaEvents];
+}
+
+/// The protocol version and feature set agreed with a peer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NegotiatedProtocol {
+    /// Protocol version used with the peer.
+    pub version: u32,
+    /// Capabilities both sides support.
+    pub capabilities: BTreeSet<Capability>,
+}
+
+impl NegotiatedProtocol {
+    /// Picks the highest common version and the shared capability set.
+    /// Returns `None` when the version ranges do not overlap.
+    pub fn negotiate(
+        local_versions: &VersionRange,
+        local_capabilities: &[Capability],
+        remote_versions: &VersionRange,
+        remote_capabilities: &[Capability],
+    ) -> Option<Self> {
+        let version = local_versions.highest_common(remote_versions)?;
+        let capabilities = local_capabilities
+            .iter()
+            .filter(|c| **c != Capability::Unknown && remote_capabilities.contains(c))
+            .copied()
+            .collect();
+        Some(Self {
+            version,
+            capabilities,
+        })
+    }
+
+    /// Whether a capability was agreed with the peer.
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+}
+
 /// A sync protocol message.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SyncMessage {
@@ -59,8 +182,15 @@ pub enum SyncMessage {
 /// Initial handshake message.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HelloMessage {
-    /// Protocol version.
+    /// Lowest protocol version the sender speaks. Peers that predate
+    /// negotiation compare this against their single supported version.
     pub version: u32,
+    /// Highest protocol version the sender speaks (absent from legacy peers).
+    #[serde(default)]
+    pub max_version: Option<u32>,
+    /// Optional features the sender supports.
+    #[serde(default)]
+    pub capabilities: Vec<Capability>,
     /// Sender's peer ID.
     pub peer_id: PeerId,
     /// Human-readable device name.
@@ -70,17 +200,46 @@ pub struct HelloMessage {
     /// Optional device identifier for device-limit enforcement.
     #[serde(default)]
     pub device_id: Option<String>,
+    /// Base64 identity key the sender signs its events with. Registered as
+    /// the sender's author key only if the sender is a paired device.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_key: Option<String>,
 }
 
 impl HelloMessage {
     /// Creates a new Hello message.
     pub fn new(peer_id: PeerId, device_name: impl Into<String>) -> Self {
         Self {
-            version: PROTOCOL_VERSION,
+            version: MIN_PROTOCOL_VERSION,
+            max_version: Some(PROTOCOL_VERSION),
+            capabilities: Capability::SUPPORTED.to_vec(),
             peer_id,
             device_name: device_name.into(),
             entity_ids: Vec::new(),
             device_id: None,
+            identity_key: None,
+        }
+    }
+
+    /// Advertises a specific version range and capability set.
+    pub fn with_protocol(mut self, versions: VersionRange, capabilities: Vec<Capability>) -> Self {
+        self.version = versions.min;
+        self.max_version = Some(versions.max);
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Returns the version range the sender speaks.
+    pub fn version_range(&self) -> VersionRange {
+        VersionRange::new(self.version, self.max_version.unwrap_or(self.version))
+    }
+
+    /// Returns the capabilities the sender supports; a legacy sender has
+    /// the [`Capability::LEGACY`] set.
+    pub fn advertised_capabilities(&self) -> &[Capability] {
+        match self.max_version {
+            Some(_) => &self.capabilities,
+            None => Capability::LEGACY,
         }
     }
 
@@ -95,13 +254,25 @@ impl HelloMessage {
         self.device_id = Some(device_id.into());
         self
     }
+
+    /// Advertises the sender's event-signing identity key.
+    pub fn with_identity_key(mut self, key: Option<String>) -> Self {
+        self.identity_key = key;
+        self
+    }
 }
 
 /// Response to Hello message.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HelloAckMessage {
-    /// Protocol version.
+    /// Protocol version selected for this session.
     pub version: u32,
+    /// Full version range the responder speaks (absent from legacy peers).
+    #[serde(default)]
+    pub supported_versions: Option<VersionRange>,
+    /// Capabilities selected for this session.
+    #[serde(default)]
+    pub capabilities: Vec<Capability>,
     /// Responder's peer ID.
     pub peer_id: PeerId,
     /// Human-readable device name.
@@ -110,6 +281,10 @@ pub struct HelloAckMessage {
     pub accepted: bool,
     /// Reason if not accepted.
     pub reason: Option<String>,
+    /// Base64 identity key the responder signs its events with (see
+    /// [`HelloMessage::identity_key`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_key: Option<String>,
 }
 
 impl HelloAckMessage {
@@ -117,10 +292,13 @@ impl HelloAckMessage {
     pub fn accept(peer_id: PeerId, device_name: impl Into<String>) -> Self {
         Self {
             version: PROTOCOL_VERSION,
+            supported_versions: Some(VersionRange::supported()),
+            capabilities: Capability::SUPPORTED.to_vec(),
             peer_id,
             device_name: device_name.into(),
             accepted: true,
             reason: None,
+            identity_key: None,
         }
     }
 
@@ -128,10 +306,47 @@ impl HelloAckMessage {
     pub fn reject(peer_id: PeerId, reason: impl Into<String>) -> Self {
         Self {
             version: PROTOCOL_VERSION,
+            supported_versions: Some(VersionRange::supported()),
+            capabilities: Vec::new(),
             peer_id,
             device_name: String::new(),
             accepted: false,
             reason: Some(reason.into()),
+            identity_key: None,
+        }
+    }
+
+    /// Records the negotiated version and capabilities, plus the responder's
+    /// full version range.
+    pub fn with_protocol(
+        mut self,
+        supported: VersionRange,
+        negotiated: &NegotiatedProtocol,
+    ) -> Self {
+        self.version = negotiated.version;
+        self.supported_versions = Some(supported);
+        self.capabilities = negotiated.capabilities.iter().copied().collect();
+        self
+    }
+
+    /// Advertises the responder's event-signing identity key.
+    pub fn with_identity_key(mut self, key: Option<String>) -> Self {
+        self.identity_key = key;
+        self
+    }
+
+    /// Returns the version range the responder speaks.
+    pub fn version_range(&self) -> VersionRange {
+        self.supported_versions
+            .unwrap_or_else(|| VersionRange::new(self.version, self.version))
+    }
+
+    /// Returns the capabilities selected for the session; a legacy
+    /// responder has the [`Capability::LEGACY`] set.
+    pub fn advertised_capabilities(&self) -> &[Capability] {
+        match self.supported_versions {
+            Some(_) => &self.capabilities,
+            None => Capability::LEGACY,
         }
     }
 }
@@ -275,6 +490,14 @@ impl ErrorMessage {
         )
     }
 
+    /// No protocol version in common with the peer.
+    pub fn no_common_version(ours: &VersionRange, theirs: &VersionRange) -> Self {
+        Self::new(
+            1,
+            format!("protocol version mismatch: we support {ours}, peer supports {theirs}"),
+        )
+    }
+
     /// Unknown document error.
     pub fn unknown_entity(id: &EntityId) -> Self {
         Self::new(2, format!("unknown document: {id}"))
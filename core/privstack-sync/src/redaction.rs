@@ -0,0 +1,233 @@
+//! Field-level redaction of outgoing entity events.
+//!
+//! Schemas label sensitive fields (`IndexedField::with_sensitivity`) and
+//! `RedactionRules` map a sync role to the labels and JSON pointers it may not
+//! see. `EnterpriseSyncPolicy::on_event_send` strips those paths from entity
+//! payloads before they leave the device and lists them in
+//! `Event::redacted_paths`; the receiving applicator keeps its local values at
+//! those paths, so a redacted copy relayed back to a full-access peer never
+//! erases data.
+//!
+//! A redacted copy no longer matches its author's signature. When the policy
+//! has a redaction signer (`EnterpriseSyncPolicy::with_redaction_signer`) the
+//! sending peer seals the copy instead (`Event::redaction_seal`), binding the
+//! redacted payload to the original event ID; receivers verify the seal
+//! against the sender's registered identity key and attribute the copy to the
+//! sender. Unsealed copies are unsigned and rejected under
+//! `SignatureMode::Required`. Event stores never let a redacted copy replace
+//! the original event.
+//!
+//! Only hide fields from roles that cannot write (`Viewer`): a writer that
+//! never saw a field sends documents without it, which whole-document merges
+//! treat as a deletion.
+
+use crate::policy::SyncRole;
+use privstack_types::{Event, EventPayload};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Which sensitivity labels and JSON pointers each role may not see.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RedactionRules {
+    /// role → sensitivity labels hidden from it.
+    labels: HashMap<SyncRole, HashSet<String>>,
+    /// role → entity type → JSON pointers hidden from it.
+    paths: HashMap<SyncRole, HashMap<String, HashSet<String>>>,
+}
+
+impl RedactionRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hides every field labelled `label` (in any entity type) from `role`.
+    pub fn hide_label(mut self, role: SyncRole, label: &str) -> Self {
+        self.labels.entry(role).or_default().insert(label.to_string());
+        self
+    }
+
+    /// Hides the JSON pointer `pointer` of entities of `entity_type` from `role`.
+    pub fn hide_path(mut self, role: SyncRole, entity_type: &str, pointer: &str) -> Self {
+        self.paths
+            .entry(role)
+            .or_default()
+            .entry(entity_type.to_string())
+            .or_default()
+            .insert(pointer.to_string());
+        self
+    }
+
+    /// Returns true if no rule hides anything.
+    pub fn is_empty(&self) -> bool {
+        self.labels.values().all(HashSet::is_empty)
+            && self
+                .paths
+                .values()
+                .flat_map(HashMap::values)
+                .all(HashSet::is_empty)
+    }
+
+    /// Roles that have at least one rule.
+    pub fn roles(&self) -> impl Iterator<Item = SyncRole> + '_ {
+        self.labels
+            .keys()
+            .chain(self.paths.keys())
+            .copied()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+    }
+
+    /// Returns the JSON pointers hidden from `role` in entities of
+    /// `entity_type`, given that type's `(field_path, label)` sensitive fields.
+    /// Sorted and deduplicated.
+    pub fn hidden_paths(
+        &self,
+        role: SyncRole,
+        entity_type: &str,
+        sensitive_fields: &[(String, String)],
+    ) -> Vec<String> {
+        let mut hidden = BTreeSet::new();
+        if let Some(labels) = self.labels.get(&role) {
+            hidden.extend(
+                sensitive_fields
+                    .iter()
+                    .filter(|(_, label)| labels.contains(label))
+                    .map(|(path, _)| path.clone()),
+            );
+        }
+        if let Some(paths) = self.paths.get(&role).and_then(|p| p.get(entity_type)) {
+            hidden.extend(paths.iter().cloned());
+        }
+        hidden.into_iter().collect()
+    }
+}
+
+/// Returns the entity type of an entity payload (`None` for ACL/team events).
+pub(crate) fn payload_entity_type(payload: &EventPayload) -> Option<&str> {
+    match payload {
+        EventPayload::EntityCreated { entity_type, .. }
+        | EventPayload::EntityUpdated { entity_type, .. }
+        | EventPayload::FullSnapshot { entity_type, .. } => Some(entity_type.as_str()),
+        _ => None,
+    }
+}
+
+/// Returns a copy of `event` with `paths` removed from its entity payload.
+///
+/// Only paths actually present are removed and recorded in `redacted_paths`.
+/// If nothing is removed the event is returned unchanged, signature included.
+/// Otherwise the copy is returned without a signature or seal; seal it with
+/// `IdentityKeyPair::seal_redaction` before sending.
+pub fn redact_event(event: &Event, paths: &[String]) -> Event {
+    if paths.is_empty() {
+        return event.clone();
+    }
+    let (entity_type, json_data) = match &event.payload {
+        EventPayload::EntityCreated { entity_type, json_data }
+        | EventPayload::EntityUpdated { entity_type, json_data }
+        | EventPayload::FullSnapshot { entity_type, json_data } => (entity_type, json_data),
+        _ => return event.clone(),
+    };
+    let Ok(mut data) = serde_json::from_str::<Value>(json_data) else {
+        return event.clone();
+    };
+
+    let removed: Vec<String> = paths
+        .iter()
+        .filter(|path| remove_pointer(&mut data, path))
+        .cloned()
+        .collect();
+    if removed.is_empty() {
+        return event.clone();
+    }
+
+    let json_data = data.to_string();
+    let entity_type = entity_type.clone();
+    let mut redacted = event.clone();
+    redacted.payload = match &event.payload {
+        EventPayload::EntityCreated { .. } => EventPayload::EntityCreated { entity_type, json_data },
+        EventPayload::EntityUpdated { .. } => EventPayload::EntityUpdated { entity_type, json_data },
+        _ => EventPayload::FullSnapshot { entity_type, json_data },
+    };
+    redacted.signature = None;
+    redacted.redaction_seal = None;
+    redacted.redacted_paths.extend(removed);
+    redacted.redacted_paths.sort();
+    redacted.redacted_paths.dedup();
+    redacted
+}
+
+/// Copies the values at `paths` from `local` into `merged`, undoing the
+/// removal of fields a redacted event never carried.
+pub fn restore_redacted(local: &Value, merged: &mut Value, paths: &[String]) {
+    for path in paths {
+        if let Some(value) = local.pointer(path) {
+            set_pointer(merged, path, value.clone());
+        }
+    }
+}
+
+/// Splits a JSON pointer into unescaped reference tokens (RFC 6901).
+fn pointer_tokens(pointer: &str) -> Option<Vec<String>> {
+    let rest = pointer.strip_prefix('/')?;
+    Some(
+        rest.split('/')
+            .map(|t| t.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+/// Removes the object member at `pointer`. Array elements are left alone,
+/// since removing one would shift every later index.
+fn remove_pointer(value: &mut Value, pointer: &str) -> bool {
+    let Some(mut tokens) = pointer_tokens(pointer) else {
+        return false;
+    };
+    let Some(last) = tokens.pop() else {
+        return false;
+    };
+    let mut parent = value;
+    for token in &tokens {
+        parent = match parent {
+            Value::Object(map) => match map.get_mut(token) {
+                Some(child) => child,
+                None => return false,
+            },
+            Value::Array(items) => match token.parse::<usize>().ok().and_then(|i| items.get_mut(i)) {
+                Some(child) => child,
+                None => return false,
+            },
+            _ => return false,
+        };
+    }
+    match parent {
+        Value::Object(map) => map.remove(&last).is_some(),
+        _ => false,
+    }
+}
+
+/// Sets the object member at `pointer`, creating missing parent objects.
+fn set_pointer(value: &mut Value, pointer: &str, new_value: Value) {
+    let Some(mut tokens) = pointer_tokens(pointer) else {
+        return;
+    };
+    let Some(last) = tokens.pop() else {
+        return;
+    };
+    let mut parent = value;
+    for token in tokens {
+        parent = match parent {
+            Value::Object(map) => map
+                .entry(token)
+                .or_insert_with(|| Value::Object(Default::default())),
+            Value::Array(items) => match token.parse::<usize>().ok().and_then(|i| items.get_mut(i)) {
+                Some(child) => child,
+                None => return,
+            },
+            _ => return,
+        };
+    }
+    if let Value::Object(map) = parent {
+        map.insert(last, new_value);
+    }
+}
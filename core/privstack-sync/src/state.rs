@@ -4,6 +4,7 @@
 //! enabling efficient delta sync. Uses a monotonic event counter per peer
 //! (not HybridTimestamp logical clocks, which are a different concept).
 
+use crate::protocol::NegotiatedProtocol;
 use privstack_crdt::VectorClock;
 use privstack_types::{EntityId, Event, EventId, HybridTimestamp, PeerId};
 use serde::{Deserialize, Serialize};
@@ -144,6 +145,9 @@ pub struct PeerSyncStatus {
     pub connected: bool,
     /// Last successful sync timestamp.
     pub last_sync: Option<HybridTimestamp>,
+    /// Protocol version and capabilities agreed in the last handshake.
+    #[serde(default)]
+    pub protocol: Option<NegotiatedProtocol>,
 }
 
 impl PeerSyncStatus {
@@ -155,6 +159,7 @@ impl PeerSyncStatus {
             shared_entities: Vec::new(),
             connected: false,
             last_sync: None,
+            protocol: None,
         }
     }
 }
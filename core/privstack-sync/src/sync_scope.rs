@@ -0,0 +1,201 @@
+//! Rule-based sync scopes for personal per-peer filtering.
+//!
+//! A `SyncScope` narrows which entities flow to a given peer based on entity
+//! metadata instead of explicit per-entity share lists:
+//! - only sync certain entity types ("only `task` and `note` to my phone")
+//! - never sync entities carrying certain tags ("#private stays off the work laptop")
+//! - never sync entity types owned by a given plugin
+//!
+//! Scopes are evaluated by `PersonalSyncPolicy` on top of explicit sharing:
+//! an entity must pass both to reach the peer.
+
+use privstack_model::Entity;
+use privstack_types::{Event, EventPayload};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// JSON pointer where entities conventionally keep their tag array.
+pub const DEFAULT_TAG_POINTER: &str = "/tags";
+
+/// Per-peer sync scope. An empty scope allows everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncScope {
+    /// If non-empty, only these entity types are synced to the peer.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub include_types: BTreeSet<String>,
+    /// Entity types that are never synced to the peer.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub exclude_types: BTreeSet<String>,
+    /// Entities carrying any of these tags are never synced to the peer.
+    /// Compared case-insensitively, with a leading `#` ignored; stored
+    /// normalized, including when deserialized.
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeSet::is_empty",
+        deserialize_with = "deserialize_tags"
+    )]
+    pub exclude_tags: BTreeSet<String>,
+    /// Plugins whose entity types are never synced to the peer.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub exclude_plugins: BTreeSet<String>,
+}
+
+impl SyncScope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn include_type(mut self, entity_type: impl Into<String>) -> Self {
+        self.include_types.insert(entity_type.into());
+        self
+    }
+
+    pub fn exclude_type(mut self, entity_type: impl Into<String>) -> Self {
+        self.exclude_types.insert(entity_type.into());
+        self
+    }
+
+    pub fn exclude_tag(mut self, tag: &str) -> Self {
+        self.exclude_tags.insert(normalize_tag(tag));
+        self
+    }
+
+    pub fn exclude_plugin(mut self, plugin_id: impl Into<String>) -> Self {
+        self.exclude_plugins.insert(plugin_id.into());
+        self
+    }
+
+    /// Returns true if the scope has no rules (everything is allowed).
+    pub fn is_empty(&self) -> bool {
+        self.include_types.is_empty()
+            && self.exclude_types.is_empty()
+            && self.exclude_tags.is_empty()
+            && self.exclude_plugins.is_empty()
+    }
+
+    /// Evaluates the scope against an entity's metadata.
+    /// `plugin_types` maps entity type → owning plugin ID.
+    pub fn allows(&self, meta: &EntityMeta, plugin_types: &HashMap<String, String>) -> bool {
+        if !self.include_types.is_empty() && !self.include_types.contains(&meta.entity_type) {
+            return false;
+        }
+        if self.exclude_types.contains(&meta.entity_type) {
+            return false;
+        }
+        if !self.exclude_plugins.is_empty() {
+            if let Some(plugin_id) = plugin_types.get(&meta.entity_type) {
+                if self.exclude_plugins.contains(plugin_id) {
+                    return false;
+                }
+            }
+        }
+        if !self.exclude_tags.is_empty()
+            && meta
+                .tags
+                .iter()
+                .any(|t| self.exclude_tags.contains(&normalize_tag(t)))
+        {
+            return false;
+        }
+        true
+    }
+}
+
+/// Entity metadata that sync scopes are evaluated against.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntityMeta {
+    pub entity_type: String,
+    pub tags: Vec<String>,
+}
+
+impl EntityMeta {
+    /// Builds metadata from a stored entity.
+    pub fn from_entity(entity: &Entity) -> Self {
+        Self {
+            entity_type: entity.entity_type.clone(),
+            tags: tags_from_value(&entity.data),
+        }
+    }
+
+    /// Builds metadata from an entity event. Returns `None` for ACL and team
+    /// events, which carry no entity data and are never scope-filtered.
+    pub fn from_event(event: &Event) -> Option<Self> {
+        match &event.payload {
+            EventPayload::EntityCreated { entity_type, json_data }
+            | EventPayload::EntityUpdated { entity_type, json_data }
+            | EventPayload::FullSnapshot { entity_type, json_data } => {
+                let tags = serde_json::from_str::<serde_json::Value>(json_data)
+                    .map(|v| tags_from_value(&v))
+                    .unwrap_or_default();
+                Some(Self {
+                    entity_type: entity_type.clone(),
+                    tags,
+                })
+            }
+            EventPayload::EntityDeleted { entity_type } => Some(Self {
+                entity_type: entity_type.clone(),
+                tags: Vec::new(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns true if any of the events carries entity data. Deletes carry
+    /// no tags, so a delete-only batch says nothing about them.
+    pub fn carries_data(events: &[Event]) -> bool {
+        events.iter().any(|e| {
+            matches!(
+                e.payload,
+                EventPayload::EntityCreated { .. }
+                    | EventPayload::EntityUpdated { .. }
+                    | EventPayload::FullSnapshot { .. }
+            )
+        })
+    }
+
+    /// Derives metadata for an entity from a set of its events, using the
+    /// newest event that carries entity data. Falls back to the newest
+    /// event's type (without tags) when only deletes are present.
+    pub fn from_events(events: &[Event]) -> Option<Self> {
+        let newest_with_data = events
+            .iter()
+            .filter(|e| {
+                matches!(
+                    e.payload,
+                    EventPayload::EntityCreated { .. }
+                        | EventPayload::EntityUpdated { .. }
+                        | EventPayload::FullSnapshot { .. }
+                )
+            })
+            .max_by_key(|e| e.timestamp);
+        match newest_with_data {
+            Some(event) => Self::from_event(event),
+            None => events
+                .iter()
+                .max_by_key(|e| e.timestamp)
+                .and_then(Self::from_event),
+        }
+    }
+}
+
+fn tags_from_value(data: &serde_json::Value) -> Vec<String> {
+    data.pointer(DEFAULT_TAG_POINTER)
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|t| t.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+fn deserialize_tags<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeSet<String>, D::Error> {
+    let tags = BTreeSet::<String>::deserialize(deserializer)?;
+    Ok(tags.iter().map(|t| normalize_tag(t)).collect())
+}
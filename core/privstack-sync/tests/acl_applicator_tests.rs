@@ -1,7 +1,7 @@
 //! Tests for acl_applicator.rs — ACL event handling and helper functions.
 
 use privstack_sync::acl_applicator::{is_acl_event, AclApplicator};
-use privstack_sync::policy::{EnterpriseSyncPolicy, SyncRole, TeamId};
+use privstack_sync::policy::{EnterpriseSyncPolicy, GrantWindow, SyncRole, TeamId};
 use privstack_sync::AclEventHandler;
 use privstack_types::{EntityId, Event, EventPayload, HybridTimestamp, PeerId};
 use std::sync::Arc;
@@ -18,6 +18,8 @@ fn is_acl_event_grant_peer() {
         entity_id: EntityId::new().to_string(),
         peer_id: PeerId::new().to_string(),
         role: "Editor".to_string(),
+        not_before: None,
+        expires_at: None,
     };
     assert!(is_acl_event(&payload));
 }
@@ -37,6 +39,8 @@ fn is_acl_event_grant_team() {
         entity_id: EntityId::new().to_string(),
         team_id: uuid::Uuid::new_v4().to_string(),
         role: "Viewer".to_string(),
+        not_before: None,
+        expires_at: None,
     };
     assert!(is_acl_event(&payload));
 }
@@ -73,6 +77,7 @@ fn is_acl_event_team_remove_peer() {
     let payload = EventPayload::TeamRemovePeer {
         team_id: uuid::Uuid::new_v4().to_string(),
         peer_id: PeerId::new().to_string(),
+        observed: Vec::new(),
     };
     assert!(is_acl_event(&payload));
 }
@@ -127,6 +132,8 @@ async fn handle_acl_grant_peer() {
             entity_id: entity.to_string(),
             peer_id: peer.to_string(),
             role: "Editor".to_string(),
+            not_before: None,
+            expires_at: None,
         },
     );
 
@@ -177,6 +184,8 @@ async fn handle_acl_grant_team() {
             entity_id: entity.to_string(),
             team_id: team.0.to_string(),
             role: "Admin".to_string(),
+            not_before: None,
+            expires_at: None,
         },
     );
 
@@ -188,6 +197,50 @@ async fn handle_acl_grant_team() {
     assert_eq!(acl.team_roles.get(&team), Some(&SyncRole::Admin));
 }
 
+#[tokio::test]
+async fn handle_acl_grant_with_window() {
+    let policy = Arc::new(EnterpriseSyncPolicy::new());
+    let applicator = AclApplicator::new(policy.clone());
+
+    let entity = EntityId::new();
+    let peer = PeerId::new();
+    let team = TeamId::new();
+    policy.add_team_member(team, peer).await;
+
+    let peer_grant = make_acl_event(
+        entity,
+        EventPayload::AclGrantPeer {
+            entity_id: entity.to_string(),
+            peer_id: peer.to_string(),
+            role: "Editor".to_string(),
+            not_before: None,
+            expires_at: Some(2_000),
+        },
+    );
+    let team_grant = make_acl_event(
+        entity,
+        EventPayload::AclGrantTeam {
+            entity_id: entity.to_string(),
+            team_id: team.0.to_string(),
+            role: "Viewer".to_string(),
+            not_before: Some(1_500),
+            expires_at: None,
+        },
+    );
+    assert!(applicator.handle_acl_event(&peer_grant).await.unwrap());
+    assert!(applicator.handle_acl_event(&team_grant).await.unwrap());
+
+    {
+        let acls = policy.acls.read().await;
+        let acl = acls.get(&entity).unwrap();
+        assert_eq!(acl.peer_windows.get(&peer), Some(&GrantWindow::until(2_000)));
+        assert_eq!(acl.team_windows.get(&team), Some(&GrantWindow::new(Some(1_500), None)));
+    }
+    assert_eq!(policy.resolve_role_at(&peer, &entity, 1_000).await, Some(SyncRole::Editor));
+    // Peer grant lapsed, team grant now in effect.
+    assert_eq!(policy.resolve_role_at(&peer, &entity, 2_000).await, Some(SyncRole::Viewer));
+}
+
 #[tokio::test]
 async fn handle_acl_revoke_team() {
     let policy = Arc::new(EnterpriseSyncPolicy::new());
@@ -323,6 +376,7 @@ async fn handle_team_remove_peer() {
         EventPayload::TeamRemovePeer {
             team_id: team.0.to_string(),
             peer_id: peer.to_string(),
+            observed: Vec::new(),
         },
     );
 
@@ -366,6 +420,8 @@ async fn invalid_role_string_returns_error() {
             entity_id: entity.to_string(),
             peer_id: PeerId::new().to_string(),
             role: "SuperUser".to_string(),
+            not_before: None,
+            expires_at: None,
         },
     );
 
@@ -387,6 +443,8 @@ async fn invalid_team_id_returns_error() {
             entity_id: entity.to_string(),
             team_id: "not-a-uuid".to_string(),
             role: "Editor".to_string(),
+            not_before: None,
+            expires_at: None,
         },
     );
 
@@ -423,6 +481,8 @@ async fn invalid_peer_id_in_grant_returns_error() {
             entity_id: entity.to_string(),
             peer_id: "not-a-uuid".to_string(),
             role: "Editor".to_string(),
+            not_before: None,
+            expires_at: None,
         },
     );
 
@@ -441,6 +501,8 @@ async fn invalid_entity_id_in_grant_returns_error() {
             entity_id: "not-a-uuid".to_string(),
             peer_id: PeerId::new().to_string(),
             role: "Editor".to_string(),
+            not_before: None,
+            expires_at: None,
         },
     );
 
@@ -463,6 +525,8 @@ async fn handle_acl_grant_peer_owner_role() {
             entity_id: entity.to_string(),
             peer_id: peer.to_string(),
             role: "Owner".to_string(),
+            not_before: None,
+            expires_at: None,
         },
     );
 
@@ -485,6 +549,8 @@ async fn handle_acl_grant_peer_viewer_role() {
             entity_id: entity.to_string(),
             peer_id: peer.to_string(),
             role: "Viewer".to_string(),
+            not_before: None,
+            expires_at: None,
         },
     );
 
@@ -542,6 +608,8 @@ async fn invalid_entity_id_in_grant_team_returns_error() {
             entity_id: "not-a-uuid".to_string(),
             team_id: uuid::Uuid::new_v4().to_string(),
             role: "Editor".to_string(),
+            not_before: None,
+            expires_at: None,
         },
     );
 
@@ -594,6 +662,7 @@ async fn invalid_team_id_in_team_remove_peer_returns_error() {
         EventPayload::TeamRemovePeer {
             team_id: "bad-uuid".to_string(),
             peer_id: PeerId::new().to_string(),
+            observed: Vec::new(),
         },
     );
 
@@ -628,6 +697,7 @@ async fn invalid_peer_id_in_team_remove_peer_returns_error() {
         EventPayload::TeamRemovePeer {
             team_id: uuid::Uuid::new_v4().to_string(),
             peer_id: "bad-uuid".to_string(),
+            observed: Vec::new(),
         },
     );
 
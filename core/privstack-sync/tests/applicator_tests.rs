@@ -1,6 +1,8 @@
 use privstack_model::{Entity, EntitySchema, MergeStrategy, PluginDomainHandler};
 use privstack_storage::EntityStore;
-use privstack_sync::applicator::EventApplicator;
+use privstack_sync::applicator::{DomainRegistry, EventApplicator};
+use std::collections::HashMap;
+use std::sync::Arc;
 use privstack_types::{EntityId, Event, EventPayload, HybridTimestamp, PeerId};
 use serde_json::json;
 
@@ -430,6 +432,78 @@ fn merge_no_schema_defaults_to_lww_document() {
     assert_eq!(merged.data["v"], "remote");
 }
 
+// ── DomainRegistry ───────────────────────────────────────────────
+
+struct AppendHandler;
+
+impl PluginDomainHandler for AppendHandler {
+    fn merge(&self, local: &Entity, remote: &Entity) -> Entity {
+        let mut merged = local.clone();
+        merged.data["log"] = json!(format!("{}+{}", local.data["log"], remote.data["log"]));
+        merged
+    }
+}
+
+#[test]
+fn apply_with_registry_uses_custom_handler() {
+    let store = make_store();
+    let applicator = EventApplicator::new(PeerId::new());
+    let eid = EntityId::new();
+    let peer = PeerId::new();
+
+    let mut schemas = HashMap::new();
+    schemas.insert("log".to_string(), make_schema("log", MergeStrategy::Custom));
+    let mut handlers: HashMap<String, Arc<dyn PluginDomainHandler>> = HashMap::new();
+    handlers.insert("log".to_string(), Arc::new(AppendHandler));
+    let registry = DomainRegistry::new(schemas, handlers);
+
+    let create = make_create_event(eid, peer, "log", r#"{"log":"a"}"#);
+    applicator.apply_event_with_registry(&create, &store, Some(&registry)).unwrap();
+    let update = make_update_event(eid, peer, "log", r#"{"log":"b"}"#);
+    applicator.apply_event_with_registry(&update, &store, Some(&registry)).unwrap();
+
+    let entity = store.get_entity(&eid.to_string()).unwrap().unwrap();
+    assert_eq!(entity.data["log"], r#""a"+"b""#);
+}
+
+#[test]
+fn apply_without_registry_entry_uses_lww() {
+    let store = make_store();
+    let applicator = EventApplicator::new(PeerId::new());
+    let eid = EntityId::new();
+    let peer = PeerId::new();
+    let registry = DomainRegistry::default();
+
+    let create = make_create_event(eid, peer, "log", r#"{"log":"a"}"#);
+    applicator.apply_event_with_registry(&create, &store, Some(&registry)).unwrap();
+    let update = make_update_event(eid, peer, "log", r#"{"log":"b"}"#);
+    applicator.apply_event_with_registry(&update, &store, Some(&registry)).unwrap();
+
+    let entity = store.get_entity(&eid.to_string()).unwrap().unwrap();
+    assert_eq!(entity.data["log"], "b");
+}
+
+#[test]
+fn registry_changes_apply_to_later_events() {
+    let store = make_store();
+    let applicator = EventApplicator::new(PeerId::new());
+    let eid = EntityId::new();
+    let peer = PeerId::new();
+    let registry = Arc::new(DomainRegistry::default());
+    let shared = Arc::clone(&registry);
+
+    let create = make_create_event(eid, peer, "log", r#"{"log":"a"}"#);
+    applicator.apply_event_with_registry(&create, &store, Some(&shared)).unwrap();
+
+    registry.register_schema(make_schema("log", MergeStrategy::Custom));
+    registry.register_handler("log".to_string(), Arc::new(AppendHandler));
+    let update = make_update_event(eid, peer, "log", r#"{"log":"b"}"#);
+    applicator.apply_event_with_registry(&update, &store, Some(&shared)).unwrap();
+
+    let entity = store.get_entity(&eid.to_string()).unwrap().unwrap();
+    assert_eq!(entity.data["log"], r#""a"+"b""#);
+}
+
 // ── create_event helper ──────────────────────────────────────────
 
 #[test]
@@ -1,6 +1,9 @@
+use privstack_crypto::IdentityKeyPair;
 use privstack_storage::{EntityStore, EventStore};
+use privstack_sync::policy::event_author;
 use privstack_sync::protocol::{
-    EventBatchMessage, HelloMessage, SyncMessage, SyncRequestMessage, PROTOCOL_VERSION,
+    Capability, EventBatchMessage, HelloAckMessage, HelloMessage, SyncMessage,
+    SyncRequestMessage, VersionRange, PROTOCOL_VERSION,
 };
 use privstack_sync::{SyncConfig, SyncEngine};
 use privstack_types::{EntityId, Event, EventPayload, HybridTimestamp, PeerId};
@@ -18,6 +21,7 @@ fn make_engine_with_config(peer_id: PeerId, device_name: &str, batch_size: usize
             device_name: device_name.to_string(),
             batch_size,
             timeout_ms: 5000,
+            ..Default::default()
         },
     )
 }
@@ -110,11 +114,28 @@ async fn make_hello_accept() {
             assert!(ack.accepted);
             assert_eq!(ack.peer_id, peer_id);
             assert_eq!(ack.device_name, "PrivStack Device");
+            assert_eq!(ack.identity_key, None);
         }
         _ => panic!("Expected HelloAck"),
     }
 }
 
+#[tokio::test]
+async fn handshake_advertises_identity_key() {
+    let mut engine = make_engine(PeerId::new());
+    let key = IdentityKeyPair::generate().public_key();
+    engine.set_identity_key(key);
+
+    match engine.make_hello(vec![]) {
+        SyncMessage::Hello(hello) => assert_eq!(hello.identity_key, Some(key.to_base64())),
+        _ => panic!("Expected Hello"),
+    }
+    match engine.make_hello_accept() {
+        SyncMessage::HelloAck(ack) => assert_eq!(ack.identity_key, Some(key.to_base64())),
+        _ => panic!("Expected HelloAck"),
+    }
+}
+
 #[tokio::test]
 async fn make_hello_reject() {
     let engine = make_engine(PeerId::new());
@@ -135,7 +156,7 @@ async fn make_sync_request() {
     let e2 = EntityId::new();
 
     let (_entity_store, event_store) = make_stores();
-    let msg = engine.make_sync_request(vec![e1, e2], &event_store).await;
+    let msg = engine.make_sync_request(&PeerId::new(), vec![e1, e2], &event_store).await;
     match msg {
         SyncMessage::SyncRequest(r) => {
             assert_eq!(r.entity_ids.len(), 2);
@@ -188,6 +209,99 @@ async fn handle_hello_rejects_wrong_version() {
     }
 }
 
+#[tokio::test]
+async fn handle_hello_negotiates_down_to_common_version() {
+    let engine = SyncEngine::new(
+        PeerId::new(),
+        SyncConfig {
+            protocol_versions: VersionRange::new(1, 2),
+            capabilities: vec![Capability::DeltaEvents, Capability::Compression],
+            ..Default::default()
+        },
+    );
+    let remote = PeerId::new();
+    let hello = HelloMessage::new(remote, "Newer").with_protocol(
+        VersionRange::new(1, 4),
+        vec![Capability::Compression, Capability::Reconciliation],
+    );
+
+    match engine.handle_hello(&hello).await {
+        SyncMessage::HelloAck(ack) => {
+            assert!(ack.accepted);
+            assert_eq!(ack.version, 2);
+            assert_eq!(ack.supported_versions, Some(VersionRange::new(1, 2)));
+            assert_eq!(ack.capabilities, vec![Capability::Compression]);
+        }
+        _ => panic!("Expected HelloAck"),
+    }
+
+    let protocol = engine.peer_protocol(&remote).await.unwrap();
+    assert_eq!(protocol.version, 2);
+    assert!(protocol.supports(Capability::Compression));
+    assert!(!protocol.supports(Capability::DeltaEvents));
+}
+
+#[tokio::test]
+async fn handle_hello_ack_records_negotiated_protocol() {
+    let engine = make_engine(PeerId::new());
+    let remote = PeerId::new();
+    let responder = make_engine(remote);
+
+    let hello = match engine.make_hello(vec![]) {
+        SyncMessage::Hello(h) => h,
+        _ => panic!("Expected Hello"),
+    };
+    let ack = match responder.handle_hello(&hello).await {
+        SyncMessage::HelloAck(a) => a,
+        _ => panic!("Expected HelloAck"),
+    };
+
+    let negotiated = engine.handle_hello_ack(&remote, &ack).await.unwrap();
+    assert_eq!(negotiated.version, PROTOCOL_VERSION);
+    assert!(negotiated.supports(Capability::DeltaEvents));
+
+    let peers = engine.connected_peers().await;
+    assert_eq!(peers.len(), 1);
+    assert_eq!(peers[0].protocol.as_ref(), Some(&negotiated));
+}
+
+#[tokio::test]
+async fn handle_hello_ack_from_legacy_peer() {
+    let engine = make_engine(PeerId::new());
+    let remote = PeerId::new();
+    let json = format!(
+        r#"{{"version":1,"peer_id":"{remote}","device_name":"Old","accepted":true,"reason":null}}"#
+    );
+    let ack: HelloAckMessage = serde_json::from_str(&json).unwrap();
+
+    let negotiated = engine.handle_hello_ack(&remote, &ack).await.unwrap();
+    assert_eq!(negotiated.version, 1);
+    // Legacy peers always exchanged event-ID deltas.
+    assert_eq!(negotiated.capabilities.into_iter().collect::<Vec<_>>(), Capability::LEGACY);
+}
+
+#[tokio::test]
+async fn handle_hello_ack_rejects_unsupported_version() {
+    let engine = make_engine(PeerId::new());
+    let remote = PeerId::new();
+    let mut ack = HelloAckMessage::accept(remote, "Future");
+    ack.version = 999;
+    ack.supported_versions = Some(VersionRange::new(999, 999));
+
+    let err = engine.handle_hello_ack(&remote, &ack).await.unwrap_err();
+    assert!(err.contains("version mismatch"));
+    assert!(engine.peer_protocol(&remote).await.is_none());
+}
+
+#[tokio::test]
+async fn handle_hello_ack_rejected() {
+    let engine = make_engine(PeerId::new());
+    let remote = PeerId::new();
+    let ack = HelloAckMessage::reject(remote, "not trusted");
+    let err = engine.handle_hello_ack(&remote, &ack).await.unwrap_err();
+    assert_eq!(err, "not trusted");
+}
+
 // ── Handle sync request ──────────────────────────────────────────
 
 #[tokio::test]
@@ -417,6 +531,60 @@ async fn handle_event_batch_multiple_events() {
     assert_eq!(updated[0], eid);
 }
 
+#[tokio::test]
+async fn handle_event_batch_drops_forged_signatures() {
+    let engine = make_engine(PeerId::new());
+    let (entity_store, event_store) = make_stores();
+    let eid = EntityId::new();
+    let author = PeerId::new();
+    let key = IdentityKeyPair::generate();
+    event_store.register_author_key(&author, &key.public_key()).unwrap();
+
+    let mut genuine = make_event(eid, author);
+    key.sign_event(&mut genuine);
+    // Same author, signed by a different key than the registered one.
+    let mut forged = make_event(eid, author);
+    IdentityKeyPair::generate().sign_event(&mut forged);
+    let batch = EventBatchMessage {
+        entity_id: eid,
+        events: vec![genuine.clone(), forged],
+        is_final: true,
+        batch_seq: 0,
+    };
+
+    let (ack, _) = engine
+        .handle_event_batch(&author, &batch, &entity_store, &event_store)
+        .await;
+    match ack {
+        SyncMessage::EventAck(a) => assert_eq!(a.received_count, 1),
+        _ => panic!("Expected EventAck"),
+    }
+    let stored = event_store.get_events_for_entity(&eid).unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].id, genuine.id);
+    assert!(stored[0].is_signed());
+}
+
+#[tokio::test]
+async fn unregistered_signer_is_treated_as_unsigned() {
+    let engine = make_engine(PeerId::new());
+    let (_entity_store, event_store) = make_stores();
+    let sender = PeerId::new();
+    let claimed_author = PeerId::new();
+
+    let mut event = make_event(EntityId::new(), claimed_author);
+    IdentityKeyPair::generate().sign_event(&mut event);
+
+    // No key is registered for the author, so the signature proves nothing:
+    // the event is delivered unsigned (attributed to the sender) and the
+    // signing key is not bound to the author.
+    let verified = engine.verify_authors(&sender, &[event.clone()], &event_store).await;
+    assert_eq!(verified.len(), 1);
+    assert!(!verified[0].is_signed());
+    assert_eq!(event_author(&verified[0], &sender), sender);
+    assert_eq!(event_store.author_key(&claimed_author).unwrap(), None);
+}
+
 // ── Record local event ───────────────────────────────────────────
 
 #[tokio::test]
@@ -572,6 +740,8 @@ async fn set_acl_handler_routes_acl_events() {
             entity_id: entity.to_string(),
             peer_id: target_peer.to_string(),
             role: "Viewer".to_string(),
+            not_before: None,
+            expires_at: None,
         },
     );
 
@@ -883,6 +1053,8 @@ async fn acl_handler_error_falls_through_to_normal_applicator() {
             entity_id: "not-a-uuid".to_string(),
             peer_id: PeerId::new().to_string(),
             role: "Editor".to_string(),
+            not_before: None,
+            expires_at: None,
         },
     );
 
@@ -920,6 +1092,7 @@ fn sync_config_clone() {
         device_name: "Test".to_string(),
         batch_size: 42,
         timeout_ms: 1000,
+        ..Default::default()
     };
     let cloned = cfg.clone();
     assert_eq!(cloned.device_name, "Test");
@@ -941,7 +1114,7 @@ async fn make_sync_request_with_known_events() {
     event_store.save_event(&e1).unwrap();
     event_store.save_event(&e2).unwrap();
 
-    let msg = engine.make_sync_request(vec![eid], &event_store).await;
+    let msg = engine.make_sync_request(&PeerId::new(), vec![eid], &event_store).await;
     match msg {
         SyncMessage::SyncRequest(r) => {
             assert_eq!(r.entity_ids, vec![eid]);
@@ -953,6 +1126,39 @@ async fn make_sync_request_with_known_events() {
     }
 }
 
+#[tokio::test]
+async fn deltas_only_exchanged_when_negotiated() {
+    let local = PeerId::new();
+    let engine = make_engine(local);
+    let (entity_store, event_store) = make_stores();
+    let eid = EntityId::new();
+    let event = make_event(eid, local);
+    event_store.save_event(&event).unwrap();
+    engine.record_local_event(&event).await;
+
+    let remote = PeerId::new();
+    let hello = HelloMessage::new(remote, "No deltas")
+        .with_protocol(VersionRange::supported(), vec![Capability::Compression]);
+    engine.handle_hello(&hello).await;
+    assert!(!engine.peer_supports(&remote, Capability::DeltaEvents).await);
+
+    match engine.make_sync_request(&remote, vec![eid], &event_store).await {
+        SyncMessage::SyncRequest(r) => assert!(r.known_event_ids.is_empty()),
+        _ => panic!("Expected SyncRequest"),
+    }
+
+    let batch = EventBatchMessage {
+        entity_id: eid,
+        events: vec![],
+        is_final: true,
+        batch_seq: 0,
+    };
+    match engine.handle_event_batch(&remote, &batch, &entity_store, &event_store).await.0 {
+        SyncMessage::EventAck(a) => assert!(a.events.is_empty(), "no reverse delta"),
+        other => panic!("Expected EventAck, got {:?}", other),
+    }
+}
+
 // ── make_sync_state ─────────────────────────────────────────────
 
 #[tokio::test]
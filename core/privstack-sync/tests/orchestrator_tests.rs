@@ -116,6 +116,9 @@ fn make_hello_ack(peer_id: PeerId) -> SyncMessage {
         device_name: "MockPeer".to_string(),
         accepted: true,
         reason: None,
+        supported_versions: None,
+        capabilities: Vec::new(),
+        identity_key: None,
     })
 }
 
@@ -654,6 +657,9 @@ async fn run_sync_with_peer_rejected() {
             device_name: "Peer".to_string(),
             accepted: false,
             reason: Some("busy".to_string()),
+            supported_versions: None,
+            capabilities: Vec::new(),
+            identity_key: None,
         }),
     ];
 
@@ -721,6 +727,9 @@ async fn run_sync_with_peer_version_mismatch() {
             device_name: "Peer".to_string(),
             accepted: true,
             reason: None,
+            supported_versions: None,
+            capabilities: Vec::new(),
+            identity_key: None,
         }),
     ];
 
@@ -1916,6 +1925,44 @@ async fn enterprise_orchestrator_creation_and_shutdown() {
     assert!(result.is_ok());
 }
 
+#[tokio::test]
+async fn enterprise_orchestrator_sweeps_expired_grants() {
+    use privstack_sync::create_enterprise_orchestrator;
+    use privstack_sync::policy::{EnterpriseSyncPolicy, GrantWindow, SyncRole};
+
+    let peer_id = PeerId::new();
+    let (es, ev) = make_stores();
+    let policy = Arc::new(EnterpriseSyncPolicy::new());
+    let entity = EntityId::new();
+    policy
+        .grant_peer_role_within(entity, PeerId::new(), SyncRole::Editor, GrantWindow::until(1))
+        .await;
+
+    let (_incoming_tx, incoming_rx) = mpsc::channel(16);
+    let transport: Arc<Mutex<dyn SyncTransport>> = Arc::new(Mutex::new(MockTransport::new(
+        peer_id,
+        vec![],
+        vec![],
+        incoming_rx,
+    )));
+    let config = OrchestratorConfig {
+        sync_interval: Duration::from_secs(3600),
+        discovery_interval: Duration::from_secs(3600),
+        auto_sync: false,
+        max_entities_per_sync: 0,
+    };
+    let (handle, _event_rx, command_rx, orchestrator) =
+        create_enterprise_orchestrator(peer_id, es, ev, config, policy.clone());
+    let join = tokio::spawn(async move { orchestrator.run(transport, command_rx).await });
+
+    // The first sync tick fires immediately.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(policy.acls.read().await[&entity].peer_roles.is_empty());
+
+    handle.shutdown().await.unwrap();
+    let _ = join.await;
+}
+
 // ── share_entity_with_peer command ──────────────────────────────
 
 #[tokio::test]
@@ -2065,6 +2112,130 @@ async fn pairing_orchestrator_skips_untrusted_peer_on_discovery() {
     let _ = join.await;
 }
 
+#[tokio::test]
+async fn pairing_orchestrator_registers_identity_keys_of_paired_peers_only() {
+    use privstack_crypto::IdentityKeyPair;
+    use privstack_sync::pairing::{DiscoveredPeerInfo, PairingStatus};
+    use privstack_sync::{create_orchestrator_with_pairing, HelloMessage, PairingManager};
+
+    let local_peer = PeerId::new();
+    let (paired, stranger, impersonated) = (PeerId::new(), PeerId::new(), PeerId::new());
+    let (paired_key, stranger_key) = (IdentityKeyPair::generate(), IdentityKeyPair::generate());
+    let forged_key = IdentityKeyPair::generate();
+    let (es, ev) = make_stores();
+
+    let mut pairing = PairingManager::new();
+    pairing.add_discovered_peer(DiscoveredPeerInfo {
+        peer_id: paired.to_string(),
+        device_name: "Paired".to_string(),
+        discovered_at: 0,
+        status: PairingStatus::PendingLocalApproval,
+        addresses: vec![],
+    });
+    pairing.approve_peer(&paired.to_string()).unwrap();
+
+    let (incoming_tx, incoming_rx) = mpsc::channel(16);
+    let transport: Arc<Mutex<dyn SyncTransport>> = Arc::new(Mutex::new(MockTransport::new(
+        local_peer,
+        vec![],
+        vec![],
+        incoming_rx,
+    )));
+    let config = OrchestratorConfig {
+        sync_interval: Duration::from_secs(3600),
+        discovery_interval: Duration::from_secs(3600),
+        auto_sync: false,
+        max_entities_per_sync: 0,
+    };
+    let (handle, _event_rx, command_rx, orchestrator) = create_orchestrator_with_pairing(
+        local_peer,
+        es,
+        ev.clone(),
+        config,
+        Arc::new(std::sync::Mutex::new(pairing)),
+    );
+    let join = tokio::spawn(async move { orchestrator.run(transport, command_rx).await });
+
+    // A paired device claiming another peer's ID must not bind a key for it.
+    for (transport_peer, author, key) in [
+        (paired, paired, &paired_key),
+        (paired, impersonated, &forged_key),
+        (stranger, stranger, &stranger_key),
+    ] {
+        let hello = HelloMessage::new(author, "Device")
+            .with_identity_key(Some(key.public_key().to_base64()));
+        incoming_tx
+            .send(IncomingSyncRequest {
+                peer_id: transport_peer,
+                message: SyncMessage::Hello(hello),
+                response_token: ResponseToken::new(()),
+            })
+            .await
+            .unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert_eq!(ev.author_key(&paired).unwrap(), Some(paired_key.public_key()));
+    assert_eq!(ev.author_key(&impersonated).unwrap(), None);
+    assert_eq!(ev.author_key(&stranger).unwrap(), None);
+
+    handle.shutdown().await.unwrap();
+    let _ = join.await;
+}
+
+#[tokio::test]
+async fn snapshot_events_are_signed_with_the_identity_key() {
+    use privstack_crypto::{IdentityKeyPair, SignatureMode};
+    use privstack_model::Entity;
+
+    let local_peer = PeerId::new();
+    let key = Arc::new(IdentityKeyPair::generate());
+    let es = Arc::new(EntityStore::open_in_memory().unwrap());
+    let ev = Arc::new(
+        EventStore::open_in_memory()
+            .unwrap()
+            .with_signature_mode(SignatureMode::Required),
+    );
+    ev.register_author_key(&local_peer, &key.public_key()).unwrap();
+    let entity_id = EntityId::new();
+    es.save_entity_raw(&Entity {
+        id: entity_id.to_string(),
+        entity_type: "note".to_string(),
+        data: serde_json::json!({"title": "pre-sync"}),
+        created_at: 0,
+        modified_at: 0,
+        created_by: local_peer.to_string(),
+    })
+    .unwrap();
+
+    let (_incoming_tx, incoming_rx) = mpsc::channel(16);
+    let transport: Arc<Mutex<dyn SyncTransport>> = Arc::new(Mutex::new(MockTransport::new(
+        local_peer,
+        vec![],
+        vec![],
+        incoming_rx,
+    )));
+    let config = OrchestratorConfig {
+        sync_interval: Duration::from_secs(3600),
+        discovery_interval: Duration::from_secs(3600),
+        auto_sync: false,
+        max_entities_per_sync: 0,
+    };
+    let (handle, _event_rx, command_rx, mut orchestrator) =
+        create_orchestrator(local_peer, es, ev.clone(), config);
+    orchestrator.set_identity_key(key);
+    let join = tokio::spawn(async move { orchestrator.run(transport, command_rx).await });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Required mode stores only signed events, so the snapshot must be signed.
+    let events = ev.get_events_for_entity(&entity_id).unwrap();
+    assert_eq!(events.len(), 1);
+    assert!(events[0].is_signed());
+
+    handle.shutdown().await.unwrap();
+    let _ = join.await;
+}
+
 // ── SyncCommand::ShareEntityWithPeer debug ──────────────────────
 
 #[test]
@@ -0,0 +1,345 @@
+use privstack_crypto::{verify_event, Authorship, IdentityKeyPair, SignatureMode};
+use privstack_sync::acl_applicator::{AclApplicator, AclEventHandler};
+use privstack_sync::policy::{EnterpriseSyncPolicy, SyncPolicy, SyncRole, TeamId};
+use privstack_sync::policy_crdt::{is_policy_event, PolicyChange, PolicyCrdt, POLICY_ENTITY_ID};
+use privstack_storage::EventStore;
+use privstack_sync::policy_store::PolicyStore;
+use privstack_types::{EntityId, Event, EventPayload, HybridTimestamp, PeerId};
+use std::sync::Arc;
+
+fn policy_event(author: PeerId, timestamp: HybridTimestamp, payload: EventPayload) -> Event {
+    Event::new(POLICY_ENTITY_ID, author, timestamp, payload)
+}
+
+fn limit_event(author: PeerId, timestamp: HybridTimestamp, peer: PeerId, max: Option<usize>) -> Event {
+    policy_event(
+        author,
+        timestamp,
+        EventPayload::DeviceLimitSet {
+            peer_id: peer.to_string(),
+            max_devices: max,
+        },
+    )
+}
+
+/// Applies `events` to a fresh policy in the given order.
+async fn converge(events: &[&Event]) -> EnterpriseSyncPolicy {
+    let policy = EnterpriseSyncPolicy::new();
+    for event in events {
+        assert!(policy.apply_policy_event(event).await.unwrap());
+    }
+    policy
+}
+
+// ── Membership (add-wins) ───────────────────────────────────────
+
+#[tokio::test]
+async fn concurrent_add_and_remove_add_wins_in_any_order() {
+    let (admin_a, admin_b, peer) = (PeerId::new(), PeerId::new(), PeerId::new());
+    let key = IdentityKeyPair::generate();
+
+    // Both admins saw the first add; A removes while B re-adds concurrently.
+    let policy_a = EnterpriseSyncPolicy::new();
+    let first_add = policy_a.replicate_add_known_peer(admin_a, &key, peer, None).await.unwrap();
+    let policy_b = converge(&[&first_add]).await;
+    let remove = policy_a.replicate_remove_known_peer(admin_a, &key, peer).await.unwrap();
+    let re_add = policy_b.replicate_add_known_peer(admin_b, &key, peer, None).await.unwrap();
+
+    let one = converge(&[&first_add, &remove, &re_add]).await;
+    let two = converge(&[&first_add, &re_add, &remove]).await;
+    let three = converge(&[&re_add, &remove, &first_add]).await;
+    for policy in [&one, &two, &three] {
+        assert!(policy.known_peers.read().await.contains(&peer));
+    }
+}
+
+#[tokio::test]
+async fn observed_remove_wins_even_if_add_arrives_late() {
+    let (admin, peer) = (PeerId::new(), PeerId::new());
+    let key = IdentityKeyPair::generate();
+    let origin = EnterpriseSyncPolicy::new();
+    let add = origin.replicate_add_known_peer(admin, &key, peer, None).await.unwrap();
+    let remove = origin.replicate_remove_known_peer(admin, &key, peer).await.unwrap();
+    assert!(!origin.known_peers.read().await.contains(&peer));
+
+    let late = converge(&[&remove, &add]).await;
+    assert!(!late.known_peers.read().await.contains(&peer));
+}
+
+#[tokio::test]
+async fn team_membership_add_wins() {
+    let (admin_a, admin_b, peer) = (PeerId::new(), PeerId::new(), PeerId::new());
+    let key = IdentityKeyPair::generate();
+    let team = TeamId::new();
+
+    let policy_a = EnterpriseSyncPolicy::new();
+    let add = policy_a.replicate_add_team_member(admin_a, &key, team, peer).await.unwrap();
+    let policy_b = converge(&[&add]).await;
+    let remove = policy_a.replicate_remove_team_member(admin_a, &key, team, peer).await.unwrap();
+    let re_add = policy_b.replicate_add_team_member(admin_b, &key, team, peer).await.unwrap();
+
+    match &remove.payload {
+        EventPayload::TeamRemovePeer { observed, .. } => {
+            assert_eq!(observed, &vec![add.id.to_string()]);
+        }
+        other => panic!("unexpected payload {other:?}"),
+    }
+
+    for order in [[&add, &remove, &re_add], [&re_add, &remove, &add]] {
+        let policy = converge(&order).await;
+        assert!(policy.teams.read().await.get(&team).unwrap().contains(&peer));
+    }
+}
+
+#[tokio::test]
+async fn untagged_remove_removes_every_known_add() {
+    let (admin, peer) = (PeerId::new(), PeerId::new());
+    let key = IdentityKeyPair::generate();
+    let team = TeamId::new();
+    let policy = EnterpriseSyncPolicy::new();
+    policy.replicate_add_team_member(admin, &key, team, peer).await.unwrap();
+
+    let legacy_remove = policy_event(
+        admin,
+        HybridTimestamp::now(),
+        EventPayload::TeamRemovePeer {
+            team_id: team.0.to_string(),
+            peer_id: peer.to_string(),
+            observed: Vec::new(),
+        },
+    );
+    policy.apply_policy_event(&legacy_remove).await.unwrap();
+    assert!(!policy.teams.read().await.get(&team).unwrap().contains(&peer));
+}
+
+#[tokio::test]
+async fn untagged_remove_converges_in_any_order() {
+    let (admin_a, admin_b, peer) = (PeerId::new(), PeerId::new(), PeerId::new());
+    let key = IdentityKeyPair::generate();
+
+    // B never saw A's add, so its remove carries no tags.
+    let add = EnterpriseSyncPolicy::new()
+        .replicate_add_known_peer(admin_a, &key, peer, None)
+        .await
+        .unwrap();
+    let policy_b = EnterpriseSyncPolicy::new();
+    policy_b.add_known_peer(peer).await;
+    let remove = policy_b.replicate_remove_known_peer(admin_b, &key, peer).await.unwrap();
+    match &remove.payload {
+        EventPayload::KnownPeerRemove { observed, .. } => assert!(observed.is_empty()),
+        other => panic!("unexpected payload {other:?}"),
+    }
+    for order in [[&add, &remove], [&remove, &add]] {
+        let policy = converge(&order).await;
+        assert!(!policy.known_peers.read().await.contains(&peer));
+    }
+
+    // An add made after the untagged remove survives it in either order.
+    let later = policy_event(
+        admin_a,
+        remove.timestamp.tick(),
+        EventPayload::KnownPeerAdd {
+            peer_id: peer.to_string(),
+            identity_key: None,
+        },
+    );
+    for order in [[&add, &remove, &later], [&later, &remove, &add]] {
+        let policy = converge(&order).await;
+        assert!(policy.known_peers.read().await.contains(&peer));
+    }
+}
+
+// ── Device limits (LWW) ─────────────────────────────────────────
+
+#[tokio::test]
+async fn device_limit_last_writer_wins_in_any_order() {
+    let (admin_a, admin_b, peer) = (PeerId::new(), PeerId::new(), PeerId::new());
+    let earlier = HybridTimestamp::now();
+    let later = earlier.tick();
+    let set_3 = limit_event(admin_a, earlier, peer, Some(3));
+    let set_5 = limit_event(admin_b, later, peer, Some(5));
+
+    for order in [[&set_3, &set_5], [&set_5, &set_3]] {
+        let policy = converge(&order).await;
+        assert_eq!(policy.device_limits.read().await.get(&peer), Some(&5));
+    }
+}
+
+#[tokio::test]
+async fn device_limit_clear_and_tie_break() {
+    let (admin_a, admin_b, peer) = (PeerId::new(), PeerId::new(), PeerId::new());
+    let ts = HybridTimestamp::now();
+    let set = limit_event(admin_a, ts, peer, Some(2));
+    let clear = limit_event(admin_b, ts, peer, None);
+
+    // Same timestamp: the higher event ID wins on every replica.
+    let winner = if clear.id.to_string() > set.id.to_string() { None } else { Some(2) };
+    for order in [[&set, &clear], [&clear, &set]] {
+        let policy = converge(&order).await;
+        assert_eq!(policy.device_limits.read().await.get(&peer).copied(), winner);
+    }
+}
+
+#[test]
+fn crdt_reports_stale_limits_and_rejects_zero() {
+    let (admin, peer) = (PeerId::new(), PeerId::new());
+    let mut crdt = PolicyCrdt::new();
+    let base = HybridTimestamp::now();
+    let newer = limit_event(admin, base.tick(), peer, Some(4));
+    let older = limit_event(admin, base, peer, Some(1));
+
+    assert_eq!(
+        crdt.apply(&newer).unwrap(),
+        Some(PolicyChange::DeviceLimit { peer, max_devices: Some(4) })
+    );
+    assert_eq!(crdt.apply(&older).unwrap(), None);
+    assert!(crdt.apply(&limit_event(admin, base.tick().tick(), peer, Some(0))).is_err());
+}
+
+// ── Authority and routing ───────────────────────────────────────
+
+#[tokio::test]
+async fn policy_events_require_admin_on_policy_entity() {
+    let policy = EnterpriseSyncPolicy::new();
+    let (admin, editor) = (PeerId::new(), PeerId::new());
+    policy.add_known_peer(admin).await;
+    policy.add_known_peer(editor).await;
+    policy.grant_peer_role(POLICY_ENTITY_ID, admin, SyncRole::Admin).await;
+    policy.grant_peer_role(POLICY_ENTITY_ID, editor, SyncRole::Editor).await;
+
+    let newcomer = PeerId::new().to_string();
+    let from_admin = policy_event(admin, HybridTimestamp::now(), EventPayload::KnownPeerAdd {
+        peer_id: newcomer.clone(),
+        identity_key: None,
+    });
+    let from_editor = policy_event(editor, HybridTimestamp::now(), EventPayload::KnownPeerAdd {
+        peer_id: newcomer,
+        identity_key: None,
+    });
+
+    let accepted = policy
+        .on_event_receive(&admin, &POLICY_ENTITY_ID, &[from_admin])
+        .await
+        .unwrap();
+    assert_eq!(accepted.len(), 1);
+    let accepted = policy
+        .on_event_receive(&editor, &POLICY_ENTITY_ID, &[from_editor])
+        .await
+        .unwrap();
+    assert!(accepted.is_empty());
+}
+
+#[tokio::test]
+async fn team_events_need_admin_on_policy_entity_not_their_channel() {
+    let policy = EnterpriseSyncPolicy::new();
+    let doc_admin = PeerId::new();
+    let doc = EntityId::new();
+    policy.add_known_peer(doc_admin).await;
+    policy.grant_peer_role(doc, doc_admin, SyncRole::Admin).await;
+
+    let team = TeamId::new().0.to_string();
+    let join = Event::new(doc, doc_admin, HybridTimestamp::now(), EventPayload::TeamAddPeer {
+        team_id: team.clone(),
+        peer_id: doc_admin.to_string(),
+    });
+    let leave = Event::new(doc, doc_admin, HybridTimestamp::now(), EventPayload::TeamRemovePeer {
+        team_id: team,
+        peer_id: PeerId::new().to_string(),
+        observed: Vec::new(),
+    });
+
+    // Admin on the document the events arrive on is not enough to change a team
+    let accepted = policy.on_event_receive(&doc_admin, &doc, &[join, leave]).await.unwrap();
+    assert!(accepted.is_empty());
+}
+
+#[tokio::test]
+async fn acl_applicator_routes_policy_events() {
+    let policy = Arc::new(EnterpriseSyncPolicy::new());
+    let applicator = AclApplicator::new(policy.clone());
+    let peer = PeerId::new();
+    let event = limit_event(PeerId::new(), HybridTimestamp::now(), peer, Some(2));
+
+    assert!(is_policy_event(&event.payload));
+    assert!(applicator.handle_acl_event(&event).await.unwrap());
+    assert_eq!(policy.device_limits.read().await.get(&peer), Some(&2));
+}
+
+// ── Signing and identity keys ───────────────────────────────────
+
+#[tokio::test]
+async fn replicated_policy_events_are_signed_by_their_author() {
+    let (admin, peer) = (PeerId::new(), PeerId::new());
+    let key = IdentityKeyPair::generate();
+    let policy = EnterpriseSyncPolicy::new();
+
+    let add = policy.replicate_add_known_peer(admin, &key, peer, None).await.unwrap();
+    let limit = policy.replicate_device_limit(admin, &key, peer, Some(3)).await.unwrap();
+    for event in [&add, &limit] {
+        assert_eq!(
+            verify_event(event, SignatureMode::Required).unwrap(),
+            Authorship::Verified(key.public_key())
+        );
+    }
+
+    // The admin's own store accepts them in Required mode once its key is registered.
+    let events = EventStore::open_in_memory()
+        .unwrap()
+        .with_signature_mode(SignatureMode::Required);
+    events.register_author_key(&admin, &key.public_key()).unwrap();
+    events.save_event(&add).unwrap();
+    events.save_event(&limit).unwrap();
+}
+
+#[tokio::test]
+async fn known_peer_registration_binds_identity_key() {
+    let (admin, peer) = (PeerId::new(), PeerId::new());
+    let admin_key = IdentityKeyPair::generate();
+    let peer_key = IdentityKeyPair::generate();
+    let events = Arc::new(
+        EventStore::open_in_memory()
+            .unwrap()
+            .with_signature_mode(SignatureMode::Required),
+    );
+
+    let origin = EnterpriseSyncPolicy::new();
+    let add = origin
+        .replicate_add_known_peer(admin, &admin_key, peer, Some(peer_key.public_key()))
+        .await
+        .unwrap();
+    let receiver = EnterpriseSyncPolicy::new().with_author_keys(events.clone());
+    assert!(receiver.apply_policy_event(&add).await.unwrap());
+    assert_eq!(events.author_key(&peer).unwrap(), Some(peer_key.public_key()));
+
+    let mut genuine = Event::entity_created(EntityId::new(), peer, "note", "{}");
+    peer_key.sign_event(&mut genuine);
+    events.save_event(&genuine).unwrap();
+
+    let mut forged = Event::entity_created(EntityId::new(), peer, "note", "{}");
+    IdentityKeyPair::generate().sign_event(&mut forged);
+    assert!(events.save_event(&forged).is_err());
+}
+
+// ── Persistence ─────────────────────────────────────────────────
+
+#[tokio::test]
+async fn merge_state_survives_reload() {
+    let store = Arc::new(PolicyStore::open_in_memory().unwrap());
+    let (admin, peer) = (PeerId::new(), PeerId::new());
+    let key = IdentityKeyPair::generate();
+    let add = {
+        let policy = EnterpriseSyncPolicy::new().with_store(store.clone());
+        policy.replicate_add_known_peer(admin, &key, peer, None).await.unwrap()
+    };
+
+    let reloaded = EnterpriseSyncPolicy::load(store).await.unwrap();
+    assert!(reloaded.known_peers.read().await.contains(&peer));
+    let remove = reloaded.replicate_remove_known_peer(admin, &key, peer).await.unwrap();
+    match &remove.payload {
+        EventPayload::KnownPeerRemove { observed, .. } => {
+            assert_eq!(observed, &vec![add.id.to_string()]);
+        }
+        other => panic!("unexpected payload {other:?}"),
+    }
+    assert!(!reloaded.known_peers.read().await.contains(&peer));
+}
@@ -0,0 +1,507 @@
+use privstack_crypto::IdentityKeyPair;
+use privstack_model::Entity;
+use privstack_storage::EntityStore;
+use privstack_sync::audit::{AuditExportFormat, AuditQuery, AuditRetention};
+use privstack_sync::policy::{
+    AuditAction, AuditDecision, AuditEntry, EnterpriseSyncPolicy, SyncPolicy, SyncRole, TeamId,
+};
+use privstack_sync::policy_file::{
+    EnterprisePolicyFile, PolicyFileFormat, PolicyFileSource,
+};
+use privstack_sync::policy_store::PolicyStore;
+use privstack_sync::SyncError;
+use privstack_types::{EntityId, Event, EventPayload, HybridTimestamp, PeerId};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+fn snapshot(entity_id: EntityId, peer: PeerId, entity_type: &str) -> Event {
+    Event::new(
+        entity_id,
+        peer,
+        HybridTimestamp::now(),
+        EventPayload::FullSnapshot {
+            entity_type: entity_type.into(),
+            json_data: r#"{"title":"test"}"#.into(),
+        },
+    )
+}
+
+fn policy_toml(alice: PeerId, bob: PeerId, team: TeamId) -> String {
+    format!(
+        r#"
+version = 1
+serial = 1
+known_peers = ["{alice}", "{bob}"]
+
+[teams.engineering]
+id = "{team}"
+members = ["{alice}", "{bob}"]
+
+[default_roles]
+note = "Viewer"
+task = "editor"
+
+[device_limits]
+"{alice}" = 2
+
+[audit]
+retention_days = 30
+export_format = "cef"
+"#
+    )
+}
+
+fn parse_toml(contents: &str) -> Result<EnterprisePolicyFile, SyncError> {
+    EnterprisePolicyFile::parse(contents, PolicyFileFormat::Toml)
+}
+
+fn write(path: &Path, contents: &str) {
+    std::fs::write(path, contents).unwrap();
+}
+
+/// Writes a policy file and its signature by `key`.
+fn write_signed(source: &PolicyFileSource, contents: &str, key: &IdentityKeyPair) {
+    write(&source.path, contents);
+    write(&source.signature_path(), &PolicyFileSource::sign(contents.as_bytes(), key));
+}
+
+// ── Parsing and validation ──────────────────────────────────────
+
+#[test]
+fn parse_toml_policy_file() {
+    let (alice, bob, team) = (PeerId::new(), PeerId::new(), TeamId::new());
+    let file = parse_toml(&policy_toml(alice, bob, team)).unwrap();
+
+    assert_eq!(file.known_peers.as_ref().unwrap().len(), 2);
+    assert_eq!(file.teams.len(), 1);
+    assert_eq!(file.teams[0].name, "engineering");
+    assert_eq!(file.teams[0].id, team);
+    assert!(file.teams[0].members.contains(&bob));
+    assert_eq!(file.default_roles["note"], SyncRole::Viewer);
+    assert_eq!(file.default_roles["task"], SyncRole::Editor);
+    assert_eq!(file.device_limits[&alice], 2);
+    assert_eq!(
+        file.audit_retention,
+        AuditRetention::new().with_max_age(Duration::from_secs(30 * 24 * 60 * 60))
+    );
+    assert_eq!(file.audit_export_format, AuditExportFormat::Cef);
+}
+
+#[test]
+fn parse_json_policy_file() {
+    let peer = PeerId::new();
+    let json = format!(
+        r#"{{"known_peers": ["{peer}"], "default_roles": {{"note": "Admin"}}, "audit": {{"max_entries": 500}}}}"#
+    );
+    let file = EnterprisePolicyFile::parse(&json, PolicyFileFormat::Json).unwrap();
+
+    assert!(file.known_peers.as_ref().unwrap().contains(&peer));
+    assert_eq!(file.default_roles["note"], SyncRole::Admin);
+    assert_eq!(file.audit_retention.max_entries, Some(500));
+    assert_eq!(file.audit_export_format, AuditExportFormat::Jsonl);
+}
+
+#[test]
+fn format_follows_extension() {
+    assert_eq!(PolicyFileFormat::from_path(Path::new("sync-policy.json")), PolicyFileFormat::Json);
+    assert_eq!(PolicyFileFormat::from_path(Path::new("sync-policy.JSON")), PolicyFileFormat::Json);
+    assert_eq!(PolicyFileFormat::from_path(Path::new("sync-policy.toml")), PolicyFileFormat::Toml);
+    assert_eq!(PolicyFileFormat::from_path(Path::new("sync-policy")), PolicyFileFormat::Toml);
+}
+
+#[test]
+fn empty_file_is_valid() {
+    let file = parse_toml("").unwrap();
+    assert_eq!(file.known_peers, None);
+    assert!(file.teams.is_empty());
+    assert!(file.audit_retention.is_unbounded());
+}
+
+#[test]
+fn invalid_files_rejected() {
+    let (peer, stranger, team) = (PeerId::new(), PeerId::new(), TeamId::new());
+    let cases = [
+        "version = 2".to_string(),
+        "known_peer = []".to_string(),
+        r#"known_peers = ["not-a-uuid"]"#.to_string(),
+        "[default_roles]\nnote = \"Superuser\"".to_string(),
+        format!("[device_limits]\n\"{peer}\" = 0"),
+        "[audit]\nexport_format = \"xml\"".to_string(),
+        format!("[teams.a]\nid = \"{team}\"\n[teams.b]\nid = \"{team}\""),
+        format!("known_peers = [\"{peer}\"]\n[teams.a]\nid = \"{team}\"\nmembers = [\"{stranger}\"]"),
+        format!("known_peers = [\"{peer}\"]\n[device_limits]\n\"{stranger}\" = 1"),
+        "[redaction.editor]\nlabels = [\"financial\"]".to_string(),
+        "[redaction.viewer]\npaths = { invoice = [\"cost\"] }".to_string(),
+    ];
+    for contents in cases {
+        let err = parse_toml(&contents).unwrap_err();
+        assert!(matches!(err, SyncError::InvalidPolicy(_)), "{contents}: {err}");
+    }
+}
+
+#[test]
+fn parse_redaction_rules() {
+    let file = parse_toml(
+        r#"
+[redaction.viewer]
+labels = ["financial"]
+paths = { invoice = ["/cost", "/vendor/iban"] }
+"#,
+    )
+    .unwrap();
+    let fields = vec![("/margin".to_string(), "financial".to_string())];
+    assert_eq!(
+        file.redaction.hidden_paths(SyncRole::Viewer, "invoice", &fields),
+        vec!["/cost", "/margin", "/vendor/iban"]
+    );
+    assert!(file.redaction.hidden_paths(SyncRole::Editor, "invoice", &fields).is_empty());
+    assert!(parse_toml("").unwrap().redaction.is_empty());
+}
+
+// ── Signatures ──────────────────────────────────────────────────
+
+#[test]
+fn source_without_trusted_keys_loads_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = PolicyFileSource::new(dir.path().join("sync-policy.toml"));
+    let contents = "[default_roles]\nnote = \"Viewer\"";
+
+    write(&source.path, contents);
+    assert!(matches!(source.load(), Err(SyncError::Auth(_))));
+
+    // Even a signed file needs a trusted key to check it against.
+    write_signed(&source, contents, &IdentityKeyPair::generate());
+    assert!(matches!(source.load(), Err(SyncError::Auth(_))));
+}
+
+#[test]
+fn signed_source_requires_trusted_signature() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("sync-policy.toml");
+    let contents = "[default_roles]\nnote = \"Viewer\"";
+    write(&path, contents);
+
+    let admin = IdentityKeyPair::generate();
+    let source = PolicyFileSource::new(&path).with_trusted_key(admin.public_key());
+
+    // Missing signature
+    assert!(matches!(source.load(), Err(SyncError::Auth(_))));
+
+    // Signed by an untrusted key
+    let rogue = IdentityKeyPair::generate();
+    write(&source.signature_path(), &PolicyFileSource::sign(contents.as_bytes(), &rogue));
+    assert!(matches!(source.load(), Err(SyncError::Auth(_))));
+
+    // Signed by the admin
+    write(&source.signature_path(), &PolicyFileSource::sign(contents.as_bytes(), &admin));
+    assert!(source.load().is_ok());
+
+    // File edited after signing
+    write(&path, "[default_roles]\nnote = \"Owner\"");
+    assert!(matches!(source.load(), Err(SyncError::Auth(_))));
+}
+
+#[test]
+fn signature_path_appends_suffix() {
+    let source = PolicyFileSource::new("/etc/privstack/sync-policy.toml");
+    assert_eq!(
+        source.signature_path(),
+        Path::new("/etc/privstack/sync-policy.toml.sig")
+    );
+}
+
+// ── Diff and apply ──────────────────────────────────────────────
+
+#[tokio::test]
+async fn apply_populates_empty_policy() {
+    let (alice, bob, team) = (PeerId::new(), PeerId::new(), TeamId::new());
+    let file = parse_toml(&policy_toml(alice, bob, team)).unwrap();
+    let policy = EnterpriseSyncPolicy::new();
+
+    let diff = policy.apply_policy_file(&file).await.unwrap();
+    assert_eq!(diff.known_peers_added.len(), 2);
+    assert_eq!(diff.team_members_added.len(), 2);
+    assert_eq!(
+        diff.default_roles_set,
+        vec![("note".to_string(), SyncRole::Viewer), ("task".to_string(), SyncRole::Editor)]
+    );
+    assert_eq!(diff.device_limits_set, vec![(alice, 2)]);
+    assert!(diff.audit_retention.is_some());
+    assert_eq!(diff.audit_export_format, Some(AuditExportFormat::Cef));
+    assert_eq!(diff.change_count(), 9);
+
+    assert!(policy.known_peers.read().await.contains(&bob));
+    assert!(policy.teams.read().await[&team].contains(&alice));
+    assert_eq!(policy.device_limits.read().await[&alice], 2);
+    assert_eq!(*policy.audit_retention.read().await, file.audit_retention);
+
+    // Re-applying the same file is a no-op.
+    assert!(policy.apply_policy_file(&file).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn apply_removes_state_dropped_from_file() {
+    let (alice, bob, team) = (PeerId::new(), PeerId::new(), TeamId::new());
+    let policy = EnterpriseSyncPolicy::new();
+    policy
+        .apply_policy_file(&parse_toml(&policy_toml(alice, bob, team)).unwrap())
+        .await
+        .unwrap();
+
+    let unmanaged_team = TeamId::new();
+    policy.add_team_member(unmanaged_team, bob).await;
+
+    let updated = format!(
+        "serial = 2\nknown_peers = [\"{alice}\"]\n[teams.engineering]\nid = \"{team}\"\nmembers = [\"{alice}\"]\n[default_roles]\nnote = \"Editor\""
+    );
+    let diff = policy.diff_policy_file(&parse_toml(&updated).unwrap()).await;
+    assert_eq!(diff.known_peers_removed, vec![bob]);
+    assert_eq!(diff.team_members_removed, vec![(team, bob)]);
+    assert_eq!(diff.default_roles_set, vec![("note".to_string(), SyncRole::Editor)]);
+    assert_eq!(diff.default_roles_removed, vec!["task".to_string()]);
+    assert_eq!(diff.device_limits_removed, vec![alice]);
+    assert_eq!(diff.audit_retention, Some(AuditRetention::default()));
+
+    policy.apply_policy_file(&parse_toml(&updated).unwrap()).await.unwrap();
+    assert!(!policy.known_peers.read().await.contains(&bob));
+    assert!(!policy.teams.read().await[&team].contains(&bob));
+    assert!(policy.device_limits.read().await.is_empty());
+    // Teams not listed in the file are left alone.
+    assert!(policy.teams.read().await[&unmanaged_team].contains(&bob));
+}
+
+#[tokio::test]
+async fn omitted_known_peers_are_left_alone() {
+    let (alice, bob, team) = (PeerId::new(), PeerId::new(), TeamId::new());
+    let policy = EnterpriseSyncPolicy::new();
+    policy
+        .apply_policy_file(&parse_toml(&policy_toml(alice, bob, team)).unwrap())
+        .await
+        .unwrap();
+
+    let diff = policy
+        .apply_policy_file(&parse_toml("serial = 2\n[default_roles]\nnote = \"Viewer\"").unwrap())
+        .await
+        .unwrap();
+    assert!(diff.known_peers_removed.is_empty());
+    assert_eq!(policy.known_peers.read().await.len(), 2);
+
+    // An explicit empty list admits every peer.
+    let diff = policy
+        .apply_policy_file(&parse_toml("serial = 3\nknown_peers = []").unwrap())
+        .await
+        .unwrap();
+    assert_eq!(diff.known_peers_removed.len(), 2);
+    assert!(policy.known_peers.read().await.is_empty());
+}
+
+#[tokio::test]
+async fn applied_policy_persists_to_store() {
+    let (alice, bob, team) = (PeerId::new(), PeerId::new(), TeamId::new());
+    let store = Arc::new(PolicyStore::open_in_memory().unwrap());
+    let policy = EnterpriseSyncPolicy::new().with_store(store.clone());
+    policy
+        .apply_policy_file(&parse_toml(&policy_toml(alice, bob, team)).unwrap())
+        .await
+        .unwrap();
+
+    let reloaded = EnterpriseSyncPolicy::load(store).await.unwrap();
+    assert_eq!(reloaded.known_peers.read().await.len(), 2);
+    assert_eq!(reloaded.teams.read().await[&team].len(), 2);
+    assert_eq!(reloaded.type_default_roles.read().await["note"], SyncRole::Viewer);
+    assert_eq!(reloaded.device_limits.read().await[&alice], 2);
+}
+
+#[tokio::test]
+async fn older_policy_files_are_rejected() {
+    let (alice, bob, team) = (PeerId::new(), PeerId::new(), TeamId::new());
+    let store = Arc::new(PolicyStore::open_in_memory().unwrap());
+    let policy = EnterpriseSyncPolicy::new().with_store(store.clone());
+    let original = parse_toml(&policy_toml(alice, bob, team)).unwrap();
+    let revoked = parse_toml(&format!("serial = 2\nknown_peers = [\"{alice}\"]")).unwrap();
+    policy.apply_policy_file(&original).await.unwrap();
+    policy.apply_policy_file(&revoked).await.unwrap();
+
+    // The older file that still lists bob cannot be re-applied, before or
+    // after a restart, and a serial cannot be reused for other contents.
+    let reused =
+        parse_toml(&format!("serial = 2\nknown_peers = [\"{alice}\", \"{bob}\"]")).unwrap();
+    let reloaded = EnterpriseSyncPolicy::load(store).await.unwrap();
+    for policy in [&policy, &reloaded] {
+        assert!(matches!(
+            policy.apply_policy_file(&original).await,
+            Err(SyncError::InvalidPolicy(_))
+        ));
+        assert!(matches!(
+            policy.apply_policy_file(&reused).await,
+            Err(SyncError::InvalidPolicy(_))
+        ));
+        assert!(!policy.known_peers.read().await.contains(&bob));
+        // The applied file itself can be re-applied.
+        assert!(policy.apply_policy_file(&revoked).await.unwrap().is_empty());
+    }
+}
+
+#[tokio::test]
+async fn applied_audit_settings_govern_export_and_pruning() {
+    let (alice, bob, team) = (PeerId::new(), PeerId::new(), TeamId::new());
+    let store = Arc::new(PolicyStore::open_in_memory().unwrap());
+    let policy = EnterpriseSyncPolicy::new().with_store(store.clone());
+    policy
+        .apply_policy_file(&parse_toml(&policy_toml(alice, bob, team)).unwrap())
+        .await
+        .unwrap();
+
+    let entry = |age_days: u64| AuditEntry {
+        peer: alice,
+        entity: None,
+        action: AuditAction::Handshake,
+        decision: AuditDecision::Allowed,
+        detail: String::new(),
+        timestamp: SystemTime::now() - Duration::from_secs(age_days * 24 * 60 * 60),
+    };
+    store.save_audit_entry(&entry(40)).unwrap();
+    store.save_audit_entry(&entry(1)).unwrap();
+
+    // retention_days = 30 drops the old entry.
+    assert_eq!(policy.prune_audit_log().await.unwrap(), 1);
+    assert_eq!(store.audit_log_count().unwrap(), 1);
+
+    // export_format = "cef"
+    let mut out = Vec::new();
+    assert_eq!(policy.export_audit_log(&AuditQuery::new(), &mut out).await.unwrap(), 1);
+    assert!(String::from_utf8(out).unwrap().starts_with("CEF:0|"));
+}
+
+#[tokio::test]
+async fn export_without_store_fails() {
+    let policy = EnterpriseSyncPolicy::new();
+    let mut out = Vec::new();
+    assert!(policy.export_audit_log(&AuditQuery::new(), &mut out).await.is_err());
+    assert_eq!(policy.prune_audit_log().await.unwrap(), 0);
+}
+
+// ── Per-type default roles ──────────────────────────────────────
+
+/// Saves an entity of `entity_type` to `store`.
+fn store_entity(store: &EntityStore, entity_type: &str) -> EntityId {
+    let id = EntityId::new();
+    store
+        .save_entity_raw(&Entity {
+            id: id.to_string(),
+            entity_type: entity_type.to_string(),
+            data: serde_json::json!({"title": "test"}),
+            created_at: 0,
+            modified_at: 0,
+            created_by: "local".to_string(),
+        })
+        .unwrap();
+    id
+}
+
+#[tokio::test]
+async fn type_default_role_applies_to_local_entities() {
+    let store = Arc::new(EntityStore::open_in_memory().unwrap());
+    let policy = EnterpriseSyncPolicy::new().with_entity_store(store.clone());
+    policy.set_type_default_role("note", Some(SyncRole::Viewer)).await;
+    let peer = PeerId::new();
+    let note = store_entity(&store, "note");
+    let secret = store_entity(&store, "secret");
+
+    assert_eq!(policy.resolve_role(&peer, &note).await, Some(SyncRole::Viewer));
+    let events = vec![snapshot(note, PeerId::new(), "note")];
+    assert_eq!(policy.on_event_send(&peer, &note, &events).await.unwrap().len(), 1);
+
+    let events = vec![snapshot(secret, PeerId::new(), "secret")];
+    assert!(policy.on_event_send(&peer, &secret, &events).await.unwrap().is_empty());
+
+    // Entities that only exist in sync traffic get no type default.
+    assert_eq!(policy.resolve_role(&peer, &EntityId::new()).await, None);
+}
+
+#[tokio::test]
+async fn entity_acl_overrides_type_default() {
+    let policy = EnterpriseSyncPolicy::new();
+    let peer = PeerId::new();
+    let entity = EntityId::new();
+    policy.set_type_default_role("note", Some(SyncRole::Editor)).await;
+    policy.register_entity_type(entity, "note").await;
+
+    assert_eq!(policy.resolve_role(&peer, &entity).await, Some(SyncRole::Editor));
+    policy.set_default_role(entity, Some(SyncRole::Viewer)).await;
+    assert_eq!(policy.resolve_role(&peer, &entity).await, Some(SyncRole::Viewer));
+}
+
+#[tokio::test]
+async fn peers_cannot_choose_entity_types() {
+    let store = Arc::new(EntityStore::open_in_memory().unwrap());
+    let policy = EnterpriseSyncPolicy::new().with_entity_store(store.clone());
+    policy.set_type_default_role("public", Some(SyncRole::Editor)).await;
+    let peer = PeerId::new();
+
+    // A local secret relabelled as public in a peer's payload stays secret.
+    let secret = store_entity(&store, "secret");
+    let recv = policy
+        .on_event_receive(&peer, &secret, &[snapshot(secret, peer, "public")])
+        .await
+        .unwrap();
+    assert!(recv.is_empty());
+    assert_eq!(policy.entity_types.read().await[&secret], "secret");
+
+    // An unknown entity claimed as public is not given the type default.
+    let unknown = EntityId::new();
+    let recv = policy
+        .on_event_receive(&peer, &unknown, &[snapshot(unknown, peer, "public")])
+        .await
+        .unwrap();
+    assert!(recv.is_empty());
+    assert!(!policy.entity_types.read().await.contains_key(&unknown));
+}
+
+// ── Watcher ─────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn watcher_applies_changes_and_ignores_invalid_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let admin = IdentityKeyPair::generate();
+    let source = PolicyFileSource::new(dir.path().join("sync-policy.toml"))
+        .with_trusted_key(admin.public_key());
+    write_signed(&source, "serial = 1\n[default_roles]\nnote = \"Viewer\"", &admin);
+
+    let policy = Arc::new(EnterpriseSyncPolicy::new());
+    let watcher = EnterpriseSyncPolicy::spawn_policy_file_watcher(
+        policy.clone(),
+        source.clone(),
+        Duration::from_millis(20),
+    );
+
+    let role_for = |entity_type: &'static str| {
+        let policy = policy.clone();
+        async move { policy.type_default_roles.read().await.get(entity_type).copied() }
+    };
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(role_for("note").await, Some(SyncRole::Viewer));
+
+    // An invalid edit leaves the applied policy in place, as does an unsigned one.
+    write_signed(&source, "serial = 2\n[default_roles]\nnote = \"Superuser\"", &admin);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(role_for("note").await, Some(SyncRole::Viewer));
+    write(&source.path, "serial = 2\n[default_roles]\nnote = \"Owner\"");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(role_for("note").await, Some(SyncRole::Viewer));
+
+    write_signed(&source, "serial = 2\n[default_roles]\ntask = \"Editor\"", &admin);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(role_for("note").await, None);
+    assert_eq!(role_for("task").await, Some(SyncRole::Editor));
+
+    // Rolling back to an older signed file is refused.
+    write_signed(&source, "serial = 1\n[default_roles]\nnote = \"Viewer\"", &admin);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(role_for("note").await, None);
+    assert_eq!(role_for("task").await, Some(SyncRole::Editor));
+
+    watcher.abort();
+}
@@ -1,8 +1,13 @@
 //! Tests for policy_store.rs — persistent storage for policy state.
 
-use privstack_sync::policy::{AuditAction, AuditDecision, AuditEntry, SyncRole};
+use privstack_sync::audit::{
+    AuditBreakReason, AuditChainBreak, AuditExportFormat, AuditQuery, AuditRetention,
+    AUDIT_GENESIS_HASH,
+};
+use privstack_sync::policy::{AuditAction, AuditDecision, AuditEntry, GrantWindow, SyncRole, TeamId};
 use privstack_sync::policy_store::PolicyStore;
 use privstack_types::{EntityId, PeerId};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 // ── Empty database loads ────────────────────────────────────────
 
@@ -113,6 +118,70 @@ fn remove_acl() {
     assert!(acls.is_empty());
 }
 
+#[test]
+fn save_and_load_acl_grant_window() {
+    let store = PolicyStore::open_in_memory().unwrap();
+    let entity = EntityId::new();
+    let peer = PeerId::new();
+    let team = TeamId::new();
+    let window = GrantWindow::new(Some(1_000), Some(2_000));
+
+    store.save_acl_grant(&entity, &peer, SyncRole::Editor, &window).unwrap();
+    store
+        .save_team_grant(&entity, &team.0.to_string(), SyncRole::Viewer, &GrantWindow::until(3_000))
+        .unwrap();
+
+    let grants = store.load_acl_grants().unwrap();
+    assert_eq!(grants, vec![(entity, peer, SyncRole::Editor, window)]);
+    let team_grants = store.load_team_grants().unwrap();
+    assert_eq!(team_grants.len(), 1);
+    assert_eq!(team_grants[0].3, GrantWindow::until(3_000));
+
+    // Permanent re-grant clears the window.
+    store.save_acl(&entity, &peer, SyncRole::Editor).unwrap();
+    assert!(store.load_acl_grants().unwrap()[0].3.is_permanent());
+}
+
+#[test]
+fn legacy_acl_tables_gain_window_columns() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("policy.db").to_string_lossy().to_string();
+    let entity = EntityId::new();
+    let peer = PeerId::new();
+    {
+        let raw = rusqlite::Connection::open(&path).unwrap();
+        raw.execute_batch(
+            "CREATE TABLE acls (
+                entity_id TEXT NOT NULL,
+                peer_id TEXT NOT NULL,
+                role TEXT NOT NULL,
+                UNIQUE(entity_id, peer_id)
+            );
+            CREATE TABLE acl_team_roles (
+                entity_id TEXT NOT NULL,
+                team_id TEXT NOT NULL,
+                role TEXT NOT NULL,
+                UNIQUE(entity_id, team_id)
+            );",
+        )
+        .unwrap();
+        raw.execute(
+            "INSERT INTO acls (entity_id, peer_id, role) VALUES (?1, ?2, 'Editor')",
+            rusqlite::params![entity.to_string(), peer.to_string()],
+        )
+        .unwrap();
+    }
+
+    let store = PolicyStore::new(&path).unwrap();
+    let grants = store.load_acl_grants().unwrap();
+    assert_eq!(grants, vec![(entity, peer, SyncRole::Editor, GrantWindow::default())]);
+
+    store
+        .save_acl_grant(&entity, &peer, SyncRole::Editor, &GrantWindow::until(5_000))
+        .unwrap();
+    assert_eq!(store.load_acl_grants().unwrap()[0].3, GrantWindow::until(5_000));
+}
+
 // ── Default role save/load ──────────────────────────────────────
 
 #[test]
@@ -240,6 +309,43 @@ fn save_device_limit_upsert() {
     assert_eq!(limits[0].1, 10);
 }
 
+#[test]
+fn remove_device_limit() {
+    let store = PolicyStore::open_in_memory().unwrap();
+    let peer = PeerId::new();
+
+    store.save_device_limit(&peer, 3).unwrap();
+    store.remove_device_limit(&peer).unwrap();
+
+    assert!(store.load_device_limits().unwrap().is_empty());
+}
+
+// ── Entity types and per-type default roles ─────────────────────
+
+#[test]
+fn save_and_load_type_default_role() {
+    let store = PolicyStore::open_in_memory().unwrap();
+
+    store.save_type_default_role("note", SyncRole::Viewer).unwrap();
+    store.save_type_default_role("note", SyncRole::Editor).unwrap();
+    store.save_type_default_role("task", SyncRole::Admin).unwrap();
+    store.remove_type_default_role("task").unwrap();
+
+    let defaults = store.load_type_default_roles().unwrap();
+    assert_eq!(defaults, vec![("note".to_string(), SyncRole::Editor)]);
+}
+
+#[test]
+fn save_and_load_entity_type() {
+    let store = PolicyStore::open_in_memory().unwrap();
+    let entity = EntityId::new();
+
+    store.save_entity_type(&entity, "note").unwrap();
+
+    let types = store.load_entity_types().unwrap();
+    assert_eq!(types, vec![(entity, "note".to_string())]);
+}
+
 // ── Known peers save/load ───────────────────────────────────────
 
 #[test]
@@ -1306,3 +1412,271 @@ fn multiple_teams_multiple_peers() {
     let teams = store.load_teams().unwrap();
     assert_eq!(teams.len(), 3);
 }
+
+// ── Audit chain, queries, export, retention ─────────────────────
+
+fn audit_entry(peer: PeerId, decision: AuditDecision, detail: &str, timestamp: SystemTime) -> AuditEntry {
+    AuditEntry {
+        peer,
+        entity: None,
+        action: AuditAction::EventReceive,
+        decision,
+        detail: detail.to_string(),
+        timestamp,
+    }
+}
+
+fn file_store_with_entries(count: usize) -> (tempfile::TempDir, String, PolicyStore) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("policy.db").to_string_lossy().to_string();
+    let store = PolicyStore::new(&path).unwrap();
+    let peer = PeerId::new();
+    for i in 0..count {
+        store
+            .save_audit_entry(&audit_entry(peer, AuditDecision::Allowed, &format!("entry {i}"), SystemTime::now()))
+            .unwrap();
+    }
+    (dir, path, store)
+}
+
+#[test]
+fn audit_chain_intact_after_writes() {
+    let (_dir, _path, store) = file_store_with_entries(5);
+    let verification = store.verify_audit_chain().unwrap();
+    assert!(verification.is_intact());
+    assert_eq!(verification.verified, 5);
+
+    let records = store.query_audit_log(&AuditQuery::new()).unwrap();
+    assert_eq!(records[0].prev_hash, AUDIT_GENESIS_HASH);
+    for pair in records.windows(2) {
+        assert_eq!(pair[1].prev_hash, pair[0].hash);
+    }
+    assert_eq!(verification.head_hash, records[4].hash);
+}
+
+#[test]
+fn audit_chain_detects_modified_row() {
+    let (_dir, path, store) = file_store_with_entries(4);
+    let raw = rusqlite::Connection::open(&path).unwrap();
+    raw.execute("UPDATE audit_log SET detail = 'rewritten' WHERE id = 3", []).unwrap();
+
+    let verification = store.verify_audit_chain().unwrap();
+    assert_eq!(verification.verified, 2);
+    assert_eq!(
+        verification.first_break,
+        Some(AuditChainBreak { row_id: 3, reason: AuditBreakReason::Modified })
+    );
+}
+
+#[test]
+fn audit_chain_detects_deleted_row() {
+    let (_dir, path, store) = file_store_with_entries(4);
+    let raw = rusqlite::Connection::open(&path).unwrap();
+    raw.execute("DELETE FROM audit_log WHERE id = 2", []).unwrap();
+
+    let verification = store.verify_audit_chain().unwrap();
+    assert!(!verification.is_intact());
+    assert_eq!(
+        verification.first_break,
+        Some(AuditChainBreak { row_id: 3, reason: AuditBreakReason::Unlinked })
+    );
+}
+
+#[test]
+fn legacy_audit_rows_chained_on_open() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("policy.db").to_string_lossy().to_string();
+    {
+        let raw = rusqlite::Connection::open(&path).unwrap();
+        raw.execute_batch(
+            "CREATE TABLE audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                peer TEXT NOT NULL,
+                entity TEXT,
+                action TEXT NOT NULL,
+                decision TEXT NOT NULL,
+                detail TEXT NOT NULL,
+                timestamp TEXT NOT NULL
+            );",
+        )
+        .unwrap();
+        for i in 0..3 {
+            raw.execute(
+                "INSERT INTO audit_log (peer, entity, action, decision, detail, timestamp) VALUES (?1, NULL, 'handshake', 'Allowed', ?2, '1700000000000')",
+                rusqlite::params![PeerId::new().to_string(), format!("old {i}")],
+            )
+            .unwrap();
+        }
+    }
+
+    let store = PolicyStore::new(&path).unwrap();
+    store
+        .save_audit_entry(&audit_entry(PeerId::new(), AuditDecision::Denied, "new", SystemTime::now()))
+        .unwrap();
+    let verification = store.verify_audit_chain().unwrap();
+    assert!(verification.is_intact());
+    assert_eq!(verification.verified, 4);
+}
+
+#[test]
+fn query_audit_log_filters() {
+    let store = PolicyStore::open_in_memory().unwrap();
+    let alice = PeerId::new();
+    let bob = PeerId::new();
+    let entity = EntityId::new();
+    let t0 = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+    store.save_audit_entry(&audit_entry(alice, AuditDecision::Allowed, "a1", t0)).unwrap();
+    store
+        .save_audit_entry(&audit_entry(alice, AuditDecision::Denied, "a2", t0 + Duration::from_secs(60)))
+        .unwrap();
+    store
+        .save_audit_entry(&AuditEntry {
+            entity: Some(entity),
+            action: AuditAction::SyncRequest,
+            ..audit_entry(bob, AuditDecision::Denied, "b1", t0 + Duration::from_secs(120))
+        })
+        .unwrap();
+
+    let by_peer = store.query_audit_log(&AuditQuery::new().with_peer(alice)).unwrap();
+    assert_eq!(by_peer.len(), 2);
+
+    let denied = store
+        .query_audit_log(&AuditQuery::new().with_decision(AuditDecision::Denied))
+        .unwrap();
+    let details: Vec<_> = denied.iter().map(|r| r.entry.detail.as_str()).collect();
+    assert_eq!(details, vec!["a2", "b1"]);
+
+    let by_entity = store.query_audit_log(&AuditQuery::new().with_entity(entity)).unwrap();
+    assert_eq!(by_entity.len(), 1);
+    assert_eq!(by_entity[0].entry.action, AuditAction::SyncRequest);
+
+    let by_action = store
+        .query_audit_log(&AuditQuery::new().with_action(AuditAction::EventReceive))
+        .unwrap();
+    assert_eq!(by_action.len(), 2);
+
+    let window = store
+        .query_audit_log(
+            &AuditQuery::new()
+                .since(t0 + Duration::from_secs(30))
+                .until(t0 + Duration::from_secs(120)),
+        )
+        .unwrap();
+    assert_eq!(window.len(), 1);
+    assert_eq!(window[0].entry.detail, "a2");
+
+    let newest = store
+        .query_audit_log(&AuditQuery::new().newest_first().with_limit(1))
+        .unwrap();
+    assert_eq!(newest[0].entry.detail, "b1");
+
+    let paged = store
+        .query_audit_log(&AuditQuery::new().with_limit(2).with_offset(1))
+        .unwrap();
+    let details: Vec<_> = paged.iter().map(|r| r.entry.detail.as_str()).collect();
+    assert_eq!(details, vec!["a2", "b1"]);
+}
+
+#[test]
+fn export_format_parses_config_values() {
+    assert_eq!("json".parse::<AuditExportFormat>().unwrap(), AuditExportFormat::Jsonl);
+    assert_eq!("JSONL".parse::<AuditExportFormat>().unwrap(), AuditExportFormat::Jsonl);
+    assert_eq!("cef".parse::<AuditExportFormat>().unwrap(), AuditExportFormat::Cef);
+    assert_eq!("syslog".parse::<AuditExportFormat>().unwrap(), AuditExportFormat::Syslog);
+    assert!("csv".parse::<AuditExportFormat>().is_err());
+}
+
+#[test]
+fn export_audit_log_jsonl() {
+    let (_dir, _path, store) = file_store_with_entries(3);
+    let mut out = Vec::new();
+    let written = store
+        .export_audit_log(&AuditQuery::new(), AuditExportFormat::Jsonl, &mut out)
+        .unwrap();
+    assert_eq!(written, 3);
+
+    let text = String::from_utf8(out).unwrap();
+    let lines: Vec<serde_json::Value> =
+        text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["detail"], "entry 0");
+    assert_eq!(lines[0]["decision"], "Allowed");
+    assert_eq!(lines[1]["prev_hash"], lines[0]["hash"]);
+}
+
+#[test]
+fn export_audit_log_cef_and_syslog_escape_fields() {
+    let store = PolicyStore::open_in_memory().unwrap();
+    let peer = PeerId::new();
+    store
+        .save_audit_entry(&audit_entry(peer, AuditDecision::Denied, "role=Viewer\nstripped", SystemTime::now()))
+        .unwrap();
+
+    let mut out = Vec::new();
+    store
+        .export_audit_log(&AuditQuery::new(), AuditExportFormat::Cef, &mut out)
+        .unwrap();
+    let line = String::from_utf8(out).unwrap();
+    assert!(line.starts_with("CEF:0|PrivStack|privstack-sync|"));
+    assert!(line.contains("|event_receive|event_receive Denied|6|"));
+    assert!(line.contains(&format!("suser={peer}")));
+    assert!(line.contains("msg=role\\=Viewer\\nstripped"));
+    assert_eq!(line.lines().count(), 1);
+
+    let mut out = Vec::new();
+    store
+        .export_audit_log(&AuditQuery::new(), AuditExportFormat::Syslog, &mut out)
+        .unwrap();
+    let line = String::from_utf8(out).unwrap();
+    assert!(line.starts_with("<84>1 "));
+    assert!(line.contains(" privstack-sync - audit - CEF:0|"));
+}
+
+#[test]
+fn retention_max_entries_keeps_chain_verifiable() {
+    let (_dir, path, store) = file_store_with_entries(10);
+    let removed = store
+        .apply_audit_retention(&AuditRetention::new().with_max_entries(4), SystemTime::now())
+        .unwrap();
+    assert_eq!(removed, 6);
+    assert_eq!(store.audit_log_count().unwrap(), 4);
+    assert_eq!(store.audit_pruned_count().unwrap(), 6);
+    assert!(store.verify_audit_chain().unwrap().is_intact());
+
+    // New entries keep linking to the pruned chain.
+    store
+        .save_audit_entry(&audit_entry(PeerId::new(), AuditDecision::Allowed, "after", SystemTime::now()))
+        .unwrap();
+    let verification = store.verify_audit_chain().unwrap();
+    assert!(verification.is_intact());
+    assert_eq!(verification.verified, 5);
+
+    // Deleting the oldest remaining row is still detected.
+    let raw = rusqlite::Connection::open(&path).unwrap();
+    raw.execute("DELETE FROM audit_log WHERE id = 7", []).unwrap();
+    assert!(!store.verify_audit_chain().unwrap().is_intact());
+}
+
+#[test]
+fn retention_max_age_prunes_old_entries() {
+    let store = PolicyStore::open_in_memory().unwrap();
+    let peer = PeerId::new();
+    let now = SystemTime::now();
+    let day = Duration::from_secs(24 * 60 * 60);
+    store.save_audit_entry(&audit_entry(peer, AuditDecision::Allowed, "old", now - day * 40)).unwrap();
+    store.save_audit_entry(&audit_entry(peer, AuditDecision::Allowed, "older", now - day * 35)).unwrap();
+    store.save_audit_entry(&audit_entry(peer, AuditDecision::Allowed, "recent", now - day)).unwrap();
+
+    let retention = AuditRetention::from_config(Some(30), None);
+    assert_eq!(store.apply_audit_retention(&retention, now).unwrap(), 2);
+
+    let remaining = store.query_audit_log(&AuditQuery::new()).unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].entry.detail, "recent");
+    assert!(store.verify_audit_chain().unwrap().is_intact());
+
+    // Nothing else to prune; unbounded rules never prune.
+    assert_eq!(store.apply_audit_retention(&retention, now).unwrap(), 0);
+    assert_eq!(store.apply_audit_retention(&AuditRetention::new(), now).unwrap(), 0);
+}
@@ -1,7 +1,8 @@
+use privstack_crypto::IdentityKeyPair;
 use privstack_storage::{EntityStore, EventStore};
 use privstack_sync::policy::{
-    AllowAllPolicy, AuditAction, AuditDecision, DeviceId, EntityAcl, EnterpriseSyncPolicy,
-    SyncPolicy, SyncRole, TeamId,
+    event_author, AllowAllPolicy, AuditAction, AuditDecision, DeviceId, EntityAcl,
+    EnterpriseSyncPolicy, GrantWindow, SyncPolicy, SyncRole, TeamId,
 };
 use privstack_sync::policy_store::PolicyStore;
 use privstack_sync::protocol::{EventBatchMessage, HelloMessage, SyncMessage};
@@ -2392,6 +2393,8 @@ fn make_acl_grant_event(
             entity_id: target_entity.to_string(),
             peer_id: target_peer.to_string(),
             role: role.to_string(),
+            not_before: None,
+            expires_at: None,
         },
     )
 }
@@ -2870,6 +2873,8 @@ async fn on_event_receive_acl_event_requires_admin() {
             entity_id: entity.to_string(),
             peer_id: PeerId::new().to_string(),
             role: "Viewer".to_string(),
+            not_before: None,
+            expires_at: None,
         },
     );
 
@@ -2894,6 +2899,8 @@ async fn on_event_receive_acl_event_accepted_by_admin() {
             entity_id: entity.to_string(),
             peer_id: PeerId::new().to_string(),
             role: "Viewer".to_string(),
+            not_before: None,
+            expires_at: None,
         },
     );
 
@@ -3352,6 +3359,8 @@ async fn on_event_receive_acl_event_from_admin_allowed() {
             entity_id: entity.to_string(),
             peer_id: PeerId::new().to_string(),
             role: "Viewer".to_string(),
+            not_before: None,
+            expires_at: None,
         },
     );
 
@@ -3538,3 +3547,209 @@ async fn enterprise_policy_load_roundtrip() {
     assert!(!policy.active_devices.read().await.is_empty());
     assert!(!policy.teams.read().await.is_empty());
 }
+
+// ── Event Authorship ────────────────────────────────────────────
+
+fn signed_event(entity_id: EntityId, author: PeerId, key: &IdentityKeyPair) -> Event {
+    let mut event = make_event(entity_id, author);
+    key.sign_event(&mut event);
+    event
+}
+
+#[tokio::test]
+async fn signed_event_checked_against_author_not_relay() {
+    let (policy, _local, relay, entity) = setup_enterprise().await;
+    let viewer = PeerId::new();
+    let acl = EntityAcl::new(entity)
+        .with_peer_role(relay, SyncRole::Editor)
+        .with_peer_role(viewer, SyncRole::Viewer);
+    policy.acls.write().await.insert(entity, acl);
+
+    // An Editor relaying a Viewer's signed write does not lend it Editor rights.
+    let events = vec![signed_event(entity, viewer, &IdentityKeyPair::generate())];
+    let recv = policy.on_event_receive(&relay, &entity, &events).await.unwrap();
+    assert!(recv.is_empty());
+}
+
+#[tokio::test]
+async fn relayed_signed_events_from_editor_accepted() {
+    let (policy, _local, relay, entity) = setup_enterprise().await;
+    let editor = PeerId::new();
+    let acl = EntityAcl::new(entity)
+        .with_peer_role(relay, SyncRole::Viewer)
+        .with_peer_role(editor, SyncRole::Editor);
+    policy.acls.write().await.insert(entity, acl);
+
+    let key = IdentityKeyPair::generate();
+    let own = make_event(entity, relay);
+    let relayed = signed_event(entity, editor, &key);
+    let events = vec![own, relayed.clone()];
+    let recv = policy.on_event_receive(&relay, &entity, &events).await.unwrap();
+    // The Viewer's own write is stripped; the Editor's signed write passes.
+    assert_eq!(recv.len(), 1);
+    assert_eq!(recv[0].id, relayed.id);
+}
+
+#[tokio::test]
+async fn relay_without_role_cannot_forward_signed_events() {
+    let (policy, _local, relay, entity) = setup_enterprise().await;
+    let editor = PeerId::new();
+    let acl = EntityAcl::new(entity).with_peer_role(editor, SyncRole::Editor);
+    policy.acls.write().await.insert(entity, acl);
+
+    let events = vec![signed_event(entity, editor, &IdentityKeyPair::generate())];
+    let recv = policy.on_event_receive(&relay, &entity, &events).await.unwrap();
+    assert!(recv.is_empty());
+
+    let log = policy.audit_log.read().await;
+    assert!(log.iter().any(|e| e.peer == relay && e.decision == AuditDecision::Denied));
+}
+
+#[tokio::test]
+async fn unsigned_event_attributed_to_sender() {
+    let (policy, _local, remote, entity) = setup_enterprise().await;
+    let owner = PeerId::new();
+    let acl = EntityAcl::new(entity)
+        .with_peer_role(remote, SyncRole::Viewer)
+        .with_peer_role(owner, SyncRole::Owner);
+    policy.acls.write().await.insert(entity, acl);
+
+    // Claiming the Owner's peer_id on an unsigned event gains nothing.
+    let forged = make_events(entity, owner, 2);
+    assert_eq!(event_author(&forged[0], &remote), remote);
+    let recv = policy.on_event_receive(&remote, &entity, &forged).await.unwrap();
+    assert!(recv.is_empty());
+}
+
+// ── Time-bounded grants ─────────────────────────────────────────
+
+#[tokio::test]
+async fn grant_window_bounds_resolved_role() {
+    let (policy, _local, remote, entity) = setup_enterprise().await;
+    policy
+        .grant_peer_role_within(entity, remote, SyncRole::Editor, GrantWindow::new(Some(1_000), Some(2_000)))
+        .await;
+
+    assert_eq!(policy.resolve_role_at(&remote, &entity, 999).await, None);
+    assert_eq!(policy.resolve_role_at(&remote, &entity, 1_000).await, Some(SyncRole::Editor));
+    assert_eq!(policy.resolve_role_at(&remote, &entity, 1_999).await, Some(SyncRole::Editor));
+    assert_eq!(policy.resolve_role_at(&remote, &entity, 2_000).await, None);
+}
+
+#[tokio::test]
+async fn expired_grant_denies_writes_without_sweep() {
+    let (policy, _local, remote, entity) = setup_enterprise().await;
+    policy
+        .grant_peer_role_within(entity, remote, SyncRole::Editor, GrantWindow::until(1))
+        .await;
+
+    let events = make_events(entity, remote, 2);
+    let recv = policy.on_event_receive(&remote, &entity, &events).await.unwrap();
+    assert!(recv.is_empty());
+    let ids = policy.on_sync_request(&remote, &[entity]).await.unwrap();
+    assert!(ids.is_empty());
+}
+
+#[tokio::test]
+async fn expired_peer_grant_falls_back_to_team_role() {
+    let (policy, _local, remote, entity) = setup_enterprise().await;
+    let team = TeamId::new();
+    policy.add_team_member(team, remote).await;
+    policy.grant_team_role(entity, team, SyncRole::Viewer).await;
+    policy
+        .grant_peer_role_within(entity, remote, SyncRole::Admin, GrantWindow::until(5_000))
+        .await;
+
+    assert_eq!(policy.resolve_role_at(&remote, &entity, 4_999).await, Some(SyncRole::Admin));
+    assert_eq!(policy.resolve_role_at(&remote, &entity, 5_000).await, Some(SyncRole::Viewer));
+}
+
+#[tokio::test]
+async fn permanent_regrant_clears_window() {
+    let (policy, _local, remote, entity) = setup_enterprise().await;
+    policy
+        .grant_peer_role_within(entity, remote, SyncRole::Editor, GrantWindow::until(1))
+        .await;
+    policy.grant_peer_role(entity, remote, SyncRole::Editor).await;
+
+    assert!(policy.acls.read().await[&entity].peer_windows.is_empty());
+    assert_eq!(policy.resolve_role(&remote, &entity).await, Some(SyncRole::Editor));
+}
+
+#[tokio::test]
+async fn sweep_removes_expired_grants_and_audits() {
+    let store = Arc::new(PolicyStore::open_in_memory().unwrap());
+    let policy = EnterpriseSyncPolicy::new().with_store(store.clone());
+    let entity = EntityId::new();
+    let contractor = PeerId::new();
+    let upcoming = PeerId::new();
+    let team = TeamId::new();
+    let member = PeerId::new();
+    policy.add_team_member(team, member).await;
+
+    policy
+        .grant_peer_role_within(entity, contractor, SyncRole::Editor, GrantWindow::until(1_000))
+        .await;
+    policy
+        .grant_peer_role_within(entity, upcoming, SyncRole::Viewer, GrantWindow::new(Some(5_000), Some(9_000)))
+        .await;
+    policy
+        .grant_team_role_within(entity, team, SyncRole::Editor, GrantWindow::until(2_000))
+        .await;
+
+    assert_eq!(policy.sweep_expired_grants(500).await, 0);
+    assert_eq!(policy.sweep_expired_grants(3_000).await, 2);
+
+    {
+        let acls = policy.acls.read().await;
+        let acl = &acls[&entity];
+        assert!(!acl.peer_roles.contains_key(&contractor));
+        assert!(acl.peer_roles.contains_key(&upcoming));
+        assert!(acl.team_roles.is_empty());
+    }
+    let persisted = store.load_acl_grants().unwrap();
+    assert_eq!(persisted.len(), 1);
+    assert_eq!(persisted[0].1, upcoming);
+    assert!(store.load_team_grants().unwrap().is_empty());
+
+    let log = policy.audit_log.read().await;
+    let expired: Vec<_> = log.iter().filter(|e| e.action == AuditAction::GrantExpired).collect();
+    assert_eq!(expired.len(), 2);
+    assert!(expired.iter().any(|e| e.peer == contractor));
+    assert!(expired.iter().any(|e| e.peer == member));
+}
+
+#[tokio::test]
+async fn grant_windows_survive_reload() {
+    let store = Arc::new(PolicyStore::open_in_memory().unwrap());
+    let policy = EnterpriseSyncPolicy::new().with_store(store.clone());
+    let entity = EntityId::new();
+    let peer = PeerId::new();
+    let team = TeamId::new();
+    policy
+        .grant_peer_role_within(entity, peer, SyncRole::Editor, GrantWindow::until(2_000))
+        .await;
+    policy
+        .grant_team_role_within(entity, team, SyncRole::Viewer, GrantWindow::new(Some(1_000), None))
+        .await;
+
+    let reloaded = EnterpriseSyncPolicy::load(store).await.unwrap();
+    let acls = reloaded.acls.read().await;
+    let acl = &acls[&entity];
+    assert_eq!(acl.peer_windows.get(&peer), Some(&GrantWindow::until(2_000)));
+    assert_eq!(acl.team_windows.get(&team), Some(&GrantWindow::new(Some(1_000), None)));
+}
+
+#[tokio::test]
+async fn maintenance_sweeps_expired_grants() {
+    let policy = Arc::new(EnterpriseSyncPolicy::new());
+    let entity = EntityId::new();
+    let peer = PeerId::new();
+    policy
+        .grant_peer_role_within(entity, peer, SyncRole::Editor, GrantWindow::until(1))
+        .await;
+
+    policy.run_maintenance().await;
+
+    assert!(policy.acls.read().await[&entity].peer_roles.is_empty());
+}
@@ -1,8 +1,9 @@
 use privstack_crdt::VectorClock;
 use privstack_sync::protocol::{
-    ErrorMessage, EventAckMessage, EventBatchMessage, EventNotifyMessage, HelloAckMessage,
-    HelloMessage, SubscribeMessage, SyncMessage, SyncRequestMessage, SyncStateMessage,
-    MAX_BATCH_SIZE, PROTOCOL_VERSION,
+    Capability, ErrorMessage, EventAckMessage, EventBatchMessage, EventNotifyMessage,
+    HelloAckMessage, HelloMessage, NegotiatedProtocol, SubscribeMessage, SyncMessage,
+    SyncRequestMessage, SyncStateMessage, VersionRange, MAX_BATCH_SIZE, MIN_PROTOCOL_VERSION,
+    PROTOCOL_VERSION,
 };
 use privstack_types::{EntityId, Event, EventPayload, HybridTimestamp, PeerId};
 
@@ -66,6 +67,70 @@ fn hello_message_serde_roundtrip() {
     assert_eq!(parsed.entity_ids, msg.entity_ids);
 }
 
+#[test]
+fn hello_message_advertises_supported_range() {
+    let msg = HelloMessage::new(PeerId::new(), "Dev");
+    assert_eq!(msg.version, MIN_PROTOCOL_VERSION);
+    assert_eq!(msg.version_range(), VersionRange::supported());
+    assert_eq!(msg.capabilities, Capability::SUPPORTED);
+}
+
+#[test]
+fn legacy_hello_without_range_deserializes() {
+    let peer_id = PeerId::new();
+    let json = format!(
+        r#"{{"version":1,"peer_id":"{peer_id}","device_name":"Old","entity_ids":[]}}"#
+    );
+    let msg: HelloMessage = serde_json::from_str(&json).unwrap();
+    assert_eq!(msg.version_range(), VersionRange::new(1, 1));
+    assert!(msg.capabilities.is_empty());
+}
+
+// ── Version negotiation ──────────────────────────────────────────
+
+#[test]
+fn version_range_highest_common() {
+    let a = VersionRange::new(1, 3);
+    assert_eq!(a.highest_common(&VersionRange::new(2, 5)), Some(3));
+    assert_eq!(a.highest_common(&VersionRange::new(1, 1)), Some(1));
+    assert_eq!(a.highest_common(&VersionRange::new(4, 6)), None);
+    assert_eq!(VersionRange::new(3, 1), a);
+}
+
+#[test]
+fn unknown_capability_deserializes() {
+    let caps: Vec<Capability> =
+        serde_json::from_str(r#"["delta_events","quantum_sync"]"#).unwrap();
+    assert_eq!(caps, vec![Capability::DeltaEvents, Capability::Unknown]);
+}
+
+#[test]
+fn negotiate_intersects_capabilities() {
+    let negotiated = NegotiatedProtocol::negotiate(
+        &VersionRange::new(1, 2),
+        &[Capability::DeltaEvents, Capability::Compression, Capability::Unknown],
+        &VersionRange::new(1, 4),
+        &[Capability::Compression, Capability::Subscriptions, Capability::Unknown],
+    )
+    .unwrap();
+    assert_eq!(negotiated.version, 2);
+    assert!(negotiated.supports(Capability::Compression));
+    assert!(!negotiated.supports(Capability::DeltaEvents));
+    assert!(!negotiated.supports(Capability::Unknown));
+    assert_eq!(negotiated.capabilities.len(), 1);
+}
+
+#[test]
+fn negotiate_fails_without_overlap() {
+    assert!(NegotiatedProtocol::negotiate(
+        &VersionRange::new(1, 1),
+        &[],
+        &VersionRange::new(2, 3),
+        &[],
+    )
+    .is_none());
+}
+
 // ── HelloAckMessage ──────────────────────────────────────────────
 
 #[test]
@@ -366,6 +431,14 @@ fn error_version_mismatch() {
     assert!(err.message.contains("2"));
 }
 
+#[test]
+fn error_no_common_version() {
+    let err = ErrorMessage::no_common_version(&VersionRange::new(1, 2), &VersionRange::new(3, 4));
+    assert_eq!(err.code, 1);
+    assert!(err.message.contains("1-2"));
+    assert!(err.message.contains("3-4"));
+}
+
 #[test]
 fn error_unknown_entity() {
     let eid = EntityId::new();
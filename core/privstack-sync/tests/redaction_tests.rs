@@ -0,0 +1,216 @@
+use privstack_crypto::{verify_event, Authorship, IdentityKeyPair, SignatureMode};
+use privstack_model::{EntitySchema, IndexedField, MergeStrategy};
+use privstack_storage::EntityStore;
+use privstack_sync::applicator::EventApplicator;
+use privstack_sync::policy::{event_author, EnterpriseSyncPolicy, EntityAcl, SyncPolicy, SyncRole};
+use privstack_sync::redaction::{redact_event, restore_redacted, RedactionRules};
+use privstack_types::{EntityId, Event, EventPayload, HybridTimestamp, PeerId};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+fn invoice_schema() -> EntitySchema {
+    EntitySchema {
+        entity_type: "invoice".to_string(),
+        indexed_fields: vec![
+            IndexedField::text("/title", true),
+            IndexedField::decimal("/cost").with_sensitivity("financial"),
+        ],
+        merge_strategy: MergeStrategy::LwwDocument,
+    }
+}
+
+fn snapshot(entity_id: EntityId, peer: PeerId, data: Value) -> Event {
+    Event::new(
+        entity_id,
+        peer,
+        HybridTimestamp::now(),
+        EventPayload::FullSnapshot {
+            entity_type: "invoice".to_string(),
+            json_data: data.to_string(),
+        },
+    )
+}
+
+fn payload_data(event: &Event) -> Value {
+    match &event.payload {
+        EventPayload::FullSnapshot { json_data, .. }
+        | EventPayload::EntityCreated { json_data, .. }
+        | EventPayload::EntityUpdated { json_data, .. } => serde_json::from_str(json_data).unwrap(),
+        other => panic!("unexpected payload {other:?}"),
+    }
+}
+
+// ── Rules ───────────────────────────────────────────────────────
+
+#[test]
+fn hidden_paths_combine_labels_and_paths() {
+    let rules = RedactionRules::new()
+        .hide_label(SyncRole::Viewer, "financial")
+        .hide_path(SyncRole::Viewer, "invoice", "/vendor/iban")
+        .hide_path(SyncRole::Viewer, "note", "/body");
+    let fields = vec![
+        ("/cost".to_string(), "financial".to_string()),
+        ("/email".to_string(), "personal".to_string()),
+    ];
+
+    assert_eq!(
+        rules.hidden_paths(SyncRole::Viewer, "invoice", &fields),
+        vec!["/cost", "/vendor/iban"]
+    );
+    assert!(rules.hidden_paths(SyncRole::Editor, "invoice", &fields).is_empty());
+    assert_eq!(rules.roles().collect::<Vec<_>>(), vec![SyncRole::Viewer]);
+    assert!(!rules.is_empty());
+    assert!(RedactionRules::new().is_empty());
+}
+
+// ── Redacting events ────────────────────────────────────────────
+
+#[test]
+fn redact_event_removes_present_paths_and_signature() {
+    let keys = IdentityKeyPair::generate();
+    let mut event = snapshot(
+        EntityId::new(),
+        PeerId::new(),
+        json!({"title": "Q3", "cost": 1200, "vendor": {"name": "Acme", "iban": "DE00"}}),
+    );
+    keys.sign_event(&mut event);
+
+    let paths = ["/cost".to_string(), "/vendor/iban".to_string(), "/missing".to_string()];
+    let redacted = redact_event(&event, &paths);
+
+    assert_eq!(payload_data(&redacted), json!({"title": "Q3", "vendor": {"name": "Acme"}}));
+    assert_eq!(redacted.redacted_paths, vec!["/cost", "/vendor/iban"]);
+    assert!(redacted.is_redacted());
+    assert!(redacted.signature.is_none());
+    assert_eq!(redacted.id, event.id);
+}
+
+#[test]
+fn redact_event_without_matches_keeps_event() {
+    let keys = IdentityKeyPair::generate();
+    let mut event = snapshot(EntityId::new(), PeerId::new(), json!({"title": "Q3"}));
+    keys.sign_event(&mut event);
+
+    let redacted = redact_event(&event, &["/cost".to_string()]);
+    assert_eq!(redacted.signature, event.signature);
+    assert!(!redacted.is_redacted());
+    assert_eq!(payload_data(&redacted), json!({"title": "Q3"}));
+}
+
+#[test]
+fn restore_redacted_copies_local_values() {
+    let local = json!({"title": "old", "cost": 1200, "vendor": {"iban": "DE00"}});
+    let mut merged = json!({"title": "new"});
+    restore_redacted(
+        &local,
+        &mut merged,
+        &["/cost".to_string(), "/vendor/iban".to_string(), "/missing".to_string()],
+    );
+    assert_eq!(merged, json!({"title": "new", "cost": 1200, "vendor": {"iban": "DE00"}}));
+}
+
+// ── Policy ──────────────────────────────────────────────────────
+
+async fn setup_policy(role: SyncRole) -> (EnterpriseSyncPolicy, PeerId, EntityId) {
+    let policy = EnterpriseSyncPolicy::new();
+    let peer = PeerId::new();
+    let entity = EntityId::new();
+    policy.known_peers.write().await.insert(peer);
+    let acl = EntityAcl::new(entity).with_peer_role(peer, role);
+    policy.acls.write().await.insert(entity, acl);
+    policy.register_schema(&invoice_schema()).await;
+    policy
+        .set_redaction_rules(RedactionRules::new().hide_label(SyncRole::Viewer, "financial"))
+        .await;
+    (policy, peer, entity)
+}
+
+#[tokio::test]
+async fn viewer_receives_redacted_snapshot() {
+    let (policy, viewer, entity) = setup_policy(SyncRole::Viewer).await;
+    let events = vec![snapshot(entity, PeerId::new(), json!({"title": "Q3", "cost": 1200}))];
+
+    let sent = policy.on_event_send(&viewer, &entity, &events).await.unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(payload_data(&sent[0]), json!({"title": "Q3"}));
+    assert_eq!(sent[0].redacted_paths, vec!["/cost"]);
+
+    let log = policy.audit_log.read().await;
+    assert!(log.last().unwrap().detail.contains("redacted=1"));
+}
+
+#[tokio::test]
+async fn redacted_copies_are_sealed_by_the_sender() {
+    let (policy, viewer, entity) = setup_policy(SyncRole::Viewer).await;
+    let (local, key) = (PeerId::new(), Arc::new(IdentityKeyPair::generate()));
+    let policy = policy.with_redaction_signer(local, key.clone());
+    let author = IdentityKeyPair::generate();
+    let mut event = snapshot(entity, PeerId::new(), json!({"title": "Q3", "cost": 1200}));
+    author.sign_event(&mut event);
+
+    let sent = policy.on_event_send(&viewer, &entity, &[event.clone()]).await.unwrap();
+    assert_eq!(sent[0].id, event.id);
+    assert!(!sent[0].is_signed());
+    assert_eq!(sent[0].redaction_seal.as_ref().unwrap().redactor, local);
+    assert_eq!(
+        verify_event(&sent[0], SignatureMode::Required).unwrap(),
+        Authorship::Redacted(key.public_key())
+    );
+    assert_eq!(event_author(&sent[0], &PeerId::new()), local);
+}
+
+#[tokio::test]
+async fn editor_receives_full_snapshot() {
+    let (policy, editor, entity) = setup_policy(SyncRole::Editor).await;
+    let events = vec![snapshot(entity, PeerId::new(), json!({"title": "Q3", "cost": 1200}))];
+
+    let sent = policy.on_event_send(&editor, &entity, &events).await.unwrap();
+    assert_eq!(payload_data(&sent[0]), json!({"title": "Q3", "cost": 1200}));
+    assert!(!sent[0].is_redacted());
+}
+
+// ── Applying redacted events ────────────────────────────────────
+
+#[test]
+fn redacted_snapshot_keeps_local_fields() {
+    let store = EntityStore::open_in_memory().unwrap();
+    let schema = invoice_schema();
+    let applicator = EventApplicator::new(PeerId::new());
+    let entity = EntityId::new();
+    let peer = PeerId::new();
+
+    let original = snapshot(entity, peer, json!({"title": "Q3", "cost": 1200}));
+    applicator.apply_event(&original, &store, Some(&schema), None).unwrap();
+
+    let update = snapshot(entity, peer, json!({"title": "Q3 final", "cost": 1200}));
+    let redacted = redact_event(&update, &["/cost".to_string()]);
+    applicator.apply_event(&redacted, &store, Some(&schema), None).unwrap();
+
+    let stored = store.get_entity(&entity.to_string()).unwrap().unwrap();
+    assert_eq!(stored.data, json!({"title": "Q3 final", "cost": 1200}));
+}
+
+#[test]
+fn redacted_create_keeps_local_fields() {
+    let store = EntityStore::open_in_memory().unwrap();
+    let applicator = EventApplicator::new(PeerId::new());
+    let entity = EntityId::new();
+    let peer = PeerId::new();
+
+    let data = json!({"title": "Q3", "cost": 1200});
+    let created = Event::new(
+        entity,
+        peer,
+        HybridTimestamp::now(),
+        EventPayload::EntityCreated {
+            entity_type: "invoice".to_string(),
+            json_data: data.to_string(),
+        },
+    );
+    applicator.apply_event(&created, &store, None, None).unwrap();
+    let redacted = redact_event(&created, &["/cost".to_string()]);
+    applicator.apply_event(&redacted, &store, None, None).unwrap();
+
+    let stored = store.get_entity(&entity.to_string()).unwrap().unwrap();
+    assert_eq!(stored.data, data);
+}
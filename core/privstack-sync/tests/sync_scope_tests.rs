@@ -0,0 +1,306 @@
+use privstack_model::Entity;
+use privstack_storage::EventStore;
+use privstack_sync::pairing::PairingManager;
+use privstack_sync::policy::{PersonalSyncPolicy, SyncPolicy};
+use privstack_sync::sync_scope::{EntityMeta, SyncScope};
+use privstack_types::{EntityId, Event, EventPayload, HybridTimestamp, PeerId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn snapshot(entity_id: EntityId, peer: PeerId, entity_type: &str, json: &str) -> Event {
+    Event::new(
+        entity_id,
+        peer,
+        HybridTimestamp::now(),
+        EventPayload::FullSnapshot {
+            entity_type: entity_type.into(),
+            json_data: json.into(),
+        },
+    )
+}
+
+fn meta(entity_type: &str, tags: &[&str]) -> EntityMeta {
+    EntityMeta {
+        entity_type: entity_type.into(),
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+    }
+}
+
+// ── SyncScope evaluation ────────────────────────────────────────
+
+#[test]
+fn empty_scope_allows_everything() {
+    let scope = SyncScope::new();
+    assert!(scope.is_empty());
+    assert!(scope.allows(&meta("note", &["private"]), &HashMap::new()));
+}
+
+#[test]
+fn include_types_restricts_to_listed_types() {
+    let scope = SyncScope::new().include_type("task").include_type("note");
+    let plugins = HashMap::new();
+    assert!(scope.allows(&meta("task", &[]), &plugins));
+    assert!(scope.allows(&meta("note", &[]), &plugins));
+    assert!(!scope.allows(&meta("journal", &[]), &plugins));
+}
+
+#[test]
+fn exclude_types_wins_over_include() {
+    let scope = SyncScope::new().include_type("task").exclude_type("task");
+    assert!(!scope.allows(&meta("task", &[]), &HashMap::new()));
+}
+
+#[test]
+fn exclude_tags_normalizes_hash_and_case() {
+    let scope = SyncScope::new().exclude_tag("#Private");
+    let plugins = HashMap::new();
+    assert!(!scope.allows(&meta("note", &["private"]), &plugins));
+    assert!(!scope.allows(&meta("note", &["#PRIVATE"]), &plugins));
+    assert!(scope.allows(&meta("note", &["work"]), &plugins));
+}
+
+#[test]
+fn exclude_plugins_uses_entity_type_ownership() {
+    let scope = SyncScope::new().exclude_plugin("privstack.finance");
+    let mut plugins = HashMap::new();
+    plugins.insert("transaction".to_string(), "privstack.finance".to_string());
+    assert!(!scope.allows(&meta("transaction", &[]), &plugins));
+    assert!(scope.allows(&meta("note", &[]), &plugins));
+}
+
+#[test]
+fn scope_json_roundtrip_omits_empty_rules() {
+    let scope = SyncScope::new().include_type("task").exclude_tag("private");
+    let json = serde_json::to_string(&scope).unwrap();
+    assert!(!json.contains("exclude_plugins"));
+    let back: SyncScope = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, scope);
+    let empty: SyncScope = serde_json::from_str("{}").unwrap();
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn deserialized_exclude_tags_are_normalized() {
+    let scope: SyncScope = serde_json::from_str(r##"{"exclude_tags":["#Private"]}"##).unwrap();
+    assert_eq!(scope, SyncScope::new().exclude_tag("private"));
+    assert!(!scope.allows(&meta("note", &["private"]), &HashMap::new()));
+}
+
+// ── EntityMeta ──────────────────────────────────────────────────
+
+#[test]
+fn meta_from_entity_reads_tags() {
+    let entity = Entity {
+        id: EntityId::new().to_string(),
+        entity_type: "note".into(),
+        data: serde_json::json!({"title": "x", "tags": ["a", "b", 3]}),
+        created_at: 0,
+        modified_at: 0,
+        created_by: "me".into(),
+    };
+    assert_eq!(EntityMeta::from_entity(&entity), meta("note", &["a", "b"]));
+}
+
+#[test]
+fn meta_from_events_uses_newest_data_event() {
+    let eid = EntityId::new();
+    let peer = PeerId::new();
+    let old = snapshot(eid, peer, "note", r#"{"tags":[]}"#);
+    std::thread::sleep(std::time::Duration::from_millis(2));
+    let new = snapshot(eid, peer, "note", r#"{"tags":["private"]}"#);
+    let delete = Event::entity_deleted(eid, peer, "note");
+    let m = EntityMeta::from_events(&[new, old, delete]).unwrap();
+    assert_eq!(m.tags, vec!["private".to_string()]);
+}
+
+#[test]
+fn meta_from_acl_event_is_none() {
+    let event = Event::new(
+        EntityId::new(),
+        PeerId::new(),
+        HybridTimestamp::now(),
+        EventPayload::TeamAddPeer {
+            team_id: "t".into(),
+            peer_id: "p".into(),
+        },
+    );
+    assert!(EntityMeta::from_events(&[event]).is_none());
+}
+
+// ── PersonalSyncPolicy integration ──────────────────────────────
+
+#[tokio::test]
+async fn personal_policy_scope_filters_event_send() {
+    let policy = PersonalSyncPolicy::new();
+    let phone = PeerId::new();
+    let laptop = PeerId::new();
+    policy
+        .set_sync_scope(phone, SyncScope::new().include_type("task"))
+        .await;
+
+    let note = EntityId::new();
+    let events = vec![snapshot(note, PeerId::new(), "note", "{}")];
+
+    let to_phone = policy.on_event_send(&phone, &note, &events).await.unwrap();
+    assert!(to_phone.is_empty());
+    // Peers without a scope are unaffected
+    let to_laptop = policy.on_event_send(&laptop, &note, &events).await.unwrap();
+    assert_eq!(to_laptop.len(), 1);
+}
+
+#[tokio::test]
+async fn personal_policy_scope_combines_with_explicit_sharing() {
+    let policy = PersonalSyncPolicy::new();
+    let peer = PeerId::new();
+    let shared = EntityId::new();
+    let unshared = EntityId::new();
+    policy.share(shared, peer).await;
+    policy
+        .set_sync_scope(peer, SyncScope::new().exclude_tag("private"))
+        .await;
+
+    let ok = vec![snapshot(shared, peer, "note", r#"{"tags":["work"]}"#)];
+    let private = vec![snapshot(shared, peer, "note", r#"{"tags":["private"]}"#)];
+    let other = vec![snapshot(unshared, peer, "note", r#"{"tags":["work"]}"#)];
+
+    assert_eq!(policy.on_event_send(&peer, &shared, &ok).await.unwrap().len(), 1);
+    assert!(policy.on_event_send(&peer, &shared, &private).await.unwrap().is_empty());
+    assert!(policy.on_event_send(&peer, &unshared, &other).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn personal_policy_plugin_exclusion() {
+    let policy = PersonalSyncPolicy::new();
+    let peer = PeerId::new();
+    policy
+        .register_plugin_entity_types("privstack.finance", &["transaction".to_string()])
+        .await;
+    policy
+        .set_sync_scope(peer, SyncScope::new().exclude_plugin("privstack.finance"))
+        .await;
+
+    assert!(!policy.scope_allows(&peer, &meta("transaction", &[])).await);
+    assert!(policy.scope_allows(&peer, &meta("note", &[])).await);
+
+    // Re-registering replaces the plugin's previous type list
+    policy
+        .register_plugin_entity_types("privstack.finance", &["budget".to_string()])
+        .await;
+    assert!(policy.scope_allows(&peer, &meta("transaction", &[])).await);
+    assert!(!policy.scope_allows(&peer, &meta("budget", &[])).await);
+}
+
+#[tokio::test]
+async fn personal_policy_empty_scope_clears() {
+    let policy = PersonalSyncPolicy::new();
+    let peer = PeerId::new();
+    policy
+        .set_sync_scope(peer, SyncScope::new().include_type("task"))
+        .await;
+    assert!(policy.has_sync_scope(&peer).await);
+    policy.set_sync_scope(peer, SyncScope::new()).await;
+    assert!(!policy.has_sync_scope(&peer).await);
+    assert!(policy.sync_scope(&peer).await.is_none());
+}
+
+#[tokio::test]
+async fn personal_policy_scope_ignores_acl_events() {
+    let policy = PersonalSyncPolicy::new();
+    let peer = PeerId::new();
+    policy
+        .set_sync_scope(peer, SyncScope::new().include_type("task"))
+        .await;
+    let eid = EntityId::new();
+    let acl = Event::new(
+        eid,
+        peer,
+        HybridTimestamp::now(),
+        EventPayload::AclSetDefault {
+            entity_id: eid.to_string(),
+            role: None,
+        },
+    );
+    let sent = policy.on_event_send(&peer, &eid, &[acl]).await.unwrap();
+    assert_eq!(sent.len(), 1);
+}
+
+fn delete(entity_id: EntityId, peer: PeerId, entity_type: &str) -> Event {
+    Event::new(
+        entity_id,
+        peer,
+        HybridTimestamp::now(),
+        EventPayload::EntityDeleted {
+            entity_type: entity_type.into(),
+        },
+    )
+}
+
+#[tokio::test]
+async fn personal_policy_checks_deletes_against_tags_from_history() {
+    let store = Arc::new(EventStore::open_in_memory().unwrap());
+    let policy = PersonalSyncPolicy::new().with_event_store(Arc::clone(&store));
+    let peer = PeerId::new();
+    let author = PeerId::new();
+    policy
+        .set_sync_scope(peer, SyncScope::new().exclude_tag("private"))
+        .await;
+
+    let private = EntityId::new();
+    store
+        .save_event(&snapshot(private, author, "note", r#"{"tags":["Private"]}"#))
+        .unwrap();
+    let work = EntityId::new();
+    store
+        .save_event(&snapshot(work, author, "note", r#"{"tags":["work"]}"#))
+        .unwrap();
+
+    let sent = policy
+        .on_event_send(&peer, &private, &[delete(private, author, "note")])
+        .await
+        .unwrap();
+    assert!(sent.is_empty());
+    let sent = policy
+        .on_event_send(&peer, &work, &[delete(work, author, "note")])
+        .await
+        .unwrap();
+    assert_eq!(sent.len(), 1);
+
+    // Unknown history: withheld from tag-excluding scopes only
+    let unknown = EntityId::new();
+    let events = [delete(unknown, author, "note")];
+    assert!(policy.on_event_send(&peer, &unknown, &events).await.unwrap().is_empty());
+    let other = PeerId::new();
+    assert_eq!(policy.on_event_send(&other, &unknown, &events).await.unwrap().len(), 1);
+}
+
+// ── Persistence alongside pairing state ─────────────────────────
+
+#[test]
+fn pairing_manager_persists_sync_scopes() {
+    let mut pm = PairingManager::new();
+    let peer = PeerId::new().to_string();
+    pm.set_sync_scope(&peer, SyncScope::new().include_type("task"));
+
+    let json = pm.to_json().unwrap();
+    let loaded = PairingManager::from_json(&json).unwrap();
+    assert_eq!(
+        loaded.sync_scope(&peer),
+        Some(&SyncScope::new().include_type("task"))
+    );
+}
+
+#[test]
+fn pairing_manager_loads_legacy_state_without_scopes() {
+    let legacy = r#"{"current_code":null,"discovered_peers":{},"trusted_peers":{}}"#;
+    let pm = PairingManager::from_json(legacy).unwrap();
+    assert!(pm.sync_scopes().is_empty());
+}
+
+#[test]
+fn removing_trusted_peer_drops_its_scope() {
+    let mut pm = PairingManager::new();
+    let peer = PeerId::new().to_string();
+    pm.set_sync_scope(&peer, SyncScope::new().exclude_tag("private"));
+    pm.remove_trusted_peer(&peer);
+    assert!(pm.sync_scope(&peer).is_none());
+}
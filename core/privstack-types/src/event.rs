@@ -94,6 +94,12 @@ pub enum EventPayload {
         entity_id: String,
         peer_id: String,
         role: String,
+        /// Milliseconds since the Unix epoch before which the grant is not in effect.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        not_before: Option<u64>,
+        /// Milliseconds since the Unix epoch at which the grant lapses.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expires_at: Option<u64>,
     },
 
     /// Revoke a peer's role on an entity.
@@ -107,6 +113,12 @@ pub enum EventPayload {
         entity_id: String,
         team_id: String,
         role: String,
+        /// Milliseconds since the Unix epoch before which the grant is not in effect.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        not_before: Option<u64>,
+        /// Milliseconds since the Unix epoch at which the grant lapses.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expires_at: Option<u64>,
     },
 
     /// Revoke a team's role on an entity.
@@ -132,7 +144,95 @@ pub enum EventPayload {
     TeamRemovePeer {
         team_id: String,
         peer_id: String,
+        /// IDs of the `TeamAddPeer` events the remover had seen. Adds not
+        /// listed survive (add-wins); an empty list removes every add the
+        /// receiver knows of.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        observed: Vec<String>,
     },
+
+    // ── Organization policy events ──────────────────────────────
+
+    /// Admit a peer to the organization's known peers.
+    KnownPeerAdd {
+        peer_id: String,
+        /// Base64 identity key the peer signs its events with, registered as
+        /// its author key when the event is applied.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        identity_key: Option<String>,
+    },
+
+    /// Remove a peer from the known peers (same add-wins rule as `TeamRemovePeer`).
+    KnownPeerRemove {
+        peer_id: String,
+        /// IDs of the `KnownPeerAdd` events the remover had seen.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        observed: Vec<String>,
+    },
+
+    /// Set or clear a peer's device limit. Concurrent changes resolve
+    /// last-writer-wins by event timestamp.
+    DeviceLimitSet {
+        peer_id: String,
+        /// Maximum active devices, or `None` to remove the limit.
+        max_devices: Option<usize>,
+    },
+}
+
+/// Author signature over an event's canonical bytes (`Event::signing_bytes`).
+///
+/// Both fields are base64-encoded: a 32-byte Ed25519 public key (the author's
+/// device identity key) and a 64-byte Ed25519 signature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventSignature {
+    /// The author's identity public key.
+    pub public_key: String,
+    /// Signature over `Event::signing_bytes()`.
+    pub signature: String,
+}
+
+/// Signature a relaying peer puts on a redacted copy of an event.
+///
+/// A redacted copy no longer matches its author's signature, so the peer that
+/// withheld the fields signs the copy instead, binding the redacted payload to
+/// the original event ID and to itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactionSeal {
+    /// The peer that redacted and sent this copy.
+    pub redactor: PeerId,
+    /// The redactor's signature over `Event::redaction_signing_bytes()`.
+    pub signature: EventSignature,
+}
+
+/// Domain separator prepended to the bytes an event signature covers.
+const EVENT_SIGNING_CONTEXT: &[u8] = b"privstack-event-v1\0";
+
+/// Domain separator prepended to the bytes a redaction seal covers.
+const REDACTION_SIGNING_CONTEXT: &[u8] = b"privstack-redaction-v1\0";
+
+/// The event fields covered by the author signature.
+#[derive(Serialize)]
+struct SignedFields<'a> {
+    id: &'a EventId,
+    entity_id: &'a EntityId,
+    peer_id: &'a PeerId,
+    timestamp: &'a HybridTimestamp,
+    payload: &'a EventPayload,
+    dependencies: &'a [EventId],
+    /// Omitted when empty so signatures over unredacted events are unchanged.
+    #[serde(skip_serializing_if = "no_paths")]
+    redacted_paths: &'a [String],
+}
+
+fn no_paths(paths: &&[String]) -> bool {
+    paths.is_empty()
+}
+
+/// The fields covered by a redaction seal.
+#[derive(Serialize)]
+struct SealedFields<'a> {
+    redactor: &'a PeerId,
+    event: SignedFields<'a>,
 }
 
 /// An event representing a change to an entity.
@@ -160,6 +260,22 @@ pub struct Event {
     /// Typically the previous event from the same peer.
     #[serde(default)]
     pub dependencies: Vec<EventId>,
+
+    /// Signature by the author's device identity key.
+    /// Absent on events created before signing was introduced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<EventSignature>,
+
+    /// JSON pointers the sender withheld from this copy of the payload.
+    /// Receivers keep their local values at these paths instead of treating
+    /// them as deleted. Empty for unredacted events.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redacted_paths: Vec<String>,
+
+    /// Signature by the peer that redacted this copy. Redacted copies carry
+    /// this instead of the author's signature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redaction_seal: Option<RedactionSeal>,
 }
 
 impl Event {
@@ -178,9 +294,60 @@ impl Event {
             timestamp,
             payload,
             dependencies: Vec::new(),
+            signature: None,
+            redacted_paths: Vec::new(),
+            redaction_seal: None,
         }
     }
 
+    /// Returns the canonical bytes an author signature covers: every field
+    /// except the signature itself, prefixed with a domain separator.
+    #[must_use]
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = EVENT_SIGNING_CONTEXT.to_vec();
+        serde_json::to_writer(&mut bytes, &self.signed_fields())
+            .expect("event fields are always serializable");
+        bytes
+    }
+
+    /// Returns the canonical bytes a redaction seal by `redactor` covers: the
+    /// fields of `signing_bytes` plus the redactor, under their own domain
+    /// separator.
+    #[must_use]
+    pub fn redaction_signing_bytes(&self, redactor: &PeerId) -> Vec<u8> {
+        let fields = SealedFields {
+            redactor,
+            event: self.signed_fields(),
+        };
+        let mut bytes = REDACTION_SIGNING_CONTEXT.to_vec();
+        serde_json::to_writer(&mut bytes, &fields).expect("event fields are always serializable");
+        bytes
+    }
+
+    fn signed_fields(&self) -> SignedFields<'_> {
+        SignedFields {
+            id: &self.id,
+            entity_id: &self.entity_id,
+            peer_id: &self.peer_id,
+            timestamp: &self.timestamp,
+            payload: &self.payload,
+            dependencies: &self.dependencies,
+            redacted_paths: &self.redacted_paths,
+        }
+    }
+
+    /// Whether fields were withheld from this copy of the event.
+    #[must_use]
+    pub fn is_redacted(&self) -> bool {
+        !self.redacted_paths.is_empty()
+    }
+
+    /// Whether the event carries an author signature.
+    #[must_use]
+    pub fn is_signed(&self) -> bool {
+        self.signature.is_some()
+    }
+
     /// Creates an entity-created event.
     #[must_use]
     pub fn entity_created(
@@ -256,6 +423,7 @@ impl Event {
     }
 
     /// Adds a dependency to this event.
+    /// Must be called before signing; it invalidates an existing signature.
     pub fn with_dependency(mut self, dep: EventId) -> Self {
         self.dependencies.push(dep);
         self
@@ -13,7 +13,7 @@ mod event;
 mod ids;
 mod timestamp;
 
-pub use event::{Event, EventId, EventPayload};
+pub use event::{Event, EventId, EventPayload, EventSignature, RedactionSeal};
 pub use ids::{EntityId, PeerId};
 pub use timestamp::HybridTimestamp;
 
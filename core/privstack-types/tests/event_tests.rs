@@ -1,4 +1,6 @@
-use privstack_types::{EntityId, Event, EventId, EventPayload, HybridTimestamp, PeerId};
+use privstack_types::{
+    EntityId, Event, EventId, EventPayload, EventSignature, HybridTimestamp, PeerId,
+};
 use std::str::FromStr;
 
 // ── EventId ───────────────────────────────────────────────────────
@@ -192,6 +194,67 @@ fn event_with_multiple_dependencies() {
     assert_eq!(event.dependencies[1], id2);
 }
 
+// ── Signing bytes ────────────────────────────────────────────────
+
+#[test]
+fn signing_bytes_exclude_signature() {
+    let mut event = Event::entity_created(EntityId::new(), PeerId::new(), "note", "{}");
+    let before = event.signing_bytes();
+    event.signature = Some(EventSignature {
+        public_key: "pk".into(),
+        signature: "sig".into(),
+    });
+    assert_eq!(event.signing_bytes(), before);
+    assert!(event.is_signed());
+}
+
+#[test]
+fn signing_bytes_cover_payload_and_author() {
+    let event = Event::entity_created(EntityId::new(), PeerId::new(), "note", "{}");
+
+    let mut tampered = event.clone();
+    tampered.payload = EventPayload::EntityDeleted {
+        entity_type: "note".into(),
+    };
+    assert_ne!(tampered.signing_bytes(), event.signing_bytes());
+
+    let mut forged = event.clone();
+    forged.peer_id = PeerId::new();
+    assert_ne!(forged.signing_bytes(), event.signing_bytes());
+}
+
+#[test]
+fn unsigned_event_omits_signature_field() {
+    let event = Event::entity_created(EntityId::new(), PeerId::new(), "note", "{}");
+    let json = serde_json::to_string(&event).unwrap();
+    assert!(!json.contains("signature"));
+    let parsed: Event = serde_json::from_str(&json).unwrap();
+    assert!(!parsed.is_signed());
+}
+
+#[test]
+fn signing_bytes_cover_redacted_paths_only_when_present() {
+    let event = Event::entity_created(EntityId::new(), PeerId::new(), "note", "{}");
+    let bytes = String::from_utf8(event.signing_bytes()).unwrap();
+    assert!(!bytes.contains("redacted_paths"));
+
+    let mut redacted = event.clone();
+    redacted.redacted_paths = vec!["/cost".into()];
+    assert!(redacted.is_redacted());
+    assert_ne!(redacted.signing_bytes(), event.signing_bytes());
+}
+
+#[test]
+fn redacted_paths_serde_roundtrip() {
+    let mut event = Event::full_snapshot(EntityId::new(), PeerId::new(), "project", "{}");
+    let json = serde_json::to_string(&event).unwrap();
+    assert!(!json.contains("redacted_paths"));
+
+    event.redacted_paths = vec!["/cost".into(), "/notes/private".into()];
+    let parsed: Event = serde_json::from_str(&serde_json::to_string(&event).unwrap()).unwrap();
+    assert_eq!(parsed.redacted_paths, event.redacted_paths);
+}
+
 // ── Event serde roundtrip ────────────────────────────────────────
 
 #[test]
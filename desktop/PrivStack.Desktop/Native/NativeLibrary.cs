@@ -788,8 +788,12 @@ internal static partial class NativeLibrary
     // Plugin Wasm Runtime (command routing)
     // ========================================
 
+    /// <summary>
+    /// Loads the Wasm of an installed package, which runs with that package's
+    /// declared permissions. Other builds load only in dev mode.
+    /// </summary>
     [LibraryImport(LibraryName, EntryPoint = "privstack_plugin_load_wasm", StringMarshalling = StringMarshalling.Utf8)]
-    public static partial PrivStackError PluginLoadWasm(string wasmPath, string? permissionsJson, out nint outPluginId);
+    public static partial PrivStackError PluginLoadWasm(string wasmPath, out nint outPluginId);
 
     /// <summary>
     /// Loads multiple Wasm plugins in parallel. Input/output are JSON arrays.
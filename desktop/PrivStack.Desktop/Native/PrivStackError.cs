@@ -114,6 +114,9 @@ public enum PrivStackError
     /// <summary>Invalid recovery mnemonic.</summary>
     InvalidRecoveryMnemonic = 35,
 
+    /// <summary>A share recipient's public key differs from the pinned one.</summary>
+    RecipientKeyChanged = 36,
+
     /// <summary>Unknown error.</summary>
     Unknown = 99,
 }
@@ -742,7 +742,6 @@ public sealed partial class PluginRegistry : ObservableObject, IPluginRegistry,
         string WasmPath,
         WasmPluginMetadataDto? Metadata,
         List<WasmEntitySchemaDto>? Schemas,
-        string PermissionsJson,
         string PluginDir);
 
     /// Result element from the batch FFI call.
@@ -1005,10 +1004,7 @@ public sealed partial class PluginRegistry : ObservableObject, IPluginRegistry,
             }
         }
 
-        var pluginId = metadata?.Id ?? Path.GetFileNameWithoutExtension(wasmFile);
-        var permissionsJson = BuildPermissionsJson(pluginId, metadata?.Capabilities, metadata?.Name);
-
-        return new PendingWasmPlugin(wasmFile, metadata, schemas, permissionsJson, dir);
+        return new PendingWasmPlugin(wasmFile, metadata, schemas, dir);
     }
 
     /// <summary>
@@ -1019,12 +1015,9 @@ public sealed partial class PluginRegistry : ObservableObject, IPluginRegistry,
     {
         _log.Information("Batch loading {Count} Wasm plugins in parallel", pending.Count);
 
-        // Build the JSON array for the FFI call
-        var batchEntries = pending.Select(p => new
-        {
-            path = p.WasmPath,
-            permissions = JsonSerializer.Deserialize<JsonElement>(p.PermissionsJson),
-        }).ToList();
+        // Build the JSON array for the FFI call. Permissions come from the
+        // installed package the Wasm belongs to, not from the caller.
+        var batchEntries = pending.Select(p => new { path = p.WasmPath }).ToList();
 
         var batchJson = JsonSerializer.Serialize(batchEntries, _batchJsonOptions);
         var resultPtr = NativeLib.PluginLoadWasmBatch(batchJson);
@@ -1196,9 +1189,7 @@ public sealed partial class PluginRegistry : ObservableObject, IPluginRegistry,
     {
         try
         {
-            var permissionsJson = BuildPermissionsJson(Path.GetFileNameWithoutExtension(wasmPath));
-
-            var result = NativeLib.PluginLoadWasm(wasmPath, permissionsJson, out var pluginIdPtr);
+            var result = NativeLib.PluginLoadWasm(wasmPath, out var pluginIdPtr);
             if (result != Native.PrivStackError.Ok)
             {
                 _log.Error("Failed to load Wasm component {Path}: {Error}", wasmPath, result);
@@ -1256,10 +1247,8 @@ public sealed partial class PluginRegistry : ObservableObject, IPluginRegistry,
     {
         try
         {
-            var permissionsJson = BuildPermissionsJson(metadata.Id, metadata.Capabilities, metadata.Name);
-
             // Load via real Wasm runtime so get_view_state() and handle_command() work
-            var result = NativeLib.PluginLoadWasm(wasmPath, permissionsJson, out var pluginIdPtr);
+            var result = NativeLib.PluginLoadWasm(wasmPath, out var pluginIdPtr);
 
             string? pluginId = null;
             if (pluginIdPtr != nint.Zero)